@@ -32,6 +32,16 @@ pub struct DeviceProfile {
     pub name: String, //useful?
     pub target_devices: Option<Vec<String>>,
     pub description: Option<String>,
+    /// Steam appids of games this profile is automatically loaded for
+    pub app_ids: Option<Vec<u32>>,
+    /// Accessibility options applied to input while the profile is loaded
+    pub accessibility: Option<AccessibilityConfig>,
+    /// LED state applied while the profile is loaded
+    pub leds: Option<LedConfig>,
+    /// Touch and stylus options applied while the profile is loaded
+    pub touch: Option<TouchConfig>,
+    /// Output range of target axes applied while the profile is loaded
+    pub axis_output: Option<Vec<AxisOutputConfig>>,
     pub mapping: Vec<ProfileMapping>,
 }
 
@@ -50,12 +60,85 @@ impl DeviceProfile {
     }
 }
 
+/// LED state of a device profile
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub struct LedConfig {
+    /// Red, green, and blue color of multicolor LEDs
+    pub color: Option<[u8; 3]>,
+    /// Brightness of all LEDs from 0.0 to 1.0
+    pub brightness: Option<f64>,
+}
+
+/// Touch and stylus options of a device profile
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub struct TouchConfig {
+    /// Control points of the pressure curve as [input, output] pairs from
+    /// 0.0 to 1.0 (e.g. [[0.5, 0.25]] to require firmer presses)
+    pub pressure_curve: Option<Vec<[f64; 2]>>,
+}
+
+/// Output range of a single target axis or trigger of a device profile
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub struct AxisOutputConfig {
+    /// Capability of the axis (e.g. "Gamepad:Axis:LeftStick" or
+    /// "Gamepad:Trigger:RightTrigger")
+    pub capability: String,
+    /// Output value for the smallest non-zero input from 0.0 to 1.0
+    /// (default 0.0)
+    pub min: Option<f64>,
+    /// Output value for an input at its saturation point from 0.0 to 1.0
+    /// (default 1.0)
+    pub max: Option<f64>,
+    /// Input value from 0.0 to 1.0 at which the output reaches its maximum
+    /// (default 1.0)
+    pub saturation: Option<f64>,
+    /// Whether to clamp the output to the configured range (default true)
+    pub clamp: Option<bool>,
+}
+
+/// Accessibility options of a device profile
+#[derive(Debug, Deserialize, Clone, Default)]
+#[serde(rename_all = "snake_case")]
+pub struct AccessibilityConfig {
+    /// Time in milliseconds after a button is released during which presses
+    /// of the same button are ignored, to filter out tremors.
+    pub debounce_ms: Option<u64>,
+    /// Factor mouse motion and analog stick values are scaled by, to slow
+    /// down input (e.g. 0.5).
+    pub input_scale: Option<f64>,
+    /// Time in milliseconds the pointer must rest after moving before a left
+    /// click is emitted.
+    pub dwell_click_ms: Option<u64>,
+    /// Single-switch scanning options
+    pub switch_scan: Option<SwitchScanConfig>,
+}
+
+/// Single-switch scanning cycles through a list of target capabilities and
+/// emits the selected one when the switch is pressed.
+#[derive(Debug, Deserialize, Clone)]
+#[serde(rename_all = "snake_case")]
+pub struct SwitchScanConfig {
+    /// Capability used as the switch, in the form "Gamepad:Button:South"
+    pub switch: String,
+    /// Capabilities to cycle through, in the form "Gamepad:Button:South"
+    pub targets: Vec<String>,
+    /// Time in milliseconds before advancing to the next target
+    pub interval_ms: Option<u64>,
+}
+
 #[derive(Debug, Deserialize, Clone)]
 #[serde(rename_all = "snake_case")]
 pub struct ProfileMapping {
     pub name: String,
     pub source_event: CapabilityConfig,
     pub target_events: Vec<CapabilityConfig>,
+    /// Optional Lua script to transform the value of translated events
+    pub script: Option<String>,
+    /// Optional name of a mapper plugin to transform translated events
+    pub plugin: Option<String>,
 }
 
 impl ProfileMapping {
@@ -166,6 +249,114 @@ impl CapabilityMap {
     }
 }
 
+/// Maps games to the device profiles that should be automatically loaded when
+/// they are focused.
+#[derive(Debug, Deserialize, Clone)]
+#[serde(rename_all = "snake_case")]
+pub struct AppProfileMap {
+    pub version: u32,
+    pub kind: String,
+    pub name: String,
+    pub mapping: Vec<AppProfileMapping>,
+}
+
+impl AppProfileMap {
+    /// Load an [AppProfileMap] from the given YAML file
+    pub fn from_yaml_file(path: String) -> Result<AppProfileMap, LoadError> {
+        let file = std::fs::File::open(path)?;
+        let map: AppProfileMap = serde_yaml::from_reader(file)?;
+        Ok(map)
+    }
+}
+
+#[derive(Debug, Deserialize, Clone)]
+#[serde(rename_all = "snake_case")]
+pub struct AppProfileMapping {
+    /// Steam appid of the game
+    pub app_id: Option<u32>,
+    /// Window class of the game, used for games without a Steam appid.
+    /// Supports glob patterns (e.g. "*.exe").
+    pub window_class: Option<String>,
+    /// Whether the device is docked (an external display is connected). Used
+    /// on its own to load a profile while docked or undocked when the focused
+    /// app has no profile.
+    pub docked: Option<bool>,
+    /// Name of the composite devices the mapping applies to. Supports glob
+    /// patterns. Defaults to all composite devices.
+    pub device: Option<String>,
+    /// Name of the device profile to load, or a path to a device profile
+    pub profile: String,
+}
+
+/// Defines a virtual handheld that replays a recorded report stream of a
+/// supported device, so the input pipeline can be tested without hardware.
+#[derive(Debug, Deserialize, Clone)]
+#[serde(rename_all = "snake_case")]
+pub struct ReplayDeviceConfig {
+    pub version: u32,
+    pub kind: String,
+    pub name: String,
+    /// Device to emulate (e.g. "steam_deck" or "legion_go")
+    pub device: String,
+    /// Path to the recorded report stream to replay
+    pub recording: String,
+    /// Whether to start over from the beginning once the recording ends
+    pub repeat: Option<bool>,
+}
+
+impl ReplayDeviceConfig {
+    /// Load a [ReplayDeviceConfig] from the given YAML file
+    pub fn from_yaml_file(path: String) -> Result<ReplayDeviceConfig, LoadError> {
+        let file = std::fs::File::open(path)?;
+        let config: ReplayDeviceConfig = serde_yaml::from_reader(file)?;
+        Ok(config)
+    }
+}
+
+/// Defines a WASM plugin that can decode input from source devices or
+/// transform events in device profile mappings.
+#[derive(Debug, Deserialize, Clone)]
+#[serde(rename_all = "snake_case")]
+pub struct PluginConfig {
+    pub version: u32,
+    pub kind: String,
+    pub name: String,
+    /// Path to the WASM module. Relative paths are resolved from the directory
+    /// containing the plugin config.
+    pub module: String,
+    /// Type of plugin. Either "decoder" or "mapper".
+    pub plugin_type: String,
+    /// HIDRaw devices the decoder plugin should be used for
+    pub matches: Option<Vec<Hidraw>>,
+    /// Capabilities the plugin can emit, in the form "Gamepad:Button:South".
+    /// Events exchanged with the plugin refer to these by index.
+    pub capabilities: Vec<String>,
+}
+
+impl PluginConfig {
+    /// Load a [PluginConfig] from the given YAML file
+    pub fn from_yaml_file(path: String) -> Result<PluginConfig, LoadError> {
+        let file = std::fs::File::open(path)?;
+        let plugin: PluginConfig = serde_yaml::from_reader(file)?;
+        Ok(plugin)
+    }
+
+    /// Returns true if this decoder plugin should be used for the given
+    /// HIDRaw device.
+    pub fn matches_hidraw(&self, vendor_id: u16, product_id: u16, interface_num: i32) -> bool {
+        let Some(matches) = self.matches.as_ref() else {
+            return false;
+        };
+        matches.iter().any(|hidraw| {
+            hidraw.vendor_id.map_or(true, |vid| vid == vendor_id)
+                && hidraw.product_id.map_or(true, |pid| pid == product_id)
+                && hidraw
+                    .interface_num
+                    .map_or(true, |num| num == interface_num)
+        })
+    }
+}
+
 #[derive(Debug, Deserialize, Clone)]
 #[serde(rename_all = "snake_case")]
 pub struct CapabilityMapping {
@@ -271,6 +462,13 @@ pub struct Evdev {
     pub handler: Option<String>,
     pub vendor_id: Option<String>,
     pub product_id: Option<String>,
+    /// Orientation of the display panel of a touchscreen, used to rotate
+    /// touch coordinates to match the displayed image. One of "normal",
+    /// "upside_down", "left_side_up" or "right_side_up".
+    pub panel_orientation: Option<String>,
+    /// Key sequences sent by the device for vendor buttons, which are decoded
+    /// into a single gamepad button instead of being forwarded as keys
+    pub key_chords: Option<Vec<KeyChord>>,
 }
 
 #[derive(Debug, Deserialize, Clone, PartialEq)]
@@ -280,6 +478,74 @@ pub struct Hidraw {
     pub product_id: Option<u16>,
     pub interface_num: Option<i32>,
     pub handler: Option<String>,
+    /// Serial number of the device. Supports glob patterns.
+    pub serial_number: Option<String>,
+    /// How the accelerometers of split controllers are combined into a single
+    /// motion stream. One of "left", "right" or "average" (default).
+    pub imu_fusion: Option<String>,
+    /// Whether the firmware mouse/keyboard emulation ("lizard mode") of Steam
+    /// Deck and Steam Controller devices stays enabled while the device is
+    /// managed. Defaults to false.
+    pub lizard_mode: Option<bool>,
+    /// Play rumble as audio waveforms on the given audio device instead of
+    /// sending rumble commands, for devices whose haptics are driven through
+    /// an audio path.
+    pub audio_haptics: Option<AudioHapticsConfig>,
+    /// Enable or disable the touchpad in the firmware of Legion Go
+    /// controllers. The firmware setting is left unchanged if not set.
+    pub touchpad_enabled: Option<bool>,
+    /// Gamepad axes and triggers controlled by the analog keys of analog
+    /// keyboards (e.g. Wooting). Defaults to WASD controlling the left stick.
+    pub analog_keys: Option<Vec<AnalogKey>>,
+}
+
+/// Maps the analog value of a key to a gamepad axis or trigger
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub struct AnalogKey {
+    /// Name of the key (e.g. "KeyW")
+    pub key: String,
+    /// Name of the gamepad axis (e.g. "LeftStick") or trigger (e.g.
+    /// "RightTrigger") controlled by the key
+    pub target: String,
+    /// Direction the key moves the axis in. One of "up", "down", "left" or
+    /// "right". Only used for axes.
+    pub direction: Option<String>,
+}
+
+/// Sequence of keys sent by a device for a single button
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub struct KeyChord {
+    /// Names of the keys in the order they are pressed (e.g. "KeyLeftMeta")
+    pub keys: Vec<String>,
+    /// Name of the gamepad button emitted for the sequence (e.g. "QuickAccess")
+    pub target: String,
+    /// Maximum time in milliseconds between the first and the last key press
+    /// of the sequence. Defaults to 50.
+    pub timeout_ms: Option<u64>,
+}
+
+/// Defines how rumble is converted into audio waveforms
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub struct AudioHapticsConfig {
+    /// ALSA device to play waveforms on (e.g. "hw:CARD=acp5x,DEV=1")
+    pub device: String,
+    /// Sample rate in Hz. Defaults to 48000.
+    pub sample_rate: Option<u32>,
+    /// Number of channels, either 1 or 2. With 2 channels, the strong motor
+    /// plays on the left and the weak motor on the right. Defaults to 2.
+    pub channels: Option<u8>,
+    /// Frequency in Hz used for the strong motor. Defaults to 80.
+    pub low_frequency_hz: Option<f64>,
+    /// Frequency in Hz used for the weak motor. Defaults to 160.
+    pub high_frequency_hz: Option<f64>,
+    /// Amplitude of the waveforms at full intensity from 0.0 to 1.0. Defaults
+    /// to 1.0.
+    pub gain: Option<f64>,
+    /// Length of each waveform chunk in milliseconds. Defaults to 20.
+    pub pulse_ms: Option<u64>,
 }
 
 #[derive(Debug, Deserialize, Clone, PartialEq)]
@@ -289,6 +555,29 @@ pub struct IIO {
     pub id: Option<String>,
     pub name: Option<String>,
     pub mount_matrix: Option<MountMatrix>,
+    /// Sampling frequency of the sensors in Hz
+    pub sample_rate: Option<f64>,
+    /// Read samples from the IIO buffer instead of polling sysfs
+    pub buffered: Option<bool>,
+    /// Name or id of the IIO trigger to use for buffered reads. If not set,
+    /// the current trigger of the device is used.
+    pub trigger: Option<String>,
+    /// Calibration of the accelerometer in meters per second squared
+    pub accel_calibration: Option<ImuCalibration>,
+    /// Calibration of the gyro in degrees per second
+    pub gyro_calibration: Option<ImuCalibration>,
+}
+
+/// Calibration of an IMU sensor, applied after the mount matrix
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub struct ImuCalibration {
+    /// Offset added to the [x, y, z] values
+    pub offset: Option<[f64; 3]>,
+    /// Scale the [x, y, z] values are multiplied by after applying the offset
+    pub scale: Option<[f64; 3]>,
+    /// Axis remap for sensors mounted differently than the rest of the IMU
+    pub orientation: Option<MountMatrix>,
 }
 
 #[derive(Debug, Deserialize, Clone, PartialEq)]
@@ -311,6 +600,129 @@ pub struct CompositeDeviceConfig {
     pub capability_map_id: Option<String>,
     pub source_devices: Vec<SourceDevice>,
     pub target_devices: Option<Vec<String>>,
+    /// Target devices to use instead of `target_devices` depending on the
+    /// type of the active session.
+    pub session_target_devices: Option<SessionTargetDevicesConfig>,
+    /// Time in milliseconds a matching source device must stay connected
+    /// before a new composite device is created for it.
+    pub hotplug_debounce_ms: Option<u64>,
+    /// Time in milliseconds to keep the composite device and its target
+    /// devices alive after all source devices are removed, in case they
+    /// reappear.
+    pub hotplug_grace_period_ms: Option<u64>,
+    /// Whether the source devices may be the same controller connected over
+    /// either USB or Bluetooth. The composite device and its target devices
+    /// are kept when the controller switches connections.
+    pub transport_switching: Option<bool>,
+    /// Whether to pause the composite device and release source devices while
+    /// Steam has any of the physical source devices open.
+    pub steam_coexistence: Option<bool>,
+    /// Whether to show desktop notifications when the device is connected,
+    /// its profile is switched, or its battery is low.
+    pub notifications: Option<bool>,
+    /// Fuse accelerometer and gyro events from IMU source devices into a
+    /// device orientation.
+    pub motion_fusion: Option<MotionFusionConfig>,
+    /// Whether to publish the orientation of the built-in IIO accelerometer
+    /// for screen auto-rotation using the iio-sensor-proxy DBus interface.
+    /// Defaults to false.
+    pub screen_orientation: Option<bool>,
+    /// Show the on-screen keyboard when a chord is pressed or a text field is
+    /// focused.
+    pub on_screen_keyboard: Option<OnScreenKeyboardConfig>,
+    /// Toggle intercept mode when a chord is held, without an external DBus
+    /// caller.
+    pub intercept_toggle: Option<InterceptToggleConfig>,
+    /// Drop duplicate analog events and rate limit axis chatter before events
+    /// are written to target devices.
+    pub event_filter: Option<EventFilterConfig>,
+    /// Name, IDs and capabilities of the "custom" target device
+    pub custom_target: Option<CustomTargetConfig>,
+}
+
+/// Defines the virtual gamepad created for the "custom" target device
+#[derive(Debug, Deserialize, Clone, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub struct CustomTargetConfig {
+    /// Name of the virtual device. Defaults to "InputPlumber Gamepad".
+    pub name: Option<String>,
+    /// USB vendor id of the virtual device. Defaults to the vendor id of the
+    /// "gamepad" target device.
+    pub vendor_id: Option<u16>,
+    /// USB product id of the virtual device. Defaults to the product id of the
+    /// "gamepad" target device.
+    pub product_id: Option<u16>,
+    /// Capabilities of the virtual device in the form "Gamepad:Button:South".
+    /// Defaults to the capabilities of the "gamepad" target device.
+    pub capabilities: Option<Vec<String>>,
+}
+
+/// Defines how duplicate analog events are suppressed
+#[derive(Debug, Deserialize, Clone, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub struct EventFilterConfig {
+    /// Axis value changes smaller than this are considered chatter. Defaults
+    /// to 0.0, so only identical values are suppressed.
+    pub chatter_threshold: Option<f64>,
+    /// Minimum time in milliseconds between chatter events of an axis.
+    /// Defaults to 50.
+    pub chatter_interval_ms: Option<u64>,
+}
+
+/// Target devices of a composite device for each type of session
+#[derive(Debug, Deserialize, Clone, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub struct SessionTargetDevicesConfig {
+    /// Target devices to use in a gamescope session (e.g. Steam Game Mode)
+    pub gamescope: Option<Vec<String>>,
+    /// Target devices to use in a desktop session
+    pub desktop: Option<Vec<String>>,
+}
+
+/// Defines the chord that toggles intercept mode on the composite device
+#[derive(Debug, Deserialize, Clone, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub struct InterceptToggleConfig {
+    /// Buttons that toggle intercept mode when held together, in the form
+    /// "Gamepad:Button:Guide".
+    pub chord: Vec<String>,
+    /// Time in milliseconds the chord must be held. Defaults to 0.
+    pub hold_ms: Option<u64>,
+    /// Intercept mode to switch to, either "all" or "motion". Defaults to
+    /// "all".
+    pub mode: Option<String>,
+    /// Whether to rumble and flash the LEDs of the device when intercept mode
+    /// is toggled. Defaults to true.
+    pub cue: Option<bool>,
+}
+
+/// Defines how the on-screen keyboard is triggered and shown
+#[derive(Debug, Deserialize, Clone, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub struct OnScreenKeyboardConfig {
+    /// Buttons that toggle the on-screen keyboard when pressed together, in
+    /// the form "Gamepad:Button:Guide".
+    pub chord: Option<Vec<String>>,
+    /// Systemd user unit that shows the on-screen keyboard in the active
+    /// session. The keyboard is not shown if unset.
+    pub unit: Option<String>,
+    /// Whether to switch the device to intercept mode while the on-screen
+    /// keyboard is shown. Defaults to true.
+    pub intercept: Option<bool>,
+}
+
+/// Defines the filter used to fuse accelerometer and gyro events
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub struct MotionFusionConfig {
+    /// Filter to use, either "madgwick" or "mahony". Defaults to "madgwick".
+    pub filter: Option<String>,
+    /// Gain of the Madgwick filter
+    pub beta: Option<f64>,
+    /// Proportional gain of the Mahony filter
+    pub kp: Option<f64>,
+    /// Integral gain of the Mahony filter
+    pub ki: Option<f64>,
 }
 
 impl CompositeDeviceConfig {
@@ -400,6 +812,15 @@ impl CompositeDeviceConfig {
             }
         }
 
+        if let Some(serial_number) = hidraw_config.serial_number {
+            let Some(device_serial) = device.serial_number() else {
+                return false;
+            };
+            if !glob_match(serial_number.as_str(), device_serial) {
+                return false;
+            }
+        }
+
         true
     }
 
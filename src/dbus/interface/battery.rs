@@ -0,0 +1,49 @@
+use tokio::sync::mpsc;
+use zbus::fdo;
+use zbus_macros::interface;
+
+use crate::input::{battery::BatteryState, composite_device::Command};
+
+/// The [BatteryInterface] provides a DBus interface that exposes the battery
+/// state of a [CompositeDevice]. It is added to the composite device path
+/// when the first source device reports its battery. If several source
+/// devices have a battery, the one with the lowest level is reported.
+pub struct BatteryInterface {
+    tx: mpsc::Sender<Command>,
+}
+
+impl BatteryInterface {
+    pub fn new(tx: mpsc::Sender<Command>) -> BatteryInterface {
+        BatteryInterface { tx }
+    }
+
+    /// Returns the current battery state from the composite device
+    async fn get_battery(&self) -> fdo::Result<BatteryState> {
+        let (sender, mut receiver) = mpsc::channel::<Option<BatteryState>>(1);
+        self.tx
+            .send(Command::GetBattery(sender))
+            .await
+            .map_err(|e| fdo::Error::Failed(e.to_string()))?;
+        let Some(battery) = receiver.recv().await.flatten() else {
+            return Ok(BatteryState::default());
+        };
+
+        Ok(battery)
+    }
+}
+
+#[interface(name = "org.shadowblip.Input.Battery")]
+impl BatteryInterface {
+    /// Battery level in percent
+    #[zbus(property)]
+    async fn percentage(&self) -> fdo::Result<u8> {
+        Ok(self.get_battery().await?.level)
+    }
+
+    /// Charging status of the battery. One of "Unknown", "Charging",
+    /// "Discharging" or "Full".
+    #[zbus(property)]
+    async fn status(&self) -> fdo::Result<String> {
+        Ok(self.get_battery().await?.status.to_string())
+    }
+}
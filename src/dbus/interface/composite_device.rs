@@ -1,16 +1,25 @@
 use std::{collections::HashSet, str::FromStr};
 
+use packed_struct::{
+    types::{Integer, SizedInteger},
+    PrimitiveEnum,
+};
 use tokio::sync::mpsc;
 use zbus::{
     fdo,
     zvariant::{self, Value},
+    SignalContext,
 };
 use zbus_macros::interface;
 
-use crate::input::{
-    capability::{Capability, Gamepad, Mouse},
-    composite_device::{Command, InterceptMode},
-    event::{native::NativeEvent, value::InputValue},
+use crate::{
+    drivers::steam_deck::hid_report::{PackedHapticPulseReport, Pad},
+    input::{
+        capability::{Capability, Gamepad, Mouse},
+        composite_device::{Command, InterceptMode},
+        event::{native::NativeEvent, value::InputValue},
+        output_event::{LedEvent, OutputEvent},
+    },
 };
 
 /// The [CompositeDeviceInterface] provides a DBus interface that can be exposed for managing
@@ -58,6 +67,59 @@ impl CompositeDeviceInterface {
         Ok(profile_name)
     }
 
+    /// Player number of the device. Controllers keep their player number
+    /// when they reconnect or InputPlumber restarts. Zero if unassigned.
+    #[zbus(property)]
+    async fn player_number(&self) -> fdo::Result<u8> {
+        let (sender, mut receiver) = mpsc::channel::<u8>(1);
+        self.tx
+            .send(Command::GetPlayerNumber(sender))
+            .await
+            .map_err(|e| fdo::Error::Failed(e.to_string()))?;
+        let Some(player_number) = receiver.recv().await else {
+            return Ok(0);
+        };
+
+        Ok(player_number)
+    }
+
+    /// Emitted when a source device encounters a recoverable problem, such as
+    /// being unable to open the device node.
+    #[zbus(signal)]
+    async fn source_device_warning(
+        ctxt: &SignalContext<'_>,
+        device_id: String,
+        message: String,
+    ) -> zbus::Result<()>;
+
+    /// Emitted when a source device stops sending input reports while still
+    /// connected, and again when it recovers. The device is reset automatically
+    /// when it stalls.
+    #[zbus(signal)]
+    async fn source_device_stalled(
+        ctxt: &SignalContext<'_>,
+        device_id: String,
+        stalled: bool,
+    ) -> zbus::Result<()>;
+
+    /// Emitted when a detachable part of a source device, such as one of the
+    /// Legion Go controllers, is detached or attached again. The capabilities
+    /// of the part are removed from the device while it is detached.
+    #[zbus(signal)]
+    async fn source_device_detached(
+        ctxt: &SignalContext<'_>,
+        device_id: String,
+        detached: bool,
+    ) -> zbus::Result<()>;
+
+    /// Emitted when the capabilities of the device change at runtime, such as
+    /// when a source device is added or removed.
+    #[zbus(signal, name = "CapabilitiesChanged")]
+    async fn capabilities_updated(
+        ctxt: &SignalContext<'_>,
+        capabilities: Vec<String>,
+    ) -> zbus::Result<()>;
+
     /// Stop the composite device and all target devices
     async fn stop(&self) -> fdo::Result<()> {
         self.tx
@@ -67,6 +129,190 @@ impl CompositeDeviceInterface {
         Ok(())
     }
 
+    /// Whether the composite device pauses itself and releases its source
+    /// devices while Steam has any of them open
+    #[zbus(property)]
+    async fn steam_coexistence(&self) -> fdo::Result<bool> {
+        let (sender, mut receiver) = mpsc::channel::<bool>(1);
+        self.tx
+            .send(Command::GetSteamCoexistence(sender))
+            .await
+            .map_err(|e| fdo::Error::Failed(e.to_string()))?;
+        let Some(enabled) = receiver.recv().await else {
+            return Ok(false);
+        };
+
+        Ok(enabled)
+    }
+
+    #[zbus(property)]
+    async fn set_steam_coexistence(&self, enabled: bool) -> zbus::Result<()> {
+        self.tx
+            .send(Command::SetSteamCoexistence(enabled))
+            .await
+            .map_err(|err| zbus::Error::Failure(err.to_string()))?;
+        Ok(())
+    }
+
+    /// Whether the on-screen keyboard is shown. Session components can set
+    /// this when a text field is focused to show the on-screen keyboard and
+    /// switch the device to intercept mode until it is dismissed.
+    #[zbus(property)]
+    async fn on_screen_keyboard(&self) -> fdo::Result<bool> {
+        let (sender, mut receiver) = mpsc::channel::<bool>(1);
+        self.tx
+            .send(Command::GetOnScreenKeyboard(sender))
+            .await
+            .map_err(|e| fdo::Error::Failed(e.to_string()))?;
+        let Some(visible) = receiver.recv().await else {
+            return Ok(false);
+        };
+
+        Ok(visible)
+    }
+
+    #[zbus(property)]
+    async fn set_on_screen_keyboard(&self, visible: bool) -> zbus::Result<()> {
+        self.tx
+            .send(Command::SetOnScreenKeyboard(visible))
+            .await
+            .map_err(|err| zbus::Error::Failure(err.to_string()))?;
+        Ok(())
+    }
+
+    /// Names of the LED class devices of the source devices
+    #[zbus(property)]
+    async fn leds(&self) -> fdo::Result<Vec<String>> {
+        let (sender, mut receiver) = mpsc::channel::<Vec<String>>(1);
+        self.tx
+            .send(Command::GetLeds(sender))
+            .await
+            .map_err(|e| fdo::Error::Failed(e.to_string()))?;
+        let Some(leds) = receiver.recv().await else {
+            return Ok(Vec::new());
+        };
+
+        Ok(leds)
+    }
+
+    /// Set the color of all multicolor LEDs of the source devices
+    async fn set_led_color(&self, red: u8, green: u8, blue: u8) -> fdo::Result<()> {
+        let event = OutputEvent::Led(LedEvent::Color(red, green, blue));
+        self.tx
+            .send(Command::ProcessOutputEvent(event))
+            .await
+            .map_err(|e| fdo::Error::Failed(e.to_string()))?;
+        Ok(())
+    }
+
+    /// Set the brightness (0.0 - 1.0) of all LEDs of the source devices
+    async fn set_led_brightness(&self, brightness: f64) -> fdo::Result<()> {
+        let event = OutputEvent::Led(LedEvent::Brightness(brightness));
+        self.tx
+            .send(Command::ProcessOutputEvent(event))
+            .await
+            .map_err(|e| fdo::Error::Failed(e.to_string()))?;
+        Ok(())
+    }
+
+    /// Play a haptic pulse on the trackpads of the source devices. Side is 0
+    /// for the left pad, 1 for the right pad and 2 for both. Period is in
+    /// microseconds and count is the number of pulses to play.
+    async fn haptic_pulse(
+        &self,
+        side: u8,
+        amplitude: u16,
+        period: u16,
+        count: u16,
+    ) -> fdo::Result<()> {
+        let Some(side) = Pad::from_primitive(side) else {
+            return Err(fdo::Error::InvalidArgs(format!("Invalid pad: {side}")));
+        };
+        let mut report = PackedHapticPulseReport::new();
+        report.side = side;
+        report.amplitude = Integer::from_primitive(amplitude);
+        report.period = Integer::from_primitive(period);
+        report.count = Integer::from_primitive(count);
+        let event = OutputEvent::SteamDeckHaptics(report);
+        self.tx
+            .send(Command::ProcessOutputEvent(event))
+            .await
+            .map_err(|e| fdo::Error::Failed(e.to_string()))?;
+        Ok(())
+    }
+
+    /// Stored calibration of the controller in YAML format. Empty if no
+    /// calibration has been stored.
+    #[zbus(property)]
+    async fn calibration(&self) -> fdo::Result<String> {
+        let (sender, mut receiver) = mpsc::channel::<String>(1);
+        self.tx
+            .send(Command::GetCalibration(sender))
+            .await
+            .map_err(|e| fdo::Error::Failed(e.to_string()))?;
+        let Some(calibration) = receiver.recv().await else {
+            return Ok(String::new());
+        };
+
+        Ok(calibration)
+    }
+
+    /// Store the given calibration (stick centers, gyro bias, and touchpad
+    /// corrections) in YAML format for the controller. The calibration is
+    /// loaded automatically whenever the controller is connected.
+    async fn set_calibration(&self, calibration: String) -> fdo::Result<()> {
+        let (sender, mut receiver) = mpsc::channel::<Result<(), String>>(1);
+        self.tx
+            .send(Command::SetCalibration(calibration, sender))
+            .await
+            .map_err(|e| fdo::Error::Failed(e.to_string()))?;
+
+        let Some(result) = receiver.recv().await else {
+            return Err(fdo::Error::Failed(
+                "No response from CompositeDevice".to_string(),
+            ));
+        };
+        result.map_err(|e| fdo::Error::Failed(format!("Failed to set calibration: {e}")))
+    }
+
+    /// Remove the stored calibration of the controller
+    async fn reset_calibration(&self) -> fdo::Result<()> {
+        let (sender, mut receiver) = mpsc::channel::<Result<(), String>>(1);
+        self.tx
+            .send(Command::ResetCalibration(sender))
+            .await
+            .map_err(|e| fdo::Error::Failed(e.to_string()))?;
+
+        let Some(result) = receiver.recv().await else {
+            return Err(fdo::Error::Failed(
+                "No response from CompositeDevice".to_string(),
+            ));
+        };
+        result.map_err(|e| fdo::Error::Failed(format!("Failed to reset calibration: {e}")))
+    }
+
+    /// Set the log level (e.g. "debug" or "trace") for this composite device
+    /// and the drivers of its source devices
+    async fn set_log_level(&self, level: String) -> fdo::Result<()> {
+        let level = log::LevelFilter::from_str(level.as_str())
+            .map_err(|e| fdo::Error::InvalidArgs(e.to_string()))?;
+        self.tx
+            .send(Command::SetLogLevel(Some(level)))
+            .await
+            .map_err(|e| fdo::Error::Failed(e.to_string()))?;
+        Ok(())
+    }
+
+    /// Reset the log level for this composite device and the drivers of its
+    /// source devices back to the default
+    async fn reset_log_level(&self) -> fdo::Result<()> {
+        self.tx
+            .send(Command::SetLogLevel(None))
+            .await
+            .map_err(|e| fdo::Error::Failed(e.to_string()))?;
+        Ok(())
+    }
+
     /// Load the device profile from the given path
     async fn load_profile_path(&self, path: String) -> fdo::Result<()> {
         let (sender, mut receiver) = mpsc::channel::<Result<(), String>>(1);
@@ -260,27 +506,7 @@ impl CompositeDeviceInterface {
             return Ok(Vec::new());
         };
 
-        let mut capability_strings = Vec::new();
-        for cap in capabilities {
-            let str = match cap {
-                Capability::Gamepad(gamepad) => match gamepad {
-                    Gamepad::Button(button) => format!("Gamepad:Button:{}", button),
-                    Gamepad::Axis(axis) => format!("Gamepad:Axis:{}", axis),
-                    Gamepad::Trigger(trigger) => format!("Gamepad:Trigger:{}", trigger),
-                    Gamepad::Accelerometer => "Gamepad:Accelerometer".to_string(),
-                    Gamepad::Gyro => "Gamepad:Gyro".to_string(),
-                },
-                Capability::Mouse(mouse) => match mouse {
-                    Mouse::Motion => "Mouse:Motion".to_string(),
-                    Mouse::Button(button) => format!("Mouse:Button:{}", button),
-                },
-                Capability::Keyboard(key) => format!("Keyboard:{}", key),
-                _ => cap.to_string(),
-            };
-            capability_strings.push(str);
-        }
-
-        Ok(capability_strings)
+        Ok(capability_strings(capabilities))
     }
 
     /// List of capabilities that all target devices implement
@@ -295,27 +521,7 @@ impl CompositeDeviceInterface {
             return Ok(Vec::new());
         };
 
-        let mut capability_strings = Vec::new();
-        for cap in capabilities {
-            let str = match cap {
-                Capability::Gamepad(gamepad) => match gamepad {
-                    Gamepad::Button(button) => format!("Gamepad:Button:{}", button),
-                    Gamepad::Axis(axis) => format!("Gamepad:Axis:{}", axis),
-                    Gamepad::Trigger(trigger) => format!("Gamepad:Trigger:{}", trigger),
-                    Gamepad::Accelerometer => "Gamepad:Accelerometer".to_string(),
-                    Gamepad::Gyro => "Gamepad:Gyro".to_string(),
-                },
-                Capability::Mouse(mouse) => match mouse {
-                    Mouse::Motion => "Mouse:Motion".to_string(),
-                    Mouse::Button(button) => format!("Mouse:Button:{}", button),
-                },
-                Capability::Keyboard(key) => format!("Keyboard:{}", key),
-                _ => cap.to_string(),
-            };
-            capability_strings.push(str);
-        }
-
-        Ok(capability_strings)
+        Ok(capability_strings(capabilities))
     }
 
     /// List of source devices that this composite device is processing inputs for
@@ -349,6 +555,7 @@ impl CompositeDeviceInterface {
             InterceptMode::None => Ok(0),
             InterceptMode::Pass => Ok(1),
             InterceptMode::Always => Ok(2),
+            InterceptMode::Motion => Ok(3),
         }
     }
 
@@ -358,6 +565,7 @@ impl CompositeDeviceInterface {
             0 => InterceptMode::None,
             1 => InterceptMode::Pass,
             2 => InterceptMode::Always,
+            3 => InterceptMode::Motion,
             _ => InterceptMode::None,
         };
         self.tx
@@ -397,3 +605,25 @@ impl CompositeDeviceInterface {
         Ok(paths)
     }
 }
+
+/// Returns the given capabilities as strings (e.g. "Gamepad:Button:South")
+pub fn capability_strings(capabilities: HashSet<Capability>) -> Vec<String> {
+    capabilities
+        .into_iter()
+        .map(|cap| match cap {
+            Capability::Gamepad(gamepad) => match gamepad {
+                Gamepad::Button(button) => format!("Gamepad:Button:{}", button),
+                Gamepad::Axis(axis) => format!("Gamepad:Axis:{}", axis),
+                Gamepad::Trigger(trigger) => format!("Gamepad:Trigger:{}", trigger),
+                Gamepad::Accelerometer => "Gamepad:Accelerometer".to_string(),
+                Gamepad::Gyro => "Gamepad:Gyro".to_string(),
+            },
+            Capability::Mouse(mouse) => match mouse {
+                Mouse::Motion => "Mouse:Motion".to_string(),
+                Mouse::Button(button) => format!("Mouse:Button:{}", button),
+            },
+            Capability::Keyboard(key) => format!("Keyboard:{}", key),
+            _ => cap.to_string(),
+        })
+        .collect()
+}
@@ -1,8 +1,36 @@
+use std::{collections::HashMap, str::FromStr};
+
 use tokio::sync::{broadcast, mpsc};
-use zbus::fdo;
+use zbus::{fdo, Connection, SignalContext};
 use zbus_macros::interface;
 
-use crate::{config::CompositeDeviceConfig, input::manager::ManagerCommand};
+use crate::{
+    config::{CompositeDeviceConfig, CustomTargetConfig},
+    constants::BUS_PREFIX,
+    input::{capability::Capability, manager::ManagerCommand},
+    logger, metrics,
+};
+
+/// Kind of device error reported by the DeviceError signal
+#[derive(Debug, Clone, Copy)]
+pub enum DeviceErrorKind {
+    /// Reading input from a source device failed
+    ReadFailed,
+    /// Writing events to a target device failed
+    WriteFailed,
+    /// A device stopped unexpectedly
+    Dropped,
+}
+
+impl DeviceErrorKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DeviceErrorKind::ReadFailed => "ReadFailed",
+            DeviceErrorKind::WriteFailed => "WriteFailed",
+            DeviceErrorKind::Dropped => "Dropped",
+        }
+    }
+}
 
 /// The [ManagerInterface] provides a DBus interface that can be exposed for managing
 /// a [Manager]. It works by sending command messages to a channel that the
@@ -15,6 +43,39 @@ impl ManagerInterface {
     pub fn new(tx: broadcast::Sender<ManagerCommand>) -> ManagerInterface {
         ManagerInterface { tx }
     }
+
+    /// Ask the manager to create a target device of the given type and return
+    /// its DBus path.
+    async fn request_target_device(
+        &self,
+        kind: String,
+        custom: Option<CustomTargetConfig>,
+    ) -> fdo::Result<String> {
+        let (sender, mut receiver) = mpsc::channel(1);
+        self.tx
+            .send(ManagerCommand::CreateTargetDevice {
+                kind,
+                serial: None,
+                player: None,
+                custom,
+                sender,
+            })
+            .map_err(|err| fdo::Error::Failed(err.to_string()))?;
+
+        // Read the response from the manager
+        let Some(response) = receiver.recv().await else {
+            return Err(fdo::Error::Failed("No response from manager".to_string()));
+        };
+        let device_path = match response {
+            Ok(path) => path,
+            Err(e) => {
+                let err = format!("Failed to create target device: {e:?}");
+                return Err(fdo::Error::Failed(err));
+            }
+        };
+
+        Ok(device_path)
+    }
 }
 
 #[interface(name = "org.shadowblip.InputManager")]
@@ -38,26 +99,79 @@ impl ManagerInterface {
     /// Create a target device of the given type. Returns the DBus path to
     /// the created target device.
     async fn create_target_device(&self, kind: String) -> fdo::Result<String> {
+        self.request_target_device(kind, None).await
+    }
+
+    /// Create a "custom" gamepad target device with the given name, USB
+    /// vendor and product ids, and capabilities in the form
+    /// "Gamepad:Button:South". Vendor and product ids of 0 use the ids of the
+    /// "gamepad" target device. Returns the DBus path to the created target
+    /// device.
+    async fn create_custom_target_device(
+        &self,
+        name: String,
+        vendor_id: u16,
+        product_id: u16,
+        capabilities: Vec<String>,
+    ) -> fdo::Result<String> {
+        if let Some(cap) = capabilities
+            .iter()
+            .find(|cap| Capability::from_str(cap).is_err())
+        {
+            return Err(fdo::Error::InvalidArgs(format!(
+                "Invalid capability: {cap}"
+            )));
+        }
+        let custom = CustomTargetConfig {
+            name: Some(name),
+            vendor_id: (vendor_id != 0).then_some(vendor_id),
+            product_id: (product_id != 0).then_some(product_id),
+            capabilities: Some(capabilities),
+        };
+        self.request_target_device("custom".to_string(), Some(custom))
+            .await
+    }
+
+    /// Returns the DBus path of every target device mapped to the DBus path of
+    /// the composite device using it, or an empty string if the target device
+    /// is not attached to a composite device.
+    async fn get_target_devices(&self) -> fdo::Result<HashMap<String, String>> {
         let (sender, mut receiver) = mpsc::channel(1);
         self.tx
-            .send(ManagerCommand::CreateTargetDevice { kind, sender })
+            .send(ManagerCommand::GetTargetDevices { sender })
             .map_err(|err| fdo::Error::Failed(err.to_string()))?;
-
-        // Read the response from the manager
-        let Some(response) = receiver.recv().await else {
+        let Some(targets) = receiver.recv().await else {
             return Err(fdo::Error::Failed("No response from manager".to_string()));
         };
-        let device_path = match response {
-            Ok(path) => path,
-            Err(e) => {
-                let err = format!("Failed to create target device: {e:?}");
-                return Err(fdo::Error::Failed(err));
-            }
-        };
+        Ok(targets)
+    }
 
-        Ok(device_path)
+    /// Set the log level (e.g. "debug" or "trace") for the given log target and
+    /// its children (e.g. "inputplumber::drivers::steam_deck")
+    async fn set_log_level(&self, target: String, level: String) -> fdo::Result<()> {
+        let level = log::LevelFilter::from_str(level.as_str())
+            .map_err(|e| fdo::Error::InvalidArgs(e.to_string()))?;
+        logger::set_level(target.as_str(), level);
+        Ok(())
+    }
+
+    /// Reset the log level for the given log target back to the default
+    async fn reset_log_level(&self, target: String) -> fdo::Result<()> {
+        logger::reset_level(target.as_str());
+        Ok(())
     }
 
+    /// Emitted when a device encounters an error, such as a failed read from a
+    /// source device, a failed write to a target device, or a device stopping
+    /// unexpectedly.
+    #[zbus(signal)]
+    async fn device_error(
+        ctxt: &SignalContext<'_>,
+        device_path: String,
+        kind: String,
+        message: String,
+    ) -> zbus::Result<()>;
+
     /// Stop the given target device
     async fn stop_target_device(&self, path: String) -> fdo::Result<()> {
         self.tx
@@ -93,3 +207,25 @@ impl ManagerInterface {
         Ok(())
     }
 }
+
+/// Emit the DeviceError signal on the manager DBus interface
+pub async fn emit_device_error(
+    conn: &Connection,
+    device_path: String,
+    kind: DeviceErrorKind,
+    message: String,
+) {
+    metrics::record_error(kind.as_str());
+    let manager_path = format!("{}/Manager", BUS_PREFIX);
+    let ctxt = match SignalContext::new(conn, manager_path) {
+        Ok(ctxt) => ctxt,
+        Err(e) => {
+            log::error!("Failed to create signal context for device error: {e:?}");
+            return;
+        }
+    };
+    let kind = kind.as_str().to_string();
+    if let Err(e) = ManagerInterface::device_error(&ctxt, device_path, kind, message).await {
+        log::error!("Failed to send device error signal: {e:?}");
+    }
+}
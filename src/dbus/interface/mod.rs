@@ -1,4 +1,6 @@
+pub mod battery;
 pub mod composite_device;
 pub mod manager;
+pub mod sensor_proxy;
 pub mod source;
 pub mod target;
@@ -0,0 +1,192 @@
+use std::error::Error;
+
+use zbus::{
+    fdo::{self, RequestNameFlags, RequestNameReply},
+    Connection, SignalContext,
+};
+use zbus_macros::interface;
+
+/// Well-known bus name used by iio-sensor-proxy
+pub const SENSOR_PROXY_NAME: &str = "net.hadess.SensorProxy";
+/// DBus path of the sensor proxy interface
+pub const SENSOR_PROXY_PATH: &str = "/net/hadess/SensorProxy";
+
+/// Angle in degrees the device must be tilted to change orientation
+const ORIENTATION_THRESHOLD: f64 = 35.0;
+/// Angle in degrees the device must be tilted to stay in its current
+/// orientation, to avoid flipping back and forth near the threshold.
+const ORIENTATION_HYSTERESIS: f64 = 25.0;
+
+/// Orientation of the device based on the direction of gravity, using the
+/// same values as iio-sensor-proxy.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Orientation {
+    #[default]
+    Undefined,
+    Normal,
+    BottomUp,
+    LeftUp,
+    RightUp,
+}
+
+impl Orientation {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Orientation::Undefined => "undefined",
+            Orientation::Normal => "normal",
+            Orientation::BottomUp => "bottom-up",
+            Orientation::LeftUp => "left-up",
+            Orientation::RightUp => "right-up",
+        }
+    }
+
+    /// Returns the orientation for the given accelerometer reading. The
+    /// previous orientation is kept unless the device is tilted far enough.
+    pub fn from_accelerometer(x: f64, y: f64, z: f64, previous: Orientation) -> Orientation {
+        if x == 0.0 && y == 0.0 && z == 0.0 {
+            return previous;
+        }
+        let portrait = x.atan2((y * y + z * z).sqrt()).to_degrees();
+        let landscape = y.atan2((x * x + z * z).sqrt()).to_degrees();
+
+        // Keep the current orientation while it is still tilted enough
+        let current = match previous {
+            Orientation::LeftUp => portrait,
+            Orientation::RightUp => -portrait,
+            Orientation::BottomUp => landscape,
+            Orientation::Normal => -landscape,
+            Orientation::Undefined => 0.0,
+        };
+        if current > ORIENTATION_HYSTERESIS {
+            return previous;
+        }
+
+        if portrait.abs() > ORIENTATION_THRESHOLD {
+            if portrait > 0.0 {
+                Orientation::LeftUp
+            } else {
+                Orientation::RightUp
+            }
+        } else if landscape.abs() > ORIENTATION_THRESHOLD {
+            if landscape > 0.0 {
+                Orientation::BottomUp
+            } else {
+                Orientation::Normal
+            }
+        } else {
+            previous
+        }
+    }
+}
+
+/// The [SensorProxyInterface] implements the iio-sensor-proxy DBus interface
+/// so the built-in accelerometer of devices that opt in with the
+/// `screen_orientation` config option can be used for screen auto-rotation.
+/// The interface is available on the InputPlumber bus name, and on the
+/// iio-sensor-proxy bus name if iio-sensor-proxy is not running.
+#[derive(Debug, Default)]
+pub struct SensorProxyInterface {
+    orientation: Orientation,
+    has_accelerometer: bool,
+}
+
+impl SensorProxyInterface {
+    pub fn new() -> SensorProxyInterface {
+        SensorProxyInterface::default()
+    }
+
+    /// Update the accelerometer orientation. Returns true if it changed.
+    pub fn set_orientation(&mut self, orientation: Orientation) -> bool {
+        let changed = !self.has_accelerometer || self.orientation != orientation;
+        self.has_accelerometer = true;
+        self.orientation = orientation;
+        changed
+    }
+}
+
+#[interface(name = "net.hadess.SensorProxy")]
+impl SensorProxyInterface {
+    /// Whether an accelerometer is available
+    #[zbus(property)]
+    async fn has_accelerometer(&self) -> fdo::Result<bool> {
+        Ok(self.has_accelerometer)
+    }
+
+    /// Orientation of the device. One of "undefined", "normal", "bottom-up",
+    /// "left-up", or "right-up".
+    #[zbus(property)]
+    async fn accelerometer_orientation(&self) -> fdo::Result<String> {
+        Ok(self.orientation.as_str().to_string())
+    }
+
+    /// Whether an ambient light sensor is available
+    #[zbus(property)]
+    async fn has_ambient_light(&self) -> fdo::Result<bool> {
+        Ok(false)
+    }
+
+    /// Whether a proximity sensor is available
+    #[zbus(property)]
+    async fn has_proximity(&self) -> fdo::Result<bool> {
+        Ok(false)
+    }
+
+    /// Start receiving accelerometer updates. Orientation is always updated
+    /// so this does nothing.
+    async fn claim_accelerometer(&self) -> fdo::Result<()> {
+        Ok(())
+    }
+
+    /// Stop receiving accelerometer updates
+    async fn release_accelerometer(&self) -> fdo::Result<()> {
+        Ok(())
+    }
+}
+
+/// Start the sensor proxy interface if it is not already running. The
+/// well-known name is only requested if iio-sensor-proxy is not running, and
+/// can be replaced by iio-sensor-proxy if it starts later.
+pub async fn start(conn: &Connection) -> Result<(), Box<dyn Error>> {
+    let iface = SensorProxyInterface::new();
+    if !conn.object_server().at(SENSOR_PROXY_PATH, iface).await? {
+        return Ok(());
+    }
+    let flags = RequestNameFlags::DoNotQueue | RequestNameFlags::AllowReplacement;
+    match conn.request_name_with_flags(SENSOR_PROXY_NAME, flags).await {
+        Ok(RequestNameReply::PrimaryOwner) => {
+            log::info!("Providing {SENSOR_PROXY_NAME}");
+        }
+        Ok(reply) => {
+            log::info!("Not providing {SENSOR_PROXY_NAME}, name already owned: {reply:?}");
+        }
+        Err(e) => {
+            log::info!("Not providing {SENSOR_PROXY_NAME}: {e:?}");
+        }
+    }
+
+    Ok(())
+}
+
+/// Update the orientation published by the sensor proxy interface and notify
+/// any listeners if it changed.
+pub async fn update_orientation(conn: &Connection, orientation: Orientation) {
+    let object_server = conn.object_server();
+    let Ok(iface_ref) = object_server
+        .interface::<_, SensorProxyInterface>(SENSOR_PROXY_PATH)
+        .await
+    else {
+        return;
+    };
+    let mut iface = iface_ref.get_mut().await;
+    if !iface.set_orientation(orientation) {
+        return;
+    }
+    log::debug!("Device orientation changed: {}", orientation.as_str());
+    let ctxt: &SignalContext = iface_ref.signal_context();
+    if let Err(e) = iface.accelerometer_orientation_changed(ctxt).await {
+        log::error!("Failed to signal orientation change: {e:?}");
+    }
+    if let Err(e) = iface.has_accelerometer_changed(ctxt).await {
+        log::error!("Failed to signal accelerometer change: {e:?}");
+    }
+}
@@ -0,0 +1,387 @@
+use tokio::sync::mpsc;
+use zbus::fdo;
+use zbus_macros::interface;
+
+use crate::input::{
+    capability::{Capability, Keyboard},
+    event::{native::NativeEvent, value::InputValue},
+    target::TargetCommand,
+};
+
+/// The [DBusInterface] provides a DBus interface that can be exposed for managing
+/// a [KeyboardDevice]. It works by sending command messages to a channel that the
+/// [KeyboardDevice] is listening on.
+pub struct TargetKeyboardInterface {
+    command_tx: mpsc::Sender<TargetCommand>,
+}
+
+impl TargetKeyboardInterface {
+    pub fn new(command_tx: mpsc::Sender<TargetCommand>) -> TargetKeyboardInterface {
+        TargetKeyboardInterface { command_tx }
+    }
+}
+
+#[interface(name = "org.shadowblip.Input.Keyboard")]
+impl TargetKeyboardInterface {
+    /// Name of the composite device
+    #[zbus(property)]
+    async fn name(&self) -> fdo::Result<String> {
+        Ok("Keyboard".into())
+    }
+
+    /// Send the given key to the virtual keyboard
+    async fn send_key(&self, key: String, value: bool) -> fdo::Result<()> {
+        // Create a NativeEvent to send to the keyboard
+        let capability = capability_from_key_string(key.as_str());
+        if matches!(capability, Capability::NotImplemented) {
+            return Err(fdo::Error::NotSupported("Invalid key code".into()));
+        }
+        let value = InputValue::Bool(value);
+        let event = NativeEvent::new(capability, value);
+
+        // Write the event to the virtual device
+        self.command_tx
+            .send(TargetCommand::WriteEvent(event))
+            .await
+            .map_err(|err| fdo::Error::Failed(err.to_string()))?;
+
+        Ok(())
+    }
+}
+
+/// Returns an input device capability from the given key string.
+fn capability_from_key_string(key: &str) -> Capability {
+    match key {
+        "KEY_ESC" => Capability::Keyboard(Keyboard::KeyEsc),
+        "KEY_1" => Capability::Keyboard(Keyboard::Key1),
+        "KEY_2" => Capability::Keyboard(Keyboard::Key2),
+        "KEY_3" => Capability::Keyboard(Keyboard::Key3),
+        "KEY_4" => Capability::Keyboard(Keyboard::Key4),
+        "KEY_5" => Capability::Keyboard(Keyboard::Key5),
+        "KEY_6" => Capability::Keyboard(Keyboard::Key6),
+        "KEY_7" => Capability::Keyboard(Keyboard::Key7),
+        "KEY_8" => Capability::Keyboard(Keyboard::Key8),
+        "KEY_9" => Capability::Keyboard(Keyboard::Key9),
+        "KEY_0" => Capability::Keyboard(Keyboard::Key0),
+        "KEY_MINUS" => Capability::Keyboard(Keyboard::KeyMinus),
+        "KEY_EQUAL" => Capability::Keyboard(Keyboard::KeyEqual),
+        "KEY_BACKSPACE" => Capability::Keyboard(Keyboard::KeyBackspace),
+        "KEY_TAB" => Capability::Keyboard(Keyboard::KeyTab),
+        "KEY_Q" => Capability::Keyboard(Keyboard::KeyQ),
+        "KEY_W" => Capability::Keyboard(Keyboard::KeyW),
+        "KEY_E" => Capability::Keyboard(Keyboard::KeyE),
+        "KEY_R" => Capability::Keyboard(Keyboard::KeyR),
+        "KEY_T" => Capability::Keyboard(Keyboard::KeyT),
+        "KEY_Y" => Capability::Keyboard(Keyboard::KeyY),
+        "KEY_U" => Capability::Keyboard(Keyboard::KeyU),
+        "KEY_I" => Capability::Keyboard(Keyboard::KeyI),
+        "KEY_O" => Capability::Keyboard(Keyboard::KeyO),
+        "KEY_P" => Capability::Keyboard(Keyboard::KeyP),
+        "KEY_LEFTBRACE" => Capability::Keyboard(Keyboard::KeyLeftBrace),
+        "KEY_RIGHTBRACE" => Capability::Keyboard(Keyboard::KeyRightBrace),
+        "KEY_ENTER" => Capability::Keyboard(Keyboard::KeyEnter),
+        "KEY_LEFTCTRL" => Capability::Keyboard(Keyboard::KeyLeftCtrl),
+        "KEY_A" => Capability::Keyboard(Keyboard::KeyA),
+        "KEY_S" => Capability::Keyboard(Keyboard::KeyS),
+        "KEY_D" => Capability::Keyboard(Keyboard::KeyD),
+        "KEY_F" => Capability::Keyboard(Keyboard::KeyF),
+        "KEY_G" => Capability::Keyboard(Keyboard::KeyG),
+        "KEY_H" => Capability::Keyboard(Keyboard::KeyH),
+        "KEY_J" => Capability::Keyboard(Keyboard::KeyJ),
+        "KEY_K" => Capability::Keyboard(Keyboard::KeyK),
+        "KEY_L" => Capability::Keyboard(Keyboard::KeyL),
+        "KEY_SEMICOLON" => Capability::Keyboard(Keyboard::KeySemicolon),
+        "KEY_APOSTROPHE" => Capability::Keyboard(Keyboard::KeyApostrophe),
+        "KEY_GRAVE" => Capability::Keyboard(Keyboard::KeyGrave),
+        "KEY_LEFTSHIFT" => Capability::Keyboard(Keyboard::KeyLeftShift),
+        "KEY_BACKSLASH" => Capability::Keyboard(Keyboard::KeyBackslash),
+        "KEY_Z" => Capability::Keyboard(Keyboard::KeyZ),
+        "KEY_X" => Capability::Keyboard(Keyboard::KeyX),
+        "KEY_C" => Capability::Keyboard(Keyboard::KeyC),
+        "KEY_V" => Capability::Keyboard(Keyboard::KeyV),
+        "KEY_B" => Capability::Keyboard(Keyboard::KeyB),
+        "KEY_N" => Capability::Keyboard(Keyboard::KeyN),
+        "KEY_M" => Capability::Keyboard(Keyboard::KeyM),
+        "KEY_COMMA" => Capability::Keyboard(Keyboard::KeyComma),
+        "KEY_DOT" => Capability::Keyboard(Keyboard::KeyDot),
+        "KEY_SLASH" => Capability::Keyboard(Keyboard::KeySlash),
+        "KEY_RIGHTSHIFT" => Capability::Keyboard(Keyboard::KeyRightShift),
+        "KEY_KPASTERISK" => Capability::Keyboard(Keyboard::KeyKpAsterisk),
+        "KEY_LEFTALT" => Capability::Keyboard(Keyboard::KeyLeftAlt),
+        "KEY_SPACE" => Capability::Keyboard(Keyboard::KeySpace),
+        "KEY_CAPSLOCK" => Capability::Keyboard(Keyboard::KeyCapslock),
+        "KEY_F1" => Capability::Keyboard(Keyboard::KeyF1),
+        "KEY_F2" => Capability::Keyboard(Keyboard::KeyF2),
+        "KEY_F3" => Capability::Keyboard(Keyboard::KeyF3),
+        "KEY_F4" => Capability::Keyboard(Keyboard::KeyF4),
+        "KEY_F5" => Capability::Keyboard(Keyboard::KeyF5),
+        "KEY_F6" => Capability::Keyboard(Keyboard::KeyF6),
+        "KEY_F7" => Capability::Keyboard(Keyboard::KeyF7),
+        "KEY_F8" => Capability::Keyboard(Keyboard::KeyF8),
+        "KEY_F9" => Capability::Keyboard(Keyboard::KeyF9),
+        "KEY_F10" => Capability::Keyboard(Keyboard::KeyF10),
+        "KEY_NUMLOCK" => Capability::Keyboard(Keyboard::KeyNumlock),
+        "KEY_SCROLLLOCK" => Capability::Keyboard(Keyboard::KeyScrollLock),
+        "KEY_KP7" => Capability::Keyboard(Keyboard::KeyKp7),
+        "KEY_KP8" => Capability::Keyboard(Keyboard::KeyKp8),
+        "KEY_KP9" => Capability::Keyboard(Keyboard::KeyKp9),
+        "KEY_KPMINUS" => Capability::Keyboard(Keyboard::KeyKpMinus),
+        "KEY_KP4" => Capability::Keyboard(Keyboard::KeyKp4),
+        "KEY_KP5" => Capability::Keyboard(Keyboard::KeyKp5),
+        "KEY_KP6" => Capability::Keyboard(Keyboard::KeyKp6),
+        "KEY_KPPLUS" => Capability::Keyboard(Keyboard::KeyKpPlus),
+        "KEY_KP1" => Capability::Keyboard(Keyboard::KeyKp1),
+        "KEY_KP2" => Capability::Keyboard(Keyboard::KeyKp2),
+        "KEY_KP3" => Capability::Keyboard(Keyboard::KeyKp3),
+        "KEY_KP0" => Capability::Keyboard(Keyboard::KeyKp0),
+        "KEY_KPDOT" => Capability::Keyboard(Keyboard::KeyKpDot),
+        "KEY_ZENKAKUHANKAKU" => Capability::Keyboard(Keyboard::KeyZenkakuhankaku),
+        "KEY_102ND" => Capability::Keyboard(Keyboard::Key102nd),
+        "KEY_F11" => Capability::Keyboard(Keyboard::KeyF11),
+        "KEY_F12" => Capability::Keyboard(Keyboard::KeyF12),
+        "KEY_RO" => Capability::Keyboard(Keyboard::KeyRo),
+        "KEY_KATAKANA" => Capability::Keyboard(Keyboard::KeyKatakana),
+        "KEY_HIRAGANA" => Capability::Keyboard(Keyboard::KeyHiragana),
+        "KEY_HENKAN" => Capability::Keyboard(Keyboard::KeyHenkan),
+        "KEY_KATAKANAHIRAGANA" => Capability::Keyboard(Keyboard::KeyKatakanaHiragana),
+        "KEY_MUHENKAN" => Capability::Keyboard(Keyboard::KeyMuhenkan),
+        "KEY_KPJPCOMMA" => Capability::Keyboard(Keyboard::KeyKpJpComma),
+        "KEY_KPENTER" => Capability::Keyboard(Keyboard::KeyKpEnter),
+        "KEY_RIGHTCTRL" => Capability::Keyboard(Keyboard::KeyRightCtrl),
+        "KEY_KPSLASH" => Capability::Keyboard(Keyboard::KeyKpSlash),
+        "KEY_SYSRQ" => Capability::Keyboard(Keyboard::KeySysrq),
+        "KEY_RIGHTALT" => Capability::Keyboard(Keyboard::KeyRightAlt),
+        "KEY_HOME" => Capability::Keyboard(Keyboard::KeyHome),
+        "KEY_UP" => Capability::Keyboard(Keyboard::KeyUp),
+        "KEY_PAGEUP" => Capability::Keyboard(Keyboard::KeyPageUp),
+        "KEY_LEFT" => Capability::Keyboard(Keyboard::KeyLeft),
+        "KEY_RIGHT" => Capability::Keyboard(Keyboard::KeyRight),
+        "KEY_END" => Capability::Keyboard(Keyboard::KeyEnd),
+        "KEY_DOWN" => Capability::Keyboard(Keyboard::KeyDown),
+        "KEY_PAGEDOWN" => Capability::Keyboard(Keyboard::KeyPageDown),
+        "KEY_INSERT" => Capability::Keyboard(Keyboard::KeyInsert),
+        "KEY_DELETE" => Capability::Keyboard(Keyboard::KeyDelete),
+        "KEY_MUTE" => Capability::Keyboard(Keyboard::KeyMute),
+        "KEY_VOLUMEDOWN" => Capability::Keyboard(Keyboard::KeyVolumeDown),
+        "KEY_VOLUMEUP" => Capability::Keyboard(Keyboard::KeyVolumeUp),
+        "KEY_POWER" => Capability::Keyboard(Keyboard::KeyPower),
+        "KEY_KPEQUAL" => Capability::Keyboard(Keyboard::KeyKpEqual),
+        "KEY_PAUSE" => Capability::Keyboard(Keyboard::KeyPause),
+        "KEY_KPCOMMA" => Capability::Keyboard(Keyboard::KeyKpComma),
+        "KEY_HANJA" => Capability::Keyboard(Keyboard::KeyHanja),
+        "KEY_YEN" => Capability::Keyboard(Keyboard::KeyYen),
+        "KEY_LEFTMETA" => Capability::Keyboard(Keyboard::KeyLeftMeta),
+        "KEY_RIGHTMETA" => Capability::Keyboard(Keyboard::KeyRightMeta),
+        "KEY_COMPOSE" => Capability::Keyboard(Keyboard::KeyCompose),
+        "KEY_STOP" => Capability::Keyboard(Keyboard::KeyStop),
+        "KEY_AGAIN" => Capability::Keyboard(Keyboard::KeyAgain),
+        "KEY_PROPS" => Capability::Keyboard(Keyboard::KeyProps),
+        "KEY_UNDO" => Capability::Keyboard(Keyboard::KeyUndo),
+        "KEY_FRONT" => Capability::Keyboard(Keyboard::KeyFront),
+        "KEY_COPY" => Capability::Keyboard(Keyboard::KeyCopy),
+        "KEY_OPEN" => Capability::Keyboard(Keyboard::KeyOpen),
+        "KEY_PASTE" => Capability::Keyboard(Keyboard::KeyPaste),
+        "KEY_FIND" => Capability::Keyboard(Keyboard::KeyFind),
+        "KEY_CUT" => Capability::Keyboard(Keyboard::KeyCut),
+        "KEY_HELP" => Capability::Keyboard(Keyboard::KeyHelp),
+        "KEY_CALC" => Capability::Keyboard(Keyboard::KeyCalc),
+        "KEY_SLEEP" => Capability::Keyboard(Keyboard::KeySleep),
+        "KEY_WWW" => Capability::Keyboard(Keyboard::KeyWww),
+        "KEY_BACK" => Capability::Keyboard(Keyboard::KeyBack),
+        "KEY_FORWARD" => Capability::Keyboard(Keyboard::KeyForward),
+        "KEY_EJECTCD" => Capability::Keyboard(Keyboard::KeyEjectCD),
+        "KEY_NEXTSONG" => Capability::Keyboard(Keyboard::KeyNextSong),
+        "KEY_PLAYPAUSE" => Capability::Keyboard(Keyboard::KeyPlayPause),
+        "KEY_PREVIOUSSONG" => Capability::Keyboard(Keyboard::KeyPreviousSong),
+        "KEY_STOPCD" => Capability::Keyboard(Keyboard::KeyStopCD),
+        "KEY_REFRESH" => Capability::Keyboard(Keyboard::KeyRefresh),
+        "KEY_EDIT" => Capability::Keyboard(Keyboard::KeyEdit),
+        "KEY_SCROLLUP" => Capability::Keyboard(Keyboard::KeyScrollUp),
+        "KEY_SCROLLDOWN" => Capability::Keyboard(Keyboard::KeyScrollDown),
+        "KEY_KPLEFTPAREN" => Capability::Keyboard(Keyboard::KeyKpLeftParen),
+        "KEY_KPRIGHTPAREN" => Capability::Keyboard(Keyboard::KeyKpRightParen),
+        "KEY_F13" => Capability::Keyboard(Keyboard::KeyF13),
+        "KEY_F14" => Capability::Keyboard(Keyboard::KeyF14),
+        "KEY_F15" => Capability::Keyboard(Keyboard::KeyF15),
+        "KEY_F16" => Capability::Keyboard(Keyboard::KeyF16),
+        "KEY_F17" => Capability::Keyboard(Keyboard::KeyF17),
+        "KEY_F18" => Capability::Keyboard(Keyboard::KeyF18),
+        "KEY_F19" => Capability::Keyboard(Keyboard::KeyF19),
+        "KEY_F20" => Capability::Keyboard(Keyboard::KeyF20),
+        "KEY_F21" => Capability::Keyboard(Keyboard::KeyF21),
+        "KEY_F22" => Capability::Keyboard(Keyboard::KeyF22),
+        "KEY_F23" => Capability::Keyboard(Keyboard::KeyF23),
+        "KEY_F24" => Capability::Keyboard(Keyboard::KeyF24),
+        "KEY_PROG1" => Capability::Keyboard(Keyboard::KeyProg1),
+        "KEY_PROG2" => Capability::Keyboard(Keyboard::KeyProg2),
+        "KEY_PROG3" => Capability::Keyboard(Keyboard::KeyProg3),
+        "KEY_PROG4" => Capability::Keyboard(Keyboard::KeyProg4),
+        "KEY_WLAN" => Capability::Keyboard(Keyboard::KeyWlan),
+        "KEY_RFKILL" => Capability::Keyboard(Keyboard::KeyRfkill),
+        "KEY_LINEFEED" => Capability::Keyboard(Keyboard::KeyLinefeed),
+        "KEY_MACRO" => Capability::Keyboard(Keyboard::KeyMacro),
+        "KEY_KPPLUSMINUS" => Capability::Keyboard(Keyboard::KeyKpPlusMinus),
+        "KEY_SCALE" => Capability::Keyboard(Keyboard::KeyScale),
+        "KEY_HANGEUL" => Capability::Keyboard(Keyboard::KeyHangeul),
+        "KEY_MENU" => Capability::Keyboard(Keyboard::KeyMenu),
+        "KEY_SETUP" => Capability::Keyboard(Keyboard::KeySetup),
+        "KEY_WAKEUP" => Capability::Keyboard(Keyboard::KeyWakeup),
+        "KEY_FILE" => Capability::Keyboard(Keyboard::KeyFile),
+        "KEY_SENDFILE" => Capability::Keyboard(Keyboard::KeySendFile),
+        "KEY_DELETEFILE" => Capability::Keyboard(Keyboard::KeyDeleteFile),
+        "KEY_XFER" => Capability::Keyboard(Keyboard::KeyXfer),
+        "KEY_MSDOS" => Capability::Keyboard(Keyboard::KeyMsdos),
+        "KEY_COFFEE" => Capability::Keyboard(Keyboard::KeyCoffee),
+        "KEY_ROTATE_DISPLAY" => Capability::Keyboard(Keyboard::KeyRotateDisplay),
+        "KEY_CYCLEWINDOWS" => Capability::Keyboard(Keyboard::KeyCycleWindows),
+        "KEY_MAIL" => Capability::Keyboard(Keyboard::KeyMail),
+        "KEY_BOOKMARKS" => Capability::Keyboard(Keyboard::KeyBookmarks),
+        "KEY_COMPUTER" => Capability::Keyboard(Keyboard::KeyComputer),
+        "KEY_CLOSECD" => Capability::Keyboard(Keyboard::KeyCloseCD),
+        "KEY_EJECTCLOSECD" => Capability::Keyboard(Keyboard::KeyEjectCloseCD),
+        "KEY_RECORD" => Capability::Keyboard(Keyboard::KeyRecord),
+        "KEY_REWIND" => Capability::Keyboard(Keyboard::KeyRewind),
+        "KEY_PHONE" => Capability::Keyboard(Keyboard::KeyPhone),
+        "KEY_ISO" => Capability::Keyboard(Keyboard::KeyIso),
+        "KEY_CONFIG" => Capability::Keyboard(Keyboard::KeyConfig),
+        "KEY_HOMEPAGE" => Capability::Keyboard(Keyboard::KeyHomepage),
+        "KEY_EXIT" => Capability::Keyboard(Keyboard::KeyExit),
+        "KEY_MOVE" => Capability::Keyboard(Keyboard::KeyMove),
+        "KEY_NEW" => Capability::Keyboard(Keyboard::KeyNew),
+        "KEY_REDO" => Capability::Keyboard(Keyboard::KeyRedo),
+        "KEY_PLAYCD" => Capability::Keyboard(Keyboard::KeyPlayCD),
+        "KEY_PAUSECD" => Capability::Keyboard(Keyboard::KeyPauseCD),
+        "KEY_DASHBOARD" => Capability::Keyboard(Keyboard::KeyDashboard),
+        "KEY_SUSPEND" => Capability::Keyboard(Keyboard::KeySuspend),
+        "KEY_CLOSE" => Capability::Keyboard(Keyboard::KeyClose),
+        "KEY_PLAY" => Capability::Keyboard(Keyboard::KeyPlay),
+        "KEY_FASTFORWARD" => Capability::Keyboard(Keyboard::KeyFastForward),
+        "KEY_BASSBOOST" => Capability::Keyboard(Keyboard::KeyBassBoost),
+        "KEY_PRINT" => Capability::Keyboard(Keyboard::KeyPrint),
+        "KEY_HP" => Capability::Keyboard(Keyboard::KeyHp),
+        "KEY_CAMERA" => Capability::Keyboard(Keyboard::KeyCamera),
+        "KEY_SOUND" => Capability::Keyboard(Keyboard::KeySound),
+        "KEY_QUESTION" => Capability::Keyboard(Keyboard::KeyQuestion),
+        "KEY_EMAIL" => Capability::Keyboard(Keyboard::KeyEmail),
+        "KEY_CHAT" => Capability::Keyboard(Keyboard::KeyChat),
+        "KEY_SEARCH" => Capability::Keyboard(Keyboard::KeySearch),
+        "KEY_CONNECT" => Capability::Keyboard(Keyboard::KeyConnect),
+        "KEY_FINANCE" => Capability::Keyboard(Keyboard::KeyFinance),
+        "KEY_SPORT" => Capability::Keyboard(Keyboard::KeySport),
+        "KEY_SHOP" => Capability::Keyboard(Keyboard::KeyShop),
+        "KEY_ALTERASE" => Capability::Keyboard(Keyboard::KeyAltErase),
+        "KEY_CANCEL" => Capability::Keyboard(Keyboard::KeyCancel),
+        "KEY_BRIGHTNESSDOWN" => Capability::Keyboard(Keyboard::KeyBrightnessDown),
+        "KEY_BRIGHTNESSUP" => Capability::Keyboard(Keyboard::KeyBrightnessUp),
+        "KEY_MEDIA" => Capability::Keyboard(Keyboard::KeyMedia),
+        "KEY_SWITCHVIDEOMODE" => Capability::Keyboard(Keyboard::KeySwitchVideoMode),
+        "KEY_KBDILLUMTOGGLE" => Capability::Keyboard(Keyboard::KeyKbdIllumToggle),
+        "KEY_KBDILLUMDOWN" => Capability::Keyboard(Keyboard::KeyKbdIllumDown),
+        "KEY_KBDILLUMUP" => Capability::Keyboard(Keyboard::KeyKbdIllumUp),
+        "KEY_SEND" => Capability::Keyboard(Keyboard::KeySend),
+        "KEY_REPLY" => Capability::Keyboard(Keyboard::KeyReply),
+        "KEY_FORWARDMAIL" => Capability::Keyboard(Keyboard::KeyForwardMail),
+        "KEY_SAVE" => Capability::Keyboard(Keyboard::KeySave),
+        "KEY_DOCUMENTS" => Capability::Keyboard(Keyboard::KeyDocuments),
+        "KEY_BATTERY" => Capability::Keyboard(Keyboard::KeyBattery),
+        "KEY_BLUETOOTH" => Capability::Keyboard(Keyboard::KeyBluetooth),
+        "KEY_UWB" => Capability::Keyboard(Keyboard::KeyUwb),
+        "KEY_VIDEO_NEXT" => Capability::Keyboard(Keyboard::KeyVideoNext),
+        "KEY_VIDEO_PREV" => Capability::Keyboard(Keyboard::KeyVideoPrev),
+        "KEY_BRIGHTNESS_CYCLE" => Capability::Keyboard(Keyboard::KeyBrightnessCycle),
+        "KEY_BRIGHTNESS_AUTO" => Capability::Keyboard(Keyboard::KeyBrightnessAuto),
+        "KEY_DISPLAY_OFF" => Capability::Keyboard(Keyboard::KeyDisplayOff),
+        "KEY_WWAN" => Capability::Keyboard(Keyboard::KeyWwan),
+        "KEY_OK" => Capability::Keyboard(Keyboard::KeyOk),
+        "KEY_SELECT" => Capability::Keyboard(Keyboard::KeySelect),
+        "KEY_GOTO" => Capability::Keyboard(Keyboard::KeyGoto),
+        "KEY_CLEAR" => Capability::Keyboard(Keyboard::KeyClear),
+        "KEY_POWER2" => Capability::Keyboard(Keyboard::KeyPower2),
+        "KEY_OPTION" => Capability::Keyboard(Keyboard::KeyOption),
+        "KEY_INFO" => Capability::Keyboard(Keyboard::KeyInfo),
+        "KEY_TIME" => Capability::Keyboard(Keyboard::KeyTime),
+        "KEY_VENDOR" => Capability::Keyboard(Keyboard::KeyVendor),
+        "KEY_ARCHIVE" => Capability::Keyboard(Keyboard::KeyArchive),
+        "KEY_PROGRAM" => Capability::Keyboard(Keyboard::KeyProgram),
+        "KEY_CHANNEL" => Capability::Keyboard(Keyboard::KeyChannel),
+        "KEY_FAVORITES" => Capability::Keyboard(Keyboard::KeyFavorites),
+        "KEY_EPG" => Capability::Keyboard(Keyboard::KeyEpg),
+        "KEY_PVR" => Capability::Keyboard(Keyboard::KeyPvr),
+        "KEY_MHP" => Capability::Keyboard(Keyboard::KeyMhp),
+        "KEY_LANGUAGE" => Capability::Keyboard(Keyboard::KeyLanguage),
+        "KEY_TITLE" => Capability::Keyboard(Keyboard::KeyTitle),
+        "KEY_SUBTITLE" => Capability::Keyboard(Keyboard::KeySubtitle),
+        "KEY_ANGLE" => Capability::Keyboard(Keyboard::KeyAngle),
+        "KEY_MODE" => Capability::Keyboard(Keyboard::KeyMode),
+        "KEY_KEYBOARD" => Capability::Keyboard(Keyboard::KeyKeyboard),
+        "KEY_SCREEN" => Capability::Keyboard(Keyboard::KeyScreen),
+        "KEY_PC" => Capability::Keyboard(Keyboard::KeyPc),
+        "KEY_TV" => Capability::Keyboard(Keyboard::KeyTv),
+        "KEY_TV2" => Capability::Keyboard(Keyboard::KeyTv2),
+        "KEY_VCR" => Capability::Keyboard(Keyboard::KeyVcr),
+        "KEY_VCR2" => Capability::Keyboard(Keyboard::KeyVcr2),
+        "KEY_SAT" => Capability::Keyboard(Keyboard::KeySat),
+        "KEY_SAT2" => Capability::Keyboard(Keyboard::KeySat2),
+        "KEY_CD" => Capability::Keyboard(Keyboard::KeyCd),
+        "KEY_TAPE" => Capability::Keyboard(Keyboard::KeyTape),
+        "KEY_RADIO" => Capability::Keyboard(Keyboard::KeyRadio),
+        "KEY_TUNER" => Capability::Keyboard(Keyboard::KeyTuner),
+        "KEY_PLAYER" => Capability::Keyboard(Keyboard::KeyPlayer),
+        "KEY_TEXT" => Capability::Keyboard(Keyboard::KeyText),
+        "KEY_DVD" => Capability::Keyboard(Keyboard::KeyDvd),
+        "KEY_AUX" => Capability::Keyboard(Keyboard::KeyAux),
+        "KEY_MP3" => Capability::Keyboard(Keyboard::KeyMp3),
+        "KEY_AUDIO" => Capability::Keyboard(Keyboard::KeyAudio),
+        "KEY_VIDEO" => Capability::Keyboard(Keyboard::KeyVideo),
+        "KEY_DIRECTORY" => Capability::Keyboard(Keyboard::KeyDirectory),
+        "KEY_LIST" => Capability::Keyboard(Keyboard::KeyList),
+        "KEY_MEMO" => Capability::Keyboard(Keyboard::KeyMemo),
+        "KEY_CALENDAR" => Capability::Keyboard(Keyboard::KeyCalendar),
+        "KEY_RED" => Capability::Keyboard(Keyboard::KeyRed),
+        "KEY_GREEN" => Capability::Keyboard(Keyboard::KeyGreen),
+        "KEY_YELLOW" => Capability::Keyboard(Keyboard::KeyYellow),
+        "KEY_BLUE" => Capability::Keyboard(Keyboard::KeyBlue),
+        "KEY_CHANNELUP" => Capability::Keyboard(Keyboard::KeyChannelUp),
+        "KEY_CHANNELDOWN" => Capability::Keyboard(Keyboard::KeyChannelDown),
+        "KEY_FIRST" => Capability::Keyboard(Keyboard::KeyFirst),
+        "KEY_LAST" => Capability::Keyboard(Keyboard::KeyLast),
+        "KEY_AB" => Capability::Keyboard(Keyboard::KeyAb),
+        "KEY_NEXT" => Capability::Keyboard(Keyboard::KeyNext),
+        "KEY_RESTART" => Capability::Keyboard(Keyboard::KeyRestart),
+        "KEY_SLOW" => Capability::Keyboard(Keyboard::KeySlow),
+        "KEY_SHUFFLE" => Capability::Keyboard(Keyboard::KeyShuffle),
+        "KEY_BREAK" => Capability::Keyboard(Keyboard::KeyBreak),
+        "KEY_PREVIOUS" => Capability::Keyboard(Keyboard::KeyPrevious),
+        "KEY_DIGITS" => Capability::Keyboard(Keyboard::KeyDigits),
+        "KEY_TEEN" => Capability::Keyboard(Keyboard::KeyTeen),
+        "KEY_TWEN" => Capability::Keyboard(Keyboard::KeyTwen),
+        "KEY_VIDEOPHONE" => Capability::Keyboard(Keyboard::KeyVideophone),
+        "KEY_GAMES" => Capability::Keyboard(Keyboard::KeyGames),
+        "KEY_ZOOMIN" => Capability::Keyboard(Keyboard::KeyZoomIn),
+        "KEY_ZOOMOUT" => Capability::Keyboard(Keyboard::KeyZoomOut),
+        "KEY_ZOOMRESET" => Capability::Keyboard(Keyboard::KeyZoomReset),
+        "KEY_WORDPROCESSOR" => Capability::Keyboard(Keyboard::KeyWordProcessor),
+        "KEY_EDITOR" => Capability::Keyboard(Keyboard::KeyEditor),
+        "KEY_SPREADSHEET" => Capability::Keyboard(Keyboard::KeySpreadsheet),
+        "KEY_GRAPHICSEDITOR" => Capability::Keyboard(Keyboard::KeyGraphicsEditor),
+        "KEY_PRESENTATION" => Capability::Keyboard(Keyboard::KeyPresentation),
+        "KEY_DATABASE" => Capability::Keyboard(Keyboard::KeyDatabase),
+        "KEY_NEWS" => Capability::Keyboard(Keyboard::KeyNews),
+        "KEY_VOICEMAIL" => Capability::Keyboard(Keyboard::KeyVoicemail),
+        "KEY_ADDRESSBOOK" => Capability::Keyboard(Keyboard::KeyAddressBook),
+        "KEY_MESSENGER" => Capability::Keyboard(Keyboard::KeyMessenger),
+        "KEY_DISPLAYTOGGLE" => Capability::Keyboard(Keyboard::KeyDisplayToggle),
+        "KEY_SPELLCHECK" => Capability::Keyboard(Keyboard::KeySpellCheck),
+        "KEY_LOGOFF" => Capability::Keyboard(Keyboard::KeyLogoff),
+        "KEY_DOLLAR" => Capability::Keyboard(Keyboard::KeyDollar),
+        "KEY_EURO" => Capability::Keyboard(Keyboard::KeyEuro),
+        "KEY_FRAMEBACK" => Capability::Keyboard(Keyboard::KeyFrameBack),
+        "KEY_FRAMEFORWARD" => Capability::Keyboard(Keyboard::KeyFrameForward),
+        "KEY_CONTEXT_MENU" => Capability::Keyboard(Keyboard::KeyContextMenu),
+        "KEY_MEDIA_REPEAT" => Capability::Keyboard(Keyboard::KeyMediaRepeat),
+        "KEY_10CHANNELSUP" => Capability::Keyboard(Keyboard::Key10ChannelsUp),
+        "KEY_10CHANNELSDOWN" => Capability::Keyboard(Keyboard::Key10ChannelsDown),
+        "KEY_IMAGES" => Capability::Keyboard(Keyboard::KeyImages),
+        _ => Capability::NotImplemented,
+    }
+}
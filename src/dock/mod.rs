@@ -0,0 +1,149 @@
+use std::{
+    error::Error,
+    fs,
+    path::{Path, PathBuf},
+    process::Stdio,
+    time::Duration,
+};
+
+use tokio::{
+    io::{AsyncBufReadExt, BufReader},
+    process::Command,
+    sync::broadcast,
+    time,
+};
+
+use crate::input::manager::ManagerCommand;
+
+/// Directory containing the DRM connectors of all graphics cards
+const DRM_CLASS_DIR: &str = "/sys/class/drm";
+/// Connector types used for built-in displays
+const INTERNAL_CONNECTORS: &[&str] = &["eDP", "LVDS", "DSI"];
+/// Time to wait before monitoring udev again if udevadm exits
+const RETRY_INTERVAL: Duration = Duration::from_secs(5);
+
+/// The [DockWatcher] watches the DRM connectors of the system for external
+/// displays and notifies the input manager when the device is docked or
+/// undocked, so the matching profile can be loaded automatically. The
+/// connectors are checked whenever udev reports a DRM hotplug event.
+///
+/// Only devices with a built-in display can be docked. A desktop with a
+/// monitor connected over DisplayPort or HDMI is never considered docked.
+#[derive(Debug)]
+pub struct DockWatcher {
+    manager: broadcast::Sender<ManagerCommand>,
+    docked: bool,
+}
+
+impl DockWatcher {
+    pub fn new(manager: broadcast::Sender<ManagerCommand>) -> Self {
+        Self {
+            manager,
+            docked: false,
+        }
+    }
+
+    /// Start watching for dock state changes in a new task if the system has
+    /// a built-in display
+    pub fn spawn(mut self) {
+        if !has_internal_display() {
+            log::info!("No built-in display found. Not watching for dock state changes.");
+            return;
+        }
+        tokio::spawn(async move { self.run().await });
+    }
+
+    /// Check the connector status on every DRM hotplug event. If udevadm
+    /// exits, it is started again after a delay.
+    async fn run(&mut self) {
+        loop {
+            self.set_docked(is_external_display_connected());
+            if let Err(e) = self.watch_hotplug().await {
+                log::warn!("Stopped watching for DRM hotplug events: {e:?}");
+            }
+            time::sleep(RETRY_INTERVAL).await;
+        }
+    }
+
+    /// Monitor udev for DRM events and update the dock state for each one
+    async fn watch_hotplug(&mut self) -> Result<(), Box<dyn Error>> {
+        let mut child = Command::new("udevadm")
+            .args(["monitor", "--kernel", "--subsystem-match=drm"])
+            .stdout(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()?;
+        let Some(stdout) = child.stdout.take() else {
+            return Err("Unable to read udevadm output".into());
+        };
+
+        // Events are printed as e.g. "KERNEL[12.34] change /devices/.../drm/card1 (drm)"
+        let mut lines = BufReader::new(stdout).lines();
+        while let Some(line) = lines.next_line().await? {
+            if !line.ends_with("(drm)") {
+                continue;
+            }
+            log::trace!("DRM event: {line}");
+            self.set_docked(is_external_display_connected());
+        }
+
+        let status = child.wait().await?;
+        Err(format!("udevadm exited with {status}").into())
+    }
+
+    /// Notify the input manager if the dock state changed
+    fn set_docked(&mut self, docked: bool) {
+        if self.docked == docked {
+            return;
+        }
+        log::info!("Dock state changed. Docked: {docked}");
+        self.docked = docked;
+        if let Err(e) = self
+            .manager
+            .send(ManagerCommand::DockStateChanged { docked })
+        {
+            log::error!("Failed to send dock state change: {e:?}");
+        }
+    }
+}
+
+/// Returns the type and path of every DRM connector. Connectors are named
+/// after the card and connector type (e.g. "HDMI-A-1" for "card1-HDMI-A-1").
+fn get_connectors() -> Vec<(String, PathBuf)> {
+    let Ok(entries) = fs::read_dir(DRM_CLASS_DIR) else {
+        return Vec::new();
+    };
+    entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            let (_, connector) = name.split_once('-')?;
+            Some((connector.to_string(), entry.path()))
+        })
+        .collect()
+}
+
+/// Returns true if the given connector type is used for built-in displays
+fn is_internal(connector: &str) -> bool {
+    INTERNAL_CONNECTORS
+        .iter()
+        .any(|internal| connector.starts_with(internal))
+}
+
+/// Returns true if the system has a built-in display
+fn has_internal_display() -> bool {
+    get_connectors()
+        .iter()
+        .any(|(connector, _)| is_internal(connector))
+}
+
+/// Returns true if any external display is connected
+fn is_external_display_connected() -> bool {
+    get_connectors()
+        .iter()
+        .any(|(connector, path)| !is_internal(connector) && is_connected(path))
+}
+
+/// Returns true if the given DRM connector reports a connected display
+fn is_connected(connector: &Path) -> bool {
+    fs::read_to_string(connector.join("status")).is_ok_and(|status| status.trim() == "connected")
+}
@@ -0,0 +1,193 @@
+//! Playback of rumble as audio waveforms for devices whose linear resonant
+//! actuators (LRAs) are driven through an audio output instead of HID rumble
+//! commands.
+//!
+//! Strong and weak rumble intensities are converted into sine waves at the
+//! configured low and high frequencies and streamed as raw PCM to the ALSA
+//! device of the actuators using `aplay`.
+use std::{
+    error::Error,
+    f64::consts::TAU,
+    io::Write,
+    process::{Child, Command, Stdio},
+    time::{Duration, Instant},
+};
+
+use crate::config::AudioHapticsConfig;
+
+/// Default sample rate of the waveforms in Hz
+const DEFAULT_SAMPLE_RATE: u32 = 48000;
+/// Default number of output channels
+const DEFAULT_CHANNELS: u8 = 2;
+/// Default frequency in Hz used for the strong (low frequency) motor
+const DEFAULT_LOW_FREQUENCY_HZ: f64 = 80.0;
+/// Default frequency in Hz used for the weak (high frequency) motor
+const DEFAULT_HIGH_FREQUENCY_HZ: f64 = 160.0;
+/// Default length of each written waveform chunk in milliseconds
+const DEFAULT_PULSE_MS: u64 = 20;
+
+/// Settings used to generate waveforms
+#[derive(Debug, Clone, PartialEq)]
+pub struct WaveformSettings {
+    pub sample_rate: u32,
+    pub channels: u8,
+    pub low_frequency_hz: f64,
+    pub high_frequency_hz: f64,
+    pub gain: f64,
+}
+
+impl From<&AudioHapticsConfig> for WaveformSettings {
+    fn from(config: &AudioHapticsConfig) -> Self {
+        Self {
+            sample_rate: config.sample_rate.unwrap_or(DEFAULT_SAMPLE_RATE),
+            channels: config.channels.unwrap_or(DEFAULT_CHANNELS).clamp(1, 2),
+            low_frequency_hz: config.low_frequency_hz.unwrap_or(DEFAULT_LOW_FREQUENCY_HZ),
+            high_frequency_hz: config
+                .high_frequency_hz
+                .unwrap_or(DEFAULT_HIGH_FREQUENCY_HZ),
+            gain: config.gain.unwrap_or(1.0).clamp(0.0, 1.0),
+        }
+    }
+}
+
+impl WaveformSettings {
+    /// Generate interleaved 16-bit samples for the given strong and weak
+    /// intensities from 0.0 to 1.0, starting at the given sample offset so
+    /// consecutive chunks stay in phase. With two channels the strong motor
+    /// plays on the left channel and the weak motor on the right channel,
+    /// otherwise both are mixed into a single channel.
+    pub fn generate(&self, strong: f64, weak: f64, offset: u64, frames: usize) -> Vec<i16> {
+        let rate = self.sample_rate as f64;
+        let strong = strong.clamp(0.0, 1.0) * self.gain;
+        let weak = weak.clamp(0.0, 1.0) * self.gain;
+        let mut samples = Vec::with_capacity(frames * self.channels as usize);
+        for frame in 0..frames {
+            let t = (offset + frame as u64) as f64 / rate;
+            let low = strong * (TAU * self.low_frequency_hz * t).sin();
+            let high = weak * (TAU * self.high_frequency_hz * t).sin();
+            if self.channels == 1 {
+                samples.push(to_sample((low + high).clamp(-1.0, 1.0)));
+            } else {
+                samples.push(to_sample(low));
+                samples.push(to_sample(high));
+            }
+        }
+        samples
+    }
+}
+
+/// Streams rumble as audio waveforms to an ALSA device
+#[derive(Debug)]
+pub struct AudioHaptics {
+    device: String,
+    settings: WaveformSettings,
+    pulse: Duration,
+    player: Option<Child>,
+    strong: f64,
+    weak: f64,
+    /// Number of frames written since playback started
+    offset: u64,
+    /// Time at which the next chunk must be written to avoid a gap
+    next_write: Option<Instant>,
+}
+
+impl AudioHaptics {
+    pub fn new(config: &AudioHapticsConfig) -> Self {
+        let pulse_ms = config.pulse_ms.unwrap_or(DEFAULT_PULSE_MS).max(1);
+        Self {
+            device: config.device.clone(),
+            settings: config.into(),
+            pulse: Duration::from_millis(pulse_ms),
+            player: None,
+            strong: 0.0,
+            weak: 0.0,
+            offset: 0,
+            next_write: None,
+        }
+    }
+
+    /// Set the strong and weak rumble intensities (0-65535)
+    pub fn set_rumble(&mut self, strong: u16, weak: u16) {
+        self.strong = strong as f64 / u16::MAX as f64;
+        self.weak = weak as f64 / u16::MAX as f64;
+    }
+
+    /// Write the next waveform chunk if the previous one is about to finish.
+    /// This should be called regularly from the driver loop.
+    pub fn update(&mut self) -> Result<(), Box<dyn Error + Send + Sync>> {
+        if self.strong == 0.0 && self.weak == 0.0 {
+            self.next_write = None;
+            return Ok(());
+        }
+        let now = Instant::now();
+        if self.next_write.is_some_and(|at| now < at) {
+            return Ok(());
+        }
+
+        let frames = (self.settings.sample_rate as u128 * self.pulse.as_millis() / 1000) as usize;
+        let samples = self
+            .settings
+            .generate(self.strong, self.weak, self.offset, frames);
+        let bytes: Vec<u8> = samples.iter().flat_map(|s| s.to_le_bytes()).collect();
+        if let Err(e) = self.write(&bytes) {
+            // Restart the player with the next chunk
+            self.stop();
+            return Err(e);
+        }
+        self.offset += frames as u64;
+
+        // Write the next chunk shortly before this one finishes playing
+        let next = self.next_write.unwrap_or(now) + self.pulse;
+        self.next_write = Some(next.max(now + self.pulse / 2));
+        Ok(())
+    }
+
+    /// Stop playback
+    pub fn stop(&mut self) {
+        if let Some(mut player) = self.player.take() {
+            if let Err(e) = player.kill() {
+                log::debug!("Failed to stop audio haptics player: {e:?}");
+            }
+            let _ = player.wait();
+        }
+        self.next_write = None;
+        self.offset = 0;
+    }
+
+    /// Write the given PCM data to the player, starting it if needed
+    fn write(&mut self, data: &[u8]) -> Result<(), Box<dyn Error + Send + Sync>> {
+        if self.player.is_none() {
+            log::debug!("Starting audio haptics playback on {}", self.device);
+            let player = Command::new("aplay")
+                .args(["-q", "-t", "raw", "-f", "S16_LE"])
+                .args(["-D", self.device.as_str()])
+                .args(["-r", self.settings.sample_rate.to_string().as_str()])
+                .args(["-c", self.settings.channels.to_string().as_str()])
+                .stdin(Stdio::piped())
+                .stdout(Stdio::null())
+                .stderr(Stdio::null())
+                .spawn()?;
+            self.player = Some(player);
+        }
+        let Some(stdin) = self
+            .player
+            .as_mut()
+            .and_then(|player| player.stdin.as_mut())
+        else {
+            return Err("Audio haptics player has no input".into());
+        };
+        stdin.write_all(data)?;
+        Ok(())
+    }
+}
+
+impl Drop for AudioHaptics {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+/// Convert the given value from -1.0 to 1.0 into a 16-bit sample
+fn to_sample(value: f64) -> i16 {
+    (value * i16::MAX as f64).round() as i16
+}
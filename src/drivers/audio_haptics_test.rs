@@ -0,0 +1,35 @@
+use crate::drivers::audio_haptics::WaveformSettings;
+
+fn settings(channels: u8) -> WaveformSettings {
+    WaveformSettings {
+        sample_rate: 1000,
+        channels,
+        low_frequency_hz: 250.0,
+        high_frequency_hz: 125.0,
+        gain: 1.0,
+    }
+}
+
+#[test]
+fn test_generate_stereo() {
+    let samples = settings(2).generate(1.0, 0.5, 0, 4);
+    assert_eq!(samples.len(), 8);
+
+    // The strong motor plays on the left channel at a quarter period per frame
+    let left: Vec<i16> = samples.iter().step_by(2).copied().collect();
+    assert_eq!(left, vec![0, i16::MAX, 0, -i16::MAX]);
+
+    // The weak motor plays on the right channel at half intensity
+    assert_eq!(samples[5], (0.5 * i16::MAX as f64).round() as i16);
+}
+
+#[test]
+fn test_generate_continues_phase() {
+    let settings = settings(1);
+    let first = settings.generate(1.0, 0.0, 0, 8);
+    let second = settings.generate(1.0, 0.0, 4, 4);
+    assert_eq!(first[4..], second[..]);
+
+    let silence = settings.generate(0.0, 0.0, 0, 4);
+    assert!(silence.iter().all(|sample| *sample == 0));
+}
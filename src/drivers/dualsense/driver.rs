@@ -1,4 +1,13 @@
 // Source: https://github.com/torvalds/linux/blob/master/drivers/hid/hid-playstation.c
+use std::{error::Error, ffi::CString};
+
+use hidapi::BusType;
+use packed_struct::{PackedStruct, PrimitiveEnum};
+
+use crate::drivers::transport::{HidTransport, QueuedTransport};
+
+use super::hid_report::{PowerState, SetStatePackedOutputData, UsbPackedOutputReport};
+
 pub const DS5_EDGE_NAME: &str = "Sony Interactive Entertainment DualSense Edge Wireless Controller";
 pub const DS5_EDGE_VERSION: u16 = 256;
 pub const DS5_EDGE_VID: u16 = 0x054C;
@@ -47,3 +56,186 @@ pub const DS5_GYRO_RES_PER_DEG_S: u32 = 1024;
 pub const DS5_GYRO_RANGE: u32 = 2048 * DS5_GYRO_RES_PER_DEG_S;
 pub const DS5_TOUCHPAD_WIDTH: f64 = 1920.0;
 pub const DS5_TOUCHPAD_HEIGHT: f64 = 1080.0;
+
+/// Tag of the Bluetooth output report
+pub const OUTPUT_TAG_BT: u8 = 0x10;
+/// Seed byte of the CRC32 of Bluetooth output reports
+const OUTPUT_CRC_SEED_BT: u8 = 0xa2;
+
+/// Seed byte of the CRC32 of Bluetooth input reports
+const INPUT_CRC_SEED_BT: u8 = 0xa1;
+
+/// DualSense timestamps count in units of 1/3 µs
+pub const DS5_TIMESTAMP_TICKS_PER_US: u128 = 3;
+/// The touch data timestamp counts in units of 2048 timestamp ticks (~682.7µs)
+pub const DS5_TOUCH_TIMESTAMP_SHIFT: u32 = 11;
+
+/// Offset of the battery status byte in USB input reports
+const INPUT_BATTERY_OFFSET_USB: usize = 53;
+/// Offset of the battery status byte in Bluetooth input reports, which have
+/// an extra tag byte before the input data
+const INPUT_BATTERY_OFFSET_BT: usize = 54;
+/// Time to wait for an input report in milliseconds
+const POLL_TIMEOUT: i32 = 100;
+
+/// Battery state read from DualSense input reports
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Battery {
+    /// Battery level in percent
+    pub level: u8,
+    pub power_state: PowerState,
+}
+
+impl Battery {
+    /// Returns the battery state from the status byte of an input report. The
+    /// high nibble is the power state and the low nibble the battery level
+    /// from 0 to 10.
+    fn from_status(status: u8) -> Option<Self> {
+        let power_state = PowerState::from_primitive(status >> 4)?;
+        let level = match power_state {
+            PowerState::Disharging | PowerState::Charging => ((status & 0x0f) * 10 + 5).min(100),
+            PowerState::Complete => 100,
+            _ => 0,
+        };
+        Some(Self { level, power_state })
+    }
+}
+
+/// Driver that writes output reports to a physical DualSense. Input is read
+/// through the kernel evdev devices, so this driver only reads input reports
+/// for the battery state.
+pub struct Driver {
+    device: Box<dyn HidTransport>,
+    bluetooth: bool,
+    /// Sequence number of Bluetooth output reports
+    sequence: u8,
+    /// Last battery state read from the controller
+    battery: Option<Battery>,
+}
+
+impl Driver {
+    pub fn new(path: String) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        let fmtpath = path.clone();
+        let path = CString::new(path)?;
+        let api = hidapi::HidApi::new()?;
+        let device = api.open_path(&path)?;
+        let info = device.get_device_info()?;
+        if info.vendor_id() != DS5_VID
+            || (info.product_id() != DS5_PID && info.product_id() != DS5_EDGE_PID)
+        {
+            return Err(format!("Device '{fmtpath}' is not a DualSense").into());
+        }
+        let bluetooth = info.bus_type() == BusType::Bluetooth;
+
+        Ok(Self::from_transport(
+            Box::new(QueuedTransport::new(device)),
+            bluetooth,
+        ))
+    }
+
+    /// Create a driver that writes reports over the given transport
+    pub fn from_transport(device: Box<dyn HidTransport>, bluetooth: bool) -> Self {
+        Self {
+            device,
+            bluetooth,
+            sequence: 0,
+            battery: None,
+        }
+    }
+
+    /// Read the next input report from the controller. Queued output reports
+    /// are written in between reads. Returns the battery state if it changed.
+    pub fn poll(&mut self) -> Result<Option<Battery>, Box<dyn Error + Send + Sync>> {
+        let mut buf = [0; INPUT_REPORT_BT_SIZE];
+        let bytes_read = self.device.read_timeout(&mut buf[..], POLL_TIMEOUT)?;
+        let offset = match buf[0] {
+            INPUT_REPORT_USB if bytes_read >= INPUT_REPORT_USB_SIZE => INPUT_BATTERY_OFFSET_USB,
+            INPUT_REPORT_BT if bytes_read >= INPUT_REPORT_BT_SIZE => INPUT_BATTERY_OFFSET_BT,
+            // Reduced Bluetooth reports don't include the battery state
+            _ => return Ok(None),
+        };
+        let Some(battery) = Battery::from_status(buf[offset]) else {
+            return Ok(None);
+        };
+        if self.battery == Some(battery) {
+            return Ok(None);
+        }
+        self.battery = Some(battery);
+
+        Ok(Some(battery))
+    }
+
+    /// Write the given output state, such as trigger effects, lightbar color
+    /// and player lights, to the controller
+    pub fn write_state(
+        &mut self,
+        state: SetStatePackedOutputData,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let buf = if self.bluetooth {
+            self.bluetooth_report(state)?
+        } else {
+            let report = UsbPackedOutputReport {
+                report_id: OUTPUT_REPORT_USB,
+                state,
+            };
+            report.pack()?.to_vec()
+        };
+        self.device.write(&buf)?;
+
+        Ok(())
+    }
+
+    /// Returns the Bluetooth output report for the given state, which is
+    /// tagged with a sequence number and ends with a CRC32 checksum.
+    fn bluetooth_report(
+        &mut self,
+        state: SetStatePackedOutputData,
+    ) -> Result<Vec<u8>, Box<dyn Error + Send + Sync>> {
+        let mut buf = vec![0; OUTPUT_REPORT_BT_SIZE];
+        buf[0] = OUTPUT_REPORT_BT;
+        buf[1] = self.sequence << 4;
+        buf[2] = OUTPUT_TAG_BT;
+        let state = state.pack()?;
+        buf[3..3 + state.len()].copy_from_slice(&state);
+        self.sequence = (self.sequence + 1) % 16;
+
+        let crc_offset = OUTPUT_REPORT_BT_SIZE - 4;
+        let crc = crc32(&[&[OUTPUT_CRC_SEED_BT], &buf[..crc_offset]]);
+        buf[crc_offset..].copy_from_slice(&crc.to_le_bytes());
+
+        Ok(buf)
+    }
+}
+
+/// Returns the Bluetooth input report for the given USB input report. The
+/// Bluetooth report contains the same input data after an extra tag byte with
+/// the sequence number, and ends with a CRC32 checksum.
+pub fn bluetooth_input_report(usb_report: &[u8], sequence: u8) -> Vec<u8> {
+    let mut buf = vec![0; INPUT_REPORT_BT_SIZE];
+    buf[0] = INPUT_REPORT_BT;
+    buf[1] = sequence << 4;
+
+    // Skip the report id of the USB report
+    let crc_offset = INPUT_REPORT_BT_SIZE - 4;
+    let data = usb_report.get(1..).unwrap_or_default();
+    let size = data.len().min(crc_offset - 2);
+    buf[2..2 + size].copy_from_slice(&data[..size]);
+
+    let crc = crc32(&[&[INPUT_CRC_SEED_BT], &buf[..crc_offset]]);
+    buf[crc_offset..].copy_from_slice(&crc.to_le_bytes());
+
+    buf
+}
+
+/// Returns the CRC32 (IEEE) checksum of the given chunks of data
+pub fn crc32(chunks: &[&[u8]]) -> u32 {
+    let mut crc = 0xffffffff_u32;
+    for byte in chunks.iter().flat_map(|chunk| chunk.iter()) {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (0xedb88320 & mask);
+        }
+    }
+    !crc
+}
@@ -0,0 +1,125 @@
+use std::{error::Error, sync::Arc};
+
+use packed_struct::PackedStruct;
+
+use crate::drivers::{
+    dualsense::{
+        driver::{
+            bluetooth_input_report, crc32, Battery, Driver, INPUT_REPORT_BT_SIZE,
+            OUTPUT_REPORT_BT_SIZE, OUTPUT_REPORT_USB_SIZE,
+        },
+        hid_report::{PowerState, SetStatePackedOutputData},
+    },
+    mock_transport::MockTransport,
+};
+
+/// Returns an output state that sets the lightbar color
+fn lightbar_state() -> SetStatePackedOutputData {
+    let mut state = SetStatePackedOutputData::unpack(&[0; 47]).unwrap();
+    state.allow_led_color = true;
+    state.led_red = 0x11;
+    state.led_green = 0x22;
+    state.led_blue = 0x33;
+    state
+}
+
+#[test]
+fn test_dualsense_crc32() {
+    assert_eq!(crc32(&[b"1234", b"56789"]), 0xcbf43926);
+}
+
+#[tokio::test]
+async fn test_dualsense_usb_output_report() -> Result<(), Box<dyn Error>> {
+    let transport = Arc::new(MockTransport::new(vec![]));
+    let mut driver = Driver::from_transport(Box::new(transport.clone()), false);
+
+    driver.write_state(lightbar_state())?;
+
+    let written = transport.written();
+    assert_eq!(written.len(), 1);
+    assert_eq!(written[0].len(), OUTPUT_REPORT_USB_SIZE);
+    assert_eq!(written[0][0], 0x02);
+    assert_eq!(written[0][45..48], [0x11, 0x22, 0x33]);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_dualsense_bluetooth_output_report() -> Result<(), Box<dyn Error>> {
+    let transport = Arc::new(MockTransport::new(vec![]));
+    let mut driver = Driver::from_transport(Box::new(transport.clone()), true);
+
+    driver.write_state(lightbar_state())?;
+    driver.write_state(lightbar_state())?;
+
+    let written = transport.written();
+    assert_eq!(written.len(), 2);
+    let report = &written[1];
+    assert_eq!(report.len(), OUTPUT_REPORT_BT_SIZE);
+    assert_eq!(report[..3], [0x31, 0x10, 0x10]);
+    assert_eq!(report[47..50], [0x11, 0x22, 0x33]);
+    let crc = crc32(&[&[0xa2], &report[..OUTPUT_REPORT_BT_SIZE - 4]]);
+    assert_eq!(report[OUTPUT_REPORT_BT_SIZE - 4..], crc.to_le_bytes());
+
+    Ok(())
+}
+
+#[test]
+fn test_dualsense_bluetooth_input_report() {
+    let mut usb = vec![0; 64];
+    usb[0] = 0x01;
+    usb[1] = 0x80; // Left stick X
+    usb[33] = 0x05; // Touch contact id
+    usb[53] = 0x14; // Battery
+
+    let report = bluetooth_input_report(&usb, 3);
+    assert_eq!(report.len(), INPUT_REPORT_BT_SIZE);
+    assert_eq!(report[..3], [0x31, 0x30, 0x80]);
+    assert_eq!(report[34], 0x05);
+    assert_eq!(report[54], 0x14);
+    let crc = crc32(&[&[0xa1], &report[..INPUT_REPORT_BT_SIZE - 4]]);
+    assert_eq!(report[INPUT_REPORT_BT_SIZE - 4..], crc.to_le_bytes());
+}
+
+#[tokio::test]
+async fn test_dualsense_battery() -> Result<(), Box<dyn Error>> {
+    let mut usb = vec![0; 64];
+    usb[0] = 0x01;
+    usb[53] = 0x14; // Charging, 40%
+    let mut bluetooth = vec![0; 78];
+    bluetooth[0] = 0x31;
+    bluetooth[54] = 0x2a; // Fully charged
+    let transport = Arc::new(MockTransport::new(vec![
+        usb.clone(),
+        usb,
+        bluetooth,
+        vec![0x01; 10],
+    ]));
+    let mut driver = Driver::from_transport(Box::new(transport.clone()), false);
+
+    let battery = driver.poll()?;
+    assert_eq!(
+        battery,
+        Some(Battery {
+            level: 45,
+            power_state: PowerState::Charging
+        })
+    );
+
+    // The battery state is only reported when it changes
+    assert_eq!(driver.poll()?, None);
+
+    let battery = driver.poll()?;
+    assert_eq!(
+        battery,
+        Some(Battery {
+            level: 100,
+            power_state: PowerState::Complete
+        })
+    );
+
+    // Reduced Bluetooth reports don't include the battery state
+    assert_eq!(driver.poll()?, None);
+
+    Ok(())
+}
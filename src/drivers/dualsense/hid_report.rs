@@ -34,11 +34,17 @@ pub enum PowerState {
     ChargingError = 0x0F,
 }
 
+/// Bit set in [TouchFingerData] context when the contact is not touching
+pub const TOUCH_INACTIVE_FLAG: u8 = 0x80;
+/// Mask of the tracking id in [TouchFingerData] context
+pub const TOUCH_TRACKING_ID_MASK: u8 = 0x7F;
+
 #[derive(PackedStruct, Debug, Copy, Clone, PartialEq)]
 #[packed_struct(bit_numbering = "msb0", size_bytes = "4")]
 pub struct TouchFingerData {
     // byte 0
-    // Set to 127 when touching and 128 when not.
+    // Bits 0-6 contain the contact tracking id. Bit 7 is set when the contact
+    // is no longer touching the touchpad.
     #[packed_field(bytes = "0")]
     pub context: u8,
     // byte 1
@@ -55,6 +61,31 @@ pub struct TouchFingerData {
 }
 
 impl TouchFingerData {
+    /// Returns true if this contact is currently touching the touchpad
+    pub fn is_active(&self) -> bool {
+        self.context & TOUCH_INACTIVE_FLAG == 0
+    }
+    /// Returns the tracking id of this contact
+    pub fn tracking_id(&self) -> u8 {
+        self.context & TOUCH_TRACKING_ID_MASK
+    }
+    /// Marks the contact as touching with the given tracking id
+    pub fn set_active(&mut self, tracking_id: u8) {
+        self.context = tracking_id & TOUCH_TRACKING_ID_MASK;
+    }
+    /// Marks the contact as lifted, preserving the last tracking id so the
+    /// liftoff is reported for the same contact.
+    pub fn set_inactive(&mut self) {
+        self.context |= TOUCH_INACTIVE_FLAG;
+    }
+    /// Returns the raw X coordinate of this contact
+    pub fn x(&self) -> u16 {
+        self.x_lo as u16 | (self.x_hi.to_primitive() as u16) << 8
+    }
+    /// Returns the raw Y coordinate of this contact
+    pub fn y(&self) -> u16 {
+        self.y_lo.to_primitive() as u16 | (self.y_hi as u16) << 4
+    }
     pub fn set_x(&mut self, x_raw: u16) {
         self.x_lo = (x_raw & 0x00FF) as u8;
         self.x_hi = Integer::from_primitive((x_raw & 0x0F00).rotate_right(8) as u8);
@@ -21,3 +21,16 @@ async fn test_ds_hid() -> Result<(), Box<dyn Error>> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn test_ds_touch_coordinates() -> Result<(), Box<dyn Error>> {
+    let mut finger = USBPackedInputDataReport::default()
+        .touch_data
+        .touch_finger_data[0];
+    finger.set_x(1234);
+    finger.set_y(987);
+    assert_eq!(finger.x(), 1234);
+    assert_eq!(finger.y(), 987);
+
+    Ok(())
+}
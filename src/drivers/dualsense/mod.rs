@@ -1,4 +1,6 @@
 pub mod driver;
+#[cfg(test)]
+mod driver_test;
 pub mod hid_report;
 mod hid_report_test;
 pub mod report_descriptor;
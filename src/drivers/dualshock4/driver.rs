@@ -0,0 +1,272 @@
+use std::{error::Error, ffi::CString, time::Instant};
+
+use packed_struct::{types::SizedInteger, PackedStruct};
+
+use crate::drivers::{
+    dualsense::hid_report::Direction,
+    transport::{HidTransport, QueuedTransport},
+};
+
+use super::{
+    event::{
+        AccelerometerEvent, AccelerometerInput, AxisEvent, AxisInput, BatteryInput, BinaryInput,
+        ButtonEvent, Event, StatusEvent, TouchAxisInput, TriggerEvent, TriggerInput,
+    },
+    hid_report::{
+        PackedInputDataReport, INPUT_DATA_OFFSET_BT, INPUT_DATA_OFFSET_USB, INPUT_DATA_SIZE,
+        INPUT_REPORT_BT, INPUT_REPORT_BT_SIZE, INPUT_REPORT_USB, INPUT_REPORT_USB_SIZE,
+    },
+};
+
+/// Vendor ID
+pub const VID: u16 = 0x054c;
+/// Product ID of the first DualShock 4 revision
+pub const PID_V1: u16 = 0x05c4;
+/// Product ID of the second DualShock 4 revision
+pub const PID_V2: u16 = 0x09cc;
+/// Product ID of the DualShock 4 USB wireless adaptor
+pub const PID_DONGLE: u16 = 0x0ba0;
+/// Size of the HID packet
+const PACKET_SIZE: usize = INPUT_REPORT_BT_SIZE;
+/// Timeout in milliseconds for reading an HID packet
+const HID_TIMEOUT: i32 = 10;
+
+pub struct Driver {
+    state: Option<PackedInputDataReport>,
+    device: Box<dyn HidTransport>,
+    /// Time the last input report was read from the device
+    last_report: Instant,
+}
+
+impl Driver {
+    pub fn new(path: String) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        let fmtpath = path.clone();
+        let path = CString::new(path)?;
+        let api = hidapi::HidApi::new()?;
+        let device = api.open_path(&path)?;
+        let info = device.get_device_info()?;
+        if !is_supported(info.vendor_id(), info.product_id()) {
+            return Err(format!("Device '{fmtpath}' is not a DualShock 4").into());
+        }
+
+        Ok(Self::from_transport(Box::new(QueuedTransport::new(device))))
+    }
+
+    /// Create a driver that reads reports from the given transport
+    pub fn from_transport(device: Box<dyn HidTransport>) -> Self {
+        Self {
+            state: None,
+            device,
+            last_report: Instant::now(),
+        }
+    }
+
+    /// Poll the device and read input reports
+    pub fn poll(&mut self) -> Result<Vec<Event>, Box<dyn Error + Send + Sync>> {
+        // Read data from the device into a buffer
+        let mut buf = [0; PACKET_SIZE];
+        let bytes_read = self.device.read_timeout(&mut buf[..], HID_TIMEOUT)?;
+        self.last_report = Instant::now();
+
+        // Bluetooth controllers send reduced reports until the kernel driver
+        // has read their calibration, which are ignored.
+        let offset = match (buf[0], bytes_read) {
+            (INPUT_REPORT_USB, INPUT_REPORT_USB_SIZE) => INPUT_DATA_OFFSET_USB,
+            (INPUT_REPORT_BT, INPUT_REPORT_BT_SIZE) => INPUT_DATA_OFFSET_BT,
+            _ => return Ok(Vec::new()),
+        };
+        let data = &buf[offset..offset + INPUT_DATA_SIZE];
+
+        // Handle the incoming input report
+        let events = self.handle_input_report(data.try_into()?)?;
+
+        Ok(events)
+    }
+
+    /// Returns the time the last input report was read from the device
+    pub fn last_report_time(&self) -> Instant {
+        self.last_report
+    }
+
+    /// Unpacks the buffer into a [PackedInputDataReport] structure and updates
+    /// the internal gamepad state
+    fn handle_input_report(
+        &mut self,
+        buf: [u8; INPUT_DATA_SIZE],
+    ) -> Result<Vec<Event>, Box<dyn Error + Send + Sync>> {
+        let input_report = PackedInputDataReport::unpack(&buf)?;
+
+        // Update the state
+        let old_state = self.state.replace(input_report);
+
+        // Translate the state into a stream of input events
+        let events = self.translate(old_state);
+
+        Ok(events)
+    }
+
+    /// Translate the state into individual events
+    fn translate(&self, old_state: Option<PackedInputDataReport>) -> Vec<Event> {
+        let mut events = Vec::new();
+        let Some(state) = self.state else {
+            return events;
+        };
+
+        // Report the battery state once, then only when it changes
+        let battery = (state.battery_percent(), state.cable_connected);
+        if old_state.map(|old| (old.battery_percent(), old.cable_connected)) != Some(battery) {
+            events.push(Event::Status(StatusEvent::Battery(BatteryInput {
+                level: state.battery_percent(),
+                cable_connected: state.cable_connected,
+            })));
+        }
+
+        let Some(old_state) = old_state else {
+            return events;
+        };
+
+        // Binary events
+        let buttons: [(bool, bool, fn(BinaryInput) -> ButtonEvent); 18] = [
+            (state.cross, old_state.cross, ButtonEvent::Cross),
+            (state.circle, old_state.circle, ButtonEvent::Circle),
+            (state.square, old_state.square, ButtonEvent::Square),
+            (state.triangle, old_state.triangle, ButtonEvent::Triangle),
+            (state.share, old_state.share, ButtonEvent::Share),
+            (state.options, old_state.options, ButtonEvent::Options),
+            (state.ps, old_state.ps, ButtonEvent::PS),
+            (
+                dpad_up(state.dpad),
+                dpad_up(old_state.dpad),
+                ButtonEvent::DPadUp,
+            ),
+            (
+                dpad_down(state.dpad),
+                dpad_down(old_state.dpad),
+                ButtonEvent::DPadDown,
+            ),
+            (
+                dpad_left(state.dpad),
+                dpad_left(old_state.dpad),
+                ButtonEvent::DPadLeft,
+            ),
+            (
+                dpad_right(state.dpad),
+                dpad_right(old_state.dpad),
+                ButtonEvent::DPadRight,
+            ),
+            (state.l1, old_state.l1, ButtonEvent::L1),
+            (state.l2, old_state.l2, ButtonEvent::L2),
+            (state.l3, old_state.l3, ButtonEvent::L3),
+            (state.r1, old_state.r1, ButtonEvent::R1),
+            (state.r2, old_state.r2, ButtonEvent::R2),
+            (state.r3, old_state.r3, ButtonEvent::R3),
+            (
+                state.touchpad_press,
+                old_state.touchpad_press,
+                ButtonEvent::TouchpadPress,
+            ),
+        ];
+        for (pressed, old_pressed, event) in buttons {
+            if pressed != old_pressed {
+                events.push(Event::Button(event(BinaryInput { pressed })));
+            }
+        }
+
+        // Axis events
+        if state.l_x != old_state.l_x || state.l_y != old_state.l_y {
+            events.push(Event::Axis(AxisEvent::LStick(AxisInput {
+                x: state.l_x,
+                y: state.l_y,
+            })));
+        }
+        if state.r_x != old_state.r_x || state.r_y != old_state.r_y {
+            events.push(Event::Axis(AxisEvent::RStick(AxisInput {
+                x: state.r_x,
+                y: state.r_y,
+            })));
+        }
+
+        // Touch events. Reports without touch data keep the last contacts.
+        if let (Some(touch), Some(old_touch)) = (state.touch_report(), old_state.touch_report()) {
+            for (index, (point, old_point)) in touch.points.iter().zip(old_touch.points).enumerate()
+            {
+                let is_touching = point.is_active();
+                if is_touching == old_point.is_active()
+                    && (!is_touching || (point.x(), point.y()) == (old_point.x(), old_point.y()))
+                {
+                    continue;
+                }
+                events.push(Event::Axis(AxisEvent::Touchpad(TouchAxisInput {
+                    index: index as u8,
+                    is_touching,
+                    x: point.x(),
+                    y: point.y(),
+                })));
+            }
+        }
+
+        // Trigger events
+        if state.l2_trigger != old_state.l2_trigger {
+            events.push(Event::Trigger(TriggerEvent::L2(TriggerInput {
+                value: state.l2_trigger,
+            })));
+        }
+        if state.r2_trigger != old_state.r2_trigger {
+            events.push(Event::Trigger(TriggerEvent::R2(TriggerInput {
+                value: state.r2_trigger,
+            })));
+        }
+
+        // Motion events
+        events.push(Event::Accelerometer(AccelerometerEvent::Accelerometer(
+            AccelerometerInput {
+                x: state.accel_x.to_primitive(),
+                y: state.accel_y.to_primitive(),
+                z: state.accel_z.to_primitive(),
+            },
+        )));
+        events.push(Event::Accelerometer(AccelerometerEvent::Gyro(
+            AccelerometerInput {
+                x: state.gyro_x.to_primitive(),
+                y: state.gyro_y.to_primitive(),
+                z: state.gyro_z.to_primitive(),
+            },
+        )));
+
+        events
+    }
+}
+
+/// Returns true if the given vendor and product id belong to a DualShock 4
+/// or its wireless adaptor
+pub fn is_supported(vendor_id: u16, product_id: u16) -> bool {
+    vendor_id == VID && [PID_V1, PID_V2, PID_DONGLE].contains(&product_id)
+}
+
+fn dpad_up(direction: Direction) -> bool {
+    matches!(
+        direction,
+        Direction::NorthWest | Direction::North | Direction::NorthEast
+    )
+}
+
+fn dpad_down(direction: Direction) -> bool {
+    matches!(
+        direction,
+        Direction::SouthEast | Direction::South | Direction::SouthWest
+    )
+}
+
+fn dpad_left(direction: Direction) -> bool {
+    matches!(
+        direction,
+        Direction::SouthWest | Direction::West | Direction::NorthWest
+    )
+}
+
+fn dpad_right(direction: Direction) -> bool {
+    matches!(
+        direction,
+        Direction::NorthEast | Direction::East | Direction::SouthEast
+    )
+}
@@ -0,0 +1,126 @@
+use std::error::Error;
+
+use crate::drivers::{
+    dualshock4::{
+        driver::Driver,
+        event::{AxisEvent, ButtonEvent, Event, StatusEvent},
+    },
+    mock_transport::MockTransport,
+};
+
+/// Returns a USB input report with no buttons pressed, all axes centered,
+/// one touch report without contacts and a half charged battery
+fn neutral_report() -> Vec<u8> {
+    let mut report = vec![0; 64];
+    report[0] = 0x01;
+    report[1..=4].copy_from_slice(&[0x80; 4]);
+    report[5] = 0x08; // DPad released
+    report[30] = 0x05; // Battery
+    report[33] = 1; // Touch reports
+    report[35] = 0x80; // First contact lifted
+    report[39] = 0x80; // Second contact lifted
+    report
+}
+
+/// Returns only the non-motion events from the given events
+fn input_events(events: Vec<Event>) -> Vec<Event> {
+    events
+        .into_iter()
+        .filter(|event| !matches!(event, Event::Accelerometer(_)))
+        .collect()
+}
+
+#[tokio::test]
+async fn test_dualshock4_usb_reports() -> Result<(), Box<dyn Error>> {
+    let mut report = neutral_report();
+    report[5] = 0x21; // Cross, DPad north east
+    report[35..39].copy_from_slice(&[0x01, 0x34, 0x52, 0x26]); // Contact at (0x234, 0x265)
+    let transport = MockTransport::new(vec![neutral_report(), report]);
+    let mut driver = Driver::from_transport(Box::new(transport));
+
+    // The first report only reports the battery level
+    let events = driver.poll()?;
+    assert_eq!(events.len(), 1);
+    assert!(matches!(
+        events[0],
+        Event::Status(StatusEvent::Battery(ref input)) if input.level == 55 && !input.cable_connected
+    ));
+
+    let events = input_events(driver.poll()?);
+    assert_eq!(events.len(), 4);
+    assert!(events.iter().any(|event| matches!(
+        event,
+        Event::Button(ButtonEvent::Cross(input)) if input.pressed
+    )));
+    assert!(events.iter().any(|event| matches!(
+        event,
+        Event::Button(ButtonEvent::DPadUp(input)) if input.pressed
+    )));
+    assert!(events.iter().any(|event| matches!(
+        event,
+        Event::Button(ButtonEvent::DPadRight(input)) if input.pressed
+    )));
+    assert!(events.iter().any(|event| matches!(
+        event,
+        Event::Axis(AxisEvent::Touchpad(input))
+            if input.index == 0 && input.is_touching && input.x == 0x234 && input.y == 0x265
+    )));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_dualshock4_bluetooth_reports() -> Result<(), Box<dyn Error>> {
+    let bluetooth = |usb: Vec<u8>| {
+        let mut report = vec![0; 78];
+        report[0] = 0x11;
+        report[3..63].copy_from_slice(&usb[1..61]);
+        report
+    };
+    let mut pressed = neutral_report();
+    pressed[7] = 0x01; // PS
+    let mut reduced = vec![0; 10];
+    reduced[0] = 0x01;
+    let transport = MockTransport::new(vec![
+        bluetooth(neutral_report()),
+        reduced,
+        bluetooth(pressed),
+    ]);
+    let mut driver = Driver::from_transport(Box::new(transport));
+
+    driver.poll()?;
+
+    // Reduced reports sent before the calibration was read are ignored
+    assert!(driver.poll()?.is_empty());
+
+    let events = input_events(driver.poll()?);
+    assert_eq!(events.len(), 1);
+    assert!(matches!(
+        events[0],
+        Event::Button(ButtonEvent::PS(ref input)) if input.pressed
+    ));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_dualshock4_battery_charging() -> Result<(), Box<dyn Error>> {
+    let mut charging = neutral_report();
+    charging[30] = 0x15; // Cable connected, half charged battery
+    let transport = MockTransport::new(vec![neutral_report(), charging.clone(), charging]);
+    let mut driver = Driver::from_transport(Box::new(transport));
+
+    driver.poll()?;
+
+    // Connecting the cable reports the battery state again
+    let events = input_events(driver.poll()?);
+    assert_eq!(events.len(), 1);
+    assert!(matches!(
+        events[0],
+        Event::Status(StatusEvent::Battery(ref input)) if input.level == 55 && input.cable_connected
+    ));
+
+    assert!(input_events(driver.poll()?).is_empty());
+
+    Ok(())
+}
@@ -0,0 +1,124 @@
+/// Events that can be emitted by the DualShock 4 controller
+#[derive(Clone, Debug)]
+pub enum Event {
+    Button(ButtonEvent),
+    Accelerometer(AccelerometerEvent),
+    Axis(AxisEvent),
+    Trigger(TriggerEvent),
+    Status(StatusEvent),
+}
+
+/// Binary input contain either pressed or unpressed
+#[derive(Clone, Debug)]
+pub struct BinaryInput {
+    pub pressed: bool,
+}
+
+/// Button events represend binary inputs
+#[derive(Clone, Debug)]
+pub enum ButtonEvent {
+    /// Cross (✕) button
+    Cross(BinaryInput),
+    /// Circle (○) button
+    Circle(BinaryInput),
+    /// Square (□) button
+    Square(BinaryInput),
+    /// Triangle (△) button
+    Triangle(BinaryInput),
+    /// Share button left of the touchpad
+    Share(BinaryInput),
+    /// Options button right of the touchpad
+    Options(BinaryInput),
+    /// PS button below the touchpad
+    PS(BinaryInput),
+    /// DPad up
+    DPadUp(BinaryInput),
+    /// DPad down
+    DPadDown(BinaryInput),
+    /// DPad left
+    DPadLeft(BinaryInput),
+    /// DPad right
+    DPadRight(BinaryInput),
+    /// Left shoulder button
+    L1(BinaryInput),
+    /// Binary sensor for left analog trigger
+    L2(BinaryInput),
+    /// Z-axis button on the left stick
+    L3(BinaryInput),
+    /// Right shoulder button
+    R1(BinaryInput),
+    /// Binary sensor for right analog trigger
+    R2(BinaryInput),
+    /// Z-axis button on the right stick
+    R3(BinaryInput),
+    /// Touchpad click
+    TouchpadPress(BinaryInput),
+}
+
+/// Axis input contain (x, y) coordinates
+#[derive(Clone, Debug)]
+pub struct AxisInput {
+    pub x: u8,
+    pub y: u8,
+}
+
+/// TouchAxisInput contains (x, y) coordinates and if a touch is detected
+#[derive(Clone, Debug)]
+pub struct TouchAxisInput {
+    pub index: u8,
+    pub is_touching: bool,
+    pub x: u16,
+    pub y: u16,
+}
+
+/// Axis events are events that have (x, y) values
+#[derive(Clone, Debug)]
+pub enum AxisEvent {
+    Touchpad(TouchAxisInput),
+    LStick(AxisInput),
+    RStick(AxisInput),
+}
+
+/// Trigger input contains non-negative integers
+#[derive(Clone, Debug)]
+pub struct TriggerInput {
+    pub value: u8,
+}
+
+/// Trigger events contain values indicating how far a trigger is pulled
+#[derive(Clone, Debug)]
+pub enum TriggerEvent {
+    L2(TriggerInput),
+    R2(TriggerInput),
+}
+
+/// AccelerometerInput represents the state of the accelerometer (x, y, z) values
+#[derive(Clone, Debug)]
+pub struct AccelerometerInput {
+    pub x: i16,
+    pub y: i16,
+    pub z: i16,
+}
+
+/// AccelerometerEvent has data from the accelerometer
+#[derive(Clone, Debug)]
+pub enum AccelerometerEvent {
+    Accelerometer(AccelerometerInput),
+    Gyro(AccelerometerInput),
+}
+
+/// Battery input contains the battery level and whether the controller is
+/// connected to a cable for charging
+#[derive(Clone, Debug)]
+pub struct BatteryInput {
+    /// Battery level in percent
+    pub level: u8,
+    pub cable_connected: bool,
+}
+
+/// Status events report the state of the controller
+#[derive(Clone, Debug)]
+pub enum StatusEvent {
+    /// Battery level and cable state
+    Battery(BatteryInput),
+}
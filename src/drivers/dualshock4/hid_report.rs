@@ -0,0 +1,159 @@
+//! Reference: https://github.com/torvalds/linux/blob/master/drivers/hid/hid-playstation.c
+use packed_struct::prelude::*;
+
+use crate::drivers::dualsense::hid_report::{Direction, TouchFingerData};
+
+pub const INPUT_REPORT_USB: u8 = 0x01;
+pub const INPUT_REPORT_USB_SIZE: usize = 64;
+pub const INPUT_REPORT_BT: u8 = 0x11;
+pub const INPUT_REPORT_BT_SIZE: usize = 78;
+/// Offset of the [PackedInputDataReport] in USB input reports
+pub const INPUT_DATA_OFFSET_USB: usize = 1;
+/// Offset of the [PackedInputDataReport] in Bluetooth input reports
+pub const INPUT_DATA_OFFSET_BT: usize = 3;
+/// Size of the [PackedInputDataReport]
+pub const INPUT_DATA_SIZE: usize = 60;
+
+// Input report axis ranges
+pub const STICK_MAX: f64 = u8::MAX as f64;
+pub const TRIGGER_MAX: f64 = u8::MAX as f64;
+pub const TOUCHPAD_WIDTH: f64 = 1920.0;
+pub const TOUCHPAD_HEIGHT: f64 = 942.0;
+
+// DualShock 4 hardware limits
+pub const ACC_RES_PER_G: f64 = 8192.0;
+pub const GYRO_RES_PER_DEG_S: f64 = 1024.0;
+/// Raw battery capacity of a full battery
+pub const BATTERY_CAPACITY_MAX: u8 = 10;
+
+/// Touchpad contacts reported at a single point in time
+#[derive(PackedStruct, Debug, Copy, Clone, PartialEq)]
+#[packed_struct(bit_numbering = "msb0", size_bytes = "9")]
+pub struct PackedTouchReport {
+    #[packed_field(bytes = "0")]
+    pub timestamp: u8,
+    #[packed_field(bytes = "1..=8", element_size_bytes = "4")]
+    pub points: [TouchFingerData; 2],
+}
+
+/// Input data shared by the USB and Bluetooth input reports, which only
+/// differ in the header that precedes it.
+#[derive(PackedStruct, Debug, Copy, Clone, PartialEq)]
+#[packed_struct(bit_numbering = "msb0", size_bytes = "60")]
+pub struct PackedInputDataReport {
+    // byte 0-3
+    #[packed_field(bytes = "0")]
+    pub l_x: u8,
+    #[packed_field(bytes = "1")]
+    pub l_y: u8,
+    #[packed_field(bytes = "2")]
+    pub r_x: u8,
+    #[packed_field(bytes = "3")]
+    pub r_y: u8,
+
+    // byte 4
+    #[packed_field(bits = "32")]
+    pub triangle: bool,
+    #[packed_field(bits = "33")]
+    pub circle: bool,
+    #[packed_field(bits = "34")]
+    pub cross: bool,
+    #[packed_field(bits = "35")]
+    pub square: bool,
+    #[packed_field(bits = "36..=39", ty = "enum")]
+    pub dpad: Direction,
+
+    // byte 5
+    #[packed_field(bits = "40")]
+    pub r3: bool,
+    #[packed_field(bits = "41")]
+    pub l3: bool,
+    #[packed_field(bits = "42")]
+    pub options: bool,
+    #[packed_field(bits = "43")]
+    pub share: bool,
+    #[packed_field(bits = "44")]
+    pub r2: bool,
+    #[packed_field(bits = "45")]
+    pub l2: bool,
+    #[packed_field(bits = "46")]
+    pub r1: bool,
+    #[packed_field(bits = "47")]
+    pub l1: bool,
+
+    // byte 6
+    #[packed_field(bits = "48..=53")]
+    pub counter: Integer<u8, packed_bits::Bits<6>>,
+    #[packed_field(bits = "54")]
+    pub touchpad_press: bool,
+    #[packed_field(bits = "55")]
+    pub ps: bool,
+
+    // byte 7-8
+    #[packed_field(bytes = "7")]
+    pub l2_trigger: u8,
+    #[packed_field(bytes = "8")]
+    pub r2_trigger: u8,
+
+    // byte 9-11
+    #[packed_field(bytes = "9..=10", endian = "lsb")]
+    pub sensor_timestamp: Integer<u16, packed_bits::Bits<16>>,
+    #[packed_field(bytes = "11")]
+    pub sensor_temperature: u8,
+
+    // byte 12-23
+    #[packed_field(bytes = "12..=13", endian = "lsb")]
+    pub gyro_x: Integer<i16, packed_bits::Bits<16>>,
+    #[packed_field(bytes = "14..=15", endian = "lsb")]
+    pub gyro_y: Integer<i16, packed_bits::Bits<16>>,
+    #[packed_field(bytes = "16..=17", endian = "lsb")]
+    pub gyro_z: Integer<i16, packed_bits::Bits<16>>,
+    #[packed_field(bytes = "18..=19", endian = "lsb")]
+    pub accel_x: Integer<i16, packed_bits::Bits<16>>,
+    #[packed_field(bytes = "20..=21", endian = "lsb")]
+    pub accel_y: Integer<i16, packed_bits::Bits<16>>,
+    #[packed_field(bytes = "22..=23", endian = "lsb")]
+    pub accel_z: Integer<i16, packed_bits::Bits<16>>,
+
+    // byte 24-28
+    #[packed_field(bytes = "24..=28")]
+    pub _reserved0: [u8; 5],
+
+    // byte 29
+    #[packed_field(bits = "232..=234")]
+    pub _unkn_status: Integer<u8, packed_bits::Bits<3>>,
+    #[packed_field(bits = "235")]
+    pub cable_connected: bool,
+    #[packed_field(bits = "236..=239")]
+    pub battery_capacity: Integer<u8, packed_bits::Bits<4>>,
+
+    // byte 30-31
+    #[packed_field(bytes = "30")]
+    pub _status1: u8,
+    #[packed_field(bytes = "31")]
+    pub _reserved1: u8,
+
+    // byte 32-59
+    #[packed_field(bytes = "32")]
+    pub num_touch_reports: u8,
+    #[packed_field(bytes = "33..=59", element_size_bytes = "9")]
+    pub touch_reports: [PackedTouchReport; 3],
+}
+
+impl PackedInputDataReport {
+    /// Returns the most recent touchpad contacts, if the report has any
+    pub fn touch_report(&self) -> Option<&PackedTouchReport> {
+        let count = (self.num_touch_reports as usize).min(self.touch_reports.len());
+        count.checked_sub(1).map(|i| &self.touch_reports[i])
+    }
+
+    /// Returns the battery level in percent
+    pub fn battery_percent(&self) -> u8 {
+        // The capacity goes above the maximum when charged over the cable
+        let capacity = self.battery_capacity.to_primitive();
+        match capacity < BATTERY_CAPACITY_MAX {
+            true => capacity * 10 + 5,
+            false => 100,
+        }
+    }
+}
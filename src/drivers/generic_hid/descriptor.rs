@@ -0,0 +1,326 @@
+use std::{collections::HashMap, error::Error};
+
+/// Generic Desktop usage page
+pub const USAGE_PAGE_GENERIC_DESKTOP: u16 = 0x01;
+/// Button usage page
+pub const USAGE_PAGE_BUTTON: u16 = 0x09;
+
+/// Generic Desktop usages
+pub const USAGE_JOYSTICK: u16 = 0x04;
+pub const USAGE_GAMEPAD: u16 = 0x05;
+pub const USAGE_X: u16 = 0x30;
+pub const USAGE_Y: u16 = 0x31;
+pub const USAGE_Z: u16 = 0x32;
+pub const USAGE_RX: u16 = 0x33;
+pub const USAGE_RY: u16 = 0x34;
+pub const USAGE_RZ: u16 = 0x35;
+pub const USAGE_HAT_SWITCH: u16 = 0x39;
+pub const USAGE_DPAD_UP: u16 = 0x90;
+pub const USAGE_DPAD_DOWN: u16 = 0x91;
+pub const USAGE_DPAD_RIGHT: u16 = 0x92;
+pub const USAGE_DPAD_LEFT: u16 = 0x93;
+
+/// Item types
+const ITEM_MAIN: u8 = 0;
+const ITEM_GLOBAL: u8 = 1;
+const ITEM_LOCAL: u8 = 2;
+/// Prefix of a long item
+const ITEM_LONG: u8 = 0xfe;
+
+/// Main item tags
+const TAG_INPUT: u8 = 0x8;
+const TAG_OUTPUT: u8 = 0x9;
+const TAG_COLLECTION: u8 = 0xa;
+const TAG_FEATURE: u8 = 0xb;
+const TAG_END_COLLECTION: u8 = 0xc;
+
+/// Global item tags
+const TAG_USAGE_PAGE: u8 = 0x0;
+const TAG_LOGICAL_MIN: u8 = 0x1;
+const TAG_LOGICAL_MAX: u8 = 0x2;
+const TAG_REPORT_SIZE: u8 = 0x7;
+const TAG_REPORT_ID: u8 = 0x8;
+const TAG_REPORT_COUNT: u8 = 0x9;
+const TAG_PUSH: u8 = 0xa;
+const TAG_POP: u8 = 0xb;
+
+/// Local item tags
+const TAG_USAGE: u8 = 0x0;
+const TAG_USAGE_MIN: u8 = 0x1;
+const TAG_USAGE_MAX: u8 = 0x2;
+
+/// Input item flags
+const INPUT_CONSTANT: u32 = 0x01;
+const INPUT_VARIABLE: u32 = 0x02;
+
+/// Collection type of an application collection
+const COLLECTION_APPLICATION: u32 = 0x01;
+
+// Limits used by the Linux HID subsystem
+/// Maximum number of values declared by a single main item
+const MAX_REPORT_COUNT: usize = 12288;
+/// Maximum size of a report in bytes
+const MAX_REPORT_SIZE: usize = 16384;
+
+/// HID usage made up of a usage page and a usage ID
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Usage {
+    pub page: u16,
+    pub id: u16,
+}
+
+impl Usage {
+    pub fn new(page: u16, id: u16) -> Self {
+        Self { page, id }
+    }
+}
+
+/// Single value in an input report described by the report descriptor
+#[derive(Clone, Debug)]
+pub struct Field {
+    pub report_id: u8,
+    pub usage: Usage,
+    /// Offset in bits from the start of the report data, after the report ID
+    pub bit_offset: usize,
+    pub bit_size: usize,
+    pub logical_min: i32,
+    pub logical_max: i32,
+}
+
+impl Field {
+    /// Extract the value of this field from the given report data. Returns
+    /// None if the report is too short to contain the field.
+    pub fn extract(&self, data: &[u8]) -> Option<i32> {
+        if (self.bit_offset + self.bit_size).div_ceil(8) > data.len() {
+            return None;
+        }
+        let mut value: u32 = 0;
+        for bit in 0..self.bit_size {
+            let pos = self.bit_offset + bit;
+            if (data[pos / 8] >> (pos % 8)) & 1 == 1 {
+                value |= 1 << bit;
+            }
+        }
+        if self.logical_min < 0 {
+            Some(sign_extend(value, self.bit_size))
+        } else {
+            Some(value as i32)
+        }
+    }
+}
+
+/// Input fields of gamepad and joystick applications found in a HID report
+/// descriptor
+#[derive(Clone, Debug, Default)]
+pub struct ReportDescriptor {
+    pub fields: Vec<Field>,
+    /// Top level application collections
+    pub applications: Vec<Usage>,
+    /// Whether reports are prefixed by a report ID
+    pub uses_report_ids: bool,
+}
+
+/// Global item state
+#[derive(Clone, Copy, Debug, Default)]
+struct GlobalState {
+    usage_page: u16,
+    logical_min: i32,
+    logical_max: i32,
+    report_size: usize,
+    report_count: usize,
+    report_id: u8,
+}
+
+/// Local item state, reset after every main item
+#[derive(Debug, Default)]
+struct LocalState {
+    usages: Vec<Usage>,
+    usage_min: Option<Usage>,
+    usage_max: Option<Usage>,
+}
+
+impl LocalState {
+    /// Returns all usages, including those declared by a usage range
+    fn resolve(&self) -> Vec<Usage> {
+        let mut usages = self.usages.clone();
+        if let (Some(min), Some(max)) = (self.usage_min, self.usage_max) {
+            usages.extend((min.id..=max.id).map(|id| Usage::new(min.page, id)));
+        }
+        usages
+    }
+}
+
+impl ReportDescriptor {
+    /// Parse the given raw report descriptor
+    pub fn parse(data: &[u8]) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        let mut descriptor = Self::default();
+        let mut global = GlobalState::default();
+        let mut global_stack = Vec::new();
+        let mut local = LocalState::default();
+        let mut depth: usize = 0;
+        let mut application = None;
+        // Bit offsets of the next field in each input report
+        let mut offsets: HashMap<u8, usize> = HashMap::new();
+
+        let mut i = 0;
+        while i < data.len() {
+            let prefix = data[i];
+            i += 1;
+            if prefix == ITEM_LONG {
+                let Some(&size) = data.get(i) else {
+                    return Err("Report descriptor long item is truncated".into());
+                };
+                i += size as usize + 2;
+                continue;
+            }
+
+            let size = match prefix & 0x03 {
+                3 => 4,
+                size => size as usize,
+            };
+            let Some(bytes) = data.get(i..i + size) else {
+                return Err("Report descriptor item is truncated".into());
+            };
+            i += size;
+            let value = bytes
+                .iter()
+                .rev()
+                .fold(0u32, |value, byte| (value << 8) | *byte as u32);
+            let tag = prefix >> 4;
+
+            match (prefix >> 2) & 0x03 {
+                ITEM_MAIN => {
+                    match tag {
+                        TAG_INPUT => {
+                            if global.report_count > MAX_REPORT_COUNT {
+                                return Err(format!(
+                                    "Report count {} exceeds the maximum of {MAX_REPORT_COUNT}",
+                                    global.report_count
+                                )
+                                .into());
+                            }
+                            let offset = offsets.entry(global.report_id).or_default();
+                            let Some(end) = global
+                                .report_size
+                                .checked_mul(global.report_count)
+                                .and_then(|bits| offset.checked_add(bits))
+                                .filter(|bits| bits.div_ceil(8) <= MAX_REPORT_SIZE)
+                            else {
+                                return Err(format!(
+                                    "Input report {} exceeds the maximum size of {MAX_REPORT_SIZE} bytes",
+                                    global.report_id
+                                )
+                                .into());
+                            };
+                            let is_gamepad = application.is_some_and(is_gamepad_application);
+                            let is_data = value & INPUT_CONSTANT == 0;
+                            let is_variable = value & INPUT_VARIABLE != 0;
+                            if is_gamepad && is_data && is_variable && global.report_size <= 32 {
+                                let usages = local.resolve();
+                                for n in 0..global.report_count {
+                                    let Some(usage) = usages.get(n).or(usages.last()) else {
+                                        break;
+                                    };
+                                    descriptor.fields.push(Field {
+                                        report_id: global.report_id,
+                                        usage: *usage,
+                                        bit_offset: *offset + n * global.report_size,
+                                        bit_size: global.report_size,
+                                        logical_min: global.logical_min,
+                                        logical_max: global.logical_max,
+                                    });
+                                }
+                            }
+                            *offset = end;
+                        }
+                        TAG_COLLECTION => {
+                            if depth == 0 && value == COLLECTION_APPLICATION {
+                                application = local.resolve().first().copied();
+                                if let Some(usage) = application {
+                                    descriptor.applications.push(usage);
+                                }
+                            }
+                            depth += 1;
+                        }
+                        TAG_END_COLLECTION => {
+                            depth = depth.saturating_sub(1);
+                            if depth == 0 {
+                                application = None;
+                            }
+                        }
+                        TAG_OUTPUT | TAG_FEATURE => (),
+                        _ => log::trace!("Ignoring unknown main item: {tag:#x}"),
+                    }
+                    local = LocalState::default();
+                }
+                ITEM_GLOBAL => match tag {
+                    TAG_USAGE_PAGE => global.usage_page = value as u16,
+                    TAG_LOGICAL_MIN => global.logical_min = sign_extend(value, size * 8),
+                    // The maximum is only signed if the minimum is negative
+                    TAG_LOGICAL_MAX => {
+                        global.logical_max = if global.logical_min < 0 {
+                            sign_extend(value, size * 8)
+                        } else {
+                            value as i32
+                        }
+                    }
+                    TAG_REPORT_SIZE => global.report_size = value as usize,
+                    TAG_REPORT_ID => {
+                        global.report_id = value as u8;
+                        descriptor.uses_report_ids = true;
+                    }
+                    TAG_REPORT_COUNT => global.report_count = value as usize,
+                    TAG_PUSH => global_stack.push(global),
+                    TAG_POP => {
+                        let Some(state) = global_stack.pop() else {
+                            return Err("Report descriptor pops an empty stack".into());
+                        };
+                        global = state;
+                    }
+                    _ => (),
+                },
+                ITEM_LOCAL => {
+                    // Four byte usages include their own usage page
+                    let usage = if size == 4 {
+                        Usage::new((value >> 16) as u16, value as u16)
+                    } else {
+                        Usage::new(global.usage_page, value as u16)
+                    };
+                    match tag {
+                        TAG_USAGE => local.usages.push(usage),
+                        TAG_USAGE_MIN => local.usage_min = Some(usage),
+                        TAG_USAGE_MAX => local.usage_max = Some(usage),
+                        _ => (),
+                    }
+                }
+                _ => (),
+            }
+        }
+
+        Ok(descriptor)
+    }
+
+    /// Returns true if the descriptor contains a gamepad or joystick
+    /// application
+    pub fn is_gamepad(&self) -> bool {
+        self.applications
+            .iter()
+            .any(|usage| is_gamepad_application(*usage))
+    }
+}
+
+/// Returns true if the given application usage is a gamepad or joystick
+fn is_gamepad_application(usage: Usage) -> bool {
+    usage.page == USAGE_PAGE_GENERIC_DESKTOP
+        && (usage.id == USAGE_JOYSTICK || usage.id == USAGE_GAMEPAD)
+}
+
+/// Interpret the lowest given number of bits of the value as a two's
+/// complement signed number
+fn sign_extend(value: u32, bits: usize) -> i32 {
+    if bits == 0 || bits >= 32 {
+        return value as i32;
+    }
+    let shift = 32 - bits;
+    ((value << shift) as i32) >> shift
+}
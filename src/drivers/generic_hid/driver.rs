@@ -0,0 +1,149 @@
+use std::{error::Error, ffi::CString, time::Instant};
+
+use hidapi::HidDevice;
+
+use crate::drivers::transport::{HidTransport, QueuedTransport};
+
+use super::{
+    descriptor::{
+        Field, ReportDescriptor, USAGE_DPAD_LEFT, USAGE_DPAD_UP, USAGE_HAT_SWITCH,
+        USAGE_PAGE_BUTTON, USAGE_PAGE_GENERIC_DESKTOP, USAGE_RZ, USAGE_X,
+    },
+    event::{AxisInput, ButtonInput, Event},
+};
+
+/// Size of the HID packet
+const PACKET_SIZE: usize = 512;
+/// Timeout in milliseconds for reading an HID packet
+const HID_TIMEOUT: i32 = 10;
+
+/// Driver for HID gamepads and joysticks without a dedicated driver, which
+/// interprets input reports using the device's report descriptor
+pub struct Driver {
+    descriptor: ReportDescriptor,
+    /// Last value of each field in the descriptor
+    state: Vec<Option<i32>>,
+    device: Box<dyn HidTransport>,
+    /// Time the last input report was read from the device
+    last_report: Instant,
+}
+
+impl Driver {
+    pub fn new(path: String) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        let fmtpath = path.clone();
+        let path = CString::new(path)?;
+        let api = hidapi::HidApi::new()?;
+        let device = api.open_path(&path)?;
+        let descriptor = get_descriptor(&device)?;
+        if !descriptor.is_gamepad() {
+            return Err(format!("Device '{fmtpath}' is not a gamepad or joystick").into());
+        }
+
+        Ok(Self::from_transport(
+            descriptor,
+            Box::new(QueuedTransport::new(device)),
+        ))
+    }
+
+    /// Create a driver that reads reports described by the given descriptor
+    /// from the given transport
+    pub fn from_transport(descriptor: ReportDescriptor, device: Box<dyn HidTransport>) -> Self {
+        let state = vec![None; descriptor.fields.len()];
+        Self {
+            descriptor,
+            state,
+            device,
+            last_report: Instant::now(),
+        }
+    }
+
+    /// Poll the device and read input reports
+    pub fn poll(&mut self) -> Result<Vec<Event>, Box<dyn Error + Send + Sync>> {
+        // Read data from the device into a buffer
+        let mut buf = [0; PACKET_SIZE];
+        let bytes_read = self.device.read_timeout(&mut buf[..], HID_TIMEOUT)?;
+        self.last_report = Instant::now();
+        if bytes_read == 0 {
+            return Ok(Vec::new());
+        }
+        let report = &buf[..bytes_read];
+
+        // Handle the incoming input report
+        let events = if self.descriptor.uses_report_ids {
+            self.handle_input_report(report[0], &report[1..])
+        } else {
+            self.handle_input_report(0, report)
+        };
+
+        Ok(events)
+    }
+
+    /// Returns the time the last input report was read from the device
+    pub fn last_report_time(&self) -> Instant {
+        self.last_report
+    }
+
+    /// Extracts the fields of the given report and translates changed values
+    /// into events
+    fn handle_input_report(&mut self, report_id: u8, data: &[u8]) -> Vec<Event> {
+        let mut events = Vec::new();
+        for (field, old_value) in self.descriptor.fields.iter().zip(self.state.iter_mut()) {
+            if field.report_id != report_id {
+                continue;
+            }
+            let Some(value) = field.extract(data) else {
+                continue;
+            };
+
+            // Only report changes after the first report
+            let Some(old_value) = old_value.replace(value) else {
+                continue;
+            };
+            if old_value == value {
+                continue;
+            }
+            if let Some(event) = translate(field, value) {
+                events.push(event);
+            }
+        }
+
+        events
+    }
+}
+
+/// Translate the given field value into an event
+fn translate(field: &Field, value: i32) -> Option<Event> {
+    let usage = field.usage;
+    match (usage.page, usage.id) {
+        (USAGE_PAGE_BUTTON, _) | (USAGE_PAGE_GENERIC_DESKTOP, USAGE_DPAD_UP..=USAGE_DPAD_LEFT) => {
+            Some(Event::Button(ButtonInput {
+                usage,
+                pressed: value != 0,
+            }))
+        }
+        (USAGE_PAGE_GENERIC_DESKTOP, USAGE_X..=USAGE_RZ | USAGE_HAT_SWITCH) => {
+            Some(Event::Axis(AxisInput {
+                usage,
+                value,
+                logical_min: field.logical_min,
+                logical_max: field.logical_max,
+            }))
+        }
+        _ => None,
+    }
+}
+
+/// Read and parse the report descriptor of the given device
+fn get_descriptor(device: &HidDevice) -> Result<ReportDescriptor, Box<dyn Error + Send + Sync>> {
+    let mut buf = [0; hidapi::MAX_REPORT_DESCRIPTOR_SIZE];
+    let size = device.get_report_descriptor(&mut buf)?;
+    ReportDescriptor::parse(&buf[..size])
+}
+
+/// Read and parse the report descriptor of the HID device at the given path
+pub fn read_descriptor(path: String) -> Result<ReportDescriptor, Box<dyn Error + Send + Sync>> {
+    let path = CString::new(path)?;
+    let api = hidapi::HidApi::new()?;
+    let device = api.open_path(&path)?;
+    get_descriptor(&device)
+}
@@ -0,0 +1,125 @@
+use std::error::Error;
+
+use crate::drivers::{
+    generic_hid::{
+        descriptor::{
+            ReportDescriptor, Usage, USAGE_HAT_SWITCH, USAGE_PAGE_BUTTON,
+            USAGE_PAGE_GENERIC_DESKTOP, USAGE_X, USAGE_Y,
+        },
+        driver::Driver,
+        event::Event,
+    },
+    mock_transport::MockTransport,
+};
+
+/// Report descriptor of a gamepad with 8 buttons, a hat switch and one stick
+const DESCRIPTOR: &[u8] = &[
+    0x05, 0x01, // Usage Page (Generic Desktop)
+    0x09, 0x05, // Usage (Gamepad)
+    0xa1, 0x01, // Collection (Application)
+    0x85, 0x01, //   Report ID (1)
+    0x05, 0x09, //   Usage Page (Button)
+    0x19, 0x01, //   Usage Minimum (1)
+    0x29, 0x08, //   Usage Maximum (8)
+    0x15, 0x00, //   Logical Minimum (0)
+    0x25, 0x01, //   Logical Maximum (1)
+    0x75, 0x01, //   Report Size (1)
+    0x95, 0x08, //   Report Count (8)
+    0x81, 0x02, //   Input (Data, Variable, Absolute)
+    0x05, 0x01, //   Usage Page (Generic Desktop)
+    0x09, 0x39, //   Usage (Hat Switch)
+    0x25, 0x07, //   Logical Maximum (7)
+    0x75, 0x04, //   Report Size (4)
+    0x95, 0x01, //   Report Count (1)
+    0x81, 0x42, //   Input (Data, Variable, Absolute, Null State)
+    0x81, 0x03, //   Input (Constant)
+    0x09, 0x30, //   Usage (X)
+    0x09, 0x31, //   Usage (Y)
+    0x15, 0x81, //   Logical Minimum (-127)
+    0x25, 0x7f, //   Logical Maximum (127)
+    0x75, 0x08, //   Report Size (8)
+    0x95, 0x02, //   Report Count (2)
+    0x81, 0x02, //   Input (Data, Variable, Absolute)
+    0xc0, // End Collection
+];
+
+#[test]
+fn test_generic_hid_descriptor() -> Result<(), Box<dyn Error>> {
+    let descriptor = ReportDescriptor::parse(DESCRIPTOR)?;
+    assert!(descriptor.is_gamepad());
+    assert!(descriptor.uses_report_ids);
+    assert_eq!(descriptor.fields.len(), 11);
+
+    let button = &descriptor.fields[7];
+    assert_eq!(button.usage, Usage::new(USAGE_PAGE_BUTTON, 8));
+    assert_eq!((button.bit_offset, button.bit_size), (7, 1));
+
+    let hat = &descriptor.fields[8];
+    assert_eq!(
+        hat.usage,
+        Usage::new(USAGE_PAGE_GENERIC_DESKTOP, USAGE_HAT_SWITCH)
+    );
+    assert_eq!((hat.bit_offset, hat.bit_size), (8, 4));
+
+    let y = &descriptor.fields[10];
+    assert_eq!(y.usage, Usage::new(USAGE_PAGE_GENERIC_DESKTOP, USAGE_Y));
+    assert_eq!((y.bit_offset, y.bit_size), (24, 8));
+    assert_eq!((y.logical_min, y.logical_max), (-127, 127));
+
+    Ok(())
+}
+
+#[test]
+fn test_generic_hid_descriptor_too_large() {
+    let descriptor = [
+        0x05, 0x01, // Usage Page (Generic Desktop)
+        0x09, 0x05, // Usage (Gamepad)
+        0xa1, 0x01, // Collection (Application)
+        0x75, 0x20, //   Report Size (32)
+        0x97, 0xff, 0xff, 0xff, 0xff, //   Report Count (4294967295)
+        0x81, 0x03, //   Input (Constant)
+        0xc0, // End Collection
+    ];
+    assert!(ReportDescriptor::parse(&descriptor).is_err());
+
+    // Many items that are each within the limits still cannot overflow the report
+    let mut descriptor = vec![0x75, 0x08, 0x96, 0x00, 0x30]; // Report Size (8), Report Count (12288)
+    for _ in 0..2 {
+        descriptor.extend_from_slice(&[0x81, 0x03]); // Input (Constant)
+    }
+    assert!(ReportDescriptor::parse(&descriptor).is_err());
+}
+
+#[tokio::test]
+async fn test_generic_hid_reports() -> Result<(), Box<dyn Error>> {
+    let descriptor = ReportDescriptor::parse(DESCRIPTOR)?;
+    let transport = MockTransport::new(vec![
+        vec![0x01, 0x00, 0x08, 0x00, 0x00],
+        vec![0x01, 0x01, 0x02, 0x81, 0x00],
+        // Reports with other IDs are ignored
+        vec![0x02, 0xff, 0xff, 0xff, 0xff],
+    ]);
+    let mut driver = Driver::from_transport(descriptor, Box::new(transport));
+
+    // The first report only sets the initial state
+    assert!(driver.poll()?.is_empty());
+
+    let events = driver.poll()?;
+    assert_eq!(events.len(), 3);
+    assert!(matches!(
+        events[0],
+        Event::Button(ref input) if input.usage.id == 1 && input.pressed
+    ));
+    assert!(matches!(
+        events[1],
+        Event::Axis(ref input) if input.usage.id == USAGE_HAT_SWITCH && input.value == 2
+    ));
+    assert!(matches!(
+        events[2],
+        Event::Axis(ref input) if input.usage.id == USAGE_X && input.value == -127
+    ));
+
+    assert!(driver.poll()?.is_empty());
+
+    Ok(())
+}
@@ -0,0 +1,25 @@
+use super::descriptor::Usage;
+
+/// Events that can be emitted by a generic HID gamepad
+#[derive(Clone, Debug)]
+pub enum Event {
+    Button(ButtonInput),
+    Axis(AxisInput),
+}
+
+/// Button or D-Pad input identified by its HID usage
+#[derive(Clone, Debug)]
+pub struct ButtonInput {
+    pub usage: Usage,
+    pub pressed: bool,
+}
+
+/// Axis or hat switch input identified by its HID usage, with the logical
+/// range declared in the report descriptor
+#[derive(Clone, Debug)]
+pub struct AxisInput {
+    pub usage: Usage,
+    pub value: i32,
+    pub logical_min: i32,
+    pub logical_max: i32,
+}
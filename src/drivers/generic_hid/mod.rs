@@ -0,0 +1,5 @@
+pub mod descriptor;
+pub mod driver;
+#[cfg(test)]
+mod driver_test;
+pub mod event;
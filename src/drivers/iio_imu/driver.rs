@@ -1,6 +1,6 @@
-use std::{collections::HashMap, error::Error};
+use std::{any::TypeId, collections::HashMap, error::Error, time::Instant};
 
-use industrial_io::{Channel, ChannelType, Device};
+use industrial_io::{Buffer, Channel, ChannelType, Context, Device};
 
 use crate::drivers::iio_imu::info::MountMatrix;
 
@@ -9,13 +9,23 @@ use super::{
     info::AxisInfo,
 };
 
+/// Number of samples read from the IIO buffer at a time
+const BUFFER_SAMPLES: usize = 1;
+
 /// Driver for reading IIO IMU data
 pub struct Driver {
+    ctx: Context,
+    device: Device,
+    /// Buffer used for triggered reads. If not set, channels are polled
+    /// through sysfs.
+    buffer: Option<Buffer>,
     mount_matrix: MountMatrix,
     accel: HashMap<String, Channel>,
     accel_info: HashMap<String, AxisInfo>,
     gyro: HashMap<String, Channel>,
     gyro_info: HashMap<String, AxisInfo>,
+    /// Time the last samples were read from the device
+    last_report: Instant,
 }
 
 impl Driver {
@@ -27,7 +37,7 @@ impl Driver {
         log::debug!("Creating IIO IMU driver instance for {name}");
 
         // Create an IIO local context used to query for devices
-        let ctx = industrial_io::context::Context::new()?;
+        let ctx = Context::new()?;
         log::debug!("IIO context version: {}", ctx.version());
 
         // Find the IMU device
@@ -35,15 +45,23 @@ impl Driver {
             return Err("Failed to find device".into());
         };
 
+        // Find all accelerometer and gyro channels and insert them into a hashmap.
+        // Some devices only expose one of the two sensors.
+        let (accel, accel_info) = get_channels_with_type(&device, ChannelType::Accel);
+        let (gyro, gyro_info) = get_channels_with_type(&device, ChannelType::AnglVel);
+        log::debug!(
+            "Found {} accelerometer and {} gyro channels",
+            accel.len(),
+            gyro.len()
+        );
+
         // Try finding the mount matrix to determine how sensors were mounted inside
         // the device.
         // https://github.com/torvalds/linux/blob/master/Documentation/devicetree/bindings/iio/mount-matrix.txt
         let mount_matrix = if let Some(matrix) = matrix {
             // Use the provided mount matrix if it is defined
             matrix
-        } else if let Some(mount) = device.find_channel("mount", false) {
-            // Read from the matrix
-            let matrix_str = mount.attr_read_str("matrix")?;
+        } else if let Some(matrix_str) = read_mount_matrix(&device, &accel, &gyro)? {
             log::debug!("Found mount matrix: {matrix_str}");
             let matrix = MountMatrix::new(matrix_str)?;
             log::debug!("Decoded mount matrix: {matrix}");
@@ -52,10 +70,6 @@ impl Driver {
             MountMatrix::default()
         };
 
-        // Find all accelerometer and gyro channels and insert them into a hashmap
-        let (accel, accel_info) = get_channels_with_type(&device, ChannelType::Accel);
-        let (gyro, gyro_info) = get_channels_with_type(&device, ChannelType::AnglVel);
-
         // Log device attributes
         for attr in device.attributes() {
             log::debug!("Found device attribute: {:?}", attr)
@@ -72,18 +86,75 @@ impl Driver {
         }
 
         Ok(Self {
+            ctx,
+            device,
+            buffer: None,
             mount_matrix,
             accel,
             accel_info,
             gyro,
             gyro_info,
+            last_report: Instant::now(),
         })
     }
 
+    /// Set the sampling frequency in Hz of the accelerometer and gyro
+    pub fn set_sample_rate(&self, rate: f64) -> Result<(), Box<dyn Error + Send + Sync>> {
+        log::debug!("Setting IIO sampling frequency to {rate}Hz");
+        if self.device.has_attr("sampling_frequency") {
+            self.device.attr_write_float("sampling_frequency", rate)?;
+            return Ok(());
+        }
+
+        // Most IMUs have a separate sampling frequency for each sensor type
+        for channel in self.accel.values().chain(self.gyro.values()) {
+            if channel.has_attr("sampling_frequency") {
+                channel.attr_write_float("sampling_frequency", rate)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Switch from polling channels through sysfs to reading them from an
+    /// IIO buffer. Reads block until the given trigger (or the current
+    /// trigger of the device if none is given) fires.
+    pub fn enable_buffer(
+        &mut self,
+        trigger: Option<&str>,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        if let Some(trigger) = trigger {
+            let Some(trigger) = self.ctx.find_device(trigger) else {
+                return Err(format!("Failed to find IIO trigger: {trigger}").into());
+            };
+            self.device.set_trigger(&trigger)?;
+        }
+
+        for channel in self.accel.values().chain(self.gyro.values()) {
+            if !channel.is_scan_element() {
+                return Err(
+                    format!("Channel {:?} does not support buffered reads", channel.id()).into(),
+                );
+            }
+            channel.enable();
+        }
+
+        let buffer = self.device.create_buffer(BUFFER_SAMPLES, false)?;
+        self.buffer = Some(buffer);
+        log::debug!("Enabled buffered reads");
+
+        Ok(())
+    }
+
     /// Poll the device for data
-    pub fn poll(&self) -> Result<Vec<Event>, Box<dyn Error + Send + Sync>> {
+    pub fn poll(&mut self) -> Result<Vec<Event>, Box<dyn Error + Send + Sync>> {
         let mut events = vec![];
 
+        // Wait for the next samples if reading from a buffer
+        if let Some(buffer) = self.buffer.as_mut() {
+            buffer.refill()?;
+        }
+        self.last_report = Instant::now();
+
         // Read from the accelerometer
         if let Some(event) = self.poll_accel()? {
             events.push(event);
@@ -97,8 +168,17 @@ impl Driver {
         Ok(events)
     }
 
+    /// Returns the time the last samples were read from the device
+    pub fn last_report_time(&self) -> Instant {
+        self.last_report
+    }
+
     /// Polls all the channels from the accelerometer
     fn poll_accel(&self) -> Result<Option<Event>, Box<dyn Error + Send + Sync>> {
+        if self.accel.is_empty() {
+            return Ok(None);
+        }
+
         // Read from each accel channel
         let mut accel_input = AxisData::default();
         for (id, channel) in self.accel.iter() {
@@ -106,7 +186,7 @@ impl Driver {
             let Some(info) = self.accel_info.get(id) else {
                 continue;
             };
-            let data = channel.attr_read::<i64>("raw")?;
+            let data = self.read_raw(channel)?;
 
             // processed_value = (raw + offset) * scale
             let value = (data + info.offset) as f64 * info.scale;
@@ -127,16 +207,20 @@ impl Driver {
 
     /// Polls all the channels from the gyro
     fn poll_gyro(&self) -> Result<Option<Event>, Box<dyn Error + Send + Sync>> {
-        // Read from each accel channel
+        if self.gyro.is_empty() {
+            return Ok(None);
+        }
+
+        // Read from each gyro channel
         let mut gyro_input = AxisData::default();
         for (id, channel) in self.gyro.iter() {
             // Get the info for the axis and read the data
             let Some(info) = self.gyro_info.get(id) else {
                 continue;
             };
-            let data = channel.attr_read::<i64>("raw")?;
+            let data = self.read_raw(channel)?;
 
-            // processed_value = (raw + offset) * scale
+            // processed_value = (raw + offset) * scale, in rads per second
             let value = (data + info.offset) as f64 * info.scale;
 
             if id.ends_with('x') {
@@ -154,31 +238,84 @@ impl Driver {
         Ok(Some(Event::Gyro(gyro_input)))
     }
 
+    /// Read the raw value of the given channel, either from the buffer or
+    /// from sysfs.
+    fn read_raw(&self, channel: &Channel) -> Result<i64, Box<dyn Error + Send + Sync>> {
+        let Some(buffer) = self.buffer.as_ref() else {
+            return Ok(channel.attr_read::<i64>("raw")?);
+        };
+
+        // Read the latest sample in the native data type of the channel
+        let value = match channel.type_of() {
+            Some(t) if t == TypeId::of::<i16>() => {
+                channel.read::<i16>(buffer)?.last().map(|v| *v as i64)
+            }
+            Some(t) if t == TypeId::of::<u16>() => {
+                channel.read::<u16>(buffer)?.last().map(|v| *v as i64)
+            }
+            Some(t) if t == TypeId::of::<i32>() => {
+                channel.read::<i32>(buffer)?.last().map(|v| *v as i64)
+            }
+            Some(t) if t == TypeId::of::<u32>() => {
+                channel.read::<u32>(buffer)?.last().map(|v| *v as i64)
+            }
+            Some(t) if t == TypeId::of::<i64>() => channel.read::<i64>(buffer)?.last().copied(),
+            _ => {
+                return Err(format!("Unsupported data type for channel {:?}", channel.id()).into())
+            }
+        };
+
+        value.ok_or_else(|| "No samples read from buffer".into())
+    }
+
     /// Rotate the given axis data according to the mount matrix. This is used
     /// to calculate the final value according to the sensor oritentation.
-    // Values are intended to be multiplied as:
-    //   x' = mxx * x + myx * y + mzx * z
-    //   y' = mxy * x + myy * y + mzy * z
-    //   z' = mxz * x + myz * y + mzz * z
     fn rotate_value(&self, value: &mut AxisData) {
-        let x = value.x;
-        let y = value.y;
-        let z = value.z;
-        let mxx = self.mount_matrix.x.0;
-        let myx = self.mount_matrix.x.1;
-        let mzx = self.mount_matrix.x.2;
-        let mxy = self.mount_matrix.y.0;
-        let myy = self.mount_matrix.y.1;
-        let mzy = self.mount_matrix.y.2;
-        let mxz = self.mount_matrix.z.0;
-        let myz = self.mount_matrix.z.1;
-        let mzz = self.mount_matrix.z.2;
-        value.x = mxx * x + myx * y + mzx * z;
-        value.y = mxy * x + myy * y + mzy * z;
-        value.z = mxz * x + myz * y + mzz * z;
+        self.mount_matrix.rotate(value);
     }
 }
 
+/// Returns true if the IIO device with the given id has any channels of the
+/// given type.
+pub fn has_channel_type(id: &str, channel_type: ChannelType) -> Result<bool, Box<dyn Error>> {
+    let ctx = Context::new()?;
+    let Some(device) = ctx.find_device(id) else {
+        return Err(format!("Failed to find device: {id}").into());
+    };
+    let found = device
+        .channels()
+        .any(|channel| channel.channel_type() == channel_type);
+    Ok(found)
+}
+
+/// Returns the mount matrix string of the given device, if the driver exposes
+/// one. Depending on the driver, the matrix is shared by all channels of the
+/// device (e.g. lsm6dso), by all input channels (e.g. bmi160) or by all
+/// channels of one sensor type (e.g. bmi323).
+fn read_mount_matrix(
+    device: &Device,
+    accel: &HashMap<String, Channel>,
+    gyro: &HashMap<String, Channel>,
+) -> Result<Option<String>, Box<dyn Error + Send + Sync>> {
+    if device.has_attr("mount_matrix") {
+        return Ok(Some(device.attr_read_str("mount_matrix")?));
+    }
+    if let Some(mount) = device.find_channel("mount", false) {
+        return Ok(Some(mount.attr_read_str("matrix")?));
+    }
+    // Both sensors are mounted on the same chip, so the accelerometer matrix
+    // also applies to the gyro
+    let channel = accel
+        .values()
+        .chain(gyro.values())
+        .find(|channel| channel.has_attr("mount_matrix"));
+    let Some(channel) = channel else {
+        return Ok(None);
+    };
+
+    Ok(Some(channel.attr_read_str("mount_matrix")?))
+}
+
 /// Returns all channels and channel information from the given device matching
 /// the given channel type.
 fn get_channels_with_type(
@@ -1,5 +1,9 @@
 use std::{error::Error, fmt};
 
+use crate::config;
+
+use super::event::AxisData;
+
 /// The [MountMatrix] is used to define how sensors are oriented inside a device
 /// https://github.com/torvalds/linux/blob/master/Documentation/devicetree/bindings/iio/mount-matrix.txt
 #[derive(Clone, Debug)]
@@ -39,6 +43,30 @@ impl MountMatrix {
 
         Ok(matrix)
     }
+
+    /// Rotate the given axis data according to the mount matrix.
+    // Values are intended to be multiplied as:
+    //   x' = mxx * x + myx * y + mzx * z
+    //   y' = mxy * x + myy * y + mzy * z
+    //   z' = mxz * x + myz * y + mzz * z
+    pub fn rotate(&self, value: &mut AxisData) {
+        let x = value.x;
+        let y = value.y;
+        let z = value.z;
+        value.x = self.x.0 * x + self.x.1 * y + self.x.2 * z;
+        value.y = self.y.0 * x + self.y.1 * y + self.y.2 * z;
+        value.z = self.z.0 * x + self.z.1 * y + self.z.2 * z;
+    }
+}
+
+impl From<&config::MountMatrix> for MountMatrix {
+    fn from(matrix: &config::MountMatrix) -> Self {
+        MountMatrix {
+            x: (matrix.x[0], matrix.x[1], matrix.x[2]),
+            y: (matrix.y[0], matrix.y[1], matrix.y[2]),
+            z: (matrix.z[0], matrix.z[1], matrix.z[2]),
+        }
+    }
 }
 
 impl Default for MountMatrix {
@@ -85,3 +113,48 @@ pub struct AxesInfo {
     pub y: Option<AxisInfo>,
     pub z: Option<AxisInfo>,
 }
+
+/// Calibration of a sensor that is applied after the values have been rotated
+/// by the mount matrix. The orientation is applied first to remap the axes,
+/// then the offset and scale.
+///   calibrated_value = (value + offset) * scale
+#[derive(Clone, Debug)]
+pub struct Calibration {
+    pub orientation: Option<MountMatrix>,
+    pub offset: (f64, f64, f64),
+    pub scale: (f64, f64, f64),
+}
+
+impl Calibration {
+    /// Apply the calibration to the given axis data
+    pub fn apply(&self, value: &mut AxisData) {
+        if let Some(orientation) = self.orientation.as_ref() {
+            orientation.rotate(value);
+        }
+        value.x = (value.x + self.offset.0) * self.scale.0;
+        value.y = (value.y + self.offset.1) * self.scale.1;
+        value.z = (value.z + self.offset.2) * self.scale.2;
+    }
+}
+
+impl Default for Calibration {
+    fn default() -> Self {
+        Calibration {
+            orientation: None,
+            offset: (0.0, 0.0, 0.0),
+            scale: (1.0, 1.0, 1.0),
+        }
+    }
+}
+
+impl From<&config::ImuCalibration> for Calibration {
+    fn from(calibration: &config::ImuCalibration) -> Self {
+        let offset = calibration.offset.unwrap_or([0.0; 3]);
+        let scale = calibration.scale.unwrap_or([1.0; 3]);
+        Calibration {
+            orientation: calibration.orientation.as_ref().map(MountMatrix::from),
+            offset: (offset[0], offset[1], offset[2]),
+            scale: (scale[0], scale[1], scale[2]),
+        }
+    }
+}
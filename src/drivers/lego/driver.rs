@@ -5,8 +5,9 @@ use std::{
     u8, vec,
 };
 
-use hidapi::HidDevice;
-use packed_struct::{types::SizedInteger, PackedStruct};
+use packed_struct::PackedStruct;
+
+use crate::drivers::transport::{HidTransport, QueuedTransport};
 
 use super::{
     event::{
@@ -15,7 +16,8 @@ use super::{
         TouchAxisInput, TriggerEvent, TriggerInput,
     },
     hid_report::{
-        DInputDataLeftReport, DInputDataRightReport, KeyboardDataReport, MouseDataReport,
+        ConfigCommand, ConfigOutputDataReport, ConfigTarget, DInputDataLeftReport,
+        DInputDataRightReport, KeyboardDataReport, MouseDataReport, RumbleOutputDataReport,
         TouchpadDataReport, XInputDataReport,
     },
 };
@@ -24,11 +26,25 @@ use super::{
 pub const VID: u16 = 0x17ef;
 pub const PID: u16 = 0x6182;
 pub const PID2: u16 = 0x6185;
+/// Supported controller variants and the report layout each one uses
+pub const DEVICES: &[DeviceVariant] = &[
+    DeviceVariant {
+        vendor_id: VID,
+        product_id: PID,
+        layout: ReportLayout::LegionGo,
+    },
+    DeviceVariant {
+        vendor_id: VID,
+        product_id: PID2,
+        layout: ReportLayout::LegionGo,
+    },
+];
 // Hardware limits
 pub const DINPUT_LEFT_DATA: u8 = 0x07;
 pub const DINPUT_RIGHT_DATA: u8 = 0x08;
 pub const KEYBOARD_TOUCH_DATA: u8 = 0x01;
 pub const MOUSE_FPS_DATA: u8 = 0x02;
+pub const MOUSE_DATA: u8 = 0x09;
 pub const XINPUT_DATA: u8 = 0x04;
 // Input report sizes
 const DINPUT_PACKET_SIZE: usize = 13;
@@ -45,9 +61,53 @@ pub const STICK_X_MAX: f64 = 255.0;
 pub const STICK_X_MIN: f64 = 0.0;
 pub const STICK_Y_MAX: f64 = 255.0;
 pub const STICK_Y_MIN: f64 = 0.0;
+const DINPUT_STICK_MAX: u16 = 4095;
+const STICK_CENTER: u8 = 128;
 pub const TRIGG_MAX: f64 = 255.0;
 
+// HID keyboard usages emitted by the left controller in FPS mode
+const KEY_A: u8 = 0x04;
+const KEY_D: u8 = 0x07;
+const KEY_S: u8 = 0x16;
+const KEY_W: u8 = 0x1a;
+const KEYBOARD_BUTTONS: [(u8, fn(BinaryInput) -> ButtonEvent); 8] = [
+    (0x10, ButtonEvent::ThumbL),    // M
+    (0x1e, ButtonEvent::DPadUp),    // 1
+    (0x1f, ButtonEvent::DPadLeft),  // 2
+    (0x20, ButtonEvent::DPadDown),  // 3
+    (0x21, ButtonEvent::DPadRight), // 4
+    (0x29, ButtonEvent::View),      // Escape
+    (0x2b, ButtonEvent::Menu),      // Tab
+    (0x2c, ButtonEvent::Y1),        // Spacebar
+];
+
+/// Layout of the input reports sent by a controller firmware
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportLayout {
+    /// Original Legion Go controllers with xinput, dinput, keyboard, mouse
+    /// and touchpad reports
+    LegionGo,
+}
+
+/// A supported controller identified by its vendor and product id
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DeviceVariant {
+    pub vendor_id: u16,
+    pub product_id: u16,
+    pub layout: ReportLayout,
+}
+
+/// Returns the supported controller variant with the given vendor and
+/// product id, if any
+pub fn find_variant(vendor_id: u16, product_id: u16) -> Option<&'static DeviceVariant> {
+    DEVICES
+        .iter()
+        .find(|variant| variant.vendor_id == vendor_id && variant.product_id == product_id)
+}
+
 pub struct Driver {
+    /// Layout of the input reports sent by the device
+    layout: ReportLayout,
     /// State for the left detachable controller when in dinput mode
     dinputl_state: Option<DInputDataLeftReport>,
     /// State for the right detachable controller when in dinput mode
@@ -61,11 +121,13 @@ pub struct Driver {
     /// State for the internal gamepad  controller
     xinput_state: Option<XInputDataReport>,
     /// HIDRAW device instance
-    device: HidDevice,
+    device: Box<dyn HidTransport>,
     /// Timestamp of the last touch event.
     last_touch: Instant,
-    /// Whether or not we are detecting a touch event currently.
-    is_touching: bool,
+    /// Contact ids of the fingers currently touching the touchpad.
+    touches: Vec<u8>,
+    /// Time the last input report was read from the device
+    last_report: Instant,
 }
 
 impl Driver {
@@ -75,11 +137,21 @@ impl Driver {
         let api = hidapi::HidApi::new()?;
         let device = api.open_path(&path)?;
         let info = device.get_device_info()?;
-        if info.vendor_id() != VID || (info.product_id() != PID && info.product_id() != PID2) {
+        let Some(variant) = find_variant(info.vendor_id(), info.product_id()) else {
             return Err(format!("Device '{fmtpath}' is not a Legion Go Controller").into());
-        }
+        };
+
+        let mut driver = Self::from_transport(Box::new(QueuedTransport::new(device)));
+        driver.layout = variant.layout;
 
-        Ok(Self {
+        Ok(driver)
+    }
+
+    /// Create a driver that exchanges reports over the given transport using
+    /// the [ReportLayout::LegionGo] layout
+    pub fn from_transport(device: Box<dyn HidTransport>) -> Self {
+        Self {
+            layout: ReportLayout::LegionGo,
             device,
             dinputl_state: None,
             dinputr_state: None,
@@ -88,8 +160,42 @@ impl Driver {
             mouse_state: None,
             touchpad_state: None,
             last_touch: Instant::now(),
-            is_touching: false,
-        })
+            touches: Vec::new(),
+            last_report: Instant::now(),
+        }
+    }
+
+    /// Rumble the controllers with the given left (strong) and right (weak)
+    /// motor speeds
+    pub fn haptic_rumble(
+        &mut self,
+        left_speed: u8,
+        right_speed: u8,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let mut report = RumbleOutputDataReport::new();
+        report.left_motor_speed = left_speed;
+        report.right_motor_speed = right_speed;
+
+        // Write the report to the device
+        let buf = report.pack()?;
+        let _bytes_written = self.device.write_rumble(&buf)?;
+
+        Ok(())
+    }
+
+    /// Enable or disable the touchpad in the controller firmware
+    pub fn set_touchpad_enabled(&self, enabled: bool) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let report = ConfigOutputDataReport::new(
+            ConfigCommand::TouchpadEnable,
+            ConfigTarget::Touchpad,
+            enabled as u8,
+        );
+
+        // Write the report to the device
+        let buf = report.pack()?;
+        let _bytes_written = self.device.write(&buf)?;
+
+        Ok(())
     }
 
     /// Poll the device and read input reports
@@ -97,13 +203,39 @@ impl Driver {
         // Read data from the device into a buffer
         let mut buf = [0; XINPUT_PACKET_SIZE];
         let bytes_read = self.device.read_timeout(&mut buf[..], HID_TIMEOUT)?;
+        if bytes_read > 0 {
+            self.last_report = Instant::now();
+        }
 
         let report_id = buf[0];
         let slice = &buf[..bytes_read];
         //log::trace!("Got Report ID: {report_id}");
         //log::trace!("Got Report Size: {bytes_read}");
 
-        let mut events = match report_id {
+        let mut events = match self.layout {
+            ReportLayout::LegionGo => self.handle_legion_go_report(report_id, slice)?,
+        };
+
+        if !self.touches.is_empty() && (self.last_touch.elapsed() > Duration::from_millis(4)) {
+            events.extend(self.release_touches());
+        }
+
+        Ok(events)
+    }
+
+    /// Returns the time the last input report was read from the device
+    pub fn last_report_time(&self) -> Instant {
+        self.last_report
+    }
+
+    /// Handle an input report in the [ReportLayout::LegionGo] layout
+    fn handle_legion_go_report(
+        &mut self,
+        report_id: u8,
+        slice: &[u8],
+    ) -> Result<Vec<Event>, Box<dyn Error + Send + Sync>> {
+        let bytes_read = slice.len();
+        let events = match report_id {
             DINPUT_LEFT_DATA => {
                 if bytes_read != DINPUT_PACKET_SIZE {
                     return Err("Invalid packet size for Direct Input Data.".into());
@@ -142,7 +274,7 @@ impl Driver {
                 }
             }
 
-            MOUSE_FPS_DATA => {
+            MOUSE_FPS_DATA | MOUSE_DATA => {
                 if bytes_read != MOUSE_PACKET_SIZE {
                     return Err("Invalid packet size for Mouse Data.".into());
                 }
@@ -168,11 +300,6 @@ impl Driver {
             }
         };
 
-        if self.is_touching && (self.last_touch.elapsed() > Duration::from_millis(4)) {
-            let event: Event = self.release_touch();
-            events.push(event);
-        }
-
         Ok(events)
     }
     /// Unpacks the buffer into a [DInputDataReport] structure and updates
@@ -208,14 +335,67 @@ impl Driver {
     }
 
     /// Translate the state into individual events
-    fn translate_dinputl(&self, _old_state: Option<DInputDataLeftReport>) -> Vec<Event> {
-        let events = Vec::new();
-        let Some(_) = self.dinputl_state else {
+    fn translate_dinputl(&self, old_state: Option<DInputDataLeftReport>) -> Vec<Event> {
+        let mut events = Vec::new();
+        let Some(state) = self.dinputl_state else {
             return events;
         };
 
         // Translate state changes into events if they have changed
-        //if let Some(_) = old_state {}
+        let Some(old_state) = old_state else {
+            return events;
+        };
+        if state.left != old_state.left {
+            events.push(Event::Button(ButtonEvent::DPadLeft(BinaryInput {
+                pressed: state.left,
+            })));
+        }
+        if state.down != old_state.down {
+            events.push(Event::Button(ButtonEvent::DPadDown(BinaryInput {
+                pressed: state.down,
+            })));
+        }
+        if state.up != old_state.up {
+            events.push(Event::Button(ButtonEvent::DPadUp(BinaryInput {
+                pressed: state.up,
+            })));
+        }
+        if state.right != old_state.right {
+            events.push(Event::Button(ButtonEvent::DPadRight(BinaryInput {
+                pressed: state.right,
+            })));
+        }
+        if state.y1 != old_state.y1 {
+            events.push(Event::Button(ButtonEvent::Y1(BinaryInput {
+                pressed: state.y1,
+            })));
+        }
+        if state.y2 != old_state.y2 {
+            events.push(Event::Button(ButtonEvent::Y2(BinaryInput {
+                pressed: state.y2,
+            })));
+        }
+        if state.select != old_state.select {
+            events.push(Event::Button(ButtonEvent::View(BinaryInput {
+                pressed: state.select,
+            })));
+        }
+        if state.start != old_state.start {
+            events.push(Event::Button(ButtonEvent::Menu(BinaryInput {
+                pressed: state.start,
+            })));
+        }
+
+        // The detached controller is held sideways, so rotate the stick axes
+        // into the same orientation as the xinput report.
+        if state.l_stick_axes != old_state.l_stick_axes {
+            let (hid_x, hid_y) = state.l_stick();
+            events.push(Event::Axis(AxisEvent::LStick(JoyAxisInput {
+                x: dinput_axis_value(DINPUT_STICK_MAX - hid_y),
+                y: dinput_axis_value(hid_x),
+            })));
+        }
+
         events
     }
 
@@ -252,14 +432,67 @@ impl Driver {
     }
 
     /// Translate the state into individual events
-    fn translate_dinputr(&self, _old_state: Option<DInputDataRightReport>) -> Vec<Event> {
-        let events = Vec::new();
-        let Some(_) = self.dinputr_state else {
+    fn translate_dinputr(&self, old_state: Option<DInputDataRightReport>) -> Vec<Event> {
+        let mut events = Vec::new();
+        let Some(state) = self.dinputr_state else {
             return events;
         };
 
         // Translate state changes into events if they have changed
-        //if let Some(_) = old_state {}
+        let Some(old_state) = old_state else {
+            return events;
+        };
+        if state.a != old_state.a {
+            events.push(Event::Button(ButtonEvent::A(BinaryInput {
+                pressed: state.a,
+            })));
+        }
+        if state.b != old_state.b {
+            events.push(Event::Button(ButtonEvent::B(BinaryInput {
+                pressed: state.b,
+            })));
+        }
+        if state.x != old_state.x {
+            events.push(Event::Button(ButtonEvent::X(BinaryInput {
+                pressed: state.x,
+            })));
+        }
+        if state.y != old_state.y {
+            events.push(Event::Button(ButtonEvent::Y(BinaryInput {
+                pressed: state.y,
+            })));
+        }
+        if state.y3 != old_state.y3 {
+            events.push(Event::Button(ButtonEvent::Y3(BinaryInput {
+                pressed: state.y3,
+            })));
+        }
+        if state.m2 != old_state.m2 {
+            events.push(Event::Button(ButtonEvent::M2(BinaryInput {
+                pressed: state.m2,
+            })));
+        }
+        if state.m3 != old_state.m3 {
+            events.push(Event::Button(ButtonEvent::M3(BinaryInput {
+                pressed: state.m3,
+            })));
+        }
+        if state.quick_access != old_state.quick_access {
+            events.push(Event::Button(ButtonEvent::QuickAccess(BinaryInput {
+                pressed: state.quick_access,
+            })));
+        }
+
+        // The detached controller is held sideways, so rotate the stick axes
+        // into the same orientation as the xinput report.
+        if state.r_stick_axes != old_state.r_stick_axes {
+            let (hid_x, hid_y) = state.r_stick();
+            events.push(Event::Axis(AxisEvent::RStick(JoyAxisInput {
+                x: dinput_axis_value(hid_y),
+                y: dinput_axis_value(DINPUT_STICK_MAX - hid_x),
+            })));
+        }
+
         events
     }
 
@@ -296,14 +529,48 @@ impl Driver {
     }
 
     /// Translate the state into individual events
-    fn translate_keyboard(&self, _old_state: Option<KeyboardDataReport>) -> Vec<Event> {
-        let events = Vec::new();
-        let Some(_) = self.keyboard_state else {
+    fn translate_keyboard(&self, old_state: Option<KeyboardDataReport>) -> Vec<Event> {
+        let mut events = Vec::new();
+        let Some(state) = self.keyboard_state else {
             return events;
         };
 
         // Translate state changes into events if they have changed
-        //if let Some(_) = old_state {}
+        let Some(old_state) = old_state else {
+            return events;
+        };
+
+        // The left trigger is reported as the left shift modifier
+        if state.left_shift != old_state.left_shift {
+            events.push(Event::Button(ButtonEvent::DTriggerL(BinaryInput {
+                pressed: state.left_shift,
+            })));
+            let value = if state.left_shift { TRIGG_MAX as u8 } else { 0 };
+            events.push(Event::Trigger(TriggerEvent::ATriggerL(TriggerInput {
+                value,
+            })));
+        }
+
+        for (key, button) in KEYBOARD_BUTTONS {
+            let pressed = state.is_pressed(key);
+            if pressed != old_state.is_pressed(key) {
+                events.push(Event::Button(button(BinaryInput { pressed })));
+            }
+        }
+
+        // The left stick is reported as WASD keys, so translate them into full
+        // stick deflections.
+        let stick = |report: &KeyboardDataReport| {
+            (
+                keyboard_axis_value(report, KEY_A, KEY_D),
+                keyboard_axis_value(report, KEY_W, KEY_S),
+            )
+        };
+        let (x, y) = stick(&state);
+        if (x, y) != stick(&old_state) {
+            events.push(Event::Axis(AxisEvent::LStick(JoyAxisInput { x, y })));
+        }
+
         events
     }
 
@@ -374,20 +641,20 @@ impl Driver {
                     pressed: state.m1,
                 })));
             }
+        }
 
-            // Axis events
-            if state.mouse_x != old_state.mouse_x || state.mouse_y != old_state.mouse_y {
-                events.push(Event::Axis(AxisEvent::Mouse(MouseAxisInput {
-                    x: state.mouse_x.to_primitive(),
-                    y: state.mouse_y.to_primitive(),
-                })));
-            }
-            if state.mouse_z != old_state.mouse_z {
-                events.push(Event::Trigger(TriggerEvent::MouseWheel(MouseWheelInput {
-                    value: state.mouse_z,
-                })));
-            }
+        // Motion and wheel values are relative, so they are emitted with
+        // every report that has them instead of only when they change.
+        let (x, y) = state.mouse_motion();
+        if x != 0 || y != 0 {
+            events.push(Event::Axis(AxisEvent::Mouse(MouseAxisInput { x, y })));
         }
+        if state.mouse_z != 0 {
+            events.push(Event::Trigger(TriggerEvent::MouseWheel(MouseWheelInput {
+                value: state.mouse_z,
+            })));
+        }
+
         events
     }
 
@@ -434,17 +701,38 @@ impl Driver {
         let Some(_) = old_state else {
             return events;
         };
-        //// Axis events
-        if !self.is_touching {
-            self.is_touching = true;
-            log::trace!("Started TOUCH event");
+
+        // Release any fingers that are no longer on the touchpad
+        let contacts = state.contacts();
+        let (touching, released): (Vec<u8>, Vec<u8>) = self
+            .touches
+            .iter()
+            .copied()
+            .partition(|index| contacts.iter().any(|(id, _, _)| id == index));
+        for index in released {
+            log::trace!("Released TOUCH event {index}");
+            events.push(Event::Axis(AxisEvent::Touchpad(TouchAxisInput {
+                index,
+                is_touching: false,
+                x: 0,
+                y: 0,
+            })));
+        }
+        self.touches = touching;
+
+        // Axis events
+        for (index, x, y) in contacts {
+            if !self.touches.contains(&index) {
+                log::trace!("Started TOUCH event {index}");
+                self.touches.push(index);
+            }
+            events.push(Event::Axis(AxisEvent::Touchpad(TouchAxisInput {
+                index,
+                is_touching: true,
+                x,
+                y,
+            })));
         }
-        events.push(Event::Axis(AxisEvent::Touchpad(TouchAxisInput {
-            index: 0,
-            is_touching: true,
-            x: state.touch_x_0,
-            y: state.touch_y_0,
-        })));
 
         self.last_touch = Instant::now();
         events
@@ -486,6 +774,30 @@ impl Driver {
             return events;
         };
 
+        // Detachable controller connection events. Controllers are assumed to
+        // be connected until the first report says otherwise.
+        let (l_connected, r_connected) = match old_state {
+            Some(old_state) => (
+                old_state.l_controller_mode0 != 0,
+                old_state.r_controller_mode0 != 0,
+            ),
+            None => (true, true),
+        };
+        if (state.l_controller_mode0 != 0) != l_connected {
+            events.push(Event::Status(StatusEvent::LeftControllerConnected(
+                BinaryInput {
+                    pressed: !l_connected,
+                },
+            )));
+        }
+        if (state.r_controller_mode0 != 0) != r_connected {
+            events.push(Event::Status(StatusEvent::RightControllerConnected(
+                BinaryInput {
+                    pressed: !r_connected,
+                },
+            )));
+        }
+
         // Translate state changes into events if they have changed
         if let Some(old_state) = old_state {
             if state.gamepad_mode != old_state.gamepad_mode {
@@ -711,14 +1023,35 @@ impl Driver {
         events
     }
 
-    fn release_touch(&mut self) -> Event {
-        log::trace!("Released TOUCH event.");
-        self.is_touching = false;
-        Event::Axis(AxisEvent::Touchpad(TouchAxisInput {
-            index: 0,
-            is_touching: false,
-            x: 0,
-            y: 0,
-        }))
+    /// Release all fingers that are touching the touchpad
+    fn release_touches(&mut self) -> Vec<Event> {
+        log::trace!("Released TOUCH events.");
+        self.touches
+            .drain(..)
+            .map(|index| {
+                Event::Axis(AxisEvent::Touchpad(TouchAxisInput {
+                    index,
+                    is_touching: false,
+                    x: 0,
+                    y: 0,
+                }))
+            })
+            .collect()
+    }
+}
+
+/// Scale the given 12-bit DInput stick value to the 8-bit range used by
+/// [JoyAxisInput]
+fn dinput_axis_value(value: u16) -> u8 {
+    (value.min(DINPUT_STICK_MAX) >> 4) as u8
+}
+
+/// Returns the stick axis value for the given pair of keys that move the
+/// axis in the negative and positive direction
+fn keyboard_axis_value(report: &KeyboardDataReport, negative: u8, positive: u8) -> u8 {
+    match (report.is_pressed(negative), report.is_pressed(positive)) {
+        (true, false) => STICK_X_MIN as u8,
+        (false, true) => STICK_X_MAX as u8,
+        _ => STICK_CENTER,
     }
 }
@@ -0,0 +1,310 @@
+use std::{error::Error, sync::Arc};
+
+use crate::drivers::{
+    lego::{
+        driver::{
+            find_variant, Driver, ReportLayout, DINPUT_LEFT_DATA, KEYBOARD_TOUCH_DATA, MOUSE_DATA,
+            PID, PID2, VID, XINPUT_DATA,
+        },
+        event::{AxisEvent, ButtonEvent, Event, StatusEvent, TriggerEvent},
+    },
+    mock_transport::MockTransport,
+};
+
+/// Returns an xinput report with both controllers docked, no buttons pressed
+/// and all axes centered
+fn neutral_report() -> Vec<u8> {
+    let mut report = vec![0; 60];
+    report[0] = XINPUT_DATA;
+    report[1] = 0x3c;
+    report[6] = 0x04;
+    report[8] = 0x04;
+    report[12] = 0x02;
+    report[13] = 0x02;
+    report[14] = 0x80;
+    report[15] = 0x80;
+    report[16] = 0x80;
+    report[17] = 0x80;
+    report
+}
+
+/// Returns only the non-gyro events from the given events
+fn input_events(events: Vec<Event>) -> Vec<Event> {
+    events
+        .into_iter()
+        .filter(|event| !matches!(event, Event::Gyro(_)))
+        .collect()
+}
+
+#[test]
+fn test_lego_find_variant() {
+    for pid in [PID, PID2] {
+        let variant = find_variant(VID, pid).expect("supported variant");
+        assert_eq!(variant.layout, ReportLayout::LegionGo);
+    }
+    assert!(find_variant(VID, 0x0000).is_none());
+    assert!(find_variant(0x0000, PID).is_none());
+}
+
+#[tokio::test]
+async fn test_lego_xinput_button_reports() -> Result<(), Box<dyn Error>> {
+    let mut pressed = neutral_report();
+    pressed[19] = 0x80; // A
+    let transport = MockTransport::new(vec![neutral_report(), pressed, neutral_report()]);
+    let mut driver = Driver::from_transport(Box::new(transport));
+
+    // The first report only initializes the state
+    assert!(input_events(driver.poll()?).is_empty());
+
+    let events = input_events(driver.poll()?);
+    assert_eq!(events.len(), 1);
+    assert!(matches!(
+        events[0],
+        Event::Button(ButtonEvent::A(ref input)) if input.pressed
+    ));
+
+    let events = input_events(driver.poll()?);
+    assert_eq!(events.len(), 1);
+    assert!(matches!(
+        events[0],
+        Event::Button(ButtonEvent::A(ref input)) if !input.pressed
+    ));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_lego_xinput_axis_and_status_reports() -> Result<(), Box<dyn Error>> {
+    let mut report = neutral_report();
+    report[5] = 42; // Left controller battery
+    report[14] = 0xff; // Left stick X
+    report[15] = 0x00; // Left stick Y
+    let transport = MockTransport::new(vec![neutral_report(), report]);
+    let mut driver = Driver::from_transport(Box::new(transport));
+
+    driver.poll()?;
+    let events = input_events(driver.poll()?);
+    assert_eq!(events.len(), 2);
+    assert!(events.iter().any(|event| matches!(
+        event,
+        Event::Axis(AxisEvent::LStick(input)) if input.x == 0xff && input.y == 0x00
+    )));
+    assert!(events.iter().any(|event| matches!(
+        event,
+        Event::Status(StatusEvent::LeftControllerBattery(input)) if input.value == 42
+    )));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_lego_controller_detach_reports() -> Result<(), Box<dyn Error>> {
+    let mut detached = neutral_report();
+    detached[8] = 0x00; // Right controller mode
+    detached[13] = 0x00;
+    let transport = MockTransport::new(vec![neutral_report(), detached, neutral_report()]);
+    let mut driver = Driver::from_transport(Box::new(transport));
+
+    assert!(input_events(driver.poll()?).is_empty());
+
+    let events = input_events(driver.poll()?);
+    assert!(events.iter().any(|event| matches!(
+        event,
+        Event::Status(StatusEvent::RightControllerConnected(input)) if !input.pressed
+    )));
+    assert!(!events.iter().any(|event| matches!(
+        event,
+        Event::Status(StatusEvent::LeftControllerConnected(_))
+    )));
+
+    let events = input_events(driver.poll()?);
+    assert!(events.iter().any(|event| matches!(
+        event,
+        Event::Status(StatusEvent::RightControllerConnected(input)) if input.pressed
+    )));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_lego_fps_mode_rejects_gamepad_reports() -> Result<(), Box<dyn Error>> {
+    let mut fps_mode = neutral_report();
+    fps_mode[9] = 2;
+    let mut pressed = fps_mode.clone();
+    pressed[19] = 0x80; // A
+    let transport = MockTransport::new(vec![fps_mode, pressed]);
+    let mut driver = Driver::from_transport(Box::new(transport));
+
+    driver.poll()?;
+    assert!(driver.poll()?.is_empty());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_lego_dinput_left_reports() -> Result<(), Box<dyn Error>> {
+    let mut neutral = vec![0; 13];
+    neutral[0] = DINPUT_LEFT_DATA;
+    neutral[1..=7].copy_from_slice(&[0x00, 0x08, 0x80, 0x00, 0x08, 0x80, 0x08]);
+    let mut report = neutral.clone();
+    report[1..=3].copy_from_slice(&[0x00, 0xf0, 0x9e]); // Left stick up
+    report[8] = 0x01; // DPad left
+    let transport = MockTransport::new(vec![neutral, report]);
+    let mut driver = Driver::from_transport(Box::new(transport));
+
+    assert!(driver.poll()?.is_empty());
+    let events = driver.poll()?;
+    assert_eq!(events.len(), 2);
+    assert!(events.iter().any(|event| matches!(
+        event,
+        Event::Button(ButtonEvent::DPadLeft(input)) if input.pressed
+    )));
+    assert!(events.iter().any(|event| matches!(
+        event,
+        Event::Axis(AxisEvent::LStick(input)) if input.y == 0x00 && input.x == 0x61
+    )));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_lego_keyboard_reports() -> Result<(), Box<dyn Error>> {
+    let mut neutral = vec![0; 15];
+    neutral[0] = KEYBOARD_TOUCH_DATA;
+    let mut report = neutral.clone();
+    report[1] = 0x02; // Left trigger
+    report[9..=11].copy_from_slice(&[0x1a, 0x04, 0x2c]); // W, A, Spacebar
+    let transport = MockTransport::new(vec![neutral, report]);
+    let mut driver = Driver::from_transport(Box::new(transport));
+
+    assert!(driver.poll()?.is_empty());
+    let events = driver.poll()?;
+    assert_eq!(events.len(), 4);
+    assert!(events.iter().any(|event| matches!(
+        event,
+        Event::Button(ButtonEvent::DTriggerL(input)) if input.pressed
+    )));
+    assert!(events.iter().any(|event| matches!(
+        event,
+        Event::Trigger(TriggerEvent::ATriggerL(input)) if input.value == 255
+    )));
+    assert!(events.iter().any(|event| matches!(
+        event,
+        Event::Button(ButtonEvent::Y1(input)) if input.pressed
+    )));
+    assert!(events.iter().any(|event| matches!(
+        event,
+        Event::Axis(AxisEvent::LStick(input)) if input.x == 0 && input.y == 0
+    )));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_lego_mouse_reports() -> Result<(), Box<dyn Error>> {
+    let mut report = vec![0; 7];
+    report[0] = MOUSE_DATA;
+    report[2..=4].copy_from_slice(&[0xfb, 0xef, 0xff]); // X: -5, Y: -2
+    report[5] = 0xff; // Wheel down
+    let transport = MockTransport::new(vec![report.clone(), report]);
+    let mut driver = Driver::from_transport(Box::new(transport));
+
+    // Relative motion is emitted with every report, even if it is unchanged
+    for _ in 0..2 {
+        let events = driver.poll()?;
+        assert_eq!(events.len(), 2);
+        assert!(events.iter().any(|event| matches!(
+            event,
+            Event::Axis(AxisEvent::Mouse(input)) if input.x == -5 && input.y == -2
+        )));
+        assert!(events.iter().any(|event| matches!(
+            event,
+            Event::Trigger(TriggerEvent::MouseWheel(input)) if input.value == -1
+        )));
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_lego_multitouch_reports() -> Result<(), Box<dyn Error>> {
+    let mut two_fingers = vec![0; 20];
+    two_fingers[0] = KEYBOARD_TOUCH_DATA;
+    two_fingers[1..=5].copy_from_slice(&[0x01, 0x55, 0x01, 0x00, 0x02]); // Id 0: 341, 512
+    two_fingers[6..=10].copy_from_slice(&[0x05, 0xaa, 0x02, 0x00, 0x02]); // Id 1: 682, 512
+    two_fingers[18] = 2; // Contact count
+    let mut first_finger = two_fingers.clone();
+    first_finger[18] = 1;
+    let mut second_finger = two_fingers.clone();
+    second_finger[1..=5].copy_from_slice(&two_fingers[6..=10]);
+    second_finger[18] = 1;
+    let transport = MockTransport::new(vec![
+        first_finger.clone(),
+        first_finger,
+        two_fingers,
+        second_finger,
+    ]);
+    let mut driver = Driver::from_transport(Box::new(transport));
+
+    driver.poll()?;
+    driver.poll()?;
+    let events = driver.poll()?;
+    assert_eq!(events.len(), 2);
+    assert!(events.iter().any(|event| matches!(
+        event,
+        Event::Axis(AxisEvent::Touchpad(input))
+            if input.index == 1 && input.is_touching && input.x == 682 && input.y == 512
+    )));
+
+    // Lifting the first finger releases only its contact
+    let events = driver.poll()?;
+    assert_eq!(events.len(), 2);
+    assert!(events.iter().any(|event| matches!(
+        event,
+        Event::Axis(AxisEvent::Touchpad(input)) if input.index == 0 && !input.is_touching
+    )));
+    assert!(events.iter().any(|event| matches!(
+        event,
+        Event::Axis(AxisEvent::Touchpad(input)) if input.index == 1 && input.is_touching
+    )));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_lego_rumble_report() -> Result<(), Box<dyn Error>> {
+    let transport = Arc::new(MockTransport::new(vec![]));
+    let mut driver = Driver::from_transport(Box::new(transport.clone()));
+
+    driver.haptic_rumble(0xff, 0x40)?;
+
+    let written = transport.written();
+    assert_eq!(written.len(), 1);
+    assert_eq!(written[0].len(), 9);
+    assert_eq!(written[0][0], XINPUT_DATA);
+    assert_eq!(written[0][4..=5], [0xff, 0x40]);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_lego_touchpad_config_report() -> Result<(), Box<dyn Error>> {
+    let transport = Arc::new(MockTransport::new(vec![]));
+    let driver = Driver::from_transport(Box::new(transport.clone()));
+
+    driver.set_touchpad_enabled(false)?;
+
+    let written = transport.written();
+    assert_eq!(written.len(), 1);
+    assert_eq!(written[0], [0x05, 0x06, 0x6b, 0x02, 0x04, 0x00, 0x01]);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_lego_invalid_report_size() {
+    let transport = MockTransport::new(vec![vec![XINPUT_DATA, 0x00, 0x00]]);
+    let mut driver = Driver::from_transport(Box::new(transport));
+
+    assert!(driver.poll().is_err());
+}
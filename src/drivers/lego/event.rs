@@ -166,7 +166,9 @@ pub enum StatusEvent {
     LeftControllerBattery(StatusInput),
     LeftControllerMode0(StatusInput),
     LeftControllerMode1(StatusInput),
+    LeftControllerConnected(BinaryInput),
     RightControllerBattery(StatusInput),
     RightControllerMode0(StatusInput),
     RightControllerMode1(StatusInput),
+    RightControllerConnected(BinaryInput),
 }
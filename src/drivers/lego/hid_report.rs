@@ -102,10 +102,14 @@ impl ReportType {
 // Somehow this has pitch, roll, and yaw in two 8 bit numbers. Perhaps it is incomplete (only two), or each
 // number is 5 bits? Reslution of 31 is really low. bytes 29 and 30 are left controller, 31 and 32
 // are right controller.
+// The values are offset binary centered at 0x80 (see the Left/Right Accel captures below). This
+// report does not contain a Z axis or any angular velocity, so full 6-axis IMU support needs the
+// dedicated IMU reports of the controllers, which have not been captured yet.
 // Also note bytes 6, 8, 11, and 12. These change when the controller is synced either docked
 // or wireless and indicate the mode for each side. 6 and 11 correspond to if the left
 // controller is docked 6:4, 11:2 or wireless 6:1, 11:3. 7 and 12 are for the right controller,
-// same numbers.
+// same numbers. A side reporting 0 in its mode byte is treated as having no controller
+// connected.
 //
 // Battery Indicators
 // 5 and 7 are left/right controller battery level, 0-100
@@ -479,8 +483,36 @@ pub struct XInputDataReport {
 pub struct KeyboardDataReport {
     #[packed_field(bytes = "0")]
     pub report_id: u8,
-    #[packed_field(bytes = "1")]
-    pub report_size: u8,
+
+    // Modifiers
+    #[packed_field(bits = "8")]
+    pub right_gui: bool,
+    #[packed_field(bits = "9")]
+    pub right_alt: bool,
+    #[packed_field(bits = "10")]
+    pub right_shift: bool,
+    #[packed_field(bits = "11")]
+    pub right_ctrl: bool,
+    #[packed_field(bits = "12")]
+    pub left_gui: bool,
+    #[packed_field(bits = "13")]
+    pub left_alt: bool,
+    #[packed_field(bits = "14")]
+    pub left_shift: bool,
+    #[packed_field(bits = "15")]
+    pub left_ctrl: bool,
+
+    // Keys
+    #[packed_field(bytes = "3..=14")]
+    pub keys: [u8; 12],
+}
+
+impl KeyboardDataReport {
+    /// Returns true if the given HID keyboard usage is pressed in any of the
+    /// key slots
+    pub fn is_pressed(&self, key: u8) -> bool {
+        key != 0 && self.keys.contains(&key)
+    }
 }
 
 //
@@ -550,32 +582,40 @@ pub struct KeyboardDataReport {
 pub struct DInputDataLeftReport {
     #[packed_field(bytes = "0")]
     pub report_id: u8,
-    #[packed_field(bytes = "1")]
-    pub report_size: u8,
 
     // Axes
-    #[packed_field(bits = "16..=27", endian = "lsb")]
-    pub l_stick_y: Integer<u16, packed_bits::Bits<12>>,
-    #[packed_field(bits = "28..=39", endian = "lsb")]
-    pub l_stick_x: Integer<u16, packed_bits::Bits<12>>,
+    // The 12-bit X and Y axes are packed into three bytes with X in the first
+    // byte and low nibble of the second byte. Use [DInputDataLeftReport::l_stick]
+    // to unpack them.
+    #[packed_field(bytes = "1..=3")]
+    pub l_stick_axes: [u8; 3],
 
     // Buttons
-    #[packed_field(bits = "72")]
-    pub left: bool,
-    #[packed_field(bits = "73")]
-    pub down: bool,
-    #[packed_field(bits = "74")]
-    pub up: bool,
-    #[packed_field(bits = "75")]
-    pub right: bool,
-    #[packed_field(bits = "76")]
-    pub y1: bool,
-    #[packed_field(bits = "77")]
-    pub y2: bool,
-    #[packed_field(bits = "78")]
-    pub select: bool,
-    #[packed_field(bits = "79")]
+    #[packed_field(bits = "64")]
     pub start: bool,
+    #[packed_field(bits = "65")]
+    pub select: bool,
+    #[packed_field(bits = "66")]
+    pub y2: bool,
+    #[packed_field(bits = "67")]
+    pub y1: bool,
+    #[packed_field(bits = "68")]
+    pub right: bool,
+    #[packed_field(bits = "69")]
+    pub up: bool,
+    #[packed_field(bits = "70")]
+    pub down: bool,
+    #[packed_field(bits = "71")]
+    pub left: bool,
+}
+
+impl DInputDataLeftReport {
+    /// Returns the raw HID (X, Y) values of the stick from 0 to 4095. The
+    /// controller is held sideways, so HID X is the vertical axis (up is 0)
+    /// and HID Y is the inverted horizontal axis (right is 0).
+    pub fn l_stick(&self) -> (u16, u16) {
+        unpack_dinput_axes(self.l_stick_axes)
+    }
 }
 
 // DInputDataRight
@@ -641,32 +681,46 @@ pub struct DInputDataLeftReport {
 pub struct DInputDataRightReport {
     #[packed_field(bytes = "0")]
     pub report_id: u8,
-    #[packed_field(bytes = "1")]
-    pub report_size: u8,
 
     // Axes
-    #[packed_field(bits = "16..=27", endian = "lsb")]
-    pub r_stick_y: Integer<u16, packed_bits::Bits<12>>,
-    #[packed_field(bits = "28..=39", endian = "lsb")]
-    pub r_stick_x: Integer<u16, packed_bits::Bits<12>>,
+    // Packed the same way as [DInputDataLeftReport::l_stick_axes]. Use
+    // [DInputDataRightReport::r_stick] to unpack them.
+    #[packed_field(bytes = "1..=3")]
+    pub r_stick_axes: [u8; 3],
 
     // Buttons
-    #[packed_field(bits = "72")]
-    pub b: bool,
-    #[packed_field(bits = "73")]
-    pub y: bool,
-    #[packed_field(bits = "74")]
-    pub a: bool,
-    #[packed_field(bits = "75")]
-    pub x: bool,
-    #[packed_field(bits = "76")]
-    pub y3: bool,
-    #[packed_field(bits = "77")]
-    pub m3: bool,
-    #[packed_field(bits = "78")]
-    pub m2: bool,
-    #[packed_field(bits = "79")]
+    #[packed_field(bits = "64")]
     pub quick_access: bool,
+    #[packed_field(bits = "65")]
+    pub m2: bool,
+    #[packed_field(bits = "66")]
+    pub m3: bool,
+    #[packed_field(bits = "67")]
+    pub y3: bool,
+    #[packed_field(bits = "68")]
+    pub x: bool,
+    #[packed_field(bits = "69")]
+    pub a: bool,
+    #[packed_field(bits = "70")]
+    pub y: bool,
+    #[packed_field(bits = "71")]
+    pub b: bool,
+}
+
+impl DInputDataRightReport {
+    /// Returns the raw HID (X, Y) values of the stick from 0 to 4095. The
+    /// controller is held sideways, so HID X is the inverted vertical axis
+    /// (up is 4095) and HID Y is the horizontal axis (right is 4095).
+    pub fn r_stick(&self) -> (u16, u16) {
+        unpack_dinput_axes(self.r_stick_axes)
+    }
+}
+
+/// Unpack the two 12-bit little endian axis values of a DInput report
+fn unpack_dinput_axes(bytes: [u8; 3]) -> (u16, u16) {
+    let x = bytes[0] as u16 | ((bytes[1] as u16 & 0x0f) << 8);
+    let y = (bytes[1] as u16 >> 4) | ((bytes[2] as u16) << 4);
+    (x, y)
 }
 
 // MouseDataFPS, MouseData
@@ -754,16 +808,25 @@ pub struct MouseDataReport {
     pub m1: bool,
 
     // Axes
-    #[packed_field(bits = "16..=27", endian = "msb")]
-    pub mouse_x: Integer<i16, packed_bits::Bits<12>>,
-    #[packed_field(bits = "28..=39", endian = "msb")]
-    pub mouse_y: Integer<i16, packed_bits::Bits<12>>,
+    // The signed 12-bit X and Y axes are packed the same way as the DInput
+    // stick axes. Use [MouseDataReport::mouse_motion] to unpack them.
+    #[packed_field(bytes = "2..=4")]
+    pub mouse_axes: [u8; 3],
     #[packed_field(bytes = "5")]
     pub mouse_z: i8,
     #[packed_field(bytes = "6")]
     pub report_count: u8,
 }
 
+impl MouseDataReport {
+    /// Returns the relative (X, Y) motion of the optical sensor
+    pub fn mouse_motion(&self) -> (i16, i16) {
+        let (x, y) = unpack_dinput_axes(self.mouse_axes);
+        // Sign extend the 12-bit values
+        (((x << 4) as i16) >> 4, ((y << 4) as i16) >> 4)
+    }
+}
+
 // TouchpadData
 //
 // X Axis
@@ -796,12 +859,8 @@ pub struct TouchpadDataReport {
     pub unk_1_9: bool,
     #[packed_field(bits = "10")]
     pub unk_1_10: bool,
-    #[packed_field(bits = "11")]
-    pub unk_1_11: bool,
-    #[packed_field(bits = "12")]
-    pub unk_1_12: bool,
-    #[packed_field(bits = "13")]
-    pub contact_id_0: bool,
+    #[packed_field(bits = "11..=13")]
+    pub contact_id_0: Integer<u8, packed_bits::Bits<3>>,
     #[packed_field(bits = "14")]
     pub tip_switch_0: bool,
     #[packed_field(bits = "15")]
@@ -819,12 +878,8 @@ pub struct TouchpadDataReport {
     pub unk_6_1: bool,
     #[packed_field(bits = "50")]
     pub unk_6_2: bool,
-    #[packed_field(bits = "51")]
-    pub unk_6_3: bool,
-    #[packed_field(bits = "52")]
-    pub unk_6_4: bool,
-    #[packed_field(bits = "53")]
-    pub contact_id_1: bool,
+    #[packed_field(bits = "51..=53")]
+    pub contact_id_1: Integer<u8, packed_bits::Bits<3>>,
     #[packed_field(bits = "54")]
     pub tip_switch_1: bool,
     #[packed_field(bits = "55")]
@@ -842,12 +897,8 @@ pub struct TouchpadDataReport {
     pub unk_11_1: bool,
     #[packed_field(bits = "90")]
     pub unk_11_2: bool,
-    #[packed_field(bits = "91")]
-    pub unk_11_3: bool,
-    #[packed_field(bits = "92")]
-    pub unk_11_4: bool,
-    #[packed_field(bits = "93")]
-    pub contact_id_2: bool,
+    #[packed_field(bits = "91..=93")]
+    pub contact_id_2: Integer<u8, packed_bits::Bits<3>>,
     #[packed_field(bits = "94")]
     pub tip_switch_2: bool,
     #[packed_field(bits = "95")]
@@ -881,3 +932,138 @@ pub struct TouchpadDataReport {
     #[packed_field(bits = "159")]
     pub unk_19_7: bool,
 }
+
+impl TouchpadDataReport {
+    /// Returns the (contact id, x, y) of every finger currently touching the
+    /// touchpad
+    pub fn contacts(&self) -> Vec<(u8, u16, u16)> {
+        let slots = [
+            (
+                self.confidence_0,
+                self.contact_id_0,
+                (self.touch_x_0, self.touch_y_0),
+            ),
+            (
+                self.confidence_1,
+                self.contact_id_1,
+                (self.touch_x_1, self.touch_y_1),
+            ),
+            (
+                self.confidence_2,
+                self.contact_id_2,
+                (self.touch_x_2, self.touch_y_2),
+            ),
+        ];
+        slots
+            .into_iter()
+            .take(self.contact_count as usize)
+            .filter(|(confidence, _, _)| *confidence)
+            .map(|(_, id, (x, y))| (id.to_primitive(), x, y))
+            .collect()
+    }
+}
+
+// RumbleOutputData
+//
+// Rumble uses the layout of the XInput rumble packet (00 08 00 <left> <right> 00 00 00) prefixed
+// with the XInput report ID. The left motor is the strong (low frequency) motor and the right
+// motor is the weak (high frequency) motor.
+#[derive(PackedStruct, Debug, Copy, Clone, PartialEq)]
+#[packed_struct(bit_numbering = "msb0", size_bytes = "9")]
+pub struct RumbleOutputDataReport {
+    #[packed_field(bytes = "0")]
+    pub report_id: u8,
+    #[packed_field(bytes = "1")]
+    pub unk_1: u8,
+    #[packed_field(bytes = "2")]
+    pub report_size: u8,
+    #[packed_field(bytes = "3")]
+    pub unk_3: u8,
+    #[packed_field(bytes = "4")]
+    pub left_motor_speed: u8,
+    #[packed_field(bytes = "5")]
+    pub right_motor_speed: u8,
+    #[packed_field(bytes = "6")]
+    pub unk_6: u8,
+    #[packed_field(bytes = "7")]
+    pub unk_7: u8,
+    #[packed_field(bytes = "8")]
+    pub unk_8: u8,
+}
+
+impl RumbleOutputDataReport {
+    pub fn new() -> Self {
+        Self {
+            report_id: ReportType::XInputData as u8,
+            unk_1: 0,
+            report_size: 8,
+            unk_3: 0,
+            left_motor_speed: 0,
+            right_motor_speed: 0,
+            unk_6: 0,
+            unk_7: 0,
+            unk_8: 0,
+        }
+    }
+}
+
+impl Default for RumbleOutputDataReport {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// ConfigOutputData
+//
+// Configuration commands share a common layout: 05 <size> <command> 02 <target> <value> 01
+// where size is the number of bytes following it and target selects the controller or
+// peripheral the command applies to.
+//
+// Touchpad enable/disable
+// 05 06 6b 02 04 01 01
+// 05 06 6b 02 04 00 01
+
+/// Configuration commands
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum ConfigCommand {
+    TouchpadEnable = 0x6b,
+}
+
+/// Targets of configuration commands
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum ConfigTarget {
+    Touchpad = 0x04,
+}
+
+#[derive(PackedStruct, Debug, Copy, Clone, PartialEq)]
+#[packed_struct(bit_numbering = "msb0", size_bytes = "7")]
+pub struct ConfigOutputDataReport {
+    #[packed_field(bytes = "0")]
+    pub report_id: u8,
+    #[packed_field(bytes = "1")]
+    pub report_size: u8,
+    #[packed_field(bytes = "2")]
+    pub command: u8,
+    #[packed_field(bytes = "3")]
+    pub unk_3: u8,
+    #[packed_field(bytes = "4")]
+    pub target: u8,
+    #[packed_field(bytes = "5")]
+    pub value: u8,
+    #[packed_field(bytes = "6")]
+    pub unk_6: u8,
+}
+
+impl ConfigOutputDataReport {
+    pub fn new(command: ConfigCommand, target: ConfigTarget, value: u8) -> Self {
+        Self {
+            report_id: 0x05,
+            report_size: 6,
+            command: command as u8,
+            unk_3: 0x02,
+            target: target as u8,
+            value,
+            unk_6: 0x01,
+        }
+    }
+}
@@ -0,0 +1,64 @@
+use std::{
+    collections::VecDeque,
+    error::Error,
+    sync::{Arc, Mutex},
+};
+
+use super::transport::HidTransport;
+
+/// Transport that returns canned input reports and records written output
+/// reports, used to unit test drivers.
+#[derive(Debug, Default)]
+pub struct MockTransport {
+    reports: Mutex<VecDeque<Vec<u8>>>,
+    written: Mutex<Vec<Vec<u8>>>,
+}
+
+impl MockTransport {
+    /// Create a transport that returns the given input reports in order
+    pub fn new(reports: Vec<Vec<u8>>) -> Self {
+        Self {
+            reports: Mutex::new(reports.into()),
+            written: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Returns all output reports written to the transport
+    pub fn written(&self) -> Vec<Vec<u8>> {
+        self.written.lock().unwrap().clone()
+    }
+}
+
+impl HidTransport for MockTransport {
+    fn read_timeout(
+        &self,
+        buf: &mut [u8],
+        _timeout: i32,
+    ) -> Result<usize, Box<dyn Error + Send + Sync>> {
+        let Some(report) = self.reports.lock().unwrap().pop_front() else {
+            return Ok(0);
+        };
+        let size = report.len().min(buf.len());
+        buf[..size].copy_from_slice(&report[..size]);
+        Ok(size)
+    }
+
+    fn write(&self, data: &[u8]) -> Result<usize, Box<dyn Error + Send + Sync>> {
+        self.written.lock().unwrap().push(data.to_vec());
+        Ok(data.len())
+    }
+}
+
+impl HidTransport for Arc<MockTransport> {
+    fn read_timeout(
+        &self,
+        buf: &mut [u8],
+        timeout: i32,
+    ) -> Result<usize, Box<dyn Error + Send + Sync>> {
+        self.as_ref().read_timeout(buf, timeout)
+    }
+
+    fn write(&self, data: &[u8]) -> Result<usize, Box<dyn Error + Send + Sync>> {
+        self.as_ref().write(data)
+    }
+}
@@ -1,4 +1,19 @@
+pub mod audio_haptics;
+#[cfg(test)]
+mod audio_haptics_test;
 pub mod dualsense;
+pub mod dualshock4;
+pub mod generic_hid;
 pub mod iio_imu;
 pub mod lego;
+#[cfg(test)]
+pub mod mock_transport;
+pub mod replay;
+pub mod rog_ally;
+pub mod steam_controller;
 pub mod steam_deck;
+pub mod switch_pro;
+pub mod transport;
+#[cfg(test)]
+mod transport_test;
+pub mod wooting;
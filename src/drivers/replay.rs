@@ -0,0 +1,226 @@
+use std::{error::Error, fs, thread, time::Duration};
+
+use uhid_virt::{Bus, CreateParams, OutputEvent, UHIDDevice};
+
+use crate::{
+    config::ReplayDeviceConfig,
+    drivers::{lego, steam_deck},
+};
+
+/// Serial number prefix of replay devices. Composite device configs can match
+/// this to use replay devices instead of real hardware.
+pub const SERIAL_PREFIX: &str = "inputplumber-replay";
+
+/// Generic vendor defined report descriptor with 64 byte input and output
+/// reports, used for devices without a known report descriptor.
+const VENDOR_DESCRIPTOR: [u8; 34] = [
+    0x06, 0x00, 0xff, // Usage Page (Vendor Defined 0xFF00)
+    0x09, 0x01, // Usage (0x01)
+    0xa1, 0x01, // Collection (Application)
+    0x15, 0x00, //  Logical Minimum (0)
+    0x26, 0xff, 0x00, //  Logical Maximum (255)
+    0x75, 0x08, //  Report Size (8)
+    0x95, 0x40, //  Report Count (64)
+    0x09, 0x01, //  Usage (0x01)
+    0x81, 0x02, //  Input (Data,Var,Abs)
+    0x95, 0x40, //  Report Count (64)
+    0x09, 0x01, //  Usage (0x01)
+    0x91, 0x02, //  Output (Data,Var,Abs)
+    0x09, 0x01, //  Usage (0x01)
+    0xb1, 0x02, //  Feature (Data,Var,Abs)
+    0xc0, // End Collection
+];
+
+/// A single recorded input report
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecordedReport {
+    /// Time to wait since the previous report
+    pub delay: Duration,
+    pub data: Vec<u8>,
+}
+
+/// A [ReplayDevice] is a virtual handheld that replays a recorded report
+/// stream of a supported device over a UHID device. The kernel exposes it as
+/// a regular hidraw device, so it goes through the same source drivers and
+/// composite devices as the real hardware would.
+///
+/// Recordings are text files with one report per line, starting with the
+/// delay in milliseconds since the previous report followed by the report
+/// bytes in hex. Empty lines and lines starting with '#' are ignored.
+///
+/// ```text
+/// # delay_ms report
+/// 4 01000109400000000000...
+/// ```
+#[derive(Debug)]
+pub struct ReplayDevice {
+    config: ReplayDeviceConfig,
+    serial: String,
+}
+
+impl ReplayDevice {
+    pub fn new(config: ReplayDeviceConfig, index: usize) -> Self {
+        let serial = format!("{SERIAL_PREFIX}-{index}");
+        Self { config, serial }
+    }
+
+    /// Load all replay device configs. Replay devices are only created if a
+    /// config was added to one of the replay config directories.
+    pub fn load_all() -> Vec<ReplayDevice> {
+        let paths = vec![
+            "./rootfs/usr/share/inputplumber/replay",
+            "/etc/inputplumber/replay.d",
+            "/usr/share/inputplumber/replay",
+        ];
+        let mut devices = Vec::new();
+        for path in paths {
+            let Ok(files) = fs::read_dir(path) else {
+                log::trace!("Failed to load directory {path}");
+                continue;
+            };
+            let mut files: Vec<_> = files.filter_map(|file| file.ok()).collect();
+            files.sort_by_key(|file| file.file_name());
+
+            for file in files {
+                let path = file.path().display().to_string();
+                if !path.ends_with(".yaml") {
+                    continue;
+                }
+                match ReplayDeviceConfig::from_yaml_file(path.clone()) {
+                    Ok(config) => devices.push(ReplayDevice::new(config, devices.len())),
+                    Err(e) => log::warn!("Failed to parse replay device config {path}: {e}"),
+                }
+            }
+        }
+        devices
+    }
+
+    /// Create the virtual device and replay the recording in a blocking thread
+    pub fn spawn(self) {
+        tokio::task::spawn_blocking(move || {
+            if let Err(e) = self.run() {
+                log::error!("Failed to run replay device {}: {e:?}", self.config.name);
+            }
+        });
+    }
+
+    /// Create the virtual device and replay the recording until it ends
+    fn run(&self) -> Result<(), Box<dyn Error>> {
+        let reports = load_recording(&self.config.recording)?;
+        if reports.is_empty() {
+            return Err(format!("No reports found in {}", self.config.recording).into());
+        }
+        let repeat = self.config.repeat.unwrap_or(false);
+
+        log::info!(
+            "Replaying {} as {} from {}",
+            self.config.name,
+            self.config.device,
+            self.config.recording
+        );
+        let mut device = self.create_virtual_device()?;
+
+        // Give udev and the manager time to pick up the new hidraw device
+        thread::sleep(Duration::from_secs(1));
+
+        'replay: loop {
+            for report in reports.iter() {
+                thread::sleep(report.delay);
+                drain_output_events(&mut device);
+                if let Err(e) = device.write(&report.data) {
+                    log::error!("Failed to write replayed report: {e:?}");
+                    break 'replay;
+                }
+            }
+            if !repeat {
+                break;
+            }
+        }
+
+        log::debug!("Finished replaying {}", self.config.name);
+        device.destroy()?;
+
+        Ok(())
+    }
+
+    /// Create the UHID device emulating the configured device
+    fn create_virtual_device(&self) -> Result<UHIDDevice<fs::File>, Box<dyn Error>> {
+        let (vendor, product, rd_data) = match self.config.device.as_str() {
+            "steam_deck" => (
+                steam_deck::driver::VID,
+                steam_deck::driver::PID,
+                steam_deck::report_descriptor::CONTROLLER_DESCRIPTOR.to_vec(),
+            ),
+            "legion_go" => (
+                lego::driver::VID,
+                lego::driver::PID,
+                VENDOR_DESCRIPTOR.to_vec(),
+            ),
+            device => return Err(format!("Unsupported replay device: {device}").into()),
+        };
+
+        let device = UHIDDevice::create(CreateParams {
+            name: self.config.name.clone(),
+            phys: String::from(""),
+            uniq: self.serial.clone(),
+            bus: Bus::USB,
+            vendor: vendor as u32,
+            product: product as u32,
+            version: 0,
+            country: 0,
+            rd_data,
+        })?;
+
+        Ok(device)
+    }
+}
+
+/// Answer any pending requests from drivers writing to the virtual device so
+/// they do not block waiting for a reply.
+fn drain_output_events(device: &mut UHIDDevice<fs::File>) {
+    while let Ok(event) = device.read() {
+        match event {
+            OutputEvent::Output { data } => {
+                log::trace!("Replay device got output data: {data:?}");
+            }
+            OutputEvent::GetReport { id, .. } => {
+                let _ = device.write_get_report_reply(id, 0, vec![]);
+            }
+            OutputEvent::SetReport { id, .. } => {
+                let _ = device.write_set_report_reply(id, 0);
+            }
+            _ => (),
+        }
+    }
+}
+
+/// Load the recorded reports from the given file
+pub fn load_recording(path: &str) -> Result<Vec<RecordedReport>, Box<dyn Error>> {
+    let content = fs::read_to_string(path)?;
+    parse_recording(&content)
+}
+
+/// Parse recorded reports in the format of "<delay_ms> <hex bytes>" per line
+pub fn parse_recording(content: &str) -> Result<Vec<RecordedReport>, Box<dyn Error>> {
+    let mut reports = Vec::new();
+    for (i, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let Some((delay, hex)) = line.split_once(char::is_whitespace) else {
+            return Err(format!("Invalid report on line {}", i + 1).into());
+        };
+        let delay = Duration::from_millis(delay.parse()?);
+        let hex: String = hex.split_whitespace().collect();
+        if !hex.is_ascii() || hex.len() % 2 != 0 {
+            return Err(format!("Invalid report bytes on line {}", i + 1).into());
+        }
+        let data = (0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&hex[i..i + 2], 16))
+            .collect::<Result<Vec<u8>, _>>()?;
+        reports.push(RecordedReport { delay, data });
+    }
+    Ok(reports)
+}
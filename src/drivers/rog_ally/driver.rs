@@ -0,0 +1,113 @@
+use std::{error::Error, ffi::CString, time::Instant};
+
+use packed_struct::PackedStruct;
+
+use crate::drivers::transport::{HidTransport, QueuedTransport};
+
+use super::{
+    event::{BinaryInput, ButtonEvent, Event},
+    hid_report::{
+        VendorInputDataReport, KEY_ARMOURY_CRATE, KEY_ARMOURY_CRATE_LONG, KEY_COMMAND_CENTER,
+        VENDOR_REPORT_ID,
+    },
+};
+
+// Hardware ID's
+pub const VID: u16 = 0x0b05;
+pub const PID: u16 = 0x1abe;
+// Input report sizes
+const PACKET_SIZE: usize = 64;
+const VENDOR_PACKET_SIZE: usize = 2;
+const HID_TIMEOUT: i32 = 10;
+
+pub struct Driver {
+    /// State of the vendor key report
+    vendor_state: Option<VendorInputDataReport>,
+    /// HIDRAW device instance
+    device: Box<dyn HidTransport>,
+    /// Time the last input report was read from the device
+    last_report: Instant,
+}
+
+impl Driver {
+    pub fn new(path: String) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        let fmtpath = path.clone();
+        let path = CString::new(path)?;
+        let api = hidapi::HidApi::new()?;
+        let device = api.open_path(&path)?;
+        let info = device.get_device_info()?;
+        if info.vendor_id() != VID || info.product_id() != PID {
+            return Err(format!("Device '{fmtpath}' is not a ROG Ally MCU").into());
+        }
+
+        Ok(Self::from_transport(Box::new(QueuedTransport::new(device))))
+    }
+
+    /// Create a driver that exchanges reports over the given transport
+    pub fn from_transport(device: Box<dyn HidTransport>) -> Self {
+        Self {
+            vendor_state: None,
+            device,
+            last_report: Instant::now(),
+        }
+    }
+
+    /// Poll the device and read input reports
+    pub fn poll(&mut self) -> Result<Vec<Event>, Box<dyn Error + Send + Sync>> {
+        // Read data from the device into a buffer
+        let mut buf = [0; PACKET_SIZE];
+        let bytes_read = self.device.read_timeout(&mut buf[..], HID_TIMEOUT)?;
+        self.last_report = Instant::now();
+
+        // Only the vendor reports carry the extra buttons, the other reports
+        // of the MCU are already handled by the kernel.
+        if bytes_read < VENDOR_PACKET_SIZE || buf[0] != VENDOR_REPORT_ID {
+            return Ok(vec![]);
+        }
+        let input_report = VendorInputDataReport::unpack(&[buf[0], buf[1]])?;
+
+        // Update the state
+        let old_state = self.vendor_state.replace(input_report);
+
+        // Translate the state into a stream of input events
+        Ok(self.translate_vendor(old_state))
+    }
+
+    /// Returns the time the last input report was read from the device
+    pub fn last_report_time(&self) -> Instant {
+        self.last_report
+    }
+
+    /// Translate the state into individual events
+    fn translate_vendor(&self, old_state: Option<VendorInputDataReport>) -> Vec<Event> {
+        let mut events = Vec::new();
+        let Some(state) = self.vendor_state else {
+            return events;
+        };
+        let old_key = old_state.map(|state| state.key_code).unwrap_or_default();
+        if state.key_code == old_key {
+            return events;
+        }
+
+        // Release the previously held key before pressing the new one
+        if let Some(event) = button_event(old_key, false) {
+            events.push(Event::Button(event));
+        }
+        if let Some(event) = button_event(state.key_code, true) {
+            events.push(Event::Button(event));
+        }
+
+        events
+    }
+}
+
+/// Returns the button event for the given vendor key code, if it is known
+fn button_event(key_code: u8, pressed: bool) -> Option<ButtonEvent> {
+    let input = BinaryInput { pressed };
+    match key_code {
+        KEY_ARMOURY_CRATE => Some(ButtonEvent::ArmouryCrate(input)),
+        KEY_ARMOURY_CRATE_LONG => Some(ButtonEvent::ArmouryCrateLong(input)),
+        KEY_COMMAND_CENTER => Some(ButtonEvent::CommandCenter(input)),
+        _ => None,
+    }
+}
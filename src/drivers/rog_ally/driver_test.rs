@@ -0,0 +1,59 @@
+use std::error::Error;
+
+use crate::drivers::{
+    mock_transport::MockTransport,
+    rog_ally::{
+        driver::Driver,
+        event::{ButtonEvent, Event},
+        hid_report::{KEY_ARMOURY_CRATE, KEY_COMMAND_CENTER, VENDOR_REPORT_ID},
+    },
+};
+
+/// Returns a vendor report holding the given key code
+fn vendor_report(key_code: u8) -> Vec<u8> {
+    let mut report = vec![0; 6];
+    report[0] = VENDOR_REPORT_ID;
+    report[1] = key_code;
+    report
+}
+
+#[tokio::test]
+async fn test_rog_ally_vendor_reports() -> Result<(), Box<dyn Error>> {
+    let transport = MockTransport::new(vec![
+        vendor_report(KEY_ARMOURY_CRATE),
+        vendor_report(KEY_COMMAND_CENTER),
+        vendor_report(0),
+        vec![0x01, KEY_ARMOURY_CRATE],
+    ]);
+    let mut driver = Driver::from_transport(Box::new(transport));
+
+    let events = driver.poll()?;
+    assert_eq!(events.len(), 1);
+    assert!(matches!(
+        events[0],
+        Event::Button(ButtonEvent::ArmouryCrate(ref input)) if input.pressed
+    ));
+
+    let events = driver.poll()?;
+    assert_eq!(events.len(), 2);
+    assert!(matches!(
+        events[0],
+        Event::Button(ButtonEvent::ArmouryCrate(ref input)) if !input.pressed
+    ));
+    assert!(matches!(
+        events[1],
+        Event::Button(ButtonEvent::CommandCenter(ref input)) if input.pressed
+    ));
+
+    let events = driver.poll()?;
+    assert_eq!(events.len(), 1);
+    assert!(matches!(
+        events[0],
+        Event::Button(ButtonEvent::CommandCenter(ref input)) if !input.pressed
+    ));
+
+    // Reports other than the vendor report are ignored
+    assert!(driver.poll()?.is_empty());
+
+    Ok(())
+}
@@ -0,0 +1,22 @@
+/// Events that can be emitted by the ROG Ally MCU
+#[derive(Clone, Debug)]
+pub enum Event {
+    Button(ButtonEvent),
+}
+
+/// Binary input contain either pressed or unpressed
+#[derive(Clone, Debug)]
+pub struct BinaryInput {
+    pub pressed: bool,
+}
+
+/// Button events represent binary inputs
+#[derive(Clone, Debug)]
+pub enum ButtonEvent {
+    /// Armoury Crate button on the right side of the screen
+    ArmouryCrate(BinaryInput),
+    /// Long press of the Armoury Crate button
+    ArmouryCrateLong(BinaryInput),
+    /// Command Center button on the left side of the screen
+    CommandCenter(BinaryInput),
+}
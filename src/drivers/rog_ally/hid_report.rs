@@ -0,0 +1,23 @@
+use packed_struct::prelude::*;
+
+/// Report ID of the ASUS vendor reports sent by the N-KEY MCU
+pub const VENDOR_REPORT_ID: u8 = 0x5a;
+
+/// Vendor key codes sent by the MCU. These are the codes the kernel hid-asus
+/// driver translates into KEY_PROG1, KEY_F16 and KEY_F17.
+pub const KEY_ARMOURY_CRATE: u8 = 0x38;
+pub const KEY_COMMAND_CENTER: u8 = 0xa6;
+pub const KEY_ARMOURY_CRATE_LONG: u8 = 0xa7;
+
+// VendorInputData
+//
+// The MCU reports the code of the key being held in the first byte after the
+// report ID, and a code of 0 once no key is held.
+#[derive(PackedStruct, Debug, Copy, Clone, PartialEq)]
+#[packed_struct(bit_numbering = "msb0", size_bytes = "2")]
+pub struct VendorInputDataReport {
+    #[packed_field(bytes = "0")]
+    pub report_id: u8,
+    #[packed_field(bytes = "1")]
+    pub key_code: u8,
+}
@@ -0,0 +1,293 @@
+use std::{error::Error, ffi::CString, time::Instant};
+
+use packed_struct::{types::SizedInteger, PackedStruct};
+
+use crate::drivers::{
+    steam_deck::hid_report::{PackedMappingsReport, Register, ReportType},
+    transport::{HidTransport, QueuedTransport},
+};
+
+use super::{
+    event::{
+        AccelerometerEvent, AccelerometerInput, AxisEvent, AxisInput, BinaryInput, ButtonEvent,
+        Event, TouchAxisInput, TriggerEvent, TriggerInput,
+    },
+    hid_report::{
+        PackedInputDataReport, PackedWriteRegistersReport, CONTROLLER_STATE, GYRO_SEND_RAW,
+        TRACKPAD_NONE,
+    },
+};
+
+/// Vendor ID
+pub const VID: u16 = 0x28de;
+/// Product ID of the wired controller
+pub const PID_WIRED: u16 = 0x1102;
+/// Product ID of the wireless dongle
+pub const PID_WIRELESS: u16 = 0x1142;
+/// Size of the HID packet
+const PACKET_SIZE: usize = 64;
+/// Timeout in milliseconds for reading an HID packet
+const HID_TIMEOUT: i32 = 10;
+
+pub struct Driver {
+    state: Option<PackedInputDataReport>,
+    /// Last stick position. The stick and the left trackpad share the same
+    /// axes in the input report, so this is tracked separately.
+    l_stick: (i16, i16),
+    /// Last left trackpad touch state and position
+    l_pad: (bool, i16, i16),
+    device: Box<dyn HidTransport>,
+    /// Time the last input report was read from the device
+    last_report: Instant,
+}
+
+impl Driver {
+    pub fn new(path: String) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        let fmtpath = path.clone();
+        let path = CString::new(path)?;
+        let api = hidapi::HidApi::new()?;
+        let device = api.open_path(&path)?;
+        let info = device.get_device_info()?;
+        if !is_supported(info.vendor_id(), info.product_id()) {
+            return Err(format!("Device '{fmtpath}' is not a Steam Controller").into());
+        }
+
+        Ok(Self::from_transport(Box::new(QueuedTransport::new(device))))
+    }
+
+    /// Create a driver that exchanges reports over the given transport
+    pub fn from_transport(device: Box<dyn HidTransport>) -> Self {
+        Self {
+            state: None,
+            l_stick: (0, 0),
+            l_pad: (false, 0, 0),
+            device,
+            last_report: Instant::now(),
+        }
+    }
+
+    /// Poll the device and read input reports
+    pub fn poll(&mut self) -> Result<Vec<Event>, Box<dyn Error + Send + Sync>> {
+        // Read data from the device into a buffer
+        let mut buf = [0; PACKET_SIZE];
+        let bytes_read = self.device.read_timeout(&mut buf[..], HID_TIMEOUT)?;
+        self.last_report = Instant::now();
+
+        // Only the controller state reports carry input
+        if bytes_read != PACKET_SIZE || buf[2] != CONTROLLER_STATE {
+            return Ok(Vec::new());
+        }
+
+        // Handle the incoming input report
+        let events = self.handle_input_report(buf)?;
+
+        Ok(events)
+    }
+
+    /// Returns the time the last input report was read from the device
+    pub fn last_report_time(&self) -> Instant {
+        self.last_report
+    }
+
+    /// Set lizard mode, which will automatically try to emulate mouse/keyboard
+    /// if enabled.
+    pub fn set_lizard_mode(&self, enabled: bool) -> Result<(), Box<dyn Error + Send + Sync>> {
+        if enabled {
+            // Restore the default keyboard mappings and the trackpad mouse
+            for report_id in [ReportType::DefaultMappings, ReportType::DefaultMouse] {
+                let report = PackedMappingsReport {
+                    report_id: report_id as u8,
+                };
+                self.device.write(&report.pack()?)?;
+            }
+            return Ok(());
+        }
+
+        // Disable the keyboard mappings, then the trackpad mouse
+        let report = PackedMappingsReport {
+            report_id: ReportType::ClearMappings as u8,
+        };
+        self.device.write(&report.pack()?)?;
+        let report = PackedWriteRegistersReport::new(
+            ReportType::WriteRegister as u8,
+            &[
+                (Register::LPadMode as u8, TRACKPAD_NONE),
+                (Register::RPadMode as u8, TRACKPAD_NONE),
+                (Register::RPadMargin as u8, 0),
+            ],
+        );
+        self.device.write(&report.pack()?)?;
+
+        Ok(())
+    }
+
+    /// Enable sending accelerometer and gyro data in the input reports
+    pub fn set_imu_enabled(&self, enabled: bool) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let mode = if enabled { GYRO_SEND_RAW } else { 0 };
+        let report = PackedWriteRegistersReport::new(
+            ReportType::WriteRegister as u8,
+            &[(Register::GyroMode as u8, mode)],
+        );
+        self.device.write(&report.pack()?)?;
+
+        Ok(())
+    }
+
+    /// Unpacks the buffer into a [PackedInputDataReport] structure and updates
+    /// the internal gamepad state
+    fn handle_input_report(
+        &mut self,
+        buf: [u8; PACKET_SIZE],
+    ) -> Result<Vec<Event>, Box<dyn Error + Send + Sync>> {
+        let input_report = PackedInputDataReport::unpack(&buf)?;
+
+        // Update the state
+        let old_state = self.state.replace(input_report);
+
+        // Translate the state into a stream of input events
+        let events = self.translate(old_state);
+
+        Ok(events)
+    }
+
+    /// Translate the state into individual events
+    fn translate(&mut self, old_state: Option<PackedInputDataReport>) -> Vec<Event> {
+        let mut events = Vec::new();
+        let Some(state) = self.state else {
+            return events;
+        };
+        let Some(old_state) = old_state else {
+            return events;
+        };
+
+        // Binary events
+        let buttons: [(bool, bool, fn(BinaryInput) -> ButtonEvent); 22] = [
+            (state.a, old_state.a, ButtonEvent::A),
+            (state.x, old_state.x, ButtonEvent::X),
+            (state.b, old_state.b, ButtonEvent::B),
+            (state.y, old_state.y, ButtonEvent::Y),
+            (state.start, old_state.start, ButtonEvent::Start),
+            (state.back, old_state.back, ButtonEvent::Back),
+            (state.steam, old_state.steam, ButtonEvent::Steam),
+            (state.down, old_state.down, ButtonEvent::DPadDown),
+            (state.up, old_state.up, ButtonEvent::DPadUp),
+            (state.left, old_state.left, ButtonEvent::DPadLeft),
+            (state.right, old_state.right, ButtonEvent::DPadRight),
+            (state.l1, old_state.l1, ButtonEvent::L1),
+            (state.l2, old_state.l2, ButtonEvent::L2),
+            (state.l3, old_state.l3, ButtonEvent::L3),
+            (state.l_grip, old_state.l_grip, ButtonEvent::LGrip),
+            (state.r1, old_state.r1, ButtonEvent::R1),
+            (state.r2, old_state.r2, ButtonEvent::R2),
+            (state.r_grip, old_state.r_grip, ButtonEvent::RGrip),
+            (
+                state.r_pad_touch,
+                old_state.r_pad_touch,
+                ButtonEvent::RPadTouch,
+            ),
+            (
+                state.l_pad_touch,
+                old_state.l_pad_touch,
+                ButtonEvent::LPadTouch,
+            ),
+            (
+                state.r_pad_press,
+                old_state.r_pad_press,
+                ButtonEvent::RPadPress,
+            ),
+            (
+                state.l_pad_press,
+                old_state.l_pad_press,
+                ButtonEvent::LPadPress,
+            ),
+        ];
+        for (pressed, old_pressed, event) in buttons {
+            if pressed != old_pressed {
+                events.push(Event::Button(event(BinaryInput { pressed })));
+            }
+        }
+
+        // The stick and the left trackpad share the same axes. The left
+        // trackpad touch bit tells which of the two they hold, and a second
+        // bit tells if both are in use, in which case the reports alternate.
+        let x = state.l_x.to_primitive();
+        let y = state.l_y.to_primitive();
+        let both_used = state.l_pad_and_stick;
+        let mut l_stick = self.l_stick;
+        let mut l_pad = (state.l_pad_touch || both_used, self.l_pad.1, self.l_pad.2);
+        if state.l_pad_touch {
+            l_pad = (true, x, y);
+            if !both_used {
+                l_stick = (0, 0);
+            }
+        } else {
+            l_stick = (x, y);
+        }
+        if l_pad != self.l_pad {
+            events.push(Event::Axis(AxisEvent::LPad(TouchAxisInput {
+                index: 0,
+                is_touching: l_pad.0,
+                x: l_pad.1,
+                y: l_pad.2,
+            })));
+            self.l_pad = l_pad;
+        }
+        if l_stick != self.l_stick {
+            events.push(Event::Axis(AxisEvent::LStick(AxisInput {
+                x: l_stick.0,
+                y: l_stick.1,
+            })));
+            self.l_stick = l_stick;
+        }
+
+        // Touch state changes are also emitted so that liftoff is always
+        // reported, even if the contact position did not change.
+        if state.r_pad_x != old_state.r_pad_x
+            || state.r_pad_y != old_state.r_pad_y
+            || state.r_pad_touch != old_state.r_pad_touch
+        {
+            events.push(Event::Axis(AxisEvent::RPad(TouchAxisInput {
+                index: 0,
+                is_touching: state.r_pad_touch,
+                x: state.r_pad_x.to_primitive(),
+                y: state.r_pad_y.to_primitive(),
+            })));
+        }
+
+        // Trigger events
+        if state.l_trigg != old_state.l_trigg {
+            events.push(Event::Trigger(TriggerEvent::LTrigger(TriggerInput {
+                value: state.l_trigg,
+            })));
+        }
+        if state.r_trigg != old_state.r_trigg {
+            events.push(Event::Trigger(TriggerEvent::RTrigger(TriggerInput {
+                value: state.r_trigg,
+            })));
+        }
+
+        // Accelerometer events
+        events.push(Event::Accelerometer(AccelerometerEvent::Accelerometer(
+            AccelerometerInput {
+                x: state.accel_x.to_primitive(),
+                y: state.accel_y.to_primitive(),
+                z: state.accel_z.to_primitive(),
+            },
+        )));
+        events.push(Event::Accelerometer(AccelerometerEvent::Attitude(
+            AccelerometerInput {
+                x: state.pitch.to_primitive(),
+                y: state.yaw.to_primitive(),
+                z: state.roll.to_primitive(),
+            },
+        )));
+
+        events
+    }
+}
+
+/// Returns true if the given vendor and product id belong to a Steam
+/// Controller or its wireless dongle
+pub fn is_supported(vendor_id: u16, product_id: u16) -> bool {
+    vendor_id == VID && (product_id == PID_WIRED || product_id == PID_WIRELESS)
+}
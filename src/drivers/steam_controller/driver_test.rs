@@ -0,0 +1,111 @@
+use std::{error::Error, sync::Arc};
+
+use crate::drivers::{
+    mock_transport::MockTransport,
+    steam_controller::{
+        driver::Driver,
+        event::{AxisEvent, ButtonEvent, Event},
+        hid_report::CONTROLLER_STATE,
+    },
+};
+
+/// Returns a controller state report with no buttons pressed and all axes
+/// centered
+fn neutral_report() -> Vec<u8> {
+    let mut report = vec![0; 64];
+    report[0] = 0x01;
+    report[2] = CONTROLLER_STATE;
+    report[3] = 0x3c;
+    report
+}
+
+/// Returns only the non-IMU events from the given events
+fn input_events(events: Vec<Event>) -> Vec<Event> {
+    events
+        .into_iter()
+        .filter(|event| !matches!(event, Event::Accelerometer(_)))
+        .collect()
+}
+
+#[tokio::test]
+async fn test_steam_controller_button_reports() -> Result<(), Box<dyn Error>> {
+    let mut pressed = neutral_report();
+    pressed[8] = 0x80; // A
+    pressed[9] = 0x80; // Left grip
+    let mut wireless_status = neutral_report();
+    wireless_status[2] = 0x03;
+    let transport = MockTransport::new(vec![neutral_report(), pressed, wireless_status]);
+    let mut driver = Driver::from_transport(Box::new(transport));
+
+    // The first report only initializes the state
+    assert!(input_events(driver.poll()?).is_empty());
+
+    let events = input_events(driver.poll()?);
+    assert_eq!(events.len(), 2);
+    assert!(events.iter().any(|event| matches!(
+        event,
+        Event::Button(ButtonEvent::A(input)) if input.pressed
+    )));
+    assert!(events.iter().any(|event| matches!(
+        event,
+        Event::Button(ButtonEvent::LGrip(input)) if input.pressed
+    )));
+
+    // Reports other than the controller state are ignored
+    assert!(driver.poll()?.is_empty());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_steam_controller_left_pad_and_stick_reports() -> Result<(), Box<dyn Error>> {
+    let mut stick = neutral_report();
+    stick[16..18].copy_from_slice(&1000_i16.to_le_bytes());
+    let mut pad = neutral_report();
+    pad[10] = 0x08; // Left pad touch
+    pad[16..18].copy_from_slice(&(-2000_i16).to_le_bytes());
+    let transport = MockTransport::new(vec![neutral_report(), stick, pad]);
+    let mut driver = Driver::from_transport(Box::new(transport));
+
+    driver.poll()?;
+    let events = input_events(driver.poll()?);
+    assert_eq!(events.len(), 1);
+    assert!(matches!(
+        events[0],
+        Event::Axis(AxisEvent::LStick(ref input)) if input.x == 1000 && input.y == 0
+    ));
+
+    // Touching only the pad reports the pad position and centers the stick
+    let events = input_events(driver.poll()?);
+    assert!(events.iter().any(|event| matches!(
+        event,
+        Event::Axis(AxisEvent::LPad(input)) if input.is_touching && input.x == -2000
+    )));
+    assert!(events.iter().any(|event| matches!(
+        event,
+        Event::Axis(AxisEvent::LStick(input)) if input.x == 0 && input.y == 0
+    )));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_steam_controller_lizard_mode_reports() -> Result<(), Box<dyn Error>> {
+    let transport = Arc::new(MockTransport::new(vec![]));
+    let driver = Driver::from_transport(Box::new(transport.clone()));
+
+    driver.set_lizard_mode(false)?;
+    driver.set_imu_enabled(true)?;
+    driver.set_lizard_mode(true)?;
+
+    let written = transport.written();
+    assert_eq!(written.len(), 5);
+    assert_eq!(written[0][0], 0x81);
+    assert_eq!(written[1][0], 0x87);
+    assert_eq!(written[1][1], 9);
+    assert_eq!(written[2][..5], [0x87, 3, 0x30, 0x18, 0x00]);
+    assert_eq!(written[3][0], 0x85);
+    assert_eq!(written[4][0], 0x8e);
+
+    Ok(())
+}
@@ -0,0 +1,157 @@
+//! Source: https://github.com/torvalds/linux/blob/master/drivers/hid/hid-steam.c
+#![allow(warnings)]
+use packed_struct::prelude::*;
+
+// Input report axis ranges
+pub const STICK_MAX: f64 = 32767.0;
+pub const PAD_MAX: f64 = 32767.0;
+pub const TRIGG_MAX: f64 = 255.0;
+
+/// Report type of the controller state input report. Other input reports,
+/// such as the wireless connection events of the dongle, are ignored.
+pub const CONTROLLER_STATE: u8 = 0x01;
+
+/// Trackpad mode register value that disables mouse emulation
+pub const TRACKPAD_NONE: u16 = 0x07;
+/// Gyro mode register value that sends raw accelerometer and gyro data
+pub const GYRO_SEND_RAW: u16 = 0x18;
+
+#[derive(PackedStruct, Debug, Copy, Clone, PartialEq)]
+#[packed_struct(bit_numbering = "msb0", size_bytes = "64")]
+pub struct PackedInputDataReport {
+    // byte 0-3
+    #[packed_field(bytes = "0")]
+    pub major_ver: u8, // Always 0x01
+    #[packed_field(bytes = "1")]
+    pub minor_ver: u8, // Always 0x00
+    #[packed_field(bytes = "2")]
+    pub report_type: u8, // 0x01 for the controller state
+    #[packed_field(bytes = "3")]
+    pub report_size: u8, // Always 60 for the controller state
+
+    // byte 4-7
+    #[packed_field(bytes = "4..=7", endian = "lsb")]
+    pub frame: Integer<u32, packed_bits::Bits<32>>,
+
+    // byte 8
+    #[packed_field(bits = "64")]
+    pub a: bool, // Button cluster
+    #[packed_field(bits = "65")]
+    pub x: bool,
+    #[packed_field(bits = "66")]
+    pub b: bool,
+    #[packed_field(bits = "67")]
+    pub y: bool,
+    #[packed_field(bits = "68")]
+    pub l1: bool, // Shoulder buttons
+    #[packed_field(bits = "69")]
+    pub r1: bool,
+    #[packed_field(bits = "70")]
+    pub l2: bool,
+    #[packed_field(bits = "71")]
+    pub r2: bool, // Binary sensor for analog triggers
+
+    // byte 9
+    #[packed_field(bits = "72")]
+    pub l_grip: bool, // Left back lever
+    #[packed_field(bits = "73")]
+    pub start: bool, // Right arrow (▶) button
+    #[packed_field(bits = "74")]
+    pub steam: bool, // STEAM button
+    #[packed_field(bits = "75")]
+    pub back: bool, // Left arrow (◀) button
+    #[packed_field(bits = "76")]
+    pub down: bool,
+    #[packed_field(bits = "77")]
+    pub left: bool,
+    #[packed_field(bits = "78")]
+    pub right: bool,
+    #[packed_field(bits = "79")]
+    pub up: bool, // Left trackpad directions
+
+    // byte 10
+    #[packed_field(bits = "80")]
+    pub l_pad_and_stick: bool, // Left trackpad and stick are used at the same time
+    #[packed_field(bits = "81")]
+    pub l3: bool, // Stick click
+    #[packed_field(bits = "82")]
+    pub _unk0: bool,
+    #[packed_field(bits = "83")]
+    pub r_pad_touch: bool, // Binary "touch" sensor for trackpads
+    #[packed_field(bits = "84")]
+    pub l_pad_touch: bool, // Also tells if the stick axes hold left trackpad coordinates
+    #[packed_field(bits = "85")]
+    pub r_pad_press: bool, // Binary "press" sensor for trackpads
+    #[packed_field(bits = "86")]
+    pub l_pad_press: bool,
+    #[packed_field(bits = "87")]
+    pub r_grip: bool, // Right back lever
+
+    // byte 11-15
+    #[packed_field(bytes = "11")]
+    pub l_trigg: u8,
+    #[packed_field(bytes = "12")]
+    pub r_trigg: u8,
+    #[packed_field(bytes = "13..=15")]
+    pub _unk1: [u8; 3],
+
+    // byte 16-23
+    #[packed_field(bytes = "16..=17", endian = "lsb")]
+    pub l_x: Integer<i16, packed_bits::Bits<16>>, // Stick or left trackpad coordinates
+    #[packed_field(bytes = "18..=19", endian = "lsb")]
+    pub l_y: Integer<i16, packed_bits::Bits<16>>,
+    #[packed_field(bytes = "20..=21", endian = "lsb")]
+    pub r_pad_x: Integer<i16, packed_bits::Bits<16>>,
+    #[packed_field(bytes = "22..=23", endian = "lsb")]
+    pub r_pad_y: Integer<i16, packed_bits::Bits<16>>,
+
+    // byte 24-27
+    #[packed_field(bytes = "24..=25", endian = "lsb")]
+    pub l_trigg_precise: Integer<u16, packed_bits::Bits<16>>, // Not sent wirelessly
+    #[packed_field(bytes = "26..=27", endian = "lsb")]
+    pub r_trigg_precise: Integer<u16, packed_bits::Bits<16>>,
+
+    // byte 28-39, only sent when enabled with the gyro mode register
+    #[packed_field(bytes = "28..=29", endian = "lsb")]
+    pub accel_x: Integer<i16, packed_bits::Bits<16>>,
+    #[packed_field(bytes = "30..=31", endian = "lsb")]
+    pub accel_y: Integer<i16, packed_bits::Bits<16>>,
+    #[packed_field(bytes = "32..=33", endian = "lsb")]
+    pub accel_z: Integer<i16, packed_bits::Bits<16>>,
+    #[packed_field(bytes = "34..=35", endian = "lsb")]
+    pub pitch: Integer<i16, packed_bits::Bits<16>>,
+    #[packed_field(bytes = "36..=37", endian = "lsb")]
+    pub yaw: Integer<i16, packed_bits::Bits<16>>,
+    #[packed_field(bytes = "38..=39", endian = "lsb")]
+    pub roll: Integer<i16, packed_bits::Bits<16>>,
+    // 40-47 quaternion, 48-63 uncalibrated values
+}
+
+/// Feature report that writes up to 20 settings registers
+#[derive(PackedStruct, Debug, Copy, Clone, PartialEq)]
+#[packed_struct(bit_numbering = "msb0", size_bytes = "64")]
+pub struct PackedWriteRegistersReport {
+    #[packed_field(bytes = "0")]
+    pub report_id: u8,
+    #[packed_field(bytes = "1")]
+    pub report_size: u8,
+    /// Register id followed by its little endian u16 value for each register
+    #[packed_field(bytes = "2..=61")]
+    pub registers: [u8; 60],
+}
+
+impl PackedWriteRegistersReport {
+    /// Return a report that writes the given (register, value) pairs
+    pub fn new(report_id: u8, registers: &[(u8, u16)]) -> Self {
+        let mut data = [0; 60];
+        for (chunk, (register, value)) in data.chunks_exact_mut(3).zip(registers) {
+            chunk[0] = *register;
+            chunk[1..].copy_from_slice(&value.to_le_bytes());
+        }
+        Self {
+            report_id,
+            report_size: (registers.len().min(20) * 3) as u8,
+            registers: data,
+        }
+    }
+}
@@ -1,7 +1,9 @@
-use std::{error::Error, ffi::CString};
+use std::{error::Error, ffi::CString, time::Instant};
 
-use crate::drivers::steam_deck::hid_report::PackedInputDataReport;
-use hidapi::HidDevice;
+use crate::drivers::{
+    steam_deck::hid_report::PackedInputDataReport,
+    transport::{HidTransport, QueuedTransport},
+};
 use packed_struct::{
     types::{Integer, SizedInteger},
     PackedStruct,
@@ -12,7 +14,7 @@ use super::{
         AccelerometerEvent, AccelerometerInput, AxisEvent, AxisInput, BinaryInput, ButtonEvent,
         Event, TouchAxisInput, TriggerEvent, TriggerInput,
     },
-    hid_report::{PackedMappingsReport, PackedRumbleReport, ReportType},
+    hid_report::{PackedHapticPulseReport, PackedMappingsReport, PackedRumbleReport, ReportType},
 };
 
 /// Vendor ID
@@ -25,12 +27,15 @@ pub const ACCEL_SCALE: f64 = 0.0006125;
 //pub const GYRO_SCALE: f64 = 0.0625;
 /// Size of the HID packet
 const PACKET_SIZE: usize = 64;
-/// Timeout in milliseconds for reading an HID packet
-const HID_TIMEOUT: i32 = 5000;
+/// Timeout in milliseconds for reading an HID packet. Kept short so commands
+/// are still processed while the device is not sending reports.
+const HID_TIMEOUT: i32 = 10;
 
 pub struct Driver {
     state: Option<PackedInputDataReport>,
-    device: HidDevice,
+    device: Box<dyn HidTransport>,
+    /// Time the last input report was received from the device
+    last_report: Instant,
 }
 
 impl Driver {
@@ -43,10 +48,16 @@ impl Driver {
             return Err("Device '{path}' is not a Steam Deck Controller".into());
         }
 
-        Ok(Self {
+        Ok(Self::from_transport(Box::new(QueuedTransport::new(device))))
+    }
+
+    /// Create a driver that exchanges reports over the given transport
+    pub fn from_transport(device: Box<dyn HidTransport>) -> Self {
+        Self {
             device,
             state: None,
-        })
+            last_report: Instant::now(),
+        }
     }
 
     /// Poll the device and read input reports
@@ -55,6 +66,12 @@ impl Driver {
         let mut buf = [0; PACKET_SIZE];
         let bytes_read = self.device.read_timeout(&mut buf[..], HID_TIMEOUT)?;
 
+        // No report was received before the timeout
+        if bytes_read == 0 {
+            return Ok(Vec::new());
+        }
+        self.last_report = Instant::now();
+
         // All report descriptors are 64 bytes, so this is just to be safe
         if bytes_read != PACKET_SIZE {
             let msg = format!("Invalid input report size was received from gamepad device: {bytes_read}/{PACKET_SIZE}");
@@ -67,6 +84,11 @@ impl Driver {
         Ok(events)
     }
 
+    /// Returns the time the last input report was read from the device
+    pub fn last_report_time(&self) -> Instant {
+        self.last_report
+    }
+
     /// Rumble the gamepad
     pub fn haptic_rumble(
         &mut self,
@@ -85,7 +107,18 @@ impl Driver {
 
         // Write the report to the device
         let buf = report.pack()?;
-        let _bytes_written = self.device.write(&buf)?;
+        let _bytes_written = self.device.write_rumble(&buf)?;
+
+        Ok(())
+    }
+
+    /// Play the given haptic pulse on the trackpad actuators
+    pub fn haptic_pulse(
+        &self,
+        report: PackedHapticPulseReport,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let buf = report.pack()?;
+        self.device.write(&buf)?;
 
         Ok(())
     }
@@ -304,15 +337,23 @@ impl Driver {
             }
 
             // Axis events
-            if state.l_pad_x != old_state.l_pad_x || state.l_pad_y != old_state.l_pad_y {
+            // Touch state changes are also emitted so that liftoff is always
+            // reported, even if the contact position did not change.
+            if state.l_pad_x != old_state.l_pad_x
+                || state.l_pad_y != old_state.l_pad_y
+                || state.l_pad_touch != old_state.l_pad_touch
+            {
                 events.push(Event::Axis(AxisEvent::LPad(TouchAxisInput {
-                    index: 0, // TODO: Something else
+                    index: 0,
                     is_touching: state.l_pad_touch,
                     x: state.l_pad_x.to_primitive(),
                     y: state.l_pad_y.to_primitive(),
                 })));
             }
-            if state.r_pad_x != old_state.r_pad_x || state.r_pad_y != old_state.r_pad_y {
+            if state.r_pad_x != old_state.r_pad_x
+                || state.r_pad_y != old_state.r_pad_y
+                || state.r_pad_touch != old_state.r_pad_touch
+            {
                 events.push(Event::Axis(AxisEvent::RPad(TouchAxisInput {
                     index: 0,
                     is_touching: state.r_pad_touch,
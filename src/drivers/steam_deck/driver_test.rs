@@ -0,0 +1,117 @@
+use std::{error::Error, sync::Arc};
+
+use packed_struct::types::{Integer, SizedInteger};
+
+use crate::drivers::{
+    mock_transport::MockTransport,
+    steam_deck::{
+        driver::Driver,
+        event::{AxisEvent, ButtonEvent, Event, TriggerEvent},
+        hid_report::{PackedHapticPulseReport, Pad},
+    },
+};
+
+/// Returns an input report with no buttons pressed and all axes centered
+fn neutral_report() -> Vec<u8> {
+    let mut report = vec![0; 64];
+    report[0] = 0x01;
+    report[2] = 0x09;
+    report[3] = 0x40;
+    report
+}
+
+/// Returns only the button, axis, and trigger events from the given events
+fn input_events(events: Vec<Event>) -> Vec<Event> {
+    events
+        .into_iter()
+        .filter(|event| !matches!(event, Event::Accelerometer(_)))
+        .collect()
+}
+
+#[tokio::test]
+async fn test_steam_deck_button_reports() -> Result<(), Box<dyn Error>> {
+    let mut pressed = neutral_report();
+    pressed[8] = 0x80; // A
+    let transport = MockTransport::new(vec![neutral_report(), pressed, neutral_report()]);
+    let mut driver = Driver::from_transport(Box::new(transport));
+
+    // The first report only initializes the state
+    assert!(input_events(driver.poll()?).is_empty());
+
+    let events = input_events(driver.poll()?);
+    assert_eq!(events.len(), 1);
+    assert!(matches!(
+        events[0],
+        Event::Button(ButtonEvent::A(ref input)) if input.pressed
+    ));
+
+    let events = input_events(driver.poll()?);
+    assert_eq!(events.len(), 1);
+    assert!(matches!(
+        events[0],
+        Event::Button(ButtonEvent::A(ref input)) if !input.pressed
+    ));
+
+    // No report is available before the timeout
+    assert!(driver.poll()?.is_empty());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_steam_deck_axis_reports() -> Result<(), Box<dyn Error>> {
+    let mut moved = neutral_report();
+    moved[44..46].copy_from_slice(&0x7fffu16.to_le_bytes()); // Left trigger
+    moved[48..50].copy_from_slice(&(-32768i16).to_le_bytes()); // Left stick X
+    moved[50..52].copy_from_slice(&1000i16.to_le_bytes()); // Left stick Y
+    let transport = MockTransport::new(vec![neutral_report(), moved]);
+    let mut driver = Driver::from_transport(Box::new(transport));
+
+    driver.poll()?;
+    let events = input_events(driver.poll()?);
+    assert_eq!(events.len(), 2);
+    assert!(events.iter().any(|event| matches!(
+        event,
+        Event::Axis(AxisEvent::LStick(input)) if input.x == -32768 && input.y == 1000
+    )));
+    assert!(events.iter().any(|event| matches!(
+        event,
+        Event::Trigger(TriggerEvent::LTrigger(input)) if input.value == 0x7fff
+    )));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_steam_deck_lizard_mode_report() -> Result<(), Box<dyn Error>> {
+    let transport = Arc::new(MockTransport::new(vec![]));
+    let driver = Driver::from_transport(Box::new(transport.clone()));
+
+    driver.set_lizard_mode(false)?;
+    driver.set_lizard_mode(true)?;
+
+    let written = transport.written();
+    assert_eq!(written.len(), 2);
+    assert_eq!(written[0].len(), 64);
+    assert_eq!(written[0][0], 0x81);
+    assert_eq!(written[1][0], 0x85);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_steam_deck_haptic_pulse_report() -> Result<(), Box<dyn Error>> {
+    let transport = Arc::new(MockTransport::new(vec![]));
+    let driver = Driver::from_transport(Box::new(transport.clone()));
+
+    let mut report = PackedHapticPulseReport::new();
+    report.side = Pad::Right;
+    report.amplitude = Integer::from_primitive(0x0102);
+    driver.haptic_pulse(report)?;
+
+    let written = transport.written();
+    assert_eq!(written.len(), 1);
+    assert_eq!(written[0][..5], [0x8f, 9, 1, 0x02, 0x01]);
+
+    Ok(())
+}
@@ -64,6 +64,31 @@ pub enum Register {
     GyroMode = 0x30,
 }
 
+/// Attributes returned in reply to a [ReportType::GetAttrib] feature report
+pub enum Attribute {
+    UniqueId = 0x00,
+    ProductId = 0x01,
+    Capabilities = 0x02,
+    FirmwareVersion = 0x03,
+    FirmwareBuildTime = 0x04,
+    BoardRevision = 0x09,
+    BootloaderBuildTime = 0x0a,
+    ConnectionIntervalUs = 0x0b,
+}
+
+/// String attributes returned in reply to a [ReportType::GetSerial] feature
+/// report
+pub enum StringAttribute {
+    BoardSerial = 0x00,
+    UnitSerial = 0x01,
+}
+
+/// Maximum length of the serial number in a [ReportType::GetSerial] reply
+pub const SERIAL_LEN: usize = 0x15;
+
+/// Size of feature reports, excluding the report id
+pub const FEATURE_REPORT_SIZE: usize = 64;
+
 #[derive(PackedStruct, Debug, Copy, Clone, PartialEq)]
 #[packed_struct(bit_numbering = "msb0", size_bytes = "64")]
 pub struct PackedInputDataReport {
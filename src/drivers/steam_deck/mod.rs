@@ -1,4 +1,6 @@
 pub mod driver;
+#[cfg(test)]
+mod driver_test;
 pub mod event;
 pub mod hid_report;
 pub mod report_descriptor;
@@ -0,0 +1,173 @@
+//! Layout of the standard input report section shared by the full (0x30) and
+//! subcommand reply (0x21) input reports of the Switch Pro Controller.
+//!
+//! Reference: https://github.com/dekuNukem/Nintendo_Switch_Reverse_Engineering/blob/master/bluetooth_hid_notes.md
+use packed_struct::prelude::*;
+
+pub const NAME: &str = "Nintendo Co., Ltd. Pro Controller";
+pub const VID: u16 = 0x057e;
+pub const PID: u16 = 0x2009;
+
+/// Input report id of subcommand replies
+pub const INPUT_SUBCOMMAND_REPLY: u8 = 0x21;
+/// Input report id of full reports with buttons, sticks and IMU data
+pub const INPUT_FULL: u8 = 0x30;
+/// Input report id of replies to USB commands
+pub const INPUT_USB_REPLY: u8 = 0x81;
+/// Output report id of USB commands
+pub const OUTPUT_USB_COMMAND: u8 = 0x80;
+/// Size of all input reports over USB
+pub const INPUT_REPORT_SIZE: usize = 64;
+
+/// Center of the 12-bit stick values
+pub const STICK_CENTER: u16 = 0x800;
+/// Distance from the center to the minimum and maximum stick values reported
+/// in the factory stick calibration
+pub const STICK_RANGE: u16 = 0x600;
+
+/// Battery and connection byte reporting a full battery that is charging
+/// over USB
+pub const BATTERY_FULL_CHARGING_USB: u8 = 0x91;
+
+/// Standard input report section with the report id, timer, battery state,
+/// buttons and sticks.
+#[derive(PackedStruct, Debug, Copy, Clone, PartialEq)]
+#[packed_struct(bit_numbering = "msb0", size_bytes = "13")]
+pub struct PackedInputDataReport {
+    // byte 0
+    #[packed_field(bytes = "0")]
+    pub report_id: u8,
+    // byte 1
+    #[packed_field(bytes = "1")]
+    pub timer: u8,
+    // byte 2
+    #[packed_field(bytes = "2")]
+    pub battery_connection: u8,
+
+    // byte 3
+    #[packed_field(bits = "24")]
+    pub zr: bool,
+    #[packed_field(bits = "25")]
+    pub r: bool,
+    #[packed_field(bits = "26")]
+    pub right_sl: bool,
+    #[packed_field(bits = "27")]
+    pub right_sr: bool,
+    #[packed_field(bits = "28")]
+    pub a: bool,
+    #[packed_field(bits = "29")]
+    pub b: bool,
+    #[packed_field(bits = "30")]
+    pub x: bool,
+    #[packed_field(bits = "31")]
+    pub y: bool,
+
+    // byte 4
+    #[packed_field(bits = "32")]
+    pub charging_grip: bool,
+    #[packed_field(bits = "33")]
+    pub _unused: bool,
+    #[packed_field(bits = "34")]
+    pub capture: bool,
+    #[packed_field(bits = "35")]
+    pub home: bool,
+    #[packed_field(bits = "36")]
+    pub l_stick: bool,
+    #[packed_field(bits = "37")]
+    pub r_stick: bool,
+    #[packed_field(bits = "38")]
+    pub plus: bool,
+    #[packed_field(bits = "39")]
+    pub minus: bool,
+
+    // byte 5
+    #[packed_field(bits = "40")]
+    pub zl: bool,
+    #[packed_field(bits = "41")]
+    pub l: bool,
+    #[packed_field(bits = "42")]
+    pub left_sl: bool,
+    #[packed_field(bits = "43")]
+    pub left_sr: bool,
+    #[packed_field(bits = "44")]
+    pub left: bool,
+    #[packed_field(bits = "45")]
+    pub right: bool,
+    #[packed_field(bits = "46")]
+    pub up: bool,
+    #[packed_field(bits = "47")]
+    pub down: bool,
+
+    // byte 6-8
+    #[packed_field(bytes = "6..=8")]
+    pub left_stick: [u8; 3],
+    // byte 9-11
+    #[packed_field(bytes = "9..=11")]
+    pub right_stick: [u8; 3],
+    // byte 12
+    #[packed_field(bytes = "12")]
+    pub vibrator_report: u8,
+}
+
+impl PackedInputDataReport {
+    /// Returns a report with no buttons pressed and centered sticks
+    pub fn new() -> Self {
+        Self {
+            report_id: INPUT_FULL,
+            timer: 0,
+            battery_connection: BATTERY_FULL_CHARGING_USB,
+            zr: false,
+            r: false,
+            right_sl: false,
+            right_sr: false,
+            a: false,
+            b: false,
+            x: false,
+            y: false,
+            charging_grip: false,
+            _unused: false,
+            capture: false,
+            home: false,
+            l_stick: false,
+            r_stick: false,
+            plus: false,
+            minus: false,
+            zl: false,
+            l: false,
+            left_sl: false,
+            left_sr: false,
+            left: false,
+            right: false,
+            up: false,
+            down: false,
+            left_stick: pack_stick(STICK_CENTER, STICK_CENTER),
+            right_stick: pack_stick(STICK_CENTER, STICK_CENTER),
+            vibrator_report: 0x80,
+        }
+    }
+}
+
+impl Default for PackedInputDataReport {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Pack the given 12-bit X and Y values into 3 bytes, as used by stick data
+/// and stick calibration.
+pub fn pack_stick(x: u16, y: u16) -> [u8; 3] {
+    let x = x & 0xfff;
+    let y = y & 0xfff;
+    [
+        (x & 0xff) as u8,
+        ((x >> 8) | ((y & 0xf) << 4)) as u8,
+        (y >> 4) as u8,
+    ]
+}
+
+/// Unpack the 12-bit X and Y values from the given 3 bytes
+pub fn unpack_stick(data: [u8; 3]) -> (u16, u16) {
+    let x = data[0] as u16 | ((data[1] as u16 & 0xf) << 8);
+    let y = (data[1] as u16 >> 4) | ((data[2] as u16) << 4);
+    (x, y)
+}
@@ -0,0 +1,101 @@
+//! Encoding of motion data into the IMU section of Switch Pro input reports.
+//!
+//! Full input reports (0x30) carry three IMU samples taken 5ms apart, each
+//! with accelerometer and gyro values for the X, Y and Z axes as little endian
+//! 16-bit integers.
+//!
+//! Reference: https://github.com/dekuNukem/Nintendo_Switch_Reverse_Engineering/blob/master/imu_sensor_notes.md
+
+/// Offset of the IMU data in a full input report
+pub const IMU_DATA_OFFSET: usize = 13;
+/// Number of IMU samples in a full input report
+pub const IMU_SAMPLE_COUNT: usize = 3;
+/// Size of a single IMU sample
+pub const IMU_SAMPLE_SIZE: usize = 12;
+
+/// Accelerometer resolution in units per G at the default +/- 8G range
+const ACCEL_RES_PER_G: f64 = 4096.0;
+/// Gyro resolution in units per degree per second at the default +/- 2000dps
+/// range
+const GYRO_RES_PER_DEG_SEC: f64 = 14.2842;
+
+/// Latest motion state to report in the IMU section of input reports. Values
+/// are stored using the InputPlumber axis convention (X right, Y up, Z towards
+/// the player) and converted to the Switch convention (X away from the
+/// player, Y left, Z up) when encoded.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ImuState {
+    /// Acceleration in meters per second squared
+    accel: [f64; 3],
+    /// Angular velocity in degrees per second
+    gyro: [f64; 3],
+}
+
+impl ImuState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Update the accelerometer axes that have a value
+    pub fn set_accel(&mut self, x: Option<f64>, y: Option<f64>, z: Option<f64>) {
+        update_axes(&mut self.accel, [x, y, z]);
+    }
+
+    /// Update the gyro axes that have a value
+    pub fn set_gyro(&mut self, x: Option<f64>, y: Option<f64>, z: Option<f64>) {
+        update_axes(&mut self.gyro, [x, y, z]);
+    }
+
+    /// Returns the accelerometer and gyro values in Switch axis order and
+    /// scaling.
+    pub fn to_raw(&self) -> ([i16; 3], [i16; 3]) {
+        let accel = to_switch_axes(self.accel).map(|value| {
+            let value = value / 9.8 * ACCEL_RES_PER_G;
+            value.clamp(i16::MIN as f64, i16::MAX as f64) as i16
+        });
+        let gyro = to_switch_axes(self.gyro).map(|value| {
+            let value = value * GYRO_RES_PER_DEG_SEC;
+            value.clamp(i16::MIN as f64, i16::MAX as f64) as i16
+        });
+        (accel, gyro)
+    }
+
+    /// Encode the current state as a single IMU sample
+    pub fn to_sample(&self) -> [u8; IMU_SAMPLE_SIZE] {
+        let (accel, gyro) = self.to_raw();
+        let mut sample = [0; IMU_SAMPLE_SIZE];
+        for (i, value) in accel.iter().chain(gyro.iter()).enumerate() {
+            sample[i * 2..i * 2 + 2].copy_from_slice(&value.to_le_bytes());
+        }
+        sample
+    }
+
+    /// Write the current state into all IMU samples of the given full input
+    /// report. Reports that are too short to hold IMU data are left untouched.
+    pub fn write_report(&self, report: &mut [u8]) {
+        let end = IMU_DATA_OFFSET + IMU_SAMPLE_COUNT * IMU_SAMPLE_SIZE;
+        let Some(data) = report.get_mut(IMU_DATA_OFFSET..end) else {
+            return;
+        };
+        let sample = self.to_sample();
+        for chunk in data.chunks_exact_mut(IMU_SAMPLE_SIZE) {
+            chunk.copy_from_slice(&sample);
+        }
+    }
+}
+
+/// Update each axis that has a value
+fn update_axes(axes: &mut [f64; 3], values: [Option<f64>; 3]) {
+    for (axis, value) in axes.iter_mut().zip(values) {
+        if let Some(value) = value {
+            *axis = value;
+        }
+    }
+}
+
+/// Convert the given axes from the InputPlumber convention into the Switch
+/// convention.
+fn to_switch_axes(axes: [f64; 3]) -> [f64; 3] {
+    let [x, y, z] = axes;
+    [-z, -x, y]
+}
@@ -0,0 +1,22 @@
+use crate::drivers::switch_pro::imu::{ImuState, IMU_DATA_OFFSET, IMU_SAMPLE_SIZE};
+
+#[test]
+fn test_imu_axes_and_scaling() {
+    let mut imu = ImuState::new();
+    // Resting flat with gravity pulling down and yawing to the left
+    imu.set_accel(Some(0.0), Some(9.8), Some(0.0));
+    imu.set_gyro(None, Some(70.0), None);
+
+    let (accel, gyro) = imu.to_raw();
+    assert_eq!(accel, [0, 0, 4096]);
+    assert_eq!(gyro, [0, 0, 999]);
+
+    let mut report = vec![0x30; 49];
+    imu.write_report(&mut report);
+    let sample = imu.to_sample();
+    for i in 0..3 {
+        let start = IMU_DATA_OFFSET + i * IMU_SAMPLE_SIZE;
+        assert_eq!(report[start..start + IMU_SAMPLE_SIZE], sample);
+    }
+    assert_eq!(sample[4..6], 4096i16.to_le_bytes());
+}
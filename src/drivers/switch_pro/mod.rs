@@ -0,0 +1,11 @@
+pub mod hid_report;
+pub mod imu;
+#[cfg(test)]
+mod imu_test;
+pub mod protocol;
+#[cfg(test)]
+mod protocol_test;
+pub mod report_descriptor;
+pub mod rumble;
+#[cfg(test)]
+mod rumble_test;
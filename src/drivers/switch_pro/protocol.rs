@@ -0,0 +1,266 @@
+//! Replies of a virtual Switch Pro Controller to the output reports sent by
+//! the host, covering the USB handshake and the subcommands that drivers such
+//! as hid-nintendo and SDL send to initialize the controller.
+//!
+//! Reference: https://github.com/dekuNukem/Nintendo_Switch_Reverse_Engineering/blob/master/USB-HID-Notes.md
+use packed_struct::PackedStruct;
+
+use super::{
+    hid_report::{
+        pack_stick, PackedInputDataReport, INPUT_FULL, INPUT_REPORT_SIZE, INPUT_SUBCOMMAND_REPLY,
+        INPUT_USB_REPLY, OUTPUT_USB_COMMAND, STICK_CENTER, STICK_RANGE,
+    },
+    rumble::OUTPUT_RUMBLE_AND_SUBCOMMAND,
+};
+
+/// USB commands sent with the [OUTPUT_USB_COMMAND] output report
+pub const USB_CMD_STATUS: u8 = 0x01;
+pub const USB_CMD_HANDSHAKE: u8 = 0x02;
+pub const USB_CMD_BAUDRATE: u8 = 0x03;
+pub const USB_CMD_NO_TIMEOUT: u8 = 0x04;
+pub const USB_CMD_TIMEOUT: u8 = 0x05;
+
+/// Subcommands sent with the [OUTPUT_RUMBLE_AND_SUBCOMMAND] output report
+pub const SUBCMD_DEVICE_INFO: u8 = 0x02;
+pub const SUBCMD_INPUT_MODE: u8 = 0x03;
+pub const SUBCMD_TRIGGER_ELAPSED: u8 = 0x04;
+pub const SUBCMD_SPI_READ: u8 = 0x10;
+pub const SUBCMD_MCU_CONFIG: u8 = 0x21;
+pub const SUBCMD_PLAYER_LIGHTS: u8 = 0x30;
+pub const SUBCMD_ENABLE_IMU: u8 = 0x40;
+pub const SUBCMD_VOLTAGE: u8 = 0x50;
+
+/// Offset of the subcommand id in an output report
+const SUBCMD_OFFSET: usize = 10;
+/// Offset of the ACK byte in a subcommand reply
+const REPLY_ACK_OFFSET: usize = 13;
+/// Maximum size of the data of a subcommand reply
+const REPLY_DATA_SIZE: usize = 35;
+
+/// Firmware version reported in the device info
+const FIRMWARE_VERSION: [u8; 2] = [0x03, 0x48];
+/// Controller type reported in the device info
+const CONTROLLER_TYPE_PRO: u8 = 0x03;
+
+/// Factory calibration and configuration stored in the SPI flash of the
+/// controller. Any address that is not listed reads as 0xff, which is also
+/// how unset user calibration reads on real controllers.
+const SPI_FACTORY_DATA: &[(u32, &[u8])] = &[
+    // Accelerometer origin and sensitivity, then gyro origin and sensitivity
+    (
+        0x6020,
+        &[
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x40, 0x00, 0x40, 0x00, 0x40, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x3b, 0x34, 0x3b, 0x34, 0x3b, 0x34,
+        ],
+    ),
+    // Body, button, left grip and right grip colors
+    (
+        0x6050,
+        &[
+            0x32, 0x32, 0x32, 0xff, 0xff, 0xff, 0x32, 0x32, 0x32, 0x32, 0x32, 0x32,
+        ],
+    ),
+    // Six axis horizontal offsets
+    (0x6080, &[0x50, 0xfd, 0x00, 0x00, 0xc6, 0x0f]),
+    // Left stick parameters
+    (
+        0x6086,
+        &[
+            0x0f, 0x30, 0x61, 0x96, 0x30, 0xf3, 0xd4, 0x14, 0x54, 0x41, 0x15, 0x54, 0xc7, 0x79,
+            0x9c, 0x33, 0x36, 0x63,
+        ],
+    ),
+    // Right stick parameters
+    (
+        0x6098,
+        &[
+            0x0f, 0x30, 0x61, 0x96, 0x30, 0xf3, 0xd4, 0x14, 0x54, 0x41, 0x15, 0x54, 0xc7, 0x79,
+            0x9c, 0x33, 0x36, 0x63,
+        ],
+    ),
+];
+/// Address of the factory calibration of the left stick
+const SPI_LEFT_STICK_CALIBRATION: u32 = 0x603d;
+/// Address of the factory calibration of the right stick
+const SPI_RIGHT_STICK_CALIBRATION: u32 = 0x6046;
+
+/// State of the host connection to a virtual Switch Pro Controller
+#[derive(Debug, Clone)]
+pub struct Protocol {
+    /// MAC address of the controller
+    mac_addr: [u8; 6],
+    /// Whether the host has asked for full input reports
+    full_reports: bool,
+    /// Whether the host has enabled the IMU
+    imu_enabled: bool,
+}
+
+impl Protocol {
+    pub fn new(mac_addr: [u8; 6]) -> Self {
+        Self {
+            mac_addr,
+            full_reports: false,
+            imu_enabled: false,
+        }
+    }
+
+    /// Returns true if full input reports should be sent to the host
+    pub fn full_reports(&self) -> bool {
+        self.full_reports
+    }
+
+    /// Returns true if the IMU section of full input reports should be filled
+    pub fn imu_enabled(&self) -> bool {
+        self.imu_enabled
+    }
+
+    /// Returns the input report to reply to the given output report from the
+    /// host, if the output report expects one. Subcommand replies include the
+    /// given button and stick state.
+    pub fn handle_output(&mut self, data: &[u8], state: &PackedInputDataReport) -> Option<Vec<u8>> {
+        match *data.first()? {
+            OUTPUT_USB_COMMAND => self.handle_usb_command(*data.get(1)?),
+            OUTPUT_RUMBLE_AND_SUBCOMMAND => {
+                let subcommand = *data.get(SUBCMD_OFFSET)?;
+                let args = data.get(SUBCMD_OFFSET + 1..).unwrap_or_default();
+                let (ack, reply) = self.handle_subcommand(subcommand, args);
+                Some(subcommand_reply(state, ack, subcommand, &reply))
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the reply to the given USB command, if it has one
+    fn handle_usb_command(&mut self, command: u8) -> Option<Vec<u8>> {
+        let data = match command {
+            USB_CMD_STATUS => {
+                let mut data = vec![0x00, CONTROLLER_TYPE_PRO];
+                data.extend(self.mac_addr.iter().rev());
+                data
+            }
+            USB_CMD_HANDSHAKE | USB_CMD_BAUDRATE => vec![],
+            USB_CMD_NO_TIMEOUT => {
+                self.full_reports = true;
+                return None;
+            }
+            USB_CMD_TIMEOUT => {
+                self.full_reports = false;
+                return None;
+            }
+            _ => return None,
+        };
+
+        let mut report = vec![0; INPUT_REPORT_SIZE];
+        report[0] = INPUT_USB_REPLY;
+        report[1] = command;
+        report[2..2 + data.len()].copy_from_slice(&data);
+        Some(report)
+    }
+
+    /// Returns the ACK byte and data to reply to the given subcommand
+    fn handle_subcommand(&mut self, subcommand: u8, args: &[u8]) -> (u8, Vec<u8>) {
+        log::trace!("Received subcommand {subcommand:#04x} with args: {args:?}");
+        match subcommand {
+            SUBCMD_DEVICE_INFO => {
+                let mut data = FIRMWARE_VERSION.to_vec();
+                data.extend([CONTROLLER_TYPE_PRO, 0x02]);
+                data.extend(self.mac_addr);
+                // Use the colors stored in the SPI flash
+                data.extend([0x01, 0x01]);
+                (0x82, data)
+            }
+            SUBCMD_INPUT_MODE => {
+                self.full_reports = args.first() == Some(&INPUT_FULL);
+                (0x80, vec![])
+            }
+            SUBCMD_TRIGGER_ELAPSED => (0x83, vec![0; 14]),
+            SUBCMD_SPI_READ => {
+                let Some(address) = args.get(..4) else {
+                    return (0x80, vec![]);
+                };
+                let size = args.get(4).copied().unwrap_or_default();
+                let size = size.min((REPLY_DATA_SIZE - 5) as u8);
+                let mut data = address.to_vec();
+                data.push(size);
+                let address = u32::from_le_bytes(address.try_into().unwrap_or_default());
+                data.extend(spi_read(address, size as usize));
+                (0x90, data)
+            }
+            // Report the MCU as ready without an NFC/IR firmware
+            SUBCMD_MCU_CONFIG => (0xa0, vec![0x01, 0x00, 0xff, 0x00, 0x08, 0x00, 0x1b, 0x01]),
+            SUBCMD_ENABLE_IMU => {
+                self.imu_enabled = args.first().is_some_and(|enabled| *enabled != 0);
+                (0x80, vec![])
+            }
+            // Regulated voltage in mV
+            SUBCMD_VOLTAGE => (0xd0, 1600_u16.to_le_bytes().to_vec()),
+            _ => (0x80, vec![]),
+        }
+    }
+}
+
+/// Returns the player lights that are on for the given output report, if it
+/// sets the player lights. Bit 0 is the first light. Flashing lights are
+/// reported as on.
+pub fn player_lights(data: &[u8]) -> Option<u8> {
+    if data.first() != Some(&OUTPUT_RUMBLE_AND_SUBCOMMAND)
+        || data.get(SUBCMD_OFFSET) != Some(&SUBCMD_PLAYER_LIGHTS)
+    {
+        return None;
+    }
+    let lights = *data.get(SUBCMD_OFFSET + 1)?;
+    Some((lights | (lights >> 4)) & 0x0f)
+}
+
+/// Returns a subcommand reply input report with the given state, ACK byte,
+/// subcommand id and data.
+fn subcommand_reply(
+    state: &PackedInputDataReport,
+    ack: u8,
+    subcommand: u8,
+    data: &[u8],
+) -> Vec<u8> {
+    let mut report = vec![0; INPUT_REPORT_SIZE];
+    let mut state = *state;
+    state.report_id = INPUT_SUBCOMMAND_REPLY;
+    if let Ok(header) = state.pack() {
+        report[..header.len()].copy_from_slice(&header);
+    }
+    report[REPLY_ACK_OFFSET] = ack;
+    report[REPLY_ACK_OFFSET + 1] = subcommand;
+    let size = data.len().min(REPLY_DATA_SIZE);
+    report[REPLY_ACK_OFFSET + 2..REPLY_ACK_OFFSET + 2 + size].copy_from_slice(&data[..size]);
+    report
+}
+
+/// Read the given number of bytes from the virtual SPI flash at the given
+/// address.
+pub fn spi_read(address: u32, size: usize) -> Vec<u8> {
+    let mut data = vec![0xff; size];
+    // Stick calibration is stored as the distance from the center to the
+    // maximum, the center and the distance from the center to the minimum for
+    // the left stick, and as center, minimum and maximum for the right stick.
+    let range = pack_stick(STICK_RANGE, STICK_RANGE);
+    let center = pack_stick(STICK_CENTER, STICK_CENTER);
+    let left_stick = [range, center, range].concat();
+    let right_stick = [center, range, range].concat();
+    let stick_calibration: [(u32, &[u8]); 2] = [
+        (SPI_LEFT_STICK_CALIBRATION, &left_stick),
+        (SPI_RIGHT_STICK_CALIBRATION, &right_stick),
+    ];
+
+    let sections = SPI_FACTORY_DATA.iter().chain(stick_calibration.iter());
+    for (start, section) in sections {
+        for (i, byte) in section.iter().enumerate() {
+            let Some(offset) = (start + i as u32).checked_sub(address) else {
+                continue;
+            };
+            if let Some(value) = data.get_mut(offset as usize) {
+                *value = *byte;
+            }
+        }
+    }
+
+    data
+}
@@ -0,0 +1,99 @@
+use crate::drivers::switch_pro::{
+    hid_report::{
+        unpack_stick, PackedInputDataReport, INPUT_SUBCOMMAND_REPLY, INPUT_USB_REPLY, STICK_CENTER,
+        STICK_RANGE,
+    },
+    protocol::{player_lights, spi_read, Protocol},
+};
+
+const MAC_ADDR: [u8; 6] = [0x01, 0x02, 0x03, 0x04, 0x05, 0x06];
+
+/// Returns a rumble and subcommand output report with neutral rumble data
+fn subcommand(id: u8, args: &[u8]) -> Vec<u8> {
+    let mut report = vec![
+        0x01, 0x00, 0x00, 0x01, 0x40, 0x40, 0x00, 0x01, 0x40, 0x40, id,
+    ];
+    report.extend_from_slice(args);
+    report
+}
+
+#[test]
+fn test_usb_handshake() {
+    let mut protocol = Protocol::new(MAC_ADDR);
+    let state = PackedInputDataReport::new();
+
+    let reply = protocol.handle_output(&[0x80, 0x01], &state).unwrap();
+    assert_eq!(reply.len(), 64);
+    assert_eq!(reply[..4], [INPUT_USB_REPLY, 0x01, 0x00, 0x03]);
+    assert_eq!(reply[4..10], [0x06, 0x05, 0x04, 0x03, 0x02, 0x01]);
+
+    let reply = protocol.handle_output(&[0x80, 0x02], &state).unwrap();
+    assert_eq!(reply[..2], [INPUT_USB_REPLY, 0x02]);
+
+    assert!(!protocol.full_reports());
+    assert_eq!(protocol.handle_output(&[0x80, 0x04], &state), None);
+    assert!(protocol.full_reports());
+}
+
+#[test]
+fn test_subcommand_replies() {
+    let mut protocol = Protocol::new(MAC_ADDR);
+    let mut state = PackedInputDataReport::new();
+    state.a = true;
+
+    // Device info
+    let reply = protocol
+        .handle_output(&subcommand(0x02, &[]), &state)
+        .unwrap();
+    assert_eq!(reply[0], INPUT_SUBCOMMAND_REPLY);
+    assert_eq!(reply[3], 0x08);
+    assert_eq!(reply[13..15], [0x82, 0x02]);
+    assert_eq!(reply[17], 0x03);
+    assert_eq!(reply[19..25], MAC_ADDR);
+
+    // Enable IMU and full input reports
+    let reply = protocol
+        .handle_output(&subcommand(0x40, &[0x01]), &state)
+        .unwrap();
+    assert_eq!(reply[13..15], [0x80, 0x40]);
+    assert!(protocol.imu_enabled());
+    protocol.handle_output(&subcommand(0x03, &[0x30]), &state);
+    assert!(protocol.full_reports());
+
+    // Unknown reports are not answered
+    assert_eq!(protocol.handle_output(&[0x10, 0x00], &state), None);
+}
+
+#[test]
+fn test_spi_read() {
+    let mut protocol = Protocol::new(MAC_ADDR);
+    let state = PackedInputDataReport::new();
+
+    // Read the factory calibration of the left stick
+    let reply = protocol
+        .handle_output(&subcommand(0x10, &[0x3d, 0x60, 0x00, 0x00, 0x09]), &state)
+        .unwrap();
+    assert_eq!(reply[13..15], [0x90, 0x10]);
+    assert_eq!(reply[15..20], [0x3d, 0x60, 0x00, 0x00, 0x09]);
+    let data: [u8; 9] = reply[20..29].try_into().unwrap();
+    assert_eq!(
+        unpack_stick(data[..3].try_into().unwrap()),
+        (STICK_RANGE, STICK_RANGE)
+    );
+    assert_eq!(
+        unpack_stick(data[3..6].try_into().unwrap()),
+        (STICK_CENTER, STICK_CENTER)
+    );
+
+    // Reads across sections and of unset user calibration
+    let data = spi_read(0x6026, 8);
+    assert_eq!(data, [0x00, 0x40, 0x00, 0x40, 0x00, 0x40, 0x00, 0x00]);
+    assert_eq!(spi_read(0x8010, 2), [0xff, 0xff]);
+}
+
+#[test]
+fn test_player_lights() {
+    assert_eq!(player_lights(&subcommand(0x30, &[0x01])), Some(0x01));
+    assert_eq!(player_lights(&subcommand(0x30, &[0x30])), Some(0x03));
+    assert_eq!(player_lights(&subcommand(0x40, &[0x01])), None);
+}
@@ -0,0 +1,144 @@
+//! Translation of Nintendo Switch "HD rumble" data into standard rumble values.
+//!
+//! Switch controllers drive linear resonant actuators, so games send each
+//! actuator a high band and a low band, each with its own frequency and
+//! amplitude. Source devices only understand a strong (low frequency) and a
+//! weak (high frequency) motor intensity, so each band is routed to one of the
+//! two motors based on its frequency.
+//!
+//! Encoding reference: https://github.com/dekuNukem/Nintendo_Switch_Reverse_Engineering/blob/master/rumble_data_table.md
+use serde::Deserialize;
+
+/// Output report id for rumble data followed by a subcommand
+pub const OUTPUT_RUMBLE_AND_SUBCOMMAND: u8 = 0x01;
+/// Output report id for rumble data only
+pub const OUTPUT_RUMBLE_ONLY: u8 = 0x10;
+
+/// Offset of the rumble data in an output report
+const RUMBLE_DATA_OFFSET: usize = 2;
+/// Size of the rumble data for a single actuator
+const RUMBLE_DATA_SIZE: usize = 4;
+
+/// Decoded HD rumble values for a single actuator
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HdRumble {
+    /// High band frequency in Hz
+    pub high_freq: f64,
+    /// High band amplitude from 0.0 to 1.0
+    pub high_amp: f64,
+    /// Low band frequency in Hz
+    pub low_freq: f64,
+    /// Low band amplitude from 0.0 to 1.0
+    pub low_amp: f64,
+}
+
+impl HdRumble {
+    /// Decode the 4 byte HD rumble encoding for a single actuator. The high
+    /// band frequency and low band amplitude are 9 bits each, so they borrow
+    /// a bit from the neighboring byte.
+    pub fn from_bytes(data: [u8; 4]) -> Self {
+        let high_freq_code = data[0] as u16 | ((data[1] as u16 & 0x01) << 8);
+        let high_amp_code = data[1] >> 1;
+        let low_freq_code = data[2] & 0x7F;
+        let low_amp_code = data[3].saturating_sub(0x40) * 2 + (data[2] >> 7);
+
+        Self {
+            high_freq: decode_frequency(high_freq_code as f64 / 4.0 + 0x60 as f64),
+            high_amp: decode_amplitude(high_amp_code),
+            low_freq: decode_frequency(low_freq_code as f64 + 0x40 as f64),
+            low_amp: decode_amplitude(low_amp_code),
+        }
+    }
+
+    /// Decode the left and right actuator rumble data from the given output
+    /// report. Returns `None` if the report does not contain rumble data.
+    pub fn from_output_report(report: &[u8]) -> Option<(Self, Self)> {
+        let report_id = *report.first()?;
+        if report_id != OUTPUT_RUMBLE_AND_SUBCOMMAND && report_id != OUTPUT_RUMBLE_ONLY {
+            return None;
+        }
+        let left_start = RUMBLE_DATA_OFFSET;
+        let right_start = left_start + RUMBLE_DATA_SIZE;
+        let left = report.get(left_start..right_start)?;
+        let right = report.get(right_start..right_start + RUMBLE_DATA_SIZE)?;
+
+        Some((
+            Self::from_bytes(left.try_into().ok()?),
+            Self::from_bytes(right.try_into().ok()?),
+        ))
+    }
+}
+
+/// Tunable mapping from HD rumble bands to strong and weak motor intensities
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct RumbleMapping {
+    /// Bands below this frequency in Hz drive the strong motor, all others
+    /// drive the weak motor.
+    pub crossover_hz: f64,
+    /// Multiplier applied to low band amplitudes
+    pub low_gain: f64,
+    /// Multiplier applied to high band amplitudes
+    pub high_gain: f64,
+    /// Amplitudes below this value are treated as silence
+    pub deadzone: f64,
+}
+
+impl Default for RumbleMapping {
+    fn default() -> Self {
+        Self {
+            crossover_hz: 240.0,
+            low_gain: 1.0,
+            high_gain: 1.0,
+            deadzone: 0.01,
+        }
+    }
+}
+
+impl RumbleMapping {
+    /// Translate the given left and right actuator rumble into strong and
+    /// weak motor intensities from 0-255.
+    pub fn translate(&self, left: &HdRumble, right: &HdRumble) -> (u8, u8) {
+        let mut strong: f64 = 0.0;
+        let mut weak: f64 = 0.0;
+        for rumble in [left, right] {
+            let bands = [
+                (rumble.low_freq, rumble.low_amp, self.low_gain),
+                (rumble.high_freq, rumble.high_amp, self.high_gain),
+            ];
+            for (freq, amp, gain) in bands {
+                if amp < self.deadzone {
+                    continue;
+                }
+                let amp = (amp * gain).clamp(0.0, 1.0);
+                if freq < self.crossover_hz {
+                    strong = strong.max(amp);
+                } else {
+                    weak = weak.max(amp);
+                }
+            }
+        }
+
+        ((strong * 255.0).round() as u8, (weak * 255.0).round() as u8)
+    }
+}
+
+/// Decode the given frequency code into a frequency in Hz
+fn decode_frequency(code: f64) -> f64 {
+    10.0 * 2f64.powf(code / 32.0)
+}
+
+/// Decode the given amplitude code (0-100) into an amplitude from 0.0 to 1.0.
+/// The encoding is logarithmic in two segments; codes below the first segment
+/// are approximated linearly.
+fn decode_amplitude(code: u8) -> f64 {
+    let code = code as f64;
+    let amp = if code >= 32.0 {
+        2f64.powf(code / 32.0) / 8.7
+    } else if code >= 16.0 {
+        2f64.powf(code / 16.0) / 17.0
+    } else {
+        code / 16.0 * (2.0 / 17.0)
+    };
+    amp.clamp(0.0, 1.0)
+}
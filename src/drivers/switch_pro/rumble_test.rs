@@ -0,0 +1,41 @@
+use crate::drivers::switch_pro::rumble::{HdRumble, RumbleMapping, OUTPUT_RUMBLE_ONLY};
+
+/// Neutral rumble data for a single actuator (320Hz/160Hz with no amplitude)
+const NEUTRAL: [u8; 4] = [0x00, 0x01, 0x40, 0x40];
+
+#[test]
+fn test_decode_neutral() {
+    let rumble = HdRumble::from_bytes(NEUTRAL);
+    assert!((rumble.high_freq - 320.0).abs() < 0.01);
+    assert!((rumble.low_freq - 160.0).abs() < 0.01);
+    assert_eq!(rumble.high_amp, 0.0);
+    assert_eq!(rumble.low_amp, 0.0);
+
+    let (strong, weak) = RumbleMapping::default().translate(&rumble, &rumble);
+    assert_eq!((strong, weak), (0, 0));
+}
+
+#[test]
+fn test_translate_bands() {
+    // Left actuator: full amplitude on the low band only
+    let left = HdRumble::from_bytes([0x00, 0x01, 0x40, 0x72]);
+    // Right actuator: full amplitude on the high band only
+    let right = HdRumble::from_bytes([0x00, 0xC9, 0x40, 0x40]);
+    assert!(left.low_amp > 0.99);
+    assert!(right.high_amp > 0.99);
+
+    let mut report = vec![OUTPUT_RUMBLE_ONLY, 0x00];
+    report.extend_from_slice(&[0x00, 0x01, 0x40, 0x72]);
+    report.extend_from_slice(&[0x00, 0xC9, 0x40, 0x40]);
+    assert_eq!(HdRumble::from_output_report(&report), Some((left, right)));
+
+    let (strong, weak) = RumbleMapping::default().translate(&left, &right);
+    assert_eq!((strong, weak), (255, 255));
+
+    let mapping = RumbleMapping {
+        high_gain: 0.5,
+        ..Default::default()
+    };
+    let (strong, weak) = mapping.translate(&left, &right);
+    assert_eq!((strong, weak), (255, 128));
+}
@@ -0,0 +1,212 @@
+use std::{
+    collections::VecDeque,
+    error::Error,
+    io,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use hidapi::HidDevice;
+
+/// Maximum number of output reports waiting to be written to a device
+const QUEUE_SIZE: usize = 32;
+/// Minimum time between two output reports written to a device
+const WRITE_INTERVAL: Duration = Duration::from_millis(4);
+/// Maximum number of attempts to write an output report while the device is
+/// busy
+const WRITE_RETRY_MAX: u32 = 5;
+
+/// Transport used by drivers to exchange reports with a HID device. Drivers
+/// use this instead of a [HidDevice] directly so they can be tested against
+/// canned report byte sequences.
+pub trait HidTransport: Send {
+    /// Read an input report into the given buffer, waiting up to the given
+    /// timeout in milliseconds. Returns the number of bytes read, or 0 if the
+    /// timeout was reached.
+    fn read_timeout(
+        &self,
+        buf: &mut [u8],
+        timeout: i32,
+    ) -> Result<usize, Box<dyn Error + Send + Sync>>;
+
+    /// Write an output report to the device. Returns the number of bytes
+    /// written.
+    fn write(&self, data: &[u8]) -> Result<usize, Box<dyn Error + Send + Sync>>;
+
+    /// Write a rumble output report to the device. Only the latest rumble
+    /// state matters, so transports may replace a rumble report that has not
+    /// been written yet instead of writing every report.
+    fn write_rumble(&self, data: &[u8]) -> Result<usize, Box<dyn Error + Send + Sync>> {
+        self.write(data)
+    }
+}
+
+impl HidTransport for HidDevice {
+    fn read_timeout(
+        &self,
+        buf: &mut [u8],
+        timeout: i32,
+    ) -> Result<usize, Box<dyn Error + Send + Sync>> {
+        Ok(HidDevice::read_timeout(self, buf, timeout)?)
+    }
+
+    fn write(&self, data: &[u8]) -> Result<usize, Box<dyn Error + Send + Sync>> {
+        Ok(HidDevice::write(self, data)?)
+    }
+}
+
+/// Transport that queues output reports (e.g. rumble or feature reports) and
+/// writes them to the device as soon as the rate limit allows, or in between
+/// reads. This serializes writes with reads, retries writes while the device
+/// is busy (EAGAIN), and limits how often reports are written.
+///
+/// Rumble reports are kept separately from other reports. A pending rumble
+/// report is replaced by newer ones, so a flood of rumble reports can never
+/// push one-shot reports like LED or feature commands out of the queue.
+pub struct QueuedTransport<T: HidTransport> {
+    device: T,
+    queue: Mutex<OutputQueue>,
+}
+
+/// Output reports waiting to be written
+#[derive(Debug, Default)]
+struct OutputQueue {
+    /// Reports that must be delivered, in order
+    reports: VecDeque<Vec<u8>>,
+    /// Latest rumble report that has not been written yet
+    rumble: Option<Vec<u8>>,
+    /// Time the last report was written to the device
+    last_write: Option<Instant>,
+    /// Number of failed attempts to write the next report
+    retries: u32,
+    /// Permanent write error of a queued report, returned by the next write
+    error: Option<String>,
+}
+
+impl OutputQueue {
+    /// Returns the next report to write. Reports that must be delivered are
+    /// written before rumble.
+    fn front(&self) -> Option<&Vec<u8>> {
+        self.reports.front().or(self.rumble.as_ref())
+    }
+
+    /// Remove the next report to write
+    fn pop_front(&mut self) {
+        self.retries = 0;
+        if self.reports.pop_front().is_none() {
+            self.rumble = None;
+        }
+    }
+}
+
+impl<T: HidTransport> QueuedTransport<T> {
+    pub fn new(device: T) -> Self {
+        Self {
+            device,
+            queue: Mutex::new(OutputQueue::default()),
+        }
+    }
+
+    /// Write queued reports to the device. Reports that cannot be written yet
+    /// due to rate limiting or a busy device stay queued. Returns true if
+    /// reports are still waiting to be written.
+    pub fn flush(&self) -> bool {
+        let Ok(mut queue) = self.queue.lock() else {
+            return false;
+        };
+        self.flush_queue(&mut queue)
+    }
+
+    fn flush_queue(&self, queue: &mut OutputQueue) -> bool {
+        while let Some(report) = queue.front() {
+            if queue
+                .last_write
+                .is_some_and(|last_write| last_write.elapsed() < WRITE_INTERVAL)
+            {
+                return true;
+            }
+            match self.device.write(report) {
+                Ok(_) => {
+                    queue.last_write = Some(Instant::now());
+                    queue.pop_front();
+                }
+                Err(e) if is_busy(e.as_ref()) && queue.retries < WRITE_RETRY_MAX => {
+                    queue.retries += 1;
+                    log::debug!(
+                        "Device busy, retrying output report ({}/{WRITE_RETRY_MAX})",
+                        queue.retries
+                    );
+                    return true;
+                }
+                Err(e) => {
+                    log::error!("Failed to write output report: {e}");
+                    queue.error = Some(e.to_string());
+                    queue.pop_front();
+                }
+            }
+        }
+        false
+    }
+
+    /// Queue the given report and write it right away if the rate limit
+    /// allows. Returns any permanent error from writing queued reports.
+    fn queue_report(
+        &self,
+        data: &[u8],
+        rumble: bool,
+    ) -> Result<usize, Box<dyn Error + Send + Sync>> {
+        let Ok(mut queue) = self.queue.lock() else {
+            return Err("Output queue is poisoned".into());
+        };
+        if rumble {
+            queue.rumble = Some(data.to_vec());
+        } else {
+            if queue.reports.len() >= QUEUE_SIZE {
+                return Err("Output queue is full".into());
+            }
+            queue.reports.push_back(data.to_vec());
+        }
+        self.flush_queue(&mut queue);
+        if let Some(error) = queue.error.take() {
+            return Err(format!("Failed to write output report: {error}").into());
+        }
+        Ok(data.len())
+    }
+}
+
+impl<T: HidTransport> HidTransport for QueuedTransport<T> {
+    fn read_timeout(
+        &self,
+        buf: &mut [u8],
+        timeout: i32,
+    ) -> Result<usize, Box<dyn Error + Send + Sync>> {
+        // Don't wait longer than the write interval if reports are waiting to
+        // be written, so they are not delayed until the next input report.
+        let pending = self.flush();
+        let interval = WRITE_INTERVAL.as_millis() as i32;
+        let timeout = if pending && (timeout < 0 || timeout > interval) {
+            interval
+        } else {
+            timeout
+        };
+        self.device.read_timeout(buf, timeout)
+    }
+
+    fn write(&self, data: &[u8]) -> Result<usize, Box<dyn Error + Send + Sync>> {
+        self.queue_report(data, false)
+    }
+
+    fn write_rumble(&self, data: &[u8]) -> Result<usize, Box<dyn Error + Send + Sync>> {
+        self.queue_report(data, true)
+    }
+}
+
+/// Returns true if the given write error means the device is temporarily busy
+fn is_busy(error: &(dyn Error + Send + Sync)) -> bool {
+    if let Some(error) = error.downcast_ref::<io::Error>() {
+        return error.kind() == io::ErrorKind::WouldBlock;
+    }
+    error
+        .to_string()
+        .contains("Resource temporarily unavailable")
+}
@@ -0,0 +1,49 @@
+use std::{error::Error, sync::Arc, thread, time::Duration};
+
+use crate::drivers::{
+    mock_transport::MockTransport,
+    transport::{HidTransport, QueuedTransport},
+};
+
+#[tokio::test]
+async fn test_queued_transport_rate_limits_writes() -> Result<(), Box<dyn Error>> {
+    let mock = Arc::new(MockTransport::new(vec![]));
+    let transport = QueuedTransport::new(mock.clone());
+
+    // The first report is written right away, and the next one waits for the
+    // rate limit
+    transport.write(&[0x01])?;
+    transport.write(&[0x02])?;
+    assert_eq!(mock.written(), vec![vec![0x01]]);
+
+    // Queued reports are written in between reads
+    thread::sleep(Duration::from_millis(10));
+    let mut buf = [0; 64];
+    transport.read_timeout(&mut buf, 0)?;
+    assert_eq!(mock.written(), vec![vec![0x01], vec![0x02]]);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_queued_transport_coalesces_rumble() -> Result<(), Box<dyn Error>> {
+    let mock = Arc::new(MockTransport::new(vec![]));
+    let transport = QueuedTransport::new(mock.clone());
+
+    // Only the latest pending rumble report is written, and reports that must
+    // be delivered are never dropped
+    transport.write(&[0x01])?;
+    transport.write_rumble(&[0x10])?;
+    transport.write(&[0x02])?;
+    transport.write_rumble(&[0x11])?;
+    transport.write_rumble(&[0x12])?;
+
+    let mut buf = [0; 64];
+    for _ in 0..3 {
+        thread::sleep(Duration::from_millis(10));
+        transport.read_timeout(&mut buf, 0)?;
+    }
+    assert_eq!(mock.written(), vec![vec![0x01], vec![0x02], vec![0x12]]);
+
+    Ok(())
+}
@@ -0,0 +1,115 @@
+use std::{collections::HashMap, error::Error, ffi::CString, time::Instant};
+
+use crate::drivers::transport::{HidTransport, QueuedTransport};
+
+use super::event::{AnalogInput, Event};
+
+/// Vendor ID of current Wooting keyboards
+pub const VID: u16 = 0x31e3;
+/// Vendor ID of early Wooting keyboards
+pub const VID_LEGACY: u16 = 0x03eb;
+/// Usage page of the interface that reports analog key values
+pub const USAGE_PAGE_ANALOG: u16 = 0xff54;
+/// Size of the HID packet
+const PACKET_SIZE: usize = 64;
+/// Timeout in milliseconds for reading an HID packet
+const HID_TIMEOUT: i32 = 10;
+/// Maximum analog value of a key
+pub const ANALOG_MAX: f64 = 255.0;
+
+/// Returns true if the given interface reports analog key values
+pub fn is_supported(vendor_id: u16, usage_page: u16) -> bool {
+    (vendor_id == VID || vendor_id == VID_LEGACY) && usage_page == USAGE_PAGE_ANALOG
+}
+
+pub struct Driver {
+    /// Analog values of all keys that are currently pressed
+    state: HashMap<u16, u8>,
+    device: Box<dyn HidTransport>,
+    /// Time the last input report was read from the device
+    last_report: Instant,
+}
+
+impl Driver {
+    pub fn new(path: String) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        let fmtpath = path.clone();
+        let path = CString::new(path)?;
+        let api = hidapi::HidApi::new()?;
+        let device = api.open_path(&path)?;
+        let info = device.get_device_info()?;
+        if !is_supported(info.vendor_id(), info.usage_page()) {
+            return Err(format!("Device '{fmtpath}' is not an analog keyboard interface").into());
+        }
+
+        Ok(Self::from_transport(Box::new(QueuedTransport::new(device))))
+    }
+
+    /// Create a driver that reads reports from the given transport
+    pub fn from_transport(device: Box<dyn HidTransport>) -> Self {
+        Self {
+            state: HashMap::new(),
+            device,
+            last_report: Instant::now(),
+        }
+    }
+
+    /// Poll the device and read input reports
+    pub fn poll(&mut self) -> Result<Vec<Event>, Box<dyn Error + Send + Sync>> {
+        // Read data from the device into a buffer
+        let mut buf = [0; PACKET_SIZE];
+        let bytes_read = self.device.read_timeout(&mut buf[..], HID_TIMEOUT)?;
+        self.last_report = Instant::now();
+        if bytes_read == 0 {
+            return Ok(Vec::new());
+        }
+
+        // Handle the incoming input report
+        let events = self.handle_input_report(&buf[..bytes_read]);
+
+        Ok(events)
+    }
+
+    /// Returns the time the last input report was read from the device
+    pub fn last_report_time(&self) -> Instant {
+        self.last_report
+    }
+
+    /// Parses the analog report and updates the internal key state. Reports
+    /// list every pressed key as a big endian key code followed by its
+    /// analog value, terminated by a key with a value of 0.
+    fn handle_input_report(&mut self, buf: &[u8]) -> Vec<Event> {
+        let mut state = HashMap::new();
+        for key in buf.chunks_exact(3) {
+            let code = u16::from_be_bytes([key[0], key[1]]);
+            let value = key[2];
+            if value == 0 {
+                break;
+            }
+            state.insert(code, value);
+        }
+
+        // Update the state
+        let old_state = std::mem::replace(&mut self.state, state);
+
+        // Translate the state into a stream of input events
+        let mut events = Vec::new();
+        for (code, value) in self.state.iter() {
+            if old_state.get(code) != Some(value) {
+                events.push(Event::Analog(AnalogInput {
+                    code: *code,
+                    value: *value,
+                }));
+            }
+        }
+        for code in old_state.keys() {
+            if !self.state.contains_key(code) {
+                events.push(Event::Analog(AnalogInput {
+                    code: *code,
+                    value: 0,
+                }));
+            }
+        }
+
+        events
+    }
+}
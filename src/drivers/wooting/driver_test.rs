@@ -0,0 +1,37 @@
+use std::error::Error;
+
+use crate::drivers::{
+    mock_transport::MockTransport,
+    wooting::{driver::Driver, event::Event},
+};
+
+/// Returns the analog value reported for the given key code in the events
+fn analog_value(events: &[Event], code: u16) -> Option<u8> {
+    events.iter().find_map(|event| match event {
+        Event::Analog(input) if input.code == code => Some(input.value),
+        _ => None,
+    })
+}
+
+#[tokio::test]
+async fn test_wooting_analog_reports() -> Result<(), Box<dyn Error>> {
+    let mut report = vec![0; 48];
+    report[..6].copy_from_slice(&[0x00, 0x1a, 0x80, 0x00, 0x04, 0xff]); // W and A
+    let mut release = vec![0; 48];
+    release[..3].copy_from_slice(&[0x00, 0x1a, 0x40]); // W only
+    let transport = MockTransport::new(vec![report, release]);
+    let mut driver = Driver::from_transport(Box::new(transport));
+
+    let events = driver.poll()?;
+    assert_eq!(events.len(), 2);
+    assert_eq!(analog_value(&events, 0x1a), Some(0x80));
+    assert_eq!(analog_value(&events, 0x04), Some(0xff));
+
+    // Keys missing from the report are released
+    let events = driver.poll()?;
+    assert_eq!(events.len(), 2);
+    assert_eq!(analog_value(&events, 0x1a), Some(0x40));
+    assert_eq!(analog_value(&events, 0x04), Some(0));
+
+    Ok(())
+}
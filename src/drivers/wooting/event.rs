@@ -0,0 +1,13 @@
+/// Events that can be emitted by an analog keyboard
+#[derive(Clone, Debug)]
+pub enum Event {
+    Analog(AnalogInput),
+}
+
+/// Analog value of a single key, identified by its HID keyboard usage
+/// (e.g. 0x1a for the W key). A value of 0 means the key is released.
+#[derive(Clone, Debug)]
+pub struct AnalogInput {
+    pub code: u16,
+    pub value: u8,
+}
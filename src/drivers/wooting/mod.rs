@@ -0,0 +1,4 @@
+pub mod driver;
+#[cfg(test)]
+mod driver_test;
+pub mod event;
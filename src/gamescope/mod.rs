@@ -0,0 +1,176 @@
+use std::{error::Error, fs, thread, time::Duration};
+
+use tokio::sync::broadcast;
+use x11rb::{
+    connection::Connection,
+    properties::WmClass,
+    protocol::{
+        xproto::{Atom, AtomEnum, ChangeWindowAttributesAux, ConnectionExt, EventMask, Window},
+        Event,
+    },
+    rust_connection::RustConnection,
+};
+
+use crate::input::{auto_profile::FocusedApp, manager::ManagerCommand};
+
+/// Directory containing the sockets of running X11 displays
+const X11_SOCKET_DIR: &str = "/tmp/.X11-unix";
+/// Root window atom gamescope sets to the appid of the focused app
+const FOCUSED_APP_ATOM: &[u8] = b"GAMESCOPE_FOCUSED_APP";
+/// Root window atom gamescope sets to the id of the focused window
+const FOCUSED_WINDOW_ATOM: &[u8] = b"GAMESCOPE_FOCUSED_WINDOW";
+/// Appid gamescope reports when Steam itself is focused
+const STEAM_APP_ID: u32 = 769;
+/// Time to wait before looking for gamescope again if it is not running
+const RETRY_INTERVAL: Duration = Duration::from_secs(5);
+
+/// The [FocusWatcher] watches the root window of gamescope's Xwayland
+/// display for changes to the focused appid and window and notifies the input
+/// manager so the matching profile can be loaded automatically.
+#[derive(Debug)]
+pub struct FocusWatcher {
+    manager: broadcast::Sender<ManagerCommand>,
+    app: FocusedApp,
+}
+
+impl FocusWatcher {
+    pub fn new(manager: broadcast::Sender<ManagerCommand>) -> Self {
+        Self {
+            manager,
+            app: FocusedApp::default(),
+        }
+    }
+
+    /// Start watching for focused app changes in a new thread
+    pub fn spawn(mut self) {
+        let result = thread::Builder::new()
+            .name("gamescope".into())
+            .spawn(move || self.run());
+        if let Err(e) = result {
+            log::error!("Failed to start gamescope focus watcher: {e:?}");
+        }
+    }
+
+    /// Watch for focused app changes. If gamescope is not running or exits,
+    /// it is looked for again periodically.
+    fn run(&mut self) {
+        loop {
+            for display in get_displays() {
+                if let Err(e) = self.watch_display(&display) {
+                    log::debug!("Stopped watching gamescope display {display}: {e:?}");
+                }
+            }
+            self.set_app(FocusedApp::default());
+            thread::sleep(RETRY_INTERVAL);
+        }
+    }
+
+    /// Watch the given X11 display for focused app changes. Returns an error
+    /// if the display is not a gamescope display or the connection is lost.
+    fn watch_display(&mut self, display: &str) -> Result<(), Box<dyn Error>> {
+        let (conn, screen_num) = RustConnection::connect(Some(display))?;
+        let root = conn.setup().roots[screen_num].root;
+        let app_atom = conn.intern_atom(true, FOCUSED_APP_ATOM)?.reply()?.atom;
+        if app_atom == u32::from(AtomEnum::NONE) {
+            return Err(format!("Display {display} is not a gamescope display").into());
+        }
+        let window_atom = conn.intern_atom(false, FOCUSED_WINDOW_ATOM)?.reply()?.atom;
+        log::info!("Watching gamescope display {display} for focused app changes");
+
+        // Listen for property changes on the root window
+        let attributes = ChangeWindowAttributesAux::new().event_mask(EventMask::PROPERTY_CHANGE);
+        conn.change_window_attributes(root, &attributes)?;
+        conn.flush()?;
+
+        let app = get_focused_app(&conn, root, app_atom, window_atom)?;
+        self.set_app(app);
+        loop {
+            let Event::PropertyNotify(event) = conn.wait_for_event()? else {
+                continue;
+            };
+            if event.atom != app_atom && event.atom != window_atom {
+                continue;
+            }
+            let app = get_focused_app(&conn, root, app_atom, window_atom)?;
+            self.set_app(app);
+        }
+    }
+
+    /// Notify the input manager if the focused app changed
+    fn set_app(&mut self, app: FocusedApp) {
+        if self.app == app {
+            return;
+        }
+        log::debug!("Focused app changed: {app:?}");
+        self.app = app.clone();
+        if let Err(e) = self.manager.send(ManagerCommand::FocusedAppChanged { app }) {
+            log::error!("Failed to send focused app change: {e:?}");
+        }
+    }
+}
+
+/// Returns the appid and window class of the focused app from the given
+/// gamescope root window. The appid is [None] if no app or Steam itself is
+/// focused.
+fn get_focused_app(
+    conn: &RustConnection,
+    root: Window,
+    app_atom: Atom,
+    window_atom: Atom,
+) -> Result<FocusedApp, Box<dyn Error>> {
+    let app_id = get_cardinal(conn, root, app_atom)?;
+    let app_id = app_id.filter(|id| *id != 0 && *id != STEAM_APP_ID);
+
+    // The window class is used to find profiles for non-Steam games
+    let window = get_cardinal(conn, root, window_atom)?.filter(|id| *id != 0);
+    let window_class = match window {
+        Some(window) => get_window_class(conn, window),
+        None => None,
+    };
+
+    Ok(FocusedApp {
+        app_id,
+        window_class,
+    })
+}
+
+/// Returns the value of the given cardinal property on the given window
+fn get_cardinal(
+    conn: &RustConnection,
+    window: Window,
+    atom: Atom,
+) -> Result<Option<u32>, Box<dyn Error>> {
+    let reply = conn
+        .get_property(false, window, atom, AtomEnum::CARDINAL, 0, 1)?
+        .reply()?;
+    Ok(reply.value32().and_then(|mut values| values.next()))
+}
+
+/// Returns the class from the WM_CLASS property of the given window. The
+/// window may already be gone, so any errors are ignored.
+fn get_window_class(conn: &RustConnection, window: Window) -> Option<String> {
+    let class = WmClass::get(conn, window).ok()?.reply().ok()??;
+    let class = String::from_utf8_lossy(class.class()).to_string();
+    if class.is_empty() {
+        return None;
+    }
+    Some(class)
+}
+
+/// Returns the names of all running X11 displays (e.g. ":0")
+fn get_displays() -> Vec<String> {
+    let Ok(entries) = fs::read_dir(X11_SOCKET_DIR) else {
+        return Vec::new();
+    };
+    let mut displays: Vec<String> = entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            let number = name.strip_prefix('X')?;
+            number.parse::<u32>().ok()?;
+            Some(format!(":{number}"))
+        })
+        .collect();
+    displays.sort();
+    displays
+}
@@ -0,0 +1,214 @@
+use std::{
+    collections::{HashMap, HashSet},
+    str::FromStr,
+    time::{Duration, Instant},
+};
+
+use crate::config::AccessibilityConfig;
+
+use super::{
+    capability::{Capability, Gamepad, GamepadAxis, Mouse, MouseButton},
+    event::{native::NativeEvent, value::InputValue},
+};
+
+/// Interval at which timed accessibility features (dwell click and switch
+/// scanning) are updated.
+pub const TICK_INTERVAL: Duration = Duration::from_millis(50);
+/// Default time in milliseconds between advancing to the next scan target
+const DEFAULT_SCAN_INTERVAL_MS: u64 = 1000;
+
+/// The [Accessibility] filter applies the accessibility options of a device
+/// profile to input events. It supports:
+///
+/// - Debouncing: presses of a button shortly after it was released are
+///   dropped to filter out tremors.
+/// - Input scaling: mouse motion and gamepad axes are scaled down to slow
+///   input.
+/// - Dwell click: a mouse click is emitted when the pointer stops moving.
+/// - Switch scanning: a single switch emits whichever target capability is
+///   currently selected, with selection cycling through the targets.
+#[derive(Debug)]
+pub struct Accessibility {
+    config: AccessibilityConfig,
+    /// Time each button was last released, used for debouncing
+    released_at: HashMap<Capability, Instant>,
+    /// Buttons whose press was dropped, so their release is also dropped
+    debounced: HashSet<Capability>,
+    /// Time to emit a dwell click if the pointer does not move again
+    dwell_deadline: Option<Instant>,
+    /// Switch used for single-switch scanning
+    scan_switch: Option<Capability>,
+    /// Capabilities cycled through with single-switch scanning
+    scan_targets: Vec<Capability>,
+    /// Index of the currently selected scan target
+    scan_index: usize,
+    /// Time the scan selection last advanced
+    scan_advanced_at: Instant,
+    /// Scan target currently held down by the switch
+    scan_held: Option<Capability>,
+}
+
+impl Accessibility {
+    pub fn new(config: AccessibilityConfig) -> Self {
+        let scan = config.switch_scan.as_ref();
+        let scan_switch = scan.and_then(|scan| parse_capability(&scan.switch));
+        let scan_targets = scan
+            .map(|scan| {
+                scan.targets
+                    .iter()
+                    .filter_map(|cap| parse_capability(cap))
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Self {
+            config,
+            released_at: HashMap::new(),
+            debounced: HashSet::new(),
+            dwell_deadline: None,
+            scan_switch,
+            scan_targets,
+            scan_index: 0,
+            scan_advanced_at: Instant::now(),
+            scan_held: None,
+        }
+    }
+
+    /// Returns true if any timed features are enabled that need to be
+    /// updated every [TICK_INTERVAL].
+    pub fn needs_tick(&self) -> bool {
+        self.config.dwell_click_ms.is_some() || !self.scan_targets.is_empty()
+    }
+
+    /// Apply the accessibility filters to the given source event. Returns
+    /// [None] if the event should be dropped.
+    pub fn process(&mut self, mut event: NativeEvent) -> Option<NativeEvent> {
+        let cap = event.as_capability();
+
+        // Replace switch events with the selected scan target
+        if self.scan_switch.as_ref() == Some(&cap) {
+            return self.process_switch(event);
+        }
+
+        if self.debounce(&cap, &event) {
+            log::trace!("Debounced event: {event:?}");
+            return None;
+        }
+
+        if let Some(scale) = self.config.input_scale {
+            if is_scalable(&cap) {
+                if let InputValue::Vector2 { x, y } = event.get_value() {
+                    event.set_value(InputValue::Vector2 {
+                        x: x.map(|x| x * scale),
+                        y: y.map(|y| y * scale),
+                    });
+                }
+            }
+        }
+
+        Some(event)
+    }
+
+    /// Returns true if the given button event should be dropped because the
+    /// button was released too recently.
+    fn debounce(&mut self, cap: &Capability, event: &NativeEvent) -> bool {
+        let Some(debounce_ms) = self.config.debounce_ms else {
+            return false;
+        };
+        let InputValue::Bool(pressed) = event.get_value() else {
+            return false;
+        };
+        let now = Instant::now();
+
+        if !pressed {
+            if self.debounced.remove(cap) {
+                return true;
+            }
+            self.released_at.insert(cap.clone(), now);
+            return false;
+        }
+
+        let window = Duration::from_millis(debounce_ms);
+        let bounced = self
+            .released_at
+            .get(cap)
+            .is_some_and(|released| now.duration_since(*released) < window);
+        if bounced {
+            self.debounced.insert(cap.clone());
+        }
+        bounced
+    }
+
+    /// Translate a switch event into an event for the selected scan target
+    fn process_switch(&mut self, event: NativeEvent) -> Option<NativeEvent> {
+        if event.pressed() {
+            let target = self.scan_targets.get(self.scan_index)?.clone();
+            self.scan_held = Some(target.clone());
+            return Some(NativeEvent::new(target, InputValue::Bool(true)));
+        }
+        let target = self.scan_held.take()?;
+        Some(NativeEvent::new(target, InputValue::Bool(false)))
+    }
+
+    /// Called when pointer motion is written to a target device. Restarts
+    /// the dwell click timer.
+    pub fn on_pointer_motion(&mut self) {
+        let Some(dwell_ms) = self.config.dwell_click_ms else {
+            return;
+        };
+        self.dwell_deadline = Some(Instant::now() + Duration::from_millis(dwell_ms));
+    }
+
+    /// Update timed features. Returns the events to emit, if any.
+    pub fn tick(&mut self) -> Vec<NativeEvent> {
+        let now = Instant::now();
+        let mut events = Vec::new();
+
+        // Click once the pointer has rested for the dwell time
+        if self.dwell_deadline.is_some_and(|deadline| now >= deadline) {
+            self.dwell_deadline = None;
+            log::debug!("Dwell click");
+            let click = Capability::Mouse(Mouse::Button(MouseButton::Left));
+            events.push(NativeEvent::new(click.clone(), InputValue::Bool(true)));
+            events.push(NativeEvent::new(click, InputValue::Bool(false)));
+        }
+
+        // Advance to the next scan target unless the switch is held
+        if !self.scan_targets.is_empty() && self.scan_held.is_none() {
+            let interval_ms = self
+                .config
+                .switch_scan
+                .as_ref()
+                .and_then(|scan| scan.interval_ms)
+                .unwrap_or(DEFAULT_SCAN_INTERVAL_MS);
+            if now.duration_since(self.scan_advanced_at) >= Duration::from_millis(interval_ms) {
+                self.scan_advanced_at = now;
+                self.scan_index = (self.scan_index + 1) % self.scan_targets.len();
+                log::debug!("Scan target: {:?}", self.scan_targets[self.scan_index]);
+            }
+        }
+
+        events
+    }
+}
+
+/// Returns true if the given capability is slowed by input scaling
+fn is_scalable(cap: &Capability) -> bool {
+    match cap {
+        Capability::Mouse(Mouse::Motion) => true,
+        Capability::Gamepad(Gamepad::Axis(GamepadAxis::LeftStick)) => true,
+        Capability::Gamepad(Gamepad::Axis(GamepadAxis::RightStick)) => true,
+        _ => false,
+    }
+}
+
+/// Parse the given capability string (e.g. "Gamepad:Button:South")
+fn parse_capability(value: &str) -> Option<Capability> {
+    match Capability::from_str(value) {
+        Ok(cap) => Some(cap),
+        Err(_) => {
+            log::error!("Invalid accessibility capability: {value}");
+            None
+        }
+    }
+}
@@ -0,0 +1,191 @@
+use std::{fs, path::Path};
+
+use glob_match::glob_match;
+
+use crate::config::{AppProfileMap, AppProfileMapping, DeviceProfile};
+
+/// The app focused in gamescope
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FocusedApp {
+    /// Steam appid of the focused app
+    pub app_id: Option<u32>,
+    /// Window class of the focused window
+    pub window_class: Option<String>,
+}
+
+impl FocusedApp {
+    /// Returns true if no app is focused
+    pub fn is_empty(&self) -> bool {
+        self.app_id.is_none() && self.window_class.is_none()
+    }
+}
+
+/// Returns the path to the device profile that should be automatically loaded
+/// on the composite device with the given name for the focused app and dock
+/// state. Profiles for the focused app take precedence over profiles for the
+/// dock state.
+pub fn find_auto_profile(app: &FocusedApp, docked: bool, device: &str) -> Option<String> {
+    let maps = load_app_profile_maps();
+    let mappings: Vec<&AppProfileMapping> = maps
+        .iter()
+        .flat_map(|map| map.mapping.iter())
+        .filter(|mapping| mapping_applies(mapping, docked, device))
+        .collect();
+
+    find_profile_for_app(app, &mappings).or_else(|| {
+        mappings
+            .iter()
+            .find(|mapping| {
+                mapping.app_id.is_none()
+                    && mapping.window_class.is_none()
+                    && mapping.docked == Some(docked)
+            })
+            .and_then(|mapping| find_profile(&mapping.profile))
+    })
+}
+
+/// Returns true if any app profile mapping depends on the dock state
+pub fn uses_dock_state() -> bool {
+    load_app_profile_maps()
+        .iter()
+        .flat_map(|map| map.mapping.iter())
+        .any(|mapping| mapping.docked.is_some())
+}
+
+/// Returns true if the conditions of the given mapping other than the app
+/// match the dock state and composite device name.
+fn mapping_applies(mapping: &AppProfileMapping, docked: bool, device: &str) -> bool {
+    if mapping.docked.is_some_and(|value| value != docked) {
+        return false;
+    }
+    mapping
+        .device
+        .as_ref()
+        .map_or(true, |pattern| glob_match(pattern, device))
+}
+
+/// Returns the path to the device profile that should be automatically loaded
+/// when the given app is focused. Steam appids are looked up in the given app
+/// profile mappings first, then in the `app_ids` field of device profiles. If
+/// no profile is found, the window class is looked up in the mappings so
+/// non-Steam games can also have profiles.
+fn find_profile_for_app(app: &FocusedApp, mappings: &[&AppProfileMapping]) -> Option<String> {
+    if app.is_empty() {
+        return None;
+    }
+
+    if let Some(app_id) = app.app_id {
+        let profile = mappings
+            .iter()
+            .find(|mapping| mapping.app_id == Some(app_id))
+            .and_then(|mapping| find_profile(&mapping.profile));
+        if profile.is_some() {
+            return profile;
+        }
+
+        let profile = load_device_profiles()
+            .into_iter()
+            .find(|(_, profile)| {
+                profile
+                    .app_ids
+                    .as_ref()
+                    .is_some_and(|ids| ids.contains(&app_id))
+            })
+            .map(|(path, _)| path);
+        if profile.is_some() {
+            log::debug!("Found profile for app {app_id}: {profile:?}");
+            return profile;
+        }
+    }
+
+    if let Some(window_class) = app.window_class.as_ref() {
+        return mappings
+            .iter()
+            .find(|mapping| {
+                mapping
+                    .window_class
+                    .as_ref()
+                    .is_some_and(|pattern| glob_match(pattern, window_class))
+            })
+            .and_then(|mapping| find_profile(&mapping.profile));
+    }
+
+    None
+}
+
+/// Returns the path to the device profile with the given name. If the given
+/// name is a path to an existing file, it is returned as is.
+fn find_profile(name: &str) -> Option<String> {
+    if name.ends_with(".yaml") && Path::new(name).is_file() {
+        return Some(name.to_string());
+    }
+    let profile = load_device_profiles()
+        .into_iter()
+        .find(|(_, profile)| profile.name == name)
+        .map(|(path, _)| path);
+    if profile.is_none() {
+        log::warn!("No device profile found with name: {name}");
+    }
+    profile
+}
+
+/// Loads all app profile maps. Mappings in earlier locations take precedence
+/// so users can override them in /etc/inputplumber/app_profiles.d.
+fn load_app_profile_maps() -> Vec<AppProfileMap> {
+    let paths = vec![
+        "./rootfs/usr/share/inputplumber/app_profiles",
+        "/etc/inputplumber/app_profiles.d",
+        "/usr/share/inputplumber/app_profiles",
+    ];
+    let mut maps = Vec::new();
+    for path in get_yaml_files(paths) {
+        match AppProfileMap::from_yaml_file(path.clone()) {
+            Ok(map) => maps.push(map),
+            Err(e) => log::debug!("Failed to parse app profile map {path}: {e}"),
+        }
+    }
+    maps
+}
+
+/// Loads all device profiles along with their paths
+fn load_device_profiles() -> Vec<(String, DeviceProfile)> {
+    let paths = vec![
+        "./rootfs/usr/share/inputplumber/profiles",
+        "/etc/inputplumber/profiles.d",
+        "/usr/share/inputplumber/profiles",
+    ];
+    let mut profiles = Vec::new();
+    for path in get_yaml_files(paths) {
+        match DeviceProfile::from_yaml_file(path.clone()) {
+            Ok(profile) => profiles.push((path, profile)),
+            Err(e) => log::debug!("Failed to parse device profile: {e}"),
+        }
+    }
+    profiles
+}
+
+/// Returns the paths to all YAML files in the given directories
+fn get_yaml_files(paths: Vec<&str>) -> Vec<String> {
+    let mut yaml_files = Vec::new();
+    for path in paths {
+        let files = fs::read_dir(path);
+        if files.is_err() {
+            log::trace!("Failed to load directory {}: {}", path, files.unwrap_err());
+            continue;
+        }
+        let mut files: Vec<_> = files.unwrap().map(|r| r.unwrap()).collect();
+        files.sort_by_key(|dir| dir.file_name());
+
+        for file in files {
+            let filename = file.file_name();
+            let filename = filename.as_os_str().to_str().unwrap();
+
+            // Skip any non-yaml files
+            if !filename.ends_with(".yaml") {
+                continue;
+            }
+            yaml_files.push(file.path().display().to_string());
+        }
+    }
+    yaml_files
+}
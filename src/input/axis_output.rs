@@ -0,0 +1,91 @@
+use std::{collections::HashMap, str::FromStr};
+
+use crate::config::AxisOutputConfig;
+
+use super::{
+    capability::Capability,
+    event::{native::NativeEvent, value::InputValue},
+};
+
+/// An [AxisOutput] rescales the value of a target axis or trigger into a
+/// configured output range. This is applied after any deadzones and curves,
+/// right before events are written to target devices, to work around games
+/// that have calibration quirks (e.g. triggers that must not reach 100% or
+/// sticks that overshoot).
+#[derive(Debug, Clone, PartialEq)]
+pub struct AxisOutput {
+    min: f64,
+    max: f64,
+    saturation: f64,
+    clamp: bool,
+}
+
+impl AxisOutput {
+    /// Create a new axis output range from the given config
+    pub fn new(config: &AxisOutputConfig) -> Self {
+        Self {
+            min: config.min.unwrap_or(0.0).clamp(0.0, 1.0),
+            max: config.max.unwrap_or(1.0).clamp(0.0, 1.0),
+            saturation: config.saturation.unwrap_or(1.0).clamp(0.01, 1.0),
+            clamp: config.clamp.unwrap_or(true),
+        }
+    }
+
+    /// Returns the output value for the given axis value from -1.0 to 1.0.
+    /// Values at rest stay at rest, while any other value is mapped into the
+    /// output range keeping its direction.
+    pub fn apply(&self, value: f64) -> f64 {
+        if value == 0.0 {
+            return 0.0;
+        }
+        let mut magnitude = value.abs() / self.saturation;
+        if self.clamp {
+            magnitude = magnitude.min(1.0);
+        }
+        let output = self.min + (self.max - self.min) * magnitude;
+        output.copysign(value)
+    }
+}
+
+/// Output ranges of all configured target axes, keyed by capability
+#[derive(Debug, Clone, Default)]
+pub struct AxisOutputMap {
+    axes: HashMap<Capability, AxisOutput>,
+}
+
+impl AxisOutputMap {
+    /// Create a new map from the given configs. Configs with an unknown
+    /// capability are ignored.
+    pub fn new(configs: &[AxisOutputConfig]) -> Self {
+        let mut axes = HashMap::new();
+        for config in configs {
+            let Ok(capability) = Capability::from_str(config.capability.as_str()) else {
+                log::warn!("Invalid axis output capability: {}", config.capability);
+                continue;
+            };
+            axes.insert(capability, AxisOutput::new(config));
+        }
+        Self { axes }
+    }
+
+    /// Apply the output range of the event's capability to its value, if one
+    /// is configured.
+    pub fn process(&self, mut event: NativeEvent) -> NativeEvent {
+        let Some(output) = self.axes.get(&event.as_capability()) else {
+            return event;
+        };
+        match event.get_value() {
+            InputValue::Float(value) => {
+                event.set_value(InputValue::Float(output.apply(value)));
+            }
+            InputValue::Vector2 { x, y } => {
+                event.set_value(InputValue::Vector2 {
+                    x: x.map(|x| output.apply(x)),
+                    y: y.map(|y| output.apply(y)),
+                });
+            }
+            _ => (),
+        }
+        event
+    }
+}
@@ -0,0 +1,24 @@
+use crate::{config::AxisOutputConfig, input::axis_output::AxisOutput};
+
+#[test]
+fn test_axis_output() {
+    let config = AxisOutputConfig {
+        capability: "Gamepad:Trigger:LeftTrigger".to_string(),
+        min: None,
+        max: Some(0.9),
+        saturation: Some(0.5),
+        clamp: None,
+    };
+    let output = AxisOutput::new(&config);
+    assert_eq!(output.apply(0.0), 0.0);
+    assert_eq!(output.apply(0.25), 0.45);
+    assert_eq!(output.apply(0.5), 0.9);
+    assert_eq!(output.apply(1.0), 0.9);
+    assert_eq!(output.apply(-0.5), -0.9);
+
+    let output = AxisOutput::new(&AxisOutputConfig {
+        clamp: Some(false),
+        ..config
+    });
+    assert_eq!(output.apply(1.0), 1.8);
+}
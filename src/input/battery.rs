@@ -0,0 +1,51 @@
+use std::fmt::Display;
+
+/// Charging status of a source device battery. The names match the `status`
+/// attribute of Linux power_supply devices.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum BatteryStatus {
+    #[default]
+    Unknown,
+    Charging,
+    Discharging,
+    Full,
+}
+
+impl Display for BatteryStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let status = match self {
+            BatteryStatus::Unknown => "Unknown",
+            BatteryStatus::Charging => "Charging",
+            BatteryStatus::Discharging => "Discharging",
+            BatteryStatus::Full => "Full",
+        };
+        write!(f, "{status}")
+    }
+}
+
+/// Battery level and charging status reported by a source device
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BatteryState {
+    /// Battery level in percent
+    pub level: u8,
+    pub status: BatteryStatus,
+}
+
+impl BatteryState {
+    pub fn new(level: u8, status: BatteryStatus) -> Self {
+        Self {
+            level: level.min(100),
+            status,
+        }
+    }
+
+    /// Returns the battery state to report for a device with several
+    /// batteries, such as a pair of detachable controllers. This is the
+    /// battery with the lowest level, since it runs out first.
+    pub fn lowest<'a>(batteries: impl IntoIterator<Item = &'a BatteryState>) -> Option<Self> {
+        batteries
+            .into_iter()
+            .min_by_key(|battery| battery.level)
+            .copied()
+    }
+}
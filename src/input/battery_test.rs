@@ -0,0 +1,15 @@
+use crate::input::battery::{BatteryState, BatteryStatus};
+
+#[test]
+fn test_lowest_battery() {
+    let batteries = [
+        BatteryState::new(80, BatteryStatus::Discharging),
+        BatteryState::new(35, BatteryStatus::Charging),
+        BatteryState::new(120, BatteryStatus::Full),
+    ];
+    let lowest = BatteryState::lowest(&batteries).unwrap();
+    assert_eq!(lowest, BatteryState::new(35, BatteryStatus::Charging));
+    assert_eq!(batteries[2].level, 100);
+    assert_eq!(BatteryState::lowest(&[]), None);
+    assert_eq!(BatteryStatus::Discharging.to_string(), "Discharging");
+}
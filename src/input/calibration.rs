@@ -0,0 +1,210 @@
+use std::{collections::HashMap, error::Error, fs, io, path::PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use super::{
+    capability::{Capability, Gamepad, Touch, Touchpad},
+    event::{native::NativeEvent, value::InputValue},
+};
+
+/// Default directory where persistent state is stored
+const DEFAULT_STATE_DIR: &str = "/var/lib/inputplumber";
+/// Environment variable used to override the state directory
+pub const STATE_DIR_ENV: &str = "INPUTPLUMBER_STATE_DIR";
+
+/// Calibration of a single controller, stored in the state directory keyed by
+/// the serial number of the controller.
+///
+/// ```yaml
+/// sticks:
+///   LeftStick:
+///     center: [0.02, -0.01]
+///     range: [0.95, 0.97]
+/// gyro_bias: [0.3, -0.1, 0.05]
+/// touchpads:
+///   RightPad:
+///     min: [0.02, 0.0]
+///     max: [0.98, 1.0]
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub struct CalibrationData {
+    /// Stick calibration keyed by axis (e.g. "LeftStick")
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub sticks: HashMap<String, StickCalibration>,
+    /// Gyro bias in degrees per second that is subtracted from gyro values
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gyro_bias: Option<[f64; 3]>,
+    /// Touchpad corrections keyed by touchpad (e.g. "RightPad")
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub touchpads: HashMap<String, TouchpadCorrection>,
+}
+
+/// Calibration of an analog stick
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub struct StickCalibration {
+    /// Value the stick reports at rest
+    #[serde(default)]
+    pub center: [f64; 2],
+    /// Furthest value the stick reaches from its center on each axis
+    #[serde(default = "default_range")]
+    pub range: [f64; 2],
+}
+
+/// Correction of the reported touchpad area
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub struct TouchpadCorrection {
+    /// Smallest x and y values the touchpad reports
+    #[serde(default)]
+    pub min: [f64; 2],
+    /// Largest x and y values the touchpad reports
+    #[serde(default = "default_range")]
+    pub max: [f64; 2],
+}
+
+fn default_range() -> [f64; 2] {
+    [1.0, 1.0]
+}
+
+impl CalibrationData {
+    /// Parse calibration data from the given YAML string
+    pub fn from_yaml(content: &str) -> Result<Self, Box<dyn Error>> {
+        Ok(serde_yaml::from_str(content)?)
+    }
+
+    /// Serialize the calibration data into a YAML string
+    pub fn to_yaml(&self) -> Result<String, Box<dyn Error>> {
+        Ok(serde_yaml::to_string(self)?)
+    }
+
+    /// Load the stored calibration of the controller with the given serial
+    /// number. Returns [None] if no calibration has been stored.
+    pub fn load(serial: &str) -> Option<Self> {
+        let path = get_path(serial);
+        let content = fs::read_to_string(&path).ok()?;
+        match Self::from_yaml(&content) {
+            Ok(data) => Some(data),
+            Err(e) => {
+                log::warn!("Failed to parse calibration {}: {e}", path.display());
+                None
+            }
+        }
+    }
+
+    /// Store the calibration of the controller with the given serial number
+    pub fn save(&self, serial: &str) -> Result<(), Box<dyn Error>> {
+        let path = get_path(serial);
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(&path, self.to_yaml()?)?;
+        log::debug!("Saved calibration to {}", path.display());
+        Ok(())
+    }
+
+    /// Remove the stored calibration of the controller with the given serial
+    /// number
+    pub fn reset(serial: &str) -> Result<(), Box<dyn Error>> {
+        match fs::remove_file(get_path(serial)) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+
+    /// Apply the calibration to the given source event
+    pub fn apply(&self, mut event: NativeEvent) -> NativeEvent {
+        let value = match (event.as_capability(), event.get_value()) {
+            (Capability::Gamepad(Gamepad::Axis(axis)), InputValue::Vector2 { x, y }) => {
+                let Some(stick) = self.sticks.get(&axis.to_string()) else {
+                    return event;
+                };
+                let calibrate = |value: f64, i: usize| {
+                    let range = if stick.range[i] == 0.0 {
+                        1.0
+                    } else {
+                        stick.range[i]
+                    };
+                    ((value - stick.center[i]) / range).clamp(-1.0, 1.0)
+                };
+                InputValue::Vector2 {
+                    x: x.map(|x| calibrate(x, 0)),
+                    y: y.map(|y| calibrate(y, 1)),
+                }
+            }
+            (Capability::Gamepad(Gamepad::Gyro), InputValue::Vector3 { x, y, z }) => {
+                let Some(bias) = self.gyro_bias else {
+                    return event;
+                };
+                InputValue::Vector3 {
+                    x: x.map(|x| x - bias[0]),
+                    y: y.map(|y| y - bias[1]),
+                    z: z.map(|z| z - bias[2]),
+                }
+            }
+            (
+                Capability::Touchpad(pad),
+                InputValue::Touch {
+                    index,
+                    is_touching,
+                    x,
+                    y,
+                    pressure,
+                },
+            ) => {
+                let (Touchpad::LeftPad(Touch::Motion)
+                | Touchpad::RightPad(Touch::Motion)
+                | Touchpad::CenterPad(Touch::Motion)) = pad
+                else {
+                    return event;
+                };
+                let Some(correction) = self.touchpads.get(&pad.to_string()) else {
+                    return event;
+                };
+                let correct = |value: f64, i: usize| {
+                    let size = correction.max[i] - correction.min[i];
+                    if size <= 0.0 {
+                        return value;
+                    }
+                    ((value - correction.min[i]) / size).clamp(0.0, 1.0)
+                };
+                InputValue::Touch {
+                    index,
+                    is_touching,
+                    x: x.map(|x| correct(x, 0)),
+                    y: y.map(|y| correct(y, 1)),
+                    pressure,
+                }
+            }
+            _ => return event,
+        };
+        event.set_value(value);
+        event
+    }
+}
+
+/// Returns the directory where persistent state is stored. This can be
+/// changed with the `INPUTPLUMBER_STATE_DIR` environment variable.
+pub fn get_state_dir() -> PathBuf {
+    std::env::var(STATE_DIR_ENV)
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| PathBuf::from(DEFAULT_STATE_DIR))
+}
+
+/// Returns the path of the calibration file for the given serial number
+fn get_path(serial: &str) -> PathBuf {
+    let name: String = serial
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    get_state_dir()
+        .join("calibration")
+        .join(format!("{name}.yaml"))
+}
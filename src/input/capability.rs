@@ -20,6 +20,29 @@ pub enum Capability {
     Mouse(Mouse),
     Keyboard(Keyboard),
     Touchpad(Touchpad),
+    Touchscreen(Touch),
+}
+
+impl Capability {
+    /// Returns the full string representation of the capability (e.g.
+    /// "Gamepad:Button:South") that can be parsed back into a [Capability].
+    pub fn to_capability_string(&self) -> String {
+        match self {
+            Capability::Gamepad(gamepad) => match gamepad {
+                Gamepad::Button(button) => format!("Gamepad:Button:{}", button),
+                Gamepad::Axis(axis) => format!("Gamepad:Axis:{}", axis),
+                Gamepad::Trigger(trigger) => format!("Gamepad:Trigger:{}", trigger),
+                Gamepad::Accelerometer => "Gamepad:Accelerometer".to_string(),
+                Gamepad::Gyro => "Gamepad:Gyro".to_string(),
+            },
+            Capability::Mouse(mouse) => match mouse {
+                Mouse::Motion => "Mouse:Motion".to_string(),
+                Mouse::Button(button) => format!("Mouse:Button:{}", button),
+            },
+            Capability::Keyboard(key) => format!("Keyboard:{}", key),
+            _ => self.to_string(),
+        }
+    }
 }
 
 impl fmt::Display for Capability {
@@ -33,6 +56,7 @@ impl fmt::Display for Capability {
             Capability::Keyboard(_) => write!(f, "Keyboard"),
             Capability::DBus(_) => write!(f, "DBus"),
             Capability::Touchpad(_) => write!(f, "Touchpad"),
+            Capability::Touchscreen(_) => write!(f, "Touchscreen"),
         }
     }
 }
@@ -61,6 +85,9 @@ impl FromStr for Capability {
             "DBus" => Ok(Capability::DBus(Action::from_str(
                 parts.join(":").as_str(),
             )?)),
+            "Touchscreen" => Ok(Capability::Touchscreen(Touch::from_str(
+                parts.join(":").as_str(),
+            )?)),
             _ => Err(()),
         }
     }
@@ -332,6 +359,8 @@ pub enum GamepadButton {
     Keyboard,
     /// Dedicated screenshot button
     Screenshot,
+    /// Microphone mute button, Sony Mute
+    Mute,
     /// Directional Pad up
     DPadUp,
     /// Directional Pad down
@@ -388,6 +417,7 @@ impl fmt::Display for GamepadButton {
             GamepadButton::QuickAccess2 => write!(f, "QuickAccess2"),
             GamepadButton::Keyboard => write!(f, "Keyboard"),
             GamepadButton::Screenshot => write!(f, "Screenshot"),
+            GamepadButton::Mute => write!(f, "Mute"),
             GamepadButton::DPadUp => write!(f, "DPadUp"),
             GamepadButton::DPadDown => write!(f, "DPadDown"),
             GamepadButton::DPadLeft => write!(f, "DPadLeft"),
@@ -428,6 +458,7 @@ impl FromStr for GamepadButton {
             "QuickAccess2" => Ok(GamepadButton::QuickAccess2),
             "Keyboard" => Ok(GamepadButton::Keyboard),
             "Screenshot" => Ok(GamepadButton::Screenshot),
+            "Mute" => Ok(GamepadButton::Mute),
             "DPadUp" => Ok(GamepadButton::DPadUp),
             "DPadDown" => Ok(GamepadButton::DPadDown),
             "DPadLeft" => Ok(GamepadButton::DPadLeft),
@@ -694,6 +725,175 @@ pub enum Keyboard {
     KeyF23,
     KeyF24,
     KeyProg1,
+    KeyProg2,
+    KeyProg3,
+    KeyProg4,
+    KeyWlan,
+    KeyRfkill,
+    KeyLinefeed,
+    KeyMacro,
+    KeyKpPlusMinus,
+    KeyScale,
+    KeyHangeul,
+    KeyMenu,
+    KeySetup,
+    KeyWakeup,
+    KeyFile,
+    KeySendFile,
+    KeyDeleteFile,
+    KeyXfer,
+    KeyMsdos,
+    KeyCoffee,
+    KeyRotateDisplay,
+    KeyCycleWindows,
+    KeyMail,
+    KeyBookmarks,
+    KeyComputer,
+    KeyCloseCD,
+    KeyEjectCloseCD,
+    KeyRecord,
+    KeyRewind,
+    KeyPhone,
+    KeyIso,
+    KeyConfig,
+    KeyHomepage,
+    KeyExit,
+    KeyMove,
+    KeyNew,
+    KeyRedo,
+    KeyPlayCD,
+    KeyPauseCD,
+    KeyDashboard,
+    KeySuspend,
+    KeyClose,
+    KeyPlay,
+    KeyFastForward,
+    KeyBassBoost,
+    KeyPrint,
+    KeyHp,
+    KeyCamera,
+    KeySound,
+    KeyQuestion,
+    KeyEmail,
+    KeyChat,
+    KeySearch,
+    KeyConnect,
+    KeyFinance,
+    KeySport,
+    KeyShop,
+    KeyAltErase,
+    KeyCancel,
+    KeyBrightnessDown,
+    KeyBrightnessUp,
+    KeyMedia,
+    KeySwitchVideoMode,
+    KeyKbdIllumToggle,
+    KeyKbdIllumDown,
+    KeyKbdIllumUp,
+    KeySend,
+    KeyReply,
+    KeyForwardMail,
+    KeySave,
+    KeyDocuments,
+    KeyBattery,
+    KeyBluetooth,
+    KeyUwb,
+    KeyVideoNext,
+    KeyVideoPrev,
+    KeyBrightnessCycle,
+    KeyBrightnessAuto,
+    KeyDisplayOff,
+    KeyWwan,
+    KeyOk,
+    KeySelect,
+    KeyGoto,
+    KeyClear,
+    KeyPower2,
+    KeyOption,
+    KeyInfo,
+    KeyTime,
+    KeyVendor,
+    KeyArchive,
+    KeyProgram,
+    KeyChannel,
+    KeyFavorites,
+    KeyEpg,
+    KeyPvr,
+    KeyMhp,
+    KeyLanguage,
+    KeyTitle,
+    KeySubtitle,
+    KeyAngle,
+    KeyMode,
+    KeyKeyboard,
+    KeyScreen,
+    KeyPc,
+    KeyTv,
+    KeyTv2,
+    KeyVcr,
+    KeyVcr2,
+    KeySat,
+    KeySat2,
+    KeyCd,
+    KeyTape,
+    KeyRadio,
+    KeyTuner,
+    KeyPlayer,
+    KeyText,
+    KeyDvd,
+    KeyAux,
+    KeyMp3,
+    KeyAudio,
+    KeyVideo,
+    KeyDirectory,
+    KeyList,
+    KeyMemo,
+    KeyCalendar,
+    KeyRed,
+    KeyGreen,
+    KeyYellow,
+    KeyBlue,
+    KeyChannelUp,
+    KeyChannelDown,
+    KeyFirst,
+    KeyLast,
+    KeyAb,
+    KeyNext,
+    KeyRestart,
+    KeySlow,
+    KeyShuffle,
+    KeyBreak,
+    KeyPrevious,
+    KeyDigits,
+    KeyTeen,
+    KeyTwen,
+    KeyVideophone,
+    KeyGames,
+    KeyZoomIn,
+    KeyZoomOut,
+    KeyZoomReset,
+    KeyWordProcessor,
+    KeyEditor,
+    KeySpreadsheet,
+    KeyGraphicsEditor,
+    KeyPresentation,
+    KeyDatabase,
+    KeyNews,
+    KeyVoicemail,
+    KeyAddressBook,
+    KeyMessenger,
+    KeyDisplayToggle,
+    KeySpellCheck,
+    KeyLogoff,
+    KeyDollar,
+    KeyEuro,
+    KeyFrameBack,
+    KeyFrameForward,
+    KeyContextMenu,
+    KeyMediaRepeat,
+    Key10ChannelsUp,
+    Key10ChannelsDown,
+    KeyImages,
 }
 
 impl fmt::Display for Keyboard {
@@ -860,6 +1060,175 @@ impl fmt::Display for Keyboard {
             Keyboard::KeyF23 => write!(f, "KeyF23"),
             Keyboard::KeyF24 => write!(f, "KeyF24"),
             Keyboard::KeyProg1 => write!(f, "KeyProg1"),
+            Keyboard::KeyProg2 => write!(f, "KeyProg2"),
+            Keyboard::KeyProg3 => write!(f, "KeyProg3"),
+            Keyboard::KeyProg4 => write!(f, "KeyProg4"),
+            Keyboard::KeyWlan => write!(f, "KeyWlan"),
+            Keyboard::KeyRfkill => write!(f, "KeyRfkill"),
+            Keyboard::KeyLinefeed => write!(f, "KeyLinefeed"),
+            Keyboard::KeyMacro => write!(f, "KeyMacro"),
+            Keyboard::KeyKpPlusMinus => write!(f, "KeyKpPlusMinus"),
+            Keyboard::KeyScale => write!(f, "KeyScale"),
+            Keyboard::KeyHangeul => write!(f, "KeyHangeul"),
+            Keyboard::KeyMenu => write!(f, "KeyMenu"),
+            Keyboard::KeySetup => write!(f, "KeySetup"),
+            Keyboard::KeyWakeup => write!(f, "KeyWakeup"),
+            Keyboard::KeyFile => write!(f, "KeyFile"),
+            Keyboard::KeySendFile => write!(f, "KeySendFile"),
+            Keyboard::KeyDeleteFile => write!(f, "KeyDeleteFile"),
+            Keyboard::KeyXfer => write!(f, "KeyXfer"),
+            Keyboard::KeyMsdos => write!(f, "KeyMsdos"),
+            Keyboard::KeyCoffee => write!(f, "KeyCoffee"),
+            Keyboard::KeyRotateDisplay => write!(f, "KeyRotateDisplay"),
+            Keyboard::KeyCycleWindows => write!(f, "KeyCycleWindows"),
+            Keyboard::KeyMail => write!(f, "KeyMail"),
+            Keyboard::KeyBookmarks => write!(f, "KeyBookmarks"),
+            Keyboard::KeyComputer => write!(f, "KeyComputer"),
+            Keyboard::KeyCloseCD => write!(f, "KeyCloseCD"),
+            Keyboard::KeyEjectCloseCD => write!(f, "KeyEjectCloseCD"),
+            Keyboard::KeyRecord => write!(f, "KeyRecord"),
+            Keyboard::KeyRewind => write!(f, "KeyRewind"),
+            Keyboard::KeyPhone => write!(f, "KeyPhone"),
+            Keyboard::KeyIso => write!(f, "KeyIso"),
+            Keyboard::KeyConfig => write!(f, "KeyConfig"),
+            Keyboard::KeyHomepage => write!(f, "KeyHomepage"),
+            Keyboard::KeyExit => write!(f, "KeyExit"),
+            Keyboard::KeyMove => write!(f, "KeyMove"),
+            Keyboard::KeyNew => write!(f, "KeyNew"),
+            Keyboard::KeyRedo => write!(f, "KeyRedo"),
+            Keyboard::KeyPlayCD => write!(f, "KeyPlayCD"),
+            Keyboard::KeyPauseCD => write!(f, "KeyPauseCD"),
+            Keyboard::KeyDashboard => write!(f, "KeyDashboard"),
+            Keyboard::KeySuspend => write!(f, "KeySuspend"),
+            Keyboard::KeyClose => write!(f, "KeyClose"),
+            Keyboard::KeyPlay => write!(f, "KeyPlay"),
+            Keyboard::KeyFastForward => write!(f, "KeyFastForward"),
+            Keyboard::KeyBassBoost => write!(f, "KeyBassBoost"),
+            Keyboard::KeyPrint => write!(f, "KeyPrint"),
+            Keyboard::KeyHp => write!(f, "KeyHp"),
+            Keyboard::KeyCamera => write!(f, "KeyCamera"),
+            Keyboard::KeySound => write!(f, "KeySound"),
+            Keyboard::KeyQuestion => write!(f, "KeyQuestion"),
+            Keyboard::KeyEmail => write!(f, "KeyEmail"),
+            Keyboard::KeyChat => write!(f, "KeyChat"),
+            Keyboard::KeySearch => write!(f, "KeySearch"),
+            Keyboard::KeyConnect => write!(f, "KeyConnect"),
+            Keyboard::KeyFinance => write!(f, "KeyFinance"),
+            Keyboard::KeySport => write!(f, "KeySport"),
+            Keyboard::KeyShop => write!(f, "KeyShop"),
+            Keyboard::KeyAltErase => write!(f, "KeyAltErase"),
+            Keyboard::KeyCancel => write!(f, "KeyCancel"),
+            Keyboard::KeyBrightnessDown => write!(f, "KeyBrightnessDown"),
+            Keyboard::KeyBrightnessUp => write!(f, "KeyBrightnessUp"),
+            Keyboard::KeyMedia => write!(f, "KeyMedia"),
+            Keyboard::KeySwitchVideoMode => write!(f, "KeySwitchVideoMode"),
+            Keyboard::KeyKbdIllumToggle => write!(f, "KeyKbdIllumToggle"),
+            Keyboard::KeyKbdIllumDown => write!(f, "KeyKbdIllumDown"),
+            Keyboard::KeyKbdIllumUp => write!(f, "KeyKbdIllumUp"),
+            Keyboard::KeySend => write!(f, "KeySend"),
+            Keyboard::KeyReply => write!(f, "KeyReply"),
+            Keyboard::KeyForwardMail => write!(f, "KeyForwardMail"),
+            Keyboard::KeySave => write!(f, "KeySave"),
+            Keyboard::KeyDocuments => write!(f, "KeyDocuments"),
+            Keyboard::KeyBattery => write!(f, "KeyBattery"),
+            Keyboard::KeyBluetooth => write!(f, "KeyBluetooth"),
+            Keyboard::KeyUwb => write!(f, "KeyUwb"),
+            Keyboard::KeyVideoNext => write!(f, "KeyVideoNext"),
+            Keyboard::KeyVideoPrev => write!(f, "KeyVideoPrev"),
+            Keyboard::KeyBrightnessCycle => write!(f, "KeyBrightnessCycle"),
+            Keyboard::KeyBrightnessAuto => write!(f, "KeyBrightnessAuto"),
+            Keyboard::KeyDisplayOff => write!(f, "KeyDisplayOff"),
+            Keyboard::KeyWwan => write!(f, "KeyWwan"),
+            Keyboard::KeyOk => write!(f, "KeyOk"),
+            Keyboard::KeySelect => write!(f, "KeySelect"),
+            Keyboard::KeyGoto => write!(f, "KeyGoto"),
+            Keyboard::KeyClear => write!(f, "KeyClear"),
+            Keyboard::KeyPower2 => write!(f, "KeyPower2"),
+            Keyboard::KeyOption => write!(f, "KeyOption"),
+            Keyboard::KeyInfo => write!(f, "KeyInfo"),
+            Keyboard::KeyTime => write!(f, "KeyTime"),
+            Keyboard::KeyVendor => write!(f, "KeyVendor"),
+            Keyboard::KeyArchive => write!(f, "KeyArchive"),
+            Keyboard::KeyProgram => write!(f, "KeyProgram"),
+            Keyboard::KeyChannel => write!(f, "KeyChannel"),
+            Keyboard::KeyFavorites => write!(f, "KeyFavorites"),
+            Keyboard::KeyEpg => write!(f, "KeyEpg"),
+            Keyboard::KeyPvr => write!(f, "KeyPvr"),
+            Keyboard::KeyMhp => write!(f, "KeyMhp"),
+            Keyboard::KeyLanguage => write!(f, "KeyLanguage"),
+            Keyboard::KeyTitle => write!(f, "KeyTitle"),
+            Keyboard::KeySubtitle => write!(f, "KeySubtitle"),
+            Keyboard::KeyAngle => write!(f, "KeyAngle"),
+            Keyboard::KeyMode => write!(f, "KeyMode"),
+            Keyboard::KeyKeyboard => write!(f, "KeyKeyboard"),
+            Keyboard::KeyScreen => write!(f, "KeyScreen"),
+            Keyboard::KeyPc => write!(f, "KeyPc"),
+            Keyboard::KeyTv => write!(f, "KeyTv"),
+            Keyboard::KeyTv2 => write!(f, "KeyTv2"),
+            Keyboard::KeyVcr => write!(f, "KeyVcr"),
+            Keyboard::KeyVcr2 => write!(f, "KeyVcr2"),
+            Keyboard::KeySat => write!(f, "KeySat"),
+            Keyboard::KeySat2 => write!(f, "KeySat2"),
+            Keyboard::KeyCd => write!(f, "KeyCd"),
+            Keyboard::KeyTape => write!(f, "KeyTape"),
+            Keyboard::KeyRadio => write!(f, "KeyRadio"),
+            Keyboard::KeyTuner => write!(f, "KeyTuner"),
+            Keyboard::KeyPlayer => write!(f, "KeyPlayer"),
+            Keyboard::KeyText => write!(f, "KeyText"),
+            Keyboard::KeyDvd => write!(f, "KeyDvd"),
+            Keyboard::KeyAux => write!(f, "KeyAux"),
+            Keyboard::KeyMp3 => write!(f, "KeyMp3"),
+            Keyboard::KeyAudio => write!(f, "KeyAudio"),
+            Keyboard::KeyVideo => write!(f, "KeyVideo"),
+            Keyboard::KeyDirectory => write!(f, "KeyDirectory"),
+            Keyboard::KeyList => write!(f, "KeyList"),
+            Keyboard::KeyMemo => write!(f, "KeyMemo"),
+            Keyboard::KeyCalendar => write!(f, "KeyCalendar"),
+            Keyboard::KeyRed => write!(f, "KeyRed"),
+            Keyboard::KeyGreen => write!(f, "KeyGreen"),
+            Keyboard::KeyYellow => write!(f, "KeyYellow"),
+            Keyboard::KeyBlue => write!(f, "KeyBlue"),
+            Keyboard::KeyChannelUp => write!(f, "KeyChannelUp"),
+            Keyboard::KeyChannelDown => write!(f, "KeyChannelDown"),
+            Keyboard::KeyFirst => write!(f, "KeyFirst"),
+            Keyboard::KeyLast => write!(f, "KeyLast"),
+            Keyboard::KeyAb => write!(f, "KeyAb"),
+            Keyboard::KeyNext => write!(f, "KeyNext"),
+            Keyboard::KeyRestart => write!(f, "KeyRestart"),
+            Keyboard::KeySlow => write!(f, "KeySlow"),
+            Keyboard::KeyShuffle => write!(f, "KeyShuffle"),
+            Keyboard::KeyBreak => write!(f, "KeyBreak"),
+            Keyboard::KeyPrevious => write!(f, "KeyPrevious"),
+            Keyboard::KeyDigits => write!(f, "KeyDigits"),
+            Keyboard::KeyTeen => write!(f, "KeyTeen"),
+            Keyboard::KeyTwen => write!(f, "KeyTwen"),
+            Keyboard::KeyVideophone => write!(f, "KeyVideophone"),
+            Keyboard::KeyGames => write!(f, "KeyGames"),
+            Keyboard::KeyZoomIn => write!(f, "KeyZoomIn"),
+            Keyboard::KeyZoomOut => write!(f, "KeyZoomOut"),
+            Keyboard::KeyZoomReset => write!(f, "KeyZoomReset"),
+            Keyboard::KeyWordProcessor => write!(f, "KeyWordProcessor"),
+            Keyboard::KeyEditor => write!(f, "KeyEditor"),
+            Keyboard::KeySpreadsheet => write!(f, "KeySpreadsheet"),
+            Keyboard::KeyGraphicsEditor => write!(f, "KeyGraphicsEditor"),
+            Keyboard::KeyPresentation => write!(f, "KeyPresentation"),
+            Keyboard::KeyDatabase => write!(f, "KeyDatabase"),
+            Keyboard::KeyNews => write!(f, "KeyNews"),
+            Keyboard::KeyVoicemail => write!(f, "KeyVoicemail"),
+            Keyboard::KeyAddressBook => write!(f, "KeyAddressBook"),
+            Keyboard::KeyMessenger => write!(f, "KeyMessenger"),
+            Keyboard::KeyDisplayToggle => write!(f, "KeyDisplayToggle"),
+            Keyboard::KeySpellCheck => write!(f, "KeySpellCheck"),
+            Keyboard::KeyLogoff => write!(f, "KeyLogoff"),
+            Keyboard::KeyDollar => write!(f, "KeyDollar"),
+            Keyboard::KeyEuro => write!(f, "KeyEuro"),
+            Keyboard::KeyFrameBack => write!(f, "KeyFrameBack"),
+            Keyboard::KeyFrameForward => write!(f, "KeyFrameForward"),
+            Keyboard::KeyContextMenu => write!(f, "KeyContextMenu"),
+            Keyboard::KeyMediaRepeat => write!(f, "KeyMediaRepeat"),
+            Keyboard::Key10ChannelsUp => write!(f, "Key10ChannelsUp"),
+            Keyboard::Key10ChannelsDown => write!(f, "Key10ChannelsDown"),
+            Keyboard::KeyImages => write!(f, "KeyImages"),
         }
     }
 }
@@ -1030,6 +1399,175 @@ impl FromStr for Keyboard {
             "KeyF23" => Ok(Keyboard::KeyF23),
             "KeyF24" => Ok(Keyboard::KeyF24),
             "KeyProg1" => Ok(Keyboard::KeyProg1),
+            "KeyProg2" => Ok(Keyboard::KeyProg2),
+            "KeyProg3" => Ok(Keyboard::KeyProg3),
+            "KeyProg4" => Ok(Keyboard::KeyProg4),
+            "KeyWlan" => Ok(Keyboard::KeyWlan),
+            "KeyRfkill" => Ok(Keyboard::KeyRfkill),
+            "KeyLinefeed" => Ok(Keyboard::KeyLinefeed),
+            "KeyMacro" => Ok(Keyboard::KeyMacro),
+            "KeyKpPlusMinus" => Ok(Keyboard::KeyKpPlusMinus),
+            "KeyScale" => Ok(Keyboard::KeyScale),
+            "KeyHangeul" => Ok(Keyboard::KeyHangeul),
+            "KeyMenu" => Ok(Keyboard::KeyMenu),
+            "KeySetup" => Ok(Keyboard::KeySetup),
+            "KeyWakeup" => Ok(Keyboard::KeyWakeup),
+            "KeyFile" => Ok(Keyboard::KeyFile),
+            "KeySendFile" => Ok(Keyboard::KeySendFile),
+            "KeyDeleteFile" => Ok(Keyboard::KeyDeleteFile),
+            "KeyXfer" => Ok(Keyboard::KeyXfer),
+            "KeyMsdos" => Ok(Keyboard::KeyMsdos),
+            "KeyCoffee" => Ok(Keyboard::KeyCoffee),
+            "KeyRotateDisplay" => Ok(Keyboard::KeyRotateDisplay),
+            "KeyCycleWindows" => Ok(Keyboard::KeyCycleWindows),
+            "KeyMail" => Ok(Keyboard::KeyMail),
+            "KeyBookmarks" => Ok(Keyboard::KeyBookmarks),
+            "KeyComputer" => Ok(Keyboard::KeyComputer),
+            "KeyCloseCD" => Ok(Keyboard::KeyCloseCD),
+            "KeyEjectCloseCD" => Ok(Keyboard::KeyEjectCloseCD),
+            "KeyRecord" => Ok(Keyboard::KeyRecord),
+            "KeyRewind" => Ok(Keyboard::KeyRewind),
+            "KeyPhone" => Ok(Keyboard::KeyPhone),
+            "KeyIso" => Ok(Keyboard::KeyIso),
+            "KeyConfig" => Ok(Keyboard::KeyConfig),
+            "KeyHomepage" => Ok(Keyboard::KeyHomepage),
+            "KeyExit" => Ok(Keyboard::KeyExit),
+            "KeyMove" => Ok(Keyboard::KeyMove),
+            "KeyNew" => Ok(Keyboard::KeyNew),
+            "KeyRedo" => Ok(Keyboard::KeyRedo),
+            "KeyPlayCD" => Ok(Keyboard::KeyPlayCD),
+            "KeyPauseCD" => Ok(Keyboard::KeyPauseCD),
+            "KeyDashboard" => Ok(Keyboard::KeyDashboard),
+            "KeySuspend" => Ok(Keyboard::KeySuspend),
+            "KeyClose" => Ok(Keyboard::KeyClose),
+            "KeyPlay" => Ok(Keyboard::KeyPlay),
+            "KeyFastForward" => Ok(Keyboard::KeyFastForward),
+            "KeyBassBoost" => Ok(Keyboard::KeyBassBoost),
+            "KeyPrint" => Ok(Keyboard::KeyPrint),
+            "KeyHp" => Ok(Keyboard::KeyHp),
+            "KeyCamera" => Ok(Keyboard::KeyCamera),
+            "KeySound" => Ok(Keyboard::KeySound),
+            "KeyQuestion" => Ok(Keyboard::KeyQuestion),
+            "KeyEmail" => Ok(Keyboard::KeyEmail),
+            "KeyChat" => Ok(Keyboard::KeyChat),
+            "KeySearch" => Ok(Keyboard::KeySearch),
+            "KeyConnect" => Ok(Keyboard::KeyConnect),
+            "KeyFinance" => Ok(Keyboard::KeyFinance),
+            "KeySport" => Ok(Keyboard::KeySport),
+            "KeyShop" => Ok(Keyboard::KeyShop),
+            "KeyAltErase" => Ok(Keyboard::KeyAltErase),
+            "KeyCancel" => Ok(Keyboard::KeyCancel),
+            "KeyBrightnessDown" => Ok(Keyboard::KeyBrightnessDown),
+            "KeyBrightnessUp" => Ok(Keyboard::KeyBrightnessUp),
+            "KeyMedia" => Ok(Keyboard::KeyMedia),
+            "KeySwitchVideoMode" => Ok(Keyboard::KeySwitchVideoMode),
+            "KeyKbdIllumToggle" => Ok(Keyboard::KeyKbdIllumToggle),
+            "KeyKbdIllumDown" => Ok(Keyboard::KeyKbdIllumDown),
+            "KeyKbdIllumUp" => Ok(Keyboard::KeyKbdIllumUp),
+            "KeySend" => Ok(Keyboard::KeySend),
+            "KeyReply" => Ok(Keyboard::KeyReply),
+            "KeyForwardMail" => Ok(Keyboard::KeyForwardMail),
+            "KeySave" => Ok(Keyboard::KeySave),
+            "KeyDocuments" => Ok(Keyboard::KeyDocuments),
+            "KeyBattery" => Ok(Keyboard::KeyBattery),
+            "KeyBluetooth" => Ok(Keyboard::KeyBluetooth),
+            "KeyUwb" => Ok(Keyboard::KeyUwb),
+            "KeyVideoNext" => Ok(Keyboard::KeyVideoNext),
+            "KeyVideoPrev" => Ok(Keyboard::KeyVideoPrev),
+            "KeyBrightnessCycle" => Ok(Keyboard::KeyBrightnessCycle),
+            "KeyBrightnessAuto" => Ok(Keyboard::KeyBrightnessAuto),
+            "KeyDisplayOff" => Ok(Keyboard::KeyDisplayOff),
+            "KeyWwan" => Ok(Keyboard::KeyWwan),
+            "KeyOk" => Ok(Keyboard::KeyOk),
+            "KeySelect" => Ok(Keyboard::KeySelect),
+            "KeyGoto" => Ok(Keyboard::KeyGoto),
+            "KeyClear" => Ok(Keyboard::KeyClear),
+            "KeyPower2" => Ok(Keyboard::KeyPower2),
+            "KeyOption" => Ok(Keyboard::KeyOption),
+            "KeyInfo" => Ok(Keyboard::KeyInfo),
+            "KeyTime" => Ok(Keyboard::KeyTime),
+            "KeyVendor" => Ok(Keyboard::KeyVendor),
+            "KeyArchive" => Ok(Keyboard::KeyArchive),
+            "KeyProgram" => Ok(Keyboard::KeyProgram),
+            "KeyChannel" => Ok(Keyboard::KeyChannel),
+            "KeyFavorites" => Ok(Keyboard::KeyFavorites),
+            "KeyEpg" => Ok(Keyboard::KeyEpg),
+            "KeyPvr" => Ok(Keyboard::KeyPvr),
+            "KeyMhp" => Ok(Keyboard::KeyMhp),
+            "KeyLanguage" => Ok(Keyboard::KeyLanguage),
+            "KeyTitle" => Ok(Keyboard::KeyTitle),
+            "KeySubtitle" => Ok(Keyboard::KeySubtitle),
+            "KeyAngle" => Ok(Keyboard::KeyAngle),
+            "KeyMode" => Ok(Keyboard::KeyMode),
+            "KeyKeyboard" => Ok(Keyboard::KeyKeyboard),
+            "KeyScreen" => Ok(Keyboard::KeyScreen),
+            "KeyPc" => Ok(Keyboard::KeyPc),
+            "KeyTv" => Ok(Keyboard::KeyTv),
+            "KeyTv2" => Ok(Keyboard::KeyTv2),
+            "KeyVcr" => Ok(Keyboard::KeyVcr),
+            "KeyVcr2" => Ok(Keyboard::KeyVcr2),
+            "KeySat" => Ok(Keyboard::KeySat),
+            "KeySat2" => Ok(Keyboard::KeySat2),
+            "KeyCd" => Ok(Keyboard::KeyCd),
+            "KeyTape" => Ok(Keyboard::KeyTape),
+            "KeyRadio" => Ok(Keyboard::KeyRadio),
+            "KeyTuner" => Ok(Keyboard::KeyTuner),
+            "KeyPlayer" => Ok(Keyboard::KeyPlayer),
+            "KeyText" => Ok(Keyboard::KeyText),
+            "KeyDvd" => Ok(Keyboard::KeyDvd),
+            "KeyAux" => Ok(Keyboard::KeyAux),
+            "KeyMp3" => Ok(Keyboard::KeyMp3),
+            "KeyAudio" => Ok(Keyboard::KeyAudio),
+            "KeyVideo" => Ok(Keyboard::KeyVideo),
+            "KeyDirectory" => Ok(Keyboard::KeyDirectory),
+            "KeyList" => Ok(Keyboard::KeyList),
+            "KeyMemo" => Ok(Keyboard::KeyMemo),
+            "KeyCalendar" => Ok(Keyboard::KeyCalendar),
+            "KeyRed" => Ok(Keyboard::KeyRed),
+            "KeyGreen" => Ok(Keyboard::KeyGreen),
+            "KeyYellow" => Ok(Keyboard::KeyYellow),
+            "KeyBlue" => Ok(Keyboard::KeyBlue),
+            "KeyChannelUp" => Ok(Keyboard::KeyChannelUp),
+            "KeyChannelDown" => Ok(Keyboard::KeyChannelDown),
+            "KeyFirst" => Ok(Keyboard::KeyFirst),
+            "KeyLast" => Ok(Keyboard::KeyLast),
+            "KeyAb" => Ok(Keyboard::KeyAb),
+            "KeyNext" => Ok(Keyboard::KeyNext),
+            "KeyRestart" => Ok(Keyboard::KeyRestart),
+            "KeySlow" => Ok(Keyboard::KeySlow),
+            "KeyShuffle" => Ok(Keyboard::KeyShuffle),
+            "KeyBreak" => Ok(Keyboard::KeyBreak),
+            "KeyPrevious" => Ok(Keyboard::KeyPrevious),
+            "KeyDigits" => Ok(Keyboard::KeyDigits),
+            "KeyTeen" => Ok(Keyboard::KeyTeen),
+            "KeyTwen" => Ok(Keyboard::KeyTwen),
+            "KeyVideophone" => Ok(Keyboard::KeyVideophone),
+            "KeyGames" => Ok(Keyboard::KeyGames),
+            "KeyZoomIn" => Ok(Keyboard::KeyZoomIn),
+            "KeyZoomOut" => Ok(Keyboard::KeyZoomOut),
+            "KeyZoomReset" => Ok(Keyboard::KeyZoomReset),
+            "KeyWordProcessor" => Ok(Keyboard::KeyWordProcessor),
+            "KeyEditor" => Ok(Keyboard::KeyEditor),
+            "KeySpreadsheet" => Ok(Keyboard::KeySpreadsheet),
+            "KeyGraphicsEditor" => Ok(Keyboard::KeyGraphicsEditor),
+            "KeyPresentation" => Ok(Keyboard::KeyPresentation),
+            "KeyDatabase" => Ok(Keyboard::KeyDatabase),
+            "KeyNews" => Ok(Keyboard::KeyNews),
+            "KeyVoicemail" => Ok(Keyboard::KeyVoicemail),
+            "KeyAddressBook" => Ok(Keyboard::KeyAddressBook),
+            "KeyMessenger" => Ok(Keyboard::KeyMessenger),
+            "KeyDisplayToggle" => Ok(Keyboard::KeyDisplayToggle),
+            "KeySpellCheck" => Ok(Keyboard::KeySpellCheck),
+            "KeyLogoff" => Ok(Keyboard::KeyLogoff),
+            "KeyDollar" => Ok(Keyboard::KeyDollar),
+            "KeyEuro" => Ok(Keyboard::KeyEuro),
+            "KeyFrameBack" => Ok(Keyboard::KeyFrameBack),
+            "KeyFrameForward" => Ok(Keyboard::KeyFrameForward),
+            "KeyContextMenu" => Ok(Keyboard::KeyContextMenu),
+            "KeyMediaRepeat" => Ok(Keyboard::KeyMediaRepeat),
+            "Key10ChannelsUp" => Ok(Keyboard::Key10ChannelsUp),
+            "Key10ChannelsDown" => Ok(Keyboard::Key10ChannelsDown),
+            "KeyImages" => Ok(Keyboard::KeyImages),
             _ => Err(()),
         }
     }
@@ -1079,6 +1617,10 @@ impl FromStr for Touchpad {
 pub enum Touch {
     Motion,
     Button(TouchButton),
+    /// Tilt of a stylus on the x and y axes from -1.0 to 1.0
+    Tilt,
+    /// Tool currently in proximity of the touch surface
+    Tool(TouchTool),
 }
 
 impl fmt::Display for Touch {
@@ -1086,6 +1628,8 @@ impl fmt::Display for Touch {
         match self {
             Touch::Motion => write!(f, "Motion"),
             Touch::Button(_) => write!(f, "Button"),
+            Touch::Tilt => write!(f, "Tilt"),
+            Touch::Tool(_) => write!(f, "Tool"),
         }
     }
 }
@@ -1102,6 +1646,8 @@ impl FromStr for Touch {
             "Button" => Ok(Touch::Button(TouchButton::from_str(
                 parts.join(":").as_str(),
             )?)),
+            "Tilt" => Ok(Touch::Tilt),
+            "Tool" => Ok(Touch::Tool(TouchTool::from_str(parts.join(":").as_str())?)),
             _ => Err(()),
         }
     }
@@ -1132,3 +1678,32 @@ impl FromStr for TouchButton {
         }
     }
 }
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum TouchTool {
+    Finger,
+    Pen,
+    Eraser,
+}
+
+impl fmt::Display for TouchTool {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TouchTool::Finger => write!(f, "Finger"),
+            TouchTool::Pen => write!(f, "Pen"),
+            TouchTool::Eraser => write!(f, "Eraser"),
+        }
+    }
+}
+
+impl FromStr for TouchTool {
+    type Err = ();
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "Finger" => Ok(TouchTool::Finger),
+            "Pen" => Ok(TouchTool::Pen),
+            "Eraser" => Ok(TouchTool::Eraser),
+            _ => Err(()),
+        }
+    }
+}
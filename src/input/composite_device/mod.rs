@@ -2,45 +2,82 @@ use std::{
     borrow::Borrow,
     collections::{BTreeSet, HashMap, HashSet},
     error::Error,
+    str::FromStr,
+    sync::Mutex,
+    time::Instant,
 };
 
 use evdev::InputEvent;
 use tokio::{
     sync::{broadcast, mpsc},
-    task::JoinSet,
+    task::{JoinHandle, JoinSet},
     time::Duration,
 };
 use zbus::Connection;
 
 use crate::{
     config::{
-        CapabilityMap, CapabilityMapping, CompositeDeviceConfig, DeviceProfile, ProfileMapping,
+        AccessibilityConfig, CapabilityMap, CapabilityMapping, CompositeDeviceConfig,
+        DeviceProfile, LedConfig, ProfileMapping,
     },
-    dbus::interface::composite_device::CompositeDeviceInterface,
+    dbus::interface::{
+        battery::BatteryInterface,
+        composite_device::{capability_strings, CompositeDeviceInterface},
+        manager::{emit_device_error, DeviceErrorKind},
+        sensor_proxy::{self, Orientation},
+    },
+    drivers::dualsense::hid_report::MuteLight,
     input::{
+        accessibility::{self, Accessibility},
+        axis_output::AxisOutputMap,
+        battery::BatteryState,
+        calibration::CalibrationData,
         capability::{Capability, Gamepad, GamepadButton, Mouse},
         event::{
             native::NativeEvent,
             value::{InputValue, TranslationError},
             Event,
         },
+        event_filter::EventFilter,
+        led::{self, Led},
         manager::SourceDeviceInfo,
-        output_event::UinputOutputEvent,
+        motion::{FusionFilter, MotionFusion},
+        output_event::{LedEvent, UinputOutputEvent},
+        plugin::{self, Plugin},
+        pressure::PressureCurve,
         source::{self, SourceDevice},
         target::TargetCommand,
     },
-    udev::{hide_device, unhide_device},
+    logger, metrics,
+    notifications::{self, Notification},
+    osk, procfs,
+    udev::HiddenDeviceGuard,
 };
 
 use super::{manager::ManagerCommand, output_event::OutputEvent, source::SourceCommand};
 
+use self::script::ScriptEngine;
+
+pub mod script;
+#[cfg(all(test, feature = "scripting"))]
+mod script_test;
+
 /// Size of the command channel buffer for processing input events and commands.
 const BUFFER_SIZE: usize = 16384;
+/// Path to the device profile loaded when a composite device is created
+const DEFAULT_PROFILE_PATH: &str = "/usr/share/inputplumber/profiles/default.yaml";
+/// Default time to wait for a controller to reconnect over a different
+/// transport (e.g. Bluetooth after unplugging USB).
+const DEFAULT_TRANSPORT_SWITCH_GRACE_PERIOD_MS: u64 = 10000;
+/// How often to check if Steam has opened any source devices
+const STEAM_CHECK_INTERVAL: Duration = Duration::from_secs(2);
+/// Duration of the rumble and LED cue when intercept mode is toggled
+const INTERCEPT_CUE_DURATION: Duration = Duration::from_millis(150);
 
 /// The [InterceptMode] defines whether or not inputs should be routed over
 /// DBus instead of to the target devices. This can be used by overlays to
 /// intercept input.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum InterceptMode {
     /// Pass all input to the target devices
     None,
@@ -48,6 +85,9 @@ pub enum InterceptMode {
     Pass,
     /// Intercept all input and send nothing to the target devices
     Always,
+    /// Pass all inputs to the target devices except gyro, accelerometer, and
+    /// touchpad inputs, which are only sent to DBus
+    Motion,
 }
 
 /// CompositeDevice commands define all the different ways to interact with [CompositeDevice]
@@ -67,10 +107,33 @@ pub enum Command {
     GetDBusDevicePaths(mpsc::Sender<Vec<String>>),
     SourceDeviceAdded(SourceDeviceInfo),
     SourceDeviceStopped(String),
+    HotplugGracePeriodExpired,
     SourceDeviceRemoved(String),
+    ReleaseSourceDevice(String),
+    SourceDeviceWarning(String, String),
+    SourceDeviceStalled(String, bool),
+    SourceDeviceDetached(String, bool, Vec<Capability>),
+    SourceDeviceBattery(String, BatteryState),
+    GetBattery(mpsc::Sender<Option<BatteryState>>),
+    SetLogLevel(Option<log::LevelFilter>),
+    GetSteamCoexistence(mpsc::Sender<bool>),
+    SetSteamCoexistence(bool),
+    AccessibilityTick,
+    GetOnScreenKeyboard(mpsc::Sender<bool>),
+    SetOnScreenKeyboard(bool),
+    InterceptToggleHeld,
+    InterceptCueEnded,
+    GetLeds(mpsc::Sender<Vec<String>>),
+    GetCalibration(mpsc::Sender<String>),
+    SetCalibration(String, mpsc::Sender<Result<(), String>>),
+    ResetCalibration(mpsc::Sender<Result<(), String>>),
+    CheckSteamClients,
+    SetSteamRunning(bool),
+    SetPausedForSteam(bool),
     SetTargetDevices(Vec<String>),
     AttachTargetDevices(HashMap<String, mpsc::Sender<TargetCommand>>),
     GetProfileName(mpsc::Sender<String>),
+    GetPlayerNumber(mpsc::Sender<u8>),
     LoadProfilePath(String, mpsc::Sender<Result<(), String>>),
     WriteEvent(NativeEvent),
     WriteChordEvent(Vec<NativeEvent>),
@@ -78,9 +141,55 @@ pub enum Command {
     HandleEvent(NativeEvent),
     RemoveRecentEvent(Capability),
     SetInterceptActivation(Vec<Capability>, Capability),
+    SubscribeEvents(mpsc::Sender<NativeEvent>),
+    SetAutoProfile(Option<String>),
     Stop,
 }
 
+/// Scripts and plugins attached to the mappings of a device profile
+#[derive(Debug, Default)]
+struct ProfileRuntime {
+    script_engine: Option<ScriptEngine>,
+    mapping_plugins: HashMap<String, Mutex<Plugin>>,
+}
+
+impl ProfileRuntime {
+    /// Read the device profile at the given path and compile the scripts and
+    /// plugins attached to its mappings. Compiling Lua and WASM can take a
+    /// while, so this should not be run on the async runtime.
+    fn load(path: String) -> Result<(DeviceProfile, Self), String> {
+        let profile = DeviceProfile::from_yaml_file(path).map_err(|e| e.to_string())?;
+        let mut runtime = Self::default();
+        for mapping in profile.mapping.iter() {
+            // Compile any script attached to the mapping
+            if let Some(script) = mapping.script.as_ref() {
+                if runtime.script_engine.is_none() {
+                    let engine = ScriptEngine::new().map_err(|e| e.to_string())?;
+                    runtime.script_engine = Some(engine);
+                }
+                if let Some(engine) = runtime.script_engine.as_mut() {
+                    engine
+                        .load(&mapping.name, script)
+                        .map_err(|e| e.to_string())?;
+                }
+            }
+
+            // Load any mapper plugin attached to the mapping
+            if let Some(name) = mapping.plugin.as_ref() {
+                let Some(config) = plugin::find_mapper(name) else {
+                    return Err(format!("No mapper plugin found with name: {name}"));
+                };
+                let plugin = Plugin::load(&config).map_err(|e| e.to_string())?;
+                runtime
+                    .mapping_plugins
+                    .insert(mapping.name.clone(), Mutex::new(plugin));
+            }
+        }
+
+        Ok((profile, runtime))
+    }
+}
+
 /// A [CompositeDevice] represents any number source input devices that
 /// can translate input to any target devices
 #[derive(Debug)]
@@ -93,8 +202,17 @@ pub struct CompositeDevice {
     config: CompositeDeviceConfig,
     /// Name of the [CompositeDeviceConfig] loaded for the device
     name: String,
+    /// Serial number of the first source device that provides one. This is
+    /// passed on to target devices so they keep a stable identity.
+    serial: Option<String>,
     /// Capabilities describe all input capabilities from all source devices
     capabilities: HashSet<Capability>,
+    /// Input capabilities of each source device
+    /// E.g. {"evdev://event0": {Capability}}
+    source_capabilities: HashMap<String, HashSet<Capability>>,
+    /// Capabilities of detachable parts of source devices that are currently
+    /// detached. E.g. {"hidraw://hidraw0/left": {Capability}}
+    detached_capabilities: HashMap<String, HashSet<Capability>>,
     /// Capability mapping for the CompositeDevice
     capability_map: Option<CapabilityMap>,
     /// Name of the currently loaded [DeviceProfile] for the CompositeDevice.
@@ -160,6 +278,88 @@ pub struct CompositeDevice {
     /// List of currently active buttons and keys. Used to block "up" events for
     /// keys that have already been handled.
     active_inputs: Vec<Capability>,
+    /// Set of axis, trigger, and motion capabilities that have been written to
+    /// target devices. Used to re-center these inputs when neutralizing targets.
+    active_axes: HashSet<Capability>,
+    /// Time when the last source device was removed. Used to keep the
+    /// composite device alive for a grace period in case sources reappear.
+    sources_removed_at: Option<Instant>,
+    /// Whether to pause the composite device while Steam has any of the
+    /// source devices open.
+    steam_coexistence: bool,
+    /// Task that periodically checks if Steam has opened any source devices
+    steam_watcher: Option<JoinHandle<()>>,
+    /// Whether Steam is running and the hidden source devices were made
+    /// accessible to it.
+    steam_running: bool,
+    /// Whether the composite device is paused because Steam is using one of
+    /// the source devices.
+    paused_for_steam: bool,
+    /// Source devices hidden by this composite device. Any devices still
+    /// hidden are restored when the composite device is dropped.
+    hidden_devices: HiddenDeviceGuard,
+    /// Engine used to run Lua scripts attached to profile mappings
+    script_engine: Option<ScriptEngine>,
+    /// Mapper plugins attached to profile mappings, keyed by mapping name
+    mapping_plugins: HashMap<String, Mutex<Plugin>>,
+    /// Channels of clients subscribed to events written to target devices
+    event_subscribers: Vec<mpsc::Sender<NativeEvent>>,
+    /// Battery state of each source device that reports one
+    /// E.g. {"hidraw://hidraw0/left": BatteryState}
+    batteries: HashMap<String, BatteryState>,
+    /// Source devices that have already shown a low battery notification
+    low_battery_devices: HashSet<String>,
+    /// Path to the device profile loaded by default or explicitly by the user
+    profile_path: String,
+    /// Path to the device profile automatically loaded for the focused app
+    auto_profile_path: Option<String>,
+    /// Whether to publish the orientation of the built-in accelerometer for
+    /// screen auto-rotation
+    screen_orientation: bool,
+    /// Orientation of the device calculated from accelerometer events
+    orientation: Orientation,
+    /// Fuses accelerometer and gyro events if motion fusion is enabled
+    motion_fusion: Option<MotionFusion>,
+    /// Filter that suppresses duplicate axis events before they are written
+    /// to target devices
+    event_filter: Option<EventFilter>,
+    /// Buttons that toggle the on-screen keyboard when pressed together
+    osk_chord: Vec<Capability>,
+    /// Buttons of the on-screen keyboard chord that are currently pressed
+    osk_pressed: HashSet<Capability>,
+    /// Whether the on-screen keyboard is currently shown
+    osk_visible: bool,
+    /// Intercept mode to restore when the on-screen keyboard is dismissed
+    osk_intercept_mode: Option<InterceptMode>,
+    /// Buttons that toggle intercept mode when held together
+    intercept_toggle_chord: Vec<Capability>,
+    /// Buttons of the intercept toggle chord that are currently pressed
+    intercept_toggle_pressed: HashSet<Capability>,
+    /// Task that toggles intercept mode once the chord has been held long
+    /// enough
+    intercept_toggle_task: Option<JoinHandle<()>>,
+    /// Intercept mode to restore when intercept mode is toggled off
+    intercept_toggle_mode: Option<InterceptMode>,
+    /// Accessibility filters from the loaded device profile
+    accessibility: Option<Accessibility>,
+    /// Task that periodically updates timed accessibility features
+    accessibility_task: Option<JoinHandle<()>>,
+    /// LED class devices of each source device
+    /// E.g. {"evdev://event0": [Led]}
+    leds: HashMap<String, Vec<Led>>,
+    /// Brightness of each LED before it was turned off for the intercept
+    /// cue, keyed by LED name
+    intercept_cue_brightness: HashMap<String, f64>,
+    /// LED state from the loaded device profile
+    led_config: Option<LedConfig>,
+    /// Touch pressure curve from the loaded device profile
+    pressure_curve: Option<PressureCurve>,
+    /// Output ranges of target axes from the loaded device profile
+    axis_output: Option<AxisOutputMap>,
+    /// Stored calibration of the controller, keyed by its serial number
+    calibration: Option<CalibrationData>,
+    /// Player number assigned by the manager
+    player_number: Option<u8>,
 }
 
 impl CompositeDevice {
@@ -173,12 +373,48 @@ impl CompositeDevice {
         log::info!("Creating CompositeDevice with config: {}", config.name);
         let (tx, rx) = mpsc::channel(BUFFER_SIZE);
         let name = config.name.clone();
+        let motion_fusion = config
+            .motion_fusion
+            .as_ref()
+            .map(|fusion| MotionFusion::new(FusionFilter::from(fusion)));
+        let event_filter = config.event_filter.as_ref().map(EventFilter::new);
+        let osk_chord = config
+            .on_screen_keyboard
+            .as_ref()
+            .and_then(|osk| osk.chord.clone())
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|cap| match Capability::from_str(cap) {
+                Ok(cap) => Some(cap),
+                Err(_) => {
+                    log::error!("Invalid on-screen keyboard chord capability: {cap}");
+                    None
+                }
+            })
+            .collect();
+        let intercept_toggle_chord = config
+            .intercept_toggle
+            .as_ref()
+            .map(|toggle| toggle.chord.clone())
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|cap| match Capability::from_str(cap) {
+                Ok(cap) => Some(cap),
+                Err(_) => {
+                    log::error!("Invalid intercept toggle chord capability: {cap}");
+                    None
+                }
+            })
+            .collect();
         let mut device = Self {
             conn,
             manager,
             config,
             name,
+            serial: None,
             capabilities: HashSet::new(),
+            source_capabilities: HashMap::new(),
+            detached_capabilities: HashMap::new(),
             capability_map,
             device_profile: None,
             device_profile_config_map: HashMap::new(),
@@ -206,6 +442,41 @@ impl CompositeDevice {
             intercept_mode_target_cap: Capability::Gamepad(Gamepad::Button(GamepadButton::Guide)),
             intercept_active_inputs: Vec::new(),
             active_inputs: Vec::new(),
+            active_axes: HashSet::new(),
+            sources_removed_at: None,
+            steam_coexistence: false,
+            steam_watcher: None,
+            steam_running: false,
+            paused_for_steam: false,
+            hidden_devices: HiddenDeviceGuard::default(),
+            script_engine: None,
+            mapping_plugins: HashMap::new(),
+            event_subscribers: Vec::new(),
+            batteries: HashMap::new(),
+            low_battery_devices: HashSet::new(),
+            profile_path: DEFAULT_PROFILE_PATH.to_string(),
+            auto_profile_path: None,
+            screen_orientation: config.screen_orientation.unwrap_or_default(),
+            orientation: Orientation::default(),
+            motion_fusion,
+            event_filter,
+            osk_chord,
+            osk_pressed: HashSet::new(),
+            osk_visible: false,
+            osk_intercept_mode: None,
+            intercept_toggle_chord,
+            intercept_toggle_pressed: HashSet::new(),
+            intercept_toggle_task: None,
+            intercept_toggle_mode: None,
+            accessibility: None,
+            accessibility_task: None,
+            leds: HashMap::new(),
+            intercept_cue_brightness: HashMap::new(),
+            led_config: None,
+            pressure_curve: None,
+            axis_output: None,
+            calibration: None,
+            player_number: None,
         };
 
         // Load the capability map if one was defined
@@ -214,7 +485,7 @@ impl CompositeDevice {
         }
 
         // Load the default profile
-        let profile_path = "/usr/share/inputplumber/profiles/default.yaml";
+        let profile_path = DEFAULT_PROFILE_PATH;
         if let Err(error) = device.load_device_profile_from_path(profile_path.to_string()) {
             log::warn!(
                 "Unable to load default profile at {}. {}",
@@ -223,18 +494,6 @@ impl CompositeDevice {
             );
         };
 
-        // If a capability map is defined, add those target capabilities to
-        // the hashset of implemented capabilities.
-        if let Some(map) = device.capability_map.as_ref() {
-            for mapping in map.mapping.clone() {
-                let cap = mapping.target_event.clone().into();
-                if cap == Capability::NotImplemented {
-                    continue;
-                }
-                device.capabilities.insert(cap);
-            }
-        }
-
         device.add_source_device(device_info)?;
 
         Ok(device)
@@ -255,6 +514,21 @@ impl CompositeDevice {
         Ok(())
     }
 
+    /// Adds the battery interface to the composite device path on DBus. This
+    /// is done once the first source device reports its battery state.
+    async fn listen_on_dbus_battery(&self) {
+        let Some(path) = self.dbus_path.clone() else {
+            return;
+        };
+        let conn = self.conn.clone();
+        let iface = BatteryInterface::new(self.tx.clone());
+        tokio::spawn(async move {
+            if let Err(e) = conn.object_server().at(path, iface).await {
+                log::error!("Failed to setup DBus battery interface for device: {:?}", e);
+            }
+        });
+    }
+
     /// Starts the [CompositeDevice] and listens for events from all source
     /// devices to translate the events and send them to the appropriate target.
     pub async fn run(
@@ -279,6 +553,13 @@ impl CompositeDevice {
         }
         self.target_devices = targets;
 
+        // Start watching for Steam if coexistence mode is enabled
+        if self.config.steam_coexistence.unwrap_or_default() {
+            self.set_steam_coexistence(true).await;
+        }
+
+        self.notify(Notification::DeviceConnected(self.name.clone()));
+
         // Loop and listen for command events
         log::debug!("CompositeDevice started");
         loop {
@@ -289,12 +570,17 @@ impl CompositeDevice {
             log::trace!("Received command: {:?}", cmd);
             match cmd {
                 Command::ProcessEvent(device_id, event) => {
+                    let started_at = metrics::is_enabled().then(Instant::now);
+                    metrics::record_source_event();
                     if let Err(e) = self.process_event(device_id, event).await {
                         log::error!("Failed to process event: {:?}", e);
                         // TODO: Use proper errors to check for 'SendError' and
                         // stop the composite device
                         break;
                     }
+                    if let Some(started_at) = started_at {
+                        metrics::record_latency(started_at.elapsed());
+                    }
                 }
                 Command::ProcessOutputEvent(event) => {
                     if let Err(e) = self.process_output_event(event).await {
@@ -318,12 +604,16 @@ impl CompositeDevice {
                         log::error!("Failed to send target capabilities: {:?}", e);
                     }
                 }
-                Command::SetInterceptMode(mode) => self.set_intercept_mode(mode),
+                Command::SetInterceptMode(mode) => self.set_intercept_mode(mode).await,
                 Command::GetInterceptMode(sender) => {
                     if let Err(e) = sender.send(self.intercept_mode.clone()).await {
                         log::error!("Failed to send intercept mode: {:?}", e);
                     }
                 }
+                Command::SubscribeEvents(sender) => {
+                    self.event_subscribers.push(sender);
+                }
+                Command::SetAutoProfile(path) => self.set_auto_profile(path).await,
                 Command::GetSourceDevicePaths(sender) => {
                     if let Err(e) = sender.send(self.get_source_device_paths()).await {
                         log::error!("Failed to send source device paths: {:?}", e);
@@ -351,7 +641,7 @@ impl CompositeDevice {
                     if let Err(e) = self.on_source_device_removed(device_id).await {
                         log::error!("Failed to remove source device: {:?}", e);
                     }
-                    if self.source_devices_used.is_empty() {
+                    if self.source_devices_used.is_empty() && self.start_grace_period() {
                         log::debug!(
                             "No source devices remain. Stopping CompositeDevice {:?}",
                             self.dbus_path
@@ -364,7 +654,7 @@ impl CompositeDevice {
                     if let Err(e) = self.on_source_device_removed(device_id).await {
                         log::error!("Failed to remove source device: {:?}", e);
                     }
-                    if self.source_devices_used.is_empty() {
+                    if self.source_devices_used.is_empty() && self.start_grace_period() {
                         log::debug!(
                             "No source devices remain. Stopping CompositeDevice {:?}",
                             self.dbus_path
@@ -372,6 +662,109 @@ impl CompositeDevice {
                         break;
                     }
                 }
+                Command::ReleaseSourceDevice(device_id) => {
+                    log::debug!("Releasing source device: {}", device_id);
+                    self.release_source_device(device_id).await;
+                }
+                Command::HotplugGracePeriodExpired => {
+                    if self.source_devices_used.is_empty() && self.grace_period_elapsed() {
+                        log::debug!(
+                            "No source devices reappeared. Stopping CompositeDevice {:?}",
+                            self.dbus_path
+                        );
+                        break;
+                    }
+                }
+                Command::SourceDeviceWarning(device_id, message) => {
+                    log::debug!("Got warning from source device {device_id}: {message}");
+                    self.signal_source_device_warning(device_id, message).await;
+                }
+                Command::SourceDeviceStalled(device_id, stalled) => {
+                    log::debug!("Source device {device_id} stalled: {stalled}");
+                    self.signal_source_device_stalled(device_id, stalled).await;
+                }
+                Command::SourceDeviceDetached(device_id, detached, capabilities) => {
+                    log::debug!("Source device {device_id} detached: {detached}");
+                    self.on_source_device_detached(device_id, detached, capabilities)
+                        .await;
+                }
+                Command::SourceDeviceBattery(device_id, battery) => {
+                    log::trace!("Source device {device_id} battery: {battery:?}");
+                    self.on_battery_changed(device_id, battery).await;
+                }
+                Command::GetBattery(sender) => {
+                    let battery = BatteryState::lowest(self.batteries.values());
+                    if let Err(e) = sender.send(battery).await {
+                        log::error!("Failed to send battery state: {:?}", e);
+                    }
+                }
+                Command::SetLogLevel(level) => self.set_log_level(level),
+                Command::GetSteamCoexistence(sender) => {
+                    if let Err(e) = sender.send(self.steam_coexistence).await {
+                        log::error!("Failed to send steam coexistence: {:?}", e);
+                    }
+                }
+                Command::SetSteamCoexistence(enabled) => {
+                    self.set_steam_coexistence(enabled).await;
+                }
+                Command::AccessibilityTick => {
+                    if let Err(e) = self.on_accessibility_tick().await {
+                        log::error!("Failed to update accessibility features: {:?}", e);
+                    }
+                }
+                Command::GetOnScreenKeyboard(sender) => {
+                    if let Err(e) = sender.send(self.osk_visible).await {
+                        log::error!("Failed to send on-screen keyboard state: {:?}", e);
+                    }
+                }
+                Command::SetOnScreenKeyboard(visible) => {
+                    self.set_osk_visible(visible).await;
+                }
+                Command::InterceptToggleHeld => {
+                    self.intercept_toggle_task = None;
+                    self.toggle_intercept_mode().await;
+                }
+                Command::InterceptCueEnded => {
+                    self.write_intercept_cue(false).await;
+                }
+                Command::GetLeds(sender) => {
+                    let leds = self
+                        .leds
+                        .values()
+                        .flatten()
+                        .map(|led| led.name.clone())
+                        .collect();
+                    if let Err(e) = sender.send(leds).await {
+                        log::error!("Failed to send LEDs: {:?}", e);
+                    }
+                }
+                Command::GetCalibration(sender) => {
+                    let calibration = self
+                        .calibration
+                        .as_ref()
+                        .and_then(|data| data.to_yaml().ok())
+                        .unwrap_or_default();
+                    if let Err(e) = sender.send(calibration).await {
+                        log::error!("Failed to send calibration: {:?}", e);
+                    }
+                }
+                Command::SetCalibration(content, sender) => {
+                    let result = self.set_calibration(&content).map_err(|e| e.to_string());
+                    if let Err(e) = sender.send(result).await {
+                        log::error!("Failed to send set calibration result: {:?}", e);
+                    }
+                }
+                Command::ResetCalibration(sender) => {
+                    let result = self.reset_calibration().map_err(|e| e.to_string());
+                    if let Err(e) = sender.send(result).await {
+                        log::error!("Failed to send reset calibration result: {:?}", e);
+                    }
+                }
+                Command::CheckSteamClients => self.check_steam_clients(),
+                Command::SetSteamRunning(running) => self.set_steam_running(running).await,
+                Command::SetPausedForSteam(paused) => {
+                    self.set_paused_for_steam(paused).await;
+                }
                 Command::SetTargetDevices(target_types) => {
                     if let Err(e) = self.set_target_devices(target_types).await {
                         log::error!("Failed to set target devices: {e:?}");
@@ -394,10 +787,25 @@ impl CompositeDevice {
                         log::error!("Failed to send profile name: {:?}", e);
                     }
                 }
+                Command::GetPlayerNumber(sender) => {
+                    let player_number = self.player_number.unwrap_or_default();
+                    if let Err(e) = sender.send(player_number).await {
+                        log::error!("Failed to send player number: {:?}", e);
+                    }
+                }
                 Command::LoadProfilePath(path, sender) => {
                     log::info!("Loading profile from path: {path}");
-                    let result = match self.load_device_profile_from_path(path.clone()) {
-                        Ok(_) => Ok(()),
+                    // Release any held inputs before the mapping changes
+                    self.neutralize_targets().await;
+                    let result = match self.load_device_profile(path.clone()).await {
+                        Ok(_) => {
+                            // An explicitly loaded profile replaces any automatic one
+                            self.profile_path = path;
+                            self.auto_profile_path = None;
+                            let profile = self.device_profile.clone().unwrap_or_default();
+                            self.notify(Notification::ProfileLoaded(self.name.clone(), profile));
+                            Ok(())
+                        }
                         Err(e) => Err(e.to_string()),
                     };
                     if let Err(e) = sender.send(result).await {
@@ -405,8 +813,12 @@ impl CompositeDevice {
                     }
                 }
                 Command::WriteEvent(event) => {
+                    let cap = event.as_capability();
                     if let Err(e) = self.write_event(event).await {
-                        log::error!("Failed to write event: {:?}", e);
+                        log::error!(
+                            capability = format!("{cap:?}").as_str();
+                            "Failed to write event: {:?}", e
+                        );
                     }
                 }
                 Command::WriteChordEvent(events) => {
@@ -420,8 +832,12 @@ impl CompositeDevice {
                     }
                 }
                 Command::HandleEvent(event) => {
+                    let cap = event.as_capability();
                     if let Err(e) = self.handle_event(event).await {
-                        log::error!("Failed to write event: {:?}", e);
+                        log::error!(
+                            capability = format!("{cap:?}").as_str();
+                            "Failed to write event: {:?}", e
+                        );
                     }
                 }
                 Command::RemoveRecentEvent(cap) => {
@@ -439,10 +855,21 @@ impl CompositeDevice {
                 }
             }
         }
-        log::info!(
-            "CompositeDevice stopping: {}",
-            self.dbus_path.as_ref().unwrap()
-        );
+        let dbus_path = self.dbus_path.clone().unwrap_or_default();
+        log::info!(composite_device = dbus_path.as_str(); "CompositeDevice stopping: {dbus_path}");
+
+        // Stop watching for Steam
+        if let Some(watcher) = self.steam_watcher.take() {
+            watcher.abort();
+        }
+
+        // Stop updating accessibility features
+        if let Some(task) = self.accessibility_task.take() {
+            task.abort();
+        }
+
+        // Release any held inputs before stopping target devices
+        self.neutralize_targets().await;
 
         // Stop all target devices
         log::debug!("Stopping target devices");
@@ -464,7 +891,7 @@ impl CompositeDevice {
                 continue;
             }
             log::debug!("Un-hiding device: {}", source_path);
-            if let Err(e) = unhide_device(source_path.clone()).await {
+            if let Err(e) = self.hidden_devices.unhide(source_path.clone()).await {
                 log::debug!("Unable to unhide device {source_path}: {:?}", e);
             }
         }
@@ -482,14 +909,466 @@ impl CompositeDevice {
             res?;
         }
 
-        log::info!(
-            "CompositeDevice stopped: {}",
-            self.dbus_path.as_ref().unwrap()
-        );
+        log::info!(composite_device = dbus_path.as_str(); "CompositeDevice stopped: {dbus_path}");
+        self.notify(Notification::DeviceDisconnected(self.name.clone()));
 
         Ok(())
     }
 
+    /// Load the given device profile automatically selected for the focused
+    /// app. If [None] is given, the default or user selected profile is
+    /// restored.
+    async fn set_auto_profile(&mut self, path: Option<String>) {
+        if self.auto_profile_path == path {
+            return;
+        }
+        let profile_path = path.clone().unwrap_or_else(|| self.profile_path.clone());
+        log::info!("Automatically loading profile from path: {profile_path}");
+
+        // Release any held inputs before the mapping changes
+        self.neutralize_targets().await;
+        if let Err(e) = self.load_device_profile(profile_path.clone()).await {
+            log::warn!("Failed to load profile {profile_path}: {e}");
+            return;
+        }
+        self.auto_profile_path = path;
+        let profile = self.device_profile.clone().unwrap_or_default();
+        self.notify(Notification::ProfileLoaded(self.name.clone(), profile));
+    }
+
+    /// Show the given desktop notification if notifications are enabled
+    fn notify(&self, notification: Notification) {
+        if !self.config.notifications.unwrap_or_default() {
+            return;
+        }
+        notifications::send(notification);
+    }
+
+    /// Executed when a source device reports its battery state. Updates the
+    /// battery interface on DBus and shows a notification once when the
+    /// battery drops to the low battery threshold.
+    async fn on_battery_changed(&mut self, device_id: String, battery: BatteryState) {
+        let first_battery = self.batteries.is_empty();
+        let previous = self.batteries.insert(device_id.clone(), battery);
+        if first_battery {
+            self.listen_on_dbus_battery().await;
+        } else if previous != Some(battery) {
+            self.signal_battery_changed().await;
+        }
+
+        if battery.level > notifications::LOW_BATTERY_THRESHOLD {
+            self.low_battery_devices.remove(&device_id);
+            return;
+        }
+        if self.low_battery_devices.insert(device_id) {
+            self.notify(Notification::BatteryLow(self.name.clone(), battery.level));
+        }
+    }
+
+    /// Hide the capabilities of a detachable part of a source device while it
+    /// is detached and restore them when it is attached again.
+    async fn on_source_device_detached(
+        &mut self,
+        device_id: String,
+        detached: bool,
+        capabilities: Vec<Capability>,
+    ) {
+        if detached {
+            self.detached_capabilities
+                .insert(device_id.clone(), capabilities.into_iter().collect());
+        } else if self.detached_capabilities.remove(&device_id).is_none() {
+            return;
+        }
+        if self.update_capabilities() {
+            self.signal_capabilities_changed().await;
+        }
+        self.signal_source_device_detached(device_id, detached)
+            .await;
+    }
+
+    /// Update motion fusion and the device orientation from the given IMU
+    /// event. If motion fusion is enabled, accelerometer events are replaced
+    /// with the fused gravity vector. The orientation is only updated from
+    /// the built-in IIO accelerometer, and only if enabled in the config.
+    async fn process_motion(&mut self, device_id: &str, mut event: NativeEvent) -> NativeEvent {
+        let InputValue::Vector3 { x, y, z } = event.get_value() else {
+            return event;
+        };
+        match event.as_capability() {
+            Capability::Gamepad(Gamepad::Gyro) => {
+                if let Some(fusion) = self.motion_fusion.as_mut() {
+                    fusion.update_gyro(x, y, z);
+                }
+            }
+            Capability::Gamepad(Gamepad::Accelerometer) => {
+                let (x, y, z) = match self.motion_fusion.as_mut() {
+                    Some(fusion) => {
+                        fusion.update_accelerometer(x, y, z);
+                        let [x, y, z] = fusion.gravity();
+                        let value = InputValue::Vector3 {
+                            x: Some(x),
+                            y: Some(y),
+                            z: Some(z),
+                        };
+                        event.set_value(value);
+                        (x, y, z)
+                    }
+                    None => {
+                        let (Some(x), Some(y), Some(z)) = (x, y, z) else {
+                            return event;
+                        };
+                        (x, y, z)
+                    }
+                };
+                if self.screen_orientation && device_id.starts_with("iio://") {
+                    self.update_orientation(x, y, z).await;
+                }
+            }
+            _ => (),
+        }
+        event
+    }
+
+    /// Update the device orientation from the given gravity vector and
+    /// publish it on the sensor proxy interface if it changed.
+    async fn update_orientation(&mut self, x: f64, y: f64, z: f64) {
+        let orientation = Orientation::from_accelerometer(x, y, z, self.orientation);
+        if orientation == self.orientation {
+            return;
+        }
+        self.orientation = orientation;
+        sensor_proxy::update_orientation(&self.conn, orientation).await;
+    }
+
+    /// Track the buttons of the on-screen keyboard chord and toggle the
+    /// on-screen keyboard when all of them are pressed.
+    async fn check_osk_chord(&mut self, event: &NativeEvent) {
+        let cap = event.as_capability();
+        if !self.osk_chord.contains(&cap) {
+            return;
+        }
+        if !event.pressed() {
+            self.osk_pressed.remove(&cap);
+            return;
+        }
+        if !self.osk_pressed.insert(cap) || self.osk_pressed.len() != self.osk_chord.len() {
+            return;
+        }
+        log::debug!("Found on-screen keyboard chord");
+        self.set_osk_visible(!self.osk_visible).await;
+    }
+
+    /// Track the buttons of the intercept toggle chord and toggle intercept
+    /// mode once all of them have been held for the configured time.
+    fn check_intercept_toggle_chord(&mut self, event: &NativeEvent) {
+        let cap = event.as_capability();
+        if !self.intercept_toggle_chord.contains(&cap) {
+            return;
+        }
+        if !event.pressed() {
+            self.intercept_toggle_pressed.remove(&cap);
+            if let Some(task) = self.intercept_toggle_task.take() {
+                task.abort();
+            }
+            return;
+        }
+        if !self.intercept_toggle_pressed.insert(cap)
+            || self.intercept_toggle_pressed.len() != self.intercept_toggle_chord.len()
+        {
+            return;
+        }
+
+        let hold_ms = self
+            .config
+            .intercept_toggle
+            .as_ref()
+            .and_then(|toggle| toggle.hold_ms)
+            .unwrap_or_default();
+        log::debug!("Found intercept toggle chord. Waiting {hold_ms}ms to toggle.");
+        let tx = self.tx.clone();
+        let task = tokio::task::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(hold_ms)).await;
+            if let Err(e) = tx.send(Command::InterceptToggleHeld).await {
+                log::error!("Failed to send intercept toggle command: {:?}", e);
+            }
+        });
+        self.intercept_toggle_task = Some(task);
+    }
+
+    /// Switch to the intercept mode configured for the intercept toggle chord,
+    /// or restore the previous intercept mode if it is already active.
+    async fn toggle_intercept_mode(&mut self) {
+        let config = self.config.intercept_toggle.clone().unwrap_or_default();
+        let mode = match config.mode.as_deref() {
+            Some("motion") => InterceptMode::Motion,
+            _ => InterceptMode::Always,
+        };
+        if self.intercept_mode == mode {
+            let previous = self
+                .intercept_toggle_mode
+                .take()
+                .unwrap_or(InterceptMode::None);
+            log::info!("Intercept toggle chord held. Restoring intercept mode {previous:?}");
+            self.set_intercept_mode(previous).await;
+        } else {
+            log::info!("Intercept toggle chord held. Setting intercept mode {mode:?}");
+            self.intercept_toggle_mode = Some(self.intercept_mode.clone());
+            self.set_intercept_mode(mode).await;
+        }
+
+        // Confirm the change with a short rumble and LED flash
+        if !config.cue.unwrap_or(true) {
+            return;
+        }
+        self.write_intercept_cue(true).await;
+        let tx = self.tx.clone();
+        tokio::task::spawn(async move {
+            tokio::time::sleep(INTERCEPT_CUE_DURATION).await;
+            if let Err(e) = tx.send(Command::InterceptCueEnded).await {
+                log::error!("Failed to send intercept cue command: {:?}", e);
+            }
+        });
+    }
+
+    /// Start or stop the rumble and LED blink used to confirm that intercept
+    /// mode was toggled. The LEDs are turned off for the duration of the cue
+    /// and then restored to their previous brightness.
+    async fn write_intercept_cue(&mut self, active: bool) {
+        if active && self.intercept_cue_brightness.is_empty() {
+            for led in self.leds.values().flatten() {
+                let Ok(brightness) = led.brightness() else {
+                    continue;
+                };
+                if let Err(e) = led.set_brightness(0.0) {
+                    log::debug!("Failed to turn off LED {}: {e:?}", led.name);
+                    continue;
+                }
+                self.intercept_cue_brightness
+                    .insert(led.name.clone(), brightness);
+            }
+        } else if !active {
+            for led in self.leds.values().flatten() {
+                let Some(brightness) = self.intercept_cue_brightness.remove(&led.name) else {
+                    continue;
+                };
+                if let Err(e) = led.set_brightness(brightness) {
+                    log::debug!("Failed to restore LED {}: {e:?}", led.name);
+                }
+            }
+            self.intercept_cue_brightness.clear();
+        }
+
+        // Use generic rumble so every source device with rumble motors plays
+        // it without receiving a full DualSense output report
+        let magnitude = if active { u8::MAX / 2 } else { 0 };
+        let event = OutputEvent::Rumble {
+            left: magnitude,
+            right: magnitude,
+        };
+        if let Err(e) = self.process_output_event(event).await {
+            log::error!("Failed to write intercept cue rumble: {e:?}");
+        }
+    }
+
+    /// Show or hide the on-screen keyboard. While it is shown, the device is
+    /// switched to intercept mode unless disabled in the config, and the
+    /// previous intercept mode is restored when it is dismissed.
+    async fn set_osk_visible(&mut self, visible: bool) {
+        if self.osk_visible == visible {
+            return;
+        }
+        let config = self.config.on_screen_keyboard.clone().unwrap_or_default();
+        let Some(unit) = config.unit else {
+            log::info!("No on-screen keyboard unit configured. Not showing the keyboard.");
+            return;
+        };
+        self.osk_visible = visible;
+        let intercept = config.intercept.unwrap_or(true);
+        log::debug!("Setting on-screen keyboard visible: {visible}");
+
+        if visible {
+            osk::show(self.conn.clone(), unit);
+            if intercept {
+                self.osk_intercept_mode = Some(self.intercept_mode.clone());
+                self.set_intercept_mode(InterceptMode::Always).await;
+            }
+        } else {
+            osk::hide(self.conn.clone(), unit);
+            if let Some(mode) = self.osk_intercept_mode.take() {
+                self.set_intercept_mode(mode).await;
+            }
+        }
+    }
+
+    /// Enable or disable Steam coexistence mode. When enabled, the hidden
+    /// source devices are made accessible while Steam is running, and the
+    /// composite device pauses itself while Steam has any of them open.
+    async fn set_steam_coexistence(&mut self, enabled: bool) {
+        self.steam_coexistence = enabled;
+        if let Some(watcher) = self.steam_watcher.take() {
+            watcher.abort();
+        }
+        if !enabled {
+            self.set_steam_running(false).await;
+            return;
+        }
+
+        log::debug!("Watching for Steam to open source devices");
+        let tx = self.tx.clone();
+        let watcher = tokio::task::spawn(async move {
+            let mut interval = tokio::time::interval(STEAM_CHECK_INTERVAL);
+            loop {
+                interval.tick().await;
+                if tx.send(Command::CheckSteamClients).await.is_err() {
+                    break;
+                }
+            }
+        });
+        self.steam_watcher = Some(watcher);
+    }
+
+    /// Check in the background if Steam is running and has any source devices
+    /// open, and pause or resume the composite device accordingly.
+    fn check_steam_clients(&self) {
+        let paths: Vec<String> = self
+            .source_device_paths
+            .iter()
+            .filter(|path| path.starts_with("/dev/"))
+            .cloned()
+            .collect();
+        let tx = self.tx.clone();
+        tokio::task::spawn(async move {
+            let result = tokio::task::spawn_blocking(move || {
+                let pids = procfs::process::get_steam_pids();
+                let pids_using = procfs::process::get_pids_using(&paths, &pids);
+                (!pids.is_empty(), !pids_using.is_empty())
+            })
+            .await;
+            let (running, using) = match result {
+                Ok(state) => state,
+                Err(e) => {
+                    log::error!("Failed to check for Steam clients: {e:?}");
+                    return;
+                }
+            };
+            if let Err(e) = tx.send(Command::SetSteamRunning(running)).await {
+                log::debug!("Failed to send steam running state: {e:?}");
+            }
+            if let Err(e) = tx.send(Command::SetPausedForSteam(using)).await {
+                log::debug!("Failed to send steam paused state: {e:?}");
+            }
+        });
+    }
+
+    /// Make the hidden source devices accessible while Steam is running, so
+    /// Steam can open them. Hidden devices have no permissions, so Steam would
+    /// otherwise never be able to use them. The devices are hidden again and
+    /// the composite device resumes once Steam exits.
+    async fn set_steam_running(&mut self, running: bool) {
+        if self.steam_running == running {
+            return;
+        }
+        self.steam_running = running;
+        if running {
+            log::info!("Steam is running. Allowing it to open {:?}", self.dbus_path);
+            self.hidden_devices.restore_permissions();
+        } else {
+            self.set_paused_for_steam(false).await;
+            self.hidden_devices.revoke_permissions();
+        }
+    }
+
+    /// Pause or resume the composite device for Steam. While paused, source
+    /// devices are released and no input events are sent to target devices.
+    async fn set_paused_for_steam(&mut self, paused: bool) {
+        if self.paused_for_steam == paused {
+            return;
+        }
+        if paused {
+            log::info!("Steam opened a source device. Pausing {:?}", self.dbus_path);
+            self.neutralize_targets().await;
+        } else {
+            log::info!("Steam released source devices. Resuming {:?}", self.dbus_path);
+        }
+        self.paused_for_steam = paused;
+
+        // Release or re-grab source devices
+        for (id, source) in self.source_devices.iter() {
+            if let Err(e) = source.send(SourceCommand::SetGrab(!paused)).await {
+                log::error!("Failed to set grab on source device {id}: {e:?}");
+            }
+        }
+    }
+
+    /// Override the log level for this composite device and its source
+    /// devices. If no level is given, the override is removed.
+    fn set_log_level(&self, level: Option<log::LevelFilter>) {
+        let Some(path) = self.dbus_path.as_ref() else {
+            return;
+        };
+        match level {
+            Some(level) => logger::set_device_level(path, level),
+            None => logger::reset_device_level(path),
+        }
+    }
+
+    /// Returns the configured hotplug grace period. There is no grace period
+    /// unless one is configured or transport switching is enabled.
+    fn hotplug_grace_period(&self) -> Duration {
+        let default = match self.config.transport_switching.unwrap_or(false) {
+            true => DEFAULT_TRANSPORT_SWITCH_GRACE_PERIOD_MS,
+            false => 0,
+        };
+        let millis = self.config.hotplug_grace_period_ms.unwrap_or(default);
+        Duration::from_millis(millis)
+    }
+
+    /// Stops the given source device without it being removed from the
+    /// system, such as when the same controller is now used over a different
+    /// connection. The device is removed once its task stops.
+    async fn release_source_device(&mut self, id: String) {
+        let Some(source) = self.source_devices.get(&id) else {
+            log::debug!("Source device {id} is not used by this composite device");
+            return;
+        };
+        if let Err(e) = source.send(SourceCommand::Stop).await {
+            log::debug!("Failed to stop source device {id}: {e:?}");
+        }
+    }
+
+    /// Starts the hotplug grace period after all source devices have been
+    /// removed. Returns true if there is no grace period and the device should
+    /// stop immediately.
+    fn start_grace_period(&mut self) -> bool {
+        let grace_period = self.hotplug_grace_period();
+        if grace_period.is_zero() {
+            return true;
+        }
+        log::debug!(
+            "No source devices remain. Waiting {grace_period:?} for devices to reappear on {:?}",
+            self.dbus_path
+        );
+        self.sources_removed_at = Some(Instant::now());
+        let tx = self.tx.clone();
+        tokio::task::spawn(async move {
+            tokio::time::sleep(grace_period).await;
+            if let Err(e) = tx.send(Command::HotplugGracePeriodExpired).await {
+                log::error!("Failed to send grace period expired: {e:?}");
+            }
+        });
+
+        false
+    }
+
+    /// Returns true if the hotplug grace period has fully elapsed since the
+    /// last source device was removed.
+    fn grace_period_elapsed(&self) -> bool {
+        let Some(removed_at) = self.sources_removed_at else {
+            return false;
+        };
+        removed_at.elapsed() >= self.hotplug_grace_period()
+    }
+
     /// Return a [Command] transmitter to communitcate with the device while it
     /// is running
     pub fn transmitter(&self) -> mpsc::Sender<Command> {
@@ -501,6 +1380,16 @@ impl CompositeDevice {
         self.source_devices_used.clone()
     }
 
+    /// Returns the serial number of the source device(s) if one is known
+    pub fn get_serial(&self) -> Option<String> {
+        self.serial.clone()
+    }
+
+    /// Sets the player number of the [CompositeDevice]
+    pub fn set_player_number(&mut self, player_number: u8) {
+        self.player_number = Some(player_number);
+    }
+
     /// Sets the DBus target devices on the [CompositeDevice].
     pub fn set_dbus_devices(&mut self, devices: HashMap<String, mpsc::Sender<TargetCommand>>) {
         self.target_dbus_devices = devices;
@@ -526,7 +1415,11 @@ impl CompositeDevice {
                 continue;
             }
             log::debug!("Hiding device: {}", source_path);
-            hide_device(source_path).await?;
+            self.hidden_devices.hide(source_path).await?;
+        }
+        // Devices that were just hidden must stay accessible to Steam
+        if self.steam_running {
+            self.hidden_devices.restore_permissions();
         }
 
         log::debug!("Starting new source devices");
@@ -541,15 +1434,32 @@ impl CompositeDevice {
                     let source_tx = device.transmitter();
                     self.source_devices.insert(device_id.clone(), source_tx);
                     let tx = self.tx.clone();
-                    self.source_device_tasks.spawn(async move {
-                        if let Err(e) = device.run().await {
-                            log::error!("Failed running event device: {:?}", e);
+                    let composite_path = self.dbus_path.clone().unwrap_or_default();
+                    let device_path = device.get_device_path();
+                    let conn = self.conn.clone();
+                    let log_scope = composite_path.clone();
+                    let task = async move {
+                        let result = device
+                            .run()
+                            .await
+                            .map_err(|e| format!("Failed running event device: {e}"));
+                        if let Err(message) = result {
+                            log::error!(
+                                source_device = device_id.as_str(),
+                                composite_device = composite_path.as_str(),
+                                driver = "evdev";
+                                "{message}"
+                            );
+                            let kind = DeviceErrorKind::ReadFailed;
+                            emit_device_error(&conn, device_path, kind, message).await;
                         }
                         log::debug!("Event device closed");
                         if let Err(e) = tx.send(Command::SourceDeviceStopped(device_id)).await {
                             log::error!("Failed to send device stop command: {:?}", e);
                         }
-                    });
+                    };
+                    self.source_device_tasks
+                        .spawn(logger::scope(log_scope, task));
                 }
 
                 // If the source device is a hidraw device (i.e. /dev/hidraw0),
@@ -557,17 +1467,51 @@ impl CompositeDevice {
                 SourceDevice::HIDRawDevice(mut device) => {
                     let device_id = device.get_id();
                     let source_tx = device.transmitter();
-                    self.source_devices.insert(device_id.clone(), source_tx);
+                    self.source_devices
+                        .insert(device_id.clone(), source_tx.clone());
                     let tx = self.tx.clone();
-                    self.source_device_tasks.spawn(async move {
-                        if let Err(e) = device.run().await {
-                            log::error!("Failed running hidraw device: {:?}", e);
+                    let composite_path = self.dbus_path.clone().unwrap_or_default();
+                    let device_path = device.get_device_path();
+                    let conn = self.conn.clone();
+                    let log_scope = composite_path.clone();
+                    // Re-open continuously reporting devices if they stall
+                    let monitor = device.report_receiver().map(|report_rx| {
+                        let monitor = source::monitor_stalls(
+                            device_id.clone(),
+                            report_rx,
+                            source_tx,
+                            tx.clone(),
+                        );
+                        tokio::spawn(logger::scope(log_scope.clone(), monitor))
+                    });
+                    let task = async move {
+                        let mut result = device
+                            .run()
+                            .await
+                            .map_err(|e| format!("Failed running hidraw device: {e}"));
+                        if let Some(monitor) = monitor {
+                            monitor.abort();
+                            if let Ok(Err(message)) = monitor.await {
+                                result = Err(message);
+                            }
+                        }
+                        if let Err(message) = result {
+                            log::error!(
+                                source_device = device_id.as_str(),
+                                composite_device = composite_path.as_str(),
+                                driver = "hidraw";
+                                "{message}"
+                            );
+                            let kind = DeviceErrorKind::ReadFailed;
+                            emit_device_error(&conn, device_path, kind, message).await;
                         }
                         log::debug!("HIDRaw device closed");
                         if let Err(e) = tx.send(Command::SourceDeviceStopped(device_id)).await {
                             log::error!("Failed to send device stop command: {:?}", e);
                         }
-                    });
+                    };
+                    self.source_device_tasks
+                        .spawn(logger::scope(log_scope, task));
                 }
 
                 // If the source device is an iio device (i.e. /sys/bus/iio/devices/iio:device0),
@@ -577,15 +1521,32 @@ impl CompositeDevice {
                     let source_tx = device.transmitter();
                     self.source_devices.insert(device_id.clone(), source_tx);
                     let tx = self.tx.clone();
-                    self.source_device_tasks.spawn(async move {
-                        if let Err(e) = device.run().await {
-                            log::error!("Failed running iio device: {:?}", e);
+                    let composite_path = self.dbus_path.clone().unwrap_or_default();
+                    let device_path = device.get_device_path();
+                    let conn = self.conn.clone();
+                    let log_scope = composite_path.clone();
+                    let task = async move {
+                        let result = device
+                            .run()
+                            .await
+                            .map_err(|e| format!("Failed running iio device: {e}"));
+                        if let Err(message) = result {
+                            log::error!(
+                                source_device = device_id.as_str(),
+                                composite_device = composite_path.as_str(),
+                                driver = "iio";
+                                "{message}"
+                            );
+                            let kind = DeviceErrorKind::ReadFailed;
+                            emit_device_error(&conn, device_path, kind, message).await;
                         }
                         log::debug!("IIO device closed");
                         if let Err(e) = tx.send(Command::SourceDeviceStopped(device_id)).await {
                             log::error!("Failed to send device stop command: {:?}", e);
                         }
-                    });
+                    };
+                    self.source_device_tasks
+                        .spawn(logger::scope(log_scope, task));
                 }
             }
         }
@@ -604,6 +1565,10 @@ impl CompositeDevice {
             log::trace!("Blocking event! {:?}", raw_event);
             return Ok(());
         }
+        if self.paused_for_steam {
+            log::trace!("Paused for Steam. Dropping event: {:?}", raw_event);
+            return Ok(());
+        }
         //log::trace!("Received event: {:?} from {device_id}", raw_event);
 
         // Convert the event into a NativeEvent
@@ -616,6 +1581,38 @@ impl CompositeDevice {
         let cap = event.as_capability();
         //log::trace!("Event capability: {:?}", cap);
 
+        // Apply the stored calibration of the controller
+        let event = match self.calibration.as_ref() {
+            Some(calibration) => calibration.apply(event),
+            None => event,
+        };
+
+        // Fuse IMU events and publish the device orientation for screen
+        // auto-rotation
+        let event = self.process_motion(&device_id, event).await;
+
+        // Toggle the on-screen keyboard if its chord was pressed
+        self.check_osk_chord(&event).await;
+
+        // Toggle intercept mode if its chord was held
+        self.check_intercept_toggle_chord(&event);
+
+        // Apply the accessibility filters of the device profile
+        let event = match self.accessibility.as_mut() {
+            Some(accessibility) => match accessibility.process(event) {
+                Some(event) => event,
+                None => return Ok(()),
+            },
+            None => event,
+        };
+
+        // Apply the touch pressure curve of the device profile
+        let event = match self.pressure_curve.as_ref() {
+            Some(curve) => curve.process(event),
+            None => event,
+        };
+        let cap = event.as_capability();
+
         // Only send valid events to the target device(s)
         if cap == Capability::NotImplemented {
             log::trace!("Refusing to send 'NotImplemented' event to target devices");
@@ -643,6 +1640,41 @@ impl CompositeDevice {
     async fn process_output_event(&mut self, event: OutputEvent) -> Result<(), Box<dyn Error>> {
         //log::trace!("Received output event: {:?}", event);
 
+        // LED class devices are managed by the composite device instead of
+        // the source device drivers
+        if let OutputEvent::Led(led_event) = &event {
+            self.write_led_event(*led_event);
+            return Ok(());
+        }
+
+        // Mirror the DualSense light bar color, player lights, and mute light
+        // onto the LEDs of source devices
+        if let OutputEvent::DualSense(report) = &event {
+            if report.allow_led_color {
+                let color = LedEvent::Color(report.led_red, report.led_green, report.led_blue);
+                self.write_led_event(color);
+            }
+            if report.allow_player_indicators {
+                let lights = [
+                    report.player_light_1,
+                    report.player_light_2,
+                    report.player_light_3,
+                    report.player_light_4,
+                    report.player_light_5,
+                ];
+                let lights = lights
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, is_on)| **is_on)
+                    .fold(0, |mask, (i, _)| mask | (1 << i));
+                self.write_led_event(LedEvent::Player(lights));
+            }
+            if report.allow_mute_light {
+                let is_on = matches!(report.mute_light_mode, MuteLight::On | MuteLight::Breathing);
+                self.write_led_event(LedEvent::Mute(is_on));
+            }
+        }
+
         // Handle any output events that need to upload FF effect data
         if let OutputEvent::Uinput(uinput) = event.borrow() {
             match uinput {
@@ -857,7 +1889,7 @@ impl CompositeDevice {
             // If we have a match, handle it now and return.
             if active_caps == self.intercept_activation_caps {
                 log::debug!("Found activation chord!");
-                self.set_intercept_mode(InterceptMode::Always);
+                self.set_intercept_mode(InterceptMode::Always).await;
                 let event = NativeEvent::new(
                     self.intercept_mode_target_cap.clone(),
                     InputValue::Bool(true),
@@ -881,6 +1913,7 @@ impl CompositeDevice {
             match cap {
                 Capability::None
                 | Capability::Touchpad(_)
+                | Capability::Touchscreen(_)
                 | Capability::NotImplemented
                 | Capability::Sync
                 | Capability::DBus(_) => {}
@@ -971,11 +2004,54 @@ impl CompositeDevice {
     }
 
     /// Writes the given event to the appropriate target device.
-    async fn write_event(&self, event: NativeEvent) -> Result<(), Box<dyn Error>> {
+    async fn write_event(&mut self, event: NativeEvent) -> Result<(), Box<dyn Error>> {
+        metrics::record_target_event();
         let cap = event.as_capability();
 
-        // If this event implements the DBus capability, send the event to DBus devices
-        if matches!(cap, Capability::DBus(_)) {
+        // Rescale target axes into the output range of the device profile
+        let event = match self.axis_output.as_ref() {
+            Some(axis_output) => axis_output.process(event),
+            None => event,
+        };
+
+        // Drop duplicate axis events that would not change the target state
+        if let Some(filter) = self.event_filter.as_mut() {
+            if !filter.process(&event) {
+                return Ok(());
+            }
+        }
+
+        // Restart the dwell click timer when the pointer moves
+        if cap == Capability::Mouse(Mouse::Motion) {
+            if let Some(accessibility) = self.accessibility.as_mut() {
+                accessibility.on_pointer_motion();
+            }
+        }
+
+        // Forward the event to any subscribed clients. Slow clients miss
+        // events instead of blocking input.
+        if !self.event_subscribers.is_empty() {
+            self.event_subscribers.retain(|subscriber| {
+                !matches!(
+                    subscriber.try_send(event.clone()),
+                    Err(mpsc::error::TrySendError::Closed(_))
+                )
+            });
+        }
+
+        // Keep track of axis inputs so they can be re-centered if needed
+        if matches!(
+            cap,
+            Capability::Gamepad(Gamepad::Axis(_))
+                | Capability::Gamepad(Gamepad::Trigger(_))
+                | Capability::Mouse(Mouse::Motion)
+        ) {
+            self.active_axes.insert(cap.clone());
+        }
+
+        // If this event implements the DBus capability, send the event to DBus devices.
+        // Touchscreen events can only be handled by DBus devices.
+        if matches!(cap, Capability::DBus(_) | Capability::Touchscreen(_)) {
             let event = TargetCommand::WriteEvent(event);
             log::trace!("Emit dbus event: {:?}", event);
             #[allow(clippy::for_kv_map)]
@@ -986,8 +2062,18 @@ impl CompositeDevice {
         }
 
         // If the device is in intercept mode, only send events to DBus
-        // target devices.
-        if matches!(self.intercept_mode, InterceptMode::Always) {
+        // target devices. In motion intercept mode, only motion and touch
+        // events are sent to DBus so overlays can use them for pointer control.
+        let is_motion = matches!(
+            cap,
+            Capability::Gamepad(Gamepad::Gyro | Gamepad::Accelerometer) | Capability::Touchpad(_)
+        );
+        let intercepted = match self.intercept_mode {
+            InterceptMode::Always => true,
+            InterceptMode::Motion => is_motion,
+            _ => false,
+        };
+        if intercepted {
             let event = TargetCommand::WriteEvent(event);
             log::trace!("Emit intercepted event: {:?}", event);
             #[allow(clippy::for_kv_map)]
@@ -1087,11 +2173,60 @@ impl CompositeDevice {
     }
 
     /// Sets the intercept mode to the given value
-    fn set_intercept_mode(&mut self, mode: InterceptMode) {
+    async fn set_intercept_mode(&mut self, mode: InterceptMode) {
         log::debug!("Setting intercept mode to: {:?}", mode);
+        if self.intercept_mode != mode {
+            // Release any held inputs before events are routed elsewhere
+            self.neutralize_targets().await;
+        }
         self.intercept_mode = mode;
     }
 
+    /// Emits release events to all target devices for every input that is
+    /// currently held and clears all tracked input state. This prevents stuck
+    /// buttons and runaway movement when event routing changes.
+    async fn neutralize_targets(&mut self) {
+        let mut events = Vec::new();
+        for cap in self.active_inputs.drain(..) {
+            events.push(NativeEvent::new(cap, InputValue::Bool(false)));
+        }
+        for cap in self.active_axes.drain() {
+            let centered = InputValue::Vector2 {
+                x: Some(0.0),
+                y: Some(0.0),
+            };
+            let event = match cap {
+                Capability::Gamepad(Gamepad::Trigger(_)) => {
+                    NativeEvent::new(cap, InputValue::Float(0.0))
+                }
+                // Translated mouse motion is velocity based, so it needs to be
+                // stopped with a translated event.
+                Capability::Mouse(Mouse::Motion) => {
+                    NativeEvent::new_translated(cap.clone(), cap, centered)
+                }
+                _ => NativeEvent::new(cap, centered),
+            };
+            events.push(event);
+        }
+        self.translatable_active_inputs.clear();
+        self.emitted_mappings.clear();
+        self.intercept_active_inputs.clear();
+
+        if events.is_empty() {
+            return;
+        }
+        log::debug!("Neutralizing held inputs: {:?}", events);
+        for event in events {
+            let cmd = TargetCommand::WriteEvent(event);
+            let targets = self.target_devices.iter().chain(self.target_dbus_devices.iter());
+            for (path, target) in targets {
+                if let Err(e) = target.send(cmd.clone()).await {
+                    log::error!("Failed to send release event to {path}: {e:?}");
+                }
+            }
+        }
+    }
+
     /// Translates the given event into a different event based on the given
     /// [CapabilityMap].
     async fn translate_capability(&mut self, event: &NativeEvent) -> Result<(), Box<dyn Error>> {
@@ -1136,6 +2271,8 @@ impl CompositeDevice {
         // capabilities may use one or more of the same source capability and
         // they would release at the same time.
         let mut emit_queue = Vec::new();
+        let mut matched_mappings = Vec::new();
+        let source_timestamp = event.get_timestamp();
 
         // Loop over each mapping and try to match source events
         for mapping in map.mapping.iter() {
@@ -1163,7 +2300,11 @@ impl CompositeDevice {
                     if cap == Capability::NotImplemented {
                         continue;
                     }
-                    let event = NativeEvent::new(cap, InputValue::Bool(false));
+                    let value = translate_mapping_value(mapping, &cap, false);
+                    let mut event = NativeEvent::new(cap, value);
+                    if let Some(timestamp) = source_timestamp {
+                        event.set_timestamp(timestamp);
+                    }
                     log::trace!("Adding event to emit queue: {:?}", event);
                     emit_queue.push(event);
                     self.emitted_mappings.remove(&mapping.name);
@@ -1185,19 +2326,40 @@ impl CompositeDevice {
                 }
 
                 if !is_missing_source_event {
-                    let cap = mapping.target_event.clone().into();
-                    if cap == Capability::NotImplemented {
-                        continue;
-                    }
-                    let event = NativeEvent::new(cap, InputValue::Bool(true));
-                    log::trace!("Adding event to emit queue: {:?}", event);
-                    emit_queue.push(event);
-                    self.emitted_mappings
-                        .insert(mapping.name.clone(), mapping.clone());
+                    matched_mappings.push(mapping);
                 }
             }
         }
 
+        // Only emit the most specific of the matched mappings, so a mapping
+        // with modifiers (e.g. KeyLeftShift + KeyW) takes precedence over a
+        // mapping of the same key without them (e.g. KeyW).
+        for mapping in matched_mappings.iter() {
+            let is_shadowed = matched_mappings
+                .iter()
+                .any(|other| is_mapping_subset(mapping, other));
+            if is_shadowed {
+                log::trace!(
+                    "Mapping {} shadowed by a more specific mapping",
+                    mapping.name
+                );
+                continue;
+            }
+            let cap = mapping.target_event.clone().into();
+            if cap == Capability::NotImplemented {
+                continue;
+            }
+            let value = translate_mapping_value(mapping, &cap, true);
+            let mut event = NativeEvent::new(cap, value);
+            if let Some(timestamp) = source_timestamp {
+                event.set_timestamp(timestamp);
+            }
+            log::trace!("Adding event to emit queue: {:?}", event);
+            emit_queue.push(event);
+            self.emitted_mappings
+                .insert(mapping.name.clone(), mapping.clone());
+        }
+
         // Emit the translated events. If this translated event has been emitted
         // very recently, delay sending subsequent events of the same type.
         let sleep_time = Duration::from_millis(4);
@@ -1302,13 +2464,48 @@ impl CompositeDevice {
                                 }
                             }
                         }
-                    };
-                    if matches!(value, InputValue::None) {
-                        continue;
+                    };
+                    if matches!(value, InputValue::None) {
+                        continue;
+                    }
+
+                    let mut translated =
+                        NativeEvent::new_translated(source_cap.clone(), target_cap, value);
+                    if let Some(timestamp) = event.get_timestamp() {
+                        translated.set_timestamp(timestamp);
+                    }
+
+                    // Run any script attached to the mapping to transform the value
+                    if let Some(engine) = self.script_engine.as_ref() {
+                        if engine.has_script(&mapping.name) {
+                            match engine.run(&mapping.name, &translated) {
+                                Ok(Some(value)) => translated.set_value(value),
+                                Ok(None) => continue,
+                                Err(e) => log::warn!(
+                                    "Failed to run script for profile mapping '{}': {e}",
+                                    mapping.name
+                                ),
+                            }
+                        }
+                    }
+
+                    // Run any plugin attached to the mapping to transform the event
+                    if let Some(plugin) = self.mapping_plugins.get(&mapping.name) {
+                        let result = match plugin.lock() {
+                            Ok(mut plugin) => plugin.transform(&translated),
+                            Err(e) => Err(e.to_string().into()),
+                        };
+                        match result {
+                            Ok(Some(transformed)) => translated = transformed,
+                            Ok(None) => continue,
+                            Err(e) => log::warn!(
+                                "Failed to run plugin for profile mapping '{}': {e}",
+                                mapping.name
+                            ),
+                        }
                     }
 
-                    let event = NativeEvent::new_translated(source_cap.clone(), target_cap, value);
-                    events.push(event);
+                    events.push(translated);
                 }
 
                 return Ok(events);
@@ -1324,11 +2521,15 @@ impl CompositeDevice {
         &mut self,
         device_info: SourceDeviceInfo,
     ) -> Result<(), Box<dyn Error>> {
+        let capabilities = self.capabilities.clone();
         self.add_source_device(device_info)?;
         self.run_source_devices().await?;
 
         // Signal to DBus that source devices have changed
         self.signal_sources_changed().await;
+        if self.capabilities != capabilities {
+            self.signal_capabilities_changed().await;
+        }
 
         log::debug!(
             "Finished adding source device. All sources: {:?}",
@@ -1339,6 +2540,17 @@ impl CompositeDevice {
 
     /// Executed whenever a source device is removed from this [CompositeDevice]
     async fn on_source_device_removed(&mut self, id: String) -> Result<(), Box<dyn Error>> {
+        self.leds.remove(&id);
+        let battery_count = self.batteries.len();
+        self.batteries.retain(|battery_id, _| {
+            battery_id != &id && !battery_id.starts_with(&format!("{id}/"))
+        });
+        if self.batteries.len() != battery_count {
+            self.signal_battery_changed().await;
+        }
+        self.source_capabilities.remove(&id);
+        self.detached_capabilities
+            .retain(|part_id, _| !part_id.starts_with(&format!("{id}/")));
         if id.starts_with("evdev://") {
             let name = id.strip_prefix("evdev://").unwrap();
             let path = format!("/dev/input/{}", name);
@@ -1368,6 +2580,9 @@ impl CompositeDevice {
 
         // Signal to DBus that source devices have changed
         self.signal_sources_changed().await;
+        if self.update_capabilities() {
+            self.signal_capabilities_changed().await;
+        }
 
         log::debug!(
             "Current source device paths: {:?}",
@@ -1384,30 +2599,35 @@ impl CompositeDevice {
     /// Creates and adds a source device using the given [SourceDeviceInfo]
     fn add_source_device(&mut self, device_info: SourceDeviceInfo) -> Result<(), Box<dyn Error>> {
         let device_info = device_info.clone();
+        self.sources_removed_at = None;
+        if self.serial.is_none() {
+            self.serial = device_info.get_serial();
+            self.load_calibration();
+        }
         match device_info.clone() {
             SourceDeviceInfo::EvdevDeviceInfo(info) => {
                 // Create an instance of the device
                 log::debug!("Adding source device: {:?}", info);
-                let device = source::evdev::EventDevice::new(info.clone(), self.tx.clone());
+                // Get any defined config for the event device
+                let config =
+                    if let Some(device_config) = self.config.get_matching_device(&device_info) {
+                        device_config.evdev
+                    } else {
+                        None
+                    };
+                let device = source::evdev::EventDevice::new(info.clone(), config, self.tx.clone());
 
                 // Get the capabilities of the source device.
-                // TODO: When we *remove* a source device, we also need to remove
-                // capabilities
                 let capabilities = device.get_capabilities()?;
-                for cap in capabilities {
-                    if self.translatable_capabilities.contains(&cap) {
-                        continue;
-                    }
-                    self.capabilities.insert(cap);
-                }
 
-                // TODO: Based on the capability map in the config, translate
-                // the capabilities.
                 // Keep track of the source device
                 let id = device.get_id();
+                self.source_capabilities
+                    .insert(id.clone(), capabilities.into_iter().collect());
                 let device_path = device.get_device_path();
                 let source_device = source::SourceDevice::EventDevice(device);
                 self.source_devices_discovered.push(source_device);
+                self.add_source_leds(id.clone(), &device_path);
                 self.source_device_paths.push(device_path);
                 self.source_devices_used.push(id.clone());
 
@@ -1422,22 +2642,27 @@ impl CompositeDevice {
             }
 
             SourceDeviceInfo::HIDRawDeviceInfo(info) => {
+                // Get any defined config for the HIDRaw device
+                let config =
+                    if let Some(device_config) = self.config.get_matching_device(&device_info) {
+                        device_config.hidraw
+                    } else {
+                        None
+                    };
+
                 log::debug!("Adding source device: {:?}", info);
-                let device = source::hidraw::HIDRawDevice::new(info, self.tx.clone());
+                let device = source::hidraw::HIDRawDevice::new(info, config, self.tx.clone());
 
                 // Get the capabilities of the source device.
                 let capabilities = device.get_capabilities()?;
-                for cap in capabilities {
-                    if self.translatable_capabilities.contains(&cap) {
-                        continue;
-                    }
-                    self.capabilities.insert(cap);
-                }
 
                 let id = device.get_id();
+                self.source_capabilities
+                    .insert(id.clone(), capabilities.into_iter().collect());
                 let device_path = device.get_device_path();
                 let source_device = source::SourceDevice::HIDRawDevice(device);
                 self.source_devices_discovered.push(source_device);
+                self.add_source_leds(id.clone(), &device_path);
                 self.source_device_paths.push(device_path);
                 self.source_devices_used.push(id.clone());
 
@@ -1464,14 +2689,10 @@ impl CompositeDevice {
 
                 // Get the capabilities of the source device.
                 let capabilities = device.get_capabilities()?;
-                for cap in capabilities {
-                    if self.translatable_capabilities.contains(&cap) {
-                        continue;
-                    }
-                    self.capabilities.insert(cap);
-                }
 
                 let id = device.get_id();
+                self.source_capabilities
+                    .insert(id.clone(), capabilities.into_iter().collect());
                 let device_path = device.get_device_path();
                 let source_device = source::SourceDevice::IIODevice(device);
                 self.source_devices_discovered.push(source_device);
@@ -1488,19 +2709,71 @@ impl CompositeDevice {
                 };
             }
         }
+        self.update_capabilities();
 
         Ok(())
     }
 
-    /// Load the given device profile from the given path
+    /// Rebuild the capabilities of the device from the capabilities of its
+    /// source devices and the target capabilities of the capability map.
+    /// Capabilities of detached parts of source devices are left out.
+    /// Returns true if the capabilities changed.
+    fn update_capabilities(&mut self) -> bool {
+        let detached: HashSet<&Capability> =
+            self.detached_capabilities.values().flatten().collect();
+        let mut capabilities: HashSet<Capability> = self
+            .source_capabilities
+            .values()
+            .flatten()
+            .filter(|cap| !self.translatable_capabilities.contains(cap))
+            .filter(|cap| !detached.contains(cap))
+            .cloned()
+            .collect();
+        if let Some(map) = self.capability_map.as_ref() {
+            for mapping in map.mapping.iter() {
+                let cap = mapping.target_event.clone().into();
+                if cap == Capability::NotImplemented {
+                    continue;
+                }
+                capabilities.insert(cap);
+            }
+        }
+        if capabilities == self.capabilities {
+            return false;
+        }
+        log::debug!("Capabilities changed: {capabilities:?}");
+        self.capabilities = capabilities;
+        true
+    }
+
+    /// Load the given device profile from the given path. Any scripts and
+    /// plugins in the profile are compiled on the calling thread, so this is
+    /// only used while the device is being created.
     pub fn load_device_profile_from_path(&mut self, path: String) -> Result<(), Box<dyn Error>> {
         log::debug!("Loading device profile from path: {path}");
+        let (profile, runtime) = ProfileRuntime::load(path)?;
+        self.set_device_profile(profile, runtime);
+        Ok(())
+    }
+
+    /// Load the given device profile from the given path. Compiling the
+    /// scripts and plugins in the profile is done on a blocking thread so
+    /// other devices keep processing events in the meantime.
+    async fn load_device_profile(&mut self, path: String) -> Result<(), Box<dyn Error>> {
+        log::debug!("Loading device profile from path: {path}");
+        let (profile, runtime) =
+            logger::spawn_blocking_in_scope(move || ProfileRuntime::load(path)).await??;
+        self.set_device_profile(profile, runtime);
+        Ok(())
+    }
+
+    /// Replace the current device profile mappings with the given profile
+    fn set_device_profile(&mut self, profile: DeviceProfile, runtime: ProfileRuntime) {
         // Remove all outdated capability mappings.
         log::debug!("Clearing old device profile mappings");
         self.device_profile_config_map.clear();
-
-        // Load and parse the device profile
-        let profile = DeviceProfile::from_yaml_file(path.clone())?;
+        self.script_engine = runtime.script_engine;
+        self.mapping_plugins = runtime.mapping_plugins;
         self.device_profile = Some(profile.name.clone());
 
         // Loop through every mapping in the profile, extract the source and target events,
@@ -1528,6 +2801,26 @@ impl CompositeDevice {
             config_map.push(mapping.clone());
         }
 
+        // Apply the accessibility options of the profile
+        self.set_accessibility(profile.accessibility.clone());
+
+        // Apply the LED state of the profile
+        self.led_config = profile.leds.clone();
+        self.apply_led_config();
+
+        // Apply the touch options of the profile
+        self.pressure_curve = profile
+            .touch
+            .as_ref()
+            .and_then(|touch| touch.pressure_curve.as_ref())
+            .map(|points| PressureCurve::new(points.as_slice()));
+
+        // Apply the axis output ranges of the profile
+        self.axis_output = profile
+            .axis_output
+            .as_ref()
+            .map(|configs| AxisOutputMap::new(configs.as_slice()));
+
         // Set the target devices to use if it is defined in the profile
         if let Some(target_devices) = profile.target_devices {
             let tx = self.tx.clone();
@@ -1539,9 +2832,122 @@ impl CompositeDevice {
         }
 
         log::debug!("Successfully loaded device profile: {}", profile.name);
+    }
+
+    /// Load the stored calibration of the controller once its serial number
+    /// is known
+    fn load_calibration(&mut self) {
+        let Some(serial) = self.serial.as_ref() else {
+            return;
+        };
+        self.calibration = CalibrationData::load(serial);
+        if self.calibration.is_some() {
+            log::info!("Loaded stored calibration for {serial}");
+        }
+    }
+
+    /// Store and apply the given calibration in YAML format
+    fn set_calibration(&mut self, content: &str) -> Result<(), Box<dyn Error>> {
+        let Some(serial) = self.serial.as_ref() else {
+            return Err("Device has no serial number to store calibration for".into());
+        };
+        let data = CalibrationData::from_yaml(content)?;
+        data.save(serial)?;
+        self.calibration = Some(data);
+        Ok(())
+    }
+
+    /// Remove the stored calibration of the controller
+    fn reset_calibration(&mut self) -> Result<(), Box<dyn Error>> {
+        self.calibration = None;
+        let Some(serial) = self.serial.as_ref() else {
+            return Ok(());
+        };
+        CalibrationData::reset(serial)?;
+        log::info!("Reset stored calibration for {serial}");
         Ok(())
     }
 
+    /// Discover the LED class devices of the given source device
+    fn add_source_leds(&mut self, id: String, device_path: &str) {
+        let leds = led::find_leds(device_path);
+        if leds.is_empty() {
+            return;
+        }
+        self.leds.insert(id, leds);
+        self.apply_led_config();
+    }
+
+    /// Set the LEDs of all source devices to the state from the loaded
+    /// device profile
+    fn apply_led_config(&self) {
+        let Some(config) = self.led_config.as_ref() else {
+            return;
+        };
+        if let Some([red, green, blue]) = config.color {
+            self.write_led_event(LedEvent::Color(red, green, blue));
+        }
+        if let Some(brightness) = config.brightness {
+            self.write_led_event(LedEvent::Brightness(brightness));
+        }
+    }
+
+    /// Write the given LED event to the LEDs of all source devices
+    fn write_led_event(&self, event: LedEvent) {
+        for (source_id, leds) in self.leds.iter() {
+            for led in leds {
+                let result = match event {
+                    LedEvent::Brightness(brightness) => led.set_brightness(brightness),
+                    LedEvent::Color(red, green, blue) => led.set_color(red, green, blue),
+                    LedEvent::Player(lights) => led.set_player(lights),
+                    LedEvent::Mute(is_on) => led.set_mute(is_on),
+                };
+                if let Err(e) = result {
+                    log::debug!("Failed to set LED {} of {source_id}: {e:?}", led.name);
+                }
+            }
+        }
+    }
+
+    /// Set the accessibility options used to filter input. Timed features
+    /// are updated by a task that periodically sends [Command::AccessibilityTick].
+    fn set_accessibility(&mut self, config: Option<AccessibilityConfig>) {
+        if let Some(task) = self.accessibility_task.take() {
+            task.abort();
+        }
+        self.accessibility = config.map(Accessibility::new);
+        let Some(accessibility) = self.accessibility.as_ref() else {
+            return;
+        };
+        if !accessibility.needs_tick() {
+            return;
+        }
+
+        let tx = self.tx.clone();
+        let task = tokio::task::spawn(async move {
+            let mut interval = tokio::time::interval(accessibility::TICK_INTERVAL);
+            loop {
+                interval.tick().await;
+                if tx.send(Command::AccessibilityTick).await.is_err() {
+                    break;
+                }
+            }
+        });
+        self.accessibility_task = Some(task);
+    }
+
+    /// Update timed accessibility features and write any events they emit
+    async fn on_accessibility_tick(&mut self) -> Result<(), Box<dyn Error>> {
+        let Some(accessibility) = self.accessibility.as_mut() else {
+            return Ok(());
+        };
+        let events = accessibility.tick();
+        if events.is_empty() {
+            return Ok(());
+        }
+        self.write_chord_events(events).await
+    }
+
     fn set_intercept_activation(
         &mut self,
         activation_caps: Vec<Capability>,
@@ -1607,7 +3013,7 @@ impl CompositeDevice {
                 }
                 if self.intercept_active_inputs == self.intercept_activation_caps {
                     log::debug!("Found activation chord!");
-                    self.set_intercept_mode(InterceptMode::Always);
+                    self.set_intercept_mode(InterceptMode::Always).await;
                     let event = NativeEvent::new(
                         self.intercept_mode_target_cap.clone(),
                         InputValue::Bool(true),
@@ -1684,7 +3090,13 @@ impl CompositeDevice {
         for kind in device_types {
             let (sender, mut receiver) = mpsc::channel(1);
             self.manager
-                .send(ManagerCommand::CreateTargetDevice { kind, sender })?;
+                .send(ManagerCommand::CreateTargetDevice {
+                    kind,
+                    serial: self.serial.clone(),
+                    player: self.player_number,
+                    custom: self.config.custom_target.clone(),
+                    sender,
+                })?;
             let Some(response) = receiver.recv().await else {
                 continue;
             };
@@ -1765,6 +3177,11 @@ impl CompositeDevice {
         &mut self,
         targets: HashMap<String, mpsc::Sender<TargetCommand>>,
     ) -> Result<(), Box<dyn Error>> {
+        // Make sure new target devices receive the current axis values
+        if let Some(filter) = self.event_filter.as_mut() {
+            filter.clear();
+        }
+
         // Keep track of all target devices
         for (path, target) in targets.into_iter() {
             log::debug!("Attaching target device: {path}");
@@ -1855,4 +3272,238 @@ impl CompositeDevice {
             }
         });
     }
+
+    /// Emit DBus property change signals when the battery state of the device
+    /// changes
+    async fn signal_battery_changed(&self) {
+        let Some(dbus_path) = self.dbus_path.clone() else {
+            log::error!("No DBus path for composite device exists to emit signal!");
+            return;
+        };
+        let conn = self.conn.clone();
+
+        tokio::task::spawn(async move {
+            // Get the object instance at the given path so we can send DBus signal
+            // updates
+            let iface_ref = match conn
+                .object_server()
+                .interface::<_, BatteryInterface>(dbus_path.clone())
+                .await
+            {
+                Ok(iface) => iface,
+                Err(e) => {
+                    log::debug!("No DBus battery interface for composite device to signal: {e:?}");
+                    return;
+                }
+            };
+
+            // Emit the battery property changed signals
+            let iface = iface_ref.get().await;
+            if let Err(e) = iface.percentage_changed(iface_ref.signal_context()).await {
+                log::error!("Failed to send battery percentage changed signal: {e:?}");
+            }
+            if let Err(e) = iface.status_changed(iface_ref.signal_context()).await {
+                log::error!("Failed to send battery status changed signal: {e:?}");
+            }
+        });
+    }
+
+    /// Emit a DBus signal when the capabilities of the device change
+    async fn signal_capabilities_changed(&self) {
+        let Some(dbus_path) = self.dbus_path.clone() else {
+            log::error!("No DBus path for composite device exists to emit signal!");
+            return;
+        };
+        let conn = self.conn.clone();
+        let capabilities = capability_strings(self.capabilities.clone());
+
+        tokio::task::spawn(async move {
+            // Get the object instance at the given path so we can send DBus signal
+            // updates
+            let iface_ref = match conn
+                .object_server()
+                .interface::<_, CompositeDeviceInterface>(dbus_path.clone())
+                .await
+            {
+                Ok(iface) => iface,
+                Err(e) => {
+                    log::error!(
+                        "Failed to get DBus interface for composite device to signal: {e:?}"
+                    );
+                    return;
+                }
+            };
+
+            // Emit the capabilities changed signal and invalidate the
+            // capability properties
+            if let Err(e) = CompositeDeviceInterface::capabilities_updated(
+                iface_ref.signal_context(),
+                capabilities,
+            )
+            .await
+            {
+                log::error!("Failed to send capabilities changed signal: {e:?}");
+            }
+            let iface = iface_ref.get().await;
+            if let Err(e) = iface.capabilities_changed(iface_ref.signal_context()).await {
+                log::error!("Failed to send capabilities changed signal: {e:?}");
+            }
+            if let Err(e) = iface
+                .target_capabilities_changed(iface_ref.signal_context())
+                .await
+            {
+                log::error!("Failed to send target capabilities changed signal: {e:?}");
+            }
+        });
+    }
+
+    /// Emit a DBus signal when a source device reports a warning
+    async fn signal_source_device_warning(&self, device_id: String, message: String) {
+        let Some(dbus_path) = self.dbus_path.clone() else {
+            log::error!("No DBus path for composite device exists to emit signal!");
+            return;
+        };
+        let conn = self.conn.clone();
+
+        tokio::task::spawn(async move {
+            // Get the object instance at the given path so we can send DBus signal
+            // updates
+            let iface_ref = match conn
+                .object_server()
+                .interface::<_, CompositeDeviceInterface>(dbus_path.clone())
+                .await
+            {
+                Ok(iface) => iface,
+                Err(e) => {
+                    log::error!(
+                        "Failed to get DBus interface for composite device to signal: {e:?}"
+                    );
+                    return;
+                }
+            };
+            // Emit the source device warning signal
+            if let Err(e) = CompositeDeviceInterface::source_device_warning(
+                iface_ref.signal_context(),
+                device_id,
+                message,
+            )
+            .await
+            {
+                log::error!("Failed to send source device warning signal: {e:?}");
+            }
+        });
+    }
+
+    /// Emit a DBus signal when a source device stops responding or recovers
+    async fn signal_source_device_stalled(&self, device_id: String, stalled: bool) {
+        let Some(dbus_path) = self.dbus_path.clone() else {
+            log::error!("No DBus path for composite device exists to emit signal!");
+            return;
+        };
+        let conn = self.conn.clone();
+
+        tokio::task::spawn(async move {
+            // Get the object instance at the given path so we can send DBus signal
+            // updates
+            let iface_ref = match conn
+                .object_server()
+                .interface::<_, CompositeDeviceInterface>(dbus_path.clone())
+                .await
+            {
+                Ok(iface) => iface,
+                Err(e) => {
+                    log::error!(
+                        "Failed to get DBus interface for composite device to signal: {e:?}"
+                    );
+                    return;
+                }
+            };
+            // Emit the source device stalled signal
+            if let Err(e) = CompositeDeviceInterface::source_device_stalled(
+                iface_ref.signal_context(),
+                device_id,
+                stalled,
+            )
+            .await
+            {
+                log::error!("Failed to send source device stalled signal: {e:?}");
+            }
+        });
+    }
+
+    /// Emit a DBus signal when a detachable part of a source device is detached or
+    /// attached again
+    async fn signal_source_device_detached(&self, device_id: String, detached: bool) {
+        let Some(dbus_path) = self.dbus_path.clone() else {
+            log::error!("No DBus path for composite device exists to emit signal!");
+            return;
+        };
+        let conn = self.conn.clone();
+
+        tokio::task::spawn(async move {
+            // Get the object instance at the given path so we can send DBus signal
+            // updates
+            let iface_ref = match conn
+                .object_server()
+                .interface::<_, CompositeDeviceInterface>(dbus_path.clone())
+                .await
+            {
+                Ok(iface) => iface,
+                Err(e) => {
+                    log::error!(
+                        "Failed to get DBus interface for composite device to signal: {e:?}"
+                    );
+                    return;
+                }
+            };
+            // Emit the source device detached signal
+            if let Err(e) = CompositeDeviceInterface::source_device_detached(
+                iface_ref.signal_context(),
+                device_id,
+                detached,
+            )
+            .await
+            {
+                log::error!("Failed to send source device detached signal: {e:?}");
+            }
+        });
+    }
+}
+
+/// Returns true if the source events of the given mapping are a strict subset
+/// of the source events of the other mapping.
+fn is_mapping_subset(mapping: &CapabilityMapping, other: &CapabilityMapping) -> bool {
+    let get_sources = |mapping: &CapabilityMapping| -> Vec<Capability> {
+        mapping
+            .source_events
+            .iter()
+            .map(|source_event| source_event.clone().into())
+            .filter(|cap| cap != &Capability::NotImplemented)
+            .collect()
+    };
+    let sources = get_sources(mapping);
+    let other_sources = get_sources(other);
+    sources.len() < other_sources.len() && sources.iter().all(|cap| other_sources.contains(cap))
+}
+
+/// Returns the value of the target event of the given mapping when its source
+/// events are pressed or released (e.g. a full axis deflection in the mapped
+/// direction for a key mapped to a gamepad axis).
+fn translate_mapping_value(
+    mapping: &CapabilityMapping,
+    target_cap: &Capability,
+    pressed: bool,
+) -> InputValue {
+    let value = InputValue::Bool(pressed);
+    match value.translate_button(target_cap, &mapping.target_event) {
+        Ok(InputValue::None) => value,
+        Ok(translated) => translated,
+        Err(e) => {
+            log::trace!(
+                "Failed to translate value of mapping {}: {e:?}",
+                mapping.name
+            );
+            value
+        }
+    }
 }
@@ -0,0 +1,47 @@
+#[cfg(not(feature = "scripting"))]
+use std::error::Error;
+
+#[cfg(not(feature = "scripting"))]
+use crate::input::event::{native::NativeEvent, value::InputValue};
+
+#[cfg(feature = "scripting")]
+mod lua;
+
+#[cfg(feature = "scripting")]
+pub use lua::ScriptEngine;
+
+/// Stand-in for the Lua [ScriptEngine] when InputPlumber is built without the
+/// "scripting" feature. Scripts cannot be loaded, so no instance can exist.
+#[cfg(not(feature = "scripting"))]
+#[derive(Debug)]
+pub enum ScriptEngine {}
+
+#[cfg(not(feature = "scripting"))]
+impl ScriptEngine {
+    /// Always fails since scripting support is not enabled
+    pub fn new() -> Result<Self, Box<dyn Error>> {
+        Err(
+            "Unable to run profile scripts: InputPlumber was built without scripting support"
+                .into(),
+        )
+    }
+
+    /// Compile the given script source and store it with the given name
+    pub fn load(&mut self, _name: &str, _source: &str) -> Result<(), Box<dyn Error>> {
+        match *self {}
+    }
+
+    /// Returns true if a script with the given name has been loaded
+    pub fn has_script(&self, _name: &str) -> bool {
+        match *self {}
+    }
+
+    /// Run the script with the given name on the given event
+    pub fn run(
+        &self,
+        _name: &str,
+        _event: &NativeEvent,
+    ) -> Result<Option<InputValue>, Box<dyn Error>> {
+        match *self {}
+    }
+}
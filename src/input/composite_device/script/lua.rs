@@ -0,0 +1,213 @@
+use std::{
+    collections::HashMap,
+    error::Error,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use mlua::{HookTriggers, Lua, LuaOptions, RegistryKey, StdLib, Table, Value, VmState};
+
+use crate::input::event::{native::NativeEvent, value::InputValue};
+
+/// Maximum time a single script invocation is allowed to run
+const SCRIPT_TIMEOUT: Duration = Duration::from_millis(2);
+/// Maximum amount of memory all scripts are allowed to allocate
+const SCRIPT_MEMORY_LIMIT: usize = 4 * 1024 * 1024;
+/// Number of Lua VM instructions between checking for script timeouts
+const SCRIPT_HOOK_INSTRUCTIONS: u32 = 1000;
+/// Base library functions that are removed from the sandbox because they can
+/// access the filesystem, load arbitrary code, or control the garbage collector
+const UNSAFE_GLOBALS: &[&str] = &[
+    "dofile",
+    "loadfile",
+    "load",
+    "loadstring",
+    "collectgarbage",
+    "print",
+    "require",
+];
+
+/// A compiled profile mapping script and its persistent state
+struct Script {
+    function: RegistryKey,
+    state: RegistryKey,
+}
+
+/// The [ScriptEngine] runs Lua scripts attached to profile mappings. Scripts
+/// are executed in a sandbox with only the `table`, `string`, and `math`
+/// libraries and the safe parts of the base library available, and are
+/// limited in both run time and memory.
+///
+/// A script is the body of a function that is called for every event
+/// translated by its mapping with the arguments `event` and `state`. The
+/// `event` table contains the `capability` name and `value` of the event, and
+/// the `state` table persists between calls to allow stateful mappings. The
+/// script returns the new value for the event, or `nil` to drop it.
+pub struct ScriptEngine {
+    lua: Lua,
+    scripts: HashMap<String, Script>,
+    started_at: Arc<Mutex<Instant>>,
+}
+
+impl std::fmt::Debug for ScriptEngine {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ScriptEngine")
+            .field("scripts", &self.scripts.keys())
+            .finish()
+    }
+}
+
+impl ScriptEngine {
+    /// Returns a new sandboxed script engine
+    pub fn new() -> Result<Self, Box<dyn Error>> {
+        let libs = StdLib::TABLE | StdLib::STRING | StdLib::MATH;
+        let lua = Lua::new_with(libs, LuaOptions::default())?;
+        lua.set_memory_limit(SCRIPT_MEMORY_LIMIT)?;
+
+        // The base library is always loaded, so remove any functions from it
+        // that would escape the sandbox
+        for name in UNSAFE_GLOBALS {
+            lua.globals().set(*name, Value::Nil)?;
+        }
+
+        // Abort any script that runs for too long
+        let started_at = Arc::new(Mutex::new(Instant::now()));
+        let hook_started_at = started_at.clone();
+        let triggers = HookTriggers::new().every_nth_instruction(SCRIPT_HOOK_INSTRUCTIONS);
+        lua.set_hook(triggers, move |_lua, _debug| {
+            let elapsed = hook_started_at
+                .lock()
+                .map(|started_at| started_at.elapsed())
+                .unwrap_or_default();
+            if elapsed > SCRIPT_TIMEOUT {
+                return Err(mlua::Error::RuntimeError("script timed out".into()));
+            }
+            Ok(VmState::Continue)
+        });
+
+        Ok(Self {
+            lua,
+            scripts: HashMap::new(),
+            started_at,
+        })
+    }
+
+    /// Compile the given script source and store it with the given name
+    pub fn load(&mut self, name: &str, source: &str) -> Result<(), Box<dyn Error>> {
+        let chunk = format!("return function(event, state)\n{source}\nend");
+        self.reset_timeout();
+        let function: mlua::Function = self.lua.load(chunk).set_name(name).eval()?;
+        let state = self.lua.create_table()?;
+        let script = Script {
+            function: self.lua.create_registry_value(function)?,
+            state: self.lua.create_registry_value(state)?,
+        };
+        self.scripts.insert(name.to_string(), script);
+
+        Ok(())
+    }
+
+    /// Returns true if a script with the given name has been loaded
+    pub fn has_script(&self, name: &str) -> bool {
+        self.scripts.contains_key(name)
+    }
+
+    /// Run the script with the given name on the given event. Returns the new
+    /// value for the event, or [None] if the event should be dropped.
+    pub fn run(
+        &self,
+        name: &str,
+        event: &NativeEvent,
+    ) -> Result<Option<InputValue>, Box<dyn Error>> {
+        let Some(script) = self.scripts.get(name) else {
+            return Ok(Some(event.get_value()));
+        };
+        let function: mlua::Function = self.lua.registry_value(&script.function)?;
+        let state: Table = self.lua.registry_value(&script.state)?;
+
+        let event_table = self.lua.create_table()?;
+        event_table.set("capability", event.as_capability().to_capability_string())?;
+        event_table.set("value", self.to_lua(&event.get_value())?)?;
+
+        self.reset_timeout();
+        let result: Value = function.call((event_table, state))?;
+
+        Ok(from_lua(&result))
+    }
+
+    /// Reset the start time used to detect script timeouts
+    fn reset_timeout(&self) {
+        if let Ok(mut started_at) = self.started_at.lock() {
+            *started_at = Instant::now();
+        }
+    }
+
+    /// Convert the given [InputValue] into a Lua value
+    fn to_lua(&self, value: &InputValue) -> mlua::Result<Value> {
+        let value = match value {
+            InputValue::None => Value::Nil,
+            InputValue::Bool(value) => Value::Boolean(*value),
+            InputValue::Float(value) => Value::Number(*value),
+            InputValue::Vector2 { x, y } => {
+                let table = self.lua.create_table()?;
+                table.set("x", *x)?;
+                table.set("y", *y)?;
+                Value::Table(table)
+            }
+            InputValue::Vector3 { x, y, z } => {
+                let table = self.lua.create_table()?;
+                table.set("x", *x)?;
+                table.set("y", *y)?;
+                table.set("z", *z)?;
+                Value::Table(table)
+            }
+            InputValue::Touch {
+                index,
+                is_touching,
+                x,
+                y,
+                pressure,
+            } => {
+                let table = self.lua.create_table()?;
+                table.set("index", *index)?;
+                table.set("is_touching", *is_touching)?;
+                table.set("x", *x)?;
+                table.set("y", *y)?;
+                table.set("pressure", *pressure)?;
+                Value::Table(table)
+            }
+        };
+
+        Ok(value)
+    }
+}
+
+/// Convert the given Lua value returned from a script into an [InputValue]
+fn from_lua(value: &Value) -> Option<InputValue> {
+    match value {
+        Value::Nil => None,
+        Value::Boolean(value) => Some(InputValue::Bool(*value)),
+        Value::Integer(value) => Some(InputValue::Float(*value as f64)),
+        Value::Number(value) => Some(InputValue::Float(*value)),
+        Value::Table(table) => {
+            let x: Option<f64> = table.get("x").ok();
+            let y: Option<f64> = table.get("y").ok();
+            if let Ok(Some(index)) = table.get::<_, Option<u8>>("index") {
+                let is_touching = table.get("is_touching").unwrap_or_default();
+                let pressure = table.get("pressure").ok();
+                return Some(InputValue::Touch {
+                    index,
+                    is_touching,
+                    x,
+                    y,
+                    pressure,
+                });
+            }
+            if let Ok(Some(z)) = table.get::<_, Option<f64>>("z") {
+                return Some(InputValue::Vector3 { x, y, z: Some(z) });
+            }
+            Some(InputValue::Vector2 { x, y })
+        }
+        _ => None,
+    }
+}
@@ -0,0 +1,42 @@
+use std::error::Error;
+
+use crate::input::{
+    capability::{Capability, Gamepad, GamepadButton},
+    composite_device::script::ScriptEngine,
+    event::{native::NativeEvent, value::InputValue},
+};
+
+fn button_event(pressed: bool) -> NativeEvent {
+    NativeEvent::new(
+        Capability::Gamepad(Gamepad::Button(GamepadButton::South)),
+        InputValue::Bool(pressed),
+    )
+}
+
+#[test]
+fn test_script_sandbox_removes_unsafe_globals() -> Result<(), Box<dyn Error>> {
+    let mut engine = ScriptEngine::new()?;
+    engine.load(
+        "sandbox",
+        "return dofile == nil and loadfile == nil and load == nil",
+    )?;
+
+    let value = engine.run("sandbox", &button_event(true))?;
+    assert!(matches!(value, Some(InputValue::Bool(true))));
+
+    Ok(())
+}
+
+#[test]
+fn test_script_receives_capability_string() -> Result<(), Box<dyn Error>> {
+    let mut engine = ScriptEngine::new()?;
+    engine.load(
+        "capability",
+        "return event.capability == \"Gamepad:Button:South\"",
+    )?;
+
+    let value = engine.run("capability", &button_event(true))?;
+    assert!(matches!(value, Some(InputValue::Bool(true))));
+
+    Ok(())
+}
@@ -32,6 +32,8 @@ pub enum Action {
     VolumeMute,
     Keyboard,
     Screenshot,
+    Power,
+    Airplane,
 }
 
 impl Action {
@@ -62,6 +64,8 @@ impl Action {
             Action::VolumeMute => "ui_volume_mute",
             Action::Keyboard => "ui_osk",
             Action::Screenshot => "ui_screenshot",
+            Action::Power => "ui_power",
+            Action::Airplane => "ui_airplane",
         }
     }
 
@@ -100,6 +104,8 @@ impl FromStr for Action {
             "ui_volume_mute" => Ok(Action::VolumeMute),
             "ui_osk" => Ok(Action::Keyboard),
             "ui_screenshot" => Ok(Action::Screenshot),
+            "ui_power" => Ok(Action::Power),
+            "ui_airplane" => Ok(Action::Airplane),
             _ => Err(()),
         }
     }
@@ -166,6 +172,7 @@ fn actions_from_capability(capability: Capability) -> Vec<Action> {
                 GamepadButton::QuickAccess2 => vec![Action::Quick2],
                 GamepadButton::Keyboard => vec![Action::Keyboard],
                 GamepadButton::Screenshot => vec![Action::Screenshot],
+                GamepadButton::Mute => vec![Action::None],
                 GamepadButton::DPadUp => vec![Action::Up],
                 GamepadButton::DPadDown => vec![Action::Down],
                 GamepadButton::DPadLeft => vec![Action::Left],
@@ -337,7 +344,7 @@ fn actions_from_capability(capability: Capability) -> Vec<Action> {
             Keyboard::KeyMute => vec![Action::VolumeMute],
             Keyboard::KeyVolumeDown => vec![Action::VolumeUp],
             Keyboard::KeyVolumeUp => vec![Action::VolumeDown],
-            Keyboard::KeyPower => vec![Action::None],
+            Keyboard::KeyPower => vec![Action::Power],
             Keyboard::KeyKpEqual => vec![Action::None],
             Keyboard::KeyPause => vec![Action::None],
             Keyboard::KeyKpComma => vec![Action::None],
@@ -386,8 +393,178 @@ fn actions_from_capability(capability: Capability) -> Vec<Action> {
             Keyboard::KeyF23 => vec![Action::None],
             Keyboard::KeyF24 => vec![Action::None],
             Keyboard::KeyProg1 => vec![Action::None],
+            Keyboard::KeyProg2 => vec![Action::None],
+            Keyboard::KeyProg3 => vec![Action::None],
+            Keyboard::KeyProg4 => vec![Action::None],
+            Keyboard::KeyWlan => vec![Action::Airplane],
+            Keyboard::KeyRfkill => vec![Action::Airplane],
+            Keyboard::KeyLinefeed => vec![Action::None],
+            Keyboard::KeyMacro => vec![Action::None],
+            Keyboard::KeyKpPlusMinus => vec![Action::None],
+            Keyboard::KeyScale => vec![Action::None],
+            Keyboard::KeyHangeul => vec![Action::None],
+            Keyboard::KeyMenu => vec![Action::None],
+            Keyboard::KeySetup => vec![Action::None],
+            Keyboard::KeyWakeup => vec![Action::None],
+            Keyboard::KeyFile => vec![Action::None],
+            Keyboard::KeySendFile => vec![Action::None],
+            Keyboard::KeyDeleteFile => vec![Action::None],
+            Keyboard::KeyXfer => vec![Action::None],
+            Keyboard::KeyMsdos => vec![Action::None],
+            Keyboard::KeyCoffee => vec![Action::None],
+            Keyboard::KeyRotateDisplay => vec![Action::None],
+            Keyboard::KeyCycleWindows => vec![Action::None],
+            Keyboard::KeyMail => vec![Action::None],
+            Keyboard::KeyBookmarks => vec![Action::None],
+            Keyboard::KeyComputer => vec![Action::None],
+            Keyboard::KeyCloseCD => vec![Action::None],
+            Keyboard::KeyEjectCloseCD => vec![Action::None],
+            Keyboard::KeyRecord => vec![Action::None],
+            Keyboard::KeyRewind => vec![Action::None],
+            Keyboard::KeyPhone => vec![Action::None],
+            Keyboard::KeyIso => vec![Action::None],
+            Keyboard::KeyConfig => vec![Action::None],
+            Keyboard::KeyHomepage => vec![Action::None],
+            Keyboard::KeyExit => vec![Action::None],
+            Keyboard::KeyMove => vec![Action::None],
+            Keyboard::KeyNew => vec![Action::None],
+            Keyboard::KeyRedo => vec![Action::None],
+            Keyboard::KeyPlayCD => vec![Action::None],
+            Keyboard::KeyPauseCD => vec![Action::None],
+            Keyboard::KeyDashboard => vec![Action::None],
+            Keyboard::KeySuspend => vec![Action::None],
+            Keyboard::KeyClose => vec![Action::None],
+            Keyboard::KeyPlay => vec![Action::None],
+            Keyboard::KeyFastForward => vec![Action::None],
+            Keyboard::KeyBassBoost => vec![Action::None],
+            Keyboard::KeyPrint => vec![Action::None],
+            Keyboard::KeyHp => vec![Action::None],
+            Keyboard::KeyCamera => vec![Action::None],
+            Keyboard::KeySound => vec![Action::None],
+            Keyboard::KeyQuestion => vec![Action::None],
+            Keyboard::KeyEmail => vec![Action::None],
+            Keyboard::KeyChat => vec![Action::None],
+            Keyboard::KeySearch => vec![Action::None],
+            Keyboard::KeyConnect => vec![Action::None],
+            Keyboard::KeyFinance => vec![Action::None],
+            Keyboard::KeySport => vec![Action::None],
+            Keyboard::KeyShop => vec![Action::None],
+            Keyboard::KeyAltErase => vec![Action::None],
+            Keyboard::KeyCancel => vec![Action::None],
+            Keyboard::KeyBrightnessDown => vec![Action::None],
+            Keyboard::KeyBrightnessUp => vec![Action::None],
+            Keyboard::KeyMedia => vec![Action::None],
+            Keyboard::KeySwitchVideoMode => vec![Action::None],
+            Keyboard::KeyKbdIllumToggle => vec![Action::None],
+            Keyboard::KeyKbdIllumDown => vec![Action::None],
+            Keyboard::KeyKbdIllumUp => vec![Action::None],
+            Keyboard::KeySend => vec![Action::None],
+            Keyboard::KeyReply => vec![Action::None],
+            Keyboard::KeyForwardMail => vec![Action::None],
+            Keyboard::KeySave => vec![Action::None],
+            Keyboard::KeyDocuments => vec![Action::None],
+            Keyboard::KeyBattery => vec![Action::None],
+            Keyboard::KeyBluetooth => vec![Action::None],
+            Keyboard::KeyUwb => vec![Action::None],
+            Keyboard::KeyVideoNext => vec![Action::None],
+            Keyboard::KeyVideoPrev => vec![Action::None],
+            Keyboard::KeyBrightnessCycle => vec![Action::None],
+            Keyboard::KeyBrightnessAuto => vec![Action::None],
+            Keyboard::KeyDisplayOff => vec![Action::None],
+            Keyboard::KeyWwan => vec![Action::None],
+            Keyboard::KeyOk => vec![Action::None],
+            Keyboard::KeySelect => vec![Action::None],
+            Keyboard::KeyGoto => vec![Action::None],
+            Keyboard::KeyClear => vec![Action::None],
+            Keyboard::KeyPower2 => vec![Action::None],
+            Keyboard::KeyOption => vec![Action::None],
+            Keyboard::KeyInfo => vec![Action::None],
+            Keyboard::KeyTime => vec![Action::None],
+            Keyboard::KeyVendor => vec![Action::None],
+            Keyboard::KeyArchive => vec![Action::None],
+            Keyboard::KeyProgram => vec![Action::None],
+            Keyboard::KeyChannel => vec![Action::None],
+            Keyboard::KeyFavorites => vec![Action::None],
+            Keyboard::KeyEpg => vec![Action::None],
+            Keyboard::KeyPvr => vec![Action::None],
+            Keyboard::KeyMhp => vec![Action::None],
+            Keyboard::KeyLanguage => vec![Action::None],
+            Keyboard::KeyTitle => vec![Action::None],
+            Keyboard::KeySubtitle => vec![Action::None],
+            Keyboard::KeyAngle => vec![Action::None],
+            Keyboard::KeyMode => vec![Action::None],
+            Keyboard::KeyKeyboard => vec![Action::None],
+            Keyboard::KeyScreen => vec![Action::None],
+            Keyboard::KeyPc => vec![Action::None],
+            Keyboard::KeyTv => vec![Action::None],
+            Keyboard::KeyTv2 => vec![Action::None],
+            Keyboard::KeyVcr => vec![Action::None],
+            Keyboard::KeyVcr2 => vec![Action::None],
+            Keyboard::KeySat => vec![Action::None],
+            Keyboard::KeySat2 => vec![Action::None],
+            Keyboard::KeyCd => vec![Action::None],
+            Keyboard::KeyTape => vec![Action::None],
+            Keyboard::KeyRadio => vec![Action::None],
+            Keyboard::KeyTuner => vec![Action::None],
+            Keyboard::KeyPlayer => vec![Action::None],
+            Keyboard::KeyText => vec![Action::None],
+            Keyboard::KeyDvd => vec![Action::None],
+            Keyboard::KeyAux => vec![Action::None],
+            Keyboard::KeyMp3 => vec![Action::None],
+            Keyboard::KeyAudio => vec![Action::None],
+            Keyboard::KeyVideo => vec![Action::None],
+            Keyboard::KeyDirectory => vec![Action::None],
+            Keyboard::KeyList => vec![Action::None],
+            Keyboard::KeyMemo => vec![Action::None],
+            Keyboard::KeyCalendar => vec![Action::None],
+            Keyboard::KeyRed => vec![Action::None],
+            Keyboard::KeyGreen => vec![Action::None],
+            Keyboard::KeyYellow => vec![Action::None],
+            Keyboard::KeyBlue => vec![Action::None],
+            Keyboard::KeyChannelUp => vec![Action::None],
+            Keyboard::KeyChannelDown => vec![Action::None],
+            Keyboard::KeyFirst => vec![Action::None],
+            Keyboard::KeyLast => vec![Action::None],
+            Keyboard::KeyAb => vec![Action::None],
+            Keyboard::KeyNext => vec![Action::None],
+            Keyboard::KeyRestart => vec![Action::None],
+            Keyboard::KeySlow => vec![Action::None],
+            Keyboard::KeyShuffle => vec![Action::None],
+            Keyboard::KeyBreak => vec![Action::None],
+            Keyboard::KeyPrevious => vec![Action::None],
+            Keyboard::KeyDigits => vec![Action::None],
+            Keyboard::KeyTeen => vec![Action::None],
+            Keyboard::KeyTwen => vec![Action::None],
+            Keyboard::KeyVideophone => vec![Action::None],
+            Keyboard::KeyGames => vec![Action::None],
+            Keyboard::KeyZoomIn => vec![Action::None],
+            Keyboard::KeyZoomOut => vec![Action::None],
+            Keyboard::KeyZoomReset => vec![Action::None],
+            Keyboard::KeyWordProcessor => vec![Action::None],
+            Keyboard::KeyEditor => vec![Action::None],
+            Keyboard::KeySpreadsheet => vec![Action::None],
+            Keyboard::KeyGraphicsEditor => vec![Action::None],
+            Keyboard::KeyPresentation => vec![Action::None],
+            Keyboard::KeyDatabase => vec![Action::None],
+            Keyboard::KeyNews => vec![Action::None],
+            Keyboard::KeyVoicemail => vec![Action::None],
+            Keyboard::KeyAddressBook => vec![Action::None],
+            Keyboard::KeyMessenger => vec![Action::None],
+            Keyboard::KeyDisplayToggle => vec![Action::None],
+            Keyboard::KeySpellCheck => vec![Action::None],
+            Keyboard::KeyLogoff => vec![Action::None],
+            Keyboard::KeyDollar => vec![Action::None],
+            Keyboard::KeyEuro => vec![Action::None],
+            Keyboard::KeyFrameBack => vec![Action::None],
+            Keyboard::KeyFrameForward => vec![Action::None],
+            Keyboard::KeyContextMenu => vec![Action::None],
+            Keyboard::KeyMediaRepeat => vec![Action::None],
+            Keyboard::Key10ChannelsUp => vec![Action::None],
+            Keyboard::Key10ChannelsDown => vec![Action::None],
+            Keyboard::KeyImages => vec![Action::None],
         },
         Capability::Touchpad(_) => vec![Action::None],
+        Capability::Touchscreen(_) => vec![Action::None],
     }
 }
 
@@ -420,6 +597,7 @@ fn dbus_event_from_value(action: Action, input_value: InputValue) -> Option<DBus
             is_touching: pressed,
             x,
             y,
+            pressure,
         } => None,
     };
     value?;
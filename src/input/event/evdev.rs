@@ -1,12 +1,17 @@
-use std::collections::HashMap;
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
 
 use evdev::{
-    AbsInfo, AbsoluteAxisCode, AbsoluteAxisEvent, EventType, InputEvent, KeyCode, RelativeAxisCode,
+    AbsInfo, AbsoluteAxisCode, AbsoluteAxisEvent, EventType, InputEvent, KeyCode, MiscCode,
+    RelativeAxisCode,
 };
+use nix::time::{clock_gettime, ClockId};
 
 use crate::input::capability::{
     Capability, Gamepad, GamepadAxis, GamepadButton, GamepadTrigger, Keyboard, Mouse, MouseButton,
-    Touch, TouchButton, Touchpad,
+    Touch, TouchButton, TouchTool, Touchpad,
 };
 
 use super::{native::NativeEvent, value::InputValue};
@@ -15,6 +20,7 @@ use super::{native::NativeEvent, value::InputValue};
 pub struct EvdevEvent {
     event: InputEvent,
     abs_info: Option<AbsInfo>,
+    timestamp: Option<Instant>,
 }
 
 impl EvdevEvent {
@@ -127,6 +133,16 @@ impl EvdevEvent {
         self.abs_info = Some(info)
     }
 
+    /// Set the time at which the source device produced this event
+    pub fn set_timestamp(&mut self, timestamp: Instant) {
+        self.timestamp = Some(timestamp)
+    }
+
+    /// Returns the time at which the source device produced this event
+    pub fn get_timestamp(&self) -> Option<Instant> {
+        self.timestamp
+    }
+
     /// Returns the event as a evdev [InputEvent]
     pub fn as_input_event(&self) -> InputEvent {
         self.event
@@ -162,6 +178,19 @@ impl EvdevEvent {
                 KeyCode::BTN_THUMBR => {
                     Capability::Gamepad(Gamepad::Button(GamepadButton::RightStick))
                 }
+                // Paddles as reported by the xpad driver for Xbox Elite controllers
+                KeyCode::BTN_TRIGGER_HAPPY5 => {
+                    Capability::Gamepad(Gamepad::Button(GamepadButton::RightPaddle1))
+                }
+                KeyCode::BTN_TRIGGER_HAPPY6 => {
+                    Capability::Gamepad(Gamepad::Button(GamepadButton::RightPaddle2))
+                }
+                KeyCode::BTN_TRIGGER_HAPPY7 => {
+                    Capability::Gamepad(Gamepad::Button(GamepadButton::LeftPaddle1))
+                }
+                KeyCode::BTN_TRIGGER_HAPPY8 => {
+                    Capability::Gamepad(Gamepad::Button(GamepadButton::LeftPaddle2))
+                }
                 KeyCode::KEY_ESC => Capability::Keyboard(Keyboard::KeyEsc),
                 KeyCode::KEY_1 => Capability::Keyboard(Keyboard::Key1),
                 KeyCode::KEY_2 => Capability::Keyboard(Keyboard::Key2),
@@ -263,7 +292,7 @@ impl EvdevEvent {
                 KeyCode::KEY_KPSLASH => Capability::Keyboard(Keyboard::KeyKpSlash),
                 KeyCode::KEY_SYSRQ => Capability::Keyboard(Keyboard::KeySysrq),
                 KeyCode::KEY_RIGHTALT => Capability::Keyboard(Keyboard::KeyRightAlt),
-                KeyCode::KEY_LINEFEED => Capability::NotImplemented,
+                KeyCode::KEY_LINEFEED => Capability::Keyboard(Keyboard::KeyLinefeed),
                 KeyCode::KEY_HOME => Capability::Keyboard(Keyboard::KeyHome),
                 KeyCode::KEY_UP => Capability::Keyboard(Keyboard::KeyUp),
                 KeyCode::KEY_PAGEUP => Capability::Keyboard(Keyboard::KeyPageUp),
@@ -274,17 +303,17 @@ impl EvdevEvent {
                 KeyCode::KEY_PAGEDOWN => Capability::Keyboard(Keyboard::KeyPageDown),
                 KeyCode::KEY_INSERT => Capability::Keyboard(Keyboard::KeyInsert),
                 KeyCode::KEY_DELETE => Capability::Keyboard(Keyboard::KeyDelete),
-                KeyCode::KEY_MACRO => Capability::NotImplemented,
+                KeyCode::KEY_MACRO => Capability::Keyboard(Keyboard::KeyMacro),
                 KeyCode::KEY_MUTE => Capability::Keyboard(Keyboard::KeyMute),
                 KeyCode::KEY_VOLUMEDOWN => Capability::Keyboard(Keyboard::KeyVolumeDown),
                 KeyCode::KEY_VOLUMEUP => Capability::Keyboard(Keyboard::KeyVolumeUp),
                 KeyCode::KEY_POWER => Capability::Keyboard(Keyboard::KeyPower),
                 KeyCode::KEY_KPEQUAL => Capability::Keyboard(Keyboard::KeyKpEqual),
-                KeyCode::KEY_KPPLUSMINUS => Capability::NotImplemented,
+                KeyCode::KEY_KPPLUSMINUS => Capability::Keyboard(Keyboard::KeyKpPlusMinus),
                 KeyCode::KEY_PAUSE => Capability::Keyboard(Keyboard::KeyPause),
-                KeyCode::KEY_SCALE => Capability::NotImplemented,
+                KeyCode::KEY_SCALE => Capability::Keyboard(Keyboard::KeyScale),
                 KeyCode::KEY_KPCOMMA => Capability::Keyboard(Keyboard::KeyKpComma),
-                KeyCode::KEY_HANGEUL => Capability::NotImplemented,
+                KeyCode::KEY_HANGEUL => Capability::Keyboard(Keyboard::KeyHangeul),
                 KeyCode::KEY_HANJA => Capability::Keyboard(Keyboard::KeyHanja),
                 KeyCode::KEY_YEN => Capability::Keyboard(Keyboard::KeyYen),
                 KeyCode::KEY_LEFTMETA => Capability::Keyboard(Keyboard::KeyLeftMeta),
@@ -301,50 +330,50 @@ impl EvdevEvent {
                 KeyCode::KEY_FIND => Capability::Keyboard(Keyboard::KeyFind),
                 KeyCode::KEY_CUT => Capability::Keyboard(Keyboard::KeyCut),
                 KeyCode::KEY_HELP => Capability::Keyboard(Keyboard::KeyHelp),
-                KeyCode::KEY_MENU => Capability::NotImplemented,
+                KeyCode::KEY_MENU => Capability::Keyboard(Keyboard::KeyMenu),
                 KeyCode::KEY_CALC => Capability::Keyboard(Keyboard::KeyCalc),
-                KeyCode::KEY_SETUP => Capability::NotImplemented,
+                KeyCode::KEY_SETUP => Capability::Keyboard(Keyboard::KeySetup),
                 KeyCode::KEY_SLEEP => Capability::Keyboard(Keyboard::KeySleep),
-                KeyCode::KEY_WAKEUP => Capability::NotImplemented,
-                KeyCode::KEY_FILE => Capability::NotImplemented,
-                KeyCode::KEY_SENDFILE => Capability::NotImplemented,
-                KeyCode::KEY_DELETEFILE => Capability::NotImplemented,
-                KeyCode::KEY_XFER => Capability::NotImplemented,
+                KeyCode::KEY_WAKEUP => Capability::Keyboard(Keyboard::KeyWakeup),
+                KeyCode::KEY_FILE => Capability::Keyboard(Keyboard::KeyFile),
+                KeyCode::KEY_SENDFILE => Capability::Keyboard(Keyboard::KeySendFile),
+                KeyCode::KEY_DELETEFILE => Capability::Keyboard(Keyboard::KeyDeleteFile),
+                KeyCode::KEY_XFER => Capability::Keyboard(Keyboard::KeyXfer),
                 KeyCode::KEY_PROG1 => Capability::Keyboard(Keyboard::KeyProg1),
-                KeyCode::KEY_PROG2 => Capability::NotImplemented,
+                KeyCode::KEY_PROG2 => Capability::Keyboard(Keyboard::KeyProg2),
                 KeyCode::KEY_WWW => Capability::Keyboard(Keyboard::KeyWww),
-                KeyCode::KEY_MSDOS => Capability::NotImplemented,
-                KeyCode::KEY_COFFEE => Capability::NotImplemented,
-                KeyCode::KEY_ROTATE_DISPLAY => Capability::NotImplemented,
-                KeyCode::KEY_CYCLEWINDOWS => Capability::NotImplemented,
-                KeyCode::KEY_MAIL => Capability::NotImplemented,
-                KeyCode::KEY_BOOKMARKS => Capability::NotImplemented,
-                KeyCode::KEY_COMPUTER => Capability::NotImplemented,
+                KeyCode::KEY_MSDOS => Capability::Keyboard(Keyboard::KeyMsdos),
+                KeyCode::KEY_COFFEE => Capability::Keyboard(Keyboard::KeyCoffee),
+                KeyCode::KEY_ROTATE_DISPLAY => Capability::Keyboard(Keyboard::KeyRotateDisplay),
+                KeyCode::KEY_CYCLEWINDOWS => Capability::Keyboard(Keyboard::KeyCycleWindows),
+                KeyCode::KEY_MAIL => Capability::Keyboard(Keyboard::KeyMail),
+                KeyCode::KEY_BOOKMARKS => Capability::Keyboard(Keyboard::KeyBookmarks),
+                KeyCode::KEY_COMPUTER => Capability::Keyboard(Keyboard::KeyComputer),
                 KeyCode::KEY_BACK => Capability::Keyboard(Keyboard::KeyBack),
                 KeyCode::KEY_FORWARD => Capability::Keyboard(Keyboard::KeyForward),
-                KeyCode::KEY_CLOSECD => Capability::NotImplemented,
+                KeyCode::KEY_CLOSECD => Capability::Keyboard(Keyboard::KeyCloseCD),
                 KeyCode::KEY_EJECTCD => Capability::Keyboard(Keyboard::KeyEjectCD),
-                KeyCode::KEY_EJECTCLOSECD => Capability::NotImplemented,
+                KeyCode::KEY_EJECTCLOSECD => Capability::Keyboard(Keyboard::KeyEjectCloseCD),
                 KeyCode::KEY_NEXTSONG => Capability::Keyboard(Keyboard::KeyNextSong),
                 KeyCode::KEY_PLAYPAUSE => Capability::Keyboard(Keyboard::KeyPlayPause),
                 KeyCode::KEY_PREVIOUSSONG => Capability::Keyboard(Keyboard::KeyPreviousSong),
                 KeyCode::KEY_STOPCD => Capability::Keyboard(Keyboard::KeyStopCD),
-                KeyCode::KEY_RECORD => Capability::NotImplemented,
-                KeyCode::KEY_REWIND => Capability::NotImplemented,
-                KeyCode::KEY_PHONE => Capability::NotImplemented,
-                KeyCode::KEY_ISO => Capability::NotImplemented,
-                KeyCode::KEY_CONFIG => Capability::NotImplemented,
-                KeyCode::KEY_HOMEPAGE => Capability::NotImplemented,
+                KeyCode::KEY_RECORD => Capability::Keyboard(Keyboard::KeyRecord),
+                KeyCode::KEY_REWIND => Capability::Keyboard(Keyboard::KeyRewind),
+                KeyCode::KEY_PHONE => Capability::Keyboard(Keyboard::KeyPhone),
+                KeyCode::KEY_ISO => Capability::Keyboard(Keyboard::KeyIso),
+                KeyCode::KEY_CONFIG => Capability::Keyboard(Keyboard::KeyConfig),
+                KeyCode::KEY_HOMEPAGE => Capability::Keyboard(Keyboard::KeyHomepage),
                 KeyCode::KEY_REFRESH => Capability::Keyboard(Keyboard::KeyRefresh),
-                KeyCode::KEY_EXIT => Capability::NotImplemented,
-                KeyCode::KEY_MOVE => Capability::NotImplemented,
+                KeyCode::KEY_EXIT => Capability::Keyboard(Keyboard::KeyExit),
+                KeyCode::KEY_MOVE => Capability::Keyboard(Keyboard::KeyMove),
                 KeyCode::KEY_EDIT => Capability::Keyboard(Keyboard::KeyEdit),
                 KeyCode::KEY_SCROLLUP => Capability::Keyboard(Keyboard::KeyScrollUp),
                 KeyCode::KEY_SCROLLDOWN => Capability::Keyboard(Keyboard::KeyScrollDown),
                 KeyCode::KEY_KPLEFTPAREN => Capability::Keyboard(Keyboard::KeyKpLeftParen),
                 KeyCode::KEY_KPRIGHTPAREN => Capability::Keyboard(Keyboard::KeyKpRightParen),
-                KeyCode::KEY_NEW => Capability::NotImplemented,
-                KeyCode::KEY_REDO => Capability::NotImplemented,
+                KeyCode::KEY_NEW => Capability::Keyboard(Keyboard::KeyNew),
+                KeyCode::KEY_REDO => Capability::Keyboard(Keyboard::KeyRedo),
                 KeyCode::KEY_F13 => Capability::Keyboard(Keyboard::KeyF13),
                 KeyCode::KEY_F14 => Capability::Keyboard(Keyboard::KeyF14),
                 KeyCode::KEY_F15 => Capability::Keyboard(Keyboard::KeyF15),
@@ -357,55 +386,145 @@ impl EvdevEvent {
                 KeyCode::KEY_F22 => Capability::Keyboard(Keyboard::KeyF22),
                 KeyCode::KEY_F23 => Capability::Keyboard(Keyboard::KeyF23),
                 KeyCode::KEY_F24 => Capability::Keyboard(Keyboard::KeyF24),
-                KeyCode::KEY_PLAYCD => Capability::NotImplemented,
-                KeyCode::KEY_PAUSECD => Capability::NotImplemented,
-                KeyCode::KEY_PROG3 => Capability::NotImplemented,
-                KeyCode::KEY_PROG4 => Capability::NotImplemented,
-                KeyCode::KEY_DASHBOARD => Capability::NotImplemented,
-                KeyCode::KEY_SUSPEND => Capability::NotImplemented,
-                KeyCode::KEY_CLOSE => Capability::NotImplemented,
-                KeyCode::KEY_PLAY => Capability::NotImplemented,
-                KeyCode::KEY_FASTFORWARD => Capability::NotImplemented,
-                KeyCode::KEY_BASSBOOST => Capability::NotImplemented,
-                KeyCode::KEY_PRINT => Capability::NotImplemented,
-                KeyCode::KEY_HP => Capability::NotImplemented,
-                KeyCode::KEY_CAMERA => Capability::NotImplemented,
-                KeyCode::KEY_SOUND => Capability::NotImplemented,
-                KeyCode::KEY_QUESTION => Capability::NotImplemented,
-                KeyCode::KEY_EMAIL => Capability::NotImplemented,
-                KeyCode::KEY_CHAT => Capability::NotImplemented,
-                KeyCode::KEY_SEARCH => Capability::NotImplemented,
-                KeyCode::KEY_CONNECT => Capability::NotImplemented,
-                KeyCode::KEY_FINANCE => Capability::NotImplemented,
-                KeyCode::KEY_SPORT => Capability::NotImplemented,
-                KeyCode::KEY_SHOP => Capability::NotImplemented,
-                KeyCode::KEY_ALTERASE => Capability::NotImplemented,
-                KeyCode::KEY_CANCEL => Capability::NotImplemented,
-                KeyCode::KEY_BRIGHTNESSDOWN => Capability::NotImplemented,
-                KeyCode::KEY_BRIGHTNESSUP => Capability::NotImplemented,
-                KeyCode::KEY_MEDIA => Capability::NotImplemented,
-                KeyCode::KEY_SWITCHVIDEOMODE => Capability::NotImplemented,
-                KeyCode::KEY_KBDILLUMTOGGLE => Capability::NotImplemented,
-                KeyCode::KEY_KBDILLUMDOWN => Capability::NotImplemented,
-                KeyCode::KEY_KBDILLUMUP => Capability::NotImplemented,
-                KeyCode::KEY_SEND => Capability::NotImplemented,
-                KeyCode::KEY_REPLY => Capability::NotImplemented,
-                KeyCode::KEY_FORWARDMAIL => Capability::NotImplemented,
-                KeyCode::KEY_SAVE => Capability::NotImplemented,
-                KeyCode::KEY_DOCUMENTS => Capability::NotImplemented,
-                KeyCode::KEY_BATTERY => Capability::NotImplemented,
-                KeyCode::KEY_BLUETOOTH => Capability::NotImplemented,
-                KeyCode::KEY_WLAN => Capability::NotImplemented,
-                KeyCode::KEY_UWB => Capability::NotImplemented,
+                KeyCode::KEY_PLAYCD => Capability::Keyboard(Keyboard::KeyPlayCD),
+                KeyCode::KEY_PAUSECD => Capability::Keyboard(Keyboard::KeyPauseCD),
+                KeyCode::KEY_PROG3 => Capability::Keyboard(Keyboard::KeyProg3),
+                KeyCode::KEY_PROG4 => Capability::Keyboard(Keyboard::KeyProg4),
+                KeyCode::KEY_DASHBOARD => Capability::Keyboard(Keyboard::KeyDashboard),
+                KeyCode::KEY_SUSPEND => Capability::Keyboard(Keyboard::KeySuspend),
+                KeyCode::KEY_CLOSE => Capability::Keyboard(Keyboard::KeyClose),
+                KeyCode::KEY_PLAY => Capability::Keyboard(Keyboard::KeyPlay),
+                KeyCode::KEY_FASTFORWARD => Capability::Keyboard(Keyboard::KeyFastForward),
+                KeyCode::KEY_BASSBOOST => Capability::Keyboard(Keyboard::KeyBassBoost),
+                KeyCode::KEY_PRINT => Capability::Keyboard(Keyboard::KeyPrint),
+                KeyCode::KEY_HP => Capability::Keyboard(Keyboard::KeyHp),
+                KeyCode::KEY_CAMERA => Capability::Keyboard(Keyboard::KeyCamera),
+                KeyCode::KEY_SOUND => Capability::Keyboard(Keyboard::KeySound),
+                KeyCode::KEY_QUESTION => Capability::Keyboard(Keyboard::KeyQuestion),
+                KeyCode::KEY_EMAIL => Capability::Keyboard(Keyboard::KeyEmail),
+                KeyCode::KEY_CHAT => Capability::Keyboard(Keyboard::KeyChat),
+                KeyCode::KEY_SEARCH => Capability::Keyboard(Keyboard::KeySearch),
+                KeyCode::KEY_CONNECT => Capability::Keyboard(Keyboard::KeyConnect),
+                KeyCode::KEY_FINANCE => Capability::Keyboard(Keyboard::KeyFinance),
+                KeyCode::KEY_SPORT => Capability::Keyboard(Keyboard::KeySport),
+                KeyCode::KEY_SHOP => Capability::Keyboard(Keyboard::KeyShop),
+                KeyCode::KEY_ALTERASE => Capability::Keyboard(Keyboard::KeyAltErase),
+                KeyCode::KEY_CANCEL => Capability::Keyboard(Keyboard::KeyCancel),
+                KeyCode::KEY_BRIGHTNESSDOWN => Capability::Keyboard(Keyboard::KeyBrightnessDown),
+                KeyCode::KEY_BRIGHTNESSUP => Capability::Keyboard(Keyboard::KeyBrightnessUp),
+                KeyCode::KEY_MEDIA => Capability::Keyboard(Keyboard::KeyMedia),
+                KeyCode::KEY_SWITCHVIDEOMODE => Capability::Keyboard(Keyboard::KeySwitchVideoMode),
+                KeyCode::KEY_KBDILLUMTOGGLE => Capability::Keyboard(Keyboard::KeyKbdIllumToggle),
+                KeyCode::KEY_KBDILLUMDOWN => Capability::Keyboard(Keyboard::KeyKbdIllumDown),
+                KeyCode::KEY_KBDILLUMUP => Capability::Keyboard(Keyboard::KeyKbdIllumUp),
+                KeyCode::KEY_SEND => Capability::Keyboard(Keyboard::KeySend),
+                KeyCode::KEY_REPLY => Capability::Keyboard(Keyboard::KeyReply),
+                KeyCode::KEY_FORWARDMAIL => Capability::Keyboard(Keyboard::KeyForwardMail),
+                KeyCode::KEY_SAVE => Capability::Keyboard(Keyboard::KeySave),
+                KeyCode::KEY_DOCUMENTS => Capability::Keyboard(Keyboard::KeyDocuments),
+                KeyCode::KEY_BATTERY => Capability::Keyboard(Keyboard::KeyBattery),
+                KeyCode::KEY_BLUETOOTH => Capability::Keyboard(Keyboard::KeyBluetooth),
+                KeyCode::KEY_WLAN => Capability::Keyboard(Keyboard::KeyWlan),
+                KeyCode::KEY_UWB => Capability::Keyboard(Keyboard::KeyUwb),
                 KeyCode::KEY_UNKNOWN => Capability::NotImplemented,
-                KeyCode::KEY_VIDEO_NEXT => Capability::NotImplemented,
-                KeyCode::KEY_VIDEO_PREV => Capability::NotImplemented,
-                KeyCode::KEY_BRIGHTNESS_CYCLE => Capability::NotImplemented,
-                KeyCode::KEY_BRIGHTNESS_AUTO => Capability::NotImplemented,
-                KeyCode::KEY_DISPLAY_OFF => Capability::NotImplemented,
-                KeyCode::KEY_WWAN => Capability::NotImplemented,
-                KeyCode::KEY_RFKILL => Capability::NotImplemented,
-                KeyCode::KEY_MICMUTE => Capability::NotImplemented,
+                KeyCode::KEY_VIDEO_NEXT => Capability::Keyboard(Keyboard::KeyVideoNext),
+                KeyCode::KEY_VIDEO_PREV => Capability::Keyboard(Keyboard::KeyVideoPrev),
+                KeyCode::KEY_BRIGHTNESS_CYCLE => Capability::Keyboard(Keyboard::KeyBrightnessCycle),
+                KeyCode::KEY_BRIGHTNESS_AUTO => Capability::Keyboard(Keyboard::KeyBrightnessAuto),
+                KeyCode::KEY_DISPLAY_OFF => Capability::Keyboard(Keyboard::KeyDisplayOff),
+                KeyCode::KEY_WWAN => Capability::Keyboard(Keyboard::KeyWwan),
+                KeyCode::KEY_RFKILL => Capability::Keyboard(Keyboard::KeyRfkill),
+                KeyCode::KEY_MICMUTE => Capability::Gamepad(Gamepad::Button(GamepadButton::Mute)),
+                KeyCode::KEY_OK => Capability::Keyboard(Keyboard::KeyOk),
+                KeyCode::KEY_SELECT => Capability::Keyboard(Keyboard::KeySelect),
+                KeyCode::KEY_GOTO => Capability::Keyboard(Keyboard::KeyGoto),
+                KeyCode::KEY_CLEAR => Capability::Keyboard(Keyboard::KeyClear),
+                KeyCode::KEY_POWER2 => Capability::Keyboard(Keyboard::KeyPower2),
+                KeyCode::KEY_OPTION => Capability::Keyboard(Keyboard::KeyOption),
+                KeyCode::KEY_INFO => Capability::Keyboard(Keyboard::KeyInfo),
+                KeyCode::KEY_TIME => Capability::Keyboard(Keyboard::KeyTime),
+                KeyCode::KEY_VENDOR => Capability::Keyboard(Keyboard::KeyVendor),
+                KeyCode::KEY_ARCHIVE => Capability::Keyboard(Keyboard::KeyArchive),
+                KeyCode::KEY_PROGRAM => Capability::Keyboard(Keyboard::KeyProgram),
+                KeyCode::KEY_CHANNEL => Capability::Keyboard(Keyboard::KeyChannel),
+                KeyCode::KEY_FAVORITES => Capability::Keyboard(Keyboard::KeyFavorites),
+                KeyCode::KEY_EPG => Capability::Keyboard(Keyboard::KeyEpg),
+                KeyCode::KEY_PVR => Capability::Keyboard(Keyboard::KeyPvr),
+                KeyCode::KEY_MHP => Capability::Keyboard(Keyboard::KeyMhp),
+                KeyCode::KEY_LANGUAGE => Capability::Keyboard(Keyboard::KeyLanguage),
+                KeyCode::KEY_TITLE => Capability::Keyboard(Keyboard::KeyTitle),
+                KeyCode::KEY_SUBTITLE => Capability::Keyboard(Keyboard::KeySubtitle),
+                KeyCode::KEY_ANGLE => Capability::Keyboard(Keyboard::KeyAngle),
+                KeyCode::KEY_MODE => Capability::Keyboard(Keyboard::KeyMode),
+                KeyCode::KEY_KEYBOARD => Capability::Keyboard(Keyboard::KeyKeyboard),
+                KeyCode::KEY_SCREEN => Capability::Keyboard(Keyboard::KeyScreen),
+                KeyCode::KEY_PC => Capability::Keyboard(Keyboard::KeyPc),
+                KeyCode::KEY_TV => Capability::Keyboard(Keyboard::KeyTv),
+                KeyCode::KEY_TV2 => Capability::Keyboard(Keyboard::KeyTv2),
+                KeyCode::KEY_VCR => Capability::Keyboard(Keyboard::KeyVcr),
+                KeyCode::KEY_VCR2 => Capability::Keyboard(Keyboard::KeyVcr2),
+                KeyCode::KEY_SAT => Capability::Keyboard(Keyboard::KeySat),
+                KeyCode::KEY_SAT2 => Capability::Keyboard(Keyboard::KeySat2),
+                KeyCode::KEY_CD => Capability::Keyboard(Keyboard::KeyCd),
+                KeyCode::KEY_TAPE => Capability::Keyboard(Keyboard::KeyTape),
+                KeyCode::KEY_RADIO => Capability::Keyboard(Keyboard::KeyRadio),
+                KeyCode::KEY_TUNER => Capability::Keyboard(Keyboard::KeyTuner),
+                KeyCode::KEY_PLAYER => Capability::Keyboard(Keyboard::KeyPlayer),
+                KeyCode::KEY_TEXT => Capability::Keyboard(Keyboard::KeyText),
+                KeyCode::KEY_DVD => Capability::Keyboard(Keyboard::KeyDvd),
+                KeyCode::KEY_AUX => Capability::Keyboard(Keyboard::KeyAux),
+                KeyCode::KEY_MP3 => Capability::Keyboard(Keyboard::KeyMp3),
+                KeyCode::KEY_AUDIO => Capability::Keyboard(Keyboard::KeyAudio),
+                KeyCode::KEY_VIDEO => Capability::Keyboard(Keyboard::KeyVideo),
+                KeyCode::KEY_DIRECTORY => Capability::Keyboard(Keyboard::KeyDirectory),
+                KeyCode::KEY_LIST => Capability::Keyboard(Keyboard::KeyList),
+                KeyCode::KEY_MEMO => Capability::Keyboard(Keyboard::KeyMemo),
+                KeyCode::KEY_CALENDAR => Capability::Keyboard(Keyboard::KeyCalendar),
+                KeyCode::KEY_RED => Capability::Keyboard(Keyboard::KeyRed),
+                KeyCode::KEY_GREEN => Capability::Keyboard(Keyboard::KeyGreen),
+                KeyCode::KEY_YELLOW => Capability::Keyboard(Keyboard::KeyYellow),
+                KeyCode::KEY_BLUE => Capability::Keyboard(Keyboard::KeyBlue),
+                KeyCode::KEY_CHANNELUP => Capability::Keyboard(Keyboard::KeyChannelUp),
+                KeyCode::KEY_CHANNELDOWN => Capability::Keyboard(Keyboard::KeyChannelDown),
+                KeyCode::KEY_FIRST => Capability::Keyboard(Keyboard::KeyFirst),
+                KeyCode::KEY_LAST => Capability::Keyboard(Keyboard::KeyLast),
+                KeyCode::KEY_AB => Capability::Keyboard(Keyboard::KeyAb),
+                KeyCode::KEY_NEXT => Capability::Keyboard(Keyboard::KeyNext),
+                KeyCode::KEY_RESTART => Capability::Keyboard(Keyboard::KeyRestart),
+                KeyCode::KEY_SLOW => Capability::Keyboard(Keyboard::KeySlow),
+                KeyCode::KEY_SHUFFLE => Capability::Keyboard(Keyboard::KeyShuffle),
+                KeyCode::KEY_BREAK => Capability::Keyboard(Keyboard::KeyBreak),
+                KeyCode::KEY_PREVIOUS => Capability::Keyboard(Keyboard::KeyPrevious),
+                KeyCode::KEY_DIGITS => Capability::Keyboard(Keyboard::KeyDigits),
+                KeyCode::KEY_TEEN => Capability::Keyboard(Keyboard::KeyTeen),
+                KeyCode::KEY_TWEN => Capability::Keyboard(Keyboard::KeyTwen),
+                KeyCode::KEY_VIDEOPHONE => Capability::Keyboard(Keyboard::KeyVideophone),
+                KeyCode::KEY_GAMES => Capability::Keyboard(Keyboard::KeyGames),
+                KeyCode::KEY_ZOOMIN => Capability::Keyboard(Keyboard::KeyZoomIn),
+                KeyCode::KEY_ZOOMOUT => Capability::Keyboard(Keyboard::KeyZoomOut),
+                KeyCode::KEY_ZOOMRESET => Capability::Keyboard(Keyboard::KeyZoomReset),
+                KeyCode::KEY_WORDPROCESSOR => Capability::Keyboard(Keyboard::KeyWordProcessor),
+                KeyCode::KEY_EDITOR => Capability::Keyboard(Keyboard::KeyEditor),
+                KeyCode::KEY_SPREADSHEET => Capability::Keyboard(Keyboard::KeySpreadsheet),
+                KeyCode::KEY_GRAPHICSEDITOR => Capability::Keyboard(Keyboard::KeyGraphicsEditor),
+                KeyCode::KEY_PRESENTATION => Capability::Keyboard(Keyboard::KeyPresentation),
+                KeyCode::KEY_DATABASE => Capability::Keyboard(Keyboard::KeyDatabase),
+                KeyCode::KEY_NEWS => Capability::Keyboard(Keyboard::KeyNews),
+                KeyCode::KEY_VOICEMAIL => Capability::Keyboard(Keyboard::KeyVoicemail),
+                KeyCode::KEY_ADDRESSBOOK => Capability::Keyboard(Keyboard::KeyAddressBook),
+                KeyCode::KEY_MESSENGER => Capability::Keyboard(Keyboard::KeyMessenger),
+                KeyCode::KEY_DISPLAYTOGGLE => Capability::Keyboard(Keyboard::KeyDisplayToggle),
+                KeyCode::KEY_SPELLCHECK => Capability::Keyboard(Keyboard::KeySpellCheck),
+                KeyCode::KEY_LOGOFF => Capability::Keyboard(Keyboard::KeyLogoff),
+                KeyCode::KEY_DOLLAR => Capability::Keyboard(Keyboard::KeyDollar),
+                KeyCode::KEY_EURO => Capability::Keyboard(Keyboard::KeyEuro),
+                KeyCode::KEY_FRAMEBACK => Capability::Keyboard(Keyboard::KeyFrameBack),
+                KeyCode::KEY_FRAMEFORWARD => Capability::Keyboard(Keyboard::KeyFrameForward),
+                KeyCode::KEY_CONTEXT_MENU => Capability::Keyboard(Keyboard::KeyContextMenu),
+                KeyCode::KEY_MEDIA_REPEAT => Capability::Keyboard(Keyboard::KeyMediaRepeat),
+                KeyCode::KEY_10CHANNELSUP => Capability::Keyboard(Keyboard::Key10ChannelsUp),
+                KeyCode::KEY_10CHANNELSDOWN => Capability::Keyboard(Keyboard::Key10ChannelsDown),
+                KeyCode::KEY_IMAGES => Capability::Keyboard(Keyboard::KeyImages),
                 _ => Capability::NotImplemented,
             },
             EventType::ABSOLUTE => match AbsoluteAxisCode(code) {
@@ -460,6 +579,7 @@ impl Default for EvdevEvent {
         Self {
             event: InputEvent::new(0, 0, 0),
             abs_info: None,
+            timestamp: None,
         }
     }
 }
@@ -469,6 +589,7 @@ impl From<InputEvent> for EvdevEvent {
         EvdevEvent {
             event: item,
             abs_info: None,
+            timestamp: None,
         }
     }
 }
@@ -481,6 +602,7 @@ impl EvdevEvent {
     ) -> Vec<Self> {
         // Native events can be translated into one or more evdev events
         let mut events = Vec::new();
+        let timestamp = event.get_timestamp();
 
         // Determine the event type to use based on the capability
         let event_type = event_type_from_capability(event.as_capability());
@@ -504,9 +626,10 @@ impl EvdevEvent {
                 None
             };
 
-            // Get the axis direction if this this an ABS event and we need to
-            // translate binary input into axis input. (e.g. DPad buttons)
-            let axis_direction = if event_type == EventType::ABSOLUTE {
+            // Get the axis direction if this this an ABS or REL event and we
+            // need to translate binary input into axis input. (e.g. DPad buttons)
+            let is_axis = matches!(event_type, EventType::ABSOLUTE | EventType::RELATIVE);
+            let axis_direction = if is_axis {
                 Some(axis_direction_from_capability(event.as_capability()))
             } else {
                 None
@@ -523,18 +646,55 @@ impl EvdevEvent {
             events.push(EvdevEvent::from(event.unwrap()));
         }
 
+        // Include the time the source device produced the event so consumers
+        // can measure input latency.
+        if !events.is_empty() {
+            if let Some(timestamp) = timestamp {
+                events.push(EvdevEvent::from(timestamp_event(timestamp)));
+            }
+        }
+
         events
     }
 }
 
+/// Returns an MSC_TIMESTAMP event for the given time. The value is the
+/// monotonic clock time in microseconds and is expected to wrap around, so it
+/// is not affected by changes to the system clock.
+fn timestamp_event(timestamp: Instant) -> InputEvent {
+    let now = clock_gettime(ClockId::CLOCK_MONOTONIC)
+        .map(Duration::from)
+        .unwrap_or_default();
+    let micros = now.saturating_sub(timestamp.elapsed()).as_micros() as u32;
+    InputEvent::new(EventType::MISC.0, MiscCode::MSC_TIMESTAMP.0, micros as i32)
+}
+
+/// Converts the kernel time of an evdev event into an [Instant]. Input devices
+/// report event times using CLOCK_REALTIME by default, so the time is converted
+/// based on how long ago the event happened. Returns `None` for events without
+/// a time, such as events created with [InputEvent::new].
+pub fn instant_from_system_time(time: SystemTime) -> Option<Instant> {
+    if time <= UNIX_EPOCH {
+        return None;
+    }
+    let age = SystemTime::now().duration_since(time).unwrap_or_default();
+    Instant::now().checked_sub(age)
+}
+
 /// Returns the event type responsible for handling the given input capability.
-fn event_type_from_capability(capability: Capability) -> Option<EventType> {
+pub fn event_type_from_capability(capability: Capability) -> Option<EventType> {
     match capability {
         Capability::Sync => Some(EventType::SYNCHRONIZATION),
         Capability::Keyboard(_) => Some(EventType::KEY),
         Capability::Mouse(mouse) => match mouse {
             Mouse::Motion => Some(EventType::RELATIVE),
-            Mouse::Button(_) => Some(EventType::KEY),
+            Mouse::Button(button) => match button {
+                MouseButton::WheelUp
+                | MouseButton::WheelDown
+                | MouseButton::WheelLeft
+                | MouseButton::WheelRight => Some(EventType::RELATIVE),
+                _ => Some(EventType::KEY),
+            },
         },
         Capability::Gamepad(gamepad) => match gamepad {
             Gamepad::Button(button) => match button {
@@ -570,13 +730,20 @@ fn axis_direction_from_capability(capability: Capability) -> AxisDirection {
                 AxisDirection::None
             }
         }
+        Capability::Mouse(Mouse::Button(button)) => match button {
+            MouseButton::WheelUp => AxisDirection::Positive,
+            MouseButton::WheelDown => AxisDirection::Negative,
+            MouseButton::WheelLeft => AxisDirection::Negative,
+            MouseButton::WheelRight => AxisDirection::Positive,
+            _ => AxisDirection::None,
+        },
         _ => AxisDirection::None,
     }
 }
 
 /// Returns a list of event codes responsible for handling the given input capability.
 /// This is typically used to translate a [NativeEvent] into an [EvdevEvent].
-fn event_codes_from_capability(capability: Capability) -> Vec<u16> {
+pub fn event_codes_from_capability(capability: Capability) -> Vec<u16> {
     match capability {
         Capability::None => vec![],
         Capability::NotImplemented => vec![],
@@ -599,6 +766,7 @@ fn event_codes_from_capability(capability: Capability) -> Vec<u16> {
                 GamepadButton::QuickAccess2 => vec![],
                 GamepadButton::Keyboard => vec![],
                 GamepadButton::Screenshot => vec![],
+                GamepadButton::Mute => vec![KeyCode::KEY_MICMUTE.0],
                 GamepadButton::LeftStick => vec![KeyCode::BTN_THUMBL.0],
                 GamepadButton::RightStick => vec![KeyCode::BTN_THUMBR.0],
                 GamepadButton::DPadUp => vec![AbsoluteAxisCode::ABS_HAT0Y.0],
@@ -606,12 +774,12 @@ fn event_codes_from_capability(capability: Capability) -> Vec<u16> {
                 GamepadButton::DPadLeft => vec![AbsoluteAxisCode::ABS_HAT0X.0],
                 GamepadButton::DPadRight => vec![AbsoluteAxisCode::ABS_HAT0X.0],
                 GamepadButton::LeftTrigger => vec![KeyCode::BTN_TL2.0],
-                GamepadButton::LeftPaddle1 => vec![],
-                GamepadButton::LeftPaddle2 => vec![],
+                GamepadButton::LeftPaddle1 => vec![KeyCode::BTN_TRIGGER_HAPPY7.0],
+                GamepadButton::LeftPaddle2 => vec![KeyCode::BTN_TRIGGER_HAPPY8.0],
                 GamepadButton::LeftStickTouch => vec![],
                 GamepadButton::RightTrigger => vec![KeyCode::BTN_TR2.0],
-                GamepadButton::RightPaddle1 => vec![],
-                GamepadButton::RightPaddle2 => vec![],
+                GamepadButton::RightPaddle1 => vec![KeyCode::BTN_TRIGGER_HAPPY5.0],
+                GamepadButton::RightPaddle2 => vec![KeyCode::BTN_TRIGGER_HAPPY6.0],
                 GamepadButton::RightStickTouch => vec![],
                 GamepadButton::LeftPaddle3 => vec![],
                 GamepadButton::RightPaddle3 => vec![],
@@ -657,10 +825,10 @@ fn event_codes_from_capability(capability: Capability) -> Vec<u16> {
                 MouseButton::Left => vec![KeyCode::BTN_LEFT.0],
                 MouseButton::Right => vec![KeyCode::BTN_RIGHT.0],
                 MouseButton::Middle => vec![KeyCode::BTN_MIDDLE.0],
-                MouseButton::WheelUp => vec![],
-                MouseButton::WheelDown => vec![],
-                MouseButton::WheelLeft => vec![],
-                MouseButton::WheelRight => vec![],
+                MouseButton::WheelUp => vec![RelativeAxisCode::REL_WHEEL.0],
+                MouseButton::WheelDown => vec![RelativeAxisCode::REL_WHEEL.0],
+                MouseButton::WheelLeft => vec![RelativeAxisCode::REL_HWHEEL.0],
+                MouseButton::WheelRight => vec![RelativeAxisCode::REL_HWHEEL.0],
                 MouseButton::Extra => vec![KeyCode::BTN_EXTRA.0],
                 MouseButton::Side => vec![KeyCode::BTN_SIDE.0],
             },
@@ -827,6 +995,175 @@ fn event_codes_from_capability(capability: Capability) -> Vec<u16> {
             Keyboard::KeyF23 => vec![KeyCode::KEY_F23.0],
             Keyboard::KeyF24 => vec![KeyCode::KEY_F24.0],
             Keyboard::KeyProg1 => vec![KeyCode::KEY_PROG1.0],
+            Keyboard::KeyProg2 => vec![KeyCode::KEY_PROG2.0],
+            Keyboard::KeyProg3 => vec![KeyCode::KEY_PROG3.0],
+            Keyboard::KeyProg4 => vec![KeyCode::KEY_PROG4.0],
+            Keyboard::KeyWlan => vec![KeyCode::KEY_WLAN.0],
+            Keyboard::KeyRfkill => vec![KeyCode::KEY_RFKILL.0],
+            Keyboard::KeyLinefeed => vec![KeyCode::KEY_LINEFEED.0],
+            Keyboard::KeyMacro => vec![KeyCode::KEY_MACRO.0],
+            Keyboard::KeyKpPlusMinus => vec![KeyCode::KEY_KPPLUSMINUS.0],
+            Keyboard::KeyScale => vec![KeyCode::KEY_SCALE.0],
+            Keyboard::KeyHangeul => vec![KeyCode::KEY_HANGEUL.0],
+            Keyboard::KeyMenu => vec![KeyCode::KEY_MENU.0],
+            Keyboard::KeySetup => vec![KeyCode::KEY_SETUP.0],
+            Keyboard::KeyWakeup => vec![KeyCode::KEY_WAKEUP.0],
+            Keyboard::KeyFile => vec![KeyCode::KEY_FILE.0],
+            Keyboard::KeySendFile => vec![KeyCode::KEY_SENDFILE.0],
+            Keyboard::KeyDeleteFile => vec![KeyCode::KEY_DELETEFILE.0],
+            Keyboard::KeyXfer => vec![KeyCode::KEY_XFER.0],
+            Keyboard::KeyMsdos => vec![KeyCode::KEY_MSDOS.0],
+            Keyboard::KeyCoffee => vec![KeyCode::KEY_COFFEE.0],
+            Keyboard::KeyRotateDisplay => vec![KeyCode::KEY_ROTATE_DISPLAY.0],
+            Keyboard::KeyCycleWindows => vec![KeyCode::KEY_CYCLEWINDOWS.0],
+            Keyboard::KeyMail => vec![KeyCode::KEY_MAIL.0],
+            Keyboard::KeyBookmarks => vec![KeyCode::KEY_BOOKMARKS.0],
+            Keyboard::KeyComputer => vec![KeyCode::KEY_COMPUTER.0],
+            Keyboard::KeyCloseCD => vec![KeyCode::KEY_CLOSECD.0],
+            Keyboard::KeyEjectCloseCD => vec![KeyCode::KEY_EJECTCLOSECD.0],
+            Keyboard::KeyRecord => vec![KeyCode::KEY_RECORD.0],
+            Keyboard::KeyRewind => vec![KeyCode::KEY_REWIND.0],
+            Keyboard::KeyPhone => vec![KeyCode::KEY_PHONE.0],
+            Keyboard::KeyIso => vec![KeyCode::KEY_ISO.0],
+            Keyboard::KeyConfig => vec![KeyCode::KEY_CONFIG.0],
+            Keyboard::KeyHomepage => vec![KeyCode::KEY_HOMEPAGE.0],
+            Keyboard::KeyExit => vec![KeyCode::KEY_EXIT.0],
+            Keyboard::KeyMove => vec![KeyCode::KEY_MOVE.0],
+            Keyboard::KeyNew => vec![KeyCode::KEY_NEW.0],
+            Keyboard::KeyRedo => vec![KeyCode::KEY_REDO.0],
+            Keyboard::KeyPlayCD => vec![KeyCode::KEY_PLAYCD.0],
+            Keyboard::KeyPauseCD => vec![KeyCode::KEY_PAUSECD.0],
+            Keyboard::KeyDashboard => vec![KeyCode::KEY_DASHBOARD.0],
+            Keyboard::KeySuspend => vec![KeyCode::KEY_SUSPEND.0],
+            Keyboard::KeyClose => vec![KeyCode::KEY_CLOSE.0],
+            Keyboard::KeyPlay => vec![KeyCode::KEY_PLAY.0],
+            Keyboard::KeyFastForward => vec![KeyCode::KEY_FASTFORWARD.0],
+            Keyboard::KeyBassBoost => vec![KeyCode::KEY_BASSBOOST.0],
+            Keyboard::KeyPrint => vec![KeyCode::KEY_PRINT.0],
+            Keyboard::KeyHp => vec![KeyCode::KEY_HP.0],
+            Keyboard::KeyCamera => vec![KeyCode::KEY_CAMERA.0],
+            Keyboard::KeySound => vec![KeyCode::KEY_SOUND.0],
+            Keyboard::KeyQuestion => vec![KeyCode::KEY_QUESTION.0],
+            Keyboard::KeyEmail => vec![KeyCode::KEY_EMAIL.0],
+            Keyboard::KeyChat => vec![KeyCode::KEY_CHAT.0],
+            Keyboard::KeySearch => vec![KeyCode::KEY_SEARCH.0],
+            Keyboard::KeyConnect => vec![KeyCode::KEY_CONNECT.0],
+            Keyboard::KeyFinance => vec![KeyCode::KEY_FINANCE.0],
+            Keyboard::KeySport => vec![KeyCode::KEY_SPORT.0],
+            Keyboard::KeyShop => vec![KeyCode::KEY_SHOP.0],
+            Keyboard::KeyAltErase => vec![KeyCode::KEY_ALTERASE.0],
+            Keyboard::KeyCancel => vec![KeyCode::KEY_CANCEL.0],
+            Keyboard::KeyBrightnessDown => vec![KeyCode::KEY_BRIGHTNESSDOWN.0],
+            Keyboard::KeyBrightnessUp => vec![KeyCode::KEY_BRIGHTNESSUP.0],
+            Keyboard::KeyMedia => vec![KeyCode::KEY_MEDIA.0],
+            Keyboard::KeySwitchVideoMode => vec![KeyCode::KEY_SWITCHVIDEOMODE.0],
+            Keyboard::KeyKbdIllumToggle => vec![KeyCode::KEY_KBDILLUMTOGGLE.0],
+            Keyboard::KeyKbdIllumDown => vec![KeyCode::KEY_KBDILLUMDOWN.0],
+            Keyboard::KeyKbdIllumUp => vec![KeyCode::KEY_KBDILLUMUP.0],
+            Keyboard::KeySend => vec![KeyCode::KEY_SEND.0],
+            Keyboard::KeyReply => vec![KeyCode::KEY_REPLY.0],
+            Keyboard::KeyForwardMail => vec![KeyCode::KEY_FORWARDMAIL.0],
+            Keyboard::KeySave => vec![KeyCode::KEY_SAVE.0],
+            Keyboard::KeyDocuments => vec![KeyCode::KEY_DOCUMENTS.0],
+            Keyboard::KeyBattery => vec![KeyCode::KEY_BATTERY.0],
+            Keyboard::KeyBluetooth => vec![KeyCode::KEY_BLUETOOTH.0],
+            Keyboard::KeyUwb => vec![KeyCode::KEY_UWB.0],
+            Keyboard::KeyVideoNext => vec![KeyCode::KEY_VIDEO_NEXT.0],
+            Keyboard::KeyVideoPrev => vec![KeyCode::KEY_VIDEO_PREV.0],
+            Keyboard::KeyBrightnessCycle => vec![KeyCode::KEY_BRIGHTNESS_CYCLE.0],
+            Keyboard::KeyBrightnessAuto => vec![KeyCode::KEY_BRIGHTNESS_AUTO.0],
+            Keyboard::KeyDisplayOff => vec![KeyCode::KEY_DISPLAY_OFF.0],
+            Keyboard::KeyWwan => vec![KeyCode::KEY_WWAN.0],
+            Keyboard::KeyOk => vec![KeyCode::KEY_OK.0],
+            Keyboard::KeySelect => vec![KeyCode::KEY_SELECT.0],
+            Keyboard::KeyGoto => vec![KeyCode::KEY_GOTO.0],
+            Keyboard::KeyClear => vec![KeyCode::KEY_CLEAR.0],
+            Keyboard::KeyPower2 => vec![KeyCode::KEY_POWER2.0],
+            Keyboard::KeyOption => vec![KeyCode::KEY_OPTION.0],
+            Keyboard::KeyInfo => vec![KeyCode::KEY_INFO.0],
+            Keyboard::KeyTime => vec![KeyCode::KEY_TIME.0],
+            Keyboard::KeyVendor => vec![KeyCode::KEY_VENDOR.0],
+            Keyboard::KeyArchive => vec![KeyCode::KEY_ARCHIVE.0],
+            Keyboard::KeyProgram => vec![KeyCode::KEY_PROGRAM.0],
+            Keyboard::KeyChannel => vec![KeyCode::KEY_CHANNEL.0],
+            Keyboard::KeyFavorites => vec![KeyCode::KEY_FAVORITES.0],
+            Keyboard::KeyEpg => vec![KeyCode::KEY_EPG.0],
+            Keyboard::KeyPvr => vec![KeyCode::KEY_PVR.0],
+            Keyboard::KeyMhp => vec![KeyCode::KEY_MHP.0],
+            Keyboard::KeyLanguage => vec![KeyCode::KEY_LANGUAGE.0],
+            Keyboard::KeyTitle => vec![KeyCode::KEY_TITLE.0],
+            Keyboard::KeySubtitle => vec![KeyCode::KEY_SUBTITLE.0],
+            Keyboard::KeyAngle => vec![KeyCode::KEY_ANGLE.0],
+            Keyboard::KeyMode => vec![KeyCode::KEY_MODE.0],
+            Keyboard::KeyKeyboard => vec![KeyCode::KEY_KEYBOARD.0],
+            Keyboard::KeyScreen => vec![KeyCode::KEY_SCREEN.0],
+            Keyboard::KeyPc => vec![KeyCode::KEY_PC.0],
+            Keyboard::KeyTv => vec![KeyCode::KEY_TV.0],
+            Keyboard::KeyTv2 => vec![KeyCode::KEY_TV2.0],
+            Keyboard::KeyVcr => vec![KeyCode::KEY_VCR.0],
+            Keyboard::KeyVcr2 => vec![KeyCode::KEY_VCR2.0],
+            Keyboard::KeySat => vec![KeyCode::KEY_SAT.0],
+            Keyboard::KeySat2 => vec![KeyCode::KEY_SAT2.0],
+            Keyboard::KeyCd => vec![KeyCode::KEY_CD.0],
+            Keyboard::KeyTape => vec![KeyCode::KEY_TAPE.0],
+            Keyboard::KeyRadio => vec![KeyCode::KEY_RADIO.0],
+            Keyboard::KeyTuner => vec![KeyCode::KEY_TUNER.0],
+            Keyboard::KeyPlayer => vec![KeyCode::KEY_PLAYER.0],
+            Keyboard::KeyText => vec![KeyCode::KEY_TEXT.0],
+            Keyboard::KeyDvd => vec![KeyCode::KEY_DVD.0],
+            Keyboard::KeyAux => vec![KeyCode::KEY_AUX.0],
+            Keyboard::KeyMp3 => vec![KeyCode::KEY_MP3.0],
+            Keyboard::KeyAudio => vec![KeyCode::KEY_AUDIO.0],
+            Keyboard::KeyVideo => vec![KeyCode::KEY_VIDEO.0],
+            Keyboard::KeyDirectory => vec![KeyCode::KEY_DIRECTORY.0],
+            Keyboard::KeyList => vec![KeyCode::KEY_LIST.0],
+            Keyboard::KeyMemo => vec![KeyCode::KEY_MEMO.0],
+            Keyboard::KeyCalendar => vec![KeyCode::KEY_CALENDAR.0],
+            Keyboard::KeyRed => vec![KeyCode::KEY_RED.0],
+            Keyboard::KeyGreen => vec![KeyCode::KEY_GREEN.0],
+            Keyboard::KeyYellow => vec![KeyCode::KEY_YELLOW.0],
+            Keyboard::KeyBlue => vec![KeyCode::KEY_BLUE.0],
+            Keyboard::KeyChannelUp => vec![KeyCode::KEY_CHANNELUP.0],
+            Keyboard::KeyChannelDown => vec![KeyCode::KEY_CHANNELDOWN.0],
+            Keyboard::KeyFirst => vec![KeyCode::KEY_FIRST.0],
+            Keyboard::KeyLast => vec![KeyCode::KEY_LAST.0],
+            Keyboard::KeyAb => vec![KeyCode::KEY_AB.0],
+            Keyboard::KeyNext => vec![KeyCode::KEY_NEXT.0],
+            Keyboard::KeyRestart => vec![KeyCode::KEY_RESTART.0],
+            Keyboard::KeySlow => vec![KeyCode::KEY_SLOW.0],
+            Keyboard::KeyShuffle => vec![KeyCode::KEY_SHUFFLE.0],
+            Keyboard::KeyBreak => vec![KeyCode::KEY_BREAK.0],
+            Keyboard::KeyPrevious => vec![KeyCode::KEY_PREVIOUS.0],
+            Keyboard::KeyDigits => vec![KeyCode::KEY_DIGITS.0],
+            Keyboard::KeyTeen => vec![KeyCode::KEY_TEEN.0],
+            Keyboard::KeyTwen => vec![KeyCode::KEY_TWEN.0],
+            Keyboard::KeyVideophone => vec![KeyCode::KEY_VIDEOPHONE.0],
+            Keyboard::KeyGames => vec![KeyCode::KEY_GAMES.0],
+            Keyboard::KeyZoomIn => vec![KeyCode::KEY_ZOOMIN.0],
+            Keyboard::KeyZoomOut => vec![KeyCode::KEY_ZOOMOUT.0],
+            Keyboard::KeyZoomReset => vec![KeyCode::KEY_ZOOMRESET.0],
+            Keyboard::KeyWordProcessor => vec![KeyCode::KEY_WORDPROCESSOR.0],
+            Keyboard::KeyEditor => vec![KeyCode::KEY_EDITOR.0],
+            Keyboard::KeySpreadsheet => vec![KeyCode::KEY_SPREADSHEET.0],
+            Keyboard::KeyGraphicsEditor => vec![KeyCode::KEY_GRAPHICSEDITOR.0],
+            Keyboard::KeyPresentation => vec![KeyCode::KEY_PRESENTATION.0],
+            Keyboard::KeyDatabase => vec![KeyCode::KEY_DATABASE.0],
+            Keyboard::KeyNews => vec![KeyCode::KEY_NEWS.0],
+            Keyboard::KeyVoicemail => vec![KeyCode::KEY_VOICEMAIL.0],
+            Keyboard::KeyAddressBook => vec![KeyCode::KEY_ADDRESSBOOK.0],
+            Keyboard::KeyMessenger => vec![KeyCode::KEY_MESSENGER.0],
+            Keyboard::KeyDisplayToggle => vec![KeyCode::KEY_DISPLAYTOGGLE.0],
+            Keyboard::KeySpellCheck => vec![KeyCode::KEY_SPELLCHECK.0],
+            Keyboard::KeyLogoff => vec![KeyCode::KEY_LOGOFF.0],
+            Keyboard::KeyDollar => vec![KeyCode::KEY_DOLLAR.0],
+            Keyboard::KeyEuro => vec![KeyCode::KEY_EURO.0],
+            Keyboard::KeyFrameBack => vec![KeyCode::KEY_FRAMEBACK.0],
+            Keyboard::KeyFrameForward => vec![KeyCode::KEY_FRAMEFORWARD.0],
+            Keyboard::KeyContextMenu => vec![KeyCode::KEY_CONTEXT_MENU.0],
+            Keyboard::KeyMediaRepeat => vec![KeyCode::KEY_MEDIA_REPEAT.0],
+            Keyboard::Key10ChannelsUp => vec![KeyCode::KEY_10CHANNELSUP.0],
+            Keyboard::Key10ChannelsDown => vec![KeyCode::KEY_10CHANNELSDOWN.0],
+            Keyboard::KeyImages => vec![KeyCode::KEY_IMAGES.0],
         },
         Capability::Touchpad(touch) => match touch {
             Touchpad::LeftPad(action) => match action {
@@ -838,6 +1175,15 @@ fn event_codes_from_capability(capability: Capability) -> Vec<u16> {
                     TouchButton::Touch => vec![KeyCode::BTN_TOUCH.0],
                     TouchButton::Press => vec![KeyCode::BTN_LEFT.0],
                 },
+                Touch::Tilt => vec![
+                    AbsoluteAxisCode::ABS_TILT_X.0,
+                    AbsoluteAxisCode::ABS_TILT_Y.0,
+                ],
+                Touch::Tool(tool) => match tool {
+                    TouchTool::Finger => vec![KeyCode::BTN_TOOL_FINGER.0],
+                    TouchTool::Pen => vec![KeyCode::BTN_TOOL_PEN.0],
+                    TouchTool::Eraser => vec![KeyCode::BTN_TOOL_RUBBER.0],
+                },
             },
             Touchpad::RightPad(action) => match action {
                 Touch::Motion => vec![
@@ -848,6 +1194,15 @@ fn event_codes_from_capability(capability: Capability) -> Vec<u16> {
                     TouchButton::Touch => vec![KeyCode::BTN_TOUCH.0],
                     TouchButton::Press => vec![KeyCode::BTN_LEFT.0],
                 },
+                Touch::Tilt => vec![
+                    AbsoluteAxisCode::ABS_TILT_X.0,
+                    AbsoluteAxisCode::ABS_TILT_Y.0,
+                ],
+                Touch::Tool(tool) => match tool {
+                    TouchTool::Finger => vec![KeyCode::BTN_TOOL_FINGER.0],
+                    TouchTool::Pen => vec![KeyCode::BTN_TOOL_PEN.0],
+                    TouchTool::Eraser => vec![KeyCode::BTN_TOOL_RUBBER.0],
+                },
             },
             Touchpad::CenterPad(action) => match action {
                 Touch::Motion => vec![
@@ -858,8 +1213,18 @@ fn event_codes_from_capability(capability: Capability) -> Vec<u16> {
                     TouchButton::Touch => vec![KeyCode::BTN_TOUCH.0],
                     TouchButton::Press => vec![KeyCode::BTN_LEFT.0],
                 },
+                Touch::Tilt => vec![
+                    AbsoluteAxisCode::ABS_TILT_X.0,
+                    AbsoluteAxisCode::ABS_TILT_Y.0,
+                ],
+                Touch::Tool(tool) => match tool {
+                    TouchTool::Finger => vec![KeyCode::BTN_TOOL_FINGER.0],
+                    TouchTool::Pen => vec![KeyCode::BTN_TOOL_PEN.0],
+                    TouchTool::Eraser => vec![KeyCode::BTN_TOOL_RUBBER.0],
+                },
             },
         },
+        Capability::Touchscreen(_) => vec![],
     }
 }
 
@@ -877,6 +1242,16 @@ fn input_event_from_value(
             // Convert the binary input value into an integar
             let value = if value { 1 } else { 0 };
 
+            // Binary input on a relative axis (e.g. mouse wheel buttons) is
+            // emitted as a single step in the axis direction when pressed.
+            if event_type == EventType::RELATIVE {
+                return match axis_direction {
+                    _ if value == 0 => None,
+                    Some(AxisDirection::Negative) => Some(InputEvent::new(event_type.0, code, -1)),
+                    _ => Some(InputEvent::new(event_type.0, code, 1)),
+                };
+            }
+
             // If this value is for an axis, we need to convert this value into
             // the minimum and maximum values for that axis depending on the
             // axis direction. This is typically done for DPad button input that
@@ -930,6 +1305,7 @@ fn input_event_from_value(
             is_touching: _,
             x: _,
             y: _,
+            pressure: _,
         } => None,
     };
     value?;
@@ -1,6 +1,9 @@
 pub mod dbus;
 pub mod evdev;
 pub mod native;
+pub mod network;
+#[cfg(test)]
+mod network_test;
 pub mod value;
 
 /// Events are events that flow from source devices to target devices
@@ -1,3 +1,5 @@
+use std::time::Instant;
+
 use crate::input::capability::Capability;
 
 use super::{evdev::EvdevEvent, value::InputValue};
@@ -15,6 +17,9 @@ pub struct NativeEvent {
     source_capability: Option<Capability>,
     /// The value of the input event.
     value: InputValue,
+    /// Optional time at which the input report was read from the source
+    /// device. This is used by target devices to report accurate input timing.
+    timestamp: Option<Instant>,
 }
 
 impl NativeEvent {
@@ -24,6 +29,7 @@ impl NativeEvent {
             capability,
             value,
             source_capability: None,
+            timestamp: None,
         }
     }
 
@@ -38,6 +44,7 @@ impl NativeEvent {
             capability,
             source_capability: Some(source_capability),
             value,
+            timestamp: None,
         }
     }
 
@@ -51,6 +58,11 @@ impl NativeEvent {
         self.value.clone()
     }
 
+    /// Sets the value of the event
+    pub fn set_value(&mut self, value: InputValue) {
+        self.value = value;
+    }
+
     /// Returns true if this event is a translated event and has a source
     /// capability defined.
     pub fn is_translated(&self) -> bool {
@@ -67,6 +79,16 @@ impl NativeEvent {
         self.source_capability.clone()
     }
 
+    /// Set the time at which the source device produced this event
+    pub fn set_timestamp(&mut self, timestamp: Instant) {
+        self.timestamp = Some(timestamp);
+    }
+
+    /// Returns the time at which the source device produced this event
+    pub fn get_timestamp(&self) -> Option<Instant> {
+        self.timestamp
+    }
+
     /// Returns whether or not the event is "pressed"
     pub fn pressed(&self) -> bool {
         self.value.pressed()
@@ -78,10 +100,12 @@ impl From<EvdevEvent> for NativeEvent {
     fn from(item: EvdevEvent) -> Self {
         let capability = item.as_capability();
         let value = item.get_value();
+        let timestamp = item.get_timestamp();
         NativeEvent {
             capability,
             value,
             source_capability: None,
+            timestamp,
         }
     }
 }
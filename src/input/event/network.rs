@@ -0,0 +1,246 @@
+use std::{
+    error::Error,
+    net::{IpAddr, SocketAddr},
+    str::FromStr,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+use crate::input::capability::Capability;
+
+use super::{native::NativeEvent, value::InputValue};
+
+/// Magic bytes at the start of every network packet
+const MAGIC: &[u8; 4] = b"IPNE";
+/// Version of the network protocol
+const VERSION: u8 = 2;
+/// Size of the packet header (magic, version, packet kind, and sequence)
+const HEADER_SIZE: usize = 14;
+/// Size of the value section of an event packet
+const VALUE_SIZE: usize = 27;
+/// Size of the HMAC-SHA256 tag at the end of every packet
+const TAG_SIZE: usize = 32;
+
+/// Default UDP port used to send and receive network events
+pub const DEFAULT_PORT: u16 = 31415;
+/// Environment variable with the pre-shared key used to authenticate packets
+pub const NETWORK_KEY_ENV: &str = "INPUTPLUMBER_NETWORK_KEY";
+/// Maximum age of a packet's sequence number before the packet is rejected
+/// as a replay. Requires the clocks of both instances to be in sync.
+pub const MAX_PACKET_AGE: Duration = Duration::from_secs(30);
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Returns the pre-shared key from the `INPUTPLUMBER_NETWORK_KEY` environment
+/// variable, if one is set.
+pub fn key_from_env() -> Option<Vec<u8>> {
+    let key = std::env::var(NETWORK_KEY_ENV).ok()?;
+    if key.is_empty() {
+        return None;
+    }
+    Some(key.into_bytes())
+}
+
+/// Resolve the given address (e.g. "192.168.1.10", "[::1]:31415" or
+/// "host.local") into a socket address, using the default port if none is
+/// given.
+pub async fn resolve_address(address: &str) -> Result<SocketAddr, Box<dyn Error>> {
+    if let Ok(address) = address.parse::<SocketAddr>() {
+        return Ok(address);
+    }
+    if let Ok(ip) = address.trim_matches(['[', ']']).parse::<IpAddr>() {
+        return Ok(SocketAddr::new(ip, DEFAULT_PORT));
+    }
+    let has_port = address
+        .rsplit_once(':')
+        .is_some_and(|(_, port)| port.parse::<u16>().is_ok());
+    let host = if has_port {
+        address.to_string()
+    } else {
+        format!("{address}:{DEFAULT_PORT}")
+    };
+    let Some(address) = tokio::net::lookup_host(&host).await?.next() else {
+        return Err(format!("Unable to resolve address: {host}").into());
+    };
+    Ok(address)
+}
+
+/// Returns the current time in microseconds since the Unix epoch. Sequence
+/// numbers of packets are never lower than this time when they are sent, so
+/// the receiver can reject packets that are too old.
+pub fn timestamp_micros() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|time| time.as_micros() as u64)
+        .unwrap_or_default()
+}
+
+/// Returns a new HMAC for the given pre-shared key
+fn new_mac(key: &[u8]) -> HmacSha256 {
+    // HMAC accepts keys of any length
+    HmacSha256::new_from_slice(key).expect("HMAC can take a key of any size")
+}
+
+/// A [NetworkEvent] is a packet sent between InputPlumber instances to
+/// forward input events from a composite device on one machine to a target
+/// device on another.
+///
+/// Packets are encoded as the magic bytes "IPNE", the protocol version (u8),
+/// the packet kind (u8), and the sequence number of the packet (little-endian
+/// u64). Event packets additionally contain the length of the capability
+/// string (u8), the capability string (e.g. "Gamepad:Button:South"), the
+/// value kind (u8), the x, y, and z values as little-endian f64, the touch
+/// index (u8), and whether the touch is active (u8). Missing vector values
+/// are represented as NaN. Every packet ends with an HMAC-SHA256 tag of the
+/// rest of the packet, keyed with the pre-shared key of both instances.
+#[derive(Debug, Clone)]
+pub enum NetworkEvent {
+    /// An input event to emit on the remote target device
+    Event(NativeEvent),
+    /// Sent periodically so the remote side knows the sender is still alive
+    KeepAlive,
+    /// Sent when the sender stops so the remote target device can be removed
+    Disconnect,
+}
+
+impl NetworkEvent {
+    /// Encode the event into a network packet with the given sequence number,
+    /// signed with the given pre-shared key
+    pub fn to_bytes(&self, sequence: u64, key: &[u8]) -> Vec<u8> {
+        let mut packet = Vec::with_capacity(96);
+        packet.extend_from_slice(MAGIC);
+        packet.push(VERSION);
+        packet.push(self.kind());
+        packet.extend_from_slice(&sequence.to_le_bytes());
+        if let NetworkEvent::Event(event) = self {
+            Self::write_event(&mut packet, event);
+        }
+
+        let mut mac = new_mac(key);
+        mac.update(&packet);
+        packet.extend_from_slice(&mac.finalize().into_bytes());
+
+        packet
+    }
+
+    /// Returns the packet kind of the event
+    fn kind(&self) -> u8 {
+        match self {
+            NetworkEvent::Event(_) => 0,
+            NetworkEvent::KeepAlive => 1,
+            NetworkEvent::Disconnect => 2,
+        }
+    }
+
+    /// Encode the given input event into the given packet
+    fn write_event(packet: &mut Vec<u8>, event: &NativeEvent) {
+        let capability = event.as_capability().to_capability_string();
+        let capability = &capability.as_bytes()[..capability.len().min(u8::MAX as usize)];
+        packet.push(capability.len() as u8);
+        packet.extend_from_slice(capability);
+
+        let optional = |value: Option<f64>| value.unwrap_or(f64::NAN);
+        let (kind, x, y, z, index, is_touching) = match event.get_value() {
+            InputValue::None => (0, 0.0, 0.0, 0.0, 0, false),
+            InputValue::Bool(value) => (1, value as u8 as f64, 0.0, 0.0, 0, false),
+            InputValue::Float(value) => (2, value, 0.0, 0.0, 0, false),
+            InputValue::Vector2 { x, y } => (3, optional(x), optional(y), 0.0, 0, false),
+            InputValue::Vector3 { x, y, z } => (4, optional(x), optional(y), optional(z), 0, false),
+            InputValue::Touch {
+                index,
+                is_touching,
+                x,
+                y,
+                pressure,
+            } => (
+                5,
+                optional(x),
+                optional(y),
+                optional(pressure),
+                index,
+                is_touching,
+            ),
+        };
+        packet.push(kind);
+        packet.extend_from_slice(&x.to_le_bytes());
+        packet.extend_from_slice(&y.to_le_bytes());
+        packet.extend_from_slice(&z.to_le_bytes());
+        packet.push(index);
+        packet.push(is_touching as u8);
+    }
+
+    /// Decode a network packet signed with the given pre-shared key into its
+    /// sequence number and [NetworkEvent]. Packets with an invalid signature
+    /// are rejected.
+    pub fn from_bytes(packet: &[u8], key: &[u8]) -> Result<(u64, NetworkEvent), Box<dyn Error>> {
+        if packet.len() < HEADER_SIZE + TAG_SIZE || &packet[0..4] != MAGIC {
+            return Err("Invalid network event packet".into());
+        }
+        if packet[4] != VERSION {
+            return Err(format!("Unsupported network protocol version: {}", packet[4]).into());
+        }
+        let (packet, tag) = packet.split_at(packet.len() - TAG_SIZE);
+        let mut mac = new_mac(key);
+        mac.update(packet);
+        if mac.verify_slice(tag).is_err() {
+            return Err("Invalid network event packet signature".into());
+        }
+
+        let sequence = u64::from_le_bytes(packet[6..HEADER_SIZE].try_into()?);
+        let event = match packet[5] {
+            0 => Self::read_event(&packet[HEADER_SIZE..])?,
+            1 => NetworkEvent::KeepAlive,
+            2 => NetworkEvent::Disconnect,
+            kind => return Err(format!("Invalid network packet kind: {kind}").into()),
+        };
+
+        Ok((sequence, event))
+    }
+
+    /// Decode the given event section of a packet into an input event
+    fn read_event(data: &[u8]) -> Result<NetworkEvent, Box<dyn Error>> {
+        let Some((&cap_len, data)) = data.split_first() else {
+            return Err("Network event packet is too short".into());
+        };
+        let cap_len = cap_len as usize;
+        if data.len() < cap_len + VALUE_SIZE {
+            return Err("Network event packet is too short".into());
+        }
+        let capability = std::str::from_utf8(&data[..cap_len])?;
+        let Ok(capability) = Capability::from_str(capability) else {
+            return Err(format!("Invalid capability in network event: {capability}").into());
+        };
+
+        let data = &data[cap_len..];
+        let x = f64::from_le_bytes(data[1..9].try_into()?);
+        let y = f64::from_le_bytes(data[9..17].try_into()?);
+        let z = f64::from_le_bytes(data[17..25].try_into()?);
+        let optional = |value: f64| (!value.is_nan()).then_some(value);
+        let value = match data[0] {
+            0 => InputValue::None,
+            1 => InputValue::Bool(x != 0.0),
+            2 => InputValue::Float(x),
+            3 => InputValue::Vector2 {
+                x: optional(x),
+                y: optional(y),
+            },
+            4 => InputValue::Vector3 {
+                x: optional(x),
+                y: optional(y),
+                z: optional(z),
+            },
+            5 => InputValue::Touch {
+                index: data[25],
+                is_touching: data[26] != 0,
+                x: optional(x),
+                y: optional(y),
+                pressure: optional(z),
+            },
+            kind => return Err(format!("Invalid value kind in network event: {kind}").into()),
+        };
+
+        Ok(NetworkEvent::Event(NativeEvent::new(capability, value)))
+    }
+}
@@ -0,0 +1,58 @@
+use std::error::Error;
+
+use crate::input::{
+    capability::{Capability, Gamepad, GamepadButton},
+    event::{
+        native::NativeEvent,
+        network::{resolve_address, NetworkEvent, DEFAULT_PORT},
+        value::InputValue,
+    },
+};
+
+const KEY: &[u8] = b"secret";
+
+#[test]
+fn test_network_event_round_trip() -> Result<(), Box<dyn Error>> {
+    let event = NativeEvent::new(
+        Capability::Gamepad(Gamepad::Button(GamepadButton::South)),
+        InputValue::Bool(true),
+    );
+    let packet = NetworkEvent::Event(event).to_bytes(42, KEY);
+
+    let (sequence, event) = NetworkEvent::from_bytes(&packet, KEY)?;
+    assert_eq!(sequence, 42);
+    let NetworkEvent::Event(event) = event else {
+        panic!("Expected an input event, got: {event:?}");
+    };
+    assert_eq!(
+        event.as_capability(),
+        Capability::Gamepad(Gamepad::Button(GamepadButton::South))
+    );
+    assert!(event.pressed());
+
+    Ok(())
+}
+
+#[test]
+fn test_network_event_rejects_invalid_signature() {
+    let mut packet = NetworkEvent::KeepAlive.to_bytes(1, KEY);
+    assert!(NetworkEvent::from_bytes(&packet, b"other key").is_err());
+
+    // Tampering with the sequence number invalidates the signature
+    packet[6] ^= 1;
+    assert!(NetworkEvent::from_bytes(&packet, KEY).is_err());
+}
+
+#[tokio::test]
+async fn test_resolve_address() -> Result<(), Box<dyn Error>> {
+    let address = resolve_address("127.0.0.1").await?;
+    assert_eq!(address.to_string(), format!("127.0.0.1:{DEFAULT_PORT}"));
+
+    let address = resolve_address("::1").await?;
+    assert_eq!(address.to_string(), format!("[::1]:{DEFAULT_PORT}"));
+
+    let address = resolve_address("[::1]:4000").await?;
+    assert_eq!(address.to_string(), "[::1]:4000");
+
+    Ok(())
+}
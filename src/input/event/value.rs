@@ -1,6 +1,6 @@
 use crate::{
     config::CapabilityConfig,
-    input::capability::{Capability, Gamepad, Mouse, Touchpad},
+    input::capability::{Capability, Gamepad, Mouse, MouseButton, Touch, Touchpad},
 };
 
 /// Possible errors while doing input value translation
@@ -37,6 +37,8 @@ pub enum InputValue {
         is_touching: bool,
         x: Option<f64>,
         y: Option<f64>,
+        /// Pressure of the touch or stylus from 0.0 to 1.0, if supported
+        pressure: Option<f64>,
     },
 }
 
@@ -54,6 +56,7 @@ impl InputValue {
                 is_touching: pressed,
                 x: _,
                 y: _,
+                pressure: _,
             } => *pressed,
         }
     }
@@ -81,46 +84,7 @@ impl InputValue {
             Capability::Gamepad(gamepad) => {
                 match gamepad {
                     // Gamepad Button -> ...
-                    Gamepad::Button(_) => {
-                        match target_cap {
-                            // Gamepad Button -> None
-                            Capability::None => Ok(InputValue::None),
-                            // Gamepad Button -> NotImplemented
-                            Capability::NotImplemented => Ok(InputValue::None),
-                            // Gamepad Button -> Sync
-                            Capability::Sync => Ok(InputValue::Bool(false)),
-                            // Gamepad Button -> DBus
-                            Capability::DBus(_) => Ok(self.clone()),
-                            // Gamepad Button -> Gamepad
-                            Capability::Gamepad(gamepad) => match gamepad {
-                                // Gamepad Button -> Gamepad Button
-                                Gamepad::Button(_) => Ok(self.clone()),
-                                // Gamepad Button -> Axis
-                                Gamepad::Axis(_) => self.translate_button_to_axis(target_config),
-                                // Gamepad Button -> Trigger
-                                Gamepad::Trigger(_) => Ok(self.translate_button_to_trigger()),
-                                // Gamepad Button -> Accelerometer
-                                Gamepad::Accelerometer => Err(TranslationError::NotImplemented),
-                                // Gamepad Button -> Gyro
-                                Gamepad::Gyro => Err(TranslationError::NotImplemented),
-                            },
-                            // Gamepad Button -> Mouse
-                            Capability::Mouse(mouse) => match mouse {
-                                // Gamepad Button -> Mouse Motion
-                                Mouse::Motion => Err(TranslationError::NotImplemented),
-                                // Gamepad Button -> Mouse Button
-                                Mouse::Button(_) => Ok(self.clone()),
-                            },
-                            // Gamepad Button -> Keyboard
-                            Capability::Keyboard(_) => Ok(self.clone()),
-                            // Gamepad Button -> Touchpad
-                            Capability::Touchpad(touch) => match touch {
-                                Touchpad::LeftPad(_) => Err(TranslationError::NotImplemented),
-                                Touchpad::RightPad(_) => Err(TranslationError::NotImplemented),
-                                Touchpad::CenterPad(_) => Err(TranslationError::NotImplemented),
-                            },
-                        }
-                    }
+                    Gamepad::Button(_) => self.translate_button(target_cap, target_config),
                     // Axis -> ...
                     Gamepad::Axis(_) => {
                         match target_cap {
@@ -161,6 +125,8 @@ impl InputValue {
                                 Touchpad::RightPad(_) => Err(TranslationError::NotImplemented),
                                 Touchpad::CenterPad(_) => Err(TranslationError::NotImplemented),
                             },
+                            // Axis -> Touchscreen
+                            Capability::Touchscreen(_) => Err(TranslationError::NotImplemented),
                         }
                     }
                     // Trigger -> ...
@@ -201,6 +167,8 @@ impl InputValue {
                             Touchpad::RightPad(_) => Err(TranslationError::NotImplemented),
                             Touchpad::CenterPad(_) => Err(TranslationError::NotImplemented),
                         },
+                        // Trigger -> Touchscreen
+                        Capability::Touchscreen(_) => Err(TranslationError::NotImplemented),
                     },
                     // Accelerometer -> ...
                     Gamepad::Accelerometer => Err(TranslationError::NotImplemented),
@@ -209,11 +177,83 @@ impl InputValue {
                 }
             }
             // Mouse -> ...
-            Capability::Mouse(_) => Err(TranslationError::NotImplemented),
+            Capability::Mouse(mouse) => match mouse {
+                // Mouse Motion -> ...
+                Mouse::Motion => Err(TranslationError::NotImplemented),
+                // Mouse Button -> ...
+                Mouse::Button(_) => self.translate_button(target_cap, target_config),
+            },
             // Keyboard -> ...
-            Capability::Keyboard(_) => Err(TranslationError::NotImplemented),
+            Capability::Keyboard(_) => self.translate_button(target_cap, target_config),
             // Touchpad -> ...
-            Capability::Touchpad(_) => Err(TranslationError::NotImplemented),
+            Capability::Touchpad(touchpad) => match touchpad {
+                Touchpad::LeftPad(Touch::Motion)
+                | Touchpad::RightPad(Touch::Motion)
+                | Touchpad::CenterPad(Touch::Motion) => match target_cap {
+                    // Touchpad Motion -> Mouse Wheel
+                    // The touch value is passed through so the target can
+                    // scroll smoothly based on how far the touch moved.
+                    Capability::Mouse(Mouse::Button(
+                        MouseButton::WheelUp
+                        | MouseButton::WheelDown
+                        | MouseButton::WheelLeft
+                        | MouseButton::WheelRight,
+                    )) => Ok(self.clone()),
+                    _ => Err(TranslationError::NotImplemented),
+                },
+                _ => Err(TranslationError::NotImplemented),
+            },
+            // Touchscreen -> ...
+            Capability::Touchscreen(_) => Err(TranslationError::NotImplemented),
+        }
+    }
+
+    /// Translate a binary button value (e.g. a gamepad button, keyboard key,
+    /// or mouse button) into the given target capability
+    pub fn translate_button(
+        &self,
+        target_cap: &Capability,
+        target_config: &CapabilityConfig,
+    ) -> Result<InputValue, TranslationError> {
+        match target_cap {
+            // Button -> None
+            Capability::None => Ok(InputValue::None),
+            // Button -> NotImplemented
+            Capability::NotImplemented => Ok(InputValue::None),
+            // Button -> Sync
+            Capability::Sync => Ok(InputValue::Bool(false)),
+            // Button -> DBus
+            Capability::DBus(_) => Ok(self.clone()),
+            // Button -> Gamepad
+            Capability::Gamepad(gamepad) => match gamepad {
+                // Button -> Gamepad Button
+                Gamepad::Button(_) => Ok(self.clone()),
+                // Button -> Axis
+                Gamepad::Axis(_) => self.translate_button_to_axis(target_config),
+                // Button -> Trigger
+                Gamepad::Trigger(_) => Ok(self.translate_button_to_trigger()),
+                // Button -> Accelerometer
+                Gamepad::Accelerometer => Err(TranslationError::NotImplemented),
+                // Button -> Gyro
+                Gamepad::Gyro => Err(TranslationError::NotImplemented),
+            },
+            // Button -> Mouse
+            Capability::Mouse(mouse) => match mouse {
+                // Button -> Mouse Motion
+                Mouse::Motion => Err(TranslationError::NotImplemented),
+                // Button -> Mouse Button
+                Mouse::Button(_) => Ok(self.clone()),
+            },
+            // Button -> Keyboard
+            Capability::Keyboard(_) => Ok(self.clone()),
+            // Button -> Touchpad
+            Capability::Touchpad(touch) => match touch {
+                Touchpad::LeftPad(_) => Err(TranslationError::NotImplemented),
+                Touchpad::RightPad(_) => Err(TranslationError::NotImplemented),
+                Touchpad::CenterPad(_) => Err(TranslationError::NotImplemented),
+            },
+            // Button -> Touchscreen
+            Capability::Touchscreen(_) => Err(TranslationError::NotImplemented),
         }
     }
 
@@ -0,0 +1,90 @@
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+use crate::config::EventFilterConfig;
+
+use super::{
+    capability::{Capability, Gamepad},
+    event::{native::NativeEvent, value::InputValue},
+};
+
+/// Default minimum time between chatter events of an axis
+const DEFAULT_CHATTER_INTERVAL_MS: u64 = 50;
+
+/// An [EventFilter] suppresses axis and trigger events that would not change
+/// the state of target devices, to reduce wakeups of games and DBus clients.
+/// Consecutive identical values are always dropped, while small value changes
+/// (chatter) are only let through at a limited rate.
+#[derive(Debug)]
+pub struct EventFilter {
+    threshold: f64,
+    interval: Duration,
+    /// Last value emitted for each capability and when it was emitted
+    last: HashMap<Capability, (InputValue, Instant)>,
+}
+
+impl EventFilter {
+    /// Create a new event filter from the given config
+    pub fn new(config: &EventFilterConfig) -> Self {
+        let interval = config
+            .chatter_interval_ms
+            .unwrap_or(DEFAULT_CHATTER_INTERVAL_MS);
+        Self {
+            threshold: config.chatter_threshold.unwrap_or(0.0).max(0.0),
+            interval: Duration::from_millis(interval),
+            last: HashMap::new(),
+        }
+    }
+
+    /// Returns whether or not the given event should be written to target
+    /// devices.
+    pub fn process(&mut self, event: &NativeEvent) -> bool {
+        let cap = event.as_capability();
+        if !matches!(
+            cap,
+            Capability::Gamepad(Gamepad::Axis(_)) | Capability::Gamepad(Gamepad::Trigger(_))
+        ) {
+            return true;
+        }
+        let value = event.get_value();
+        let now = Instant::now();
+        if let Some((last_value, last_time)) = self.last.get(&cap) {
+            match difference(last_value, &value) {
+                Some(diff) if diff == 0.0 => return false,
+                Some(diff) if diff < self.threshold => {
+                    if now.duration_since(*last_time) < self.interval {
+                        return false;
+                    }
+                }
+                _ => (),
+            }
+        }
+        self.last.insert(cap, (value, now));
+        true
+    }
+
+    /// Forget the last emitted values, so the next event of every axis is
+    /// written. This should be called when target devices change.
+    pub fn clear(&mut self) {
+        self.last.clear();
+    }
+}
+
+/// Returns the largest change between the two values, or `None` if they
+/// cannot be compared.
+fn difference(old: &InputValue, new: &InputValue) -> Option<f64> {
+    match (old, new) {
+        (InputValue::Float(old), InputValue::Float(new)) => Some((new - old).abs()),
+        (InputValue::Vector2 { x: x0, y: y0 }, InputValue::Vector2 { x: x1, y: y1 }) => {
+            let axis_diff = |old: &Option<f64>, new: &Option<f64>| match (old, new) {
+                (Some(old), Some(new)) => Some((new - old).abs()),
+                (_, None) => Some(0.0),
+                (None, Some(_)) => None,
+            };
+            Some(axis_diff(x0, x1)?.max(axis_diff(y0, y1)?))
+        }
+        _ => None,
+    }
+}
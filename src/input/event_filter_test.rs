@@ -0,0 +1,26 @@
+use crate::{
+    config::EventFilterConfig,
+    input::{
+        capability::{Capability, Gamepad, GamepadTrigger},
+        event::{native::NativeEvent, value::InputValue},
+        event_filter::EventFilter,
+    },
+};
+
+#[test]
+fn test_event_filter() {
+    let mut filter = EventFilter::new(&EventFilterConfig {
+        chatter_threshold: Some(0.05),
+        chatter_interval_ms: Some(60_000),
+    });
+    let cap = Capability::Gamepad(Gamepad::Trigger(GamepadTrigger::LeftTrigger));
+    let event = |value| NativeEvent::new(cap.clone(), InputValue::Float(value));
+
+    assert!(filter.process(&event(0.5)));
+    assert!(!filter.process(&event(0.5)));
+    assert!(!filter.process(&event(0.52)));
+    assert!(filter.process(&event(0.6)));
+
+    filter.clear();
+    assert!(filter.process(&event(0.6)));
+}
@@ -0,0 +1,163 @@
+use std::{
+    error::Error,
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Directory containing all LED class devices
+const LEDS_PATH: &str = "/sys/class/leds";
+
+/// An LED class device (e.g. /sys/class/leds/playstation::00:11:22:33:44:55::rgb)
+/// that belongs to a source device, such as the player LEDs or light ring of
+/// a controller.
+#[derive(Debug, Clone)]
+pub struct Led {
+    pub name: String,
+    path: PathBuf,
+    max_brightness: u32,
+    /// Color channels of a multicolor LED (e.g. ["red", "green", "blue"])
+    color_channels: Vec<String>,
+}
+
+impl Led {
+    /// Create a new [Led] from the given LED class device path
+    pub fn new(path: &Path) -> Result<Self, Box<dyn Error>> {
+        let Some(name) = path.file_name() else {
+            return Err(format!("Invalid LED path: {}", path.display()).into());
+        };
+        let max_brightness = fs::read_to_string(path.join("max_brightness"))?
+            .trim()
+            .parse()?;
+        let color_channels = fs::read_to_string(path.join("multi_index"))
+            .map(|index| index.split_whitespace().map(String::from).collect())
+            .unwrap_or_default();
+
+        Ok(Self {
+            name: name.to_string_lossy().to_string(),
+            path: path.to_path_buf(),
+            max_brightness,
+            color_channels,
+        })
+    }
+
+    /// Returns true if this LED can change color
+    pub fn is_multicolor(&self) -> bool {
+        !self.color_channels.is_empty()
+    }
+
+    /// Returns the function of the LED from its "devicename:color:function"
+    /// name (e.g. "player-1" or "micmute")
+    pub fn function(&self) -> &str {
+        self.name.rsplit(':').next().unwrap_or_default()
+    }
+
+    /// Turn player indicator LEDs (e.g. input5:white:player-1) on or off based
+    /// on the given bit mask, where bit 0 is the first player light. Other
+    /// LEDs are ignored.
+    pub fn set_player(&self, lights: u8) -> Result<(), Box<dyn Error>> {
+        let Some(number) = self.function().strip_prefix("player-") else {
+            return Ok(());
+        };
+        let Ok(number) = number.parse::<u8>() else {
+            return Ok(());
+        };
+        let is_on = number > 0 && number <= 8 && lights & (1 << (number - 1)) != 0;
+        self.set_brightness(if is_on { 1.0 } else { 0.0 })
+    }
+
+    /// Turn a mute indicator LED (e.g. input5:white:micmute) on or off. Other
+    /// LEDs are ignored.
+    pub fn set_mute(&self, is_on: bool) -> Result<(), Box<dyn Error>> {
+        if !self.function().contains("mute") {
+            return Ok(());
+        }
+        self.set_brightness(if is_on { 1.0 } else { 0.0 })
+    }
+
+    /// Returns the current brightness of the LED from 0.0 to 1.0
+    pub fn brightness(&self) -> Result<f64, Box<dyn Error>> {
+        let value: u32 = fs::read_to_string(self.path.join("brightness"))?
+            .trim()
+            .parse()?;
+        Ok(value as f64 / self.max_brightness.max(1) as f64)
+    }
+
+    /// Set the brightness of the LED from 0.0 to 1.0
+    pub fn set_brightness(&self, brightness: f64) -> Result<(), Box<dyn Error>> {
+        let value = (brightness.clamp(0.0, 1.0) * self.max_brightness as f64).round() as u32;
+        fs::write(self.path.join("brightness"), value.to_string())?;
+        Ok(())
+    }
+
+    /// Set the color of a multicolor LED. Single color LEDs are ignored.
+    pub fn set_color(&self, red: u8, green: u8, blue: u8) -> Result<(), Box<dyn Error>> {
+        if !self.is_multicolor() {
+            return Ok(());
+        }
+        let intensities: Vec<String> = self
+            .color_channels
+            .iter()
+            .map(|channel| match channel.as_str() {
+                "red" => red,
+                "green" => green,
+                "blue" => blue,
+                _ => 0,
+            })
+            .map(|value| value.to_string())
+            .collect();
+        fs::write(self.path.join("multi_intensity"), intensities.join(" "))?;
+        Ok(())
+    }
+}
+
+/// Returns all LED class devices that belong to the physical device of the
+/// given source device node (e.g. /dev/hidraw0 or /dev/input/event3). Keyboard
+/// LEDs of input devices (e.g. input3::capslock) are not included since they
+/// are managed by the kernel, but player and mute indicators of input devices
+/// (e.g. input5:white:player-1) are.
+pub fn find_leds(device_path: &str) -> Vec<Led> {
+    let Some(root) = get_sysfs_root(device_path) else {
+        return vec![];
+    };
+    let Ok(entries) = fs::read_dir(LEDS_PATH) else {
+        return vec![];
+    };
+
+    let mut leds = Vec::new();
+    for entry in entries.filter_map(|entry| entry.ok()) {
+        let name = entry.file_name().to_string_lossy().to_string();
+        if name.starts_with("input") && name.contains("::") {
+            continue;
+        }
+        let Ok(parent) = fs::canonicalize(entry.path().join("device")) else {
+            continue;
+        };
+        if !parent.starts_with(&root) {
+            continue;
+        }
+        match Led::new(&entry.path()) {
+            Ok(led) => {
+                log::debug!("Found LED {name} for {device_path}");
+                leds.push(led);
+            }
+            Err(e) => log::debug!("Failed to read LED {name}: {e:?}"),
+        }
+    }
+    leds
+}
+
+/// Returns the sysfs path of the physical device (e.g. the HID device) of the
+/// given device node.
+fn get_sysfs_root(device_path: &str) -> Option<PathBuf> {
+    let name = Path::new(device_path).file_name()?.to_string_lossy();
+    let link = if name.starts_with("hidraw") {
+        format!("/sys/class/hidraw/{name}/device")
+    } else if name.starts_with("event") {
+        // The parent of the event device is the input device (e.g. input3),
+        // so use its parent.
+        format!("/sys/class/input/{name}/device/device")
+    } else {
+        return None;
+    };
+    fs::canonicalize(link).ok()
+}
@@ -1,43 +1,76 @@
 use std::collections::HashMap;
+use std::collections::HashSet;
 use std::error::Error;
 use std::fs;
 use std::time::Duration;
+use std::time::Instant;
 
 use thiserror::Error;
 use tokio::sync::broadcast;
 use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
 use zbus::zvariant::ObjectPath;
 use zbus::Connection;
 
 use crate::config::CapabilityMap;
 use crate::config::CompositeDeviceConfig;
+use crate::config::CustomTargetConfig;
+use crate::config::SessionTargetDevicesConfig;
 use crate::config::SourceDevice;
 use crate::constants::BUS_PREFIX;
 use crate::constants::BUS_TARGETS_PREFIX;
+use crate::dbus::interface::battery::BatteryInterface;
 use crate::dbus::interface::composite_device::CompositeDeviceInterface;
+use crate::dbus::interface::manager::emit_device_error;
+use crate::dbus::interface::manager::DeviceErrorKind;
 use crate::dbus::interface::manager::ManagerInterface;
+use crate::dbus::interface::sensor_proxy;
 use crate::dbus::interface::source::evdev::SourceEventDeviceInterface;
 use crate::dbus::interface::source::hidraw::SourceHIDRawInterface;
 use crate::dmi::data::DMIData;
 use crate::dmi::get_dmi_data;
+use crate::dock::DockWatcher;
+use crate::drivers::replay::ReplayDevice;
+#[cfg(feature = "x11")]
+use crate::gamescope::FocusWatcher;
 use crate::iio;
+use crate::input::auto_profile;
+use crate::input::auto_profile::FocusedApp;
 use crate::input::composite_device;
 use crate::input::composite_device::CompositeDevice;
+use crate::input::player::PlayerSlots;
 use crate::input::source;
 use crate::input::source::hidraw;
+use crate::input::source::network::NetworkListener;
+use crate::input::target::custom::CustomGamepad;
 use crate::input::target::dbus::DBusDevice;
 use crate::input::target::dualsense;
 use crate::input::target::dualsense::DualSenseDevice;
 use crate::input::target::dualsense::DualSenseHardware;
 use crate::input::target::gamepad::GenericGamepad;
+use crate::input::target::identity::TargetIdentities;
 use crate::input::target::keyboard::KeyboardDevice;
 use crate::input::target::mouse::MouseDevice;
+use crate::input::target::network::NetworkDevice;
 use crate::input::target::steam_deck::SteamDeckDevice;
+use crate::input::target::switch_pro::SwitchProDevice;
+use crate::input::target::tablet::TabletDevice;
+use crate::input::target::touchpad::TouchpadDevice;
+use crate::input::target::touchscreen::TouchscreenDevice;
 use crate::input::target::xb360::XBox360Controller;
+use crate::input::target::xbox_elite::XboxEliteController;
 use crate::input::target::TargetDeviceType;
+use crate::logger;
+use crate::metrics;
+use crate::metrics::MetricsExporter;
 use crate::procfs;
+use crate::session::SessionType;
+use crate::session::SessionWatcher;
+use crate::udev::unhide_device;
 use crate::watcher;
 use crate::watcher::WatchEvent;
+#[cfg(feature = "websocket")]
+use crate::websocket::WebSocketServer;
 
 use super::target::TargetCommand;
 
@@ -45,6 +78,11 @@ const DEV_PATH: &str = "/dev";
 const INPUT_PATH: &str = "/dev/input";
 const IIO_PATH: &str = "/sys/bus/iio/devices";
 const BUFFER_SIZE: usize = 1024;
+/// Time to wait before restarting a composite device that crashed
+const RESTART_DELAY: Duration = Duration::from_secs(1);
+/// Default time a source device must stay connected before a new composite
+/// device is created for it
+const DEFAULT_HOTPLUG_DEBOUNCE_MS: u64 = 250;
 
 #[derive(Error, Debug)]
 pub enum ManagerError {
@@ -66,6 +104,11 @@ pub enum ManagerCommand {
     SourceDeviceRemoved {
         id: String,
     },
+    SourceDeviceSettled {
+        id: String,
+        info: SourceDeviceInfo,
+        appeared_at: Instant,
+    },
     EventDeviceAdded {
         name: String,
     },
@@ -89,8 +132,18 @@ pub enum ManagerCommand {
     },
     CreateTargetDevice {
         kind: String,
+        serial: Option<String>,
+        /// Player number of the composite device the target is created for,
+        /// used to give the target a deterministic DBus path
+        player: Option<u8>,
+        /// Definition of the virtual device if the kind is "custom"
+        custom: Option<CustomTargetConfig>,
         sender: mpsc::Sender<Result<String, ManagerError>>,
     },
+    CreateRemoteTargetDevice {
+        kind: String,
+        sender: mpsc::Sender<Result<(String, mpsc::Sender<TargetCommand>), ManagerError>>,
+    },
     StopTargetDevice {
         path: String,
     },
@@ -102,7 +155,26 @@ pub enum ManagerCommand {
     TargetDeviceStopped {
         path: String,
     },
+    TargetDeviceCrashed {
+        path: String,
+    },
     CompositeDeviceStopped(String),
+    CompositeDeviceCrashed(String),
+    GetCompositeDevices {
+        sender: mpsc::Sender<HashMap<String, mpsc::Sender<composite_device::Command>>>,
+    },
+    GetTargetDevices {
+        sender: mpsc::Sender<HashMap<String, String>>,
+    },
+    FocusedAppChanged {
+        app: FocusedApp,
+    },
+    DockStateChanged {
+        docked: bool,
+    },
+    SessionTypeChanged {
+        session: SessionType,
+    },
 }
 
 /// Information used to create a source device
@@ -113,6 +185,78 @@ pub enum SourceDeviceInfo {
     IIODeviceInfo(iio::device::Device),
 }
 
+impl SourceDeviceInfo {
+    /// Returns the serial number or unique id (e.g. MAC address) of the source
+    /// device if one is available.
+    pub fn get_serial(&self) -> Option<String> {
+        let serial = match self {
+            SourceDeviceInfo::EvdevDeviceInfo(info) => info.unique_id.clone(),
+            SourceDeviceInfo::HIDRawDeviceInfo(info) => {
+                info.serial_number().unwrap_or_default().to_string()
+            }
+            SourceDeviceInfo::IIODeviceInfo(_) => return None,
+        };
+        let serial = serial.trim().to_string();
+        if serial.is_empty() {
+            return None;
+        }
+        Some(serial)
+    }
+
+    /// Returns the vendor and product id of the source device if available
+    pub fn get_vendor_product(&self) -> Option<(u16, u16)> {
+        match self {
+            SourceDeviceInfo::EvdevDeviceInfo(info) => {
+                let vendor = u16::from_str_radix(info.id.vendor.as_str(), 16).ok()?;
+                let product = u16::from_str_radix(info.id.product.as_str(), 16).ok()?;
+                Some((vendor, product))
+            }
+            SourceDeviceInfo::HIDRawDeviceInfo(info) => Some((info.vendor_id(), info.product_id())),
+            SourceDeviceInfo::IIODeviceInfo(_) => None,
+        }
+    }
+
+    /// Returns the location of the physical device that the source device
+    /// belongs to, used to tell apart identical devices without a serial
+    /// number. For event devices this is the phys path without the
+    /// interface (e.g. "usb-0000:00:14.0-9"). For hidraw devices this is the
+    /// sysfs path of the USB device (e.g. "/sys/devices/.../usb1/1-9").
+    pub fn get_physical_path(&self) -> Option<String> {
+        let path = match self {
+            SourceDeviceInfo::EvdevDeviceInfo(info) => info
+                .phys_path
+                .rsplit_once('/')
+                .map(|(device, _)| device.to_string())
+                .unwrap_or_else(|| info.phys_path.clone()),
+            SourceDeviceInfo::HIDRawDeviceInfo(info) => {
+                let name = info.path().to_string_lossy();
+                let name = name.rsplit('/').next().unwrap_or_default();
+                let link = format!("/sys/class/hidraw/{name}/device");
+                // The HID device's parent is the USB interface, so use its
+                // parent.
+                let path = fs::canonicalize(link).ok()?;
+                path.ancestors().nth(2)?.to_string_lossy().to_string()
+            }
+            SourceDeviceInfo::IIODeviceInfo(_) => return None,
+        };
+        if path.is_empty() {
+            return None;
+        }
+        Some(path)
+    }
+
+    /// Returns true if the source device is connected over Bluetooth
+    pub fn is_bluetooth(&self) -> bool {
+        match self {
+            SourceDeviceInfo::EvdevDeviceInfo(info) => info.id.bus_type == "0005",
+            SourceDeviceInfo::HIDRawDeviceInfo(info) => {
+                info.bus_type() == hidapi::BusType::Bluetooth
+            }
+            SourceDeviceInfo::IIODeviceInfo(_) => false,
+        }
+    }
+}
+
 /// Manages input devices
 ///
 /// The [Manager] discovers input devices and interepts their input so
@@ -162,6 +306,35 @@ pub struct Manager {
     /// Mapping of target devices to their respective handles
     /// E.g. {"/org/shadowblip/InputPlumber/devices/target/dbus0": <Handle>}
     target_devices: HashMap<String, mpsc::Sender<TargetCommand>>,
+    /// Mapping of source devices to their serial number and whether or not
+    /// they are connected over Bluetooth. Used to detect the same physical
+    /// device connected over both USB and Bluetooth.
+    /// E.g. {"evdev://event0": ("01:23:45:67:89:ab", true)}
+    source_device_connections: HashMap<String, (String, bool)>,
+    /// Mapping of source devices to the information used to create them. Used
+    /// to re-add source devices when restarting a crashed composite device.
+    /// E.g. {"evdev://event0": <SourceDeviceInfo>}
+    source_device_info: HashMap<String, SourceDeviceInfo>,
+    /// Set of composite device DBus paths that should have their source
+    /// devices re-added after they stop.
+    composite_devices_restarting: HashSet<String>,
+    /// Source devices waiting for the hotplug debounce time to pass before a
+    /// composite device is created for them, with the time they appeared.
+    /// E.g. {"evdev://event0": <Instant>}
+    source_devices_pending: HashMap<String, Instant>,
+    /// App currently focused in gamescope, used to automatically select
+    /// device profiles
+    focused_app: FocusedApp,
+    /// Whether an external display is connected, used to automatically select
+    /// device profiles
+    docked: bool,
+    /// Type of the active session, used to select the default target devices
+    session: SessionType,
+    /// Player numbers of composite devices, remembered by controller serial
+    player_slots: PlayerSlots,
+    /// Stored serial numbers of target devices for source devices without a
+    /// serial number
+    target_identities: TargetIdentities,
 }
 
 impl Manager {
@@ -186,6 +359,15 @@ impl Manager {
             used_configs: HashMap::new(),
             composite_device_sources: HashMap::new(),
             composite_device_targets: HashMap::new(),
+            source_device_connections: HashMap::new(),
+            source_device_info: HashMap::new(),
+            composite_devices_restarting: HashSet::new(),
+            source_devices_pending: HashMap::new(),
+            focused_app: FocusedApp::default(),
+            docked: false,
+            session: SessionType::Unknown,
+            player_slots: PlayerSlots::load(),
+            target_identities: TargetIdentities::load(),
         }
     }
 
@@ -198,6 +380,58 @@ impl Manager {
         // Create a DBus interface
         self.listen_on_dbus().await?;
 
+        // Listen for remote devices if enabled
+        if let Some(mut listener) = NetworkListener::from_env(self.tx.clone()) {
+            tokio::spawn(async move {
+                if let Err(e) = listener.run().await {
+                    log::error!("Failed to listen for remote devices: {e:?}");
+                }
+            });
+        }
+
+        // Watch for the focused app in gamescope to automatically load profiles
+        #[cfg(feature = "x11")]
+        FocusWatcher::new(self.tx.clone()).spawn();
+
+        // Watch for external displays if any profiles are loaded when docked
+        if auto_profile::uses_dock_state() {
+            DockWatcher::new(self.tx.clone()).spawn();
+        }
+
+        // Watch for the active session type if any configs switch default
+        // target devices based on it
+        let configs = self.load_device_configs().await;
+        if configs
+            .iter()
+            .any(|config| config.session_target_devices.is_some())
+        {
+            SessionWatcher::new(self.tx.clone(), self.dbus.clone()).spawn();
+        }
+
+        // Create any virtual handhelds that replay recorded report streams
+        for device in ReplayDevice::load_all() {
+            device.spawn();
+        }
+
+        // Start the WebSocket API if enabled
+        #[cfg(feature = "websocket")]
+        if let Some(server) = WebSocketServer::from_env(self.tx.clone()) {
+            tokio::spawn(async move {
+                if let Err(e) = server.run().await {
+                    log::error!("Failed to run WebSocket server: {e:?}");
+                }
+            });
+        }
+
+        // Export input health metrics if enabled
+        if let Some(exporter) = MetricsExporter::from_env() {
+            tokio::spawn(async move {
+                if let Err(e) = exporter.run().await {
+                    log::error!("Failed to run metrics exporter: {e:?}");
+                }
+            });
+        }
+
         // Loop and listen for command events
         while let Ok(cmd) = self.rx.recv().await {
             log::debug!("Received command: {:?}", cmd);
@@ -238,7 +472,7 @@ impl Manager {
                     }
                 }
                 ManagerCommand::SourceDeviceAdded { id, info } => {
-                    if let Err(e) = self.on_source_device_added(id, info).await {
+                    if let Err(e) = self.on_source_device_added(id, info, false).await {
                         log::error!("Error handling added source device: {:?}", e);
                     }
                 }
@@ -247,14 +481,43 @@ impl Manager {
                         log::error!("Error handling removed source device: {:?}", e);
                     }
                 }
+                ManagerCommand::SourceDeviceSettled {
+                    id,
+                    info,
+                    appeared_at,
+                } => {
+                    // Ignore devices that were removed or re-added since
+                    if self.source_devices_pending.get(&id) != Some(&appeared_at) {
+                        continue;
+                    }
+                    self.source_devices_pending.remove(&id);
+                    if let Err(e) = self.on_source_device_added(id, info, true).await {
+                        log::error!("Error handling added source device: {:?}", e);
+                    }
+                }
                 ManagerCommand::CompositeDeviceStopped(path) => {
                     if let Err(e) = self.on_composite_device_stopped(path).await {
                         log::error!("Error handling stopped composite device: {:?}", e);
                     }
                 }
-                ManagerCommand::CreateTargetDevice { kind, sender } => {
+                ManagerCommand::CompositeDeviceCrashed(path) => {
+                    self.composite_devices_restarting.insert(path.clone());
+                    if let Err(e) = self.on_composite_device_stopped(path).await {
+                        log::error!("Error handling crashed composite device: {:?}", e);
+                    }
+                }
+                ManagerCommand::CreateTargetDevice {
+                    kind,
+                    serial,
+                    player,
+                    custom,
+                    sender,
+                } => {
                     // Create the target device
-                    let device = match self.create_and_start_target_device(kind.as_str()).await {
+                    let device = match self
+                        .create_and_start_target_device(kind.as_str(), serial, player, custom)
+                        .await
+                    {
                         Ok(device) => device,
                         Err(err) => {
                             if let Err(e) = sender.send(Err(err)).await {
@@ -277,6 +540,43 @@ impl Manager {
                         log::error!("Failed to send response: {e:?}");
                     }
                 }
+                ManagerCommand::DockStateChanged { docked } => {
+                    self.on_dock_state_changed(docked).await;
+                }
+                ManagerCommand::SessionTypeChanged { session } => {
+                    self.on_session_type_changed(session).await;
+                }
+                ManagerCommand::FocusedAppChanged { app } => {
+                    self.on_focused_app_changed(app).await;
+                }
+                ManagerCommand::GetCompositeDevices { sender } => {
+                    if let Err(e) = sender.send(self.composite_devices.clone()).await {
+                        log::error!("Failed to send composite devices: {e:?}");
+                    }
+                }
+                ManagerCommand::GetTargetDevices { sender } => {
+                    if let Err(e) = sender.send(self.get_target_device_mapping()).await {
+                        log::error!("Failed to send target devices: {e:?}");
+                    }
+                }
+                ManagerCommand::CreateRemoteTargetDevice { kind, sender } => {
+                    // Create the target device
+                    let response = match self
+                        .create_and_start_target_device(kind.as_str(), None, None, None)
+                        .await
+                    {
+                        Ok(device) => device.into_iter().next().ok_or_else(|| {
+                            ManagerError::CreateTargetDeviceFailed(
+                                "Unable to find device path".to_string(),
+                            )
+                        }),
+                        Err(err) => Err(err),
+                    };
+
+                    if let Err(e) = sender.send(response).await {
+                        log::error!("Failed to send response: {e:?}");
+                    }
+                }
                 ManagerCommand::AttachTargetDevice {
                     target_path,
                     composite_path,
@@ -303,6 +603,15 @@ impl Manager {
                         continue;
                     };
 
+                    // Keep track of the target devices used by the composite device
+                    let paths = self
+                        .composite_device_targets
+                        .entry(composite_path.clone())
+                        .or_default();
+                    if !paths.contains(&target_path) {
+                        paths.push(target_path.clone());
+                    }
+
                     // Send the attach command to the composite device
                     let mut targets = HashMap::new();
                     targets.insert(target_path.clone(), target.clone());
@@ -329,8 +638,20 @@ impl Manager {
                 ManagerCommand::TargetDeviceStopped { path } => {
                     log::debug!("Target device stopped: {path}");
                     self.target_devices.remove(&path);
+                    for paths in self.composite_device_targets.values_mut() {
+                        paths.retain(|target_path| target_path != &path);
+                    }
+                }
+                ManagerCommand::TargetDeviceCrashed { path } => {
+                    self.on_target_device_crashed(path).await;
                 }
             }
+
+            metrics::set_device_counts(
+                self.composite_devices.len(),
+                self.source_devices_used.len(),
+                self.target_devices.len(),
+            );
         }
 
         log::info!("Stopped input manager");
@@ -361,6 +682,14 @@ impl Manager {
             None
         };
 
+        // Publish the orientation of the built-in accelerometer if the
+        // config opted in to screen auto-rotation
+        if config.screen_orientation.unwrap_or_default() {
+            if let Err(e) = sensor_proxy::start(&self.dbus).await {
+                log::error!("Failed to start sensor proxy interface: {e:?}");
+            }
+        }
+
         // Create a composite device to manage these devices
         log::info!("Found matching source devices: {:?}", config.name);
         let config = config.clone();
@@ -393,17 +722,32 @@ impl Manager {
     }
 
     /// Create target input device to emulate based on the given device type.
+    /// If a source device serial is given, it will be used as the unique id of
+    /// target devices that support it. The "custom" device type is defined by
+    /// the given custom target config.
     async fn create_target_device(
         &mut self,
         kind: &str,
+        serial: Option<String>,
+        custom: Option<CustomTargetConfig>,
     ) -> Result<TargetDeviceType, Box<dyn Error>> {
         log::debug!("Creating target device: {kind}");
         // Create the target device to emulate based on the kind
         let device = match kind {
             "gamepad" => TargetDeviceType::GenericGamepad(GenericGamepad::new(self.dbus.clone())),
-            "deck" => TargetDeviceType::SteamDeck(SteamDeckDevice::new(self.dbus.clone())),
+            "custom" => TargetDeviceType::Custom(CustomGamepad::new(
+                self.dbus.clone(),
+                custom.unwrap_or_default(),
+            )),
+            "deck" => {
+                let mut device = SteamDeckDevice::new(self.dbus.clone());
+                if let Some(serial) = serial {
+                    device.set_serial(serial);
+                }
+                TargetDeviceType::SteamDeck(device)
+            }
             "ds5" | "ds5-usb" | "ds5-bt" | "ds5-edge" | "ds5-edge-usb" | "ds5-edge-bt" => {
-                let hw = match kind {
+                let mut hw = match kind {
                     "ds5" | "ds5-usb" => DualSenseHardware::new(
                         dualsense::ModelType::Normal,
                         dualsense::BusType::Usb,
@@ -421,12 +765,33 @@ impl Manager {
                     ),
                     _ => DualSenseHardware::default(),
                 };
+                if let Some(serial) = serial {
+                    hw.set_serial(serial.as_str());
+                }
                 TargetDeviceType::DualSense(DualSenseDevice::new(self.dbus.clone(), hw))
             }
-            "xb360" => TargetDeviceType::XBox360(XBox360Controller::new()),
+            "switch-pro" => {
+                let mut device = SwitchProDevice::new(self.dbus.clone());
+                if let Some(serial) = serial {
+                    device.set_serial(serial.as_str());
+                }
+                TargetDeviceType::SwitchPro(device)
+            }
+            "xb360" | "x360" => {
+                TargetDeviceType::XBox360(XBox360Controller::new(self.dbus.clone()))
+            }
+            "xbox-elite" => {
+                TargetDeviceType::XboxElite(XboxEliteController::new(self.dbus.clone()))
+            }
             "dbus" => TargetDeviceType::DBus(DBusDevice::new(self.dbus.clone())),
             "mouse" => TargetDeviceType::Mouse(MouseDevice::new(self.dbus.clone())),
+            "tablet" => TargetDeviceType::Tablet(TabletDevice::new(self.dbus.clone())),
+            "touchpad" => TargetDeviceType::Touchpad(TouchpadDevice::new(self.dbus.clone())),
+            "touchscreen" => {
+                TargetDeviceType::Touchscreen(TouchscreenDevice::new(self.dbus.clone()))
+            }
             "keyboard" => TargetDeviceType::Keyboard(KeyboardDevice::new(self.dbus.clone())),
+            "network" => TargetDeviceType::Network(NetworkDevice::new()),
             _ => TargetDeviceType::Null,
         };
         log::debug!("Created target input device: {kind}");
@@ -434,94 +799,170 @@ impl Manager {
     }
 
     /// Start and run the given target devices. Returns a HashMap of transmitters
-    /// to send events to the given targets.
+    /// to send events to the given targets. If a player number is given, the
+    /// DBus paths of the targets are based on it instead of creation order.
     async fn start_target_devices(
         &mut self,
         targets: Vec<TargetDeviceType>,
+        player: Option<u8>,
     ) -> Result<HashMap<String, mpsc::Sender<TargetCommand>>, Box<dyn Error>> {
         let mut target_devices = HashMap::new();
         for target in targets {
             match target {
                 TargetDeviceType::Null => (),
                 TargetDeviceType::Keyboard(mut device) => {
-                    let path = self.next_target_path("keyboard")?;
+                    let path = self.next_target_path("keyboard", player)?;
                     let event_tx = device.transmitter();
                     target_devices.insert(path.clone(), event_tx.clone());
                     self.target_devices.insert(path.clone(), event_tx.clone());
-                    device.listen_on_dbus(path).await?;
-                    tokio::spawn(async move {
-                        if let Err(e) = device.run().await {
-                            log::error!("Failed to run target keyboard: {:?}", e);
-                        }
-                        log::debug!("Target keyboard device closed");
+                    device.listen_on_dbus(path.clone()).await?;
+                    let task = tokio::spawn(async move {
+                        device.run().await.map_err(|e| e.to_string())
                     });
+                    self.watch_target_task(path, "keyboard", task);
                 }
                 TargetDeviceType::Mouse(mut mouse) => {
-                    let path = self.next_target_path("mouse")?;
+                    let path = self.next_target_path("mouse", player)?;
                     let event_tx = mouse.transmitter();
                     target_devices.insert(path.clone(), event_tx.clone());
                     self.target_devices.insert(path.clone(), event_tx.clone());
-                    mouse.listen_on_dbus(path).await?;
-                    tokio::spawn(async move {
-                        if let Err(e) = mouse.run().await {
-                            log::error!("Failed to run target mouse: {:?}", e);
-                        }
-                        log::debug!("Target mouse device closed");
+                    mouse.listen_on_dbus(path.clone()).await?;
+                    let task = tokio::spawn(async move {
+                        mouse.run().await.map_err(|e| e.to_string())
+                    });
+                    self.watch_target_task(path, "mouse", task);
+                }
+                TargetDeviceType::Tablet(mut tablet) => {
+                    let path = self.next_target_path("tablet", player)?;
+                    let event_tx = tablet.transmitter();
+                    target_devices.insert(path.clone(), event_tx.clone());
+                    self.target_devices.insert(path.clone(), event_tx.clone());
+                    tablet.listen_on_dbus(path.clone()).await?;
+                    let task = tokio::spawn(async move {
+                        tablet.run().await.map_err(|e| e.to_string())
+                    });
+                    self.watch_target_task(path, "tablet", task);
+                }
+                TargetDeviceType::Touchpad(mut touchpad) => {
+                    let path = self.next_target_path("touchpad", player)?;
+                    let event_tx = touchpad.transmitter();
+                    target_devices.insert(path.clone(), event_tx.clone());
+                    self.target_devices.insert(path.clone(), event_tx.clone());
+                    touchpad.listen_on_dbus(path.clone()).await?;
+                    let task = tokio::spawn(async move {
+                        touchpad.run().await.map_err(|e| e.to_string())
+                    });
+                    self.watch_target_task(path, "touchpad", task);
+                }
+                TargetDeviceType::Touchscreen(mut device) => {
+                    let path = self.next_target_path("touchscreen", player)?;
+                    let event_tx = device.transmitter();
+                    target_devices.insert(path.clone(), event_tx.clone());
+                    self.target_devices.insert(path.clone(), event_tx.clone());
+                    device.listen_on_dbus(path.clone()).await?;
+                    let task = tokio::spawn(async move {
+                        device.run().await.map_err(|e| e.to_string())
+                    });
+                    self.watch_target_task(path, "touchscreen", task);
+                }
+                TargetDeviceType::Custom(mut gamepad) => {
+                    let path = self.next_target_path("gamepad", player)?;
+                    let event_tx = gamepad.transmitter();
+                    target_devices.insert(path.clone(), event_tx.clone());
+                    self.target_devices.insert(path.clone(), event_tx.clone());
+                    gamepad.listen_on_dbus(path.clone()).await?;
+                    let task = tokio::spawn(async move {
+                        gamepad.run().await.map_err(|e| e.to_string())
                     });
+                    self.watch_target_task(path, "custom gamepad", task);
                 }
                 TargetDeviceType::GenericGamepad(mut gamepad) => {
-                    let path = self.next_target_path("gamepad")?;
+                    let path = self.next_target_path("gamepad", player)?;
                     let event_tx = gamepad.transmitter();
                     target_devices.insert(path.clone(), event_tx.clone());
                     self.target_devices.insert(path.clone(), event_tx.clone());
-                    gamepad.listen_on_dbus(path).await?;
-                    tokio::spawn(async move {
-                        if let Err(e) = gamepad.run().await {
-                            log::error!("Failed to run target gamepad: {:?}", e);
-                        }
-                        log::debug!("Target gamepad device closed");
+                    gamepad.listen_on_dbus(path.clone()).await?;
+                    let task = tokio::spawn(async move {
+                        gamepad.run().await.map_err(|e| e.to_string())
                     });
+                    self.watch_target_task(path, "gamepad", task);
                 }
                 TargetDeviceType::DBus(mut device) => {
-                    let path = self.next_target_path("dbus")?;
+                    let path = self.next_target_path("dbus", player)?;
                     let event_tx = device.transmitter();
                     target_devices.insert(path.clone(), event_tx.clone());
                     self.target_devices.insert(path.clone(), event_tx.clone());
-                    device.listen_on_dbus(path).await?;
-                    tokio::spawn(async move {
-                        if let Err(e) = device.run().await {
-                            log::error!("Failed to run target dbus device: {:?}", e);
-                        }
-                        log::debug!("Target dbus device closed");
+                    device.listen_on_dbus(path.clone()).await?;
+                    let task = tokio::spawn(async move {
+                        device.run().await.map_err(|e| e.to_string())
                     });
+                    self.watch_target_task(path, "dbus", task);
                 }
                 TargetDeviceType::SteamDeck(mut device) => {
-                    let path = self.next_target_path("gamepad")?;
+                    let path = self.next_target_path("gamepad", player)?;
                     let event_tx = device.transmitter();
                     target_devices.insert(path.clone(), event_tx.clone());
                     self.target_devices.insert(path.clone(), event_tx.clone());
-                    device.listen_on_dbus(path).await?;
-                    tokio::spawn(async move {
-                        if let Err(e) = device.run().await {
-                            log::error!("Failed to run target steam deck device: {:?}", e);
-                        }
-                        log::debug!("Target steam deck device closed");
+                    device.listen_on_dbus(path.clone()).await?;
+                    let task = tokio::spawn(async move {
+                        device.run().await.map_err(|e| e.to_string())
                     });
+                    self.watch_target_task(path, "steam deck", task);
                 }
                 TargetDeviceType::DualSense(mut device) => {
-                    let path = self.next_target_path("gamepad")?;
+                    let path = self.next_target_path("gamepad", player)?;
                     let event_tx = device.transmitter();
                     target_devices.insert(path.clone(), event_tx.clone());
                     self.target_devices.insert(path.clone(), event_tx.clone());
-                    device.listen_on_dbus(path).await?;
-                    tokio::spawn(async move {
-                        if let Err(e) = device.run().await {
-                            log::error!("Failed to run target dualsense device: {:?}", e);
-                        }
-                        log::debug!("Target dualsense device closed");
+                    device.listen_on_dbus(path.clone()).await?;
+                    let task = tokio::spawn(async move {
+                        device.run().await.map_err(|e| e.to_string())
+                    });
+                    self.watch_target_task(path, "dualsense", task);
+                }
+                TargetDeviceType::SwitchPro(mut device) => {
+                    let path = self.next_target_path("gamepad", player)?;
+                    let event_tx = device.transmitter();
+                    target_devices.insert(path.clone(), event_tx.clone());
+                    self.target_devices.insert(path.clone(), event_tx.clone());
+                    device.listen_on_dbus(path.clone()).await?;
+                    let task = tokio::spawn(async move {
+                        device.run().await.map_err(|e| e.to_string())
+                    });
+                    self.watch_target_task(path, "switch pro", task);
+                }
+                TargetDeviceType::Network(mut device) => {
+                    let path = self.next_target_path("network", player)?;
+                    let event_tx = device.transmitter();
+                    target_devices.insert(path.clone(), event_tx.clone());
+                    self.target_devices.insert(path.clone(), event_tx.clone());
+                    let task = tokio::spawn(async move {
+                        device.run().await.map_err(|e| e.to_string())
+                    });
+                    self.watch_target_task(path, "network", task);
+                }
+                TargetDeviceType::XBox360(mut gamepad) => {
+                    let path = self.next_target_path("gamepad", player)?;
+                    let event_tx = gamepad.transmitter();
+                    target_devices.insert(path.clone(), event_tx.clone());
+                    self.target_devices.insert(path.clone(), event_tx.clone());
+                    gamepad.listen_on_dbus(path.clone()).await?;
+                    let task = tokio::spawn(async move {
+                        gamepad.run().await.map_err(|e| e.to_string())
+                    });
+                    self.watch_target_task(path, "xbox 360", task);
+                }
+                TargetDeviceType::XboxElite(mut gamepad) => {
+                    let path = self.next_target_path("gamepad", player)?;
+                    let event_tx = gamepad.transmitter();
+                    target_devices.insert(path.clone(), event_tx.clone());
+                    self.target_devices.insert(path.clone(), event_tx.clone());
+                    gamepad.listen_on_dbus(path.clone()).await?;
+                    let task = tokio::spawn(async move {
+                        gamepad.run().await.map_err(|e| e.to_string())
                     });
+                    self.watch_target_task(path, "xbox elite", task);
                 }
-                TargetDeviceType::XBox360(_) => todo!(),
             }
         }
 
@@ -541,15 +982,50 @@ impl Manager {
         Ok(target_devices)
     }
 
+    /// Watches the given running target device task and notifies the manager
+    /// if the target device fails or panics so its composite device can be
+    /// restarted.
+    fn watch_target_task(
+        &self,
+        path: String,
+        kind: &'static str,
+        task: JoinHandle<Result<(), String>>,
+    ) {
+        let tx = self.tx.clone();
+        let conn = self.dbus.clone();
+        tokio::spawn(async move {
+            let (error, error_kind) = match task.await {
+                Ok(Ok(())) => {
+                    log::debug!("Target {kind} device closed");
+                    return;
+                }
+                Ok(Err(e)) => (e, DeviceErrorKind::WriteFailed),
+                Err(e) => (e.to_string(), DeviceErrorKind::Dropped),
+            };
+            log::error!(
+                target_device = path.as_str(),
+                driver = kind;
+                "Target {kind} device {path} crashed: {error}"
+            );
+            emit_device_error(&conn, path.clone(), error_kind, error).await;
+            if let Err(e) = tx.send(ManagerCommand::TargetDeviceCrashed { path }) {
+                log::error!("Failed to send target device crashed: {e:?}");
+            }
+        });
+    }
+
     /// Create and start the given type of target device and return a mapping
     /// of the dbus path to the target device and sender to send messages to the
     /// device.
     async fn create_and_start_target_device(
         &mut self,
         kind: &str,
+        serial: Option<String>,
+        player: Option<u8>,
+        custom: Option<CustomTargetConfig>,
     ) -> Result<HashMap<String, mpsc::Sender<TargetCommand>>, ManagerError> {
         // Create the target device
-        let device = match self.create_target_device(kind).await {
+        let device = match self.create_target_device(kind, serial, custom).await {
             Ok(device) => device,
             Err(e) => {
                 let err = format!("Error creating target device: {e:?}");
@@ -559,7 +1035,7 @@ impl Manager {
         };
 
         // Start the target device
-        let paths = match self.start_target_devices(vec![device]).await {
+        let paths = match self.start_target_devices(vec![device], player).await {
             Ok(paths) => paths,
             Err(e) => {
                 let err = format!("Error starting target device: {e:?}");
@@ -610,9 +1086,22 @@ impl Manager {
         // Keep track of target devices that this composite device is using
         let mut target_device_paths = Vec::new();
 
+        // Use a stored serial number for the target devices if the source
+        // devices do not have one, so they keep their identity after a restart.
+        let serial = device
+            .get_serial()
+            .or_else(|| Some(self.get_target_serial(&device, &config)));
+
+        // Assign the player number, restoring the one previously used by the
+        // controller if possible
+        let player_number = self.player_slots.claim(&path, serial.as_deref());
+        device.set_player_number(player_number);
+
         // Create a DBus target device
-        let dbus_device = self.create_target_device("dbus").await?;
-        let dbus_devices = self.start_target_devices(vec![dbus_device]).await?;
+        let dbus_device = self.create_target_device("dbus", None, None).await?;
+        let dbus_devices = self
+            .start_target_devices(vec![dbus_device], Some(player_number))
+            .await?;
         let dbus_paths = dbus_devices.keys();
         for dbus_path in dbus_paths {
             target_device_paths.push(dbus_path.clone());
@@ -623,13 +1112,21 @@ impl Manager {
         let mut target_devices = Vec::new();
         if let Some(target_devices_config) = target_types {
             for kind in target_devices_config {
-                let device = self.create_target_device(kind.as_str()).await?;
+                let device = self
+                    .create_target_device(
+                        kind.as_str(),
+                        serial.clone(),
+                        config.custom_target.clone(),
+                    )
+                    .await?;
                 target_devices.push(device);
             }
         }
 
         // Start the target input devices
-        let targets = self.start_target_devices(target_devices).await?;
+        let targets = self
+            .start_target_devices(target_devices, Some(player_number))
+            .await?;
         let target_paths = targets.keys();
         for target_path in target_paths {
             target_device_paths.push(target_path.clone());
@@ -637,18 +1134,47 @@ impl Manager {
 
         // Run the device
         let dbus_path = path.clone();
+        let config_name = config.name.clone();
         let tx = self.tx.clone();
+        let conn = self.dbus.clone();
+        let log_scope = dbus_path.clone();
+        let task = tokio::spawn(logger::scope(log_scope, async move {
+            device.run(targets).await.map_err(|e| e.to_string())
+        }));
         tokio::spawn(async move {
-            if let Err(e) = device.run(targets).await {
-                log::error!("Error running device: {:?}", e);
-            }
-            log::debug!("Composite device stopped running: {:?}", dbus_path);
-            if let Err(e) = tx.send(ManagerCommand::CompositeDeviceStopped(dbus_path)) {
+            // Catch any errors or panics from the composite device so its
+            // source devices can be released and the device restarted.
+            let error = match task.await {
+                Ok(Ok(())) => None,
+                Ok(Err(e)) => Some(e),
+                Err(e) => Some(e.to_string()),
+            };
+            let cmd = if let Some(error) = error {
+                log::error!(
+                    composite_device = dbus_path.as_str(),
+                    config = config_name.as_str();
+                    "Composite device {dbus_path} crashed: {error}"
+                );
+                emit_device_error(&conn, dbus_path.clone(), DeviceErrorKind::Dropped, error)
+                    .await;
+                ManagerCommand::CompositeDeviceCrashed(dbus_path)
+            } else {
+                log::debug!("Composite device stopped running: {:?}", dbus_path);
+                ManagerCommand::CompositeDeviceStopped(dbus_path)
+            };
+            if let Err(e) = tx.send(cmd) {
                 log::error!("Error sending composite device stopped: {:?}", e);
             }
         });
         let comp_path = path.clone();
 
+        // Load the profile for the focused app or dock state if one applies
+        if let Some(profile_path) = self.find_auto_profile(&config) {
+            handle
+                .send(composite_device::Command::SetAutoProfile(Some(profile_path)))
+                .await?;
+        }
+
         // Add the device to our maps
         self.composite_devices.insert(comp_path, handle);
         log::debug!("Managed source devices: {:?}", self.source_devices_used);
@@ -661,16 +1187,124 @@ impl Manager {
         Ok(())
     }
 
+    /// Returns the stored serial number of the target devices for the given
+    /// composite device whose source devices have no serial number. Composite
+    /// devices are identified by their config and the physical path of their
+    /// source device.
+    fn get_target_serial(
+        &mut self,
+        device: &CompositeDevice,
+        config: &CompositeDeviceConfig,
+    ) -> String {
+        let physical_path = device
+            .get_source_devices_used()
+            .iter()
+            .filter_map(|id| self.source_device_info.get(id))
+            .find_map(|info| info.get_physical_path())
+            .unwrap_or_default();
+        let key = format!("{}:{physical_path}", config.name);
+        self.target_identities.get_serial(&key)
+    }
+
+    /// Called when the app focused in gamescope changes. Loads the device
+    /// profile for the newly focused app on all composite devices, or restores
+    /// their previous profile if the app has no profile.
+    async fn on_focused_app_changed(&mut self, app: FocusedApp) {
+        log::info!("Focused app changed to {app:?}");
+        self.focused_app = app;
+        self.update_auto_profiles().await;
+    }
+
+    /// Called when an external display is connected or disconnected. Loads
+    /// the device profile for the dock state on all composite devices.
+    async fn on_dock_state_changed(&mut self, docked: bool) {
+        log::info!("Dock state changed. Docked: {docked}");
+        self.docked = docked;
+        self.update_auto_profiles().await;
+    }
+
+    /// Called when the type of the active session changes. Switches the
+    /// target devices of composite devices that use different target devices
+    /// for each session type.
+    async fn on_session_type_changed(&mut self, session: SessionType) {
+        log::info!("Session type changed to {session:?}");
+        let previous = self.session;
+        self.session = session;
+        for (path, handle) in self.composite_devices.iter() {
+            let Some(config) = self.used_configs.get(path) else {
+                continue;
+            };
+            let Some(session_targets) = config.session_target_devices.as_ref() else {
+                continue;
+            };
+            let old_targets = session_target_devices(config, session_targets, previous);
+            let new_targets = session_target_devices(config, session_targets, session);
+            if old_targets == new_targets {
+                continue;
+            }
+            log::debug!("Using target devices for {path}: {new_targets:?}");
+            let command =
+                composite_device::Command::SetTargetDevices(new_targets.unwrap_or_default());
+            if let Err(e) = handle.send(command).await {
+                log::error!("Failed to set target devices for {path}: {e:?}");
+            }
+        }
+    }
+
+    /// Returns the target devices a composite device with the given config
+    /// should use in the active session.
+    fn default_target_devices(&self, config: &CompositeDeviceConfig) -> Option<Vec<String>> {
+        match config.session_target_devices.as_ref() {
+            Some(session_targets) => session_target_devices(config, session_targets, self.session),
+            None => config.target_devices.clone(),
+        }
+    }
+
+    /// Returns the path to the device profile to automatically load on a
+    /// composite device with the given config for the focused app and dock
+    /// state.
+    fn find_auto_profile(&self, config: &CompositeDeviceConfig) -> Option<String> {
+        auto_profile::find_auto_profile(&self.focused_app, self.docked, &config.name)
+    }
+
+    /// Select the automatic device profile of all composite devices. Composite
+    /// devices restore their previous profile if no profile applies.
+    async fn update_auto_profiles(&self) {
+        for (path, handle) in self.composite_devices.iter() {
+            let Some(config) = self.used_configs.get(path) else {
+                continue;
+            };
+            let profile_path = self.find_auto_profile(config);
+            log::debug!("Using automatic profile for {path}: {profile_path:?}");
+            let command = composite_device::Command::SetAutoProfile(profile_path);
+            if let Err(e) = handle.send(command).await {
+                log::error!("Failed to set automatic profile for {path}: {e:?}");
+            }
+        }
+    }
+
     /// Called when a composite device stops running
     async fn on_composite_device_stopped(&mut self, path: String) -> Result<(), Box<dyn Error>> {
         log::debug!("Removing composite device: {}", path);
 
+        // Restart the source devices of composite devices that crashed
+        if self.composite_devices_restarting.remove(&path) {
+            self.restart_source_devices(&path).await;
+        }
+
         // Remove the DBus interface
         let dbus_path = ObjectPath::from_string_unchecked(path.clone());
         self.dbus
             .object_server()
-            .remove::<CompositeDeviceInterface, ObjectPath>(dbus_path)
+            .remove::<CompositeDeviceInterface, ObjectPath>(dbus_path.clone())
             .await?;
+        // The battery interface only exists if a source device reported its
+        // battery, so it is fine if it cannot be removed.
+        let _ = self
+            .dbus
+            .object_server()
+            .remove::<BatteryInterface, ObjectPath>(dbus_path)
+            .await;
 
         // Find any source devices that were in use by the composite device
         let mut to_remove = Vec::new();
@@ -691,6 +1325,9 @@ impl Manager {
             }
         }
 
+        // Free the player number for other controllers
+        self.player_slots.release(&path);
+
         // Remove the composite device from our list
         self.composite_devices.remove::<String>(&path);
         log::debug!("Composite device removed: {}", path);
@@ -702,6 +1339,78 @@ impl Manager {
         Ok(())
     }
 
+    /// Called when a target device fails or panics. The composite device using
+    /// the target device is stopped and restarted.
+    async fn on_target_device_crashed(&mut self, path: String) {
+        self.target_devices.remove(&path);
+        let composite_path = self
+            .composite_device_targets
+            .iter()
+            .find(|(_, targets)| targets.contains(&path))
+            .map(|(composite_path, _)| composite_path.clone());
+        let Some(composite_path) = composite_path else {
+            log::debug!("Crashed target device {path} is not used by any composite device");
+            return;
+        };
+        let Some(handle) = self.composite_devices.get(&composite_path) else {
+            return;
+        };
+
+        log::info!(
+            "Restarting composite device {composite_path} after target device {path} crashed"
+        );
+        self.composite_devices_restarting.insert(composite_path.clone());
+        if let Err(e) = handle.send(composite_device::Command::Stop).await {
+            log::error!("Failed to stop composite device {composite_path}: {e:?}");
+        }
+    }
+
+    /// Releases the source devices used by the given stopped composite device
+    /// and re-adds them after a short delay so a new composite device can be
+    /// created for them.
+    async fn restart_source_devices(&self, composite_path: &str) {
+        let source_ids: Vec<String> = self
+            .source_devices_used
+            .iter()
+            .filter(|(_, path)| path.as_str() == composite_path)
+            .map(|(id, _)| id.clone())
+            .collect();
+
+        let mut sources = Vec::new();
+        for id in source_ids {
+            // Un-hide the source device in case the composite device could not
+            // clean up after itself.
+            let device_path = if let Some(name) = id.strip_prefix("evdev://") {
+                Some(format!("{INPUT_PATH}/{name}"))
+            } else {
+                id.strip_prefix("hidraw://")
+                    .map(|name| format!("{DEV_PATH}/{name}"))
+            };
+            if let Some(device_path) = device_path {
+                if let Err(e) = unhide_device(device_path.clone()).await {
+                    log::debug!("Unable to unhide device {device_path}: {e:?}");
+                }
+            }
+
+            let Some(info) = self.source_device_info.get(&id) else {
+                log::warn!("Unable to restart source device {id}: no device info found");
+                continue;
+            };
+            sources.push((id, info.clone()));
+        }
+
+        log::info!("Restarting source devices for composite device {composite_path}: {sources:?}");
+        let tx = self.tx.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(RESTART_DELAY).await;
+            for (id, info) in sources {
+                if let Err(e) = tx.send(ManagerCommand::SourceDeviceAdded { id, info }) {
+                    log::error!("Failed to re-add source device: {e:?}");
+                }
+            }
+        });
+    }
+
     /// Called when any source device is added. This method will load all
     /// device configurations to check and see if any configuration matches
     /// the input devices on the system. If a match is found, a [CompositeDevice]
@@ -710,7 +1419,15 @@ impl Manager {
         &mut self,
         id: String,
         device_info: SourceDeviceInfo,
+        settled: bool,
     ) -> Result<(), Box<dyn Error>> {
+        // Only manage one connection of a physical device that is connected
+        // over both USB and Bluetooth.
+        self.source_device_info.insert(id.clone(), device_info.clone());
+        if self.is_duplicate_connection(&id, &device_info).await {
+            return Ok(());
+        }
+
         // Check all existing composite devices to see if this device is part of
         // their config
         'start: for composite_device in self.composite_devices.keys() {
@@ -718,6 +1435,12 @@ impl Manager {
                 continue;
             };
             log::debug!("Checking existing config {:?} for device", config.name);
+            if self.is_other_controller(composite_device, &device_info) {
+                log::debug!(
+                    "Device is an identical controller to the one used by {composite_device}"
+                );
+                continue;
+            }
             let source_devices = config.source_devices.clone();
             match device_info.clone() {
                 SourceDeviceInfo::EvdevDeviceInfo(info) => {
@@ -738,17 +1461,21 @@ impl Manager {
                                     if let Some(unique) = source_device.clone().unique {
                                         if unique {
                                             log::debug!("Found unique device {:?}, not adding to composite device {}", source_device, composite_device);
-                                            break 'start;
+                                            continue 'start;
                                         }
                                     // Default to being unique
                                     } else {
                                         log::debug!("Found unique device {:?}, not adding to composite device {}", source_device, composite_device);
-                                        break 'start;
+                                        continue 'start;
                                     }
                                 }
                             }
 
-                            log::info!("Found missing device, adding source device {id} to existing composite device: {composite_device}");
+                            log::info!(
+                                source_device = id.as_str(),
+                                composite_device = composite_device.as_str();
+                                "Found missing device, adding source device {id} to existing composite device: {composite_device}"
+                            );
                             let handle = self.composite_devices.get(composite_device.as_str());
                             if handle.is_none() {
                                 log::error!(
@@ -796,16 +1523,20 @@ impl Manager {
                                     if let Some(unique) = source_device.clone().unique {
                                         if unique {
                                             log::debug!("Found unique device {:?}, not adding to composite device {}", source_device, composite_device);
-                                            break 'start;
+                                            continue 'start;
                                         }
                                     } else {
                                         log::debug!("Found unique device {:?}, not adding to composite device {}", source_device, composite_device);
-                                        break 'start;
+                                        continue 'start;
                                     }
                                 }
                             }
 
-                            log::info!("Found missing device, adding source device {id} to existing composite device: {composite_device}");
+                            log::info!(
+                                source_device = id.as_str(),
+                                composite_device = composite_device.as_str();
+                                "Found missing device, adding source device {id} to existing composite device: {composite_device}"
+                            );
                             let handle = self.composite_devices.get(composite_device.as_str());
                             if handle.is_none() {
                                 log::error!(
@@ -852,16 +1583,20 @@ impl Manager {
                                     if let Some(unique) = source_device.clone().unique {
                                         if unique {
                                             log::debug!("Found unique device {:?}, not adding to composite device {}", source_device, composite_device);
-                                            break 'start;
+                                            continue 'start;
                                         }
                                     } else {
                                         log::debug!("Found unique device {:?}, not adding to composite device {}", source_device, composite_device);
-                                        break 'start;
+                                        continue 'start;
                                     }
                                 }
                             }
 
-                            log::info!("Found missing device, adding source device {id} to existing composite device: {composite_device}");
+                            log::info!(
+                                source_device = id.as_str(),
+                                composite_device = composite_device.as_str();
+                                "Found missing device, adding source device {id} to existing composite device: {composite_device}"
+                            );
                             let handle = self.composite_devices.get(composite_device.as_str());
                             if handle.is_none() {
                                 log::error!(
@@ -918,13 +1653,20 @@ impl Manager {
                         // how to refrence source devices used by this config?
 
                         if config.has_matching_evdev(&info, &source_device.clone().evdev.unwrap()) {
-                            log::info!("Found a matching event device, creating composite device");
+                            log::info!(
+                                source_device = id.as_str(),
+                                config = config.name.as_str();
+                                "Found a matching event device, creating composite device"
+                            );
+                            if !settled && self.debounce_source_device(&id, &device_info, &config) {
+                                return Ok(());
+                            }
                             let device = self
                                 .create_composite_device_from_config(&config, device_info.clone())
                                 .await?;
 
                             // Get the target input devices from the config
-                            let target_devices_config = config.target_devices.clone();
+                            let target_devices_config = self.default_target_devices(&config);
 
                             // Create the composite deivce
                             self.start_composite_device(
@@ -946,13 +1688,20 @@ impl Manager {
                         }
                         if config.has_matching_hidraw(&info, &source_device.clone().hidraw.unwrap())
                         {
-                            log::info!("Found a matching hidraw device, creating composite device");
+                            log::info!(
+                                source_device = id.as_str(),
+                                config = config.name.as_str();
+                                "Found a matching hidraw device, creating composite device"
+                            );
+                            if !settled && self.debounce_source_device(&id, &device_info, &config) {
+                                return Ok(());
+                            }
                             let device = self
                                 .create_composite_device_from_config(&config, device_info.clone())
                                 .await?;
 
                             // Get the target input devices from the config
-                            let target_devices_config = config.target_devices.clone();
+                            let target_devices_config = self.default_target_devices(&config);
 
                             // Create the composite deivce
                             self.start_composite_device(
@@ -973,13 +1722,20 @@ impl Manager {
                             continue;
                         }
                         if config.has_matching_iio(&info, &source_device.clone().iio.unwrap()) {
-                            log::info!("Found a matching iio device, creating composite device");
+                            log::info!(
+                                source_device = id.as_str(),
+                                config = config.name.as_str();
+                                "Found a matching iio device, creating composite device"
+                            );
+                            if !settled && self.debounce_source_device(&id, &device_info, &config) {
+                                return Ok(());
+                            }
                             let device = self
                                 .create_composite_device_from_config(&config, device_info.clone())
                                 .await?;
 
                             // Get the target input devices from the config
-                            let target_devices_config = config.target_devices.clone();
+                            let target_devices_config = self.default_target_devices(&config);
 
                             // Create the composite deivce
                             self.start_composite_device(
@@ -1002,9 +1758,224 @@ impl Manager {
         Ok(())
     }
 
+    /// Defers creating a composite device for the given source device until
+    /// it has stayed connected for the hotplug debounce time of the given
+    /// config. This keeps devices that rapidly appear and disappear (e.g. dock
+    /// insertion or a flaky cable) from churning composite and target
+    /// devices. Returns true if the device was deferred.
+    fn debounce_source_device(
+        &mut self,
+        id: &str,
+        device_info: &SourceDeviceInfo,
+        config: &CompositeDeviceConfig,
+    ) -> bool {
+        let millis = config
+            .hotplug_debounce_ms
+            .unwrap_or(DEFAULT_HOTPLUG_DEBOUNCE_MS);
+        if millis == 0 {
+            return false;
+        }
+        let debounce = Duration::from_millis(millis);
+        log::debug!("Waiting {debounce:?} for source device {id} to stay connected");
+        let appeared_at = Instant::now();
+        self.source_devices_pending
+            .insert(id.to_string(), appeared_at);
+
+        let tx = self.tx.clone();
+        let cmd = ManagerCommand::SourceDeviceSettled {
+            id: id.to_string(),
+            info: device_info.clone(),
+            appeared_at,
+        };
+        tokio::task::spawn(async move {
+            tokio::time::sleep(debounce).await;
+            if let Err(e) = tx.send(cmd) {
+                log::error!("Failed to send source device settled: {e:?}");
+            }
+        });
+
+        true
+    }
+
+    /// Returns true if the given source device is another controller
+    /// identical (same vendor and product id) to one used by the given
+    /// composite device, so it must get its own composite device instead. The
+    /// controllers are told apart by their serial number (e.g. uniq) or by the
+    /// physical path of the device.
+    fn is_other_controller(&self, composite_path: &str, device_info: &SourceDeviceInfo) -> bool {
+        let Some(ids) = device_info.get_vendor_product() else {
+            return false;
+        };
+        let serial = device_info.get_serial();
+        let physical_path = device_info.get_physical_path();
+
+        for (id, path) in self.source_devices_used.iter() {
+            if path != composite_path {
+                continue;
+            }
+            let Some(other_info) = self.source_device_info.get(id) else {
+                continue;
+            };
+            if other_info.get_vendor_product() != Some(ids) {
+                continue;
+            }
+            if let (Some(serial), Some(other_serial)) = (&serial, other_info.get_serial()) {
+                if serial != &other_serial {
+                    return true;
+                }
+                continue;
+            }
+            let same_kind =
+                std::mem::discriminant(device_info) == std::mem::discriminant(other_info);
+            if !same_kind {
+                continue;
+            }
+            if let (Some(physical_path), Some(other_path)) =
+                (&physical_path, other_info.get_physical_path())
+            {
+                if physical_path != &other_path {
+                    return true;
+                }
+            }
+        }
+
+        false
+    }
+
+    /// Checks to see if the given source device is the same physical device
+    /// (matching serial number) as one already being managed over a different
+    /// connection. USB connections are preferred, so a Bluetooth duplicate is
+    /// ignored, and a composite device using a Bluetooth connection is stopped
+    /// when the same device is connected over USB. Composite devices that allow
+    /// transport switching instead release the Bluetooth connection and keep
+    /// running with the USB connection. Returns true if the given source device
+    /// should not be managed.
+    async fn is_duplicate_connection(
+        &mut self,
+        id: &str,
+        device_info: &SourceDeviceInfo,
+    ) -> bool {
+        let Some(serial) = device_info.get_serial() else {
+            return false;
+        };
+        let is_bluetooth = device_info.is_bluetooth();
+        self.source_device_connections
+            .insert(id.to_string(), (serial.clone(), is_bluetooth));
+
+        let mut bluetooth_composite_paths = Vec::new();
+        let mut bluetooth_source_ids = Vec::new();
+        for (other_id, (other_serial, other_is_bluetooth)) in self.source_device_connections.iter()
+        {
+            if other_serial != &serial || other_is_bluetooth == &is_bluetooth {
+                continue;
+            }
+            let Some(composite_path) = self.source_devices_used.get(other_id) else {
+                continue;
+            };
+            if is_bluetooth {
+                log::info!("Source device {id} with serial {serial} is already connected over USB by {other_id}. Ignoring Bluetooth connection.");
+                return true;
+            }
+            let transport_switching = self
+                .used_configs
+                .get(composite_path)
+                .and_then(|config| config.transport_switching)
+                .unwrap_or(false);
+            if transport_switching {
+                bluetooth_source_ids.push(other_id.clone());
+            } else {
+                bluetooth_composite_paths.push(composite_path.clone());
+            }
+        }
+
+        // Release the Bluetooth connection from composite devices that allow
+        // switching transports. The USB connection is then added to the same
+        // composite device.
+        for other_id in bluetooth_source_ids {
+            log::info!("Source device {id} with serial {serial} connected over USB. Switching from Bluetooth connection {other_id}.");
+            self.release_source_device(&other_id).await;
+        }
+
+        // Stop any composite devices using the Bluetooth connection in favor
+        // of the USB connection.
+        bluetooth_composite_paths.sort();
+        bluetooth_composite_paths.dedup();
+        for composite_path in bluetooth_composite_paths {
+            log::info!("Source device {id} with serial {serial} connected over USB. Stopping composite device {composite_path} using Bluetooth connection.");
+            // Remove the config so no new source devices are added to the
+            // stopping composite device.
+            self.used_configs.remove(&composite_path);
+            let Some(handle) = self.composite_devices.get(&composite_path) else {
+                continue;
+            };
+            if let Err(e) = handle.send(composite_device::Command::Stop).await {
+                log::error!("Failed to stop composite device {composite_path}: {e:?}");
+            }
+        }
+
+        false
+    }
+
+    /// Stop using the given source device in its composite device without it
+    /// being removed from the system.
+    async fn release_source_device(&mut self, id: &str) {
+        let Some(composite_path) = self.source_devices_used.remove(id) else {
+            return;
+        };
+        if let Some(handle) = self.composite_devices.get(&composite_path) {
+            let cmd = composite_device::Command::ReleaseSourceDevice(id.to_string());
+            if let Err(e) = handle.send(cmd).await {
+                log::error!("Failed to release source device {id}: {e:?}");
+            }
+        }
+        if let Some(device) = self.source_devices.remove(id) {
+            if let Some(sources) = self.composite_device_sources.get_mut(&composite_path) {
+                if let Some(idx) = sources.iter().position(|item| item == &device) {
+                    sources.remove(idx);
+                }
+            }
+        }
+        self.source_device_dbus_paths.remove(id);
+    }
+
+    /// Returns the ids of source devices that are another connection of the
+    /// given source device (same serial number) and are not being managed,
+    /// such as a Bluetooth connection ignored while connected over USB.
+    fn unmanaged_connections(&self, id: &str) -> Vec<String> {
+        let Some((serial, _)) = self.source_device_connections.get(id) else {
+            return Vec::new();
+        };
+        self.source_device_connections
+            .iter()
+            .filter(|(other_id, (other_serial, _))| {
+                other_id.as_str() != id
+                    && other_serial == serial
+                    && !self.source_devices_used.contains_key(other_id.as_str())
+            })
+            .map(|(other_id, _)| other_id.clone())
+            .collect()
+    }
+
     /// Called when any source device is removed
     async fn on_source_device_removed(&mut self, id: String) -> Result<(), Box<dyn Error>> {
         log::debug!("Source device removed: {}", id);
+
+        // Manage any other connection of the same device that was ignored
+        // while this one was in use.
+        for other_id in self.unmanaged_connections(&id) {
+            let Some(info) = self.source_device_info.get(&other_id).cloned() else {
+                continue;
+            };
+            log::info!("Managing other connection {other_id} of removed source device {id}");
+            let cmd = ManagerCommand::SourceDeviceAdded { id: other_id, info };
+            if let Err(e) = self.tx.send(cmd) {
+                log::error!("Failed to add source device connection: {e:?}");
+            }
+        }
+
+        self.source_device_connections.remove(&id);
+        self.source_device_info.remove(&id);
+        self.source_devices_pending.remove(&id);
         let Some(composite_device_path) = self.source_devices_used.get(&id) else {
             log::debug!("Source device not being managed by a composite device");
             return Ok(());
@@ -1187,15 +2158,26 @@ impl Manager {
         Ok(())
     }
 
-    /// Returns the next available target device dbus path
-    fn next_target_path(&self, kind: &str) -> Result<String, Box<dyn Error>> {
+    /// Returns the next available target device dbus path. Targets of a
+    /// composite device are named after its player number (e.g. "gamepad0"
+    /// for player 1), with a suffix if the path is already taken (e.g.
+    /// "gamepad0_1"). Other targets use the lowest free index.
+    fn next_target_path(&self, kind: &str, player: Option<u8>) -> Result<String, Box<dyn Error>> {
         let max = 2048;
+        let base = match player {
+            Some(player) => format!("{BUS_TARGETS_PREFIX}/{kind}{}", player.saturating_sub(1)),
+            None => format!("{BUS_TARGETS_PREFIX}/{kind}"),
+        };
         let mut i = 0;
         loop {
             if i > max {
                 return Err("Devices exceeded maximum of 2048".into());
             }
-            let path = format!("{BUS_TARGETS_PREFIX}/{kind}{i}");
+            let path = match (player, i) {
+                (Some(_), 0) => base.clone(),
+                (Some(_), i) => format!("{base}_{i}"),
+                (None, i) => format!("{base}{i}"),
+            };
             if self.target_devices.get(&path).is_some() {
                 i += 1;
                 continue;
@@ -1204,6 +2186,24 @@ impl Manager {
         }
     }
 
+    /// Returns the DBus path of every target device mapped to the DBus path
+    /// of the composite device using it, or an empty string if it is not used
+    /// by a composite device.
+    fn get_target_device_mapping(&self) -> HashMap<String, String> {
+        self.target_devices
+            .keys()
+            .map(|target_path| {
+                let composite_path = self
+                    .composite_device_targets
+                    .iter()
+                    .find(|(_, targets)| targets.contains(target_path))
+                    .map(|(composite_path, _)| composite_path.clone())
+                    .unwrap_or_default();
+                (target_path.clone(), composite_path)
+            })
+            .collect()
+    }
+
     /// Returns the next available composite device dbus path
     fn next_composite_dbus_path(&self) -> String {
         let max = 2048;
@@ -1531,6 +2531,7 @@ impl Manager {
         let iface = ManagerInterface::new(self.tx.clone());
         let manager_path = format!("{}/Manager", BUS_PREFIX);
         self.dbus.object_server().at(manager_path, iface).await?;
+
         Ok(())
     }
 
@@ -1581,3 +2582,18 @@ impl Manager {
         Ok(())
     }
 }
+
+/// Returns the target devices to use in the given session type, falling back
+/// to the default target devices of the config.
+fn session_target_devices(
+    config: &CompositeDeviceConfig,
+    session_targets: &SessionTargetDevicesConfig,
+    session: SessionType,
+) -> Option<Vec<String>> {
+    let targets = match session {
+        SessionType::Gamescope => session_targets.gamescope.as_ref(),
+        SessionType::Desktop => session_targets.desktop.as_ref(),
+        SessionType::Unknown => None,
+    };
+    targets.or(config.target_devices.as_ref()).cloned()
+}
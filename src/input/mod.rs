@@ -1,9 +1,32 @@
 //pub mod device;
+pub mod accessibility;
+pub mod auto_profile;
+pub mod axis_output;
+#[cfg(test)]
+mod axis_output_test;
+pub mod battery;
+#[cfg(test)]
+mod battery_test;
+pub mod calibration;
 pub mod capability;
 pub mod composite_device;
 pub mod event;
+pub mod event_filter;
+#[cfg(test)]
+mod event_filter_test;
+pub mod led;
 pub mod manager;
+pub mod motion;
+#[cfg(test)]
+mod motion_test;
 pub mod output_capability;
 pub mod output_event;
+pub mod player;
+#[cfg(test)]
+mod player_test;
+pub mod plugin;
+pub mod pressure;
+#[cfg(test)]
+mod pressure_test;
 pub mod source;
 pub mod target;
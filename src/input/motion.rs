@@ -0,0 +1,266 @@
+use std::time::Instant;
+
+use crate::config::MotionFusionConfig;
+
+/// Standard gravity in meters per second squared
+pub const GRAVITY: f64 = 9.80665;
+/// Default gain of the Madgwick filter
+const DEFAULT_BETA: f64 = 0.1;
+/// Default proportional gain of the Mahony filter
+const DEFAULT_KP: f64 = 1.0;
+/// Default integral gain of the Mahony filter
+const DEFAULT_KI: f64 = 0.0;
+/// Largest time step in seconds between gyro samples. Longer gaps (e.g. after
+/// the device was paused) are clamped to avoid large jumps in orientation.
+const MAX_TIME_STEP: f64 = 0.1;
+
+/// Orientation quaternion with `w` as the scalar part
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Quaternion {
+    pub w: f64,
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+}
+
+impl Default for Quaternion {
+    fn default() -> Self {
+        Self {
+            w: 1.0,
+            x: 0.0,
+            y: 0.0,
+            z: 0.0,
+        }
+    }
+}
+
+impl Quaternion {
+    /// Returns the quaternion scaled to unit length
+    pub fn normalize(&self) -> Quaternion {
+        let norm = (self.w * self.w + self.x * self.x + self.y * self.y + self.z * self.z).sqrt();
+        if norm == 0.0 {
+            return Quaternion::default();
+        }
+        Quaternion {
+            w: self.w / norm,
+            x: self.x / norm,
+            y: self.y / norm,
+            z: self.z / norm,
+        }
+    }
+
+    /// Returns the direction of gravity in the device frame as a unit vector
+    pub fn gravity(&self) -> [f64; 3] {
+        let Quaternion { w, x, y, z } = *self;
+        [
+            2.0 * (x * z - w * y),
+            2.0 * (w * x + y * z),
+            w * w - x * x - y * y + z * z,
+        ]
+    }
+}
+
+/// Filter used to fuse accelerometer and gyro readings
+#[derive(Debug, Clone, PartialEq)]
+pub enum FusionFilter {
+    /// Gradient descent filter by Sebastian Madgwick
+    Madgwick { beta: f64 },
+    /// Complementary filter by Robert Mahony with integral feedback
+    Mahony {
+        kp: f64,
+        ki: f64,
+        integral: [f64; 3],
+    },
+}
+
+impl From<&MotionFusionConfig> for FusionFilter {
+    fn from(config: &MotionFusionConfig) -> Self {
+        match config.filter.as_deref() {
+            Some("mahony") => FusionFilter::Mahony {
+                kp: config.kp.unwrap_or(DEFAULT_KP),
+                ki: config.ki.unwrap_or(DEFAULT_KI),
+                integral: [0.0; 3],
+            },
+            _ => FusionFilter::Madgwick {
+                beta: config.beta.unwrap_or(DEFAULT_BETA),
+            },
+        }
+    }
+}
+
+/// The [MotionFusion] combines accelerometer and gyro events from IMU source
+/// devices into an orientation quaternion. Accelerometer values are expected
+/// in meters per second squared and gyro values in degrees per second.
+#[derive(Debug, Clone)]
+pub struct MotionFusion {
+    filter: FusionFilter,
+    orientation: Quaternion,
+    accel: Option<[f64; 3]>,
+    last_update: Option<Instant>,
+}
+
+impl MotionFusion {
+    pub fn new(filter: FusionFilter) -> Self {
+        Self {
+            filter,
+            orientation: Quaternion::default(),
+            accel: None,
+            last_update: None,
+        }
+    }
+
+    /// Returns the current orientation
+    pub fn orientation(&self) -> Quaternion {
+        self.orientation
+    }
+
+    /// Returns the fused gravity vector in meters per second squared
+    pub fn gravity(&self) -> [f64; 3] {
+        self.orientation.gravity().map(|v| v * GRAVITY)
+    }
+
+    /// Update the latest accelerometer reading. Missing axes keep their
+    /// previous value.
+    pub fn update_accelerometer(&mut self, x: Option<f64>, y: Option<f64>, z: Option<f64>) {
+        let previous = self.accel.unwrap_or_default();
+        self.accel = Some([
+            x.unwrap_or(previous[0]),
+            y.unwrap_or(previous[1]),
+            z.unwrap_or(previous[2]),
+        ]);
+    }
+
+    /// Update the orientation with the given gyro reading, using the time
+    /// since the last reading as the time step.
+    pub fn update_gyro(&mut self, x: Option<f64>, y: Option<f64>, z: Option<f64>) {
+        let now = Instant::now();
+        let dt = self
+            .last_update
+            .map(|last| now.duration_since(last).as_secs_f64().min(MAX_TIME_STEP))
+            .unwrap_or_default();
+        self.last_update = Some(now);
+        if dt == 0.0 {
+            return;
+        }
+        let gyro = [
+            x.unwrap_or_default().to_radians(),
+            y.unwrap_or_default().to_radians(),
+            z.unwrap_or_default().to_radians(),
+        ];
+        self.update(gyro, dt);
+    }
+
+    /// Update the orientation with the given gyro reading in radians per
+    /// second and time step in seconds.
+    pub fn update(&mut self, gyro: [f64; 3], dt: f64) {
+        let accel = self.accel.and_then(normalize);
+        self.orientation = match &mut self.filter {
+            FusionFilter::Madgwick { beta } => madgwick(self.orientation, gyro, accel, *beta, dt),
+            FusionFilter::Mahony { kp, ki, integral } => {
+                mahony(self.orientation, gyro, accel, *kp, *ki, integral, dt)
+            }
+        };
+    }
+}
+
+/// Returns the given vector scaled to unit length, or [None] if it is zero
+fn normalize(v: [f64; 3]) -> Option<[f64; 3]> {
+    let norm = (v[0] * v[0] + v[1] * v[1] + v[2] * v[2]).sqrt();
+    if norm == 0.0 {
+        return None;
+    }
+    Some(v.map(|c| c / norm))
+}
+
+/// Returns the rate of change of the given orientation from the given gyro
+/// reading in radians per second.
+fn rate_of_change(q: Quaternion, g: [f64; 3]) -> Quaternion {
+    Quaternion {
+        w: 0.5 * (-q.x * g[0] - q.y * g[1] - q.z * g[2]),
+        x: 0.5 * (q.w * g[0] + q.y * g[2] - q.z * g[1]),
+        y: 0.5 * (q.w * g[1] - q.x * g[2] + q.z * g[0]),
+        z: 0.5 * (q.w * g[2] + q.x * g[1] - q.y * g[0]),
+    }
+}
+
+/// Integrate the given rate of change into the orientation
+fn integrate(q: Quaternion, dq: Quaternion, dt: f64) -> Quaternion {
+    Quaternion {
+        w: q.w + dq.w * dt,
+        x: q.x + dq.x * dt,
+        y: q.y + dq.y * dt,
+        z: q.z + dq.z * dt,
+    }
+    .normalize()
+}
+
+/// Madgwick filter update using a gradient descent step towards the measured
+/// gravity direction.
+fn madgwick(
+    q: Quaternion,
+    gyro: [f64; 3],
+    accel: Option<[f64; 3]>,
+    beta: f64,
+    dt: f64,
+) -> Quaternion {
+    let mut dq = rate_of_change(q, gyro);
+
+    if let Some([ax, ay, az]) = accel {
+        let Quaternion { w, x, y, z } = q;
+
+        // Objective function between estimated and measured gravity
+        let f1 = 2.0 * (x * z - w * y) - ax;
+        let f2 = 2.0 * (w * x + y * z) - ay;
+        let f3 = 1.0 - 2.0 * (x * x + y * y) - az;
+
+        // Gradient of the objective function
+        let step = Quaternion {
+            w: -2.0 * y * f1 + 2.0 * x * f2,
+            x: 2.0 * z * f1 + 2.0 * w * f2 - 4.0 * x * f3,
+            y: -2.0 * w * f1 + 2.0 * z * f2 - 4.0 * y * f3,
+            z: 2.0 * x * f1 + 2.0 * y * f2,
+        };
+        let norm = (step.w * step.w + step.x * step.x + step.y * step.y + step.z * step.z).sqrt();
+        if norm > 0.0 {
+            dq.w -= beta * step.w / norm;
+            dq.x -= beta * step.x / norm;
+            dq.y -= beta * step.y / norm;
+            dq.z -= beta * step.z / norm;
+        }
+    }
+
+    integrate(q, dq, dt)
+}
+
+/// Mahony filter update using proportional and integral feedback of the error
+/// between estimated and measured gravity.
+fn mahony(
+    q: Quaternion,
+    gyro: [f64; 3],
+    accel: Option<[f64; 3]>,
+    kp: f64,
+    ki: f64,
+    integral: &mut [f64; 3],
+    dt: f64,
+) -> Quaternion {
+    let mut gyro = gyro;
+
+    if let Some([ax, ay, az]) = accel {
+        // Error is the cross product of measured and estimated gravity
+        let [vx, vy, vz] = q.gravity();
+        let error = [ay * vz - az * vy, az * vx - ax * vz, ax * vy - ay * vx];
+
+        if ki > 0.0 {
+            for (sum, e) in integral.iter_mut().zip(error) {
+                *sum += ki * e * dt;
+            }
+        } else {
+            *integral = [0.0; 3];
+        }
+        for ((g, e), sum) in gyro.iter_mut().zip(error).zip(integral.iter()) {
+            *g += kp * e + sum;
+        }
+    }
+
+    integrate(q, rate_of_change(q, gyro), dt)
+}
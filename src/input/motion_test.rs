@@ -0,0 +1,74 @@
+use std::f64::consts::FRAC_PI_2;
+
+use crate::input::motion::{FusionFilter, MotionFusion, GRAVITY};
+
+/// Time step of the simulated IMU in seconds
+const DT: f64 = 0.01;
+
+/// Returns a Madgwick and a Mahony filter with their default gains
+fn filters() -> [FusionFilter; 2] {
+    [
+        FusionFilter::Madgwick { beta: 0.1 },
+        FusionFilter::Mahony {
+            kp: 1.0,
+            ki: 0.0,
+            integral: [0.0; 3],
+        },
+    ]
+}
+
+/// Asserts that the given vectors are equal within the given tolerance
+fn assert_near(actual: [f64; 3], expected: [f64; 3], tolerance: f64) {
+    for (a, e) in actual.iter().zip(expected) {
+        assert!(
+            (a - e).abs() < tolerance,
+            "expected {expected:?}, got {actual:?}"
+        );
+    }
+}
+
+#[test]
+fn test_filters_converge_to_gravity() {
+    // The device rests tilted 30 degrees while the filter starts level
+    let tilt = 30_f64.to_radians();
+    let expected = [0.3 * tilt.sin(), -0.4 * tilt.sin(), tilt.cos()];
+    let norm = expected.iter().map(|v| v * v).sum::<f64>().sqrt();
+    let expected = expected.map(|v| v / norm);
+
+    for filter in filters() {
+        let mut fusion = MotionFusion::new(filter);
+        let [x, y, z] = expected.map(|v| v * GRAVITY);
+        fusion.update_accelerometer(Some(x), Some(y), Some(z));
+        for _ in 0..2000 {
+            fusion.update([0.0; 3], DT);
+        }
+        assert_near(fusion.orientation().gravity(), expected, 0.01);
+        assert_near(fusion.gravity(), expected.map(|v| v * GRAVITY), 0.1);
+    }
+}
+
+#[test]
+fn test_constant_gyro_rate_integrates_to_angle() {
+    // Rotate at 90 degrees per second for one second around each axis
+    for axis in 0..3 {
+        for filter in filters() {
+            let mut fusion = MotionFusion::new(filter);
+            let mut gyro = [0.0; 3];
+            gyro[axis] = FRAC_PI_2;
+            for _ in 0..1000 {
+                fusion.update(gyro, 0.001);
+            }
+
+            let q = fusion.orientation();
+            let angle = 2.0 * q.w.clamp(-1.0, 1.0).acos();
+            assert!(
+                (angle - FRAC_PI_2).abs() < 1e-3,
+                "expected {FRAC_PI_2}, got {angle}"
+            );
+            let rotation_axis = [q.x, q.y, q.z].map(|v| v / (angle / 2.0).sin());
+            let mut expected = [0.0; 3];
+            expected[axis] = 1.0;
+            assert_near(rotation_axis, expected, 1e-3);
+        }
+    }
+}
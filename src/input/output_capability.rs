@@ -6,6 +6,7 @@ pub enum OutputCapability {
     ForceFeedback,
     ForceFeedbackUpload,
     ForceFeedbackErase,
+    Haptics,
     #[allow(clippy::upper_case_acronyms)]
     LED(LED),
 }
@@ -16,4 +17,6 @@ pub enum OutputCapability {
 pub enum LED {
     Brightness,
     Color,
+    Player,
+    Mute,
 }
@@ -2,9 +2,12 @@ use std::sync::mpsc::Sender;
 
 use ::evdev::{FFEffectData, InputEvent};
 
-use crate::drivers::dualsense::hid_report::SetStatePackedOutputData;
+use crate::drivers::{
+    dualsense::hid_report::SetStatePackedOutputData,
+    steam_deck::hid_report::PackedHapticPulseReport,
+};
 
-use super::output_capability::OutputCapability;
+use super::output_capability::{OutputCapability, LED};
 
 /// Output events are events that flow from target devices back to source devices
 #[derive(Debug, Clone)]
@@ -12,6 +15,16 @@ pub enum OutputEvent {
     Evdev(InputEvent),
     Uinput(UinputOutputEvent),
     DualSense(SetStatePackedOutputData),
+    /// Haptic pulse to play on the trackpads of a Steam Deck
+    SteamDeckHaptics(PackedHapticPulseReport),
+    Led(LedEvent),
+    /// Rumble to play on any source device with rumble motors, with the
+    /// strength of the left (strong) and right (weak) motors. Zero for both
+    /// motors stops the rumble.
+    Rumble {
+        left: u8,
+        right: u8,
+    },
 }
 
 impl OutputEvent {
@@ -47,10 +60,32 @@ impl OutputEvent {
                     OutputCapability::NotImplemented
                 }
             }
+            OutputEvent::SteamDeckHaptics(_) => OutputCapability::Haptics,
+            OutputEvent::Rumble { .. } => OutputCapability::ForceFeedback,
+            OutputEvent::Led(event) => match event {
+                LedEvent::Brightness(_) => OutputCapability::LED(LED::Brightness),
+                LedEvent::Color(_, _, _) => OutputCapability::LED(LED::Color),
+                LedEvent::Player(_) => OutputCapability::LED(LED::Player),
+                LedEvent::Mute(_) => OutputCapability::LED(LED::Mute),
+            },
         }
     }
 }
 
+/// Events that control the LEDs of source devices, such as player LEDs and
+/// light rings
+#[derive(Debug, Clone, Copy)]
+pub enum LedEvent {
+    /// Brightness from 0.0 to 1.0
+    Brightness(f64),
+    /// Red, green, and blue color values
+    Color(u8, u8, u8),
+    /// Player indicator lights to turn on, where bit 0 is the first light
+    Player(u8),
+    /// Whether the mute indicator light is on
+    Mute(bool),
+}
+
 #[derive(Debug, Clone)]
 pub enum UinputOutputEvent {
     /// Effect data to upload to a source device and a channel to send back
@@ -0,0 +1,103 @@
+use std::{collections::HashMap, error::Error, fs, path::PathBuf};
+
+use super::calibration::get_state_dir;
+
+/// Assigns player numbers to composite devices and remembers which controller
+/// serial held which player number, so the same controller gets the same
+/// player number when it reconnects or the daemon restarts.
+#[derive(Debug, Default)]
+pub struct PlayerSlots {
+    /// Player numbers last used by each controller serial. This is stored in
+    /// the state directory.
+    /// E.g. {"01:23:45:67:89:ab": 1}
+    remembered: HashMap<String, u8>,
+    /// Player numbers of running composite devices
+    /// E.g. {"/org/shadowblip/InputPlumber/CompositeDevice0": 1}
+    active: HashMap<String, u8>,
+}
+
+impl PlayerSlots {
+    /// Load the remembered player numbers from the state directory
+    pub fn load() -> Self {
+        let path = get_path();
+        let remembered = match fs::read_to_string(&path) {
+            Ok(content) => serde_yaml::from_str(&content).unwrap_or_else(|e| {
+                log::warn!("Failed to parse player numbers {}: {e}", path.display());
+                HashMap::new()
+            }),
+            Err(_) => HashMap::new(),
+        };
+        Self::new(remembered)
+    }
+
+    /// Returns player slots with the given remembered player numbers
+    pub fn new(remembered: HashMap<String, u8>) -> Self {
+        Self {
+            remembered,
+            active: HashMap::new(),
+        }
+    }
+
+    /// Assign a player number to the composite device with the given DBus
+    /// path. Controllers with a serial number get their previous player number
+    /// back if it is free, otherwise the lowest free player number is used.
+    pub fn claim(&mut self, path: &str, serial: Option<&str>) -> u8 {
+        if let Some(player) = self.active.get(path) {
+            return *player;
+        }
+        let in_use = |player: u8| self.active.values().any(|p| *p == player);
+
+        let previous = serial.and_then(|serial| self.remembered.get(serial).copied());
+        let player = match previous {
+            Some(player) if !in_use(player) => player,
+            _ => {
+                // Prefer player numbers that are not remembered by another
+                // controller, so it keeps its number when it reconnects.
+                let remembered_by_other = |player: u8| {
+                    self.remembered
+                        .iter()
+                        .any(|(s, p)| *p == player && Some(s.as_str()) != serial)
+                };
+                (1..=u8::MAX)
+                    .find(|p| !in_use(*p) && !remembered_by_other(*p))
+                    .or_else(|| (1..=u8::MAX).find(|p| !in_use(*p)))
+                    .unwrap_or(u8::MAX)
+            }
+        };
+
+        self.active.insert(path.to_string(), player);
+        if let Some(serial) = serial {
+            if previous != Some(player) {
+                self.remembered.retain(|_, p| *p != player);
+                self.remembered.insert(serial.to_string(), player);
+                if let Err(e) = self.save() {
+                    log::warn!("Failed to save player numbers: {e:?}");
+                }
+            }
+        }
+        log::debug!("Assigned player {player} to {path} with serial {serial:?}");
+
+        player
+    }
+
+    /// Release the player number of the composite device with the given DBus
+    /// path. The player number stays reserved for its controller serial.
+    pub fn release(&mut self, path: &str) {
+        self.active.remove(path);
+    }
+
+    /// Store the remembered player numbers in the state directory
+    fn save(&self) -> Result<(), Box<dyn Error>> {
+        let path = get_path();
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(&path, serde_yaml::to_string(&self.remembered)?)?;
+        Ok(())
+    }
+}
+
+/// Returns the path of the file where player numbers are stored
+fn get_path() -> PathBuf {
+    get_state_dir().join("players.yaml")
+}
@@ -0,0 +1,13 @@
+use std::collections::HashMap;
+
+use crate::input::player::PlayerSlots;
+
+#[test]
+fn test_claim_remembered_player() {
+    let mut slots = PlayerSlots::new(HashMap::from([("b".to_string(), 1)]));
+    assert_eq!(slots.claim("/dev0", None), 2);
+    assert_eq!(slots.claim("/dev1", None), 3);
+    assert_eq!(slots.claim("/dev2", Some("b")), 1);
+    slots.release("/dev0");
+    assert_eq!(slots.claim("/dev3", None), 2);
+}
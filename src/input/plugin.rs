@@ -0,0 +1,152 @@
+use std::{error::Error, fs, path::Path, str::FromStr};
+
+#[cfg(not(feature = "plugins"))]
+use crate::input::event::native::NativeEvent;
+use crate::{config::PluginConfig, input::capability::Capability};
+
+#[cfg(feature = "plugins")]
+mod wasm;
+
+#[cfg(feature = "plugins")]
+pub use wasm::Plugin;
+
+/// Maximum size of a report that can be passed to a decoder plugin
+pub const MAX_REPORT_SIZE: usize = 1024;
+/// Plugin type for plugins that decode reports from HIDRaw source devices
+pub const PLUGIN_TYPE_DECODER: &str = "decoder";
+/// Plugin type for plugins that transform events in profile mappings
+pub const PLUGIN_TYPE_MAPPER: &str = "mapper";
+
+/// Stand-in for the WASM [Plugin] when InputPlumber is built without the
+/// "plugins" feature. Plugins cannot be loaded, so no instance can exist.
+#[cfg(not(feature = "plugins"))]
+#[derive(Debug)]
+pub enum Plugin {}
+
+#[cfg(not(feature = "plugins"))]
+impl Plugin {
+    /// Always fails since plugin support is not enabled
+    pub fn load(config: &PluginConfig) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        Err(format!(
+            "Unable to load plugin '{}': InputPlumber was built without plugin support",
+            config.name
+        )
+        .into())
+    }
+
+    /// Returns the name of the plugin
+    pub fn name(&self) -> &str {
+        match *self {}
+    }
+
+    /// Decode the given report read from a source device into input events
+    pub fn decode(
+        &mut self,
+        _report: &[u8],
+    ) -> Result<Vec<NativeEvent>, Box<dyn Error + Send + Sync>> {
+        match *self {}
+    }
+
+    /// Transform the given event
+    pub fn transform(
+        &mut self,
+        _event: &NativeEvent,
+    ) -> Result<Option<NativeEvent>, Box<dyn Error + Send + Sync>> {
+        match *self {}
+    }
+}
+
+/// Parse the capabilities defined in the given plugin config
+fn get_capabilities(
+    config: &PluginConfig,
+) -> Result<Vec<Capability>, Box<dyn Error + Send + Sync>> {
+    let mut capabilities = Vec::with_capacity(config.capabilities.len());
+    for cap in config.capabilities.iter() {
+        let Ok(capability) = Capability::from_str(cap) else {
+            return Err(format!("Plugin '{}' has invalid capability: {cap}", config.name).into());
+        };
+        capabilities.push(capability);
+    }
+    Ok(capabilities)
+}
+
+/// Looks in all default locations for [PluginConfig] definitions and returns
+/// them with their module paths resolved.
+pub fn load_plugin_configs() -> Vec<PluginConfig> {
+    let mut plugins: Vec<PluginConfig> = Vec::new();
+    let paths = vec![
+        "./rootfs/usr/share/inputplumber/plugins",
+        "/etc/inputplumber/plugins.d",
+        "/usr/share/inputplumber/plugins",
+    ];
+
+    // Look for plugins in all known locations
+    for path in paths {
+        let files = fs::read_dir(path);
+        if files.is_err() {
+            log::trace!("Failed to load directory {}: {}", path, files.unwrap_err());
+            continue;
+        }
+        let mut files: Vec<_> = files.unwrap().map(|r| r.unwrap()).collect();
+        files.sort_by_key(|dir| dir.file_name());
+
+        // Look at each file in the directory and try to load them
+        for file in files {
+            let filename = file.file_name();
+            let filename = filename.as_os_str().to_str().unwrap();
+
+            // Skip any non-yaml files
+            if !filename.ends_with(".yaml") {
+                continue;
+            }
+
+            // Try to load the plugin config
+            log::trace!("Found file: {}", file.path().display());
+            let plugin = PluginConfig::from_yaml_file(file.path().display().to_string());
+            if plugin.is_err() {
+                log::debug!("Failed to parse plugin config: {}", plugin.unwrap_err());
+                continue;
+            }
+            let mut plugin = plugin.unwrap();
+
+            // Plugins in earlier locations override ones with the same name
+            if plugins.iter().any(|p| p.name == plugin.name) {
+                continue;
+            }
+
+            // Resolve the module path relative to the plugin config
+            if Path::new(&plugin.module).is_relative() {
+                let module = Path::new(path).join(&plugin.module);
+                plugin.module = module.display().to_string();
+            }
+            plugins.push(plugin);
+        }
+    }
+
+    plugins
+}
+
+/// Returns the config of the decoder plugin for the given HIDRaw device if
+/// one exists. Decoder plugins are ignored if plugin support is not enabled,
+/// so the device can still be handled by another driver.
+pub fn find_decoder(vendor_id: u16, product_id: u16, interface_num: i32) -> Option<PluginConfig> {
+    if cfg!(not(feature = "plugins")) {
+        return None;
+    }
+    load_plugin_configs().into_iter().find(|plugin| {
+        plugin.plugin_type == PLUGIN_TYPE_DECODER
+            && plugin.matches_hidraw(vendor_id, product_id, interface_num)
+    })
+}
+
+/// Returns the config of the mapper plugin with the given name if one exists
+pub fn find_mapper(name: &str) -> Option<PluginConfig> {
+    load_plugin_configs()
+        .into_iter()
+        .find(|plugin| plugin.plugin_type == PLUGIN_TYPE_MAPPER && plugin.name == name)
+}
+
+/// Returns the capabilities of the given plugin
+pub fn get_plugin_capabilities(config: &PluginConfig) -> Result<Vec<Capability>, Box<dyn Error>> {
+    get_capabilities(config).map_err(|e| e.to_string().into())
+}
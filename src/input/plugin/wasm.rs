@@ -0,0 +1,278 @@
+use std::error::Error;
+
+use wasmtime::{
+    Config, Engine, Linker, Memory, Module, Store, StoreLimits, StoreLimitsBuilder, TypedFunc,
+};
+use wasmtime_wasi::{
+    preview1::{self, WasiP1Ctx},
+    WasiCtxBuilder,
+};
+
+use crate::{
+    config::PluginConfig,
+    input::{
+        capability::Capability,
+        event::{native::NativeEvent, value::InputValue},
+    },
+};
+
+use super::{get_capabilities, MAX_REPORT_SIZE};
+
+/// Amount of fuel (roughly the number of WASM instructions) a plugin is
+/// allowed to consume for each call.
+const PLUGIN_FUEL: u64 = 1_000_000;
+/// Maximum amount of linear memory a plugin is allowed to use
+const PLUGIN_MEMORY_LIMIT: usize = 4 * 1024 * 1024;
+/// Maximum number of events a decoder plugin can emit for a single report
+const MAX_EVENTS: usize = 64;
+/// Size in bytes of a single event record exchanged with a plugin
+const EVENT_SIZE: usize = 32;
+
+/// Kind of value contained in an event record
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u32)]
+enum ValueKind {
+    None = 0,
+    Bool = 1,
+    Float = 2,
+    Vector2 = 3,
+    Vector3 = 4,
+}
+
+impl TryFrom<u32> for ValueKind {
+    type Error = String;
+
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(Self::None),
+            1 => Ok(Self::Bool),
+            2 => Ok(Self::Float),
+            3 => Ok(Self::Vector2),
+            4 => Ok(Self::Vector3),
+            _ => Err(format!("Invalid value kind: {value}")),
+        }
+    }
+}
+
+/// Store data available to a running plugin
+struct PluginState {
+    wasi: WasiP1Ctx,
+    limits: StoreLimits,
+}
+
+/// A [Plugin] is a sandboxed WASM module that can decode reports from source
+/// devices or transform events translated by device profile mappings.
+///
+/// Plugins are compiled as WASI modules and only have access to their own
+/// memory. They must export `memory` and `alloc(len: u32) -> u32`. Decoder
+/// plugins export `decode(report_ptr, report_len, events_ptr, max_events) -> u32`
+/// which writes events for the given report and returns the number written.
+/// Mapper plugins export `transform(event_ptr) -> u32` which modifies the
+/// event in place and returns 0 if the event should be dropped.
+///
+/// Events are exchanged as 32 byte little-endian records containing the
+/// index of the capability in the plugin config (u32), the value kind (u32,
+/// 0=none, 1=bool, 2=float, 3=vector2, 4=vector3), and the x, y, and z values
+/// (f64). Missing vector values are represented as NaN.
+pub struct Plugin {
+    name: String,
+    capabilities: Vec<Capability>,
+    store: Store<PluginState>,
+    memory: Memory,
+    buffer_ptr: u32,
+    decode: Option<TypedFunc<(u32, u32, u32, u32), u32>>,
+    transform: Option<TypedFunc<u32, u32>>,
+}
+
+impl std::fmt::Debug for Plugin {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Plugin").field("name", &self.name).finish()
+    }
+}
+
+impl Plugin {
+    /// Compile and instantiate the WASM module for the given plugin config
+    pub fn load(config: &PluginConfig) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        log::debug!("Loading plugin '{}' from {}", config.name, config.module);
+        let capabilities = get_capabilities(config)?;
+
+        let mut engine_config = Config::new();
+        engine_config.consume_fuel(true);
+        let engine = Engine::new(&engine_config)?;
+        let module = Module::from_file(&engine, &config.module)?;
+
+        // Plugins get a WASI context without access to the filesystem,
+        // environment, or network.
+        let mut linker: Linker<PluginState> = Linker::new(&engine);
+        preview1::add_to_linker_sync(&mut linker, |state: &mut PluginState| &mut state.wasi)?;
+        let state = PluginState {
+            wasi: WasiCtxBuilder::new().build_p1(),
+            limits: StoreLimitsBuilder::new()
+                .memory_size(PLUGIN_MEMORY_LIMIT)
+                .build(),
+        };
+        let mut store = Store::new(&engine, state);
+        store.limiter(|state| &mut state.limits);
+        store.set_fuel(PLUGIN_FUEL)?;
+
+        let instance = linker.instantiate(&mut store, &module)?;
+        let Some(memory) = instance.get_memory(&mut store, "memory") else {
+            return Err(format!("Plugin '{}' does not export memory", config.name).into());
+        };
+        let alloc: TypedFunc<u32, u32> = instance.get_typed_func(&mut store, "alloc")?;
+        let decode = instance.get_typed_func(&mut store, "decode").ok();
+        let transform = instance.get_typed_func(&mut store, "transform").ok();
+
+        // Allocate a buffer in the plugin for exchanging reports and events
+        let size = MAX_REPORT_SIZE + MAX_EVENTS * EVENT_SIZE;
+        store.set_fuel(PLUGIN_FUEL)?;
+        let buffer_ptr = alloc.call(&mut store, size as u32)?;
+
+        Ok(Self {
+            name: config.name.clone(),
+            capabilities,
+            store,
+            memory,
+            buffer_ptr,
+            decode,
+            transform,
+        })
+    }
+
+    /// Returns the name of the plugin
+    pub fn name(&self) -> &str {
+        self.name.as_str()
+    }
+
+    /// Decode the given report read from a source device into input events
+    pub fn decode(
+        &mut self,
+        report: &[u8],
+    ) -> Result<Vec<NativeEvent>, Box<dyn Error + Send + Sync>> {
+        let Some(decode) = self.decode.clone() else {
+            return Err(format!("Plugin '{}' does not export decode", self.name).into());
+        };
+        let report = &report[..report.len().min(MAX_REPORT_SIZE)];
+        let report_ptr = self.buffer_ptr;
+        let events_ptr = self.buffer_ptr + MAX_REPORT_SIZE as u32;
+        self.memory
+            .write(&mut self.store, report_ptr as usize, report)?;
+
+        self.store.set_fuel(PLUGIN_FUEL)?;
+        let args = (
+            report_ptr,
+            report.len() as u32,
+            events_ptr,
+            MAX_EVENTS as u32,
+        );
+        let count = decode.call(&mut self.store, args)? as usize;
+
+        let mut buf = vec![0; count.min(MAX_EVENTS) * EVENT_SIZE];
+        self.memory
+            .read(&self.store, events_ptr as usize, buf.as_mut_slice())?;
+        let mut events = Vec::with_capacity(count);
+        for record in buf.chunks_exact(EVENT_SIZE) {
+            events.push(self.decode_event(record)?);
+        }
+
+        Ok(events)
+    }
+
+    /// Transform the given event. Returns [None] if the event should be
+    /// dropped. Events with capabilities not listed in the plugin config are
+    /// returned unchanged.
+    pub fn transform(
+        &mut self,
+        event: &NativeEvent,
+    ) -> Result<Option<NativeEvent>, Box<dyn Error + Send + Sync>> {
+        let Some(transform) = self.transform.clone() else {
+            return Err(format!("Plugin '{}' does not export transform", self.name).into());
+        };
+        let Some(record) = self.encode_event(event) else {
+            return Ok(Some(event.clone()));
+        };
+        let event_ptr = self.buffer_ptr;
+        self.memory
+            .write(&mut self.store, event_ptr as usize, &record)?;
+
+        self.store.set_fuel(PLUGIN_FUEL)?;
+        if transform.call(&mut self.store, event_ptr)? == 0 {
+            return Ok(None);
+        }
+
+        let mut record = [0; EVENT_SIZE];
+        self.memory
+            .read(&self.store, event_ptr as usize, &mut record)?;
+        let mut transformed = self.decode_event(&record)?;
+        if let Some(source_cap) = event.get_source_capability() {
+            transformed.set_source_capability(source_cap);
+        }
+        if let Some(timestamp) = event.get_timestamp() {
+            transformed.set_timestamp(timestamp);
+        }
+
+        Ok(Some(transformed))
+    }
+
+    /// Convert the given event record into a [NativeEvent]
+    fn decode_event(&self, record: &[u8]) -> Result<NativeEvent, Box<dyn Error + Send + Sync>> {
+        let index = u32::from_le_bytes(record[0..4].try_into()?) as usize;
+        let kind = ValueKind::try_from(u32::from_le_bytes(record[4..8].try_into()?))?;
+        let x = f64::from_le_bytes(record[8..16].try_into()?);
+        let y = f64::from_le_bytes(record[16..24].try_into()?);
+        let z = f64::from_le_bytes(record[24..32].try_into()?);
+
+        let Some(capability) = self.capabilities.get(index) else {
+            return Err(
+                format!("Plugin '{}' emitted invalid capability {index}", self.name).into(),
+            );
+        };
+        let optional = |value: f64| (!value.is_nan()).then_some(value);
+        let value = match kind {
+            ValueKind::None => InputValue::None,
+            ValueKind::Bool => InputValue::Bool(x != 0.0),
+            ValueKind::Float => InputValue::Float(x),
+            ValueKind::Vector2 => InputValue::Vector2 {
+                x: optional(x),
+                y: optional(y),
+            },
+            ValueKind::Vector3 => InputValue::Vector3 {
+                x: optional(x),
+                y: optional(y),
+                z: optional(z),
+            },
+        };
+
+        Ok(NativeEvent::new(capability.clone(), value))
+    }
+
+    /// Convert the given [NativeEvent] into an event record. Returns [None]
+    /// if the event capability or value cannot be represented.
+    fn encode_event(&self, event: &NativeEvent) -> Option<[u8; EVENT_SIZE]> {
+        let capability = event.as_capability();
+        let index = self
+            .capabilities
+            .iter()
+            .position(|cap| *cap == capability)?;
+        let optional = |value: Option<f64>| value.unwrap_or(f64::NAN);
+        let (kind, x, y, z) = match event.get_value() {
+            InputValue::None => (ValueKind::None, 0.0, 0.0, 0.0),
+            InputValue::Bool(value) => (ValueKind::Bool, value as u8 as f64, 0.0, 0.0),
+            InputValue::Float(value) => (ValueKind::Float, value, 0.0, 0.0),
+            InputValue::Vector2 { x, y } => (ValueKind::Vector2, optional(x), optional(y), 0.0),
+            InputValue::Vector3 { x, y, z } => {
+                (ValueKind::Vector3, optional(x), optional(y), optional(z))
+            }
+            InputValue::Touch { .. } => return None,
+        };
+
+        let mut record = [0; EVENT_SIZE];
+        record[0..4].copy_from_slice(&(index as u32).to_le_bytes());
+        record[4..8].copy_from_slice(&(kind as u32).to_le_bytes());
+        record[8..16].copy_from_slice(&x.to_le_bytes());
+        record[16..24].copy_from_slice(&y.to_le_bytes());
+        record[24..32].copy_from_slice(&z.to_le_bytes());
+
+        Some(record)
+    }
+}
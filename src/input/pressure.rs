@@ -0,0 +1,68 @@
+use super::event::{native::NativeEvent, value::InputValue};
+
+/// A [PressureCurve] remaps the pressure of touch and stylus events to change
+/// how hard the surface must be pressed to reach a given pressure. The curve
+/// is defined by control points of input to output pressure from 0.0 to 1.0,
+/// with linear interpolation between them.
+#[derive(Debug, Clone)]
+pub struct PressureCurve {
+    points: Vec<(f64, f64)>,
+}
+
+impl PressureCurve {
+    /// Create a new curve from the given control points (e.g.
+    /// `[[0.5, 0.25]]`). The curve always starts at (0.0, 0.0) and ends at
+    /// (1.0, 1.0) unless other end points are given.
+    pub fn new(points: &[[f64; 2]]) -> Self {
+        let mut points: Vec<(f64, f64)> = points
+            .iter()
+            .map(|[x, y]| (x.clamp(0.0, 1.0), y.clamp(0.0, 1.0)))
+            .collect();
+        points.sort_by(|a, b| a.0.total_cmp(&b.0));
+        if points.first().map(|(x, _)| *x) != Some(0.0) {
+            points.insert(0, (0.0, 0.0));
+        }
+        if points.last().map(|(x, _)| *x) != Some(1.0) {
+            points.push((1.0, 1.0));
+        }
+        Self { points }
+    }
+
+    /// Returns the output pressure for the given input pressure
+    pub fn apply(&self, pressure: f64) -> f64 {
+        let pressure = pressure.clamp(0.0, 1.0);
+        for window in self.points.windows(2) {
+            let (x0, y0) = window[0];
+            let (x1, y1) = window[1];
+            if pressure > x1 {
+                continue;
+            }
+            if x1 == x0 {
+                return y1;
+            }
+            return y0 + (pressure - x0) * (y1 - y0) / (x1 - x0);
+        }
+        self.points.last().map_or(pressure, |(_, y)| *y)
+    }
+
+    /// Apply the curve to the pressure of the given event, if it has one
+    pub fn process(&self, mut event: NativeEvent) -> NativeEvent {
+        if let InputValue::Touch {
+            index,
+            is_touching,
+            x,
+            y,
+            pressure: Some(pressure),
+        } = event.get_value()
+        {
+            event.set_value(InputValue::Touch {
+                index,
+                is_touching,
+                x,
+                y,
+                pressure: Some(self.apply(pressure)),
+            });
+        }
+        event
+    }
+}
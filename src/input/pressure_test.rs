@@ -0,0 +1,12 @@
+use crate::input::pressure::PressureCurve;
+
+#[test]
+fn test_pressure_curve() {
+    let curve = PressureCurve::new(&[[0.5, 0.25]]);
+    assert_eq!(curve.apply(0.0), 0.0);
+    assert_eq!(curve.apply(0.25), 0.125);
+    assert_eq!(curve.apply(0.5), 0.25);
+    assert_eq!(curve.apply(0.75), 0.625);
+    assert_eq!(curve.apply(1.0), 1.0);
+    assert_eq!(curve.apply(2.0), 1.0);
+}
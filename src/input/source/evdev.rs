@@ -1,25 +1,51 @@
-use std::{collections::HashMap, error::Error, os::fd::AsRawFd, time::Duration};
+pub mod chord;
+#[cfg(test)]
+mod chord_test;
+pub mod clock;
+#[cfg(test)]
+mod clock_test;
+pub mod touchscreen;
+#[cfg(test)]
+mod touchscreen_test;
+
+use std::{
+    collections::HashMap,
+    error::Error,
+    os::fd::AsRawFd,
+    str::FromStr,
+    time::{Duration, Instant},
+};
 
 use evdev::{
     AbsInfo, AbsoluteAxisCode, Device, EventType, FFEffect, FFEffectData, FFEffectKind, FFReplay,
-    FFTrigger, InputEvent,
+    FFTrigger, InputEvent, MiscCode, PropType,
 };
 use nix::fcntl::{FcntlArg, OFlag};
 use tokio::sync::mpsc::{self, error::TryRecvError};
 
 use crate::{
+    config,
     constants::BUS_PREFIX,
     drivers::dualsense::hid_report::SetStatePackedOutputData,
     input::{
-        capability::Capability,
+        capability::{Capability, Touch},
         composite_device::Command,
-        event::{evdev::EvdevEvent, Event},
+        event::{
+            evdev::{instant_from_system_time, EvdevEvent},
+            Event,
+        },
         output_event::OutputEvent,
     },
     procfs,
 };
 
-use super::SourceCommand;
+use self::{
+    chord::ChordDecoder,
+    clock::DeviceClock,
+    touchscreen::{Orientation, Touchscreen},
+};
+
+use super::{open_with_retry, SourceCommand};
 
 /// Size of the [SourceCommand] buffer for receiving output events
 const BUFFER_SIZE: usize = 2048;
@@ -30,6 +56,7 @@ const POLL_RATE: Duration = Duration::from_micros(1666);
 #[derive(Debug)]
 pub struct EventDevice {
     info: procfs::device::Device,
+    config: Option<config::Evdev>,
     composite_tx: mpsc::Sender<Command>,
     tx: mpsc::Sender<SourceCommand>,
     rx: mpsc::Receiver<SourceCommand>,
@@ -38,10 +65,15 @@ pub struct EventDevice {
 }
 
 impl EventDevice {
-    pub fn new(info: procfs::device::Device, composite_tx: mpsc::Sender<Command>) -> Self {
+    pub fn new(
+        info: procfs::device::Device,
+        config: Option<config::Evdev>,
+        composite_tx: mpsc::Sender<Command>,
+    ) -> Self {
         let (tx, rx) = mpsc::channel(BUFFER_SIZE);
         Self {
             info,
+            config,
             composite_tx,
             tx,
             rx,
@@ -59,8 +91,12 @@ impl EventDevice {
     pub async fn run(&mut self) -> Result<(), Box<dyn Error>> {
         let path = self.get_device_path();
         log::debug!("Opening device at: {}", path);
-        let mut device = Device::open(path.clone())?;
-        device.grab()?;
+        let mut device = open_with_retry(&self.get_id(), &self.composite_tx, || {
+            let mut device = Device::open(path.clone())?;
+            device.grab()?;
+            Ok(device)
+        })
+        .await?;
 
         // Set the device to do non-blocking reads
         // TODO: use epoll to wake up when data is available
@@ -76,6 +112,22 @@ impl EventDevice {
             axes_info.insert(axis, info);
         }
 
+        // Touchscreens report multi-touch contacts that are translated into
+        // touchscreen events instead of being forwarded as evdev events
+        let mut touchscreen = if is_touchscreen(&device) {
+            Touchscreen::new(&axes_info, self.get_panel_orientation())
+        } else {
+            None
+        };
+
+        // Vendor buttons that are sent as key sequences are decoded into
+        // gamepad buttons
+        let mut chords = ChordDecoder::from_evdev_config(self.config.as_ref());
+
+        // Devices that report MSC_TIMESTAMP have their own clock which is
+        // mapped onto the host clock to timestamp events
+        let mut clock = DeviceClock::new();
+
         // Loop to read events from the device and commands over the channel
         log::debug!("Reading events from {}", path);
         let mut interval = tokio::time::interval(POLL_RATE);
@@ -84,9 +136,16 @@ impl EventDevice {
             interval.tick().await;
 
             // Receive commands/output events
-            if let Err(e) = self.receive_commands(&mut device) {
-                log::debug!("Error receiving commands: {:?}", e);
-                break;
+            match self.receive_commands(&mut device) {
+                Ok(false) => (),
+                Ok(true) => {
+                    log::debug!("Device stop requested");
+                    break;
+                }
+                Err(e) => {
+                    log::debug!("Error receiving commands: {:?}", e);
+                    break;
+                }
             }
 
             // Read events from the device
@@ -99,7 +158,16 @@ impl EventDevice {
             };
 
             // Process events from the device
-            if let Err(err) = self.process_events(events, &axes_info).await {
+            if let Err(err) = self
+                .process_events(
+                    events,
+                    &axes_info,
+                    touchscreen.as_mut(),
+                    chords.as_mut(),
+                    &mut clock,
+                )
+                .await
+            {
                 log::error!("Failed to process events: {:?}", err);
                 break;
             }
@@ -132,9 +200,43 @@ impl EventDevice {
         &self,
         events: Vec<InputEvent>,
         axes_info: &HashMap<AbsoluteAxisCode, AbsInfo>,
+        mut touchscreen: Option<&mut Touchscreen>,
+        mut chords: Option<&mut ChordDecoder>,
+        clock: &mut DeviceClock,
     ) -> Result<(), Box<dyn Error>> {
-        for event in events {
+        // Forward held back key presses of sequences that timed out
+        let now = Instant::now();
+        if let Some(chords) = chords.as_mut() {
+            for event in chords.check_timeout(now) {
+                self.composite_tx
+                    .send(Command::ProcessEvent(self.get_id(), event))
+                    .await?;
+            }
+        }
+
+        let timestamps = event_timestamps(&events, clock);
+        for (event, timestamp) in events.into_iter().zip(timestamps) {
             log::trace!("Received event: {:?}", event);
+            if let Some(touchscreen) = touchscreen.as_mut() {
+                for event in touchscreen.handle_event(&event) {
+                    let event = Event::Native(event);
+                    self.composite_tx
+                        .send(Command::ProcessEvent(self.get_id(), event))
+                        .await?;
+                }
+                continue;
+            }
+            if let Some(chords) = chords.as_mut() {
+                if event.event_type() == EventType::KEY {
+                    for event in chords.handle_event(event, now) {
+                        self.composite_tx
+                            .send(Command::ProcessEvent(self.get_id(), event))
+                            .await?;
+                    }
+                    continue;
+                }
+            }
+
             // If this is an ABS event, get the min/max info for this type of
             // event so we can normalize the value.
             let abs_info = if event.event_type() == EventType::ABSOLUTE {
@@ -149,6 +251,9 @@ impl EventDevice {
             if let Some(info) = abs_info {
                 evdev_event.set_abs_info(*info);
             }
+            if let Some(timestamp) = timestamp {
+                evdev_event.set_timestamp(timestamp);
+            }
 
             // Send the event to the composite device
             let event = Event::Evdev(evdev_event);
@@ -161,8 +266,8 @@ impl EventDevice {
     }
 
     /// Read commands sent to this device from the channel until it is
-    /// empty.
-    fn receive_commands(&mut self, device: &mut Device) -> Result<(), Box<dyn Error>> {
+    /// empty. Returns `true` if the device was asked to stop.
+    fn receive_commands(&mut self, device: &mut Device) -> Result<bool, Box<dyn Error>> {
         const MAX_COMMANDS: u8 = 64;
         let mut commands_processed = 0;
         loop {
@@ -212,13 +317,31 @@ impl EventDevice {
                                     }
                                 }
                             }
+                            OutputEvent::Rumble { left, right } => {
+                                if let Err(e) = self.process_rumble(device, left, right) {
+                                    log::error!("Failed to process rumble: {:?}", e);
+                                }
+                            }
                             OutputEvent::Uinput(_) => (),
+                            OutputEvent::SteamDeckHaptics(_) => (),
+                            OutputEvent::Led(_) => (),
+                        }
+                    }
+                    SourceCommand::SetGrab(grab) => {
+                        let result = if grab {
+                            device.grab()
+                        } else {
+                            device.ungrab()
+                        };
+                        if let Err(e) = result {
+                            log::error!("Failed to set grab on device to {grab}: {e:?}");
                         }
                     }
-                    SourceCommand::Stop => return Err("Device stopped".into()),
+                    SourceCommand::Reopen => (),
+                    SourceCommand::Stop => return Ok(true),
                 },
                 Err(e) => match e {
-                    TryRecvError::Empty => return Ok(()),
+                    TryRecvError::Empty => return Ok(false),
                     TryRecvError::Disconnected => {
                         log::debug!("Receive channel disconnected");
                         return Err("Receive channel disconnected".into());
@@ -228,7 +351,7 @@ impl EventDevice {
 
             commands_processed += 1;
             if commands_processed >= MAX_COMMANDS {
-                return Ok(());
+                return Ok(false);
             }
         }
     }
@@ -290,6 +413,19 @@ impl EventDevice {
         &mut self,
         device: &mut Device,
         report: SetStatePackedOutputData,
+    ) -> Result<(), Box<dyn Error>> {
+        let left = report.rumble_emulation_left;
+        let right = report.rumble_emulation_right;
+        self.process_rumble(device, left, right)
+    }
+
+    /// Play rumble with the given left (strong) and right (weak) motor
+    /// strengths using an evdev force feedback effect
+    fn process_rumble(
+        &mut self,
+        device: &mut Device,
+        left: u8,
+        right: u8,
     ) -> Result<(), Box<dyn Error>> {
         // If no effect was uploaded to handle DualSense force feedback, upload one.
         if self.ff_effects_dualsense.is_none() {
@@ -319,7 +455,7 @@ impl EventDevice {
         let effect = self.ff_effects.get_mut(&effect_id).unwrap();
 
         // Stop playing the effect if values are set to zero
-        if report.rumble_emulation_left == 0 && report.rumble_emulation_right == 0 {
+        if left == 0 && right == 0 {
             log::trace!("Stopping FF effect");
             effect.stop()?;
             return Ok(());
@@ -338,8 +474,8 @@ impl EventDevice {
             },
             kind: FFEffectKind::Rumble {
                 // DualSense values are u8, so scale them to be from u16::MIN-u16::MAX
-                strong_magnitude: report.rumble_emulation_left as u16 * 256,
-                weak_magnitude: report.rumble_emulation_right as u16 * 256,
+                strong_magnitude: left as u16 * 256,
+                weak_magnitude: right as u16 * 256,
             },
         };
         log::trace!("Updating effect data");
@@ -373,6 +509,22 @@ impl EventDevice {
         format!("/dev/input/{}", handler)
     }
 
+    /// Returns the orientation of the display panel of a touchscreen from
+    /// the config
+    fn get_panel_orientation(&self) -> Orientation {
+        let Some(orientation) = self
+            .config
+            .as_ref()
+            .and_then(|config| config.panel_orientation.as_ref())
+        else {
+            return Orientation::default();
+        };
+        Orientation::from_str(orientation).unwrap_or_else(|e| {
+            log::warn!("{e}. Using the normal orientation.");
+            Orientation::default()
+        })
+    }
+
     /// Returns the capabilities that this source device can fulfill.
     pub fn get_capabilities(&self) -> Result<Vec<Capability>, Box<dyn Error>> {
         let mut capabilities = vec![];
@@ -381,6 +533,13 @@ impl EventDevice {
         let path = self.get_device_path();
         log::debug!("Opening device at: {}", path);
         let device = Device::open(path.clone())?;
+        if is_touchscreen(&device) {
+            capabilities.push(Capability::Touchscreen(Touch::Motion));
+            return Ok(capabilities);
+        }
+        if let Some(chords) = ChordDecoder::from_evdev_config(self.config.as_ref()) {
+            capabilities.extend(chords.get_capabilities());
+        }
 
         // Loop through all support events
         let events = device.supported_events();
@@ -447,6 +606,47 @@ impl EventDevice {
     }
 }
 
+/// Returns true if the given device is a multi-touch touchscreen
+/// Returns the time each of the given events was produced. Events are grouped
+/// into frames ending with a SYN_REPORT. If a frame includes an MSC_TIMESTAMP
+/// event, the device clock is used for every event in the frame, otherwise the
+/// time the kernel received the frame is used.
+fn event_timestamps(events: &[InputEvent], clock: &mut DeviceClock) -> Vec<Option<Instant>> {
+    let mut timestamps = Vec::with_capacity(events.len());
+    let mut frame_start = 0;
+    for (i, event) in events.iter().enumerate() {
+        let is_last = i + 1 == events.len();
+        if event.event_type() != EventType::SYNCHRONIZATION && !is_last {
+            continue;
+        }
+
+        let frame = &events[frame_start..=i];
+        let received = instant_from_system_time(event.timestamp());
+        let device_time = frame.iter().find(|event| {
+            event.event_type() == EventType::MISC && event.code() == MiscCode::MSC_TIMESTAMP.0
+        });
+        let timestamp = match (device_time, received) {
+            (Some(device_time), Some(received)) => {
+                Some(clock.to_instant(device_time.value() as u32, received))
+            }
+            _ => received,
+        };
+
+        timestamps.extend(std::iter::repeat(timestamp).take(frame.len()));
+        frame_start = i + 1;
+    }
+
+    timestamps
+}
+
+fn is_touchscreen(device: &Device) -> bool {
+    let is_direct = device.properties().contains(PropType::DIRECT);
+    let is_multitouch = device
+        .supported_absolute_axes()
+        .is_some_and(|axes| axes.contains(AbsoluteAxisCode::ABS_MT_POSITION_X));
+    is_direct && is_multitouch
+}
+
 /// Returns the DBus object path for evdev devices
 pub fn get_dbus_path(handler: String) -> String {
     format!("{}/devices/source/{}", BUS_PREFIX, handler.clone())
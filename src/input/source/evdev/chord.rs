@@ -0,0 +1,224 @@
+use std::{
+    error::Error,
+    str::FromStr,
+    time::{Duration, Instant},
+};
+
+use evdev::{EventType, InputEvent};
+
+use crate::{
+    config,
+    input::{
+        capability::{Capability, Gamepad, GamepadButton, Keyboard},
+        event::{evdev::EvdevEvent, native::NativeEvent, value::InputValue, Event},
+    },
+};
+
+/// Default maximum time between the first and the last key of a chord
+const DEFAULT_TIMEOUT: Duration = Duration::from_millis(50);
+
+/// Sequence of key presses that is decoded into a single gamepad button
+#[derive(Debug, Clone)]
+pub struct Chord {
+    keys: Vec<Capability>,
+    target: GamepadButton,
+    timeout: Duration,
+}
+
+impl Chord {
+    /// Create a chord from the given config
+    pub fn from_config(config: &config::KeyChord) -> Result<Self, Box<dyn Error>> {
+        if config.keys.is_empty() {
+            return Err(format!("No keys defined for chord {}", config.target).into());
+        }
+        let mut keys = Vec::with_capacity(config.keys.len());
+        for key in config.keys.iter() {
+            let key = Keyboard::from_str(key).map_err(|_| format!("Invalid key: {key}"))?;
+            keys.push(Capability::Keyboard(key));
+        }
+        let target = GamepadButton::from_str(&config.target)
+            .map_err(|_| format!("Invalid gamepad button: {}", config.target))?;
+        let timeout = config
+            .timeout_ms
+            .map(Duration::from_millis)
+            .unwrap_or(DEFAULT_TIMEOUT);
+
+        Ok(Self {
+            keys,
+            target,
+            timeout,
+        })
+    }
+
+    /// Returns the capability emitted for this chord
+    pub fn capability(&self) -> Capability {
+        Capability::Gamepad(Gamepad::Button(self.target.clone()))
+    }
+}
+
+/// Decodes sequences of key presses that some devices send for vendor buttons
+/// (e.g. Ctrl+Alt+Del) into a single gamepad button. Key presses that start a
+/// known sequence are held back until the sequence either completes or stops
+/// matching, in which case they are forwarded unchanged.
+#[derive(Debug)]
+pub struct ChordDecoder {
+    chords: Vec<Chord>,
+    /// Key presses matching the start of one or more chords
+    pending: Vec<(Capability, InputEvent)>,
+    /// Time the first pending key was pressed
+    pending_since: Option<Instant>,
+    /// Index of the chord that is currently pressed and its held keys
+    active: Option<(usize, Vec<Capability>)>,
+}
+
+impl ChordDecoder {
+    pub fn new(chords: Vec<Chord>) -> Self {
+        Self {
+            chords,
+            pending: Vec::new(),
+            pending_since: None,
+            active: None,
+        }
+    }
+
+    /// Create a decoder for the chords defined in the given config. Returns
+    /// None if no valid chords are defined.
+    pub fn from_evdev_config(config: Option<&config::Evdev>) -> Option<Self> {
+        let key_chords = config.and_then(|config| config.key_chords.as_ref())?;
+        let chords: Vec<Chord> = key_chords
+            .iter()
+            .filter_map(|chord| match Chord::from_config(chord) {
+                Ok(chord) => Some(chord),
+                Err(e) => {
+                    log::warn!("Skipping key chord: {e}");
+                    None
+                }
+            })
+            .collect();
+        if chords.is_empty() {
+            return None;
+        }
+        Some(Self::new(chords))
+    }
+
+    /// Returns the capabilities emitted by the decoded chords
+    pub fn get_capabilities(&self) -> Vec<Capability> {
+        let mut capabilities = Vec::new();
+        for chord in self.chords.iter() {
+            let capability = chord.capability();
+            if !capabilities.contains(&capability) {
+                capabilities.push(capability);
+            }
+        }
+        capabilities
+    }
+
+    /// Handle the given input event received at the given time, returning
+    /// the events that should be sent to the composite device
+    pub fn handle_event(&mut self, event: InputEvent, now: Instant) -> Vec<Event> {
+        if event.event_type() != EventType::KEY {
+            return vec![Event::Evdev(event.into())];
+        }
+        let capability = EvdevEvent::from(event).as_capability();
+
+        // Keys of a decoded chord are consumed until all of them are released
+        if let Some((index, held)) = self.active.as_mut() {
+            let chord = &self.chords[*index];
+            if chord.keys.contains(&capability) {
+                if event.value() != 0 {
+                    return Vec::new();
+                }
+                held.retain(|key| key != &capability);
+                if !held.is_empty() {
+                    return Vec::new();
+                }
+                let release = button_event(chord, false);
+                self.active = None;
+                return vec![release];
+            }
+        }
+
+        let is_pending = self.pending.iter().any(|(key, _)| key == &capability);
+        match event.value() {
+            // Key press
+            1 => {
+                let mut sequence = self.pending_keys();
+                sequence.push(capability.clone());
+                let is_prefix = |chord: &Chord| chord.keys.starts_with(&sequence);
+                if !self.chords.iter().any(is_prefix) {
+                    let mut events = self.flush();
+                    events.push(Event::Evdev(event.into()));
+                    return events;
+                }
+
+                // Emit the chord once all of its keys were pressed
+                let completed = self.chords.iter().position(|chord| chord.keys == sequence);
+                if let Some(index) = completed {
+                    let chord = &self.chords[index];
+                    log::debug!("Decoded key chord as {}", chord.target);
+                    self.pending.clear();
+                    self.pending_since = None;
+                    self.active = Some((index, chord.keys.clone()));
+                    return vec![button_event(chord, true)];
+                }
+
+                if self.pending.is_empty() {
+                    self.pending_since = Some(now);
+                }
+                self.pending.push((capability, event));
+                Vec::new()
+            }
+            // Key repeat
+            2 if is_pending => Vec::new(),
+            // Releasing a pending key ends the sequence
+            0 if is_pending => {
+                let mut events = self.flush();
+                events.push(Event::Evdev(event.into()));
+                events
+            }
+            _ => vec![Event::Evdev(event.into())],
+        }
+    }
+
+    /// Returns the pending key presses if the sequence they started was not
+    /// completed in time
+    pub fn check_timeout(&mut self, now: Instant) -> Vec<Event> {
+        let Some(since) = self.pending_since else {
+            return Vec::new();
+        };
+        let sequence = self.pending_keys();
+        let timeout = self
+            .chords
+            .iter()
+            .filter(|chord| chord.keys.starts_with(&sequence))
+            .map(|chord| chord.timeout)
+            .max()
+            .unwrap_or_default();
+        if now.duration_since(since) < timeout {
+            return Vec::new();
+        }
+        self.flush()
+    }
+
+    /// Returns the keys of the pending key presses
+    fn pending_keys(&self) -> Vec<Capability> {
+        self.pending.iter().map(|(key, _)| key.clone()).collect()
+    }
+
+    /// Returns the pending key presses as evdev events
+    fn flush(&mut self) -> Vec<Event> {
+        self.pending_since = None;
+        self.pending
+            .drain(..)
+            .map(|(_, event)| Event::Evdev(event.into()))
+            .collect()
+    }
+}
+
+/// Returns a button event for the given chord
+fn button_event(chord: &Chord, pressed: bool) -> Event {
+    Event::Native(NativeEvent::new(
+        chord.capability(),
+        InputValue::Bool(pressed),
+    ))
+}
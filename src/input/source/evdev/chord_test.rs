@@ -0,0 +1,99 @@
+use std::time::{Duration, Instant};
+
+use evdev::{EventType, InputEvent, KeyCode};
+
+use crate::{
+    config::KeyChord,
+    input::{
+        capability::{Capability, Gamepad, GamepadButton, Keyboard},
+        event::{value::InputValue, Event},
+        source::evdev::chord::{Chord, ChordDecoder},
+    },
+};
+
+/// Returns a key event with the given code and value
+fn key_event(code: KeyCode, value: i32) -> InputEvent {
+    InputEvent::new(EventType::KEY.0, code.0, value)
+}
+
+/// Returns a decoder for the Ctrl+Alt+Del sequence
+fn decoder() -> ChordDecoder {
+    let config = KeyChord {
+        keys: vec![
+            "KeyRightCtrl".to_string(),
+            "KeyRightAlt".to_string(),
+            "KeyDelete".to_string(),
+        ],
+        target: "QuickAccess".to_string(),
+        timeout_ms: Some(50),
+    };
+    ChordDecoder::new(vec![Chord::from_config(&config).unwrap()])
+}
+
+/// Returns whether the given event is a QuickAccess event with the given state
+fn is_quick_access(event: &Event, pressed: bool) -> bool {
+    let Event::Native(event) = event else {
+        return false;
+    };
+    event.as_capability() == Capability::Gamepad(Gamepad::Button(GamepadButton::QuickAccess))
+        && matches!(event.get_value(), InputValue::Bool(value) if value == pressed)
+}
+
+#[test]
+fn test_chord_decoded() {
+    let mut decoder = decoder();
+    let now = Instant::now();
+
+    // Key presses of the sequence are held back until it completes
+    assert!(decoder
+        .handle_event(key_event(KeyCode::KEY_RIGHTCTRL, 1), now)
+        .is_empty());
+    assert!(decoder
+        .handle_event(key_event(KeyCode::KEY_RIGHTALT, 1), now)
+        .is_empty());
+    let events = decoder.handle_event(key_event(KeyCode::KEY_DELETE, 1), now);
+    assert_eq!(events.len(), 1);
+    assert!(is_quick_access(&events[0], true));
+
+    // The button is released once all keys are released
+    assert!(decoder
+        .handle_event(key_event(KeyCode::KEY_DELETE, 0), now)
+        .is_empty());
+    assert!(decoder
+        .handle_event(key_event(KeyCode::KEY_RIGHTALT, 0), now)
+        .is_empty());
+    let events = decoder.handle_event(key_event(KeyCode::KEY_RIGHTCTRL, 0), now);
+    assert_eq!(events.len(), 1);
+    assert!(is_quick_access(&events[0], false));
+}
+
+#[test]
+fn test_chord_forwarded() {
+    let mut decoder = decoder();
+    let now = Instant::now();
+
+    // Keys that stop matching the sequence are forwarded in order
+    assert!(decoder
+        .handle_event(key_event(KeyCode::KEY_RIGHTCTRL, 1), now)
+        .is_empty());
+    let events = decoder.handle_event(key_event(KeyCode::KEY_C, 1), now);
+    assert_eq!(events.len(), 2);
+    assert!(matches!(
+        events[0],
+        Event::Evdev(ref event) if event.as_capability() == Capability::Keyboard(Keyboard::KeyRightCtrl)
+    ));
+    assert!(matches!(
+        events[1],
+        Event::Evdev(ref event) if event.as_capability() == Capability::Keyboard(Keyboard::KeyC)
+    ));
+
+    // Incomplete sequences are forwarded after the timeout
+    assert!(decoder
+        .handle_event(key_event(KeyCode::KEY_RIGHTCTRL, 1), now)
+        .is_empty());
+    assert!(decoder
+        .check_timeout(now + Duration::from_millis(10))
+        .is_empty());
+    let events = decoder.check_timeout(now + Duration::from_millis(50));
+    assert_eq!(events.len(), 1);
+}
@@ -0,0 +1,39 @@
+use std::time::{Duration, Instant};
+
+/// Maximum difference between the device clock and the time an event was
+/// received before the device clock is considered out of sync.
+const MAX_DRIFT: Duration = Duration::from_millis(50);
+
+/// Maps the MSC_TIMESTAMP values reported by a device onto the host clock.
+/// The device clock is a free-running microsecond counter that wraps around,
+/// so it is anchored to the time the first timestamped frame was received and
+/// re-anchored whenever it drifts too far from the host clock.
+#[derive(Debug, Default)]
+pub struct DeviceClock {
+    anchor: Option<(u32, Instant)>,
+}
+
+impl DeviceClock {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the host time at which the device produced a frame with the
+    /// given MSC_TIMESTAMP value. The `received` time is the time the kernel
+    /// received the frame and is used whenever the device clock is unusable.
+    pub fn to_instant(&mut self, device_micros: u32, received: Instant) -> Instant {
+        let Some((anchor_micros, anchor_time)) = self.anchor else {
+            self.anchor = Some((device_micros, received));
+            return received;
+        };
+
+        let elapsed = device_micros.wrapping_sub(anchor_micros);
+        let time = anchor_time + Duration::from_micros(elapsed as u64);
+        if time > received || received - time > MAX_DRIFT {
+            self.anchor = Some((device_micros, received));
+            return received;
+        }
+
+        time
+    }
+}
@@ -0,0 +1,51 @@
+use std::time::{Duration, Instant};
+
+use crate::input::source::evdev::clock::DeviceClock;
+
+#[test]
+fn test_first_frame_uses_received_time() {
+    let mut clock = DeviceClock::new();
+    let received = Instant::now();
+    assert_eq!(clock.to_instant(1000, received), received);
+}
+
+#[test]
+fn test_device_clock_removes_jitter() {
+    let mut clock = DeviceClock::new();
+    let start = Instant::now();
+    clock.to_instant(1000, start);
+
+    // The frame was produced 4ms after the first one, but delivered late
+    let received = start + Duration::from_millis(7);
+    let time = clock.to_instant(5000, received);
+    assert_eq!(time, start + Duration::from_millis(4));
+}
+
+#[test]
+fn test_device_clock_wraps_around() {
+    let mut clock = DeviceClock::new();
+    let start = Instant::now();
+    clock.to_instant(u32::MAX - 999, start);
+
+    let received = start + Duration::from_millis(3);
+    let time = clock.to_instant(1000, received);
+    assert_eq!(time, start + Duration::from_millis(2));
+}
+
+#[test]
+fn test_device_clock_resyncs() {
+    let mut clock = DeviceClock::new();
+    let start = Instant::now();
+    clock.to_instant(1000, start);
+
+    // A device clock that runs ahead of the host is re-anchored
+    let received = start + Duration::from_millis(1);
+    assert_eq!(clock.to_instant(9000, received), received);
+
+    // A device clock that falls too far behind is re-anchored
+    let received = received + Duration::from_millis(200);
+    assert_eq!(clock.to_instant(10000, received), received);
+
+    let time = clock.to_instant(11000, received + Duration::from_millis(2));
+    assert_eq!(time, received + Duration::from_millis(1));
+}
@@ -0,0 +1,161 @@
+use std::{collections::HashMap, error::Error, str::FromStr};
+
+use evdev::{AbsInfo, AbsoluteAxisCode, EventType, InputEvent, SynchronizationCode};
+
+use crate::input::{
+    capability::{Capability, Touch},
+    event::{native::NativeEvent, value::InputValue},
+};
+
+/// Maximum number of touch contacts tracked at the same time
+const MAX_SLOTS: usize = 10;
+
+/// Orientation of the display panel relative to its natural orientation,
+/// matching the DRM "panel orientation" connector property. Touch coordinates
+/// are reported in the natural orientation of the panel and are rotated so
+/// they match the orientation of the displayed image.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum Orientation {
+    #[default]
+    Normal,
+    UpsideDown,
+    LeftSideUp,
+    RightSideUp,
+}
+
+impl Orientation {
+    /// Rotate the given normalized panel coordinates into display coordinates
+    pub fn rotate(&self, x: f64, y: f64) -> (f64, f64) {
+        match self {
+            Orientation::Normal => (x, y),
+            Orientation::UpsideDown => (1.0 - x, 1.0 - y),
+            Orientation::LeftSideUp => (y, 1.0 - x),
+            Orientation::RightSideUp => (1.0 - y, x),
+        }
+    }
+}
+
+impl FromStr for Orientation {
+    type Err = Box<dyn Error>;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "normal" => Ok(Orientation::Normal),
+            "upside_down" => Ok(Orientation::UpsideDown),
+            "left_side_up" => Ok(Orientation::LeftSideUp),
+            "right_side_up" => Ok(Orientation::RightSideUp),
+            _ => Err(format!("Invalid panel orientation: {s}").into()),
+        }
+    }
+}
+
+/// State of a single touch contact
+#[derive(Debug, Clone, Copy, Default)]
+struct Slot {
+    /// Tracking id assigned by the kernel, or None if the slot is unused
+    tracking_id: Option<i32>,
+    x: i32,
+    y: i32,
+    /// Whether the contact changed since the last sync event
+    changed: bool,
+}
+
+/// Translates multi-touch (protocol B) events from a touchscreen into
+/// normalized [Capability::Touchscreen] events. Contacts are collected until
+/// the next sync event, so each touch event contains both coordinates.
+#[derive(Debug)]
+pub struct Touchscreen {
+    orientation: Orientation,
+    x_info: AbsInfo,
+    y_info: AbsInfo,
+    slot: usize,
+    slots: [Slot; MAX_SLOTS],
+}
+
+impl Touchscreen {
+    /// Returns a new [Touchscreen] if the given axes describe a multi-touch
+    /// device
+    pub fn new(
+        axes_info: &HashMap<AbsoluteAxisCode, AbsInfo>,
+        orientation: Orientation,
+    ) -> Option<Self> {
+        let x_info = axes_info.get(&AbsoluteAxisCode::ABS_MT_POSITION_X)?;
+        let y_info = axes_info.get(&AbsoluteAxisCode::ABS_MT_POSITION_Y)?;
+        Some(Self {
+            orientation,
+            x_info: *x_info,
+            y_info: *y_info,
+            slot: 0,
+            slots: [Slot::default(); MAX_SLOTS],
+        })
+    }
+
+    /// Handle the given input event, returning touch events for all changed
+    /// contacts once a sync event is received
+    pub fn handle_event(&mut self, event: &InputEvent) -> Vec<NativeEvent> {
+        let value = event.value();
+        match event.event_type() {
+            EventType::ABSOLUTE => {
+                let code = AbsoluteAxisCode(event.code());
+                if code == AbsoluteAxisCode::ABS_MT_SLOT {
+                    self.slot = value.max(0) as usize;
+                    return Vec::new();
+                }
+                let Some(slot) = self.slots.get_mut(self.slot) else {
+                    return Vec::new();
+                };
+                match code {
+                    AbsoluteAxisCode::ABS_MT_TRACKING_ID => {
+                        slot.tracking_id = (value >= 0).then_some(value);
+                    }
+                    AbsoluteAxisCode::ABS_MT_POSITION_X => slot.x = value,
+                    AbsoluteAxisCode::ABS_MT_POSITION_Y => slot.y = value,
+                    _ => return Vec::new(),
+                }
+                slot.changed = true;
+                Vec::new()
+            }
+            EventType::SYNCHRONIZATION
+                if SynchronizationCode(event.code()) == SynchronizationCode::SYN_REPORT =>
+            {
+                self.sync()
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    /// Returns touch events for all contacts that changed since the last sync
+    fn sync(&mut self) -> Vec<NativeEvent> {
+        let mut events = Vec::new();
+        for (index, slot) in self.slots.iter_mut().enumerate() {
+            if !slot.changed {
+                continue;
+            }
+            slot.changed = false;
+
+            let x = normalize(slot.x, &self.x_info);
+            let y = normalize(slot.y, &self.y_info);
+            let (x, y) = self.orientation.rotate(x, y);
+            events.push(NativeEvent::new(
+                Capability::Touchscreen(Touch::Motion),
+                InputValue::Touch {
+                    index: index as u8,
+                    is_touching: slot.tracking_id.is_some(),
+                    x: Some(x),
+                    y: Some(y),
+                    pressure: None,
+                },
+            ));
+        }
+        events
+    }
+}
+
+/// Returns a value between 0.0 and 1.0 for the given axis value
+fn normalize(value: i32, info: &AbsInfo) -> f64 {
+    let range = (info.maximum() - info.minimum()) as f64;
+    if range <= 0.0 {
+        return 0.0;
+    }
+    ((value - info.minimum()) as f64 / range).clamp(0.0, 1.0)
+}
@@ -0,0 +1,78 @@
+use std::collections::HashMap;
+
+use evdev::{AbsInfo, AbsoluteAxisCode, EventType, InputEvent, SynchronizationCode};
+
+use crate::input::{
+    capability::{Capability, Touch},
+    event::value::InputValue,
+    source::evdev::touchscreen::{Orientation, Touchscreen},
+};
+
+/// Returns an absolute axis event with the given code and value
+fn abs_event(code: AbsoluteAxisCode, value: i32) -> InputEvent {
+    InputEvent::new(EventType::ABSOLUTE.0, code.0, value)
+}
+
+/// Returns a sync report event
+fn sync_event() -> InputEvent {
+    InputEvent::new(
+        EventType::SYNCHRONIZATION.0,
+        SynchronizationCode::SYN_REPORT.0,
+        0,
+    )
+}
+
+#[test]
+fn test_touchscreen_orientation() {
+    let mut axes_info = HashMap::new();
+    axes_info.insert(
+        AbsoluteAxisCode::ABS_MT_POSITION_X,
+        AbsInfo::new(0, 0, 800, 0, 0, 0),
+    );
+    axes_info.insert(
+        AbsoluteAxisCode::ABS_MT_POSITION_Y,
+        AbsInfo::new(0, 0, 1280, 0, 0, 0),
+    );
+    let mut touchscreen = Touchscreen::new(&axes_info, Orientation::RightSideUp).unwrap();
+
+    // Contacts are only reported once the frame is complete
+    let events = [
+        abs_event(AbsoluteAxisCode::ABS_MT_SLOT, 1),
+        abs_event(AbsoluteAxisCode::ABS_MT_TRACKING_ID, 42),
+        abs_event(AbsoluteAxisCode::ABS_MT_POSITION_X, 200),
+        abs_event(AbsoluteAxisCode::ABS_MT_POSITION_Y, 320),
+    ];
+    for event in events.iter() {
+        assert!(touchscreen.handle_event(event).is_empty());
+    }
+    let events = touchscreen.handle_event(&sync_event());
+    assert_eq!(events.len(), 1);
+    assert_eq!(
+        events[0].as_capability(),
+        Capability::Touchscreen(Touch::Motion)
+    );
+    let InputValue::Touch {
+        index,
+        is_touching,
+        x,
+        y,
+        ..
+    } = events[0].get_value()
+    else {
+        panic!("Expected a touch value");
+    };
+    assert_eq!((index, is_touching), (1, true));
+    assert_eq!((x, y), (Some(0.75), Some(0.25)));
+
+    // Lifting the contact reports its last position
+    touchscreen.handle_event(&abs_event(AbsoluteAxisCode::ABS_MT_TRACKING_ID, -1));
+    let events = touchscreen.handle_event(&sync_event());
+    assert_eq!(events.len(), 1);
+    assert!(matches!(
+        events[0].get_value(),
+        InputValue::Touch {
+            is_touching: false,
+            ..
+        }
+    ));
+}
@@ -1,18 +1,26 @@
+pub mod dualsense;
+pub mod dualshock4;
+pub mod generic;
 pub mod lego;
+pub mod plugin;
+pub mod rog_ally;
+pub mod steam_controller;
 pub mod steam_deck;
+pub mod wooting;
 
-use std::error::Error;
+use std::{error::Error, fs::OpenOptions, time::Instant};
 
 use hidapi::{DeviceInfo, HidApi};
-use tokio::sync::mpsc;
+use tokio::sync::{mpsc, watch};
 
 use crate::{
+    config,
     constants::BUS_PREFIX,
     drivers::{self},
-    input::{capability::Capability, composite_device::Command},
+    input::{capability::Capability, composite_device::Command, plugin as plugins},
 };
 
-use super::SourceCommand;
+use super::{open_with_retry, SourceCommand};
 
 /// Size of the [SourceCommand] buffer for receiving output events
 const BUFFER_SIZE: usize = 2048;
@@ -21,19 +29,31 @@ const BUFFER_SIZE: usize = 2048;
 #[derive(Debug)]
 pub struct HIDRawDevice {
     info: DeviceInfo,
+    config: Option<config::Hidraw>,
     composite_tx: mpsc::Sender<Command>,
     tx: mpsc::Sender<SourceCommand>,
     rx: Option<mpsc::Receiver<SourceCommand>>,
+    /// Time of the last input report received by the driver
+    report_tx: Option<watch::Sender<Instant>>,
+    report_rx: watch::Receiver<Instant>,
 }
 
 impl HIDRawDevice {
-    pub fn new(info: DeviceInfo, composite_tx: mpsc::Sender<Command>) -> Self {
+    pub fn new(
+        info: DeviceInfo,
+        config: Option<config::Hidraw>,
+        composite_tx: mpsc::Sender<Command>,
+    ) -> Self {
         let (tx, rx) = mpsc::channel(BUFFER_SIZE);
+        let (report_tx, report_rx) = watch::channel(Instant::now());
         Self {
             info,
+            config,
             composite_tx,
             tx,
             rx: Some(rx),
+            report_tx: Some(report_tx),
+            report_rx,
         }
     }
 
@@ -42,24 +62,147 @@ impl HIDRawDevice {
         self.tx.clone()
     }
 
+    /// Returns a receiver for the time of the last input report if the driver
+    /// for this device continuously sends input reports, so the device can be
+    /// monitored for stalls.
+    pub fn report_receiver(&self) -> Option<watch::Receiver<Instant>> {
+        let reports_continuously = (self.info.vendor_id() == steam_deck::VID
+            && self.info.product_id() == steam_deck::PID)
+            || drivers::lego::driver::find_variant(self.info.vendor_id(), self.info.product_id())
+                .is_some();
+        reports_continuously.then(|| self.report_rx.clone())
+    }
+
     /// Run the source device handler. HIDRaw devices require device-specific
     /// implementations. If one does not exist, an error will be returned.
     pub async fn run(&mut self) -> Result<(), Box<dyn Error>> {
+        // Ensure the device can be opened before starting the driver
+        let path = self.get_device_path();
+        open_with_retry(&self.get_id(), &self.composite_tx, || {
+            OpenOptions::new().read(true).write(true).open(&path)
+        })
+        .await?;
+
         // Run the appropriate HIDRaw driver
         if self.info.vendor_id() == steam_deck::VID && self.info.product_id() == steam_deck::PID {
             log::info!("Detected Steam Deck");
             let tx = self.composite_tx.clone();
             let rx = self.rx.take().unwrap();
-            let mut driver =
-                steam_deck::DeckController::new(self.info.clone(), tx, rx, self.get_id());
+            let report_tx = self.report_tx.take().unwrap();
+            let lizard_mode = self
+                .config
+                .as_ref()
+                .and_then(|config| config.lizard_mode)
+                .unwrap_or(false);
+            let audio_haptics = self
+                .config
+                .as_ref()
+                .and_then(|config| config.audio_haptics.clone());
+            let mut driver = steam_deck::DeckController::new(
+                self.info.clone(),
+                lizard_mode,
+                audio_haptics,
+                tx,
+                rx,
+                report_tx,
+                self.get_id(),
+            );
             driver.run().await?;
-        } else if self.info.vendor_id() == drivers::lego::driver::VID
-            && (self.info.product_id() == drivers::lego::driver::PID
-                || self.info.product_id() == drivers::lego::driver::PID2)
+        } else if drivers::lego::driver::find_variant(self.info.vendor_id(), self.info.product_id())
+            .is_some()
         {
             log::info!("Detected Legion Go");
             let tx = self.composite_tx.clone();
-            let driver = lego::LegionController::new(self.info.clone(), tx, self.get_id());
+            let rx = self.rx.take().unwrap();
+            let report_tx = self.report_tx.take().unwrap();
+            let fusion = self
+                .config
+                .as_ref()
+                .and_then(|config| config.imu_fusion.as_deref())
+                .map(lego::ImuFusion::from_config)
+                .unwrap_or_default();
+            let touchpad_enabled = self
+                .config
+                .as_ref()
+                .and_then(|config| config.touchpad_enabled);
+            let mut driver = lego::LegionController::new(
+                self.info.clone(),
+                fusion,
+                touchpad_enabled,
+                tx,
+                rx,
+                report_tx,
+                self.get_id(),
+            );
+            driver.run().await?;
+        } else if self.info.vendor_id() == drivers::rog_ally::driver::VID
+            && self.info.product_id() == drivers::rog_ally::driver::PID
+        {
+            log::info!("Detected ROG Ally");
+            let tx = self.composite_tx.clone();
+            let driver = rog_ally::RogAllyController::new(self.info.clone(), tx, self.get_id());
+            driver.run().await?;
+        } else if self.info.vendor_id() == drivers::dualsense::driver::DS5_VID
+            && (self.info.product_id() == drivers::dualsense::driver::DS5_PID
+                || self.info.product_id() == drivers::dualsense::driver::DS5_EDGE_PID)
+        {
+            log::info!("Detected DualSense");
+            let tx = self.composite_tx.clone();
+            let rx = self.rx.take().unwrap();
+            let mut driver =
+                dualsense::DualSenseController::new(self.info.clone(), tx, rx, self.get_id());
+            driver.run().await?;
+        } else if drivers::dualshock4::driver::is_supported(
+            self.info.vendor_id(),
+            self.info.product_id(),
+        ) {
+            log::info!("Detected DualShock 4");
+            let tx = self.composite_tx.clone();
+            let driver =
+                dualshock4::DualShock4Controller::new(self.info.clone(), tx, self.get_id());
+            driver.run().await?;
+        } else if drivers::steam_controller::driver::is_supported(
+            self.info.vendor_id(),
+            self.info.product_id(),
+        ) {
+            log::info!("Detected Steam Controller");
+            let tx = self.composite_tx.clone();
+            let lizard_mode = self
+                .config
+                .as_ref()
+                .and_then(|config| config.lizard_mode)
+                .unwrap_or(false);
+            let driver = steam_controller::SteamController::new(
+                self.info.clone(),
+                lizard_mode,
+                tx,
+                self.get_id(),
+            );
+            driver.run().await?;
+        } else if drivers::wooting::driver::is_supported(
+            self.info.vendor_id(),
+            self.info.usage_page(),
+        ) {
+            log::info!("Detected analog keyboard");
+            let tx = self.composite_tx.clone();
+            let mappings = wooting::AnalogKeyMapping::from_hidraw_config(self.config.as_ref());
+            let driver =
+                wooting::AnalogKeyboard::new(self.info.clone(), mappings, tx, self.get_id());
+            driver.run().await?;
+        } else if let Some(config) = plugins::find_decoder(
+            self.info.vendor_id(),
+            self.info.product_id(),
+            self.info.interface_number(),
+        ) {
+            log::info!("Detected device supported by plugin '{}'", config.name);
+            let tx = self.composite_tx.clone();
+            let driver =
+                plugin::PluginController::new(self.info.clone(), config, tx, self.get_id());
+            driver.run().await?;
+        } else if generic::find_gamepad_descriptor(&self.info).is_some() {
+            log::info!("Detected generic HID gamepad");
+            let tx = self.composite_tx.clone();
+            let driver = generic::GenericController::new(self.info.clone(), tx, self.get_id());
             driver.run().await?;
         } else {
             return Err(format!(
@@ -94,11 +237,43 @@ impl HIDRawDevice {
     pub fn get_capabilities(&self) -> Result<Vec<Capability>, Box<dyn Error>> {
         if self.info.vendor_id() == steam_deck::VID && self.info.product_id() == steam_deck::PID {
             Ok(Vec::from(steam_deck::CAPABILITIES))
-        } else if self.info.vendor_id() == drivers::lego::driver::VID
-            && (self.info.product_id() == drivers::lego::driver::PID
-                || self.info.product_id() == drivers::lego::driver::PID2)
+        } else if drivers::lego::driver::find_variant(self.info.vendor_id(), self.info.product_id())
+            .is_some()
         {
             Ok(Vec::from(lego::CAPABILITIES))
+        } else if self.info.vendor_id() == drivers::rog_ally::driver::VID
+            && self.info.product_id() == drivers::rog_ally::driver::PID
+        {
+            Ok(Vec::from(rog_ally::CAPABILITIES))
+        } else if self.info.vendor_id() == drivers::dualsense::driver::DS5_VID
+            && (self.info.product_id() == drivers::dualsense::driver::DS5_PID
+                || self.info.product_id() == drivers::dualsense::driver::DS5_EDGE_PID)
+        {
+            Ok(Vec::from(dualsense::CAPABILITIES))
+        } else if drivers::dualshock4::driver::is_supported(
+            self.info.vendor_id(),
+            self.info.product_id(),
+        ) {
+            Ok(Vec::from(dualshock4::CAPABILITIES))
+        } else if drivers::steam_controller::driver::is_supported(
+            self.info.vendor_id(),
+            self.info.product_id(),
+        ) {
+            Ok(Vec::from(steam_controller::CAPABILITIES))
+        } else if drivers::wooting::driver::is_supported(
+            self.info.vendor_id(),
+            self.info.usage_page(),
+        ) {
+            let mappings = wooting::AnalogKeyMapping::from_hidraw_config(self.config.as_ref());
+            Ok(wooting::get_capabilities(&mappings))
+        } else if let Some(config) = plugins::find_decoder(
+            self.info.vendor_id(),
+            self.info.product_id(),
+            self.info.interface_number(),
+        ) {
+            plugins::get_plugin_capabilities(&config)
+        } else if let Some(descriptor) = generic::find_gamepad_descriptor(&self.info) {
+            Ok(generic::get_capabilities(&descriptor))
         } else {
             Err(format!(
                 "No driver for hidraw interface found. VID: {}, PID: {}",
@@ -0,0 +1,134 @@
+use std::error::Error;
+
+use hidapi::DeviceInfo;
+use tokio::sync::mpsc::{self, error::TryRecvError};
+
+use crate::{
+    drivers::dualsense::{
+        driver::{Battery, Driver},
+        hid_report::PowerState,
+    },
+    input::{
+        battery::{BatteryState, BatteryStatus},
+        capability::Capability,
+        composite_device::Command,
+        output_event::OutputEvent,
+        source::SourceCommand,
+    },
+    logger,
+};
+
+/// DualSense implementation of HIDRAW interface. Input events and rumble are
+/// handled by the kernel evdev devices, so this only reads the battery state
+/// and forwards output reports for features evdev does not expose, such as
+/// adaptive trigger effects.
+#[derive(Debug)]
+pub struct DualSenseController {
+    info: DeviceInfo,
+    composite_tx: mpsc::Sender<Command>,
+    rx: Option<mpsc::Receiver<SourceCommand>>,
+    device_id: String,
+}
+
+impl DualSenseController {
+    pub fn new(
+        info: DeviceInfo,
+        composite_tx: mpsc::Sender<Command>,
+        rx: mpsc::Receiver<SourceCommand>,
+        device_id: String,
+    ) -> Self {
+        Self {
+            info,
+            composite_tx,
+            rx: Some(rx),
+            device_id,
+        }
+    }
+
+    pub async fn run(&mut self) -> Result<(), Box<dyn Error>> {
+        log::debug!("Starting DualSense driver");
+        let path = self.info.path().to_string_lossy().to_string();
+        let tx = self.composite_tx.clone();
+        let mut rx = self.rx.take().unwrap();
+
+        // Spawn a blocking task to read the battery state and write output
+        // reports
+        let device_id = self.device_id.clone();
+        let task =
+            logger::spawn_blocking_in_scope(move || -> Result<(), Box<dyn Error + Send + Sync>> {
+                let mut driver = Driver::new(path)?;
+                // While released, no output reports are written so another
+                // program (e.g. Steam) can control the device.
+                let mut grabbed = true;
+                loop {
+                    if let Some(battery) = driver.poll()? {
+                        tx.blocking_send(Command::SourceDeviceBattery(
+                            device_id.clone(),
+                            translate_battery(battery),
+                        ))?;
+                    }
+
+                    // Receive commands/output events
+                    loop {
+                        let cmd = match rx.try_recv() {
+                            Ok(cmd) => cmd,
+                            Err(TryRecvError::Empty) => break,
+                            Err(TryRecvError::Disconnected) => return Ok(()),
+                        };
+                        match cmd {
+                            SourceCommand::WriteEvent(OutputEvent::DualSense(_)) if !grabbed => {
+                                log::trace!("Ignoring output event while device is released");
+                            }
+                            SourceCommand::WriteEvent(OutputEvent::DualSense(mut state)) => {
+                                // Rumble is played through evdev force feedback
+                                state.use_rumble_not_haptics = false;
+                                state.enable_rumble_emulation = false;
+                                state.enable_improved_rumble_emulation = false;
+                                if let Err(e) = driver.write_state(state) {
+                                    log::error!("Failed to write DualSense output report: {:?}", e);
+                                }
+                            }
+                            SourceCommand::WriteEvent(_) => (),
+                            SourceCommand::UploadEffect(_, composite_dev) => {
+                                let _ = composite_dev.send(Err("Not supported".into()));
+                            }
+                            SourceCommand::UpdateEffect(_, _) => (),
+                            SourceCommand::EraseEffect(_, composite_dev) => {
+                                let _ = composite_dev.send(Err("Not supported".into()));
+                            }
+                            SourceCommand::SetGrab(grab) => {
+                                log::debug!("Setting grab on device to {grab}");
+                                grabbed = grab;
+                            }
+                            SourceCommand::Reopen => (),
+                            SourceCommand::Stop => return Ok(()),
+                        }
+                    }
+                }
+            });
+
+        // Wait for the task to finish
+        if let Err(e) = task.await? {
+            return Err(e.to_string().into());
+        }
+
+        log::debug!("DualSense driver stopped");
+
+        Ok(())
+    }
+}
+
+/// Translate the given DualSense battery state
+fn translate_battery(battery: Battery) -> BatteryState {
+    let status = match battery.power_state {
+        PowerState::Disharging => BatteryStatus::Discharging,
+        PowerState::Charging => BatteryStatus::Charging,
+        PowerState::Complete => BatteryStatus::Full,
+        _ => BatteryStatus::Unknown,
+    };
+    BatteryState::new(battery.level, status)
+}
+
+/// List of all capabilities that the DualSense driver implements. Inputs are
+/// provided by the evdev source devices instead.
+pub const CAPABILITIES: &[Capability] = &[];
@@ -1 +1,235 @@
-//https://crates.io/crates/dualshock4
+use std::error::Error;
+
+use hidapi::DeviceInfo;
+use tokio::sync::mpsc;
+
+use crate::{
+    drivers::dualshock4::{
+        driver::Driver,
+        event,
+        hid_report::{
+            ACC_RES_PER_G, GYRO_RES_PER_DEG_S, STICK_MAX, TOUCHPAD_HEIGHT, TOUCHPAD_WIDTH,
+            TRIGGER_MAX,
+        },
+    },
+    input::{
+        battery::{BatteryState, BatteryStatus},
+        capability::{
+            Capability, Gamepad, GamepadAxis, GamepadButton, GamepadTrigger, Touch, TouchButton,
+            Touchpad,
+        },
+        composite_device::Command,
+        event::{native::NativeEvent, value::InputValue, Event},
+    },
+    logger,
+};
+
+/// DualShock 4 implementation of HIDRAW interface
+#[derive(Debug)]
+pub struct DualShock4Controller {
+    info: DeviceInfo,
+    composite_tx: mpsc::Sender<Command>,
+    device_id: String,
+}
+
+impl DualShock4Controller {
+    pub fn new(info: DeviceInfo, composite_tx: mpsc::Sender<Command>, device_id: String) -> Self {
+        Self {
+            info,
+            composite_tx,
+            device_id,
+        }
+    }
+
+    pub async fn run(&self) -> Result<(), Box<dyn Error>> {
+        log::debug!("Starting DualShock 4 driver");
+        let path = self.info.path().to_string_lossy().to_string();
+        let tx = self.composite_tx.clone();
+
+        // Spawn a blocking task to read the events
+        let device_id = self.device_id.clone();
+        let task =
+            logger::spawn_blocking_in_scope(move || -> Result<(), Box<dyn Error + Send + Sync>> {
+                let mut driver = Driver::new(path)?;
+                loop {
+                    let events = driver.poll()?;
+                    let read_time = driver.last_report_time();
+                    for event in events.iter() {
+                        let event::Event::Status(event::StatusEvent::Battery(status)) = event
+                        else {
+                            continue;
+                        };
+                        tx.blocking_send(Command::SourceDeviceBattery(
+                            device_id.clone(),
+                            translate_battery(status),
+                        ))?;
+                    }
+                    for mut event in translate_events(events) {
+                        event.set_timestamp(read_time);
+                        tx.blocking_send(Command::ProcessEvent(
+                            device_id.clone(),
+                            Event::Native(event),
+                        ))?;
+                    }
+                }
+            });
+
+        // Wait for the task to finish
+        if let Err(e) = task.await? {
+            return Err(e.to_string().into());
+        }
+
+        log::debug!("DualShock 4 driver stopped");
+
+        Ok(())
+    }
+}
+
+/// Translate the given DualShock 4 battery input into a battery state
+fn translate_battery(battery: &event::BatteryInput) -> BatteryState {
+    let status = match (battery.cable_connected, battery.level) {
+        (true, 100) => BatteryStatus::Full,
+        (true, _) => BatteryStatus::Charging,
+        (false, _) => BatteryStatus::Discharging,
+    };
+    BatteryState::new(battery.level, status)
+}
+
+/// Returns a value between -1.0 and 1.0 for the given raw stick value
+fn normalize_stick_value(raw_value: u8) -> f64 {
+    let mid = STICK_MAX / 2.0;
+    (raw_value as f64 - mid) / mid
+}
+
+/// Translate the given DualShock 4 events into native events
+fn translate_events(events: Vec<event::Event>) -> Vec<NativeEvent> {
+    events.into_iter().filter_map(translate_event).collect()
+}
+
+/// Translate the given DualShock 4 event into a native event
+fn translate_event(event: event::Event) -> Option<NativeEvent> {
+    let event = match event {
+        event::Event::Button(button) => {
+            let (capability, value) = match button {
+                event::ButtonEvent::Cross(value) => (GamepadButton::South, value),
+                event::ButtonEvent::Circle(value) => (GamepadButton::East, value),
+                event::ButtonEvent::Square(value) => (GamepadButton::West, value),
+                event::ButtonEvent::Triangle(value) => (GamepadButton::North, value),
+                event::ButtonEvent::Share(value) => (GamepadButton::Select, value),
+                event::ButtonEvent::Options(value) => (GamepadButton::Start, value),
+                event::ButtonEvent::PS(value) => (GamepadButton::Guide, value),
+                event::ButtonEvent::DPadUp(value) => (GamepadButton::DPadUp, value),
+                event::ButtonEvent::DPadDown(value) => (GamepadButton::DPadDown, value),
+                event::ButtonEvent::DPadLeft(value) => (GamepadButton::DPadLeft, value),
+                event::ButtonEvent::DPadRight(value) => (GamepadButton::DPadRight, value),
+                event::ButtonEvent::L1(value) => (GamepadButton::LeftBumper, value),
+                event::ButtonEvent::L2(value) => (GamepadButton::LeftTrigger, value),
+                event::ButtonEvent::L3(value) => (GamepadButton::LeftStick, value),
+                event::ButtonEvent::R1(value) => (GamepadButton::RightBumper, value),
+                event::ButtonEvent::R2(value) => (GamepadButton::RightTrigger, value),
+                event::ButtonEvent::R3(value) => (GamepadButton::RightStick, value),
+                event::ButtonEvent::TouchpadPress(value) => {
+                    return Some(NativeEvent::new(
+                        Capability::Touchpad(Touchpad::CenterPad(Touch::Button(
+                            TouchButton::Press,
+                        ))),
+                        InputValue::Bool(value.pressed),
+                    ))
+                }
+            };
+            NativeEvent::new(
+                Capability::Gamepad(Gamepad::Button(capability)),
+                InputValue::Bool(value.pressed),
+            )
+        }
+        event::Event::Accelerometer(accel) => match accel {
+            // Accelerometer values are measured in meters per second squared
+            event::AccelerometerEvent::Accelerometer(value) => NativeEvent::new(
+                Capability::Gamepad(Gamepad::Accelerometer),
+                InputValue::Vector3 {
+                    x: Some(value.x as f64 / ACC_RES_PER_G * 9.8),
+                    y: Some(value.y as f64 / ACC_RES_PER_G * 9.8),
+                    z: Some(value.z as f64 / ACC_RES_PER_G * 9.8),
+                },
+            ),
+            // Gyro values are measured in degrees per second
+            event::AccelerometerEvent::Gyro(value) => NativeEvent::new(
+                Capability::Gamepad(Gamepad::Gyro),
+                InputValue::Vector3 {
+                    x: Some(value.x as f64 / GYRO_RES_PER_DEG_S),
+                    y: Some(value.y as f64 / GYRO_RES_PER_DEG_S),
+                    z: Some(value.z as f64 / GYRO_RES_PER_DEG_S),
+                },
+            ),
+        },
+        event::Event::Axis(axis) => match axis {
+            event::AxisEvent::Touchpad(value) => NativeEvent::new(
+                Capability::Touchpad(Touchpad::CenterPad(Touch::Motion)),
+                InputValue::Touch {
+                    index: value.index,
+                    is_touching: value.is_touching,
+                    x: Some(value.x as f64 / TOUCHPAD_WIDTH),
+                    y: Some(value.y as f64 / TOUCHPAD_HEIGHT),
+                    pressure: None,
+                },
+            ),
+            event::AxisEvent::LStick(value) => NativeEvent::new(
+                Capability::Gamepad(Gamepad::Axis(GamepadAxis::LeftStick)),
+                InputValue::Vector2 {
+                    x: Some(normalize_stick_value(value.x)),
+                    y: Some(normalize_stick_value(value.y)),
+                },
+            ),
+            event::AxisEvent::RStick(value) => NativeEvent::new(
+                Capability::Gamepad(Gamepad::Axis(GamepadAxis::RightStick)),
+                InputValue::Vector2 {
+                    x: Some(normalize_stick_value(value.x)),
+                    y: Some(normalize_stick_value(value.y)),
+                },
+            ),
+        },
+        event::Event::Trigger(trigg) => {
+            let (trigger, value) = match trigg {
+                event::TriggerEvent::L2(value) => (GamepadTrigger::LeftTrigger, value),
+                event::TriggerEvent::R2(value) => (GamepadTrigger::RightTrigger, value),
+            };
+            NativeEvent::new(
+                Capability::Gamepad(Gamepad::Trigger(trigger)),
+                InputValue::Float(value.value as f64 / TRIGGER_MAX),
+            )
+        }
+        // Status events are sent to the composite device directly
+        event::Event::Status(_) => return None,
+    };
+
+    Some(event)
+}
+
+/// List of all capabilities that the DualShock 4 driver implements
+pub const CAPABILITIES: &[Capability] = &[
+    Capability::Gamepad(Gamepad::Accelerometer),
+    Capability::Gamepad(Gamepad::Axis(GamepadAxis::LeftStick)),
+    Capability::Gamepad(Gamepad::Axis(GamepadAxis::RightStick)),
+    Capability::Gamepad(Gamepad::Button(GamepadButton::DPadDown)),
+    Capability::Gamepad(Gamepad::Button(GamepadButton::DPadLeft)),
+    Capability::Gamepad(Gamepad::Button(GamepadButton::DPadRight)),
+    Capability::Gamepad(Gamepad::Button(GamepadButton::DPadUp)),
+    Capability::Gamepad(Gamepad::Button(GamepadButton::East)),
+    Capability::Gamepad(Gamepad::Button(GamepadButton::Guide)),
+    Capability::Gamepad(Gamepad::Button(GamepadButton::LeftBumper)),
+    Capability::Gamepad(Gamepad::Button(GamepadButton::LeftStick)),
+    Capability::Gamepad(Gamepad::Button(GamepadButton::LeftTrigger)),
+    Capability::Gamepad(Gamepad::Button(GamepadButton::North)),
+    Capability::Gamepad(Gamepad::Button(GamepadButton::RightBumper)),
+    Capability::Gamepad(Gamepad::Button(GamepadButton::RightStick)),
+    Capability::Gamepad(Gamepad::Button(GamepadButton::RightTrigger)),
+    Capability::Gamepad(Gamepad::Button(GamepadButton::Select)),
+    Capability::Gamepad(Gamepad::Button(GamepadButton::South)),
+    Capability::Gamepad(Gamepad::Button(GamepadButton::Start)),
+    Capability::Gamepad(Gamepad::Button(GamepadButton::West)),
+    Capability::Gamepad(Gamepad::Gyro),
+    Capability::Gamepad(Gamepad::Trigger(GamepadTrigger::LeftTrigger)),
+    Capability::Gamepad(Gamepad::Trigger(GamepadTrigger::RightTrigger)),
+    Capability::Touchpad(Touchpad::CenterPad(Touch::Button(TouchButton::Press))),
+    Capability::Touchpad(Touchpad::CenterPad(Touch::Motion)),
+];
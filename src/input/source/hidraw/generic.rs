@@ -0,0 +1,270 @@
+use std::error::Error;
+
+use hidapi::DeviceInfo;
+use tokio::sync::mpsc;
+
+use crate::{
+    drivers::generic_hid::{
+        descriptor::{
+            ReportDescriptor, Usage, USAGE_DPAD_DOWN, USAGE_DPAD_LEFT, USAGE_DPAD_RIGHT,
+            USAGE_DPAD_UP, USAGE_HAT_SWITCH, USAGE_PAGE_BUTTON, USAGE_PAGE_GENERIC_DESKTOP,
+            USAGE_RX, USAGE_RY, USAGE_RZ, USAGE_X, USAGE_Y, USAGE_Z,
+        },
+        driver::{self, Driver},
+        event,
+    },
+    input::{
+        capability::{Capability, Gamepad, GamepadAxis, GamepadButton, GamepadTrigger},
+        composite_device::Command,
+        event::{native::NativeEvent, value::InputValue, Event},
+    },
+    logger,
+};
+
+/// D-Pad buttons reported by a hat switch
+const HAT_BUTTONS: [GamepadButton; 4] = [
+    GamepadButton::DPadUp,
+    GamepadButton::DPadRight,
+    GamepadButton::DPadDown,
+    GamepadButton::DPadLeft,
+];
+
+/// Generic HID gamepad implementation of HIDRAW interface, used for gamepads
+/// and joysticks without a dedicated driver
+#[derive(Debug)]
+pub struct GenericController {
+    info: DeviceInfo,
+    composite_tx: mpsc::Sender<Command>,
+    device_id: String,
+}
+
+impl GenericController {
+    pub fn new(info: DeviceInfo, composite_tx: mpsc::Sender<Command>, device_id: String) -> Self {
+        Self {
+            info,
+            composite_tx,
+            device_id,
+        }
+    }
+
+    pub async fn run(&self) -> Result<(), Box<dyn Error>> {
+        log::debug!("Starting generic HID gamepad driver");
+        let path = self.info.path().to_string_lossy().to_string();
+        let tx = self.composite_tx.clone();
+
+        // Spawn a blocking task to read the events
+        let device_id = self.device_id.clone();
+        let task =
+            logger::spawn_blocking_in_scope(move || -> Result<(), Box<dyn Error + Send + Sync>> {
+                let mut driver = Driver::new(path)?;
+                loop {
+                    let events = driver.poll()?;
+                    let read_time = driver.last_report_time();
+                    for mut event in translate_events(events) {
+                        event.set_timestamp(read_time);
+                        tx.blocking_send(Command::ProcessEvent(
+                            device_id.clone(),
+                            Event::Native(event),
+                        ))?;
+                    }
+                }
+            });
+
+        // Wait for the task to finish
+        if let Err(e) = task.await? {
+            return Err(e.to_string().into());
+        }
+
+        log::debug!("Generic HID gamepad driver stopped");
+
+        Ok(())
+    }
+}
+
+/// Returns the report descriptor of the given device if it describes a
+/// gamepad or joystick
+pub fn find_gamepad_descriptor(info: &DeviceInfo) -> Option<ReportDescriptor> {
+    let path = info.path().to_string_lossy().to_string();
+    let descriptor = match driver::read_descriptor(path.clone()) {
+        Ok(descriptor) => descriptor,
+        Err(e) => {
+            log::debug!("Unable to read report descriptor of {path}: {e:?}");
+            return None;
+        }
+    };
+    descriptor.is_gamepad().then_some(descriptor)
+}
+
+/// Returns the capabilities of the gamepad described by the given descriptor
+pub fn get_capabilities(descriptor: &ReportDescriptor) -> Vec<Capability> {
+    let mut capabilities = Vec::new();
+    for field in descriptor.fields.iter() {
+        for capability in usage_capabilities(field.usage) {
+            if !capabilities.contains(&capability) {
+                capabilities.push(capability);
+            }
+        }
+    }
+    capabilities
+}
+
+/// Returns the capabilities implemented by the given usage
+fn usage_capabilities(usage: Usage) -> Vec<Capability> {
+    if usage.page == USAGE_PAGE_GENERIC_DESKTOP && usage.id == USAGE_HAT_SWITCH {
+        return HAT_BUTTONS
+            .into_iter()
+            .map(|button| Capability::Gamepad(Gamepad::Button(button)))
+            .collect();
+    }
+    if let Some(button) = button_for_usage(usage) {
+        return vec![Capability::Gamepad(Gamepad::Button(button))];
+    }
+    if usage.page != USAGE_PAGE_GENERIC_DESKTOP {
+        return Vec::new();
+    }
+    let capability = match usage.id {
+        USAGE_X | USAGE_Y => Gamepad::Axis(GamepadAxis::LeftStick),
+        USAGE_RX | USAGE_RY => Gamepad::Axis(GamepadAxis::RightStick),
+        USAGE_Z => Gamepad::Trigger(GamepadTrigger::LeftTrigger),
+        USAGE_RZ => Gamepad::Trigger(GamepadTrigger::RightTrigger),
+        _ => return Vec::new(),
+    };
+    vec![Capability::Gamepad(capability)]
+}
+
+/// Returns the gamepad button for the given usage. Buttons are numbered in
+/// the same order the kernel uses for gamepads, where buttons 3 and 6 (C and
+/// Z) have no equivalent.
+fn button_for_usage(usage: Usage) -> Option<GamepadButton> {
+    let button = match (usage.page, usage.id) {
+        (USAGE_PAGE_BUTTON, 1) => GamepadButton::South,
+        (USAGE_PAGE_BUTTON, 2) => GamepadButton::East,
+        (USAGE_PAGE_BUTTON, 4) => GamepadButton::North,
+        (USAGE_PAGE_BUTTON, 5) => GamepadButton::West,
+        (USAGE_PAGE_BUTTON, 7) => GamepadButton::LeftBumper,
+        (USAGE_PAGE_BUTTON, 8) => GamepadButton::RightBumper,
+        (USAGE_PAGE_BUTTON, 9) => GamepadButton::LeftTrigger,
+        (USAGE_PAGE_BUTTON, 10) => GamepadButton::RightTrigger,
+        (USAGE_PAGE_BUTTON, 11) => GamepadButton::Select,
+        (USAGE_PAGE_BUTTON, 12) => GamepadButton::Start,
+        (USAGE_PAGE_BUTTON, 13) => GamepadButton::Guide,
+        (USAGE_PAGE_BUTTON, 14) => GamepadButton::LeftStick,
+        (USAGE_PAGE_BUTTON, 15) => GamepadButton::RightStick,
+        (USAGE_PAGE_GENERIC_DESKTOP, USAGE_DPAD_UP) => GamepadButton::DPadUp,
+        (USAGE_PAGE_GENERIC_DESKTOP, USAGE_DPAD_DOWN) => GamepadButton::DPadDown,
+        (USAGE_PAGE_GENERIC_DESKTOP, USAGE_DPAD_RIGHT) => GamepadButton::DPadRight,
+        (USAGE_PAGE_GENERIC_DESKTOP, USAGE_DPAD_LEFT) => GamepadButton::DPadLeft,
+        _ => return None,
+    };
+    Some(button)
+}
+
+/// Returns a value between -1.0 and 1.0 for the given axis value
+fn normalize_axis_value(value: &event::AxisInput) -> f64 {
+    let mid = (value.logical_max as f64 + value.logical_min as f64) / 2.0;
+    let range = (value.logical_max as f64 - value.logical_min as f64) / 2.0;
+    if range <= 0.0 {
+        return 0.0;
+    }
+    ((value.value as f64 - mid) / range).clamp(-1.0, 1.0)
+}
+
+/// Returns a value between 0.0 and 1.0 for the given trigger value
+fn normalize_trigger_value(value: &event::AxisInput) -> f64 {
+    let range = value.logical_max as f64 - value.logical_min as f64;
+    if range <= 0.0 {
+        return 0.0;
+    }
+    ((value.value as f64 - value.logical_min as f64) / range).clamp(0.0, 1.0)
+}
+
+/// Returns the pressed state of the up, right, down and left D-Pad buttons
+/// for the given hat switch value. Hat switches report eight directions
+/// clockwise from north, any other value means the hat is centered.
+fn hat_directions(value: &event::AxisInput) -> [bool; 4] {
+    match value.value - value.logical_min {
+        0 => [true, false, false, false],
+        1 => [true, true, false, false],
+        2 => [false, true, false, false],
+        3 => [false, true, true, false],
+        4 => [false, false, true, false],
+        5 => [false, false, true, true],
+        6 => [false, false, false, true],
+        7 => [true, false, false, true],
+        _ => [false; 4],
+    }
+}
+
+/// Translate the given generic HID events into native events
+fn translate_events(events: Vec<event::Event>) -> Vec<NativeEvent> {
+    events.into_iter().flat_map(translate_event).collect()
+}
+
+/// Translate the given generic HID event into native events
+fn translate_event(event: event::Event) -> Vec<NativeEvent> {
+    match event {
+        event::Event::Button(value) => {
+            let Some(button) = button_for_usage(value.usage) else {
+                return Vec::new();
+            };
+            vec![NativeEvent::new(
+                Capability::Gamepad(Gamepad::Button(button)),
+                InputValue::Bool(value.pressed),
+            )]
+        }
+        event::Event::Axis(value) => {
+            let (capability, value) = match value.usage.id {
+                USAGE_X => (
+                    Gamepad::Axis(GamepadAxis::LeftStick),
+                    InputValue::Vector2 {
+                        x: Some(normalize_axis_value(&value)),
+                        y: None,
+                    },
+                ),
+                USAGE_Y => (
+                    Gamepad::Axis(GamepadAxis::LeftStick),
+                    InputValue::Vector2 {
+                        x: None,
+                        y: Some(normalize_axis_value(&value)),
+                    },
+                ),
+                USAGE_RX => (
+                    Gamepad::Axis(GamepadAxis::RightStick),
+                    InputValue::Vector2 {
+                        x: Some(normalize_axis_value(&value)),
+                        y: None,
+                    },
+                ),
+                USAGE_RY => (
+                    Gamepad::Axis(GamepadAxis::RightStick),
+                    InputValue::Vector2 {
+                        x: None,
+                        y: Some(normalize_axis_value(&value)),
+                    },
+                ),
+                USAGE_Z => (
+                    Gamepad::Trigger(GamepadTrigger::LeftTrigger),
+                    InputValue::Float(normalize_trigger_value(&value)),
+                ),
+                USAGE_RZ => (
+                    Gamepad::Trigger(GamepadTrigger::RightTrigger),
+                    InputValue::Float(normalize_trigger_value(&value)),
+                ),
+                USAGE_HAT_SWITCH => {
+                    return HAT_BUTTONS
+                        .into_iter()
+                        .zip(hat_directions(&value))
+                        .map(|(button, pressed)| {
+                            NativeEvent::new(
+                                Capability::Gamepad(Gamepad::Button(button)),
+                                InputValue::Bool(pressed),
+                            )
+                        })
+                        .collect();
+                }
+                _ => return Vec::new(),
+            };
+            vec![NativeEvent::new(Capability::Gamepad(capability), value)]
+        }
+    }
+}
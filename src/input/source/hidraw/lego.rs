@@ -1,55 +1,190 @@
-use std::{error::Error, thread, time};
+use std::{collections::HashMap, error::Error, thread, time};
 
+use evdev::{FFEffectData, FFEffectKind};
 use hidapi::DeviceInfo;
-use tokio::sync::mpsc;
+use tokio::sync::{
+    mpsc::{self, error::TryRecvError},
+    watch,
+};
 
 use crate::{
-    drivers::lego::{
-        driver::{self, Driver},
-        event,
+    drivers::{
+        dualsense::hid_report::SetStatePackedOutputData,
+        lego::{
+            driver::{self, Driver},
+            event,
+        },
     },
     input::{
+        battery::{BatteryState, BatteryStatus},
         capability::{
             Capability, Gamepad, GamepadAxis, GamepadButton, GamepadTrigger, Mouse, MouseButton,
             Touch, Touchpad,
         },
         composite_device::Command,
         event::{native::NativeEvent, value::InputValue, Event},
+        output_event::OutputEvent,
+        source::SourceCommand,
     },
+    logger,
 };
 
+/// Range of the controller accelerometers in meters per second squared
+const ACCEL_RANGE: f64 = 2.0 * 9.8;
+
+/// How the accelerometer streams of the left and right controllers are
+/// combined into a single accelerometer capability.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ImuFusion {
+    /// Only use the left controller
+    Left,
+    /// Only use the right controller
+    Right,
+    /// Average both controllers. If only one controller reports data, its
+    /// values are used as-is.
+    #[default]
+    Average,
+}
+
+impl ImuFusion {
+    /// Returns the [ImuFusion] for the given config value (e.g. "left")
+    pub fn from_config(value: &str) -> Self {
+        match value {
+            "left" => ImuFusion::Left,
+            "right" => ImuFusion::Right,
+            "average" => ImuFusion::Average,
+            _ => {
+                log::warn!("Invalid IMU fusion mode '{value}', using 'average'");
+                ImuFusion::Average
+            }
+        }
+    }
+}
+
 /// Legion Go implementation of HIDRAW interface
 #[derive(Debug)]
 pub struct LegionController {
     info: DeviceInfo,
+    fusion: ImuFusion,
+    /// Firmware touchpad setting to apply when the device is opened
+    touchpad_enabled: Option<bool>,
     composite_tx: mpsc::Sender<Command>,
+    rx: Option<mpsc::Receiver<SourceCommand>>,
+    /// Publishes the time of the last input report to detect stalls
+    report_tx: Option<watch::Sender<time::Instant>>,
     device_id: String,
 }
 
 impl LegionController {
-    pub fn new(info: DeviceInfo, composite_tx: mpsc::Sender<Command>, device_id: String) -> Self {
+    pub fn new(
+        info: DeviceInfo,
+        fusion: ImuFusion,
+        touchpad_enabled: Option<bool>,
+        composite_tx: mpsc::Sender<Command>,
+        rx: mpsc::Receiver<SourceCommand>,
+        report_tx: watch::Sender<time::Instant>,
+        device_id: String,
+    ) -> Self {
         Self {
             info,
+            fusion,
+            touchpad_enabled,
             composite_tx,
+            rx: Some(rx),
+            report_tx: Some(report_tx),
             device_id,
         }
     }
 
-    pub async fn run(&self) -> Result<(), Box<dyn Error>> {
+    pub async fn run(&mut self) -> Result<(), Box<dyn Error>> {
         log::debug!("Starting Legion Controller driver");
+        let rx = self.rx.take().unwrap();
+        let report_tx = self.report_tx.take().unwrap();
         let path = self.info.path().to_string_lossy().to_string();
         let tx = self.composite_tx.clone();
 
         // Spawn a blocking task to read the events
         let device_path = path.clone();
         let device_id = self.device_id.clone();
+        let fusion = self.fusion;
+        let touchpad_enabled = self.touchpad_enabled;
         let task =
-            tokio::task::spawn_blocking(move || -> Result<(), Box<dyn Error + Send + Sync>> {
-                let mut driver = Driver::new(device_path.clone())?;
+            logger::spawn_blocking_in_scope(move || -> Result<(), Box<dyn Error + Send + Sync>> {
+                let mut output_handler = LegionOutput::new(rx);
+                let open = || -> Result<Driver, Box<dyn Error + Send + Sync>> {
+                    let mut driver = Driver::new(device_path.clone())?;
+                    if let Some(enabled) = touchpad_enabled {
+                        log::debug!("Setting touchpad enabled to {enabled} for {device_id}");
+                        driver.set_touchpad_enabled(enabled)?;
+                    }
+                    Ok(driver)
+                };
+                let mut driver = open()?;
                 loop {
+                    let last_report = driver.last_report_time();
                     let events = driver.poll()?;
-                    let native_events = translate_events(events);
-                    for event in native_events {
+                    let read_time = driver.last_report_time();
+                    if read_time != last_report {
+                        report_tx.send_replace(read_time);
+                    }
+
+                    // Report the battery levels and connection state of the
+                    // detachable controllers
+                    for event in events.iter() {
+                        let event::Event::Status(status) = event else {
+                            continue;
+                        };
+                        let command = match status {
+                            event::StatusEvent::LeftControllerBattery(status) => {
+                                Command::SourceDeviceBattery(
+                                    format!("{device_id}/left"),
+                                    BatteryState::new(status.value, BatteryStatus::Unknown),
+                                )
+                            }
+                            event::StatusEvent::RightControllerBattery(status) => {
+                                Command::SourceDeviceBattery(
+                                    format!("{device_id}/right"),
+                                    BatteryState::new(status.value, BatteryStatus::Unknown),
+                                )
+                            }
+                            event::StatusEvent::LeftControllerConnected(input) => {
+                                log::info!(
+                                    "Legion Go left controller connected: {}",
+                                    input.pressed
+                                );
+                                Command::SourceDeviceDetached(
+                                    format!("{device_id}/left"),
+                                    !input.pressed,
+                                    LEFT_CONTROLLER_CAPABILITIES.to_vec(),
+                                )
+                            }
+                            event::StatusEvent::RightControllerConnected(input) => {
+                                log::info!(
+                                    "Legion Go right controller connected: {}",
+                                    input.pressed
+                                );
+                                Command::SourceDeviceDetached(
+                                    format!("{device_id}/right"),
+                                    !input.pressed,
+                                    RIGHT_CONTROLLER_CAPABILITIES.to_vec(),
+                                )
+                            }
+                            _ => continue,
+                        };
+                        tx.blocking_send(command)?;
+                    }
+
+                    // Input is not forwarded while another program has the
+                    // device
+                    let mut native_events = Vec::new();
+                    if output_handler.grabbed {
+                        if let Some(event) = combine_accel(fusion, &events) {
+                            native_events.push(event);
+                        }
+                        native_events.extend(translate_events(events));
+                    }
+                    for mut event in native_events {
+                        event.set_timestamp(read_time);
                         // Don't send un-implemented events
                         if matches!(event.as_capability(), Capability::NotImplemented) {
                             continue;
@@ -60,10 +195,33 @@ impl LegionController {
                         ))?;
                     }
 
+                    // Receive commands/output events
+                    match output_handler.receive_commands(&mut driver) {
+                        Ok(false) => (),
+                        Ok(true) => {
+                            log::debug!("Device stop requested");
+                            break;
+                        }
+                        Err(e) => {
+                            log::debug!("Error receiving commands: {:?}", e);
+                            break;
+                        }
+                    }
+
+                    // Re-open the device if the composite device detected a
+                    // stall
+                    if output_handler.reopen_requested {
+                        output_handler.reopen_requested = false;
+                        log::debug!("Re-opening device {device_id}");
+                        driver = open()?;
+                    }
+
                     // Polling interval is about 4ms so we can sleep a little
                     let duration = time::Duration::from_micros(250);
                     thread::sleep(duration);
                 }
+
+                Ok(())
             });
 
         // Wait for the task to finish
@@ -77,6 +235,227 @@ impl LegionController {
     }
 }
 
+/// Manages handling output events and source device commands
+#[derive(Debug)]
+struct LegionOutput {
+    rx: mpsc::Receiver<SourceCommand>,
+    ff_evdev_effects: HashMap<i16, FFEffectData>,
+    /// Whether the device is in use. While released, no output reports are
+    /// written so another program (e.g. Steam) can control the device.
+    grabbed: bool,
+    /// Whether the device should be re-opened
+    reopen_requested: bool,
+}
+
+impl LegionOutput {
+    pub fn new(rx: mpsc::Receiver<SourceCommand>) -> Self {
+        Self {
+            rx,
+            ff_evdev_effects: HashMap::new(),
+            grabbed: true,
+            reopen_requested: false,
+        }
+    }
+
+    /// Read commands sent to this device from the channel until it is
+    /// empty. Returns `true` if the device was asked to stop.
+    fn receive_commands(&mut self, driver: &mut Driver) -> Result<bool, Box<dyn Error>> {
+        const MAX_COMMANDS: u8 = 64;
+        let mut commands_processed = 0;
+        loop {
+            match self.rx.try_recv() {
+                Ok(cmd) => match cmd {
+                    SourceCommand::UploadEffect(data, composite_dev) => {
+                        self.upload_ff_effect(data, composite_dev);
+                    }
+                    SourceCommand::UpdateEffect(id, data) => {
+                        self.update_ff_effect(id, data);
+                    }
+                    SourceCommand::EraseEffect(id, composite_dev) => {
+                        self.erase_ff_effect(id, composite_dev);
+                    }
+                    SourceCommand::WriteEvent(event) => {
+                        log::trace!("Received output event: {:?}", event);
+                        if !self.grabbed {
+                            log::trace!("Ignoring output event while device is released");
+                            continue;
+                        }
+                        match event {
+                            OutputEvent::Evdev(input_event) => {
+                                if let Err(e) = self.process_evdev_ff(driver, input_event) {
+                                    log::error!("Failed to write output event: {:?}", e);
+                                }
+                            }
+                            OutputEvent::DualSense(report) => {
+                                log::debug!("Received DualSense output report");
+                                if report.use_rumble_not_haptics
+                                    || report.enable_improved_rumble_emulation
+                                {
+                                    if let Err(e) = self.process_dualsense_ff(driver, report) {
+                                        log::error!(
+                                            "Failed to process dualsense output report: {:?}",
+                                            e
+                                        );
+                                    }
+                                }
+                            }
+                            OutputEvent::Rumble { left, right } => {
+                                if let Err(e) = driver.haptic_rumble(left, right) {
+                                    log::error!("Failed to do rumble: {:?}", e);
+                                }
+                            }
+                            OutputEvent::Uinput(_) => (),
+                            OutputEvent::SteamDeckHaptics(_) => (),
+                            OutputEvent::Led(_) => (),
+                        }
+                    }
+                    SourceCommand::SetGrab(grab) => {
+                        log::debug!("Setting grab on device to {grab}");
+                        self.grabbed = grab;
+                    }
+                    SourceCommand::Reopen => self.reopen_requested = true,
+                    SourceCommand::Stop => return Ok(true),
+                },
+                Err(e) => match e {
+                    TryRecvError::Empty => return Ok(false),
+                    TryRecvError::Disconnected => {
+                        log::debug!("Receive channel disconnected");
+                        return Err("Receive channel disconnected".into());
+                    }
+                },
+            };
+
+            commands_processed += 1;
+            if commands_processed >= MAX_COMMANDS {
+                return Ok(false);
+            }
+        }
+    }
+
+    /// Returns the next available evdev effect id
+    fn next_ff_effect_id(&self) -> i16 {
+        const MAX: i16 = 2096;
+        let mut i = 0;
+        loop {
+            if !self.ff_evdev_effects.contains_key(&i) {
+                return i;
+            }
+            i += 1;
+            if i > MAX {
+                return -1;
+            }
+        }
+    }
+
+    /// Upload the given effect data to the device and send the result to
+    /// the composite device.
+    fn upload_ff_effect(
+        &mut self,
+        data: evdev::FFEffectData,
+        composite_dev: std::sync::mpsc::Sender<Result<i16, Box<dyn Error + Send + Sync>>>,
+    ) {
+        log::debug!("Uploading FF effect data");
+        let id = self.next_ff_effect_id();
+        if id == -1 {
+            if let Err(e) = composite_dev.send(Err("Maximum FF effects uploaded".into())) {
+                log::error!("Failed to send upload result: {:?}", e);
+            }
+            return;
+        }
+
+        self.ff_evdev_effects.insert(id, data);
+        if let Err(e) = composite_dev.send(Ok(id)) {
+            log::error!("Failed to send upload result: {:?}", e);
+        }
+    }
+
+    /// Update the effect with the given id using the given effect data.
+    fn update_ff_effect(&mut self, id: i16, data: FFEffectData) {
+        log::debug!("Updating FF effect data with id {id}");
+        self.ff_evdev_effects.insert(id, data);
+    }
+
+    /// Erase the effect from the device with the given effect id and send the
+    /// result to the composite device.
+    fn erase_ff_effect(
+        &mut self,
+        id: i16,
+        composite_dev: std::sync::mpsc::Sender<Result<(), Box<dyn Error + Send + Sync>>>,
+    ) {
+        log::debug!("Erasing FF effect data");
+        self.ff_evdev_effects.remove(&id);
+        if let Err(err) = composite_dev.send(Ok(())) {
+            log::error!("Failed to send erase result: {:?}", err);
+        }
+    }
+
+    /// Process evdev force feedback events. Evdev events will send events with
+    /// the effect id set in the 'code' field.
+    fn process_evdev_ff(
+        &self,
+        driver: &mut Driver,
+        input_event: evdev::InputEvent,
+    ) -> Result<(), Box<dyn Error>> {
+        // Get the code (effect id) and value of the event
+        let (code, value) =
+            if let evdev::EventSummary::ForceFeedback(_, code, value) = input_event.destructure() {
+                (code, value)
+            } else {
+                log::debug!("Unhandled evdev output event: {:?}", input_event);
+                return Ok(());
+            };
+
+        // Find the effect data for this event
+        let effect_id = code.0 as i16;
+        let Some(effect_data) = self.ff_evdev_effects.get(&effect_id) else {
+            log::warn!("No effect id found: {}", code.0);
+            return Ok(());
+        };
+
+        // The value determines if the effect should be playing or not.
+        if value == 0 {
+            log::trace!("Stopping rumble");
+            if let Err(e) = driver.haptic_rumble(0, 0) {
+                log::debug!("Failed to stop rumble: {:?}", e);
+            }
+            return Ok(());
+        }
+
+        // Only rumble effects are supported by the controllers
+        // TODO: handle effect duration, etc.
+        if let FFEffectKind::Rumble {
+            strong_magnitude,
+            weak_magnitude,
+        } = effect_data.kind
+        {
+            let left_speed = (strong_magnitude >> 8) as u8;
+            let right_speed = (weak_magnitude >> 8) as u8;
+            if let Err(e) = driver.haptic_rumble(left_speed, right_speed) {
+                let err = format!("Failed to do rumble: {:?}", e);
+                return Err(err.into());
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Process dualsense force feedback output reports
+    fn process_dualsense_ff(
+        &self,
+        driver: &mut Driver,
+        report: SetStatePackedOutputData,
+    ) -> Result<(), Box<dyn Error>> {
+        // Set the rumble values based on the DualSense output report
+        let left_speed = report.rumble_emulation_left;
+        let right_speed = report.rumble_emulation_right;
+        if let Err(e) = driver.haptic_rumble(left_speed, right_speed) {
+            let err = format!("Failed to do rumble: {:?}", e);
+            return Err(err.into());
+        }
+        Ok(())
+    }
+}
+
 /// Returns a value between -1.0 and 1.0 based on the given value with its
 /// minimum and maximum values.
 fn normalize_signed_value(raw_value: f64, min: f64, max: f64) -> f64 {
@@ -123,6 +502,7 @@ fn normalize_axis_value(event: event::AxisEvent) -> InputValue {
                 is_touching: value.is_touching,
                 x,
                 y,
+                pressure: None,
             }
         }
         event::AxisEvent::LStick(value) => {
@@ -181,9 +561,69 @@ fn normalize_trigger_value(event: event::TriggerEvent) -> InputValue {
     }
 }
 
+/// Combine the accelerometer events of the left and right controllers into a
+/// single accelerometer event using the given [ImuFusion] mode.
+fn combine_accel(fusion: ImuFusion, events: &[event::Event]) -> Option<NativeEvent> {
+    let mut left = None;
+    let mut right = None;
+    for event in events {
+        match event {
+            event::Event::Gyro(event::GyroEvent::LeftGyro(value)) => left = Some(value),
+            event::Event::Gyro(event::GyroEvent::RightGyro(value)) => right = Some(value),
+            _ => (),
+        }
+    }
+
+    let to_accel = |value: &event::GyroInput| {
+        let x = normalize_signed_value(value.x as f64, 0.0, 255.0) * ACCEL_RANGE;
+        let y = normalize_signed_value(value.y as f64, 0.0, 255.0) * ACCEL_RANGE;
+        (x, y)
+    };
+    let (x, y) = match fusion {
+        ImuFusion::Left => to_accel(left?),
+        ImuFusion::Right => to_accel(right?),
+        ImuFusion::Average => match (left, right) {
+            (Some(left), Some(right)) => {
+                let (left_x, left_y) = to_accel(left);
+                let (right_x, right_y) = to_accel(right);
+                ((left_x + right_x) / 2.0, (left_y + right_y) / 2.0)
+            }
+            (Some(value), None) | (None, Some(value)) => to_accel(value),
+            (None, None) => return None,
+        },
+    };
+
+    // The controllers do not report the z axis
+    Some(NativeEvent::new(
+        Capability::Gamepad(Gamepad::Accelerometer),
+        InputValue::Vector3 {
+            x: Some(x),
+            y: Some(y),
+            z: None,
+        },
+    ))
+}
+
 /// Translate the given Legion Go events into native events
 fn translate_events(events: Vec<event::Event>) -> Vec<NativeEvent> {
-    events.into_iter().map(translate_event).collect()
+    let mut native_events = Vec::with_capacity(events.len());
+    for event in events {
+        let is_wheel = matches!(
+            event,
+            event::Event::Trigger(event::TriggerEvent::MouseWheel(_))
+        );
+        let native_event = translate_event(event);
+
+        // Wheel steps have no release, so release the wheel button right away
+        if is_wheel {
+            let release = NativeEvent::new(native_event.as_capability(), InputValue::Bool(false));
+            native_events.push(native_event);
+            native_events.push(release);
+            continue;
+        }
+        native_events.push(native_event);
+    }
+    native_events
 }
 
 /// Translate the given Legion Go event into a native event
@@ -313,8 +753,16 @@ fn translate_event(event: event::Event) -> NativeEvent {
                 Capability::Gamepad(Gamepad::Trigger(GamepadTrigger::RightTrigger)),
                 normalize_trigger_value(trigg),
             ),
-            event::TriggerEvent::MouseWheel(_) => {
-                NativeEvent::new(Capability::NotImplemented, InputValue::Bool(false))
+            event::TriggerEvent::MouseWheel(value) => {
+                let button = if value.value > 0 {
+                    MouseButton::WheelUp
+                } else {
+                    MouseButton::WheelDown
+                };
+                NativeEvent::new(
+                    Capability::Mouse(Mouse::Button(button)),
+                    InputValue::Bool(true),
+                )
             }
         },
         event::Event::MouseButton(button) => match button {
@@ -346,6 +794,7 @@ fn translate_event(event: event::Event) -> NativeEvent {
 
 /// List of all capabilities that the Legion Go driver implements
 pub const CAPABILITIES: &[Capability] = &[
+    Capability::Gamepad(Gamepad::Accelerometer),
     Capability::Gamepad(Gamepad::Axis(GamepadAxis::LeftStick)),
     Capability::Gamepad(Gamepad::Axis(GamepadAxis::RightStick)),
     Capability::Gamepad(Gamepad::Button(GamepadButton::DPadDown)),
@@ -378,6 +827,52 @@ pub const CAPABILITIES: &[Capability] = &[
     Capability::Mouse(Mouse::Button(MouseButton::Middle)),
     Capability::Mouse(Mouse::Button(MouseButton::Right)),
     Capability::Mouse(Mouse::Button(MouseButton::Side)),
+    Capability::Mouse(Mouse::Button(MouseButton::WheelDown)),
+    Capability::Mouse(Mouse::Button(MouseButton::WheelUp)),
+    Capability::Mouse(Mouse::Motion),
+    Capability::Touchpad(Touchpad::RightPad(Touch::Motion)),
+];
+
+/// Capabilities provided by the left detachable controller
+const LEFT_CONTROLLER_CAPABILITIES: &[Capability] = &[
+    Capability::Gamepad(Gamepad::Axis(GamepadAxis::LeftStick)),
+    Capability::Gamepad(Gamepad::Button(GamepadButton::DPadDown)),
+    Capability::Gamepad(Gamepad::Button(GamepadButton::DPadLeft)),
+    Capability::Gamepad(Gamepad::Button(GamepadButton::DPadRight)),
+    Capability::Gamepad(Gamepad::Button(GamepadButton::DPadUp)),
+    Capability::Gamepad(Gamepad::Button(GamepadButton::Guide)),
+    Capability::Gamepad(Gamepad::Button(GamepadButton::LeftBumper)),
+    Capability::Gamepad(Gamepad::Button(GamepadButton::LeftPaddle1)),
+    Capability::Gamepad(Gamepad::Button(GamepadButton::LeftPaddle2)),
+    Capability::Gamepad(Gamepad::Button(GamepadButton::LeftStick)),
+    Capability::Gamepad(Gamepad::Button(GamepadButton::LeftTrigger)),
+    Capability::Gamepad(Gamepad::Button(GamepadButton::Select)),
+    Capability::Gamepad(Gamepad::Button(GamepadButton::Start)),
+    Capability::Gamepad(Gamepad::Trigger(GamepadTrigger::LeftTrigger)),
+];
+
+/// Capabilities provided by the right detachable controller
+const RIGHT_CONTROLLER_CAPABILITIES: &[Capability] = &[
+    Capability::Gamepad(Gamepad::Axis(GamepadAxis::RightStick)),
+    Capability::Gamepad(Gamepad::Button(GamepadButton::East)),
+    Capability::Gamepad(Gamepad::Button(GamepadButton::North)),
+    Capability::Gamepad(Gamepad::Button(GamepadButton::QuickAccess)),
+    Capability::Gamepad(Gamepad::Button(GamepadButton::RightBumper)),
+    Capability::Gamepad(Gamepad::Button(GamepadButton::RightPaddle1)),
+    Capability::Gamepad(Gamepad::Button(GamepadButton::RightPaddle2)),
+    Capability::Gamepad(Gamepad::Button(GamepadButton::RightPaddle3)),
+    Capability::Gamepad(Gamepad::Button(GamepadButton::RightStick)),
+    Capability::Gamepad(Gamepad::Button(GamepadButton::RightTrigger)),
+    Capability::Gamepad(Gamepad::Button(GamepadButton::South)),
+    Capability::Gamepad(Gamepad::Button(GamepadButton::West)),
+    Capability::Gamepad(Gamepad::Trigger(GamepadTrigger::RightTrigger)),
+    Capability::Mouse(Mouse::Button(MouseButton::Extra)),
+    Capability::Mouse(Mouse::Button(MouseButton::Left)),
+    Capability::Mouse(Mouse::Button(MouseButton::Middle)),
+    Capability::Mouse(Mouse::Button(MouseButton::Right)),
+    Capability::Mouse(Mouse::Button(MouseButton::Side)),
+    Capability::Mouse(Mouse::Button(MouseButton::WheelDown)),
+    Capability::Mouse(Mouse::Button(MouseButton::WheelUp)),
     Capability::Mouse(Mouse::Motion),
     Capability::Touchpad(Touchpad::RightPad(Touch::Motion)),
 ];
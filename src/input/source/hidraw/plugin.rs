@@ -0,0 +1,89 @@
+use std::{error::Error, time::Instant};
+
+use hidapi::DeviceInfo;
+use tokio::sync::mpsc;
+
+use crate::{
+    config::PluginConfig,
+    input::{
+        capability::Capability,
+        composite_device::Command,
+        event::Event,
+        plugin::{Plugin, MAX_REPORT_SIZE},
+    },
+    logger,
+};
+
+/// Timeout in milliseconds for reading a report from the device
+const HID_TIMEOUT: i32 = 10;
+
+/// HIDRaw source device that uses a decoder [Plugin] to translate reports
+/// into input events.
+#[derive(Debug)]
+pub struct PluginController {
+    info: DeviceInfo,
+    config: PluginConfig,
+    composite_tx: mpsc::Sender<Command>,
+    device_id: String,
+}
+
+impl PluginController {
+    pub fn new(
+        info: DeviceInfo,
+        config: PluginConfig,
+        composite_tx: mpsc::Sender<Command>,
+        device_id: String,
+    ) -> Self {
+        Self {
+            info,
+            config,
+            composite_tx,
+            device_id,
+        }
+    }
+
+    pub async fn run(&self) -> Result<(), Box<dyn Error>> {
+        log::debug!("Starting plugin driver '{}'", self.config.name);
+        let path = self.info.path().to_owned();
+        let config = self.config.clone();
+        let tx = self.composite_tx.clone();
+
+        // Spawn a blocking task to read and decode the reports
+        let device_id = self.device_id.clone();
+        let task =
+            logger::spawn_blocking_in_scope(move || -> Result<(), Box<dyn Error + Send + Sync>> {
+                let mut plugin = Plugin::load(&config)?;
+                let api = hidapi::HidApi::new()?;
+                let device = api.open_path(&path)?;
+                let mut buf = [0; MAX_REPORT_SIZE];
+                loop {
+                    let bytes_read = device.read_timeout(&mut buf[..], HID_TIMEOUT)?;
+                    let read_time = Instant::now();
+                    if bytes_read == 0 {
+                        continue;
+                    }
+                    let events = plugin.decode(&buf[..bytes_read])?;
+                    for mut event in events {
+                        event.set_timestamp(read_time);
+                        // Don't send un-implemented events
+                        if matches!(event.as_capability(), Capability::NotImplemented) {
+                            continue;
+                        }
+                        tx.blocking_send(Command::ProcessEvent(
+                            device_id.clone(),
+                            Event::Native(event),
+                        ))?;
+                    }
+                }
+            });
+
+        // Wait for the task to finish
+        if let Err(e) = task.await? {
+            return Err(e.to_string().into());
+        }
+
+        log::debug!("Plugin driver '{}' stopped", self.config.name);
+
+        Ok(())
+    }
+}
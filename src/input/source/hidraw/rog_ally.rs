@@ -0,0 +1,98 @@
+use std::error::Error;
+
+use hidapi::DeviceInfo;
+use tokio::sync::mpsc;
+
+use crate::{
+    drivers::rog_ally::{driver::Driver, event},
+    input::{
+        capability::{Capability, Gamepad, GamepadButton},
+        composite_device::Command,
+        event::{native::NativeEvent, value::InputValue, Event},
+    },
+    logger,
+};
+
+/// ROG Ally implementation of HIDRAW interface for the extra buttons of the
+/// ASUS N-KEY MCU
+#[derive(Debug)]
+pub struct RogAllyController {
+    info: DeviceInfo,
+    composite_tx: mpsc::Sender<Command>,
+    device_id: String,
+}
+
+impl RogAllyController {
+    pub fn new(info: DeviceInfo, composite_tx: mpsc::Sender<Command>, device_id: String) -> Self {
+        Self {
+            info,
+            composite_tx,
+            device_id,
+        }
+    }
+
+    pub async fn run(&self) -> Result<(), Box<dyn Error>> {
+        log::debug!("Starting ROG Ally driver");
+        let path = self.info.path().to_string_lossy().to_string();
+        let tx = self.composite_tx.clone();
+
+        // Spawn a blocking task to read the events
+        let device_id = self.device_id.clone();
+        let task =
+            logger::spawn_blocking_in_scope(move || -> Result<(), Box<dyn Error + Send + Sync>> {
+                let mut driver = Driver::new(path)?;
+                loop {
+                    let events = driver.poll()?;
+                    let read_time = driver.last_report_time();
+                    for mut event in translate_events(events) {
+                        event.set_timestamp(read_time);
+                        tx.blocking_send(Command::ProcessEvent(
+                            device_id.clone(),
+                            Event::Native(event),
+                        ))?;
+                    }
+                }
+            });
+
+        // Wait for the task to finish
+        if let Err(e) = task.await? {
+            return Err(e.to_string().into());
+        }
+
+        log::debug!("ROG Ally driver stopped");
+
+        Ok(())
+    }
+}
+
+/// Translate the given ROG Ally events into native events
+fn translate_events(events: Vec<event::Event>) -> Vec<NativeEvent> {
+    events.into_iter().map(translate_event).collect()
+}
+
+/// Translate the given ROG Ally event into a native event
+fn translate_event(event: event::Event) -> NativeEvent {
+    match event {
+        event::Event::Button(button) => match button {
+            event::ButtonEvent::ArmouryCrate(value) => NativeEvent::new(
+                Capability::Gamepad(Gamepad::Button(GamepadButton::QuickAccess)),
+                InputValue::Bool(value.pressed),
+            ),
+            event::ButtonEvent::ArmouryCrateLong(value) => NativeEvent::new(
+                Capability::Gamepad(Gamepad::Button(GamepadButton::QuickAccess2)),
+                InputValue::Bool(value.pressed),
+            ),
+            event::ButtonEvent::CommandCenter(value) => NativeEvent::new(
+                Capability::Gamepad(Gamepad::Button(GamepadButton::Guide)),
+                InputValue::Bool(value.pressed),
+            ),
+        },
+    }
+}
+
+/// List of all capabilities that the ROG Ally driver implements
+pub const CAPABILITIES: &[Capability] = &[
+    Capability::Gamepad(Gamepad::Button(GamepadButton::Guide)),
+    Capability::Gamepad(Gamepad::Button(GamepadButton::QuickAccess)),
+    Capability::Gamepad(Gamepad::Button(GamepadButton::QuickAccess2)),
+];
@@ -0,0 +1,253 @@
+use std::error::Error;
+
+use hidapi::DeviceInfo;
+use tokio::sync::mpsc;
+
+use crate::{
+    drivers::{
+        steam_controller::{
+            driver::Driver,
+            event,
+            hid_report::{PAD_MAX, STICK_MAX, TRIGG_MAX},
+        },
+        steam_deck::driver::ACCEL_SCALE,
+    },
+    input::{
+        capability::{
+            Capability, Gamepad, GamepadAxis, GamepadButton, GamepadTrigger, Touch, TouchButton,
+            Touchpad,
+        },
+        composite_device::Command,
+        event::{native::NativeEvent, value::InputValue, Event},
+    },
+    logger,
+};
+
+/// Steam Controller implementation of HIDRAW interface, used for both the
+/// wired controller and the wireless dongle
+#[derive(Debug)]
+pub struct SteamController {
+    info: DeviceInfo,
+    /// Whether the firmware mouse/keyboard emulation stays enabled
+    lizard_mode: bool,
+    composite_tx: mpsc::Sender<Command>,
+    device_id: String,
+}
+
+impl SteamController {
+    pub fn new(
+        info: DeviceInfo,
+        lizard_mode: bool,
+        composite_tx: mpsc::Sender<Command>,
+        device_id: String,
+    ) -> Self {
+        Self {
+            info,
+            lizard_mode,
+            composite_tx,
+            device_id,
+        }
+    }
+
+    pub async fn run(&self) -> Result<(), Box<dyn Error>> {
+        log::debug!("Starting Steam Controller driver");
+        let path = self.info.path().to_string_lossy().to_string();
+        let tx = self.composite_tx.clone();
+        let lizard_mode = self.lizard_mode;
+
+        // Spawn a blocking task to read the events
+        let device_id = self.device_id.clone();
+        let task =
+            logger::spawn_blocking_in_scope(move || -> Result<(), Box<dyn Error + Send + Sync>> {
+                let mut driver = Driver::new(path)?;
+                if !lizard_mode {
+                    driver.set_lizard_mode(false)?;
+                }
+                driver.set_imu_enabled(true)?;
+
+                let result = (|| -> Result<(), Box<dyn Error + Send + Sync>> {
+                    loop {
+                        let events = driver.poll()?;
+                        let read_time = driver.last_report_time();
+                        for mut event in translate_events(events) {
+                            event.set_timestamp(read_time);
+                            tx.blocking_send(Command::ProcessEvent(
+                                device_id.clone(),
+                                Event::Native(event),
+                            ))?;
+                        }
+                    }
+                })();
+
+                // Give the controller back its mouse/keyboard emulation
+                if !lizard_mode {
+                    if let Err(e) = driver.set_lizard_mode(true) {
+                        log::debug!("Unable to restore lizard mode: {e:?}");
+                    }
+                }
+
+                result
+            });
+
+        // Wait for the task to finish
+        if let Err(e) = task.await? {
+            return Err(e.to_string().into());
+        }
+
+        log::debug!("Steam Controller driver stopped");
+
+        Ok(())
+    }
+}
+
+/// Normalize the value to something between -1.0 and 1.0 based on the given
+/// maximum. The Y-axis is inverted so that up is negative.
+fn normalize_axis_value(event: event::AxisEvent) -> InputValue {
+    match event {
+        event::AxisEvent::LPad(value) | event::AxisEvent::RPad(value) => InputValue::Touch {
+            index: value.index,
+            is_touching: value.is_touching,
+            x: Some(value.x as f64 / PAD_MAX),
+            y: Some(-(value.y as f64) / PAD_MAX),
+            pressure: None,
+        },
+        event::AxisEvent::LStick(value) => InputValue::Vector2 {
+            x: Some(value.x as f64 / STICK_MAX),
+            y: Some(-(value.y as f64) / STICK_MAX),
+        },
+    }
+}
+
+/// Translate the given Steam Controller events into native events
+fn translate_events(events: Vec<event::Event>) -> Vec<NativeEvent> {
+    events.into_iter().map(translate_event).collect()
+}
+
+/// Translate the given Steam Controller event into a native event
+fn translate_event(event: event::Event) -> NativeEvent {
+    match event {
+        event::Event::Button(button) => {
+            let (button, value) = match button {
+                event::ButtonEvent::A(value) => (GamepadButton::South, value),
+                event::ButtonEvent::X(value) => (GamepadButton::North, value),
+                event::ButtonEvent::B(value) => (GamepadButton::East, value),
+                event::ButtonEvent::Y(value) => (GamepadButton::West, value),
+                event::ButtonEvent::Start(value) => (GamepadButton::Start, value),
+                event::ButtonEvent::Back(value) => (GamepadButton::Select, value),
+                event::ButtonEvent::Steam(value) => (GamepadButton::Guide, value),
+                event::ButtonEvent::DPadDown(value) => (GamepadButton::DPadDown, value),
+                event::ButtonEvent::DPadUp(value) => (GamepadButton::DPadUp, value),
+                event::ButtonEvent::DPadLeft(value) => (GamepadButton::DPadLeft, value),
+                event::ButtonEvent::DPadRight(value) => (GamepadButton::DPadRight, value),
+                event::ButtonEvent::L1(value) => (GamepadButton::LeftBumper, value),
+                event::ButtonEvent::L2(value) => (GamepadButton::LeftTrigger, value),
+                event::ButtonEvent::L3(value) => (GamepadButton::LeftStick, value),
+                event::ButtonEvent::LGrip(value) => (GamepadButton::LeftPaddle1, value),
+                event::ButtonEvent::R1(value) => (GamepadButton::RightBumper, value),
+                event::ButtonEvent::R2(value) => (GamepadButton::RightTrigger, value),
+                event::ButtonEvent::RGrip(value) => (GamepadButton::RightPaddle1, value),
+                event::ButtonEvent::RPadTouch(value) => {
+                    return NativeEvent::new(
+                        Capability::Touchpad(Touchpad::RightPad(Touch::Button(TouchButton::Touch))),
+                        InputValue::Bool(value.pressed),
+                    )
+                }
+                event::ButtonEvent::LPadTouch(value) => {
+                    return NativeEvent::new(
+                        Capability::Touchpad(Touchpad::LeftPad(Touch::Button(TouchButton::Touch))),
+                        InputValue::Bool(value.pressed),
+                    )
+                }
+                event::ButtonEvent::RPadPress(value) => {
+                    return NativeEvent::new(
+                        Capability::Touchpad(Touchpad::RightPad(Touch::Button(TouchButton::Press))),
+                        InputValue::Bool(value.pressed),
+                    )
+                }
+                event::ButtonEvent::LPadPress(value) => {
+                    return NativeEvent::new(
+                        Capability::Touchpad(Touchpad::LeftPad(Touch::Button(TouchButton::Press))),
+                        InputValue::Bool(value.pressed),
+                    )
+                }
+            };
+            NativeEvent::new(
+                Capability::Gamepad(Gamepad::Button(button)),
+                InputValue::Bool(value.pressed),
+            )
+        }
+        event::Event::Accelerometer(accel) => match accel {
+            event::AccelerometerEvent::Accelerometer(value) => NativeEvent::new(
+                Capability::Gamepad(Gamepad::Accelerometer),
+                InputValue::Vector3 {
+                    x: Some(value.x as f64 * ACCEL_SCALE),
+                    y: Some(value.y as f64 * ACCEL_SCALE),
+                    z: Some(value.z as f64 * ACCEL_SCALE),
+                },
+            ),
+            event::AccelerometerEvent::Attitude(value) => NativeEvent::new(
+                Capability::Gamepad(Gamepad::Gyro),
+                InputValue::Vector3 {
+                    x: Some(value.x as f64),
+                    y: Some(value.y as f64),
+                    z: Some(value.z as f64),
+                },
+            ),
+        },
+        event::Event::Axis(axis) => {
+            let capability = match axis {
+                event::AxisEvent::LPad(_) => Capability::Touchpad(Touchpad::LeftPad(Touch::Motion)),
+                event::AxisEvent::RPad(_) => {
+                    Capability::Touchpad(Touchpad::RightPad(Touch::Motion))
+                }
+                event::AxisEvent::LStick(_) => {
+                    Capability::Gamepad(Gamepad::Axis(GamepadAxis::LeftStick))
+                }
+            };
+            NativeEvent::new(capability, normalize_axis_value(axis))
+        }
+        event::Event::Trigger(trigg) => {
+            let (trigger, value) = match trigg {
+                event::TriggerEvent::LTrigger(value) => (GamepadTrigger::LeftTrigger, value),
+                event::TriggerEvent::RTrigger(value) => (GamepadTrigger::RightTrigger, value),
+            };
+            NativeEvent::new(
+                Capability::Gamepad(Gamepad::Trigger(trigger)),
+                InputValue::Float(value.value as f64 / TRIGG_MAX),
+            )
+        }
+    }
+}
+
+/// List of all capabilities that the Steam Controller driver implements
+pub const CAPABILITIES: &[Capability] = &[
+    Capability::Gamepad(Gamepad::Accelerometer),
+    Capability::Gamepad(Gamepad::Axis(GamepadAxis::LeftStick)),
+    Capability::Gamepad(Gamepad::Button(GamepadButton::DPadDown)),
+    Capability::Gamepad(Gamepad::Button(GamepadButton::DPadLeft)),
+    Capability::Gamepad(Gamepad::Button(GamepadButton::DPadRight)),
+    Capability::Gamepad(Gamepad::Button(GamepadButton::DPadUp)),
+    Capability::Gamepad(Gamepad::Button(GamepadButton::East)),
+    Capability::Gamepad(Gamepad::Button(GamepadButton::Guide)),
+    Capability::Gamepad(Gamepad::Button(GamepadButton::LeftBumper)),
+    Capability::Gamepad(Gamepad::Button(GamepadButton::LeftPaddle1)),
+    Capability::Gamepad(Gamepad::Button(GamepadButton::LeftStick)),
+    Capability::Gamepad(Gamepad::Button(GamepadButton::LeftTrigger)),
+    Capability::Gamepad(Gamepad::Button(GamepadButton::North)),
+    Capability::Gamepad(Gamepad::Button(GamepadButton::RightBumper)),
+    Capability::Gamepad(Gamepad::Button(GamepadButton::RightPaddle1)),
+    Capability::Gamepad(Gamepad::Button(GamepadButton::RightTrigger)),
+    Capability::Gamepad(Gamepad::Button(GamepadButton::Select)),
+    Capability::Gamepad(Gamepad::Button(GamepadButton::South)),
+    Capability::Gamepad(Gamepad::Button(GamepadButton::Start)),
+    Capability::Gamepad(Gamepad::Button(GamepadButton::West)),
+    Capability::Gamepad(Gamepad::Gyro),
+    Capability::Gamepad(Gamepad::Trigger(GamepadTrigger::LeftTrigger)),
+    Capability::Gamepad(Gamepad::Trigger(GamepadTrigger::RightTrigger)),
+    Capability::Touchpad(Touchpad::LeftPad(Touch::Button(TouchButton::Press))),
+    Capability::Touchpad(Touchpad::LeftPad(Touch::Button(TouchButton::Touch))),
+    Capability::Touchpad(Touchpad::LeftPad(Touch::Motion)),
+    Capability::Touchpad(Touchpad::RightPad(Touch::Button(TouchButton::Press))),
+    Capability::Touchpad(Touchpad::RightPad(Touch::Button(TouchButton::Touch))),
+    Capability::Touchpad(Touchpad::RightPad(Touch::Motion)),
+];
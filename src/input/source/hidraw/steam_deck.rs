@@ -4,15 +4,20 @@ use std::{
     collections::HashMap,
     error::Error,
     thread,
-    time::{self, Duration},
+    time::{Duration, Instant},
 };
 
 use evdev::{FFEffectData, FFEffectKind};
 use hidapi::DeviceInfo;
-use tokio::sync::mpsc::{self, error::TryRecvError};
+use tokio::sync::{
+    mpsc::{self, error::TryRecvError},
+    watch,
+};
 
 use crate::{
+    config::AudioHapticsConfig,
     drivers::{
+        audio_haptics::AudioHaptics,
         dualsense::hid_report::SetStatePackedOutputData,
         steam_deck::{
             self,
@@ -30,6 +35,7 @@ use crate::{
         output_event::OutputEvent,
         source::SourceCommand,
     },
+    logger,
 };
 
 /// Vendor ID
@@ -43,22 +49,34 @@ const POLL_RATE: Duration = Duration::from_micros(250);
 #[derive(Debug)]
 pub struct DeckController {
     info: DeviceInfo,
+    /// Whether the firmware mouse/keyboard emulation stays enabled
+    lizard_mode: bool,
+    /// Play rumble as audio waveforms instead of haptic rumble commands
+    audio_haptics: Option<AudioHapticsConfig>,
     composite_tx: mpsc::Sender<Command>,
     rx: Option<mpsc::Receiver<SourceCommand>>,
+    /// Publishes the time of the last input report to detect stalls
+    report_tx: Option<watch::Sender<Instant>>,
     device_id: String,
 }
 
 impl DeckController {
     pub fn new(
         info: DeviceInfo,
+        lizard_mode: bool,
+        audio_haptics: Option<AudioHapticsConfig>,
         composite_tx: mpsc::Sender<Command>,
         rx: mpsc::Receiver<SourceCommand>,
+        report_tx: watch::Sender<Instant>,
         device_id: String,
     ) -> Self {
         Self {
             info,
+            lizard_mode,
+            audio_haptics,
             composite_tx,
             rx: Some(rx),
+            report_tx: Some(report_tx),
             device_id,
         }
     }
@@ -67,63 +85,48 @@ impl DeckController {
         log::debug!("Starting Steam Deck Controller driver");
         let rx = self.rx.take().unwrap();
         let tx = self.composite_tx.clone();
-        let path = self.info.path().to_string_lossy().to_string();
-        let device_path = path.clone();
+        let device_path = self.info.path().to_string_lossy().to_string();
         let device_id = self.device_id.clone();
+        let report_tx = self.report_tx.take().unwrap();
+        let lizard_mode = self.lizard_mode;
+        let audio_haptics = self.audio_haptics.as_ref().map(AudioHaptics::new);
 
-        // Spawn a blocking task to handle lizard mode
-        let lizard_task =
-            tokio::task::spawn_blocking(move || -> Result<(), Box<dyn Error + Send + Sync>> {
-                let driver = Driver::new(path.clone())?;
-                loop {
-                    // Keep the lizard asleep
-                    driver.handle_lizard_mode()?;
-
-                    // Polling interval is about 4ms so we can sleep a little
-                    let duration = time::Duration::from_secs(LIZARD_SLEEP_SEC as u64);
-                    thread::sleep(duration);
-                }
-            });
-
-        // Spawn a blocking task to read the events
+        // Spawn a blocking task to read the events. Lizard mode is handled in
+        // the same task so all output reports go through the same queue.
         let task =
-            tokio::task::spawn_blocking(move || -> Result<(), Box<dyn Error + Send + Sync>> {
-                let mut output_handler = DeckOutput::new(rx);
+            logger::spawn_blocking_in_scope(move || -> Result<(), Box<dyn Error + Send + Sync>> {
+                let mut output_handler = DeckOutput::new(rx, audio_haptics);
                 let mut driver = Driver::new(device_path.clone())?;
-                loop {
-                    let events = driver.poll()?;
-                    let native_events = translate_events(events);
-                    for event in native_events {
-                        // Don't send un-implemented events
-                        if matches!(event.as_capability(), Capability::NotImplemented) {
-                            continue;
-                        }
-                        tx.blocking_send(Command::ProcessEvent(
-                            device_id.clone(),
-                            Event::Native(event),
-                        ))?;
-                    }
-
-                    // Receive commands/output events
-                    if let Err(e) = output_handler.receive_commands(&mut driver) {
-                        log::debug!("Error receiving commands: {:?}", e);
-                        break;
+                if lizard_mode {
+                    log::debug!("Leaving lizard mode enabled for {device_id}");
+                    driver.set_lizard_mode(true)?;
+                }
+                let result = run_driver(
+                    &mut driver,
+                    &mut output_handler,
+                    lizard_mode,
+                    &device_path,
+                    &device_id,
+                    &tx,
+                    &report_tx,
+                );
+
+                // Give the firmware mouse/keyboard emulation back when the
+                // device is released.
+                if !lizard_mode {
+                    log::debug!("Restoring lizard mode for {device_id}");
+                    if let Err(e) = driver.set_lizard_mode(true) {
+                        log::debug!("Failed to restore lizard mode for {device_id}: {e:?}");
                     }
-
-                    // Polling interval is about 4ms so we can sleep a little
-                    thread::sleep(POLL_RATE);
                 }
 
-                Ok(())
+                result
             });
 
         // Wait for the task to finish
         if let Err(e) = task.await? {
             return Err(e.to_string().into());
         }
-        if let Err(e) = lizard_task.await? {
-            return Err(e.to_string().into());
-        }
 
         log::debug!("Steam Deck Controller driver stopped");
 
@@ -131,24 +134,143 @@ impl DeckController {
     }
 }
 
+/// Read events from the device and process output commands until the device
+/// is released or an error occurs.
+fn run_driver(
+    driver: &mut Driver,
+    output_handler: &mut DeckOutput,
+    lizard_mode: bool,
+    device_path: &str,
+    device_id: &str,
+    tx: &mpsc::Sender<Command>,
+    report_tx: &watch::Sender<Instant>,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let lizard_interval = Duration::from_secs_f64(LIZARD_SLEEP_SEC);
+    let mut lizard_at: Option<Instant> = None;
+    loop {
+        // Keep the lizard asleep while the device is grabbed
+        if !lizard_mode
+            && output_handler.grabbed
+            && !lizard_at.is_some_and(|at| at.elapsed() < lizard_interval)
+        {
+            driver.handle_lizard_mode()?;
+            lizard_at = Some(Instant::now());
+        }
+
+        let last_report = driver.last_report_time();
+        let events = driver.poll()?;
+
+        // Input is not forwarded while another program has the device
+        let read_time = driver.last_report_time();
+        if read_time != last_report {
+            report_tx.send_replace(read_time);
+        }
+        let native_events = if output_handler.grabbed {
+            translate_events(events)
+        } else {
+            Vec::new()
+        };
+        for mut event in native_events {
+            event.set_timestamp(read_time);
+            // Don't send un-implemented events
+            if matches!(event.as_capability(), Capability::NotImplemented) {
+                continue;
+            }
+            tx.blocking_send(Command::ProcessEvent(
+                device_id.to_string(),
+                Event::Native(event),
+            ))?;
+        }
+
+        // Receive commands/output events
+        match output_handler.receive_commands(driver) {
+            Ok(false) => (),
+            Ok(true) => {
+                log::debug!("Device stop requested");
+                break;
+            }
+            Err(e) => {
+                log::debug!("Error receiving commands: {:?}", e);
+                break;
+            }
+        }
+        output_handler.update_audio_haptics();
+
+        // Re-open the device if the composite device detected a stall
+        if output_handler.reopen_requested {
+            output_handler.reopen_requested = false;
+            log::debug!("Re-opening device {device_id}");
+            *driver = Driver::new(device_path.to_string())?;
+            lizard_at = None;
+        }
+
+        // Polling interval is about 4ms so we can sleep a little
+        thread::sleep(POLL_RATE);
+    }
+
+    Ok(())
+}
+
 /// Manages handling output events and source device commands
 #[derive(Debug)]
 struct DeckOutput {
     rx: mpsc::Receiver<SourceCommand>,
     ff_evdev_effects: HashMap<i16, FFEffectData>,
+    audio_haptics: Option<AudioHaptics>,
+    /// Whether the device is in use. While released, no output reports are
+    /// written so another program (e.g. Steam) can control the device.
+    grabbed: bool,
+    /// Whether the device should be re-opened
+    reopen_requested: bool,
 }
 
 impl DeckOutput {
-    pub fn new(rx: mpsc::Receiver<SourceCommand>) -> Self {
+    pub fn new(rx: mpsc::Receiver<SourceCommand>, audio_haptics: Option<AudioHaptics>) -> Self {
         Self {
             rx,
             ff_evdev_effects: HashMap::new(),
+            audio_haptics,
+            grabbed: true,
+            reopen_requested: false,
         }
     }
 
+    /// Stream the next audio haptics waveform chunk if needed
+    fn update_audio_haptics(&mut self) {
+        let Some(audio_haptics) = self.audio_haptics.as_mut() else {
+            return;
+        };
+        if let Err(e) = audio_haptics.update() {
+            log::debug!("Failed to play audio haptics: {e:?}");
+        }
+    }
+
+    /// Rumble the device with the given strong and weak intensities, either
+    /// through audio haptics or haptic rumble commands.
+    fn rumble(
+        &mut self,
+        driver: &mut Driver,
+        strong: u16,
+        weak: u16,
+    ) -> Result<(), Box<dyn Error>> {
+        if let Some(audio_haptics) = self.audio_haptics.as_mut() {
+            audio_haptics.set_rumble(strong, weak);
+            return Ok(());
+        }
+
+        let intensity = 0;
+        let left_gain = if strong == 0 { 0 } else { 130 };
+        let right_gain = if weak == 0 { 0 } else { 130 };
+        if let Err(e) = driver.haptic_rumble(intensity, strong, weak, left_gain, right_gain) {
+            let err = format!("Failed to do haptic rumble: {:?}", e);
+            return Err(err.into());
+        }
+        Ok(())
+    }
+
     /// Read commands sent to this device from the channel until it is
-    /// empty.
-    fn receive_commands(&mut self, driver: &mut Driver) -> Result<(), Box<dyn Error>> {
+    /// empty. Returns `true` if the device was asked to stop.
+    fn receive_commands(&mut self, driver: &mut Driver) -> Result<bool, Box<dyn Error>> {
         const MAX_COMMANDS: u8 = 64;
         let mut commands_processed = 0;
         loop {
@@ -165,6 +287,10 @@ impl DeckOutput {
                     }
                     SourceCommand::WriteEvent(event) => {
                         log::trace!("Received output event: {:?}", event);
+                        if !self.grabbed {
+                            log::trace!("Ignoring output event while device is released");
+                            continue;
+                        }
                         match event {
                             OutputEvent::Evdev(input_event) => {
                                 if let Err(e) = self.process_evdev_ff(driver, input_event) {
@@ -184,13 +310,30 @@ impl DeckOutput {
                                     }
                                 }
                             }
+                            OutputEvent::SteamDeckHaptics(report) => {
+                                if let Err(e) = driver.haptic_pulse(report) {
+                                    log::error!("Failed to write haptic pulse: {:?}", e);
+                                }
+                            }
+                            OutputEvent::Rumble { left, right } => {
+                                let (strong, weak) = (left as u16 * 256, right as u16 * 256);
+                                if let Err(e) = self.rumble(driver, strong, weak) {
+                                    log::error!("Failed to do rumble: {:?}", e);
+                                }
+                            }
                             OutputEvent::Uinput(_) => (),
+                            OutputEvent::Led(_) => (),
                         }
                     }
-                    SourceCommand::Stop => return Err("Device stopped".into()),
+                    SourceCommand::SetGrab(grab) => {
+                        log::debug!("Setting grab on device to {grab}");
+                        self.grabbed = grab;
+                    }
+                    SourceCommand::Reopen => self.reopen_requested = true,
+                    SourceCommand::Stop => return Ok(true),
                 },
                 Err(e) => match e {
-                    TryRecvError::Empty => return Ok(()),
+                    TryRecvError::Empty => return Ok(false),
                     TryRecvError::Disconnected => {
                         log::debug!("Receive channel disconnected");
                         return Err("Receive channel disconnected".into());
@@ -200,7 +343,7 @@ impl DeckOutput {
 
             commands_processed += 1;
             if commands_processed >= MAX_COMMANDS {
-                return Ok(());
+                return Ok(false);
             }
         }
     }
@@ -265,7 +408,7 @@ impl DeckOutput {
     /// Process evdev force feedback events. Evdev events will send events with
     /// the effect id set in the 'code' field.
     fn process_evdev_ff(
-        &self,
+        &mut self,
         device: &mut Driver,
         input_event: evdev::InputEvent,
     ) -> Result<(), Box<dyn Error>> {
@@ -288,7 +431,7 @@ impl DeckOutput {
         // The value determines if the effect should be playing or not.
         if value == 0 {
             log::trace!("Stopping haptic rumble");
-            if let Err(e) = device.haptic_rumble(0, 0, 0, 0, 0) {
+            if let Err(e) = self.rumble(device, 0, 0) {
                 log::debug!("Failed to stop haptic rumble: {:?}", e);
                 return Ok(());
             }
@@ -324,25 +467,7 @@ impl DeckOutput {
                 weak_magnitude,
             } => {
                 // Set rumble values based on the effect data
-                let intensity = 0;
-                let left_speed = strong_magnitude;
-                let right_speed = weak_magnitude;
-                let mut left_gain = 130;
-                let mut right_gain = 130;
-                if left_speed == 0 {
-                    left_gain = 0;
-                }
-                if right_speed == 0 {
-                    right_gain = 0;
-                }
-
-                // Do rumble
-                if let Err(e) =
-                    device.haptic_rumble(intensity, left_speed, right_speed, left_gain, right_gain)
-                {
-                    let err = format!("Failed to do haptic rumble: {:?}", e);
-                    return Err(err.into());
-                }
+                self.rumble(device, strong_magnitude, weak_magnitude)?;
             }
         }
 
@@ -356,26 +481,9 @@ impl DeckOutput {
         report: SetStatePackedOutputData,
     ) -> Result<(), Box<dyn Error>> {
         // Set the rumble values based on the DualSense output report
-        let intensity = 0;
         let left_speed = report.rumble_emulation_left as u16 * 256;
         let right_speed = report.rumble_emulation_right as u16 * 256;
-        let mut left_gain = 130;
-        let mut right_gain = 130;
-        if left_speed == 0 {
-            left_gain = 0;
-        }
-        if right_speed == 0 {
-            right_gain = 0;
-        }
-
-        if let Err(e) =
-            driver.haptic_rumble(intensity, left_speed, right_speed, left_gain, right_gain)
-        {
-            let err = format!("Failed to do haptic rumble: {:?}", e);
-            return Err(err.into());
-        }
-
-        Ok(())
+        self.rumble(driver, left_speed, right_speed)
     }
 }
 
@@ -422,6 +530,7 @@ fn normalize_axis_value(event: steam_deck::event::AxisEvent) -> InputValue {
                 is_touching: value.is_touching,
                 x,
                 y,
+                pressure: None,
             }
         }
         steam_deck::event::AxisEvent::RPad(value) => {
@@ -440,6 +549,7 @@ fn normalize_axis_value(event: steam_deck::event::AxisEvent) -> InputValue {
                 is_touching: value.is_touching,
                 x,
                 y,
+                pressure: None,
             }
         }
         steam_deck::event::AxisEvent::LStick(value) => {
@@ -0,0 +1,296 @@
+use std::{collections::HashMap, error::Error, str::FromStr};
+
+use hidapi::DeviceInfo;
+use tokio::sync::mpsc;
+
+use crate::{
+    config,
+    drivers::wooting::{
+        driver::{Driver, ANALOG_MAX},
+        event,
+    },
+    input::{
+        capability::{Capability, Gamepad, GamepadAxis, GamepadTrigger, Keyboard},
+        composite_device::Command,
+        event::{native::NativeEvent, value::InputValue, Event},
+    },
+    logger,
+};
+
+/// Direction a key moves a gamepad axis in
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Direction {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+/// Gamepad input controlled by an analog key
+#[derive(Debug, Clone, PartialEq)]
+enum AnalogTarget {
+    Axis(GamepadAxis, Direction),
+    Trigger(GamepadTrigger),
+}
+
+/// Analog key mapped to a gamepad input
+#[derive(Debug, Clone)]
+pub struct AnalogKeyMapping {
+    /// HID keyboard usage of the key
+    code: u16,
+    target: AnalogTarget,
+}
+
+impl AnalogKeyMapping {
+    /// Create a mapping from the given config
+    pub fn from_config(config: &config::AnalogKey) -> Result<Self, Box<dyn Error>> {
+        let key =
+            Keyboard::from_str(&config.key).map_err(|_| format!("Invalid key: {}", config.key))?;
+        let Some(code) = hid_usage_from_key(&key) else {
+            return Err(format!("Key has no analog value: {}", config.key).into());
+        };
+        let target = if let Ok(trigger) = GamepadTrigger::from_str(&config.target) {
+            AnalogTarget::Trigger(trigger)
+        } else if let Ok(axis) = GamepadAxis::from_str(&config.target) {
+            let direction = match config.direction.as_deref() {
+                Some("up") => Direction::Up,
+                Some("down") => Direction::Down,
+                Some("left") => Direction::Left,
+                Some("right") => Direction::Right,
+                _ => return Err(format!("Invalid direction for axis {}", config.target).into()),
+            };
+            AnalogTarget::Axis(axis, direction)
+        } else {
+            return Err(format!("Invalid axis or trigger: {}", config.target).into());
+        };
+
+        Ok(Self { code, target })
+    }
+
+    /// Returns the mappings defined in the given config, or the default
+    /// mappings if none are defined. Invalid mappings are skipped.
+    pub fn from_hidraw_config(config: Option<&config::Hidraw>) -> Vec<Self> {
+        let Some(analog_keys) = config.and_then(|config| config.analog_keys.as_ref()) else {
+            return Self::defaults();
+        };
+        analog_keys
+            .iter()
+            .filter_map(|key| match Self::from_config(key) {
+                Ok(mapping) => Some(mapping),
+                Err(e) => {
+                    log::warn!("Skipping analog key mapping: {e}");
+                    None
+                }
+            })
+            .collect()
+    }
+
+    /// Returns the default mapping of WASD to the left stick
+    pub fn defaults() -> Vec<Self> {
+        [
+            (Keyboard::KeyW, Direction::Up),
+            (Keyboard::KeyA, Direction::Left),
+            (Keyboard::KeyS, Direction::Down),
+            (Keyboard::KeyD, Direction::Right),
+        ]
+        .into_iter()
+        .filter_map(|(key, direction)| {
+            Some(Self {
+                code: hid_usage_from_key(&key)?,
+                target: AnalogTarget::Axis(GamepadAxis::LeftStick, direction),
+            })
+        })
+        .collect()
+    }
+}
+
+/// Analog keyboard (e.g. Wooting) implementation of HIDRAW interface
+#[derive(Debug)]
+pub struct AnalogKeyboard {
+    info: DeviceInfo,
+    mappings: Vec<AnalogKeyMapping>,
+    composite_tx: mpsc::Sender<Command>,
+    device_id: String,
+}
+
+impl AnalogKeyboard {
+    pub fn new(
+        info: DeviceInfo,
+        mappings: Vec<AnalogKeyMapping>,
+        composite_tx: mpsc::Sender<Command>,
+        device_id: String,
+    ) -> Self {
+        Self {
+            info,
+            mappings,
+            composite_tx,
+            device_id,
+        }
+    }
+
+    pub async fn run(&self) -> Result<(), Box<dyn Error>> {
+        log::debug!("Starting analog keyboard driver");
+        let path = self.info.path().to_string_lossy().to_string();
+        let tx = self.composite_tx.clone();
+        let mappings = self.mappings.clone();
+
+        // Spawn a blocking task to read the events
+        let device_id = self.device_id.clone();
+        let task =
+            logger::spawn_blocking_in_scope(move || -> Result<(), Box<dyn Error + Send + Sync>> {
+                let mut driver = Driver::new(path)?;
+                let mut values = HashMap::new();
+                loop {
+                    let events = driver.poll()?;
+                    let read_time = driver.last_report_time();
+                    for mut event in translate_events(events, &mappings, &mut values) {
+                        event.set_timestamp(read_time);
+                        tx.blocking_send(Command::ProcessEvent(
+                            device_id.clone(),
+                            Event::Native(event),
+                        ))?;
+                    }
+                }
+            });
+
+        // Wait for the task to finish
+        if let Err(e) = task.await? {
+            return Err(e.to_string().into());
+        }
+
+        log::debug!("Analog keyboard driver stopped");
+
+        Ok(())
+    }
+}
+
+/// Returns the capabilities of an analog keyboard with the given mappings
+pub fn get_capabilities(mappings: &[AnalogKeyMapping]) -> Vec<Capability> {
+    let mut capabilities = Vec::new();
+    for mapping in mappings {
+        let capability = match &mapping.target {
+            AnalogTarget::Axis(axis, _) => Capability::Gamepad(Gamepad::Axis(axis.clone())),
+            AnalogTarget::Trigger(trigger) => {
+                Capability::Gamepad(Gamepad::Trigger(trigger.clone()))
+            }
+        };
+        if !capabilities.contains(&capability) {
+            capabilities.push(capability);
+        }
+    }
+    capabilities
+}
+
+/// Translate the given analog key events into native events for the gamepad
+/// inputs controlled by the changed keys. The given values are updated with
+/// the current value of every key.
+fn translate_events(
+    events: Vec<event::Event>,
+    mappings: &[AnalogKeyMapping],
+    values: &mut HashMap<u16, f64>,
+) -> Vec<NativeEvent> {
+    // Update the key values and find the affected gamepad inputs
+    let mut targets: Vec<&AnalogTarget> = Vec::new();
+    for event in events {
+        let event::Event::Analog(input) = event;
+        values.insert(input.code, input.value as f64 / ANALOG_MAX);
+        for mapping in mappings.iter().filter(|mapping| mapping.code == input.code) {
+            let target = &mapping.target;
+            let is_same_input = |other: &&AnalogTarget| match (other, target) {
+                (AnalogTarget::Axis(a, _), AnalogTarget::Axis(b, _)) => a == b,
+                (a, b) => *a == b,
+            };
+            if !targets.iter().any(is_same_input) {
+                targets.push(target);
+            }
+        }
+    }
+
+    // Returns the value of the key mapped to the given target
+    let value_of = |target: AnalogTarget| -> f64 {
+        mappings
+            .iter()
+            .filter(|mapping| mapping.target == target)
+            .filter_map(|mapping| values.get(&mapping.code))
+            .fold(0.0, |max: f64, value| max.max(*value))
+    };
+
+    targets
+        .into_iter()
+        .map(|target| match target {
+            AnalogTarget::Axis(axis, _) => {
+                let value = |direction| value_of(AnalogTarget::Axis(axis.clone(), direction));
+                NativeEvent::new(
+                    Capability::Gamepad(Gamepad::Axis(axis.clone())),
+                    InputValue::Vector2 {
+                        x: Some(value(Direction::Right) - value(Direction::Left)),
+                        y: Some(value(Direction::Down) - value(Direction::Up)),
+                    },
+                )
+            }
+            AnalogTarget::Trigger(trigger) => NativeEvent::new(
+                Capability::Gamepad(Gamepad::Trigger(trigger.clone())),
+                InputValue::Float(value_of(target.clone())),
+            ),
+        })
+        .collect()
+}
+
+/// Returns the HID keyboard usage of the given key
+fn hid_usage_from_key(key: &Keyboard) -> Option<u16> {
+    let code = match key {
+        Keyboard::KeyA => 0x04,
+        Keyboard::KeyB => 0x05,
+        Keyboard::KeyC => 0x06,
+        Keyboard::KeyD => 0x07,
+        Keyboard::KeyE => 0x08,
+        Keyboard::KeyF => 0x09,
+        Keyboard::KeyG => 0x0a,
+        Keyboard::KeyH => 0x0b,
+        Keyboard::KeyI => 0x0c,
+        Keyboard::KeyJ => 0x0d,
+        Keyboard::KeyK => 0x0e,
+        Keyboard::KeyL => 0x0f,
+        Keyboard::KeyM => 0x10,
+        Keyboard::KeyN => 0x11,
+        Keyboard::KeyO => 0x12,
+        Keyboard::KeyP => 0x13,
+        Keyboard::KeyQ => 0x14,
+        Keyboard::KeyR => 0x15,
+        Keyboard::KeyS => 0x16,
+        Keyboard::KeyT => 0x17,
+        Keyboard::KeyU => 0x18,
+        Keyboard::KeyV => 0x19,
+        Keyboard::KeyW => 0x1a,
+        Keyboard::KeyX => 0x1b,
+        Keyboard::KeyY => 0x1c,
+        Keyboard::KeyZ => 0x1d,
+        Keyboard::Key1 => 0x1e,
+        Keyboard::Key2 => 0x1f,
+        Keyboard::Key3 => 0x20,
+        Keyboard::Key4 => 0x21,
+        Keyboard::Key5 => 0x22,
+        Keyboard::Key6 => 0x23,
+        Keyboard::Key7 => 0x24,
+        Keyboard::Key8 => 0x25,
+        Keyboard::Key9 => 0x26,
+        Keyboard::Key0 => 0x27,
+        Keyboard::KeyEnter => 0x28,
+        Keyboard::KeyEsc => 0x29,
+        Keyboard::KeyBackspace => 0x2a,
+        Keyboard::KeyTab => 0x2b,
+        Keyboard::KeySpace => 0x2c,
+        Keyboard::KeyRight => 0x4f,
+        Keyboard::KeyLeft => 0x50,
+        Keyboard::KeyDown => 0x51,
+        Keyboard::KeyUp => 0x52,
+        Keyboard::KeyLeftCtrl => 0xe0,
+        Keyboard::KeyLeftShift => 0xe1,
+        Keyboard::KeyLeftAlt => 0xe2,
+        Keyboard::KeyRightCtrl => 0xe4,
+        Keyboard::KeyRightShift => 0xe5,
+        Keyboard::KeyRightAlt => 0xe6,
+        _ => return None,
+    };
+    Some(code)
+}
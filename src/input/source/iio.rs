@@ -1,4 +1,6 @@
 pub mod iio_imu;
+#[cfg(test)]
+mod iio_imu_test;
 
 use std::error::Error;
 
@@ -18,6 +20,10 @@ use super::SourceCommand;
 
 /// Size of the [SourceCommand] buffer for receiving output events
 const BUFFER_SIZE: usize = 2048;
+/// Names of IIO devices handled by the IMU driver, including devices that only
+/// expose an accelerometer or a gyro.
+const IMU_NAMES: &str =
+    "{i2c-BMI*,accel-display,bmi160,bmi*-imu,lsm6ds*,gyro_3d,accel_3d,bmg160,cros-ec-gyro}";
 
 /// DBusInterface exposing information about a IIO device
 pub struct DBusInterface {
@@ -91,7 +97,20 @@ impl IIODevice {
     }
 
     pub fn get_capabilities(&self) -> Result<Vec<Capability>, Box<dyn Error>> {
-        Ok(vec![])
+        let Some(id) = self.info.id.as_ref() else {
+            return Ok(vec![]);
+        };
+        let name = self.info.name.clone().unwrap_or_default();
+        if !glob_match(IMU_NAMES, name.as_str()) {
+            return Ok(vec![]);
+        }
+        match iio_imu::get_capabilities(id) {
+            Ok(capabilities) => Ok(capabilities),
+            Err(e) => {
+                log::warn!("Failed to get capabilities of IIO device {id}: {e:?}");
+                Ok(vec![])
+            }
+        }
     }
 
     /// Returns a unique identifier for the source device.
@@ -116,10 +135,7 @@ impl IIODevice {
         };
 
         // IIO IMU Driver
-        if glob_match(
-            "{i2c-BMI*,accel-display,bmi*-imu,gyro_3d,accel_3d}",
-            name.as_str(),
-        ) {
+        if glob_match(IMU_NAMES, name.as_str()) {
             log::info!("Detected IMU: {name}");
             let tx = self.composite_tx.clone();
             let rx = self.rx.take().unwrap();
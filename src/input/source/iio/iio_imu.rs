@@ -1,11 +1,16 @@
 use core::time;
 use std::{error::Error, f64::consts::PI, thread};
 
+use industrial_io::ChannelType;
 use tokio::sync::mpsc::{self, error::TryRecvError};
 
 use crate::{
     config,
-    drivers::iio_imu::{self, driver::Driver, info::MountMatrix},
+    drivers::iio_imu::{
+        self,
+        driver::{self, Driver},
+        info::{Calibration, MountMatrix},
+    },
     iio::device::Device,
     input::{
         capability::{Capability, Gamepad},
@@ -13,6 +18,7 @@ use crate::{
         event::{native::NativeEvent, value::InputValue, Event},
         source::SourceCommand,
     },
+    logger,
 };
 
 /// IIO IMU implementation of IIO interface
@@ -54,31 +60,81 @@ impl IMU {
         let mut rx = self.rx.take().unwrap();
 
         // Override the mount matrix if one is defined in the config
-        let mount_matrix = if let Some(config) = self.config.as_ref() {
-            if let Some(matrix_config) = config.mount_matrix.as_ref() {
-                let matrix = MountMatrix {
-                    x: (matrix_config.x[0], matrix_config.x[1], matrix_config.x[2]),
-                    y: (matrix_config.y[0], matrix_config.y[1], matrix_config.y[2]),
-                    z: (matrix_config.z[0], matrix_config.z[1], matrix_config.z[2]),
-                };
-                Some(matrix)
-            } else {
-                None
-            }
+        let mount_matrix = self
+            .config
+            .as_ref()
+            .and_then(|config| config.mount_matrix.as_ref())
+            .map(MountMatrix::from);
+
+        // Calibration applied to the sensor values before they are sent to
+        // the composite device
+        let accel_calibration = self
+            .config
+            .as_ref()
+            .and_then(|config| config.accel_calibration.as_ref())
+            .map(Calibration::from)
+            .unwrap_or_default();
+        let gyro_calibration = self
+            .config
+            .as_ref()
+            .and_then(|config| config.gyro_calibration.as_ref())
+            .map(Calibration::from)
+            .unwrap_or_default();
+
+        // Sampling options of the IMU
+        let sample_rate = self.config.as_ref().and_then(|config| config.sample_rate);
+        let buffered = self
+            .config
+            .as_ref()
+            .and_then(|config| config.buffered)
+            .unwrap_or(false);
+        let trigger = self
+            .config
+            .as_ref()
+            .and_then(|config| config.trigger.clone());
+
+        // When polling through sysfs, only poll as often as new samples are
+        // expected. Buffered reads block until new samples arrive.
+        let poll_interval = if buffered {
+            time::Duration::ZERO
         } else {
-            None
+            match sample_rate {
+                Some(rate) if rate > 0.0 => time::Duration::from_secs_f64(1.0 / rate),
+                _ => time::Duration::from_micros(250),
+            }
         };
 
         // Spawn a blocking task with the given poll rate to poll the IMU for
         // data.
         let task =
-            tokio::task::spawn_blocking(move || -> Result<(), Box<dyn Error + Send + Sync>> {
-                let driver = Driver::new(id, name, mount_matrix)?;
+            logger::spawn_blocking_in_scope(move || -> Result<(), Box<dyn Error + Send + Sync>> {
+                let mut driver = Driver::new(id, name, mount_matrix)?;
+                if let Some(rate) = sample_rate {
+                    if let Err(e) = driver.set_sample_rate(rate) {
+                        log::warn!("Failed to set IIO sampling frequency: {e:?}");
+                    }
+                }
+                if buffered {
+                    driver.enable_buffer(trigger.as_deref())?;
+                }
                 loop {
-                    receive_commands(&mut rx)?;
+                    match receive_commands(&mut rx) {
+                        Ok(false) => (),
+                        Ok(true) => {
+                            log::debug!("Device stop requested");
+                            break;
+                        }
+                        Err(e) => {
+                            log::debug!("Error receiving commands: {:?}", e);
+                            break;
+                        }
+                    }
                     let events = driver.poll()?;
-                    let native_events = translate_events(events);
-                    for event in native_events {
+                    let read_time = driver.last_report_time();
+                    let native_events =
+                        translate_events(events, &accel_calibration, &gyro_calibration);
+                    for mut event in native_events {
+                        event.set_timestamp(read_time);
                         log::trace!("Sending event to CompositeDevice: {:?}", event);
                         // Don't send un-implemented events
                         if matches!(event.as_capability(), Capability::NotImplemented) {
@@ -91,9 +147,12 @@ impl IMU {
                     }
 
                     // Sleep between each poll iteration
-                    let duration = time::Duration::from_micros(250);
-                    thread::sleep(duration);
+                    if !poll_interval.is_zero() {
+                        thread::sleep(poll_interval);
+                    }
                 }
+
+                Ok(())
             });
 
         // Wait for the task to finish
@@ -108,15 +167,40 @@ impl IMU {
     }
 }
 
-/// Translate the given driver events into native events
-fn translate_events(events: Vec<iio_imu::event::Event>) -> Vec<NativeEvent> {
-    events.into_iter().map(translate_event).collect()
+/// Returns the capabilities of the IIO IMU with the given id, depending on
+/// whether it exposes accelerometer and/or gyro channels.
+pub fn get_capabilities(id: &str) -> Result<Vec<Capability>, Box<dyn Error>> {
+    let mut capabilities = Vec::new();
+    if driver::has_channel_type(id, ChannelType::Accel)? {
+        capabilities.push(Capability::Gamepad(Gamepad::Accelerometer));
+    }
+    if driver::has_channel_type(id, ChannelType::AnglVel)? {
+        capabilities.push(Capability::Gamepad(Gamepad::Gyro));
+    }
+    Ok(capabilities)
+}
+
+/// Translate the given driver events into calibrated native events
+fn translate_events(
+    events: Vec<iio_imu::event::Event>,
+    accel_calibration: &Calibration,
+    gyro_calibration: &Calibration,
+) -> Vec<NativeEvent> {
+    events
+        .into_iter()
+        .map(|event| translate_event(event, accel_calibration, gyro_calibration))
+        .collect()
 }
 
-/// Translate the given driver event into a native event
-fn translate_event(event: iio_imu::event::Event) -> NativeEvent {
+/// Translate the given driver event into a calibrated native event
+fn translate_event(
+    event: iio_imu::event::Event,
+    accel_calibration: &Calibration,
+    gyro_calibration: &Calibration,
+) -> NativeEvent {
     match event {
-        iio_imu::event::Event::Accelerometer(data) => {
+        iio_imu::event::Event::Accelerometer(mut data) => {
+            accel_calibration.apply(&mut data);
             let cap = Capability::Gamepad(Gamepad::Accelerometer);
             let value = InputValue::Vector3 {
                 x: Some(data.x),
@@ -125,13 +209,17 @@ fn translate_event(event: iio_imu::event::Event) -> NativeEvent {
             };
             NativeEvent::new(cap, value)
         }
-        iio_imu::event::Event::Gyro(data) => {
+        iio_imu::event::Event::Gyro(mut data) => {
             // Translate gyro values into the expected units of degrees per sec
+            data.x *= 180.0 / PI;
+            data.y *= 180.0 / PI;
+            data.z *= 180.0 / PI;
+            gyro_calibration.apply(&mut data);
             let cap = Capability::Gamepad(Gamepad::Gyro);
             let value = InputValue::Vector3 {
-                x: Some(data.x * (180.0 / PI)),
-                y: Some(data.y * (180.0 / PI)),
-                z: Some(data.z * (180.0 / PI)),
+                x: Some(data.x),
+                y: Some(data.y),
+                z: Some(data.z),
             };
             NativeEvent::new(cap, value)
         }
@@ -139,20 +227,20 @@ fn translate_event(event: iio_imu::event::Event) -> NativeEvent {
 }
 
 /// Read commands sent to this device from the channel until it is
-/// empty.
-fn receive_commands(
+/// empty. Returns `true` if the device was asked to stop.
+pub fn receive_commands(
     rx: &mut mpsc::Receiver<SourceCommand>,
-) -> Result<(), Box<dyn Error + Send + Sync>> {
+) -> Result<bool, Box<dyn Error + Send + Sync>> {
     const MAX_COMMANDS: u8 = 64;
     let mut commands_processed = 0;
     loop {
         match rx.try_recv() {
             Ok(cmd) => match cmd {
-                SourceCommand::Stop => return Err("Device stopped".into()),
+                SourceCommand::Stop => return Ok(true),
                 _ => {}
             },
             Err(e) => match e {
-                TryRecvError::Empty => return Ok(()),
+                TryRecvError::Empty => return Ok(false),
                 TryRecvError::Disconnected => {
                     log::debug!("Receive channel disconnected");
                     return Err("Receive channel disconnected".into());
@@ -162,7 +250,7 @@ fn receive_commands(
 
         commands_processed += 1;
         if commands_processed >= MAX_COMMANDS {
-            return Ok(());
+            return Ok(false);
         }
     }
 }
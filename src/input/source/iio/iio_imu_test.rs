@@ -0,0 +1,27 @@
+use tokio::sync::mpsc;
+
+use crate::input::source::{iio::iio_imu::receive_commands, SourceCommand};
+
+#[test]
+fn test_stop_is_not_an_error() {
+    let (tx, mut rx) = mpsc::channel(8);
+    tx.try_send(SourceCommand::Stop).unwrap();
+
+    // A stopped source must return cleanly so no DeviceError is emitted
+    let stopped = receive_commands(&mut rx).unwrap();
+    assert!(stopped);
+}
+
+#[test]
+fn test_no_commands() {
+    let (_tx, mut rx) = mpsc::channel::<SourceCommand>(8);
+    let stopped = receive_commands(&mut rx).unwrap();
+    assert!(!stopped);
+}
+
+#[test]
+fn test_disconnected() {
+    let (tx, mut rx) = mpsc::channel::<SourceCommand>(8);
+    drop(tx);
+    assert!(receive_commands(&mut rx).is_err());
+}
@@ -1,12 +1,25 @@
-use std::{error::Error, sync::mpsc::Sender};
+use std::{
+    error::Error,
+    io,
+    sync::mpsc::Sender,
+    time::{Duration, Instant},
+};
 
 use ::evdev::FFEffectData;
+use nix::errno::Errno;
+use tokio::{
+    sync::{mpsc, watch},
+    time,
+};
 
-use super::output_event::OutputEvent;
+use super::{composite_device::Command, output_event::OutputEvent};
 
 pub mod evdev;
 pub mod hidraw;
 pub mod iio;
+pub mod network;
+#[cfg(test)]
+mod network_test;
 
 /// A [SourceDevice] is any physical input device that emits input events
 #[derive(Debug)]
@@ -27,5 +40,139 @@ pub enum SourceCommand {
     ),
     UpdateEffect(i16, FFEffectData),
     EraseEffect(i16, Sender<Result<(), Box<dyn Error + Send + Sync>>>),
+    SetGrab(bool),
+    /// Close and re-open the underlying device, e.g. to recover a device
+    /// that stopped sending input reports
+    Reopen,
     Stop,
 }
+
+/// Time without input reports before a continuously reporting source device
+/// is considered stalled.
+pub const STALL_TIMEOUT: Duration = Duration::from_secs(2);
+/// Maximum number of consecutive attempts to reset a stalled source device
+pub const STALL_RESET_MAX: u32 = 3;
+/// Interval to check continuously reporting source devices for stalls
+const STALL_CHECK_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Delay before the first retry to open a device. Doubles after every attempt.
+const OPEN_RETRY_DELAY: Duration = Duration::from_millis(250);
+/// Maximum delay between attempts to open a device
+const OPEN_RETRY_DELAY_MAX: Duration = Duration::from_secs(8);
+
+/// Opens a source device using the given function, retrying with exponential
+/// backoff if the device is busy or not yet accessible (e.g. udev rules have
+/// not been applied yet or another process holds the device). Retries
+/// continue at the maximum delay until the device can be opened or fails with
+/// any other error, such as when the device was removed. A warning is sent to
+/// the composite device every time the delay increases.
+pub async fn open_with_retry<T, F>(
+    device_id: &str,
+    composite_tx: &mpsc::Sender<Command>,
+    mut open: F,
+) -> Result<T, Box<dyn Error>>
+where
+    F: FnMut() -> io::Result<T>,
+{
+    let mut delay = OPEN_RETRY_DELAY;
+    let mut escalated = false;
+    let mut attempt = 1;
+    loop {
+        let err = match open() {
+            Ok(device) => return Ok(device),
+            Err(e) => e,
+        };
+        if !is_retryable_open_error(&err) {
+            return Err(err.into());
+        }
+
+        let msg = format!(
+            "Failed to open device (attempt {attempt}): {err}. Retrying in {}ms",
+            delay.as_millis()
+        );
+        if escalated {
+            log::debug!("{device_id}: {msg}");
+        } else {
+            log::warn!("{device_id}: {msg}");
+            let cmd = Command::SourceDeviceWarning(device_id.to_string(), msg);
+            if let Err(e) = composite_tx.send(cmd).await {
+                log::debug!("Failed to send source device warning: {e:?}");
+            }
+        }
+
+        tokio::time::sleep(delay).await;
+        escalated = delay >= OPEN_RETRY_DELAY_MAX;
+        delay = (delay * 2).min(OPEN_RETRY_DELAY_MAX);
+        attempt += 1;
+    }
+}
+
+/// Watches the time of the last input report received by a continuously
+/// reporting source device and asks the source device to re-open itself if
+/// it stops sending reports. The stall and recovery are signaled to the
+/// composite device. Monitoring starts once the device sent its first report,
+/// and an error is returned if the device does not recover after
+/// [STALL_RESET_MAX] attempts.
+pub async fn monitor_stalls(
+    device_id: String,
+    mut report_rx: watch::Receiver<Instant>,
+    source_tx: mpsc::Sender<SourceCommand>,
+    composite_tx: mpsc::Sender<Command>,
+) -> Result<(), String> {
+    if report_rx.changed().await.is_err() {
+        return Ok(());
+    }
+
+    let mut resets = 0;
+    let mut reset_at: Option<Instant> = None;
+    let mut interval = time::interval(STALL_CHECK_INTERVAL);
+    loop {
+        interval.tick().await;
+        let last_report = *report_rx.borrow();
+        match reset_at {
+            Some(at) if last_report > at => {
+                log::info!("Device {device_id} recovered from stall");
+                resets = 0;
+                reset_at = None;
+                let cmd = Command::SourceDeviceStalled(device_id.clone(), false);
+                if let Err(e) = composite_tx.send(cmd).await {
+                    log::debug!("Failed to send source device stall state: {e:?}");
+                }
+                continue;
+            }
+            // Give the re-opened device time to start reporting again
+            Some(at) if at.elapsed() < STALL_TIMEOUT => continue,
+            None if last_report.elapsed() < STALL_TIMEOUT => continue,
+            _ => (),
+        }
+
+        if resets >= STALL_RESET_MAX {
+            if let Err(e) = source_tx.send(SourceCommand::Stop).await {
+                log::debug!("Failed to stop stalled device: {e:?}");
+            }
+            return Err("Device stopped responding".into());
+        }
+        if resets == 0 {
+            log::warn!("Device {device_id} stopped sending input reports");
+            let cmd = Command::SourceDeviceStalled(device_id.clone(), true);
+            if let Err(e) = composite_tx.send(cmd).await {
+                log::debug!("Failed to send source device stall state: {e:?}");
+            }
+        }
+        resets += 1;
+        log::info!("Resetting stalled device {device_id} (attempt {resets}/{STALL_RESET_MAX})");
+        if source_tx.send(SourceCommand::Reopen).await.is_err() {
+            return Ok(());
+        }
+        reset_at = Some(Instant::now());
+    }
+}
+
+/// Returns true if the given error from opening a device may go away on its
+/// own, such as permission or busy errors.
+fn is_retryable_open_error(err: &io::Error) -> bool {
+    if err.kind() == io::ErrorKind::PermissionDenied {
+        return true;
+    }
+    matches!(err.raw_os_error(), Some(code) if code == Errno::EBUSY as i32)
+}
@@ -0,0 +1,262 @@
+use std::{
+    collections::HashMap,
+    error::Error,
+    net::{IpAddr, SocketAddr},
+    time::{Duration, Instant},
+};
+
+use tokio::{
+    net::UdpSocket,
+    sync::{broadcast, mpsc},
+    time,
+};
+
+use crate::input::{
+    event::network::{self, NetworkEvent},
+    manager::{ManagerCommand, ManagerError},
+    target::TargetCommand,
+};
+
+/// Maximum size of a network event packet
+const MAX_PACKET_SIZE: usize = 512;
+/// Maximum number of remote devices that can be connected at the same time
+const MAX_REMOTE_DEVICES: usize = 4;
+/// Address to listen on if no address is configured
+const DEFAULT_LISTEN_ADDRESS: &str = "127.0.0.1";
+/// Time without receiving any packets before a remote device is removed
+const PEER_TIMEOUT: Duration = Duration::from_secs(5);
+/// Environment variable used to enable listening for remote devices
+pub const NETWORK_LISTEN_ENV: &str = "INPUTPLUMBER_NETWORK_LISTEN";
+/// Kind of target device created for each remote device
+const REMOTE_TARGET_KIND: &str = "gamepad";
+
+/// A remote InputPlumber instance sending events to this one
+#[derive(Debug)]
+struct RemoteDevice {
+    target_path: String,
+    target_tx: mpsc::Sender<TargetCommand>,
+    last_seen: Instant,
+}
+
+/// The [ReplayGuard] rejects replayed network packets. It keeps the highest
+/// sequence number received from each sender address, even after the remote
+/// device was removed, and rejects any packet whose sequence number is older
+/// than [network::MAX_PACKET_AGE]. Since sequence numbers are never lower than
+/// the time a packet was sent, a captured packet can only be replayed from
+/// another address within that window.
+#[derive(Debug, Default)]
+pub struct ReplayGuard {
+    sequences: HashMap<IpAddr, u64>,
+}
+
+impl ReplayGuard {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns whether a packet with the given sequence number from the given
+    /// sender should be accepted at the given time in microseconds since the
+    /// Unix epoch. Accepted sequence numbers are remembered for the sender.
+    pub fn accept(&mut self, sender: IpAddr, sequence: u64, now_micros: u64) -> bool {
+        if now_micros.saturating_sub(sequence) > network::MAX_PACKET_AGE.as_micros() as u64 {
+            return false;
+        }
+        let last_sequence = self.sequences.entry(sender).or_default();
+        if sequence <= *last_sequence {
+            return false;
+        }
+        *last_sequence = sequence;
+        true
+    }
+
+    /// Forget the sequence numbers of senders that are older than the
+    /// maximum packet age, since older packets are rejected anyway.
+    pub fn prune(&mut self, now_micros: u64) {
+        let max_age = network::MAX_PACKET_AGE.as_micros() as u64;
+        self.sequences
+            .retain(|_, sequence| now_micros.saturating_sub(*sequence) <= max_age);
+    }
+}
+
+/// The [NetworkListener] receives input events forwarded from network target
+/// devices of other InputPlumber instances over UDP and emits them on a local
+/// target gamepad. A target device is created for every remote sender, up to
+/// a maximum number of senders, and removed when the sender disconnects or
+/// stops sending packets. Packets must be signed with the pre-shared key set
+/// in the `INPUTPLUMBER_NETWORK_KEY` environment variable.
+#[derive(Debug)]
+pub struct NetworkListener {
+    address: String,
+    key: Vec<u8>,
+    manager_tx: broadcast::Sender<ManagerCommand>,
+    remotes: HashMap<SocketAddr, RemoteDevice>,
+    replay_guard: ReplayGuard,
+}
+
+impl NetworkListener {
+    pub fn new(
+        address: String,
+        key: Vec<u8>,
+        manager_tx: broadcast::Sender<ManagerCommand>,
+    ) -> Self {
+        let address = if address.is_empty() {
+            DEFAULT_LISTEN_ADDRESS.to_string()
+        } else {
+            address
+        };
+        Self {
+            address,
+            key,
+            manager_tx,
+            remotes: HashMap::new(),
+            replay_guard: ReplayGuard::new(),
+        }
+    }
+
+    /// Returns a [NetworkListener] if listening for remote devices was enabled
+    /// with the `INPUTPLUMBER_NETWORK_LISTEN` environment variable (e.g.
+    /// "192.168.1.10", "[::]:31415", or an empty value to listen on the
+    /// loopback address). A pre-shared key must also be set in the
+    /// `INPUTPLUMBER_NETWORK_KEY` environment variable.
+    pub fn from_env(manager_tx: broadcast::Sender<ManagerCommand>) -> Option<Self> {
+        let address = std::env::var(NETWORK_LISTEN_ENV).ok()?;
+        let Some(key) = network::key_from_env() else {
+            log::error!(
+                "Not listening for remote devices: {} is not set",
+                network::NETWORK_KEY_ENV
+            );
+            return None;
+        };
+        Some(Self::new(address, key, manager_tx))
+    }
+
+    /// Listen for network events from remote devices
+    pub async fn run(&mut self) -> Result<(), Box<dyn Error>> {
+        let address = network::resolve_address(&self.address).await?;
+        let socket = UdpSocket::bind(address).await?;
+        log::info!("Listening for remote devices on {address}");
+
+        let mut buf = [0; MAX_PACKET_SIZE];
+        let mut interval = time::interval(PEER_TIMEOUT);
+        loop {
+            tokio::select! {
+                result = socket.recv_from(&mut buf) => {
+                    let (len, peer) = result?;
+                    match NetworkEvent::from_bytes(&buf[..len], &self.key) {
+                        Ok((sequence, event)) => self.on_event(peer, sequence, event).await,
+                        Err(e) => log::debug!("Invalid packet from {peer}: {e:?}"),
+                    }
+                }
+                _ = interval.tick() => {
+                    self.remove_stale_remotes();
+                    self.replay_guard.prune(network::timestamp_micros());
+                }
+            }
+        }
+    }
+
+    /// Handle the given event received from a remote device
+    async fn on_event(&mut self, peer: SocketAddr, sequence: u64, event: NetworkEvent) {
+        // Drop any replayed, reordered, or stale packets
+        if !self
+            .replay_guard
+            .accept(peer.ip(), sequence, network::timestamp_micros())
+        {
+            log::debug!("Dropping out of sequence packet from {peer}");
+            return;
+        }
+        if let Some(remote) = self.remotes.get_mut(&peer) {
+            remote.last_seen = Instant::now();
+        }
+
+        let event = match event {
+            NetworkEvent::Event(event) => event,
+            NetworkEvent::KeepAlive => return,
+            NetworkEvent::Disconnect => {
+                self.remove_remote(&peer);
+                return;
+            }
+        };
+
+        // Create a target device for new remote devices
+        if !self.remotes.contains_key(&peer) {
+            if self.remotes.len() >= MAX_REMOTE_DEVICES {
+                log::warn!("Ignoring remote device {peer}: too many remote devices connected");
+                return;
+            }
+            match self.create_target_device().await {
+                Ok((target_path, target_tx)) => {
+                    log::info!("Remote device {peer} connected as {target_path}");
+                    let remote = RemoteDevice {
+                        target_path,
+                        target_tx,
+                        last_seen: Instant::now(),
+                    };
+                    self.remotes.insert(peer, remote);
+                }
+                Err(e) => {
+                    log::error!("Failed to create target device for {peer}: {e:?}");
+                    return;
+                }
+            }
+        }
+
+        let Some(remote) = self.remotes.get(&peer) else {
+            return;
+        };
+        if let Err(e) = remote
+            .target_tx
+            .send(TargetCommand::WriteEvent(event))
+            .await
+        {
+            log::error!("Failed to write event from remote device {peer}: {e:?}");
+            self.remove_remote(&peer);
+        }
+    }
+
+    /// Ask the manager to create a new target device for a remote device
+    async fn create_target_device(
+        &self,
+    ) -> Result<(String, mpsc::Sender<TargetCommand>), Box<dyn Error>> {
+        let (sender, mut receiver) = mpsc::channel(1);
+        self.manager_tx
+            .send(ManagerCommand::CreateRemoteTargetDevice {
+                kind: REMOTE_TARGET_KIND.to_string(),
+                sender,
+            })
+            .map_err(|e| e.to_string())?;
+        let Some(result) = receiver.recv().await else {
+            return Err("No response from manager".into());
+        };
+        result.map_err(|e: ManagerError| e.to_string().into())
+    }
+
+    /// Remove any remote devices that have not sent packets recently
+    fn remove_stale_remotes(&mut self) {
+        let stale: Vec<SocketAddr> = self
+            .remotes
+            .iter()
+            .filter(|(_, remote)| remote.last_seen.elapsed() > PEER_TIMEOUT)
+            .map(|(peer, _)| *peer)
+            .collect();
+        for peer in stale {
+            log::info!("Remote device {peer} timed out");
+            self.remove_remote(&peer);
+        }
+    }
+
+    /// Stop the target device for the given remote device
+    fn remove_remote(&mut self, peer: &SocketAddr) {
+        let Some(remote) = self.remotes.remove(peer) else {
+            return;
+        };
+        log::info!("Remote device {peer} disconnected");
+        let path = remote.target_path;
+        if let Err(e) = self
+            .manager_tx
+            .send(ManagerCommand::StopTargetDevice { path })
+        {
+            log::error!("Failed to stop target device for {peer}: {e:?}");
+        }
+    }
+}
@@ -0,0 +1,42 @@
+use std::net::{IpAddr, Ipv4Addr};
+
+use crate::input::{event::network::MAX_PACKET_AGE, source::network::ReplayGuard};
+
+const SENDER: IpAddr = IpAddr::V4(Ipv4Addr::new(192, 168, 1, 10));
+const OTHER_SENDER: IpAddr = IpAddr::V4(Ipv4Addr::new(192, 168, 1, 11));
+const NOW: u64 = 1_700_000_000_000_000;
+
+#[test]
+fn test_replay_guard_rejects_replayed_packets() {
+    let mut guard = ReplayGuard::new();
+    assert!(guard.accept(SENDER, NOW, NOW));
+    assert!(guard.accept(SENDER, NOW + 1, NOW));
+    assert!(!guard.accept(SENDER, NOW + 1, NOW));
+    assert!(!guard.accept(SENDER, NOW, NOW));
+
+    // Other senders keep their own sequence numbers
+    assert!(guard.accept(OTHER_SENDER, NOW - 1, NOW));
+}
+
+#[test]
+fn test_replay_guard_rejects_stale_packets() {
+    let mut guard = ReplayGuard::new();
+    let max_age = MAX_PACKET_AGE.as_micros() as u64;
+    assert!(!guard.accept(SENDER, NOW - max_age - 1, NOW));
+    assert!(guard.accept(SENDER, NOW - max_age, NOW));
+}
+
+#[test]
+fn test_replay_guard_remembers_until_stale() {
+    let mut guard = ReplayGuard::new();
+    let max_age = MAX_PACKET_AGE.as_micros() as u64;
+    assert!(guard.accept(SENDER, NOW, NOW));
+
+    // Sequence numbers are kept while replays could still be accepted
+    guard.prune(NOW + max_age);
+    assert!(!guard.accept(SENDER, NOW, NOW + max_age));
+
+    // Pruned sequence numbers are rejected by their age instead
+    guard.prune(NOW + max_age + 1);
+    assert!(!guard.accept(SENDER, NOW, NOW + max_age + 1));
+}
@@ -0,0 +1,301 @@
+//! The CustomGamepad target provides a virtual gamepad whose name, vendor and
+//! product ids, and capabilities are defined by the device config or the DBus
+//! caller, to match what a specific game or anticheat expects.
+use std::{
+    collections::HashMap,
+    error::Error,
+    os::fd::AsRawFd,
+    str::FromStr,
+    sync::{Arc, Mutex},
+};
+
+use evdev::{
+    uinput::{VirtualDevice, VirtualDeviceBuilder},
+    AbsInfo, AbsoluteAxisCode, AttributeSet, BusType, EventType, FFEffectCode, InputEvent, InputId,
+    KeyCode, MiscCode, RelativeAxisCode, SynchronizationCode, SynchronizationEvent, UinputAbsSetup,
+};
+use nix::fcntl::{FcntlArg, OFlag};
+use tokio::sync::mpsc;
+use zbus::Connection;
+
+use crate::{
+    config::CustomTargetConfig,
+    dbus::interface::target::gamepad::TargetGamepadInterface,
+    input::{
+        capability::{Capability, Gamepad, GamepadAxis, GamepadButton, GamepadTrigger},
+        composite_device::Command,
+        event::{
+            evdev::{event_codes_from_capability, event_type_from_capability, EvdevEvent},
+            native::NativeEvent,
+        },
+    },
+};
+
+use super::{
+    gamepad::{self, GenericGamepad},
+    TargetCommand,
+};
+
+/// Size of the [TargetCommand] buffer for receiving input events
+const BUFFER_SIZE: usize = 2048;
+
+const DEFAULT_NAME: &str = "InputPlumber Gamepad";
+
+/// Capabilities used if the config does not define any
+const DEFAULT_CAPABILITIES: &[Capability] = &[
+    Capability::Gamepad(Gamepad::Button(GamepadButton::South)),
+    Capability::Gamepad(Gamepad::Button(GamepadButton::North)),
+    Capability::Gamepad(Gamepad::Button(GamepadButton::East)),
+    Capability::Gamepad(Gamepad::Button(GamepadButton::West)),
+    Capability::Gamepad(Gamepad::Button(GamepadButton::Start)),
+    Capability::Gamepad(Gamepad::Button(GamepadButton::Select)),
+    Capability::Gamepad(Gamepad::Button(GamepadButton::Guide)),
+    Capability::Gamepad(Gamepad::Button(GamepadButton::DPadDown)),
+    Capability::Gamepad(Gamepad::Button(GamepadButton::DPadUp)),
+    Capability::Gamepad(Gamepad::Button(GamepadButton::DPadLeft)),
+    Capability::Gamepad(Gamepad::Button(GamepadButton::DPadRight)),
+    Capability::Gamepad(Gamepad::Button(GamepadButton::LeftBumper)),
+    Capability::Gamepad(Gamepad::Button(GamepadButton::LeftStick)),
+    Capability::Gamepad(Gamepad::Button(GamepadButton::RightBumper)),
+    Capability::Gamepad(Gamepad::Button(GamepadButton::RightStick)),
+    Capability::Gamepad(Gamepad::Axis(GamepadAxis::LeftStick)),
+    Capability::Gamepad(Gamepad::Axis(GamepadAxis::RightStick)),
+    Capability::Gamepad(Gamepad::Trigger(GamepadTrigger::LeftTrigger)),
+    Capability::Gamepad(Gamepad::Trigger(GamepadTrigger::RightTrigger)),
+];
+
+#[derive(Debug)]
+pub struct CustomGamepad {
+    conn: Connection,
+    dbus_path: Option<String>,
+    tx: mpsc::Sender<TargetCommand>,
+    rx: mpsc::Receiver<TargetCommand>,
+    composite_tx: Option<mpsc::Sender<Command>>,
+    name: String,
+    vendor_id: u16,
+    product_id: u16,
+    capabilities: Vec<Capability>,
+}
+
+impl CustomGamepad {
+    pub fn new(conn: Connection, config: CustomTargetConfig) -> Self {
+        let (tx, rx) = mpsc::channel(BUFFER_SIZE);
+        let capabilities = match config.capabilities {
+            Some(capabilities) => capabilities
+                .iter()
+                .filter_map(|cap| match Capability::from_str(cap) {
+                    Ok(cap) => Some(cap),
+                    Err(_) => {
+                        log::error!("Invalid custom target capability: {cap}");
+                        None
+                    }
+                })
+                .collect(),
+            None => DEFAULT_CAPABILITIES.to_vec(),
+        };
+        Self {
+            conn,
+            dbus_path: None,
+            tx,
+            rx,
+            composite_tx: None,
+            name: config.name.unwrap_or_else(|| DEFAULT_NAME.to_string()),
+            vendor_id: config.vendor_id.unwrap_or(gamepad::VENDOR_ID),
+            product_id: config.product_id.unwrap_or(gamepad::PRODUCT_ID),
+            capabilities,
+        }
+    }
+
+    /// Returns a transmitter channel that can be used to send events to this device
+    pub fn transmitter(&self) -> mpsc::Sender<TargetCommand> {
+        self.tx.clone()
+    }
+
+    /// Configures the device to send output events to the given composite device
+    /// channel.
+    pub fn set_composite_device(&mut self, tx: mpsc::Sender<Command>) {
+        self.composite_tx = Some(tx);
+    }
+
+    /// Creates a new instance of the dbus device interface on DBus.
+    pub async fn listen_on_dbus(&mut self, path: String) -> Result<(), Box<dyn Error>> {
+        let conn = self.conn.clone();
+        let name = self.name.clone();
+        self.dbus_path = Some(path.clone());
+        tokio::spawn(async move {
+            let iface = TargetGamepadInterface::new(name);
+            if let Err(e) = conn.object_server().at(path, iface).await {
+                log::error!("Failed to setup DBus interface for Gamepad device: {:?}", e);
+            }
+        });
+        Ok(())
+    }
+
+    /// Creates and runs the target device
+    pub async fn run(&mut self) -> Result<(), Box<dyn Error>> {
+        log::debug!("Creating virtual custom gamepad: {}", self.name);
+        let device = self.create_virtual_device()?;
+
+        // Put the device behind an Arc Mutex so it can be shared between the
+        // read and write threads
+        let device = Arc::new(Mutex::new(device));
+
+        // Query information about the device to get the absolute ranges
+        let axes_map = self.get_abs_info();
+
+        // Listen for events from source devices
+        log::debug!("Started listening for events");
+        while let Some(command) = self.rx.recv().await {
+            match command {
+                TargetCommand::SetCompositeDevice(tx) => {
+                    self.set_composite_device(tx.clone());
+
+                    // Spawn a thread to listen for force feedback events
+                    let ff_device = device.clone();
+                    GenericGamepad::spawn_ff_thread(ff_device, tx);
+                }
+                TargetCommand::WriteEvent(event) => {
+                    log::trace!("Got event to emit: {:?}", event);
+                    if !self.capabilities.contains(&event.as_capability()) {
+                        continue;
+                    }
+                    let evdev_events = self.translate_event(event, axes_map.clone());
+                    if let Ok(mut dev) = device.lock() {
+                        dev.emit(evdev_events.as_slice())?;
+                        dev.emit(&[
+                            SynchronizationEvent::new(SynchronizationCode::SYN_REPORT, 0).into(),
+                        ])?;
+                    }
+                }
+                TargetCommand::GetCapabilities(tx) => {
+                    let caps = self.capabilities.clone();
+                    if let Err(e) = tx.send(caps).await {
+                        log::error!("Failed to send target capabilities: {e:?}");
+                    }
+                }
+                TargetCommand::Stop => break,
+            }
+        }
+
+        log::debug!(
+            "Stopping device {}",
+            self.dbus_path.clone().unwrap_or_default()
+        );
+
+        // Remove the DBus interface
+        if let Some(path) = self.dbus_path.clone() {
+            log::debug!("Removing DBus interface for {path}");
+            self.conn
+                .object_server()
+                .remove::<TargetGamepadInterface, String>(path)
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Translate the given native event into an evdev event
+    fn translate_event(
+        &self,
+        event: NativeEvent,
+        axis_map: HashMap<AbsoluteAxisCode, AbsInfo>,
+    ) -> Vec<InputEvent> {
+        EvdevEvent::from_native_event(event, axis_map)
+            .into_iter()
+            .map(|event| event.as_input_event())
+            .collect()
+    }
+
+    /// Return a hashmap of ABS information for the axes used by the
+    /// capabilities of this virtual device. This information is used to
+    /// denormalize input event values.
+    fn get_abs_info(&self) -> HashMap<AbsoluteAxisCode, AbsInfo> {
+        let mut axes_info = HashMap::new();
+        for capability in self.capabilities.iter() {
+            if event_type_from_capability(capability.clone()) != Some(EventType::ABSOLUTE) {
+                continue;
+            }
+            for code in event_codes_from_capability(capability.clone()) {
+                let code = AbsoluteAxisCode(code);
+                axes_info.insert(code, abs_info_for_axis(code));
+            }
+        }
+
+        axes_info
+    }
+
+    /// Create the virtual device to emulate
+    fn create_virtual_device(&self) -> Result<VirtualDevice, Box<dyn Error>> {
+        // Setup the inputs used by the configured capabilities
+        let mut keys = AttributeSet::<KeyCode>::new();
+        let mut relative_axes = AttributeSet::<RelativeAxisCode>::new();
+        for capability in self.capabilities.iter() {
+            let codes = event_codes_from_capability(capability.clone());
+            match event_type_from_capability(capability.clone()) {
+                Some(EventType::KEY) => {
+                    for code in codes {
+                        keys.insert(KeyCode::new(code));
+                    }
+                }
+                Some(EventType::RELATIVE) => {
+                    for code in codes {
+                        relative_axes.insert(RelativeAxisCode(code));
+                    }
+                }
+                Some(EventType::ABSOLUTE) => (),
+                _ => {
+                    log::warn!("Unsupported custom target capability: {capability}");
+                }
+            }
+        }
+
+        // Setup Force Feedback
+        let mut ff = AttributeSet::<FFEffectCode>::new();
+        ff.insert(FFEffectCode::FF_RUMBLE);
+        ff.insert(FFEffectCode::FF_PERIODIC);
+        ff.insert(FFEffectCode::FF_SQUARE);
+        ff.insert(FFEffectCode::FF_TRIANGLE);
+        ff.insert(FFEffectCode::FF_SINE);
+        ff.insert(FFEffectCode::FF_GAIN);
+
+        // Build the device
+        let id = InputId::new(BusType::BUS_USB, self.vendor_id, self.product_id, 1);
+        let mut builder = VirtualDeviceBuilder::new()?
+            .name(self.name.as_str())
+            .input_id(id);
+        if keys.iter().next().is_some() {
+            builder = builder.with_keys(&keys)?;
+        }
+        if relative_axes.iter().next().is_some() {
+            builder = builder.with_relative_axes(&relative_axes)?;
+        }
+        for (code, info) in self.get_abs_info() {
+            builder = builder.with_absolute_axis(&UinputAbsSetup::new(code, info))?;
+        }
+        let device = builder
+            .with_ff(&ff)?
+            .with_ff_effects_max(16)
+            .with_msc(&AttributeSet::from_iter([MiscCode::MSC_TIMESTAMP]))?
+            .build()?;
+
+        // Set the device to do non-blocking reads
+        let raw_fd = device.as_raw_fd();
+        nix::fcntl::fcntl(raw_fd, FcntlArg::F_SETFL(OFlag::O_NONBLOCK))?;
+
+        Ok(device)
+    }
+}
+
+/// Returns the range to use for the given absolute axis
+fn abs_info_for_axis(code: AbsoluteAxisCode) -> AbsInfo {
+    match code {
+        AbsoluteAxisCode::ABS_Z | AbsoluteAxisCode::ABS_RZ => AbsInfo::new(0, 0, 255, 0, 0, 1),
+        AbsoluteAxisCode::ABS_HAT0X
+        | AbsoluteAxisCode::ABS_HAT0Y
+        | AbsoluteAxisCode::ABS_HAT1X
+        | AbsoluteAxisCode::ABS_HAT1Y
+        | AbsoluteAxisCode::ABS_HAT2X
+        | AbsoluteAxisCode::ABS_HAT2Y => AbsInfo::new(0, -1, 1, 0, 0, 1),
+        _ => AbsInfo::new(0, -32768, 32767, 16, 128, 1),
+    }
+}
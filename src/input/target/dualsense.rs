@@ -0,0 +1,1972 @@
+//! Emulates a Sony DualSense gamepad as a target input device.
+//! The DualSense implementation is based on the great work done by NeroReflex
+//! and the ROGueENEMY project:
+//! https://github.com/NeroReflex/ROGueENEMY/
+use std::{
+    cmp::Ordering,
+    error::Error,
+    fmt::Debug,
+    fs::File,
+    time::{Duration, Instant},
+    usize,
+};
+
+use packed_struct::prelude::*;
+use tokio::sync::mpsc::{self, error::TryRecvError};
+use uhid_virt::{Bus, CreateParams, ReportType, UHIDDevice};
+use zbus::{fdo, Connection};
+use zbus_macros::dbus_interface;
+
+use crate::{
+    dbus::interface::target::gamepad::TargetGamepadInterface,
+    drivers::dualsense::{
+        driver::{
+            bluetooth_input_report, DS5_ACC_RES_PER_G, DS5_EDGE_NAME, DS5_EDGE_PID,
+            DS5_EDGE_VERSION, DS5_EDGE_VID, DS5_NAME, DS5_PID, DS5_TIMESTAMP_TICKS_PER_US,
+            DS5_TOUCHPAD_HEIGHT, DS5_TOUCHPAD_WIDTH, DS5_TOUCH_TIMESTAMP_SHIFT, DS5_VERSION,
+            DS5_VID, FEATURE_REPORT_CALIBRATION, FEATURE_REPORT_FIRMWARE_INFO,
+            FEATURE_REPORT_PAIRING_INFO, OUTPUT_REPORT_BT, OUTPUT_REPORT_BT_SIZE,
+            OUTPUT_REPORT_USB, OUTPUT_REPORT_USB_SHORT_SIZE, OUTPUT_REPORT_USB_SIZE, STICK_X_MAX,
+            STICK_X_MIN, STICK_Y_MAX, STICK_Y_MIN, TRIGGER_MAX,
+        },
+        hid_report::{
+            Direction, PackedInputDataReport, SetStatePackedOutputData, USBPackedInputDataReport,
+            UsbPackedOutputReport, UsbPackedOutputReportShort, TOUCH_TRACKING_ID_MASK,
+        },
+        report_descriptor::{
+            DS_BT_DESCRIPTOR, DS_EDGE_BT_DESCRIPTOR, DS_EDGE_USB_DESCRIPTOR, DS_USB_DESCRIPTOR,
+        },
+    },
+    input::{
+        capability::{
+            Capability, Gamepad, GamepadAxis, GamepadButton, GamepadTrigger, Touch, TouchButton,
+            Touchpad,
+        },
+        composite_device::Command,
+        event::{native::NativeEvent, value::InputValue},
+        output_event,
+    },
+};
+
+use super::{
+    uhid::{self, FeatureReports, EIO},
+    TargetCommand,
+};
+
+const POLL_INTERVAL_MS: u64 = 4;
+const BUFFER_SIZE: usize = 2048;
+/// Size of the state data in output reports
+const OUTPUT_STATE_SIZE: usize = 47;
+
+/// The type of DualSense device to emulate. Currently two models are supported:
+/// DualSense and DualSense Edge.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum ModelType {
+    Normal,
+    Edge,
+}
+
+/// The DualSense device can be emulated using either the USB or Bluetooth buses
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum BusType {
+    Usb,
+    Bluetooth,
+}
+
+/// The [DualSenseHardware] defines the kind of DualSense controller to emulate
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct DualSenseHardware {
+    model: ModelType,
+    bus_type: BusType,
+    mac_addr: [u8; 6],
+}
+
+impl DualSenseHardware {
+    pub fn new(model: ModelType, bus_type: BusType) -> Self {
+        // "e8:47:3a:d6:e7:74"
+        let mac_addr = [0x74, 0xe7, 0xd6, 0x3a, 0x47, 0xe8];
+        Self {
+            model,
+            bus_type,
+            mac_addr,
+        }
+    }
+}
+
+impl DualSenseHardware {
+    /// Set the MAC address of the emulated controller based on the given source
+    /// device serial number. Serials that are MAC addresses are used as-is,
+    /// otherwise a stable address is derived from the serial.
+    pub fn set_serial(&mut self, serial: &str) {
+        self.mac_addr = mac_addr_from_serial(serial);
+    }
+}
+
+impl Default for DualSenseHardware {
+    fn default() -> Self {
+        Self {
+            model: ModelType::Normal,
+            bus_type: BusType::Usb,
+            mac_addr: [0x74, 0xe7, 0xd6, 0x3a, 0x47, 0xe8],
+        }
+    }
+}
+
+/// Area of a target touchpad that a source touchpad is mapped onto. Left and
+/// right source pads each take half of the touchpad so both can be used at the
+/// same time.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum TouchpadRegion {
+    Full,
+    Left,
+    Right,
+}
+
+impl TouchpadRegion {
+    /// Transform the given normalized x coordinate from the source touchpad
+    /// into a normalized x coordinate on the target touchpad.
+    pub fn transform_x(&self, x: f64) -> f64 {
+        let x = x.clamp(0.0, 1.0);
+        match self {
+            TouchpadRegion::Full => x,
+            TouchpadRegion::Left => x * 0.5,
+            TouchpadRegion::Right => 0.5 + x * 0.5,
+        }
+    }
+}
+
+impl From<&Touchpad> for TouchpadRegion {
+    fn from(touchpad: &Touchpad) -> Self {
+        match touchpad {
+            Touchpad::LeftPad(_) => TouchpadRegion::Left,
+            Touchpad::RightPad(_) => TouchpadRegion::Right,
+            Touchpad::CenterPad(_) => TouchpadRegion::Full,
+        }
+    }
+}
+
+/// The [DualSenseDevice] is a target input device implementation that emulates
+/// a Playstation DualSense controller using uhid.
+#[derive(Debug)]
+pub struct DualSenseDevice {
+    conn: Connection,
+    dbus_path: Option<String>,
+    tx: mpsc::Sender<TargetCommand>,
+    rx: mpsc::Receiver<TargetCommand>,
+    state: PackedInputDataReport,
+    composite_tx: Option<mpsc::Sender<Command>>,
+    hardware: DualSenseHardware,
+    /// Tracking id to assign to the next new touch contact
+    next_touch_id: u8,
+    /// Source touch contacts currently assigned to each touchpad finger slot
+    touch_slots: [Option<(TouchpadRegion, u8)>; 2],
+    /// Source touchpads that are currently being clicked
+    touch_pressed: Vec<TouchpadRegion>,
+    /// Time the device was created, which the report timestamps count from
+    started_at: Instant,
+    /// Sequence number of the next Bluetooth input report
+    bt_sequence: u8,
+}
+
+impl DualSenseDevice {
+    pub fn new(conn: Connection, hardware: DualSenseHardware) -> Self {
+        let (tx, rx) = mpsc::channel(BUFFER_SIZE);
+        Self {
+            conn,
+            dbus_path: None,
+            tx,
+            rx,
+            state: PackedInputDataReport::Usb(USBPackedInputDataReport::new()),
+            composite_tx: None,
+            hardware,
+            next_touch_id: 0,
+            touch_slots: [None; 2],
+            touch_pressed: Vec::new(),
+            started_at: Instant::now(),
+            bt_sequence: 0,
+        }
+    }
+
+    /// Returns a transmitter channel that can be used to send events to this device
+    pub fn transmitter(&self) -> mpsc::Sender<TargetCommand> {
+        self.tx.clone()
+    }
+
+    /// Configures the device to send output events to the given composite device
+    /// channel.
+    pub fn set_composite_device(&mut self, tx: mpsc::Sender<Command>) {
+        self.composite_tx = Some(tx);
+    }
+
+    /// Creates a new instance of the dbus device interface on DBus.
+    pub async fn listen_on_dbus(&mut self, path: String) -> Result<(), Box<dyn Error>> {
+        let conn = self.conn.clone();
+        self.dbus_path = Some(path.clone());
+
+        let name = match self.hardware.model {
+            ModelType::Edge => match self.hardware.bus_type {
+                BusType::Bluetooth => "DualSense Edge (bluetooth)".to_string(),
+                BusType::Usb => "DualSense Edge".to_string(),
+            },
+            ModelType::Normal => match self.hardware.bus_type {
+                BusType::Bluetooth => "DualSense (bluetooth)".to_string(),
+                BusType::Usb => "DualSense".to_string(),
+            },
+        };
+
+        tokio::spawn(async move {
+            let iface = TargetGamepadInterface::new(name);
+            if let Err(e) = conn.object_server().at(path, iface).await {
+                log::error!("Failed to setup DBus interface for Gamepad device: {:?}", e);
+            }
+        });
+        Ok(())
+    }
+
+    /// Creates and runs the target device
+    pub async fn run(&mut self) -> Result<(), Box<dyn Error>> {
+        log::debug!("Creating virtual dualsense controller");
+        let mut device = self.create_virtual_device()?;
+
+        // Start the main run loop
+        log::debug!("Starting run loop");
+        let duration = Duration::from_millis(POLL_INTERVAL_MS);
+        let mut interval = tokio::time::interval(duration);
+        loop {
+            // Sleep for the given polling interval
+            interval.tick().await;
+
+            // Receive commands/events and update local state
+            if let Err(e) = self.receive_commands().await {
+                log::debug!("Error receiving commands: {:?}", e);
+                break;
+            }
+
+            // Poll the HIDRaw device
+            if let Err(e) = self.poll(&mut device).await {
+                log::debug!("Error polling UHID device: {:?}", e);
+                break;
+            }
+
+            // Write the state to the device
+            if let Err(e) = self.write_state(&mut device) {
+                log::debug!("Error writing state to device: {:?}", e);
+                break;
+            }
+        }
+
+        log::debug!("Stopped listening for events");
+        device.destroy()?;
+
+        // Remove the DBus interface
+        if let Some(path) = self.dbus_path.clone() {
+            log::debug!("Removing DBus interface for {path}");
+            self.conn
+                .object_server()
+                .remove::<TargetGamepadInterface, String>(path)
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Create the virtual device to emulate
+    fn create_virtual_device(&self) -> Result<UHIDDevice<File>, Box<dyn Error>> {
+        let device = UHIDDevice::create(CreateParams {
+            name: match self.hardware.model {
+                ModelType::Edge => String::from(DS5_EDGE_NAME),
+                ModelType::Normal => String::from(DS5_NAME),
+            },
+            phys: String::from(""),
+            uniq: format!(
+                "{:02x}:{:02x}:{:02x}:{:02x}:{:02x}:{:02x}",
+                self.hardware.mac_addr[5],
+                self.hardware.mac_addr[4],
+                self.hardware.mac_addr[3],
+                self.hardware.mac_addr[2],
+                self.hardware.mac_addr[1],
+                self.hardware.mac_addr[0],
+            ),
+            bus: match self.hardware.bus_type {
+                BusType::Bluetooth => Bus::BLUETOOTH,
+                BusType::Usb => Bus::USB,
+            },
+            vendor: match self.hardware.model {
+                ModelType::Edge => DS5_EDGE_VID as u32,
+                ModelType::Normal => DS5_VID as u32,
+            },
+            product: match self.hardware.model {
+                ModelType::Edge => DS5_EDGE_PID as u32,
+                ModelType::Normal => DS5_PID as u32,
+            },
+            version: match self.hardware.model {
+                ModelType::Edge => DS5_EDGE_VERSION as u32,
+                ModelType::Normal => DS5_VERSION as u32,
+            },
+            country: 0,
+            rd_data: match self.hardware.model {
+                ModelType::Edge => match self.hardware.bus_type {
+                    BusType::Bluetooth => DS_EDGE_BT_DESCRIPTOR.to_vec(),
+                    BusType::Usb => DS_EDGE_USB_DESCRIPTOR.to_vec(),
+                },
+                ModelType::Normal => match self.hardware.bus_type {
+                    BusType::Bluetooth => DS_BT_DESCRIPTOR.to_vec(),
+                    BusType::Usb => DS_USB_DESCRIPTOR.to_vec(),
+                },
+            },
+        })?;
+
+        Ok(device)
+    }
+
+    /// Read commands and events sent to this device
+    async fn receive_commands(&mut self) -> Result<(), Box<dyn Error>> {
+        // Read commands sent to this device from the channel until it is
+        // empty.
+        loop {
+            match self.rx.try_recv() {
+                Ok(cmd) => {
+                    match cmd {
+                        TargetCommand::SetCompositeDevice(tx) => {
+                            log::trace!("Recieved command to set composite device");
+                            self.set_composite_device(tx.clone());
+                        }
+                        TargetCommand::WriteEvent(event) => {
+                            log::trace!("Recieved event to write: {:?}", event);
+                            // Update internal state
+                            self.update_state(event);
+                        }
+                        TargetCommand::GetCapabilities(tx) => {
+                            let caps = self.get_capabilities();
+                            if let Err(e) = tx.send(caps).await {
+                                log::error!("Failed to send target capabilities: {e:?}");
+                            }
+                        }
+                        TargetCommand::Stop => return Err("Device stopped".into()),
+                    }
+                }
+                Err(e) => match e {
+                    TryRecvError::Empty => break,
+                    TryRecvError::Disconnected => {
+                        return Err("Receive channel disconnected".into());
+                    }
+                },
+            };
+        }
+
+        Ok(())
+    }
+
+    /// Handle reading from the device and processing output reports and
+    /// requests from the kernel.
+    async fn poll(&mut self, device: &mut UHIDDevice<File>) -> Result<(), Box<dyn Error>> {
+        let Some(data) = uhid::read_output(device, self)? else {
+            return Ok(());
+        };
+        if let Err(e) = self.handle_output(data).await {
+            let err = format!("Failed process output event: {:?}", e);
+            return Err(err.into());
+        }
+
+        Ok(())
+    }
+
+    /// Handle output reports from the HIDRAW device. These are
+    /// events which should be forwarded back to source devices.
+    async fn handle_output(&mut self, data: Vec<u8>) -> Result<(), Box<dyn Error>> {
+        // Validate the output report size
+        let _expected_report_size = match self.hardware.bus_type {
+            BusType::Bluetooth => OUTPUT_REPORT_BT_SIZE,
+            BusType::Usb => OUTPUT_REPORT_USB_SIZE,
+        };
+
+        // The first byte should be the report id
+        let Some(report_id) = data.first() else {
+            log::warn!("Received empty output report.");
+            return Ok(());
+        };
+
+        log::debug!("Got output report with ID: {report_id}");
+
+        let state = match *report_id {
+            OUTPUT_REPORT_USB => {
+                log::debug!("Received USB output report with length: {}", data.len());
+                match data.len() {
+                    OUTPUT_REPORT_USB_SIZE => {
+                        let buf: [u8; OUTPUT_REPORT_USB_SIZE] = data.try_into().unwrap();
+                        let report = UsbPackedOutputReport::unpack(&buf)?;
+                        report.state
+                    }
+                    OUTPUT_REPORT_USB_SHORT_SIZE => {
+                        let buf: [u8; OUTPUT_REPORT_USB_SHORT_SIZE] = data.try_into().unwrap();
+                        let report = UsbPackedOutputReportShort::unpack(&buf)?;
+
+                        // NOTE: Hack for supporting Steam Input rumble
+                        let mut state = report.state;
+                        if !state.allow_audio_control
+                            && !state.allow_mic_volume
+                            && !state.allow_speaker_volume
+                            && !state.allow_headphone_volume
+                            && !state.allow_left_trigger_ffb
+                            && !state.allow_right_trigger_ffb
+                            && !state.use_rumble_not_haptics
+                            && !state.enable_rumble_emulation
+                        {
+                            state.use_rumble_not_haptics = true;
+                        }
+                        state
+                    }
+                    _ => {
+                        log::warn!("Failed to unpack output report. Expected size {OUTPUT_REPORT_USB_SIZE} or {OUTPUT_REPORT_USB_SHORT_SIZE}, got {}.", data.len());
+                        return Ok(());
+                    }
+                }
+            }
+            OUTPUT_REPORT_BT => {
+                log::debug!(
+                    "Received Bluetooth output report with length: {}",
+                    data.len()
+                );
+                // The state data follows the report id, sequence tag, and tag
+                // bytes of the Bluetooth report.
+                let Some(buf) = data.get(3..3 + OUTPUT_STATE_SIZE) else {
+                    log::warn!("Failed to unpack output report. Expected size {OUTPUT_REPORT_BT_SIZE}, got {}.", data.len());
+                    return Ok(());
+                };
+                let buf: [u8; OUTPUT_STATE_SIZE] = buf.try_into()?;
+                SetStatePackedOutputData::unpack(&buf)?
+            }
+            _ => {
+                log::debug!("Unknown output report: {report_id}");
+                return Ok(());
+            }
+        };
+
+        log::trace!("{}", state);
+
+        // Send the output report to the composite device so it can
+        // be processed by source devices.
+        let Some(tx) = self.composite_tx.as_ref() else {
+            log::warn!("No composite device to handle output reports");
+            return Ok(());
+        };
+
+        let event = output_event::OutputEvent::DualSense(state);
+        let cmd = Command::ProcessOutputEvent(event);
+        tx.send(cmd).await?;
+
+        Ok(())
+    }
+
+    /// Write the current device state to the device. Bluetooth devices send
+    /// the full Bluetooth input report, which wraps the USB report layout.
+    fn write_state(&mut self, device: &mut UHIDDevice<File>) -> Result<(), Box<dyn Error>> {
+        match self.state {
+            PackedInputDataReport::Usb(state) => {
+                let mut data = state.pack()?.to_vec();
+                if self.hardware.bus_type == BusType::Bluetooth {
+                    data = bluetooth_input_report(&data, self.bt_sequence);
+                    self.bt_sequence = (self.bt_sequence + 1) % 16;
+                }
+
+                // Write the state to the virtual HID
+                if let Err(e) = device.write(&data) {
+                    let err = format!("Failed to write input data report: {:?}", e);
+                    return Err(err.into());
+                }
+            }
+            PackedInputDataReport::Bluetooth(state) => {
+                let data = state.pack()?;
+
+                // Write the state to the virtual HID
+                if let Err(e) = device.write(&data) {
+                    let err = format!("Failed to write input data report: {:?}", e);
+                    return Err(err.into());
+                }
+            }
+        };
+
+        Ok(())
+    }
+
+    /// Update the internal controller state when events are emitted.
+    fn update_state(&mut self, event: NativeEvent) {
+        let value = event.get_value();
+        let capability = event.as_capability();
+        match capability {
+            Capability::None => (),
+            Capability::NotImplemented => (),
+            Capability::Sync => (),
+            Capability::Gamepad(gamepad) => match gamepad {
+                Gamepad::Button(btn) => match btn {
+                    GamepadButton::South => match self.state {
+                        PackedInputDataReport::Usb(ref mut state) => state.cross = event.pressed(),
+                        PackedInputDataReport::Bluetooth(ref mut state) => {
+                            state.cross = event.pressed()
+                        }
+                    },
+                    GamepadButton::East => match self.state {
+                        PackedInputDataReport::Usb(ref mut state) => state.circle = event.pressed(),
+                        PackedInputDataReport::Bluetooth(ref mut state) => {
+                            state.circle = event.pressed()
+                        }
+                    },
+                    GamepadButton::North => match self.state {
+                        PackedInputDataReport::Usb(ref mut state) => state.square = event.pressed(),
+                        PackedInputDataReport::Bluetooth(ref mut state) => {
+                            state.square = event.pressed()
+                        }
+                    },
+                    GamepadButton::West => match self.state {
+                        PackedInputDataReport::Usb(ref mut state) => {
+                            state.triangle = event.pressed()
+                        }
+                        PackedInputDataReport::Bluetooth(ref mut state) => {
+                            state.triangle = event.pressed()
+                        }
+                    },
+                    GamepadButton::Start => match self.state {
+                        PackedInputDataReport::Usb(ref mut state) => {
+                            state.options = event.pressed()
+                        }
+                        PackedInputDataReport::Bluetooth(ref mut state) => {
+                            state.options = event.pressed()
+                        }
+                    },
+                    GamepadButton::Select => match self.state {
+                        PackedInputDataReport::Usb(ref mut state) => state.create = event.pressed(),
+                        PackedInputDataReport::Bluetooth(ref mut state) => {
+                            state.create = event.pressed()
+                        }
+                    },
+                    GamepadButton::Guide => match self.state {
+                        PackedInputDataReport::Usb(ref mut state) => state.ps = event.pressed(),
+                        PackedInputDataReport::Bluetooth(ref mut state) => {
+                            state.ps = event.pressed()
+                        }
+                    },
+                    GamepadButton::QuickAccess => (),
+                    GamepadButton::Mute => match self.state {
+                        PackedInputDataReport::Usb(ref mut state) => state.mute = event.pressed(),
+                        PackedInputDataReport::Bluetooth(_) => (),
+                    },
+                    GamepadButton::DPadUp => match self.state {
+                        PackedInputDataReport::Usb(ref mut state) => match state.dpad {
+                            Direction::North => {
+                                if !event.pressed() {
+                                    state.dpad = Direction::None
+                                }
+                            }
+                            Direction::NorthEast => {
+                                if !event.pressed() {
+                                    state.dpad = Direction::East
+                                }
+                            }
+                            Direction::East => {
+                                if event.pressed() {
+                                    state.dpad = Direction::NorthEast
+                                }
+                            }
+                            Direction::SouthEast => {
+                                if event.pressed() {
+                                    state.dpad = Direction::NorthEast
+                                }
+                            }
+                            Direction::South => {
+                                if event.pressed() {
+                                    state.dpad = Direction::North
+                                }
+                            }
+                            Direction::SouthWest => {
+                                if event.pressed() {
+                                    state.dpad = Direction::NorthWest
+                                }
+                            }
+                            Direction::West => {
+                                if event.pressed() {
+                                    state.dpad = Direction::NorthWest
+                                }
+                            }
+                            Direction::NorthWest => {
+                                if !event.pressed() {
+                                    state.dpad = Direction::West
+                                }
+                            }
+                            Direction::None => {
+                                if event.pressed() {
+                                    state.dpad = Direction::North
+                                }
+                            }
+                        },
+                        PackedInputDataReport::Bluetooth(ref mut state) => match state.dpad {
+                            Direction::North => {
+                                if !event.pressed() {
+                                    state.dpad = Direction::None
+                                }
+                            }
+                            Direction::NorthEast => {
+                                if !event.pressed() {
+                                    state.dpad = Direction::East
+                                }
+                            }
+                            Direction::East => {
+                                if event.pressed() {
+                                    state.dpad = Direction::NorthEast
+                                }
+                            }
+                            Direction::SouthEast => {
+                                if event.pressed() {
+                                    state.dpad = Direction::NorthEast
+                                }
+                            }
+                            Direction::South => {
+                                if event.pressed() {
+                                    state.dpad = Direction::North
+                                }
+                            }
+                            Direction::SouthWest => {
+                                if event.pressed() {
+                                    state.dpad = Direction::NorthWest
+                                }
+                            }
+                            Direction::West => {
+                                if event.pressed() {
+                                    state.dpad = Direction::NorthWest
+                                }
+                            }
+                            Direction::NorthWest => {
+                                if !event.pressed() {
+                                    state.dpad = Direction::West
+                                }
+                            }
+                            Direction::None => {
+                                if event.pressed() {
+                                    state.dpad = Direction::North
+                                }
+                            }
+                        },
+                    },
+                    GamepadButton::DPadDown => match self.state {
+                        PackedInputDataReport::Usb(ref mut state) => match state.dpad {
+                            Direction::North => {
+                                if event.pressed() {
+                                    state.dpad = Direction::South
+                                }
+                            }
+                            Direction::NorthEast => {
+                                if event.pressed() {
+                                    state.dpad = Direction::SouthEast
+                                }
+                            }
+                            Direction::East => {
+                                if event.pressed() {
+                                    state.dpad = Direction::SouthEast
+                                }
+                            }
+                            Direction::SouthEast => {
+                                if !event.pressed() {
+                                    state.dpad = Direction::East
+                                }
+                            }
+                            Direction::South => {
+                                if !event.pressed() {
+                                    state.dpad = Direction::None
+                                }
+                            }
+                            Direction::SouthWest => {
+                                if !event.pressed() {
+                                    state.dpad = Direction::West
+                                }
+                            }
+                            Direction::West => {
+                                if event.pressed() {
+                                    state.dpad = Direction::SouthWest
+                                }
+                            }
+                            Direction::NorthWest => {
+                                if event.pressed() {
+                                    state.dpad = Direction::SouthWest
+                                }
+                            }
+                            Direction::None => {
+                                if event.pressed() {
+                                    state.dpad = Direction::South
+                                }
+                            }
+                        },
+                        PackedInputDataReport::Bluetooth(ref mut state) => match state.dpad {
+                            Direction::North => {
+                                if event.pressed() {
+                                    state.dpad = Direction::South
+                                }
+                            }
+                            Direction::NorthEast => {
+                                if event.pressed() {
+                                    state.dpad = Direction::SouthEast
+                                }
+                            }
+                            Direction::East => {
+                                if event.pressed() {
+                                    state.dpad = Direction::SouthEast
+                                }
+                            }
+                            Direction::SouthEast => {
+                                if !event.pressed() {
+                                    state.dpad = Direction::East
+                                }
+                            }
+                            Direction::South => {
+                                if !event.pressed() {
+                                    state.dpad = Direction::None
+                                }
+                            }
+                            Direction::SouthWest => {
+                                if !event.pressed() {
+                                    state.dpad = Direction::West
+                                }
+                            }
+                            Direction::West => {
+                                if event.pressed() {
+                                    state.dpad = Direction::SouthWest
+                                }
+                            }
+                            Direction::NorthWest => {
+                                if event.pressed() {
+                                    state.dpad = Direction::SouthWest
+                                }
+                            }
+                            Direction::None => {
+                                if event.pressed() {
+                                    state.dpad = Direction::South
+                                }
+                            }
+                        },
+                    },
+                    GamepadButton::DPadLeft => match self.state {
+                        PackedInputDataReport::Usb(ref mut state) => match state.dpad {
+                            Direction::North => {
+                                if event.pressed() {
+                                    state.dpad = Direction::NorthWest
+                                }
+                            }
+                            Direction::NorthEast => {
+                                if event.pressed() {
+                                    state.dpad = Direction::NorthWest
+                                }
+                            }
+                            Direction::East => {
+                                if event.pressed() {
+                                    state.dpad = Direction::West
+                                }
+                            }
+                            Direction::SouthEast => {
+                                if event.pressed() {
+                                    state.dpad = Direction::SouthWest
+                                }
+                            }
+                            Direction::South => {
+                                if event.pressed() {
+                                    state.dpad = Direction::SouthWest
+                                }
+                            }
+                            Direction::SouthWest => {
+                                if !event.pressed() {
+                                    state.dpad = Direction::South
+                                }
+                            }
+                            Direction::West => {
+                                if !event.pressed() {
+                                    state.dpad = Direction::None
+                                }
+                            }
+                            Direction::NorthWest => {
+                                if !event.pressed() {
+                                    state.dpad = Direction::North
+                                }
+                            }
+                            Direction::None => {
+                                if event.pressed() {
+                                    state.dpad = Direction::West
+                                }
+                            }
+                        },
+                        PackedInputDataReport::Bluetooth(ref mut state) => match state.dpad {
+                            Direction::North => {
+                                if event.pressed() {
+                                    state.dpad = Direction::NorthWest
+                                }
+                            }
+                            Direction::NorthEast => {
+                                if event.pressed() {
+                                    state.dpad = Direction::NorthWest
+                                }
+                            }
+                            Direction::East => {
+                                if event.pressed() {
+                                    state.dpad = Direction::West
+                                }
+                            }
+                            Direction::SouthEast => {
+                                if event.pressed() {
+                                    state.dpad = Direction::SouthWest
+                                }
+                            }
+                            Direction::South => {
+                                if event.pressed() {
+                                    state.dpad = Direction::SouthWest
+                                }
+                            }
+                            Direction::SouthWest => {
+                                if !event.pressed() {
+                                    state.dpad = Direction::South
+                                }
+                            }
+                            Direction::West => {
+                                if !event.pressed() {
+                                    state.dpad = Direction::None
+                                }
+                            }
+                            Direction::NorthWest => {
+                                if !event.pressed() {
+                                    state.dpad = Direction::North
+                                }
+                            }
+                            Direction::None => {
+                                if event.pressed() {
+                                    state.dpad = Direction::West
+                                }
+                            }
+                        },
+                    },
+                    GamepadButton::DPadRight => match self.state {
+                        PackedInputDataReport::Usb(ref mut state) => match state.dpad {
+                            Direction::North => {
+                                if event.pressed() {
+                                    state.dpad = Direction::NorthEast
+                                }
+                            }
+                            Direction::NorthEast => {
+                                if !event.pressed() {
+                                    state.dpad = Direction::North
+                                }
+                            }
+                            Direction::East => {
+                                if !event.pressed() {
+                                    state.dpad = Direction::None
+                                }
+                            }
+                            Direction::SouthEast => {
+                                if !event.pressed() {
+                                    state.dpad = Direction::South
+                                }
+                            }
+                            Direction::South => {
+                                if event.pressed() {
+                                    state.dpad = Direction::SouthEast
+                                }
+                            }
+                            Direction::SouthWest => {
+                                if event.pressed() {
+                                    state.dpad = Direction::SouthEast
+                                }
+                            }
+                            Direction::West => {
+                                if event.pressed() {
+                                    state.dpad = Direction::East
+                                }
+                            }
+                            Direction::NorthWest => {
+                                if event.pressed() {
+                                    state.dpad = Direction::NorthEast
+                                }
+                            }
+                            Direction::None => {
+                                if event.pressed() {
+                                    state.dpad = Direction::East
+                                }
+                            }
+                        },
+                        PackedInputDataReport::Bluetooth(ref mut state) => match state.dpad {
+                            Direction::North => {
+                                if event.pressed() {
+                                    state.dpad = Direction::NorthEast
+                                }
+                            }
+                            Direction::NorthEast => {
+                                if !event.pressed() {
+                                    state.dpad = Direction::North
+                                }
+                            }
+                            Direction::East => {
+                                if !event.pressed() {
+                                    state.dpad = Direction::None
+                                }
+                            }
+                            Direction::SouthEast => {
+                                if !event.pressed() {
+                                    state.dpad = Direction::South
+                                }
+                            }
+                            Direction::South => {
+                                if event.pressed() {
+                                    state.dpad = Direction::SouthEast
+                                }
+                            }
+                            Direction::SouthWest => {
+                                if event.pressed() {
+                                    state.dpad = Direction::SouthEast
+                                }
+                            }
+                            Direction::West => {
+                                if event.pressed() {
+                                    state.dpad = Direction::East
+                                }
+                            }
+                            Direction::NorthWest => {
+                                if event.pressed() {
+                                    state.dpad = Direction::NorthEast
+                                }
+                            }
+                            Direction::None => {
+                                if event.pressed() {
+                                    state.dpad = Direction::East
+                                }
+                            }
+                        },
+                    },
+                    GamepadButton::LeftBumper => match self.state {
+                        PackedInputDataReport::Usb(ref mut state) => state.l1 = event.pressed(),
+                        PackedInputDataReport::Bluetooth(ref mut state) => {
+                            state.l1 = event.pressed()
+                        }
+                    },
+                    GamepadButton::LeftTrigger => match self.state {
+                        PackedInputDataReport::Usb(ref mut state) => state.l2 = event.pressed(),
+                        PackedInputDataReport::Bluetooth(ref mut state) => {
+                            state.l2 = event.pressed()
+                        }
+                    },
+                    GamepadButton::LeftPaddle1 => match self.state {
+                        PackedInputDataReport::Usb(ref mut state) => {
+                            state.left_paddle = event.pressed()
+                        }
+                        PackedInputDataReport::Bluetooth(_) => (),
+                    },
+                    GamepadButton::LeftPaddle2 => match self.state {
+                        PackedInputDataReport::Usb(ref mut state) => {
+                            state.left_fn = event.pressed()
+                        }
+                        PackedInputDataReport::Bluetooth(_) => (),
+                    },
+                    GamepadButton::LeftStick => match self.state {
+                        PackedInputDataReport::Usb(ref mut state) => state.l3 = event.pressed(),
+                        PackedInputDataReport::Bluetooth(ref mut state) => {
+                            state.l3 = event.pressed()
+                        }
+                    },
+                    GamepadButton::LeftStickTouch => (),
+                    GamepadButton::RightBumper => match self.state {
+                        PackedInputDataReport::Usb(ref mut state) => state.r1 = event.pressed(),
+                        PackedInputDataReport::Bluetooth(ref mut state) => {
+                            state.r1 = event.pressed()
+                        }
+                    },
+                    GamepadButton::RightTrigger => match self.state {
+                        PackedInputDataReport::Usb(ref mut state) => state.r2 = event.pressed(),
+                        PackedInputDataReport::Bluetooth(ref mut state) => {
+                            state.r2 = event.pressed()
+                        }
+                    },
+                    GamepadButton::RightPaddle1 => match self.state {
+                        PackedInputDataReport::Usb(ref mut state) => {
+                            state.right_paddle = event.pressed()
+                        }
+                        PackedInputDataReport::Bluetooth(_) => (),
+                    },
+                    GamepadButton::RightPaddle2 => match self.state {
+                        PackedInputDataReport::Usb(ref mut state) => {
+                            state.right_fn = event.pressed()
+                        }
+                        PackedInputDataReport::Bluetooth(_) => (),
+                    },
+                    GamepadButton::RightStick => match self.state {
+                        PackedInputDataReport::Usb(ref mut state) => state.r3 = event.pressed(),
+                        PackedInputDataReport::Bluetooth(ref mut state) => {
+                            state.r3 = event.pressed()
+                        }
+                    },
+                    GamepadButton::RightStickTouch => (),
+                    GamepadButton::LeftPaddle3 => (),
+                    GamepadButton::RightPaddle3 => (),
+                    _ => (),
+                },
+                Gamepad::Axis(axis) => match axis {
+                    GamepadAxis::LeftStick => {
+                        if let InputValue::Vector2 { x, y } = value {
+                            if let Some(x) = x {
+                                let value = denormalize_signed_value(x, STICK_X_MIN, STICK_X_MAX);
+                                match self.state {
+                                    PackedInputDataReport::Usb(ref mut state) => {
+                                        state.joystick_l_x = value
+                                    }
+                                    PackedInputDataReport::Bluetooth(ref mut state) => {
+                                        state.joystick_l_x = value
+                                    }
+                                }
+                            }
+                            if let Some(y) = y {
+                                let value = denormalize_signed_value(y, STICK_Y_MIN, STICK_Y_MAX);
+                                match self.state {
+                                    PackedInputDataReport::Usb(ref mut state) => {
+                                        state.joystick_l_y = value
+                                    }
+                                    PackedInputDataReport::Bluetooth(ref mut state) => {
+                                        state.joystick_l_y = value
+                                    }
+                                }
+                            }
+                        }
+                    }
+                    GamepadAxis::RightStick => {
+                        if let InputValue::Vector2 { x, y } = value {
+                            if let Some(x) = x {
+                                let value = denormalize_signed_value(x, STICK_X_MIN, STICK_X_MAX);
+                                match self.state {
+                                    PackedInputDataReport::Usb(ref mut state) => {
+                                        state.joystick_r_x = value
+                                    }
+                                    PackedInputDataReport::Bluetooth(ref mut state) => {
+                                        state.joystick_r_x = value
+                                    }
+                                }
+                            }
+                            if let Some(y) = y {
+                                let value = denormalize_signed_value(y, STICK_Y_MIN, STICK_Y_MAX);
+                                match self.state {
+                                    PackedInputDataReport::Usb(ref mut state) => {
+                                        state.joystick_r_y = value
+                                    }
+                                    PackedInputDataReport::Bluetooth(ref mut state) => {
+                                        state.joystick_r_y = value
+                                    }
+                                }
+                            }
+                        }
+                    }
+                    GamepadAxis::Hat1 => {
+                        if let InputValue::Vector2 { x, y } = value {
+                            if let Some(x) = x {
+                                let value = denormalize_signed_value(x, -1.0, 1.0);
+                                match value.cmp(&0) {
+                                    Ordering::Less => match self.state {
+                                        PackedInputDataReport::Usb(ref mut state) => {
+                                            match state.dpad {
+                                                Direction::North => {
+                                                    state.dpad = Direction::NorthWest
+                                                }
+                                                Direction::South => {
+                                                    state.dpad = Direction::SouthWest
+                                                }
+                                                _ => state.dpad = Direction::West,
+                                            }
+                                        }
+                                        PackedInputDataReport::Bluetooth(ref mut state) => {
+                                            match state.dpad {
+                                                Direction::North => {
+                                                    state.dpad = Direction::NorthWest
+                                                }
+                                                Direction::South => {
+                                                    state.dpad = Direction::SouthWest
+                                                }
+                                                _ => state.dpad = Direction::West,
+                                            }
+                                        }
+                                    },
+                                    Ordering::Equal => match self.state {
+                                        PackedInputDataReport::Usb(ref mut state) => {
+                                            match state.dpad {
+                                                Direction::NorthWest => {
+                                                    state.dpad = Direction::North
+                                                }
+                                                Direction::SouthWest => {
+                                                    state.dpad = Direction::South
+                                                }
+                                                Direction::NorthEast => {
+                                                    state.dpad = Direction::North
+                                                }
+                                                Direction::SouthEast => {
+                                                    state.dpad = Direction::South
+                                                }
+                                                Direction::East => state.dpad = Direction::None,
+                                                Direction::West => state.dpad = Direction::None,
+                                                _ => (),
+                                            }
+                                        }
+                                        PackedInputDataReport::Bluetooth(ref mut state) => {
+                                            match state.dpad {
+                                                Direction::NorthWest => {
+                                                    state.dpad = Direction::North
+                                                }
+                                                Direction::SouthWest => {
+                                                    state.dpad = Direction::South
+                                                }
+                                                Direction::NorthEast => {
+                                                    state.dpad = Direction::North
+                                                }
+                                                Direction::SouthEast => {
+                                                    state.dpad = Direction::South
+                                                }
+                                                Direction::East => state.dpad = Direction::None,
+                                                Direction::West => state.dpad = Direction::None,
+                                                _ => (),
+                                            }
+                                        }
+                                    },
+                                    Ordering::Greater => match self.state {
+                                        PackedInputDataReport::Usb(ref mut state) => {
+                                            match state.dpad {
+                                                Direction::North => {
+                                                    state.dpad = Direction::NorthEast
+                                                }
+                                                Direction::South => {
+                                                    state.dpad = Direction::SouthEast
+                                                }
+                                                _ => state.dpad = Direction::East,
+                                            }
+                                        }
+                                        PackedInputDataReport::Bluetooth(ref mut state) => {
+                                            match state.dpad {
+                                                Direction::North => {
+                                                    state.dpad = Direction::NorthEast
+                                                }
+                                                Direction::South => {
+                                                    state.dpad = Direction::SouthEast
+                                                }
+                                                _ => state.dpad = Direction::East,
+                                            }
+                                        }
+                                    },
+                                }
+                            }
+                            if let Some(y) = y {
+                                let value = denormalize_signed_value(y, -1.0, 1.0);
+                                match value.cmp(&0) {
+                                    Ordering::Less => match self.state {
+                                        PackedInputDataReport::Usb(ref mut state) => {
+                                            match state.dpad {
+                                                Direction::East => {
+                                                    state.dpad = Direction::NorthEast
+                                                }
+                                                Direction::West => {
+                                                    state.dpad = Direction::NorthWest
+                                                }
+                                                _ => state.dpad = Direction::North,
+                                            }
+                                        }
+                                        PackedInputDataReport::Bluetooth(ref mut state) => {
+                                            match state.dpad {
+                                                Direction::East => {
+                                                    state.dpad = Direction::NorthEast
+                                                }
+                                                Direction::West => {
+                                                    state.dpad = Direction::NorthWest
+                                                }
+                                                _ => state.dpad = Direction::North,
+                                            }
+                                        }
+                                    },
+                                    Ordering::Equal => match self.state {
+                                        PackedInputDataReport::Usb(ref mut state) => {
+                                            match state.dpad {
+                                                Direction::NorthWest => {
+                                                    state.dpad = Direction::West
+                                                }
+                                                Direction::SouthWest => {
+                                                    state.dpad = Direction::West
+                                                }
+                                                Direction::NorthEast => {
+                                                    state.dpad = Direction::East
+                                                }
+                                                Direction::SouthEast => {
+                                                    state.dpad = Direction::East
+                                                }
+                                                Direction::North => state.dpad = Direction::None,
+                                                Direction::South => state.dpad = Direction::None,
+                                                _ => (),
+                                            }
+                                        }
+                                        PackedInputDataReport::Bluetooth(ref mut state) => {
+                                            match state.dpad {
+                                                Direction::NorthWest => {
+                                                    state.dpad = Direction::West
+                                                }
+                                                Direction::SouthWest => {
+                                                    state.dpad = Direction::West
+                                                }
+                                                Direction::NorthEast => {
+                                                    state.dpad = Direction::East
+                                                }
+                                                Direction::SouthEast => {
+                                                    state.dpad = Direction::East
+                                                }
+                                                Direction::North => state.dpad = Direction::None,
+                                                Direction::South => state.dpad = Direction::None,
+                                                _ => (),
+                                            }
+                                        }
+                                    },
+                                    Ordering::Greater => match self.state {
+                                        PackedInputDataReport::Usb(ref mut state) => {
+                                            match state.dpad {
+                                                Direction::East => {
+                                                    state.dpad = Direction::SouthEast
+                                                }
+                                                Direction::West => {
+                                                    state.dpad = Direction::SouthWest
+                                                }
+                                                _ => state.dpad = Direction::South,
+                                            }
+                                        }
+                                        PackedInputDataReport::Bluetooth(ref mut state) => {
+                                            match state.dpad {
+                                                Direction::East => {
+                                                    state.dpad = Direction::SouthEast
+                                                }
+                                                Direction::West => {
+                                                    state.dpad = Direction::SouthWest
+                                                }
+                                                _ => state.dpad = Direction::South,
+                                            }
+                                        }
+                                    },
+                                }
+                            }
+                        }
+                    }
+                    GamepadAxis::Hat2 => (),
+                    GamepadAxis::Hat3 => (),
+                    // TODO: Remove GamepadAxis::Buttons
+                    // NativeEvent { capability: Gamepad(Axis(Buttons(DPadLeft, DPadRight))), source_capability: None, value: Vector2 { x: Some(1.0), y: None } }
+                    GamepadAxis::Buttons(neg, _) => match neg {
+                        GamepadButton::DPadUp =>
+                        {
+                            #[allow(clippy::collapsible_match)]
+                            if let InputValue::Vector2 { x: _, y } = value {
+                                if let Some(y) = y {
+                                    let value = y as i8;
+                                    match value.cmp(&0) {
+                                        Ordering::Less => match self.state {
+                                            PackedInputDataReport::Usb(ref mut state) => {
+                                                match state.dpad {
+                                                    Direction::East => {
+                                                        state.dpad = Direction::NorthEast
+                                                    }
+                                                    Direction::West => {
+                                                        state.dpad = Direction::NorthWest
+                                                    }
+                                                    _ => state.dpad = Direction::North,
+                                                }
+                                            }
+                                            PackedInputDataReport::Bluetooth(ref mut state) => {
+                                                match state.dpad {
+                                                    Direction::East => {
+                                                        state.dpad = Direction::NorthEast
+                                                    }
+                                                    Direction::West => {
+                                                        state.dpad = Direction::NorthWest
+                                                    }
+                                                    _ => state.dpad = Direction::North,
+                                                }
+                                            }
+                                        },
+                                        Ordering::Equal => match self.state {
+                                            PackedInputDataReport::Usb(ref mut state) => {
+                                                match state.dpad {
+                                                    Direction::NorthWest => {
+                                                        state.dpad = Direction::West
+                                                    }
+                                                    Direction::SouthWest => {
+                                                        state.dpad = Direction::West
+                                                    }
+                                                    Direction::NorthEast => {
+                                                        state.dpad = Direction::East
+                                                    }
+                                                    Direction::SouthEast => {
+                                                        state.dpad = Direction::East
+                                                    }
+                                                    Direction::North => {
+                                                        state.dpad = Direction::None
+                                                    }
+                                                    Direction::South => {
+                                                        state.dpad = Direction::None
+                                                    }
+                                                    _ => (),
+                                                }
+                                            }
+                                            PackedInputDataReport::Bluetooth(ref mut state) => {
+                                                match state.dpad {
+                                                    Direction::NorthWest => {
+                                                        state.dpad = Direction::West
+                                                    }
+                                                    Direction::SouthWest => {
+                                                        state.dpad = Direction::West
+                                                    }
+                                                    Direction::NorthEast => {
+                                                        state.dpad = Direction::East
+                                                    }
+                                                    Direction::SouthEast => {
+                                                        state.dpad = Direction::East
+                                                    }
+                                                    Direction::North => {
+                                                        state.dpad = Direction::None
+                                                    }
+                                                    Direction::South => {
+                                                        state.dpad = Direction::None
+                                                    }
+                                                    _ => (),
+                                                }
+                                            }
+                                        },
+                                        Ordering::Greater => match self.state {
+                                            PackedInputDataReport::Usb(ref mut state) => {
+                                                match state.dpad {
+                                                    Direction::East => {
+                                                        state.dpad = Direction::SouthEast
+                                                    }
+                                                    Direction::West => {
+                                                        state.dpad = Direction::SouthWest
+                                                    }
+                                                    _ => state.dpad = Direction::South,
+                                                }
+                                            }
+                                            PackedInputDataReport::Bluetooth(ref mut state) => {
+                                                match state.dpad {
+                                                    Direction::East => {
+                                                        state.dpad = Direction::SouthEast
+                                                    }
+                                                    Direction::West => {
+                                                        state.dpad = Direction::SouthWest
+                                                    }
+                                                    _ => state.dpad = Direction::South,
+                                                }
+                                            }
+                                        },
+                                    }
+                                }
+                            }
+                        }
+                        GamepadButton::DPadLeft =>
+                        {
+                            #[allow(clippy::collapsible_match)]
+                            if let InputValue::Vector2 { x, y: _ } = value {
+                                if let Some(x) = x {
+                                    let value = x as i8;
+                                    match value.cmp(&0) {
+                                        Ordering::Less => match self.state {
+                                            PackedInputDataReport::Usb(ref mut state) => {
+                                                match state.dpad {
+                                                    Direction::North => {
+                                                        state.dpad = Direction::NorthWest
+                                                    }
+                                                    Direction::South => {
+                                                        state.dpad = Direction::SouthWest
+                                                    }
+                                                    _ => state.dpad = Direction::West,
+                                                }
+                                            }
+                                            PackedInputDataReport::Bluetooth(ref mut state) => {
+                                                match state.dpad {
+                                                    Direction::North => {
+                                                        state.dpad = Direction::NorthWest
+                                                    }
+                                                    Direction::South => {
+                                                        state.dpad = Direction::SouthWest
+                                                    }
+                                                    _ => state.dpad = Direction::West,
+                                                }
+                                            }
+                                        },
+                                        Ordering::Equal => match self.state {
+                                            PackedInputDataReport::Usb(ref mut state) => {
+                                                match state.dpad {
+                                                    Direction::NorthWest => {
+                                                        state.dpad = Direction::North
+                                                    }
+                                                    Direction::SouthWest => {
+                                                        state.dpad = Direction::South
+                                                    }
+                                                    Direction::NorthEast => {
+                                                        state.dpad = Direction::North
+                                                    }
+                                                    Direction::SouthEast => {
+                                                        state.dpad = Direction::South
+                                                    }
+                                                    Direction::East => state.dpad = Direction::None,
+                                                    Direction::West => state.dpad = Direction::None,
+                                                    _ => (),
+                                                }
+                                            }
+                                            PackedInputDataReport::Bluetooth(ref mut state) => {
+                                                match state.dpad {
+                                                    Direction::NorthWest => {
+                                                        state.dpad = Direction::North
+                                                    }
+                                                    Direction::SouthWest => {
+                                                        state.dpad = Direction::South
+                                                    }
+                                                    Direction::NorthEast => {
+                                                        state.dpad = Direction::North
+                                                    }
+                                                    Direction::SouthEast => {
+                                                        state.dpad = Direction::South
+                                                    }
+                                                    Direction::East => state.dpad = Direction::None,
+                                                    Direction::West => state.dpad = Direction::None,
+                                                    _ => (),
+                                                }
+                                            }
+                                        },
+                                        Ordering::Greater => match self.state {
+                                            PackedInputDataReport::Usb(ref mut state) => {
+                                                match state.dpad {
+                                                    Direction::North => {
+                                                        state.dpad = Direction::NorthEast
+                                                    }
+                                                    Direction::South => {
+                                                        state.dpad = Direction::SouthEast
+                                                    }
+                                                    _ => state.dpad = Direction::East,
+                                                }
+                                            }
+                                            PackedInputDataReport::Bluetooth(ref mut state) => {
+                                                match state.dpad {
+                                                    Direction::North => {
+                                                        state.dpad = Direction::NorthEast
+                                                    }
+                                                    Direction::South => {
+                                                        state.dpad = Direction::SouthEast
+                                                    }
+                                                    _ => state.dpad = Direction::East,
+                                                }
+                                            }
+                                        },
+                                    }
+                                }
+                            }
+                        }
+                        _ => (),
+                    },
+                },
+                Gamepad::Trigger(trigger) => match trigger {
+                    GamepadTrigger::LeftTrigger => {
+                        if let InputValue::Float(normal_value) = value {
+                            let value = denormalize_unsigned_value(normal_value, TRIGGER_MAX);
+                            match self.state {
+                                PackedInputDataReport::Usb(ref mut state) => {
+                                    state.l2_trigger = value
+                                }
+                                PackedInputDataReport::Bluetooth(ref mut state) => {
+                                    state.l2_trigger = value
+                                }
+                            }
+                        }
+                    }
+                    GamepadTrigger::LeftTouchpadForce => (),
+                    GamepadTrigger::LeftStickForce => (),
+                    GamepadTrigger::RightTrigger => {
+                        if let InputValue::Float(normal_value) = value {
+                            let value = denormalize_unsigned_value(normal_value, TRIGGER_MAX);
+                            match self.state {
+                                PackedInputDataReport::Usb(ref mut state) => {
+                                    state.r2_trigger = value
+                                }
+                                PackedInputDataReport::Bluetooth(ref mut state) => {
+                                    state.r2_trigger = value
+                                }
+                            }
+                        }
+                    }
+                    GamepadTrigger::RightTouchpadForce => (),
+                    GamepadTrigger::RightStickForce => (),
+                },
+                Gamepad::Accelerometer => {
+                    if let InputValue::Vector3 { x, y, z } = value {
+                        if let Some(x) = x {
+                            match self.state {
+                                PackedInputDataReport::Usb(ref mut state) => {
+                                    state.accel_x =
+                                        Integer::from_primitive(denormalize_accel_value(x))
+                                }
+                                PackedInputDataReport::Bluetooth(_) => (),
+                            }
+                        }
+                        if let Some(y) = y {
+                            match self.state {
+                                PackedInputDataReport::Usb(ref mut state) => {
+                                    state.accel_y =
+                                        Integer::from_primitive(denormalize_accel_value(y))
+                                }
+                                PackedInputDataReport::Bluetooth(_) => (),
+                            }
+                        }
+                        if let Some(z) = z {
+                            match self.state {
+                                PackedInputDataReport::Usb(ref mut state) => {
+                                    state.accel_z =
+                                        Integer::from_primitive(denormalize_accel_value(z))
+                                }
+                                PackedInputDataReport::Bluetooth(_) => (),
+                            }
+                        }
+                    }
+                }
+                Gamepad::Gyro => {
+                    if let InputValue::Vector3 { x, y, z } = value {
+                        if let Some(x) = x {
+                            match self.state {
+                                PackedInputDataReport::Usb(ref mut state) => {
+                                    state.gyro_x =
+                                        Integer::from_primitive(denormalize_gyro_value(x));
+                                }
+                                PackedInputDataReport::Bluetooth(_) => (),
+                            }
+                        }
+                        if let Some(y) = y {
+                            match self.state {
+                                PackedInputDataReport::Usb(ref mut state) => {
+                                    state.gyro_y =
+                                        Integer::from_primitive(denormalize_gyro_value(y))
+                                }
+                                PackedInputDataReport::Bluetooth(_) => (),
+                            }
+                        }
+                        if let Some(z) = z {
+                            match self.state {
+                                PackedInputDataReport::Usb(ref mut state) => {
+                                    state.gyro_z =
+                                        Integer::from_primitive(denormalize_gyro_value(z))
+                                }
+                                PackedInputDataReport::Bluetooth(_) => (),
+                            }
+                        }
+                    }
+                }
+            },
+            Capability::Touchpad(touch) => {
+                // Source touchpads are mapped onto a region of the DualSense
+                // touchpad based on which pad they are.
+                let region = TouchpadRegion::from(&touch);
+                let touch_event = match touch {
+                    Touchpad::LeftPad(touch_event) => touch_event,
+                    Touchpad::RightPad(touch_event) => touch_event,
+                    Touchpad::CenterPad(touch_event) => touch_event,
+                };
+                match touch_event {
+                    Touch::Motion => {
+                        if let InputValue::Touch {
+                            index,
+                            is_touching,
+                            x,
+                            y,
+                            pressure: _,
+                        } = value
+                        {
+                            self.update_touch(region, index, is_touching, x, y);
+                        }
+                    }
+                    Touch::Button(button) => match button {
+                        TouchButton::Touch => (),
+                        TouchButton::Press => self.update_touch_press(region, event.pressed()),
+                    },
+                    Touch::Tilt => (),
+                    Touch::Tool(_) => (),
+                }
+            }
+            Capability::Mouse(_) => (),
+            Capability::Keyboard(_) => (),
+            Capability::Touchscreen(_) => (),
+            Capability::DBus(_) => (),
+        };
+    }
+
+    /// Update the touch finger data from a source touch contact. Contacts from
+    /// any source touchpad are assigned to one of the two available finger
+    /// slots for as long as they are touching.
+    fn update_touch(
+        &mut self,
+        region: TouchpadRegion,
+        index: u8,
+        is_touching: bool,
+        x: Option<f64>,
+        y: Option<f64>,
+    ) {
+        let contact = Some((region, index));
+        let slot = match self.touch_slots.iter().position(|slot| *slot == contact) {
+            Some(slot) => slot,
+            None => {
+                if !is_touching {
+                    return;
+                }
+                // TouchData has an array size of 2, ignore more than 2 touches.
+                let Some(slot) = self.touch_slots.iter().position(|slot| slot.is_none()) else {
+                    return;
+                };
+                self.touch_slots[slot] = contact;
+                slot
+            }
+        };
+
+        // Each new contact gets a new tracking id which is kept until liftoff,
+        // so slot reuse is reported as a new touch instead of a jump of the
+        // old one.
+        let tracking_id = self.next_touch_id;
+        let timestamp = self.touch_timestamp();
+        if !is_touching {
+            self.touch_slots[slot] = None;
+        }
+
+        // Bluetooth targets also keep their state in the USB report layout and
+        // wrap it in the full Bluetooth input report when it is written. The
+        // basic Bluetooth report (0x01) has no touchpad data.
+        let PackedInputDataReport::Usb(ref mut state) = self.state else {
+            return;
+        };
+        state.touch_data.timestamp = timestamp;
+        let finger = &mut state.touch_data.touch_finger_data[slot];
+        if let Some(x) = x {
+            finger.set_x(denormalize_touch_value(
+                region.transform_x(x),
+                DS5_TOUCHPAD_WIDTH,
+            ));
+        }
+        if let Some(y) = y {
+            finger.set_y(denormalize_touch_value(
+                y.clamp(0.0, 1.0),
+                DS5_TOUCHPAD_HEIGHT,
+            ));
+        }
+        if !is_touching {
+            finger.set_inactive();
+        } else if !finger.is_active() {
+            finger.set_active(tracking_id);
+            self.next_touch_id = (tracking_id + 1) & TOUCH_TRACKING_ID_MASK;
+        }
+
+        log::trace!("Got new touch state: {}", finger);
+    }
+
+    /// Returns the touch data timestamp for the current time. The DualSense
+    /// counts in units of 1/3 µs and the touch timestamp is a coarser, wrapping
+    /// view of that counter.
+    fn touch_timestamp(&self) -> u8 {
+        let ticks = self.started_at.elapsed().as_micros() * DS5_TIMESTAMP_TICKS_PER_US;
+        (ticks >> DS5_TOUCH_TIMESTAMP_SHIFT) as u8
+    }
+
+    /// Update the touchpad click state. The touchpad button is held as long
+    /// as any source touchpad is being clicked.
+    fn update_touch_press(&mut self, region: TouchpadRegion, pressed: bool) {
+        if pressed {
+            if !self.touch_pressed.contains(&region) {
+                self.touch_pressed.push(region);
+            }
+        } else {
+            self.touch_pressed.retain(|pressed| *pressed != region);
+        }
+        let pressed = !self.touch_pressed.is_empty();
+        match self.state {
+            PackedInputDataReport::Usb(ref mut state) => state.touchpad = pressed,
+            PackedInputDataReport::Bluetooth(ref mut state) => state.touchpad = pressed,
+        }
+    }
+
+    /// Returns capabilities of the target device
+    fn get_capabilities(&self) -> Vec<Capability> {
+        vec![
+            Capability::Gamepad(Gamepad::Button(GamepadButton::South)),
+            Capability::Gamepad(Gamepad::Button(GamepadButton::North)),
+            Capability::Gamepad(Gamepad::Button(GamepadButton::East)),
+            Capability::Gamepad(Gamepad::Button(GamepadButton::West)),
+            Capability::Gamepad(Gamepad::Button(GamepadButton::Start)),
+            Capability::Gamepad(Gamepad::Button(GamepadButton::Select)),
+            Capability::Gamepad(Gamepad::Button(GamepadButton::Guide)),
+            Capability::Gamepad(Gamepad::Button(GamepadButton::Mute)),
+            Capability::Gamepad(Gamepad::Button(GamepadButton::DPadDown)),
+            Capability::Gamepad(Gamepad::Button(GamepadButton::DPadUp)),
+            Capability::Gamepad(Gamepad::Button(GamepadButton::DPadLeft)),
+            Capability::Gamepad(Gamepad::Button(GamepadButton::DPadRight)),
+            Capability::Gamepad(Gamepad::Button(GamepadButton::LeftBumper)),
+            Capability::Gamepad(Gamepad::Button(GamepadButton::LeftTrigger)),
+            Capability::Gamepad(Gamepad::Button(GamepadButton::LeftStick)),
+            Capability::Gamepad(Gamepad::Button(GamepadButton::LeftPaddle1)),
+            Capability::Gamepad(Gamepad::Button(GamepadButton::RightBumper)),
+            Capability::Gamepad(Gamepad::Button(GamepadButton::RightTrigger)),
+            Capability::Gamepad(Gamepad::Button(GamepadButton::RightStick)),
+            Capability::Gamepad(Gamepad::Button(GamepadButton::RightPaddle1)),
+            Capability::Gamepad(Gamepad::Axis(GamepadAxis::LeftStick)),
+            Capability::Gamepad(Gamepad::Axis(GamepadAxis::RightStick)),
+            Capability::Gamepad(Gamepad::Trigger(GamepadTrigger::LeftTrigger)),
+            Capability::Gamepad(Gamepad::Trigger(GamepadTrigger::RightTrigger)),
+            Capability::Gamepad(Gamepad::Accelerometer),
+            Capability::Gamepad(Gamepad::Gyro),
+            Capability::Touchpad(Touchpad::CenterPad(Touch::Motion)),
+            Capability::Touchpad(Touchpad::CenterPad(Touch::Button(TouchButton::Press))),
+        ]
+    }
+}
+
+impl FeatureReports for DualSenseDevice {
+    /// Returns the feature report requested by the HIDRAW device
+    fn get_report(&mut self, report_number: u8, _report_type: ReportType) -> Result<Vec<u8>, u16> {
+        // Handle report pairing requests
+        let data = match report_number {
+            // Pairing information report
+            FEATURE_REPORT_PAIRING_INFO => {
+                log::debug!("Got report pairing report request");
+                // TODO: Can we define this somewhere as a const?
+                let data = vec![
+                    FEATURE_REPORT_PAIRING_INFO,
+                    self.hardware.mac_addr[0],
+                    self.hardware.mac_addr[1],
+                    self.hardware.mac_addr[2],
+                    self.hardware.mac_addr[3],
+                    self.hardware.mac_addr[4],
+                    self.hardware.mac_addr[5],
+                    0x08,
+                    0x25,
+                    0x00,
+                    0x1e,
+                    0x00,
+                    0xee,
+                    0x74,
+                    0xd0,
+                    0xbc,
+                    0x00,
+                    0x00,
+                    0x00,
+                    0x00,
+                ];
+
+                // If this is a bluetooth gamepad, include the crc
+                if self.hardware.bus_type == BusType::Bluetooth {
+                    // TODO: Handle bluetooth CRC32
+                }
+
+                data
+            }
+            // Firmware information report
+            FEATURE_REPORT_FIRMWARE_INFO => {
+                log::debug!("Got report firmware info request");
+                // TODO: Can we define this somewhere as a const?
+                let data = vec![
+                    FEATURE_REPORT_FIRMWARE_INFO,
+                    0x4a,
+                    0x75,
+                    0x6e,
+                    0x20,
+                    0x31,
+                    0x39,
+                    0x20,
+                    0x32,
+                    0x30,
+                    0x32,
+                    0x33,
+                    0x31,
+                    0x34,
+                    0x3a,
+                    0x34,
+                    0x37,
+                    0x3a,
+                    0x33,
+                    0x34,
+                    0x03,
+                    0x00,
+                    0x44,
+                    0x00,
+                    0x08,
+                    0x02,
+                    0x00,
+                    0x01,
+                    0x36,
+                    0x00,
+                    0x00,
+                    0x01,
+                    0xc1,
+                    0xc8,
+                    0x00,
+                    0x00,
+                    0x00,
+                    0x00,
+                    0x00,
+                    0x00,
+                    0x00,
+                    0x00,
+                    0x00,
+                    0x00,
+                    0x54,
+                    0x01,
+                    0x00,
+                    0x00,
+                    0x14,
+                    0x00,
+                    0x00,
+                    0x00,
+                    0x0b,
+                    0x00,
+                    0x01,
+                    0x00,
+                    0x06,
+                    0x00,
+                    0x00,
+                    0x00,
+                    0x00,
+                    0x00,
+                    0x00,
+                    0x00,
+                ];
+
+                // If this is a bluetooth gamepad, include the crc
+                if self.hardware.bus_type == BusType::Bluetooth {
+                    // TODO: Handle bluetooth CRC32
+                }
+
+                data
+            }
+            // Calibration report
+            FEATURE_REPORT_CALIBRATION => {
+                log::debug!("Got report request for calibration");
+                // TODO: Can we define this somewhere as a const?
+                let data = vec![
+                    FEATURE_REPORT_CALIBRATION,
+                    0xff,
+                    0xfc,
+                    0xff,
+                    0xfe,
+                    0xff,
+                    0x83,
+                    0x22,
+                    0x78,
+                    0xdd,
+                    0x92,
+                    0x22,
+                    0x5f,
+                    0xdd,
+                    0x95,
+                    0x22,
+                    0x6d,
+                    0xdd,
+                    0x1c,
+                    0x02,
+                    0x1c,
+                    0x02,
+                    0xf2,
+                    0x1f,
+                    0xed,
+                    0xdf,
+                    0xe3,
+                    0x20,
+                    0xda,
+                    0xe0,
+                    0xee,
+                    0x1f,
+                    0xdf,
+                    0xdf,
+                    0x0b,
+                    0x00,
+                    0x00,
+                    0x00,
+                    0x00,
+                    0x00,
+                    0x00,
+                ];
+
+                // If this is a bluetooth gamepad, include the crc
+                if self.hardware.bus_type == BusType::Bluetooth {
+                    // TODO: Handle bluetooth CRC32
+                }
+
+                data
+            }
+            _ => {
+                log::debug!("Unknown get report request with report number: {report_number}");
+                return Err(EIO);
+            }
+        };
+
+        Ok(data)
+    }
+}
+
+/// Returns a MAC address in the byte order used by DualSense reports (reversed)
+/// for the given serial number. If the serial is not a MAC address, a locally
+/// administered address is derived from an FNV-1a hash of the serial.
+pub fn mac_addr_from_serial(serial: &str) -> [u8; 6] {
+    let octets: Vec<u8> = serial
+        .split([':', '-'])
+        .filter_map(|octet| u8::from_str_radix(octet, 16).ok())
+        .collect();
+    let mut mac_addr = [0u8; 6];
+    if octets.len() == 6 && serial.len() == 17 {
+        mac_addr.copy_from_slice(&octets);
+    } else {
+        let mut hash: u64 = 0xcbf29ce484222325;
+        for byte in serial.bytes() {
+            hash ^= byte as u64;
+            hash = hash.wrapping_mul(0x100000001b3);
+        }
+        mac_addr.copy_from_slice(&hash.to_be_bytes()[..6]);
+        // Set the locally administered bit and clear the multicast bit
+        mac_addr[0] = (mac_addr[0] | 0x02) & !0x01;
+    }
+    mac_addr.reverse();
+    mac_addr
+}
+
+/// Convert the given normalized value between -1.0 - 1.0 to the real value
+/// based on the given minimum and maximum axis range. Playstation gamepads
+/// use a range from 0-255, with 127 being the "nuetral" point.
+fn denormalize_signed_value(normal_value: f64, min: f64, max: f64) -> u8 {
+    let mid = (max + min) / 2.0;
+    let normal_value_abs = normal_value.abs();
+    if normal_value >= 0.0 {
+        let maximum = max - mid;
+        let value = normal_value * maximum + mid;
+        value as u8
+    } else {
+        let minimum = min - mid;
+        let value = normal_value_abs * minimum + mid;
+        value as u8
+    }
+}
+
+/// De-normalizes the given value from 0.0 - 1.0 into a real value based on
+/// the maximum axis range.
+fn denormalize_unsigned_value(normal_value: f64, max: f64) -> u8 {
+    (normal_value * max).round() as u8
+}
+
+/// De-normalizes the given value from 0.0 - 1.0 into a real value based on
+/// the maximum axis range.
+fn denormalize_touch_value(normal_value: f64, max: f64) -> u16 {
+    (normal_value * max).round() as u16
+}
+
+/// De-normalizes the given value in meters per second into a real value that
+/// the DS5 controller understands.
+/// DualSense accelerometer values are measured in [DS5_ACC_RES_PER_G]
+/// units of G acceleration (1G == 9.8m/s). InputPlumber accelerometer
+/// values are measured in units of meters per second. To denormalize
+/// the value, it needs to be converted into G units (by dividing by 9.8),
+/// then multiplying that value by the [DS5_ACC_RES_PER_G].
+fn denormalize_accel_value(value_meters_sec: f64) -> i16 {
+    let value_g = value_meters_sec / 9.8;
+    let value = value_g * DS5_ACC_RES_PER_G as f64;
+    value as i16
+}
+
+/// DualSense gyro values are measured in units of degrees per second.
+/// InputPlumber gyro values are also measured in degrees per second.
+fn denormalize_gyro_value(value_degrees_sec: f64) -> i16 {
+    let value = value_degrees_sec;
+    value as i16
+}
@@ -0,0 +1,79 @@
+use std::{
+    collections::{hash_map::RandomState, HashMap},
+    error::Error,
+    fs,
+    hash::{BuildHasher, Hasher},
+    path::PathBuf,
+    time::SystemTime,
+};
+
+use crate::input::calibration::get_state_dir;
+
+/// Serial numbers of target devices for source devices that do not have a
+/// serial number of their own (e.g. built-in gamepads of handhelds). The
+/// serials are stored in the state directory so target devices keep the same
+/// identity (e.g. uniq or MAC address) after InputPlumber restarts, which lets
+/// Steam and emulators keep their per-controller configuration.
+#[derive(Debug, Default)]
+pub struct TargetIdentities {
+    /// Generated serial numbers keyed by composite device identity
+    /// E.g. {"Lenovo Legion Go:usb-0000:c2:00.3-3": "5F0A3C9E21B7"}
+    serials: HashMap<String, String>,
+}
+
+impl TargetIdentities {
+    /// Load the stored target device serial numbers from the state directory
+    pub fn load() -> Self {
+        let path = get_path();
+        let serials = match fs::read_to_string(&path) {
+            Ok(content) => serde_yaml::from_str(&content).unwrap_or_else(|e| {
+                log::warn!("Failed to parse target identities {}: {e}", path.display());
+                HashMap::new()
+            }),
+            Err(_) => HashMap::new(),
+        };
+        Self { serials }
+    }
+
+    /// Returns the serial number for the given composite device identity,
+    /// generating and storing a new one if none exists yet.
+    pub fn get_serial(&mut self, key: &str) -> String {
+        if let Some(serial) = self.serials.get(key) {
+            return serial.clone();
+        }
+
+        let serial = generate_serial(key);
+        log::debug!("Generated target device serial {serial} for {key}");
+        self.serials.insert(key.to_string(), serial.clone());
+        if let Err(e) = self.save() {
+            log::warn!("Failed to save target identities: {e:?}");
+        }
+
+        serial
+    }
+
+    /// Store the target device serial numbers in the state directory
+    fn save(&self) -> Result<(), Box<dyn Error>> {
+        let path = get_path();
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(&path, serde_yaml::to_string(&self.serials)?)?;
+        Ok(())
+    }
+}
+
+/// Generate a new random 12 digit hexadecimal serial number
+fn generate_serial(key: &str) -> String {
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write(key.as_bytes());
+    if let Ok(time) = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH) {
+        hasher.write_u128(time.as_nanos());
+    }
+    format!("{:012X}", hasher.finish() & 0xFFFF_FFFF_FFFF)
+}
+
+/// Returns the path of the file where target device serials are stored
+fn get_path() -> PathBuf {
+    get_state_dir().join("targets.yaml")
+}
@@ -0,0 +1,811 @@
+use std::{collections::HashMap, error::Error};
+
+use evdev::{
+    uinput::{VirtualDevice, VirtualDeviceBuilder},
+    AbsInfo, AbsoluteAxisCode, AttributeSet, InputEvent, KeyCode, MiscCode,
+    SynchronizationCode, SynchronizationEvent,
+};
+use tokio::sync::mpsc;
+use zbus::Connection;
+
+use crate::{
+    dbus::interface::target::keyboard::TargetKeyboardInterface,
+    input::{
+        capability::{Capability, Keyboard},
+        composite_device,
+        event::{evdev::EvdevEvent, native::NativeEvent},
+    },
+};
+
+use super::TargetCommand;
+
+const BUFFER_SIZE: usize = 2048;
+
+#[derive(Debug)]
+pub struct KeyboardDevice {
+    conn: Connection,
+    dbus_path: Option<String>,
+    tx: mpsc::Sender<TargetCommand>,
+    rx: mpsc::Receiver<TargetCommand>,
+    composite_tx: Option<mpsc::Sender<composite_device::Command>>,
+}
+
+impl KeyboardDevice {
+    pub fn new(conn: Connection) -> Self {
+        let (tx, rx) = mpsc::channel(BUFFER_SIZE);
+        Self {
+            conn,
+            dbus_path: None,
+            composite_tx: None,
+            tx,
+            rx,
+        }
+    }
+
+    /// Returns the DBus path of this device
+    pub fn get_dbus_path(&self) -> Option<String> {
+        self.dbus_path.clone()
+    }
+
+    /// Returns a transmitter channel that can be used to send events to this device
+    pub fn transmitter(&self) -> mpsc::Sender<TargetCommand> {
+        self.tx.clone()
+    }
+
+    /// Configures the device to send output events to the given composite device
+    /// channel.
+    pub fn set_composite_device(&mut self, tx: mpsc::Sender<composite_device::Command>) {
+        self.composite_tx = Some(tx);
+    }
+
+    /// Creates a new instance of the device interface on DBus.
+    pub async fn listen_on_dbus(&mut self, path: String) -> Result<(), Box<dyn Error>> {
+        let conn = self.conn.clone();
+        self.dbus_path = Some(path.clone());
+        let tx = self.tx.clone();
+        tokio::spawn(async move {
+            let iface = TargetKeyboardInterface::new(tx);
+            if let Err(e) = conn.object_server().at(path, iface).await {
+                log::error!("Failed to setup DBus interface for device: {:?}", e);
+            }
+        });
+        Ok(())
+    }
+
+    /// Creates and runs the target device
+    pub async fn run(&mut self) -> Result<(), Box<dyn Error>> {
+        log::debug!("Creating virtual keyboard");
+        let mut device = self.create_virtual_device()?;
+        let axis_map = HashMap::new();
+
+        // Listen for send events
+        log::debug!("Started listening for events to send");
+        while let Some(command) = self.rx.recv().await {
+            match command {
+                TargetCommand::SetCompositeDevice(tx) => {
+                    self.set_composite_device(tx);
+                }
+                TargetCommand::WriteEvent(event) => {
+                    //log::debug!("Got event to emit: {:?}", event);
+                    let evdev_events = self.translate_event(event, axis_map.clone());
+                    device.emit(evdev_events.as_slice())?;
+                    device.emit(&[SynchronizationEvent::new(
+                        SynchronizationCode::SYN_REPORT,
+                        0,
+                    )
+                    .into()])?;
+                }
+                TargetCommand::GetCapabilities(tx) => {
+                    let caps = self.get_capabilities();
+                    if let Err(e) = tx.send(caps).await {
+                        log::error!("Failed to send target capabilities: {e:?}");
+                    }
+                }
+                TargetCommand::Stop => break,
+            };
+        }
+
+        log::debug!("Stopping device");
+
+        // Remove the DBus interface
+        if let Some(path) = self.dbus_path.clone() {
+            log::debug!("Removing DBus interface");
+            self.conn
+                .object_server()
+                .remove::<TargetKeyboardInterface, String>(path)
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Translate the given native event into an evdev event
+    fn translate_event(
+        &self,
+        event: NativeEvent,
+        axis_map: HashMap<AbsoluteAxisCode, AbsInfo>,
+    ) -> Vec<InputEvent> {
+        EvdevEvent::from_native_event(event, axis_map)
+            .into_iter()
+            .map(|event| event.as_input_event())
+            .collect()
+    }
+
+    /// Create the virtual device to emulate
+    fn create_virtual_device(&self) -> Result<VirtualDevice, Box<dyn Error>> {
+        let mut keys = AttributeSet::<KeyCode>::new();
+        keys.insert(KeyCode::KEY_ESC);
+        keys.insert(KeyCode::KEY_1);
+        keys.insert(KeyCode::KEY_2);
+        keys.insert(KeyCode::KEY_3);
+        keys.insert(KeyCode::KEY_4);
+        keys.insert(KeyCode::KEY_5);
+        keys.insert(KeyCode::KEY_6);
+        keys.insert(KeyCode::KEY_7);
+        keys.insert(KeyCode::KEY_8);
+        keys.insert(KeyCode::KEY_9);
+        keys.insert(KeyCode::KEY_0);
+        keys.insert(KeyCode::KEY_MINUS);
+        keys.insert(KeyCode::KEY_EQUAL);
+        keys.insert(KeyCode::KEY_BACKSPACE);
+        keys.insert(KeyCode::KEY_TAB);
+        keys.insert(KeyCode::KEY_Q);
+        keys.insert(KeyCode::KEY_W);
+        keys.insert(KeyCode::KEY_E);
+        keys.insert(KeyCode::KEY_R);
+        keys.insert(KeyCode::KEY_T);
+        keys.insert(KeyCode::KEY_Y);
+        keys.insert(KeyCode::KEY_U);
+        keys.insert(KeyCode::KEY_I);
+        keys.insert(KeyCode::KEY_O);
+        keys.insert(KeyCode::KEY_P);
+        keys.insert(KeyCode::KEY_LEFTBRACE);
+        keys.insert(KeyCode::KEY_RIGHTBRACE);
+        keys.insert(KeyCode::KEY_ENTER);
+        keys.insert(KeyCode::KEY_LEFTCTRL);
+        keys.insert(KeyCode::KEY_A);
+        keys.insert(KeyCode::KEY_S);
+        keys.insert(KeyCode::KEY_D);
+        keys.insert(KeyCode::KEY_F);
+        keys.insert(KeyCode::KEY_G);
+        keys.insert(KeyCode::KEY_H);
+        keys.insert(KeyCode::KEY_J);
+        keys.insert(KeyCode::KEY_K);
+        keys.insert(KeyCode::KEY_L);
+        keys.insert(KeyCode::KEY_SEMICOLON);
+        keys.insert(KeyCode::KEY_APOSTROPHE);
+        keys.insert(KeyCode::KEY_GRAVE);
+        keys.insert(KeyCode::KEY_LEFTSHIFT);
+        keys.insert(KeyCode::KEY_BACKSLASH);
+        keys.insert(KeyCode::KEY_Z);
+        keys.insert(KeyCode::KEY_X);
+        keys.insert(KeyCode::KEY_C);
+        keys.insert(KeyCode::KEY_V);
+        keys.insert(KeyCode::KEY_B);
+        keys.insert(KeyCode::KEY_N);
+        keys.insert(KeyCode::KEY_M);
+        keys.insert(KeyCode::KEY_COMMA);
+        keys.insert(KeyCode::KEY_DOT);
+        keys.insert(KeyCode::KEY_SLASH);
+        keys.insert(KeyCode::KEY_RIGHTSHIFT);
+        keys.insert(KeyCode::KEY_KPASTERISK);
+        keys.insert(KeyCode::KEY_LEFTALT);
+        keys.insert(KeyCode::KEY_SPACE);
+        keys.insert(KeyCode::KEY_CAPSLOCK);
+        keys.insert(KeyCode::KEY_F1);
+        keys.insert(KeyCode::KEY_F2);
+        keys.insert(KeyCode::KEY_F3);
+        keys.insert(KeyCode::KEY_F4);
+        keys.insert(KeyCode::KEY_F5);
+        keys.insert(KeyCode::KEY_F6);
+        keys.insert(KeyCode::KEY_F7);
+        keys.insert(KeyCode::KEY_F8);
+        keys.insert(KeyCode::KEY_F9);
+        keys.insert(KeyCode::KEY_F10);
+        keys.insert(KeyCode::KEY_NUMLOCK);
+        keys.insert(KeyCode::KEY_SCROLLLOCK);
+        keys.insert(KeyCode::KEY_KP7);
+        keys.insert(KeyCode::KEY_KP8);
+        keys.insert(KeyCode::KEY_KP9);
+        keys.insert(KeyCode::KEY_KPMINUS);
+        keys.insert(KeyCode::KEY_KP4);
+        keys.insert(KeyCode::KEY_KP5);
+        keys.insert(KeyCode::KEY_KP6);
+        keys.insert(KeyCode::KEY_KPPLUS);
+        keys.insert(KeyCode::KEY_KP1);
+        keys.insert(KeyCode::KEY_KP2);
+        keys.insert(KeyCode::KEY_KP3);
+        keys.insert(KeyCode::KEY_KP0);
+        keys.insert(KeyCode::KEY_KPDOT);
+        keys.insert(KeyCode::KEY_ZENKAKUHANKAKU);
+        keys.insert(KeyCode::KEY_102ND);
+        keys.insert(KeyCode::KEY_F11);
+        keys.insert(KeyCode::KEY_F12);
+        keys.insert(KeyCode::KEY_RO);
+        keys.insert(KeyCode::KEY_KATAKANA);
+        keys.insert(KeyCode::KEY_HIRAGANA);
+        keys.insert(KeyCode::KEY_HENKAN);
+        keys.insert(KeyCode::KEY_KATAKANAHIRAGANA);
+        keys.insert(KeyCode::KEY_MUHENKAN);
+        keys.insert(KeyCode::KEY_KPJPCOMMA);
+        keys.insert(KeyCode::KEY_KPENTER);
+        keys.insert(KeyCode::KEY_RIGHTCTRL);
+        keys.insert(KeyCode::KEY_KPSLASH);
+        keys.insert(KeyCode::KEY_SYSRQ);
+        keys.insert(KeyCode::KEY_RIGHTALT);
+        keys.insert(KeyCode::KEY_HOME);
+        keys.insert(KeyCode::KEY_UP);
+        keys.insert(KeyCode::KEY_PAGEUP);
+        keys.insert(KeyCode::KEY_LEFT);
+        keys.insert(KeyCode::KEY_RIGHT);
+        keys.insert(KeyCode::KEY_END);
+        keys.insert(KeyCode::KEY_DOWN);
+        keys.insert(KeyCode::KEY_PAGEDOWN);
+        keys.insert(KeyCode::KEY_INSERT);
+        keys.insert(KeyCode::KEY_DELETE);
+        keys.insert(KeyCode::KEY_MUTE);
+        keys.insert(KeyCode::KEY_VOLUMEDOWN);
+        keys.insert(KeyCode::KEY_VOLUMEUP);
+        keys.insert(KeyCode::KEY_POWER);
+        keys.insert(KeyCode::KEY_KPEQUAL);
+        keys.insert(KeyCode::KEY_PAUSE);
+        keys.insert(KeyCode::KEY_KPCOMMA);
+        keys.insert(KeyCode::KEY_HANJA);
+        keys.insert(KeyCode::KEY_YEN);
+        keys.insert(KeyCode::KEY_LEFTMETA);
+        keys.insert(KeyCode::KEY_RIGHTMETA);
+        keys.insert(KeyCode::KEY_COMPOSE);
+        keys.insert(KeyCode::KEY_STOP);
+        keys.insert(KeyCode::KEY_AGAIN);
+        keys.insert(KeyCode::KEY_PROPS);
+        keys.insert(KeyCode::KEY_UNDO);
+        keys.insert(KeyCode::KEY_FRONT);
+        keys.insert(KeyCode::KEY_COPY);
+        keys.insert(KeyCode::KEY_OPEN);
+        keys.insert(KeyCode::KEY_PASTE);
+        keys.insert(KeyCode::KEY_FIND);
+        keys.insert(KeyCode::KEY_CUT);
+        keys.insert(KeyCode::KEY_HELP);
+        keys.insert(KeyCode::KEY_CALC);
+        keys.insert(KeyCode::KEY_SLEEP);
+        keys.insert(KeyCode::KEY_WWW);
+        keys.insert(KeyCode::KEY_BACK);
+        keys.insert(KeyCode::KEY_FORWARD);
+        keys.insert(KeyCode::KEY_EJECTCD);
+        keys.insert(KeyCode::KEY_NEXTSONG);
+        keys.insert(KeyCode::KEY_PLAYPAUSE);
+        keys.insert(KeyCode::KEY_PREVIOUSSONG);
+        keys.insert(KeyCode::KEY_STOPCD);
+        keys.insert(KeyCode::KEY_REFRESH);
+        keys.insert(KeyCode::KEY_EDIT);
+        keys.insert(KeyCode::KEY_SCROLLUP);
+        keys.insert(KeyCode::KEY_SCROLLDOWN);
+        keys.insert(KeyCode::KEY_KPLEFTPAREN);
+        keys.insert(KeyCode::KEY_KPRIGHTPAREN);
+        keys.insert(KeyCode::KEY_F13);
+        keys.insert(KeyCode::KEY_F14);
+        keys.insert(KeyCode::KEY_F15);
+        keys.insert(KeyCode::KEY_F16);
+        keys.insert(KeyCode::KEY_F17);
+        keys.insert(KeyCode::KEY_F18);
+        keys.insert(KeyCode::KEY_F19);
+        keys.insert(KeyCode::KEY_F20);
+        keys.insert(KeyCode::KEY_F21);
+        keys.insert(KeyCode::KEY_F22);
+        keys.insert(KeyCode::KEY_F23);
+        keys.insert(KeyCode::KEY_F24);
+        keys.insert(KeyCode::KEY_PROG1);
+        keys.insert(KeyCode::KEY_PROG2);
+        keys.insert(KeyCode::KEY_PROG3);
+        keys.insert(KeyCode::KEY_PROG4);
+        keys.insert(KeyCode::KEY_WLAN);
+        keys.insert(KeyCode::KEY_RFKILL);
+        keys.insert(KeyCode::KEY_LINEFEED);
+        keys.insert(KeyCode::KEY_MACRO);
+        keys.insert(KeyCode::KEY_KPPLUSMINUS);
+        keys.insert(KeyCode::KEY_SCALE);
+        keys.insert(KeyCode::KEY_HANGEUL);
+        keys.insert(KeyCode::KEY_MENU);
+        keys.insert(KeyCode::KEY_SETUP);
+        keys.insert(KeyCode::KEY_WAKEUP);
+        keys.insert(KeyCode::KEY_FILE);
+        keys.insert(KeyCode::KEY_SENDFILE);
+        keys.insert(KeyCode::KEY_DELETEFILE);
+        keys.insert(KeyCode::KEY_XFER);
+        keys.insert(KeyCode::KEY_MSDOS);
+        keys.insert(KeyCode::KEY_COFFEE);
+        keys.insert(KeyCode::KEY_ROTATE_DISPLAY);
+        keys.insert(KeyCode::KEY_CYCLEWINDOWS);
+        keys.insert(KeyCode::KEY_MAIL);
+        keys.insert(KeyCode::KEY_BOOKMARKS);
+        keys.insert(KeyCode::KEY_COMPUTER);
+        keys.insert(KeyCode::KEY_CLOSECD);
+        keys.insert(KeyCode::KEY_EJECTCLOSECD);
+        keys.insert(KeyCode::KEY_RECORD);
+        keys.insert(KeyCode::KEY_REWIND);
+        keys.insert(KeyCode::KEY_PHONE);
+        keys.insert(KeyCode::KEY_ISO);
+        keys.insert(KeyCode::KEY_CONFIG);
+        keys.insert(KeyCode::KEY_HOMEPAGE);
+        keys.insert(KeyCode::KEY_EXIT);
+        keys.insert(KeyCode::KEY_MOVE);
+        keys.insert(KeyCode::KEY_NEW);
+        keys.insert(KeyCode::KEY_REDO);
+        keys.insert(KeyCode::KEY_PLAYCD);
+        keys.insert(KeyCode::KEY_PAUSECD);
+        keys.insert(KeyCode::KEY_DASHBOARD);
+        keys.insert(KeyCode::KEY_SUSPEND);
+        keys.insert(KeyCode::KEY_CLOSE);
+        keys.insert(KeyCode::KEY_PLAY);
+        keys.insert(KeyCode::KEY_FASTFORWARD);
+        keys.insert(KeyCode::KEY_BASSBOOST);
+        keys.insert(KeyCode::KEY_PRINT);
+        keys.insert(KeyCode::KEY_HP);
+        keys.insert(KeyCode::KEY_CAMERA);
+        keys.insert(KeyCode::KEY_SOUND);
+        keys.insert(KeyCode::KEY_QUESTION);
+        keys.insert(KeyCode::KEY_EMAIL);
+        keys.insert(KeyCode::KEY_CHAT);
+        keys.insert(KeyCode::KEY_SEARCH);
+        keys.insert(KeyCode::KEY_CONNECT);
+        keys.insert(KeyCode::KEY_FINANCE);
+        keys.insert(KeyCode::KEY_SPORT);
+        keys.insert(KeyCode::KEY_SHOP);
+        keys.insert(KeyCode::KEY_ALTERASE);
+        keys.insert(KeyCode::KEY_CANCEL);
+        keys.insert(KeyCode::KEY_BRIGHTNESSDOWN);
+        keys.insert(KeyCode::KEY_BRIGHTNESSUP);
+        keys.insert(KeyCode::KEY_MEDIA);
+        keys.insert(KeyCode::KEY_SWITCHVIDEOMODE);
+        keys.insert(KeyCode::KEY_KBDILLUMTOGGLE);
+        keys.insert(KeyCode::KEY_KBDILLUMDOWN);
+        keys.insert(KeyCode::KEY_KBDILLUMUP);
+        keys.insert(KeyCode::KEY_SEND);
+        keys.insert(KeyCode::KEY_REPLY);
+        keys.insert(KeyCode::KEY_FORWARDMAIL);
+        keys.insert(KeyCode::KEY_SAVE);
+        keys.insert(KeyCode::KEY_DOCUMENTS);
+        keys.insert(KeyCode::KEY_BATTERY);
+        keys.insert(KeyCode::KEY_BLUETOOTH);
+        keys.insert(KeyCode::KEY_UWB);
+        keys.insert(KeyCode::KEY_VIDEO_NEXT);
+        keys.insert(KeyCode::KEY_VIDEO_PREV);
+        keys.insert(KeyCode::KEY_BRIGHTNESS_CYCLE);
+        keys.insert(KeyCode::KEY_BRIGHTNESS_AUTO);
+        keys.insert(KeyCode::KEY_DISPLAY_OFF);
+        keys.insert(KeyCode::KEY_WWAN);
+        keys.insert(KeyCode::KEY_OK);
+        keys.insert(KeyCode::KEY_SELECT);
+        keys.insert(KeyCode::KEY_GOTO);
+        keys.insert(KeyCode::KEY_CLEAR);
+        keys.insert(KeyCode::KEY_POWER2);
+        keys.insert(KeyCode::KEY_OPTION);
+        keys.insert(KeyCode::KEY_INFO);
+        keys.insert(KeyCode::KEY_TIME);
+        keys.insert(KeyCode::KEY_VENDOR);
+        keys.insert(KeyCode::KEY_ARCHIVE);
+        keys.insert(KeyCode::KEY_PROGRAM);
+        keys.insert(KeyCode::KEY_CHANNEL);
+        keys.insert(KeyCode::KEY_FAVORITES);
+        keys.insert(KeyCode::KEY_EPG);
+        keys.insert(KeyCode::KEY_PVR);
+        keys.insert(KeyCode::KEY_MHP);
+        keys.insert(KeyCode::KEY_LANGUAGE);
+        keys.insert(KeyCode::KEY_TITLE);
+        keys.insert(KeyCode::KEY_SUBTITLE);
+        keys.insert(KeyCode::KEY_ANGLE);
+        keys.insert(KeyCode::KEY_MODE);
+        keys.insert(KeyCode::KEY_KEYBOARD);
+        keys.insert(KeyCode::KEY_SCREEN);
+        keys.insert(KeyCode::KEY_PC);
+        keys.insert(KeyCode::KEY_TV);
+        keys.insert(KeyCode::KEY_TV2);
+        keys.insert(KeyCode::KEY_VCR);
+        keys.insert(KeyCode::KEY_VCR2);
+        keys.insert(KeyCode::KEY_SAT);
+        keys.insert(KeyCode::KEY_SAT2);
+        keys.insert(KeyCode::KEY_CD);
+        keys.insert(KeyCode::KEY_TAPE);
+        keys.insert(KeyCode::KEY_RADIO);
+        keys.insert(KeyCode::KEY_TUNER);
+        keys.insert(KeyCode::KEY_PLAYER);
+        keys.insert(KeyCode::KEY_TEXT);
+        keys.insert(KeyCode::KEY_DVD);
+        keys.insert(KeyCode::KEY_AUX);
+        keys.insert(KeyCode::KEY_MP3);
+        keys.insert(KeyCode::KEY_AUDIO);
+        keys.insert(KeyCode::KEY_VIDEO);
+        keys.insert(KeyCode::KEY_DIRECTORY);
+        keys.insert(KeyCode::KEY_LIST);
+        keys.insert(KeyCode::KEY_MEMO);
+        keys.insert(KeyCode::KEY_CALENDAR);
+        keys.insert(KeyCode::KEY_RED);
+        keys.insert(KeyCode::KEY_GREEN);
+        keys.insert(KeyCode::KEY_YELLOW);
+        keys.insert(KeyCode::KEY_BLUE);
+        keys.insert(KeyCode::KEY_CHANNELUP);
+        keys.insert(KeyCode::KEY_CHANNELDOWN);
+        keys.insert(KeyCode::KEY_FIRST);
+        keys.insert(KeyCode::KEY_LAST);
+        keys.insert(KeyCode::KEY_AB);
+        keys.insert(KeyCode::KEY_NEXT);
+        keys.insert(KeyCode::KEY_RESTART);
+        keys.insert(KeyCode::KEY_SLOW);
+        keys.insert(KeyCode::KEY_SHUFFLE);
+        keys.insert(KeyCode::KEY_BREAK);
+        keys.insert(KeyCode::KEY_PREVIOUS);
+        keys.insert(KeyCode::KEY_DIGITS);
+        keys.insert(KeyCode::KEY_TEEN);
+        keys.insert(KeyCode::KEY_TWEN);
+        keys.insert(KeyCode::KEY_VIDEOPHONE);
+        keys.insert(KeyCode::KEY_GAMES);
+        keys.insert(KeyCode::KEY_ZOOMIN);
+        keys.insert(KeyCode::KEY_ZOOMOUT);
+        keys.insert(KeyCode::KEY_ZOOMRESET);
+        keys.insert(KeyCode::KEY_WORDPROCESSOR);
+        keys.insert(KeyCode::KEY_EDITOR);
+        keys.insert(KeyCode::KEY_SPREADSHEET);
+        keys.insert(KeyCode::KEY_GRAPHICSEDITOR);
+        keys.insert(KeyCode::KEY_PRESENTATION);
+        keys.insert(KeyCode::KEY_DATABASE);
+        keys.insert(KeyCode::KEY_NEWS);
+        keys.insert(KeyCode::KEY_VOICEMAIL);
+        keys.insert(KeyCode::KEY_ADDRESSBOOK);
+        keys.insert(KeyCode::KEY_MESSENGER);
+        keys.insert(KeyCode::KEY_DISPLAYTOGGLE);
+        keys.insert(KeyCode::KEY_SPELLCHECK);
+        keys.insert(KeyCode::KEY_LOGOFF);
+        keys.insert(KeyCode::KEY_DOLLAR);
+        keys.insert(KeyCode::KEY_EURO);
+        keys.insert(KeyCode::KEY_FRAMEBACK);
+        keys.insert(KeyCode::KEY_FRAMEFORWARD);
+        keys.insert(KeyCode::KEY_CONTEXT_MENU);
+        keys.insert(KeyCode::KEY_MEDIA_REPEAT);
+        keys.insert(KeyCode::KEY_10CHANNELSUP);
+        keys.insert(KeyCode::KEY_10CHANNELSDOWN);
+        keys.insert(KeyCode::KEY_IMAGES);
+
+        let device = VirtualDeviceBuilder::new()?
+            .name("InputPlumber Keyboard")
+            .with_keys(&keys)?
+            .with_msc(&AttributeSet::from_iter([MiscCode::MSC_TIMESTAMP]))?
+            .build()?;
+
+        Ok(device)
+    }
+
+    fn get_capabilities(&self) -> Vec<Capability> {
+        vec![
+            Capability::Keyboard(Keyboard::KeyEsc),
+            Capability::Keyboard(Keyboard::Key1),
+            Capability::Keyboard(Keyboard::Key2),
+            Capability::Keyboard(Keyboard::Key3),
+            Capability::Keyboard(Keyboard::Key4),
+            Capability::Keyboard(Keyboard::Key5),
+            Capability::Keyboard(Keyboard::Key6),
+            Capability::Keyboard(Keyboard::Key7),
+            Capability::Keyboard(Keyboard::Key8),
+            Capability::Keyboard(Keyboard::Key9),
+            Capability::Keyboard(Keyboard::Key0),
+            Capability::Keyboard(Keyboard::KeyMinus),
+            Capability::Keyboard(Keyboard::KeyEqual),
+            Capability::Keyboard(Keyboard::KeyBackspace),
+            Capability::Keyboard(Keyboard::KeyTab),
+            Capability::Keyboard(Keyboard::KeyQ),
+            Capability::Keyboard(Keyboard::KeyW),
+            Capability::Keyboard(Keyboard::KeyE),
+            Capability::Keyboard(Keyboard::KeyR),
+            Capability::Keyboard(Keyboard::KeyT),
+            Capability::Keyboard(Keyboard::KeyY),
+            Capability::Keyboard(Keyboard::KeyU),
+            Capability::Keyboard(Keyboard::KeyI),
+            Capability::Keyboard(Keyboard::KeyO),
+            Capability::Keyboard(Keyboard::KeyP),
+            Capability::Keyboard(Keyboard::KeyLeftBrace),
+            Capability::Keyboard(Keyboard::KeyRightBrace),
+            Capability::Keyboard(Keyboard::KeyEnter),
+            Capability::Keyboard(Keyboard::KeyLeftCtrl),
+            Capability::Keyboard(Keyboard::KeyA),
+            Capability::Keyboard(Keyboard::KeyS),
+            Capability::Keyboard(Keyboard::KeyD),
+            Capability::Keyboard(Keyboard::KeyF),
+            Capability::Keyboard(Keyboard::KeyG),
+            Capability::Keyboard(Keyboard::KeyH),
+            Capability::Keyboard(Keyboard::KeyJ),
+            Capability::Keyboard(Keyboard::KeyK),
+            Capability::Keyboard(Keyboard::KeyL),
+            Capability::Keyboard(Keyboard::KeySemicolon),
+            Capability::Keyboard(Keyboard::KeyApostrophe),
+            Capability::Keyboard(Keyboard::KeyGrave),
+            Capability::Keyboard(Keyboard::KeyLeftShift),
+            Capability::Keyboard(Keyboard::KeyBackslash),
+            Capability::Keyboard(Keyboard::KeyZ),
+            Capability::Keyboard(Keyboard::KeyX),
+            Capability::Keyboard(Keyboard::KeyC),
+            Capability::Keyboard(Keyboard::KeyV),
+            Capability::Keyboard(Keyboard::KeyB),
+            Capability::Keyboard(Keyboard::KeyN),
+            Capability::Keyboard(Keyboard::KeyM),
+            Capability::Keyboard(Keyboard::KeyComma),
+            Capability::Keyboard(Keyboard::KeyDot),
+            Capability::Keyboard(Keyboard::KeySlash),
+            Capability::Keyboard(Keyboard::KeyRightShift),
+            Capability::Keyboard(Keyboard::KeyKpAsterisk),
+            Capability::Keyboard(Keyboard::KeyLeftAlt),
+            Capability::Keyboard(Keyboard::KeySpace),
+            Capability::Keyboard(Keyboard::KeyCapslock),
+            Capability::Keyboard(Keyboard::KeyF1),
+            Capability::Keyboard(Keyboard::KeyF2),
+            Capability::Keyboard(Keyboard::KeyF3),
+            Capability::Keyboard(Keyboard::KeyF4),
+            Capability::Keyboard(Keyboard::KeyF5),
+            Capability::Keyboard(Keyboard::KeyF6),
+            Capability::Keyboard(Keyboard::KeyF7),
+            Capability::Keyboard(Keyboard::KeyF8),
+            Capability::Keyboard(Keyboard::KeyF9),
+            Capability::Keyboard(Keyboard::KeyF10),
+            Capability::Keyboard(Keyboard::KeyNumlock),
+            Capability::Keyboard(Keyboard::KeyScrollLock),
+            Capability::Keyboard(Keyboard::KeyKp7),
+            Capability::Keyboard(Keyboard::KeyKp8),
+            Capability::Keyboard(Keyboard::KeyKp9),
+            Capability::Keyboard(Keyboard::KeyKpMinus),
+            Capability::Keyboard(Keyboard::KeyKp4),
+            Capability::Keyboard(Keyboard::KeyKp5),
+            Capability::Keyboard(Keyboard::KeyKp6),
+            Capability::Keyboard(Keyboard::KeyKpPlus),
+            Capability::Keyboard(Keyboard::KeyKp1),
+            Capability::Keyboard(Keyboard::KeyKp2),
+            Capability::Keyboard(Keyboard::KeyKp3),
+            Capability::Keyboard(Keyboard::KeyKp0),
+            Capability::Keyboard(Keyboard::KeyKpDot),
+            Capability::Keyboard(Keyboard::KeyZenkakuhankaku),
+            Capability::Keyboard(Keyboard::Key102nd),
+            Capability::Keyboard(Keyboard::KeyF11),
+            Capability::Keyboard(Keyboard::KeyF12),
+            Capability::Keyboard(Keyboard::KeyRo),
+            Capability::Keyboard(Keyboard::KeyKatakana),
+            Capability::Keyboard(Keyboard::KeyHiragana),
+            Capability::Keyboard(Keyboard::KeyHenkan),
+            Capability::Keyboard(Keyboard::KeyKatakanaHiragana),
+            Capability::Keyboard(Keyboard::KeyMuhenkan),
+            Capability::Keyboard(Keyboard::KeyKpJpComma),
+            Capability::Keyboard(Keyboard::KeyKpEnter),
+            Capability::Keyboard(Keyboard::KeyRightCtrl),
+            Capability::Keyboard(Keyboard::KeyKpSlash),
+            Capability::Keyboard(Keyboard::KeySysrq),
+            Capability::Keyboard(Keyboard::KeyRightAlt),
+            Capability::Keyboard(Keyboard::KeyHome),
+            Capability::Keyboard(Keyboard::KeyUp),
+            Capability::Keyboard(Keyboard::KeyPageUp),
+            Capability::Keyboard(Keyboard::KeyLeft),
+            Capability::Keyboard(Keyboard::KeyRight),
+            Capability::Keyboard(Keyboard::KeyEnd),
+            Capability::Keyboard(Keyboard::KeyDown),
+            Capability::Keyboard(Keyboard::KeyPageDown),
+            Capability::Keyboard(Keyboard::KeyInsert),
+            Capability::Keyboard(Keyboard::KeyDelete),
+            Capability::Keyboard(Keyboard::KeyMute),
+            Capability::Keyboard(Keyboard::KeyVolumeDown),
+            Capability::Keyboard(Keyboard::KeyVolumeUp),
+            Capability::Keyboard(Keyboard::KeyPower),
+            Capability::Keyboard(Keyboard::KeyKpEqual),
+            Capability::Keyboard(Keyboard::KeyPause),
+            Capability::Keyboard(Keyboard::KeyKpComma),
+            Capability::Keyboard(Keyboard::KeyHanja),
+            Capability::Keyboard(Keyboard::KeyYen),
+            Capability::Keyboard(Keyboard::KeyLeftMeta),
+            Capability::Keyboard(Keyboard::KeyRightMeta),
+            Capability::Keyboard(Keyboard::KeyCompose),
+            Capability::Keyboard(Keyboard::KeyStop),
+            Capability::Keyboard(Keyboard::KeyAgain),
+            Capability::Keyboard(Keyboard::KeyProps),
+            Capability::Keyboard(Keyboard::KeyUndo),
+            Capability::Keyboard(Keyboard::KeyFront),
+            Capability::Keyboard(Keyboard::KeyCopy),
+            Capability::Keyboard(Keyboard::KeyOpen),
+            Capability::Keyboard(Keyboard::KeyPaste),
+            Capability::Keyboard(Keyboard::KeyFind),
+            Capability::Keyboard(Keyboard::KeyCut),
+            Capability::Keyboard(Keyboard::KeyHelp),
+            Capability::Keyboard(Keyboard::KeyCalc),
+            Capability::Keyboard(Keyboard::KeySleep),
+            Capability::Keyboard(Keyboard::KeyWww),
+            Capability::Keyboard(Keyboard::KeyBack),
+            Capability::Keyboard(Keyboard::KeyForward),
+            Capability::Keyboard(Keyboard::KeyEjectCD),
+            Capability::Keyboard(Keyboard::KeyNextSong),
+            Capability::Keyboard(Keyboard::KeyPlayPause),
+            Capability::Keyboard(Keyboard::KeyPreviousSong),
+            Capability::Keyboard(Keyboard::KeyStopCD),
+            Capability::Keyboard(Keyboard::KeyRefresh),
+            Capability::Keyboard(Keyboard::KeyEdit),
+            Capability::Keyboard(Keyboard::KeyScrollUp),
+            Capability::Keyboard(Keyboard::KeyScrollDown),
+            Capability::Keyboard(Keyboard::KeyKpLeftParen),
+            Capability::Keyboard(Keyboard::KeyKpRightParen),
+            Capability::Keyboard(Keyboard::KeyF13),
+            Capability::Keyboard(Keyboard::KeyF14),
+            Capability::Keyboard(Keyboard::KeyF15),
+            Capability::Keyboard(Keyboard::KeyF16),
+            Capability::Keyboard(Keyboard::KeyF17),
+            Capability::Keyboard(Keyboard::KeyF18),
+            Capability::Keyboard(Keyboard::KeyF19),
+            Capability::Keyboard(Keyboard::KeyF20),
+            Capability::Keyboard(Keyboard::KeyF21),
+            Capability::Keyboard(Keyboard::KeyF22),
+            Capability::Keyboard(Keyboard::KeyF23),
+            Capability::Keyboard(Keyboard::KeyF24),
+            Capability::Keyboard(Keyboard::KeyProg1),
+            Capability::Keyboard(Keyboard::KeyProg2),
+            Capability::Keyboard(Keyboard::KeyProg3),
+            Capability::Keyboard(Keyboard::KeyProg4),
+            Capability::Keyboard(Keyboard::KeyWlan),
+            Capability::Keyboard(Keyboard::KeyRfkill),
+            Capability::Keyboard(Keyboard::KeyLinefeed),
+            Capability::Keyboard(Keyboard::KeyMacro),
+            Capability::Keyboard(Keyboard::KeyKpPlusMinus),
+            Capability::Keyboard(Keyboard::KeyScale),
+            Capability::Keyboard(Keyboard::KeyHangeul),
+            Capability::Keyboard(Keyboard::KeyMenu),
+            Capability::Keyboard(Keyboard::KeySetup),
+            Capability::Keyboard(Keyboard::KeyWakeup),
+            Capability::Keyboard(Keyboard::KeyFile),
+            Capability::Keyboard(Keyboard::KeySendFile),
+            Capability::Keyboard(Keyboard::KeyDeleteFile),
+            Capability::Keyboard(Keyboard::KeyXfer),
+            Capability::Keyboard(Keyboard::KeyMsdos),
+            Capability::Keyboard(Keyboard::KeyCoffee),
+            Capability::Keyboard(Keyboard::KeyRotateDisplay),
+            Capability::Keyboard(Keyboard::KeyCycleWindows),
+            Capability::Keyboard(Keyboard::KeyMail),
+            Capability::Keyboard(Keyboard::KeyBookmarks),
+            Capability::Keyboard(Keyboard::KeyComputer),
+            Capability::Keyboard(Keyboard::KeyCloseCD),
+            Capability::Keyboard(Keyboard::KeyEjectCloseCD),
+            Capability::Keyboard(Keyboard::KeyRecord),
+            Capability::Keyboard(Keyboard::KeyRewind),
+            Capability::Keyboard(Keyboard::KeyPhone),
+            Capability::Keyboard(Keyboard::KeyIso),
+            Capability::Keyboard(Keyboard::KeyConfig),
+            Capability::Keyboard(Keyboard::KeyHomepage),
+            Capability::Keyboard(Keyboard::KeyExit),
+            Capability::Keyboard(Keyboard::KeyMove),
+            Capability::Keyboard(Keyboard::KeyNew),
+            Capability::Keyboard(Keyboard::KeyRedo),
+            Capability::Keyboard(Keyboard::KeyPlayCD),
+            Capability::Keyboard(Keyboard::KeyPauseCD),
+            Capability::Keyboard(Keyboard::KeyDashboard),
+            Capability::Keyboard(Keyboard::KeySuspend),
+            Capability::Keyboard(Keyboard::KeyClose),
+            Capability::Keyboard(Keyboard::KeyPlay),
+            Capability::Keyboard(Keyboard::KeyFastForward),
+            Capability::Keyboard(Keyboard::KeyBassBoost),
+            Capability::Keyboard(Keyboard::KeyPrint),
+            Capability::Keyboard(Keyboard::KeyHp),
+            Capability::Keyboard(Keyboard::KeyCamera),
+            Capability::Keyboard(Keyboard::KeySound),
+            Capability::Keyboard(Keyboard::KeyQuestion),
+            Capability::Keyboard(Keyboard::KeyEmail),
+            Capability::Keyboard(Keyboard::KeyChat),
+            Capability::Keyboard(Keyboard::KeySearch),
+            Capability::Keyboard(Keyboard::KeyConnect),
+            Capability::Keyboard(Keyboard::KeyFinance),
+            Capability::Keyboard(Keyboard::KeySport),
+            Capability::Keyboard(Keyboard::KeyShop),
+            Capability::Keyboard(Keyboard::KeyAltErase),
+            Capability::Keyboard(Keyboard::KeyCancel),
+            Capability::Keyboard(Keyboard::KeyBrightnessDown),
+            Capability::Keyboard(Keyboard::KeyBrightnessUp),
+            Capability::Keyboard(Keyboard::KeyMedia),
+            Capability::Keyboard(Keyboard::KeySwitchVideoMode),
+            Capability::Keyboard(Keyboard::KeyKbdIllumToggle),
+            Capability::Keyboard(Keyboard::KeyKbdIllumDown),
+            Capability::Keyboard(Keyboard::KeyKbdIllumUp),
+            Capability::Keyboard(Keyboard::KeySend),
+            Capability::Keyboard(Keyboard::KeyReply),
+            Capability::Keyboard(Keyboard::KeyForwardMail),
+            Capability::Keyboard(Keyboard::KeySave),
+            Capability::Keyboard(Keyboard::KeyDocuments),
+            Capability::Keyboard(Keyboard::KeyBattery),
+            Capability::Keyboard(Keyboard::KeyBluetooth),
+            Capability::Keyboard(Keyboard::KeyUwb),
+            Capability::Keyboard(Keyboard::KeyVideoNext),
+            Capability::Keyboard(Keyboard::KeyVideoPrev),
+            Capability::Keyboard(Keyboard::KeyBrightnessCycle),
+            Capability::Keyboard(Keyboard::KeyBrightnessAuto),
+            Capability::Keyboard(Keyboard::KeyDisplayOff),
+            Capability::Keyboard(Keyboard::KeyWwan),
+            Capability::Keyboard(Keyboard::KeyOk),
+            Capability::Keyboard(Keyboard::KeySelect),
+            Capability::Keyboard(Keyboard::KeyGoto),
+            Capability::Keyboard(Keyboard::KeyClear),
+            Capability::Keyboard(Keyboard::KeyPower2),
+            Capability::Keyboard(Keyboard::KeyOption),
+            Capability::Keyboard(Keyboard::KeyInfo),
+            Capability::Keyboard(Keyboard::KeyTime),
+            Capability::Keyboard(Keyboard::KeyVendor),
+            Capability::Keyboard(Keyboard::KeyArchive),
+            Capability::Keyboard(Keyboard::KeyProgram),
+            Capability::Keyboard(Keyboard::KeyChannel),
+            Capability::Keyboard(Keyboard::KeyFavorites),
+            Capability::Keyboard(Keyboard::KeyEpg),
+            Capability::Keyboard(Keyboard::KeyPvr),
+            Capability::Keyboard(Keyboard::KeyMhp),
+            Capability::Keyboard(Keyboard::KeyLanguage),
+            Capability::Keyboard(Keyboard::KeyTitle),
+            Capability::Keyboard(Keyboard::KeySubtitle),
+            Capability::Keyboard(Keyboard::KeyAngle),
+            Capability::Keyboard(Keyboard::KeyMode),
+            Capability::Keyboard(Keyboard::KeyKeyboard),
+            Capability::Keyboard(Keyboard::KeyScreen),
+            Capability::Keyboard(Keyboard::KeyPc),
+            Capability::Keyboard(Keyboard::KeyTv),
+            Capability::Keyboard(Keyboard::KeyTv2),
+            Capability::Keyboard(Keyboard::KeyVcr),
+            Capability::Keyboard(Keyboard::KeyVcr2),
+            Capability::Keyboard(Keyboard::KeySat),
+            Capability::Keyboard(Keyboard::KeySat2),
+            Capability::Keyboard(Keyboard::KeyCd),
+            Capability::Keyboard(Keyboard::KeyTape),
+            Capability::Keyboard(Keyboard::KeyRadio),
+            Capability::Keyboard(Keyboard::KeyTuner),
+            Capability::Keyboard(Keyboard::KeyPlayer),
+            Capability::Keyboard(Keyboard::KeyText),
+            Capability::Keyboard(Keyboard::KeyDvd),
+            Capability::Keyboard(Keyboard::KeyAux),
+            Capability::Keyboard(Keyboard::KeyMp3),
+            Capability::Keyboard(Keyboard::KeyAudio),
+            Capability::Keyboard(Keyboard::KeyVideo),
+            Capability::Keyboard(Keyboard::KeyDirectory),
+            Capability::Keyboard(Keyboard::KeyList),
+            Capability::Keyboard(Keyboard::KeyMemo),
+            Capability::Keyboard(Keyboard::KeyCalendar),
+            Capability::Keyboard(Keyboard::KeyRed),
+            Capability::Keyboard(Keyboard::KeyGreen),
+            Capability::Keyboard(Keyboard::KeyYellow),
+            Capability::Keyboard(Keyboard::KeyBlue),
+            Capability::Keyboard(Keyboard::KeyChannelUp),
+            Capability::Keyboard(Keyboard::KeyChannelDown),
+            Capability::Keyboard(Keyboard::KeyFirst),
+            Capability::Keyboard(Keyboard::KeyLast),
+            Capability::Keyboard(Keyboard::KeyAb),
+            Capability::Keyboard(Keyboard::KeyNext),
+            Capability::Keyboard(Keyboard::KeyRestart),
+            Capability::Keyboard(Keyboard::KeySlow),
+            Capability::Keyboard(Keyboard::KeyShuffle),
+            Capability::Keyboard(Keyboard::KeyBreak),
+            Capability::Keyboard(Keyboard::KeyPrevious),
+            Capability::Keyboard(Keyboard::KeyDigits),
+            Capability::Keyboard(Keyboard::KeyTeen),
+            Capability::Keyboard(Keyboard::KeyTwen),
+            Capability::Keyboard(Keyboard::KeyVideophone),
+            Capability::Keyboard(Keyboard::KeyGames),
+            Capability::Keyboard(Keyboard::KeyZoomIn),
+            Capability::Keyboard(Keyboard::KeyZoomOut),
+            Capability::Keyboard(Keyboard::KeyZoomReset),
+            Capability::Keyboard(Keyboard::KeyWordProcessor),
+            Capability::Keyboard(Keyboard::KeyEditor),
+            Capability::Keyboard(Keyboard::KeySpreadsheet),
+            Capability::Keyboard(Keyboard::KeyGraphicsEditor),
+            Capability::Keyboard(Keyboard::KeyPresentation),
+            Capability::Keyboard(Keyboard::KeyDatabase),
+            Capability::Keyboard(Keyboard::KeyNews),
+            Capability::Keyboard(Keyboard::KeyVoicemail),
+            Capability::Keyboard(Keyboard::KeyAddressBook),
+            Capability::Keyboard(Keyboard::KeyMessenger),
+            Capability::Keyboard(Keyboard::KeyDisplayToggle),
+            Capability::Keyboard(Keyboard::KeySpellCheck),
+            Capability::Keyboard(Keyboard::KeyLogoff),
+            Capability::Keyboard(Keyboard::KeyDollar),
+            Capability::Keyboard(Keyboard::KeyEuro),
+            Capability::Keyboard(Keyboard::KeyFrameBack),
+            Capability::Keyboard(Keyboard::KeyFrameForward),
+            Capability::Keyboard(Keyboard::KeyContextMenu),
+            Capability::Keyboard(Keyboard::KeyMediaRepeat),
+            Capability::Keyboard(Keyboard::Key10ChannelsUp),
+            Capability::Keyboard(Keyboard::Key10ChannelsDown),
+            Capability::Keyboard(Keyboard::KeyImages),
+        ]
+    }
+}
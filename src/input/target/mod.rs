@@ -0,0 +1,50 @@
+use tokio::sync::mpsc::Sender;
+
+use super::{capability::Capability, composite_device::Command, event::native::NativeEvent};
+
+pub mod custom;
+pub mod dbus;
+pub mod dualsense;
+pub mod gamepad;
+pub mod identity;
+pub mod keyboard;
+pub mod mouse;
+pub mod network;
+pub mod steam_deck;
+pub mod switch_pro;
+pub mod tablet;
+pub mod touchpad;
+pub mod touchscreen;
+pub mod uhid;
+pub mod xb360;
+pub mod xbox_elite;
+
+/// A [TargetDevice] is any virtual input device that emits input events
+#[derive(Debug)]
+pub enum TargetDeviceType {
+    Null,
+    DBus(dbus::DBusDevice),
+    Keyboard(keyboard::KeyboardDevice),
+    Mouse(mouse::MouseDevice),
+    Tablet(tablet::TabletDevice),
+    Touchpad(touchpad::TouchpadDevice),
+    Touchscreen(touchscreen::TouchscreenDevice),
+    GenericGamepad(gamepad::GenericGamepad),
+    Custom(custom::CustomGamepad),
+    XBox360(xb360::XBox360Controller),
+    XboxElite(xbox_elite::XboxEliteController),
+    SteamDeck(steam_deck::SteamDeckDevice),
+    DualSense(dualsense::DualSenseDevice),
+    SwitchPro(switch_pro::SwitchProDevice),
+    Network(network::NetworkDevice),
+}
+
+/// A [TargetCommand] is a message that can be sent to a [TargetDevice] over
+/// a channel.
+#[derive(Debug, Clone)]
+pub enum TargetCommand {
+    WriteEvent(NativeEvent),
+    SetCompositeDevice(Sender<Command>),
+    GetCapabilities(Sender<Vec<Capability>>),
+    Stop,
+}
@@ -0,0 +1,473 @@
+use std::{collections::HashMap, error::Error, time::Duration};
+
+use evdev::{
+    uinput::{VirtualDevice, VirtualDeviceBuilder},
+    AbsInfo, AbsoluteAxisCode, AttributeSet, EventType, InputEvent, KeyCode, MiscCode,
+    RelativeAxisCode, SynchronizationCode, SynchronizationEvent,
+};
+use tokio::{
+    sync::mpsc::{self, error::TryRecvError},
+    time::Instant,
+};
+use zbus::Connection;
+
+use crate::{
+    dbus::interface::target::mouse::TargetMouseInterface,
+    input::{
+        capability::{Capability, Mouse, MouseButton},
+        composite_device,
+        event::{evdev::EvdevEvent, native::NativeEvent, value::InputValue},
+    },
+};
+
+use super::TargetCommand;
+
+/// Size of the target command channel buffer for processing events
+const BUFFER_SIZE: usize = 2048;
+
+/// Poll rate that the virtual mouse uses to process translated mouse events
+const STATE_POLL_RATE: Duration = Duration::from_millis(16);
+
+/// Number of high resolution wheel units in a single wheel detent
+const WHEEL_HI_RES_DETENT: i32 = 120;
+
+/// Number of wheel detents scrolled when a touch moves across a full touchpad
+const TOUCH_SCROLL_DETENTS: f64 = 10.0;
+
+/// [MouseDevice] is a target virtual mouse that can be used to send mouse input
+#[derive(Debug)]
+pub struct MouseDevice {
+    conn: Connection,
+    dbus_path: Option<String>,
+    tx: mpsc::Sender<TargetCommand>,
+    rx: mpsc::Receiver<TargetCommand>,
+    composite_tx: Option<mpsc::Sender<composite_device::Command>>,
+    vertical_wheel: WheelState,
+    horizontal_wheel: WheelState,
+}
+
+impl MouseDevice {
+    pub fn new(conn: Connection) -> Self {
+        let (tx, rx) = mpsc::channel(BUFFER_SIZE);
+        Self {
+            conn,
+            dbus_path: None,
+            composite_tx: None,
+            tx,
+            rx,
+            vertical_wheel: WheelState::default(),
+            horizontal_wheel: WheelState::default(),
+        }
+    }
+
+    /// Returns the DBus path of this device
+    pub fn get_dbus_path(&self) -> Option<String> {
+        self.dbus_path.clone()
+    }
+
+    /// Returns a transmitter channel that can be used to send events to this device
+    pub fn transmitter(&self) -> mpsc::Sender<TargetCommand> {
+        self.tx.clone()
+    }
+
+    /// Configures the device to send output events to the given composite device
+    /// channel.
+    pub fn set_composite_device(&mut self, tx: mpsc::Sender<composite_device::Command>) {
+        self.composite_tx = Some(tx);
+    }
+
+    /// Creates a new instance of the device interface on DBus.
+    pub async fn listen_on_dbus(&mut self, path: String) -> Result<(), Box<dyn Error>> {
+        let conn = self.conn.clone();
+        self.dbus_path = Some(path.clone());
+        tokio::spawn(async move {
+            let iface = TargetMouseInterface::new();
+            if let Err(e) = conn.object_server().at(path, iface).await {
+                log::error!("Failed to setup DBus interface for device: {:?}", e);
+            }
+        });
+        Ok(())
+    }
+
+    /// Creates and runs the target device
+    pub async fn run(&mut self) -> Result<(), Box<dyn Error>> {
+        log::debug!("Creating virtual mouse");
+        let mut device = self.create_virtual_device()?;
+        let axis_map = HashMap::new();
+
+        // Create a thread with the virtual mouse state
+        let tx = self.tx.clone();
+        let mut state = MouseMotionState::new(tx);
+        let state_tx = state.transmitter();
+        tokio::spawn(async move {
+            // Create the state
+            let mut interval = tokio::time::interval(STATE_POLL_RATE);
+            let mut current_time = Instant::now();
+            loop {
+                // Calculate the delta between each tick
+                let last_time = current_time;
+                current_time = Instant::now();
+                let delta = current_time - last_time;
+
+                // Process the current mouse state
+                if let Err(e) = state.process(delta).await {
+                    log::debug!("Channel disconnected for processing mouse state: {:?}", e);
+                    break;
+                }
+                interval.tick().await;
+            }
+        });
+
+        // Listen for send events
+        log::debug!("Started listening for events to send");
+        while let Some(command) = self.rx.recv().await {
+            match command {
+                TargetCommand::SetCompositeDevice(tx) => {
+                    self.set_composite_device(tx);
+                }
+                TargetCommand::WriteEvent(event) => {
+                    log::trace!("Got event to emit: {:?}", event);
+
+                    // Check if this event needs to be processed by the
+                    // mouse state.
+                    if event.is_translated()
+                        && matches!(event.as_capability(), Capability::Mouse(Mouse::Motion))
+                    {
+                        log::trace!("Got translated mouse motion event: {:?}", event);
+                        if let Some(tx) = state_tx.as_ref() {
+                            if let Err(e) = tx.send(event).await {
+                                log::warn!(
+                                    "Failed to send translated event to mouse state: {:?}",
+                                    e
+                                );
+                                continue;
+                            }
+                        }
+                        continue;
+                    }
+
+                    // Translate and emit the event(s)
+                    let evdev_events = match event.as_capability() {
+                        Capability::Mouse(Mouse::Button(
+                            button @ (MouseButton::WheelUp
+                            | MouseButton::WheelDown
+                            | MouseButton::WheelLeft
+                            | MouseButton::WheelRight),
+                        )) => self.translate_wheel_event(button, event.get_value()),
+                        _ => self.translate_event(event, axis_map.clone()),
+                    };
+                    if evdev_events.is_empty() {
+                        continue;
+                    }
+                    device.emit(evdev_events.as_slice())?;
+                    device.emit(&[SynchronizationEvent::new(
+                        SynchronizationCode::SYN_REPORT,
+                        0,
+                    )
+                    .into()])?;
+                }
+                TargetCommand::GetCapabilities(tx) => {
+                    let caps = self.get_capabilities();
+                    if let Err(e) = tx.send(caps).await {
+                        log::error!("Failed to send target capabilities: {e:?}");
+                    }
+                }
+                TargetCommand::Stop => break,
+            };
+        }
+
+        log::debug!("Stopping device");
+
+        // Remove the DBus interface
+        if let Some(path) = self.dbus_path.clone() {
+            log::debug!("Removing DBus interface");
+            self.conn
+                .object_server()
+                .remove::<TargetMouseInterface, String>(path)
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Translate the given native event into an evdev event
+    fn translate_event(
+        &self,
+        event: NativeEvent,
+        axis_map: HashMap<AbsoluteAxisCode, AbsInfo>,
+    ) -> Vec<InputEvent> {
+        EvdevEvent::from_native_event(event, axis_map)
+            .into_iter()
+            .map(|event| event.as_input_event())
+            .collect()
+    }
+
+    /// Translate the given wheel event into low and high resolution wheel
+    /// events. Button presses scroll a full detent, float values scroll the
+    /// given fraction of a detent and touch values scroll based on how far the
+    /// touch moved, so touchpads can scroll smoothly.
+    fn translate_wheel_event(&mut self, button: MouseButton, value: InputValue) -> Vec<InputEvent> {
+        let is_vertical = matches!(button, MouseButton::WheelUp | MouseButton::WheelDown);
+        let (state, code, hi_res_code) = if is_vertical {
+            (
+                &mut self.vertical_wheel,
+                RelativeAxisCode::REL_WHEEL,
+                RelativeAxisCode::REL_WHEEL_HI_RES,
+            )
+        } else {
+            (
+                &mut self.horizontal_wheel,
+                RelativeAxisCode::REL_HWHEEL,
+                RelativeAxisCode::REL_HWHEEL_HI_RES,
+            )
+        };
+        let direction = match button {
+            MouseButton::WheelDown | MouseButton::WheelLeft => -1.0,
+            _ => 1.0,
+        };
+
+        let detents = match value {
+            InputValue::Bool(pressed) => {
+                if !pressed {
+                    return vec![];
+                }
+                direction
+            }
+            InputValue::Float(value) => direction * value,
+            // Moving a touch up or right scrolls up or right, regardless of
+            // which wheel direction the touchpad is mapped to.
+            InputValue::Touch {
+                index,
+                is_touching,
+                x,
+                y,
+                pressure: _,
+            } => {
+                let (position, direction) = if is_vertical { (y, -1.0) } else { (x, 1.0) };
+                let distance = state.touch_distance(index, is_touching, position);
+                direction * distance * TOUCH_SCROLL_DETENTS
+            }
+            _ => return vec![],
+        };
+
+        let hi_res = (detents * WHEEL_HI_RES_DETENT as f64).round() as i32;
+        if hi_res == 0 {
+            return vec![];
+        }
+        let mut events = vec![InputEvent::new(
+            EventType::RELATIVE.0,
+            hi_res_code.0,
+            hi_res,
+        )];
+        let detents = state.scroll(hi_res);
+        if detents != 0 {
+            events.push(InputEvent::new(EventType::RELATIVE.0, code.0, detents));
+        }
+
+        events
+    }
+
+    /// Create the virtual device to emulate
+    fn create_virtual_device(&self) -> Result<VirtualDevice, Box<dyn Error>> {
+        let mut buttons = AttributeSet::<KeyCode>::new();
+        buttons.insert(KeyCode::BTN_LEFT);
+        buttons.insert(KeyCode::BTN_RIGHT);
+        buttons.insert(KeyCode::BTN_MIDDLE);
+        buttons.insert(KeyCode::BTN_SIDE);
+        buttons.insert(KeyCode::BTN_EXTRA);
+        let device = VirtualDeviceBuilder::new()?
+            .name("InputPlumber Mouse")
+            .with_keys(&buttons)?
+            .with_relative_axes(&AttributeSet::from_iter([
+                RelativeAxisCode::REL_X,
+                RelativeAxisCode::REL_Y,
+                RelativeAxisCode::REL_WHEEL,
+                RelativeAxisCode::REL_HWHEEL,
+                RelativeAxisCode::REL_WHEEL_HI_RES,
+                RelativeAxisCode::REL_HWHEEL_HI_RES,
+            ]))?
+            .with_msc(&AttributeSet::from_iter([MiscCode::MSC_TIMESTAMP]))?
+            .build()?;
+
+        Ok(device)
+    }
+
+    fn get_capabilities(&self) -> Vec<Capability> {
+        vec![
+            Capability::Mouse(Mouse::Button(MouseButton::Left)),
+            Capability::Mouse(Mouse::Button(MouseButton::Right)),
+            Capability::Mouse(Mouse::Button(MouseButton::Middle)),
+            Capability::Mouse(Mouse::Button(MouseButton::Side)),
+            Capability::Mouse(Mouse::Button(MouseButton::Extra)),
+            Capability::Mouse(Mouse::Button(MouseButton::WheelUp)),
+            Capability::Mouse(Mouse::Button(MouseButton::WheelDown)),
+            Capability::Mouse(Mouse::Button(MouseButton::WheelLeft)),
+            Capability::Mouse(Mouse::Button(MouseButton::WheelRight)),
+            Capability::Mouse(Mouse::Motion),
+        ]
+    }
+}
+
+/// The [WheelState] keeps track of partial wheel detents and of the touch
+/// contacts used to scroll a single wheel axis.
+#[derive(Debug, Default)]
+struct WheelState {
+    /// High resolution units scrolled since the last full detent
+    remainder: i32,
+    /// Last position of each touch contact that is scrolling the wheel
+    touches: HashMap<u8, f64>,
+}
+
+impl WheelState {
+    /// Add the given high resolution units to the scrolled amount and return
+    /// the number of full detents that were completed.
+    fn scroll(&mut self, hi_res: i32) -> i32 {
+        self.remainder += hi_res;
+        let detents = self.remainder / WHEEL_HI_RES_DETENT;
+        self.remainder -= detents * WHEEL_HI_RES_DETENT;
+        detents
+    }
+
+    /// Returns the normalized distance the given touch contact moved since
+    /// its last position. A new contact starts without any movement.
+    fn touch_distance(&mut self, index: u8, is_touching: bool, position: Option<f64>) -> f64 {
+        if !is_touching {
+            self.touches.remove(&index);
+            if self.touches.is_empty() {
+                self.remainder = 0;
+            }
+            return 0.0;
+        }
+        let Some(position) = position else {
+            return 0.0;
+        };
+        match self.touches.insert(index, position) {
+            Some(last_position) => position - last_position,
+            None => 0.0,
+        }
+    }
+}
+
+/// The [MouseMotionState] keeps track of the mouse velocity from translated
+/// input events (like a joystick), and sends mouse motion events to the
+/// [MouseDevice] based on the current velocity.
+#[derive(Debug)]
+pub struct MouseMotionState {
+    rx: mpsc::Receiver<NativeEvent>,
+    tx: Option<mpsc::Sender<NativeEvent>>,
+    device_tx: mpsc::Sender<TargetCommand>,
+    mouse_remainder: (f64, f64),
+    mouse_velocity: (f64, f64),
+}
+
+impl MouseMotionState {
+    /// Create a new mouse motion state to keep track of mouse velocity.
+    pub fn new(device_tx: mpsc::Sender<TargetCommand>) -> Self {
+        let (tx, rx) = mpsc::channel(BUFFER_SIZE);
+        Self {
+            rx,
+            tx: Some(tx),
+            device_tx,
+            mouse_remainder: (0.0, 0.0),
+            mouse_velocity: (0.0, 0.0),
+        }
+    }
+
+    /// Returns a transmitter that can be used to send events to process.
+    pub fn transmitter(&mut self) -> Option<mpsc::Sender<NativeEvent>> {
+        let tx = self.tx.clone();
+        self.tx = None;
+        tx
+    }
+
+    /// Move the mouse based on the given input event translation
+    pub async fn process(&mut self, delta: Duration) -> Result<(), TryRecvError> {
+        // Process any events that come over the channel from the device
+        loop {
+            match self.rx.try_recv() {
+                Ok(event) => self.update_state(event),
+                Err(err) => match err {
+                    TryRecvError::Empty => break,
+                    TryRecvError::Disconnected => return Err(err),
+                },
+            }
+        }
+
+        // Calculate how much the mouse should move based on the current mouse velocity
+        let mut pixels_to_move = (0.0, 0.0);
+        pixels_to_move.0 = delta.as_secs_f64() * self.mouse_velocity.0;
+        pixels_to_move.1 = delta.as_secs_f64() * self.mouse_velocity.1;
+
+        // Get the fractional value of the position so we can accumulate them
+        // in between invocations
+        let mut x = pixels_to_move.0 as i32; // E.g. 3.14 -> 3
+        let mut y = pixels_to_move.1 as i32;
+        self.mouse_remainder.0 += pixels_to_move.0 - x as f64;
+        self.mouse_remainder.1 += pixels_to_move.1 - y as f64;
+
+        // Keep track of relative mouse movements to keep around fractional values
+        if self.mouse_remainder.0 >= 1.0 {
+            x += 1;
+            self.mouse_remainder.0 -= 1.0;
+        }
+        if self.mouse_remainder.0 <= -1.0 {
+            x -= 1;
+            self.mouse_remainder.0 += 1.0;
+        }
+        if self.mouse_remainder.1 >= 1.0 {
+            y += 1;
+            self.mouse_remainder.1 -= 1.0;
+        }
+        if self.mouse_remainder.1 <= -1.0 {
+            y -= 1;
+            self.mouse_remainder.1 += 1.0;
+        }
+
+        // Send events to the device if the mouse state has changed
+        if x != 0 {
+            let value = InputValue::Vector2 {
+                x: Some(x as f64),
+                y: None,
+            };
+            let event = NativeEvent::new(Capability::Mouse(Mouse::Motion), value);
+            if let Err(e) = self.device_tx.send(TargetCommand::WriteEvent(event)).await {
+                log::warn!("Failed to send write event: {:?}", e);
+                return Err(TryRecvError::Disconnected);
+            }
+        }
+        if y != 0 {
+            let value = InputValue::Vector2 {
+                x: None,
+                y: Some(y as f64),
+            };
+            let event = NativeEvent::new(Capability::Mouse(Mouse::Motion), value);
+            if let Err(e) = self.device_tx.send(TargetCommand::WriteEvent(event)).await {
+                log::warn!("Failed to send write event: {:?}", e);
+                return Err(TryRecvError::Disconnected);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Processes the given mouse motion or button input event.
+    fn update_state(&mut self, event: NativeEvent) {
+        // Get the mouse position from the event value
+        let value = event.get_value();
+        let (x, y) = match value {
+            InputValue::Vector2 { x, y } => (x, y),
+            InputValue::Vector3 { x, y, z: _ } => (x, y),
+            _ => (None, None),
+        };
+
+        // Update the mouse velocity
+        if let Some(x) = x {
+            self.mouse_velocity.0 = x;
+            log::trace!("Updating mouse state: {:?}", self.mouse_velocity);
+        }
+        if let Some(y) = y {
+            self.mouse_velocity.1 = y;
+            log::trace!("Updating mouse state: {:?}", self.mouse_velocity);
+        }
+    }
+}
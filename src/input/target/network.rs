@@ -0,0 +1,167 @@
+use std::{error::Error, time::Duration};
+
+use tokio::{net::UdpSocket, sync::mpsc, time};
+
+use crate::input::{
+    capability::{Capability, Gamepad, GamepadAxis, GamepadButton, GamepadTrigger},
+    composite_device,
+    event::network::{self, NetworkEvent},
+};
+
+use super::TargetCommand;
+
+/// Size of the channel buffer for events
+const BUFFER_SIZE: usize = 2048;
+/// Interval to send keep alive packets when no events are being sent
+const KEEP_ALIVE_INTERVAL: Duration = Duration::from_secs(1);
+/// Environment variable used to configure the remote address to send events to
+pub const NETWORK_TARGET_ENV: &str = "INPUTPLUMBER_NETWORK_TARGET";
+
+/// The [NetworkDevice] is a virtual target device that forwards input events
+/// over UDP to another InputPlumber instance, where they are emitted by a
+/// virtual gamepad. The remote address is read from the
+/// `INPUTPLUMBER_NETWORK_TARGET` environment variable (e.g. "192.168.1.10",
+/// "192.168.1.10:31415" or "[fd00::10]:31415"), and packets are signed with
+/// the pre-shared key in the `INPUTPLUMBER_NETWORK_KEY` environment variable.
+#[derive(Debug)]
+pub struct NetworkDevice {
+    address: Option<String>,
+    key: Option<Vec<u8>>,
+    /// Sequence number of the last packet sent. Sequence numbers are never
+    /// lower than the current time in microseconds, so they keep increasing
+    /// across restarts and the remote side can reject stale packets.
+    sequence: u64,
+    tx: mpsc::Sender<TargetCommand>,
+    rx: mpsc::Receiver<TargetCommand>,
+    composite_tx: Option<mpsc::Sender<composite_device::Command>>,
+}
+
+impl NetworkDevice {
+    // Create a new [NetworkDevice] instance.
+    pub fn new() -> Self {
+        let (tx, rx) = mpsc::channel(BUFFER_SIZE);
+        let address = std::env::var(NETWORK_TARGET_ENV).ok();
+        Self {
+            address,
+            key: network::key_from_env(),
+            sequence: 0,
+            composite_tx: None,
+            tx,
+            rx,
+        }
+    }
+
+    /// Returns a transmitter channel that can be used to send events to this device
+    pub fn transmitter(&self) -> mpsc::Sender<TargetCommand> {
+        self.tx.clone()
+    }
+
+    /// Configures the device to send output events to the given composite device
+    /// channel.
+    pub fn set_composite_device(&mut self, tx: mpsc::Sender<composite_device::Command>) {
+        self.composite_tx = Some(tx);
+    }
+
+    /// Creates and runs the target device
+    pub async fn run(&mut self) -> Result<(), Box<dyn Error>> {
+        let Some(address) = self.address.clone() else {
+            return Err(format!("No remote address set in {NETWORK_TARGET_ENV}").into());
+        };
+        if self.key.is_none() {
+            return Err(format!("No pre-shared key set in {}", network::NETWORK_KEY_ENV).into());
+        }
+        let address = network::resolve_address(&address).await?;
+        log::debug!("Creating network device to send events to {address}");
+        let local_address = if address.is_ipv6() {
+            "[::]:0"
+        } else {
+            "0.0.0.0:0"
+        };
+        let socket = UdpSocket::bind(local_address).await?;
+        socket.connect(address).await?;
+
+        // Listen for send events
+        log::debug!("Started listening for events to send");
+        let mut interval = time::interval(KEEP_ALIVE_INTERVAL);
+        loop {
+            let command = tokio::select! {
+                command = self.rx.recv() => command,
+                _ = interval.tick() => {
+                    self.send(&socket, NetworkEvent::KeepAlive).await;
+                    continue;
+                }
+            };
+            let Some(command) = command else {
+                break;
+            };
+            match command {
+                TargetCommand::SetCompositeDevice(tx) => {
+                    self.set_composite_device(tx);
+                }
+                TargetCommand::WriteEvent(event) => {
+                    self.send(&socket, NetworkEvent::Event(event)).await;
+                    interval.reset();
+                }
+                TargetCommand::GetCapabilities(tx) => {
+                    let caps = self.get_capabilities();
+                    if let Err(e) = tx.send(caps).await {
+                        log::error!("Failed to send target capabilities: {e:?}");
+                    }
+                }
+                TargetCommand::Stop => break,
+            };
+        }
+        log::debug!("Stopping device");
+
+        // Let the remote side know it can remove its target device
+        self.send(&socket, NetworkEvent::Disconnect).await;
+
+        Ok(())
+    }
+
+    /// Send the given event to the remote InputPlumber instance. Failures are
+    /// only logged since the remote side may not be listening yet.
+    async fn send(&mut self, socket: &UdpSocket, event: NetworkEvent) {
+        let Some(key) = self.key.as_ref() else {
+            return;
+        };
+        self.sequence = (self.sequence + 1).max(network::timestamp_micros());
+        let packet = event.to_bytes(self.sequence, key);
+        if let Err(e) = socket.send(&packet).await {
+            log::trace!("Failed to send network event: {e:?}");
+        }
+    }
+
+    /// Returns capabilities of the target device
+    fn get_capabilities(&self) -> Vec<Capability> {
+        vec![
+            Capability::Gamepad(Gamepad::Button(GamepadButton::South)),
+            Capability::Gamepad(Gamepad::Button(GamepadButton::North)),
+            Capability::Gamepad(Gamepad::Button(GamepadButton::East)),
+            Capability::Gamepad(Gamepad::Button(GamepadButton::West)),
+            Capability::Gamepad(Gamepad::Button(GamepadButton::Start)),
+            Capability::Gamepad(Gamepad::Button(GamepadButton::Select)),
+            Capability::Gamepad(Gamepad::Button(GamepadButton::Guide)),
+            Capability::Gamepad(Gamepad::Button(GamepadButton::DPadDown)),
+            Capability::Gamepad(Gamepad::Button(GamepadButton::DPadUp)),
+            Capability::Gamepad(Gamepad::Button(GamepadButton::DPadLeft)),
+            Capability::Gamepad(Gamepad::Button(GamepadButton::DPadRight)),
+            Capability::Gamepad(Gamepad::Button(GamepadButton::LeftBumper)),
+            Capability::Gamepad(Gamepad::Button(GamepadButton::LeftTrigger)),
+            Capability::Gamepad(Gamepad::Button(GamepadButton::LeftStick)),
+            Capability::Gamepad(Gamepad::Button(GamepadButton::RightBumper)),
+            Capability::Gamepad(Gamepad::Button(GamepadButton::RightTrigger)),
+            Capability::Gamepad(Gamepad::Button(GamepadButton::RightStick)),
+            Capability::Gamepad(Gamepad::Axis(GamepadAxis::LeftStick)),
+            Capability::Gamepad(Gamepad::Axis(GamepadAxis::RightStick)),
+            Capability::Gamepad(Gamepad::Trigger(GamepadTrigger::LeftTrigger)),
+            Capability::Gamepad(Gamepad::Trigger(GamepadTrigger::RightTrigger)),
+        ]
+    }
+}
+
+impl Default for NetworkDevice {
+    fn default() -> Self {
+        Self::new()
+    }
+}
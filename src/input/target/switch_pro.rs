@@ -0,0 +1,440 @@
+//! Emulates a Nintendo Switch Pro Controller as a target input device.
+//! The emulated controller answers the USB handshake and the subcommands used
+//! to initialize it, so drivers like hid-nintendo and emulators reading the
+//! hidraw device directly get buttons, sticks and native motion data.
+use std::{error::Error, fs::File, time::Duration};
+
+use packed_struct::prelude::*;
+use tokio::sync::mpsc::{self, error::TryRecvError};
+use uhid_virt::{Bus, CreateParams, UHIDDevice};
+use zbus::Connection;
+
+use crate::{
+    dbus::interface::target::gamepad::TargetGamepadInterface,
+    drivers::{
+        dualsense::hid_report::SetStatePackedOutputData,
+        switch_pro::{
+            hid_report::{
+                pack_stick, unpack_stick, PackedInputDataReport, INPUT_FULL, INPUT_REPORT_SIZE,
+                NAME, PID, STICK_CENTER, STICK_RANGE, VID,
+            },
+            imu::ImuState,
+            protocol::{self, Protocol},
+            report_descriptor::PRO_CONTROLLER_DESCRIPTOR,
+            rumble::{HdRumble, RumbleMapping},
+        },
+    },
+    input::{
+        capability::{Capability, Gamepad, GamepadAxis, GamepadButton, GamepadTrigger},
+        composite_device::Command,
+        event::{native::NativeEvent, value::InputValue},
+        output_event::{LedEvent, OutputEvent as InputOutputEvent},
+    },
+};
+
+use super::{
+    dualsense::mac_addr_from_serial,
+    uhid::{self, FeatureReports},
+    TargetCommand,
+};
+
+const POLL_INTERVAL_MS: u64 = 4;
+const BUFFER_SIZE: usize = 2048;
+/// Size of the state data in DualSense output reports used to forward rumble
+const OUTPUT_STATE_SIZE: usize = 47;
+/// Normalized trigger value above which the digital ZL/ZR buttons are pressed
+const TRIGGER_THRESHOLD: f64 = 0.5;
+/// Hardware version of the emulated controller
+const VERSION: u32 = 0x0200;
+
+/// The [SwitchProDevice] is a target input device implementation that emulates
+/// a Nintendo Switch Pro Controller connected over USB using uhid.
+#[derive(Debug)]
+pub struct SwitchProDevice {
+    conn: Connection,
+    dbus_path: Option<String>,
+    tx: mpsc::Sender<TargetCommand>,
+    rx: mpsc::Receiver<TargetCommand>,
+    state: PackedInputDataReport,
+    imu: ImuState,
+    protocol: Protocol,
+    composite_tx: Option<mpsc::Sender<Command>>,
+    /// MAC address of the controller, in the byte order used by reports
+    mac_addr: [u8; 6],
+    /// Last rumble intensity forwarded to source devices
+    rumble: (u8, u8),
+    /// Whether the left and right triggers are pressed as buttons or axes
+    triggers: [(bool, bool); 2],
+}
+
+impl SwitchProDevice {
+    pub fn new(conn: Connection) -> Self {
+        let (tx, rx) = mpsc::channel(BUFFER_SIZE);
+        let mac_addr = [0x98, 0xb6, 0xe9, 0x3a, 0x47, 0xe8];
+        Self {
+            conn,
+            dbus_path: None,
+            tx,
+            rx,
+            state: PackedInputDataReport::new(),
+            imu: ImuState::new(),
+            protocol: Protocol::new(mac_addr),
+            composite_tx: None,
+            mac_addr,
+            rumble: (0, 0),
+            triggers: [(false, false); 2],
+        }
+    }
+
+    /// Set the MAC address of the emulated controller based on the given source
+    /// device serial number. Serials that are MAC addresses are used as-is,
+    /// otherwise a stable address is derived from the serial.
+    pub fn set_serial(&mut self, serial: &str) {
+        let mut mac_addr = mac_addr_from_serial(serial);
+        mac_addr.reverse();
+        self.mac_addr = mac_addr;
+        self.protocol = Protocol::new(mac_addr);
+    }
+
+    /// Returns a transmitter channel that can be used to send events to this device
+    pub fn transmitter(&self) -> mpsc::Sender<TargetCommand> {
+        self.tx.clone()
+    }
+
+    /// Configures the device to send output events to the given composite device
+    /// channel.
+    pub fn set_composite_device(&mut self, tx: mpsc::Sender<Command>) {
+        self.composite_tx = Some(tx);
+    }
+
+    /// Creates a new instance of the dbus device interface on DBus.
+    pub async fn listen_on_dbus(&mut self, path: String) -> Result<(), Box<dyn Error>> {
+        let conn = self.conn.clone();
+        self.dbus_path = Some(path.clone());
+        tokio::spawn(async move {
+            let iface = TargetGamepadInterface::new("Switch Pro Controller".into());
+            if let Err(e) = conn.object_server().at(path, iface).await {
+                log::error!("Failed to setup DBus interface for Gamepad device: {:?}", e);
+            }
+        });
+        Ok(())
+    }
+
+    /// Creates and runs the target device
+    pub async fn run(&mut self) -> Result<(), Box<dyn Error>> {
+        log::debug!("Creating virtual Switch Pro controller");
+        let mut device = self.create_virtual_device()?;
+
+        // Start the main run loop
+        log::debug!("Starting run loop");
+        let duration = Duration::from_millis(POLL_INTERVAL_MS);
+        let mut interval = tokio::time::interval(duration);
+        loop {
+            // Sleep for the given polling interval
+            interval.tick().await;
+
+            // Receive commands/events and update local state
+            if let Err(e) = self.receive_commands().await {
+                log::debug!("Error receiving commands: {:?}", e);
+                break;
+            }
+
+            // Poll the HIDRaw device
+            if let Err(e) = self.poll(&mut device).await {
+                log::debug!("Error polling UHID device: {:?}", e);
+                break;
+            }
+
+            // Write the state to the device
+            if let Err(e) = self.write_state(&mut device) {
+                log::debug!("Error writing state to device: {:?}", e);
+                break;
+            }
+        }
+
+        log::debug!("Stopped listening for events");
+        device.destroy()?;
+
+        // Remove the DBus interface
+        if let Some(path) = self.dbus_path.clone() {
+            log::debug!("Removing DBus interface for {path}");
+            self.conn
+                .object_server()
+                .remove::<TargetGamepadInterface, String>(path)
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Create the virtual device to emulate
+    fn create_virtual_device(&self) -> Result<UHIDDevice<File>, Box<dyn Error>> {
+        let device = UHIDDevice::create(CreateParams {
+            name: String::from(NAME),
+            phys: String::from(""),
+            uniq: self
+                .mac_addr
+                .iter()
+                .map(|octet| format!("{octet:02x}"))
+                .collect::<Vec<String>>()
+                .join(":"),
+            bus: Bus::USB,
+            vendor: VID as u32,
+            product: PID as u32,
+            version: VERSION,
+            country: 0,
+            rd_data: PRO_CONTROLLER_DESCRIPTOR.to_vec(),
+        })?;
+
+        Ok(device)
+    }
+
+    /// Read commands and events sent to this device
+    async fn receive_commands(&mut self) -> Result<(), Box<dyn Error>> {
+        // Read commands sent to this device from the channel until it is
+        // empty.
+        loop {
+            match self.rx.try_recv() {
+                Ok(cmd) => match cmd {
+                    TargetCommand::SetCompositeDevice(tx) => {
+                        log::trace!("Recieved command to set composite device");
+                        self.set_composite_device(tx.clone());
+                    }
+                    TargetCommand::WriteEvent(event) => {
+                        log::trace!("Recieved event to write: {:?}", event);
+                        self.update_state(event);
+                    }
+                    TargetCommand::GetCapabilities(tx) => {
+                        let caps = self.get_capabilities();
+                        if let Err(e) = tx.send(caps).await {
+                            log::error!("Failed to send target capabilities: {e:?}");
+                        }
+                    }
+                    TargetCommand::Stop => return Err("Device stopped".into()),
+                },
+                Err(e) => match e {
+                    TryRecvError::Empty => break,
+                    TryRecvError::Disconnected => {
+                        return Err("Receive channel disconnected".into());
+                    }
+                },
+            };
+        }
+
+        Ok(())
+    }
+
+    /// Handle reading from the device and processing output reports and
+    /// requests from the kernel.
+    async fn poll(&mut self, device: &mut UHIDDevice<File>) -> Result<(), Box<dyn Error>> {
+        let Some(data) = uhid::read_output(device, self)? else {
+            return Ok(());
+        };
+        if let Err(e) = self.handle_output(device, data).await {
+            let err = format!("Failed process output event: {:?}", e);
+            return Err(err.into());
+        }
+
+        Ok(())
+    }
+
+    /// Handle output reports from the HIDRAW device. Commands
+    /// are answered directly, while rumble and player lights are forwarded to
+    /// source devices.
+    async fn handle_output(
+        &mut self,
+        device: &mut UHIDDevice<File>,
+        data: Vec<u8>,
+    ) -> Result<(), Box<dyn Error>> {
+        // Reply to USB commands and subcommands
+        if let Some(reply) = self.protocol.handle_output(&data, &self.state) {
+            self.state.timer = self.state.timer.wrapping_add(1);
+            device.write(&reply)?;
+        }
+
+        let Some(tx) = self.composite_tx.as_ref() else {
+            return Ok(());
+        };
+
+        // Forward player lights
+        if let Some(lights) = protocol::player_lights(&data) {
+            log::debug!("Setting player lights: {lights:#06b}");
+            let event = InputOutputEvent::Led(LedEvent::Player(lights));
+            tx.send(Command::ProcessOutputEvent(event)).await?;
+        }
+
+        // Forward rumble as a DualSense rumble report, which all sources with
+        // force feedback support
+        let Some((left, right)) = HdRumble::from_output_report(&data) else {
+            return Ok(());
+        };
+        let rumble = RumbleMapping::default().translate(&left, &right);
+        if rumble == self.rumble {
+            return Ok(());
+        }
+        self.rumble = rumble;
+        let (strong, weak) = rumble;
+        let mut report = SetStatePackedOutputData::unpack(&[0; OUTPUT_STATE_SIZE])?;
+        report.use_rumble_not_haptics = true;
+        report.rumble_emulation_left = strong;
+        report.rumble_emulation_right = weak;
+        let event = InputOutputEvent::DualSense(report);
+        tx.send(Command::ProcessOutputEvent(event)).await?;
+
+        Ok(())
+    }
+
+    /// Write the current device state to the device once the host has
+    /// enabled full input reports.
+    fn write_state(&mut self, device: &mut UHIDDevice<File>) -> Result<(), Box<dyn Error>> {
+        if !self.protocol.full_reports() {
+            return Ok(());
+        }
+
+        self.state.report_id = INPUT_FULL;
+        self.state.timer = self.state.timer.wrapping_add(1);
+        let mut data = vec![0; INPUT_REPORT_SIZE];
+        let header = self.state.pack()?;
+        data[..header.len()].copy_from_slice(&header);
+        if self.protocol.imu_enabled() {
+            self.imu.write_report(&mut data);
+        }
+
+        // Write the state to the virtual HID
+        if let Err(e) = device.write(&data) {
+            let err = format!("Failed to write input data report: {:?}", e);
+            return Err(err.into());
+        }
+
+        Ok(())
+    }
+
+    /// Update the internal controller state when events are emitted. Buttons
+    /// use the Nintendo layout, where the south button is B.
+    fn update_state(&mut self, event: NativeEvent) {
+        let value = event.get_value();
+        let capability = event.as_capability();
+        let Capability::Gamepad(gamepad) = capability else {
+            return;
+        };
+        match gamepad {
+            Gamepad::Button(btn) => {
+                let pressed = event.pressed();
+                match btn {
+                    GamepadButton::South => self.state.b = pressed,
+                    GamepadButton::East => self.state.a = pressed,
+                    GamepadButton::North => self.state.x = pressed,
+                    GamepadButton::West => self.state.y = pressed,
+                    GamepadButton::Start => self.state.plus = pressed,
+                    GamepadButton::Select => self.state.minus = pressed,
+                    GamepadButton::Guide => self.state.home = pressed,
+                    GamepadButton::QuickAccess => self.state.capture = pressed,
+                    GamepadButton::DPadUp => self.state.up = pressed,
+                    GamepadButton::DPadDown => self.state.down = pressed,
+                    GamepadButton::DPadLeft => self.state.left = pressed,
+                    GamepadButton::DPadRight => self.state.right = pressed,
+                    GamepadButton::LeftBumper => self.state.l = pressed,
+                    GamepadButton::RightBumper => self.state.r = pressed,
+                    GamepadButton::LeftStick => self.state.l_stick = pressed,
+                    GamepadButton::RightStick => self.state.r_stick = pressed,
+                    GamepadButton::LeftTrigger => {
+                        self.triggers[0].0 = pressed;
+                        self.state.zl = self.triggers[0].0 || self.triggers[0].1;
+                    }
+                    GamepadButton::RightTrigger => {
+                        self.triggers[1].0 = pressed;
+                        self.state.zr = self.triggers[1].0 || self.triggers[1].1;
+                    }
+                    _ => (),
+                }
+            }
+            Gamepad::Axis(axis) => {
+                let InputValue::Vector2 { x, y } = value else {
+                    return;
+                };
+                let stick = match axis {
+                    GamepadAxis::LeftStick => &mut self.state.left_stick,
+                    GamepadAxis::RightStick => &mut self.state.right_stick,
+                    _ => return,
+                };
+                let (mut stick_x, mut stick_y) = unpack_stick(*stick);
+                if let Some(x) = x {
+                    stick_x = denormalize_stick_value(x);
+                }
+                // Switch sticks report up as higher values
+                if let Some(y) = y {
+                    stick_y = denormalize_stick_value(-y);
+                }
+                *stick = pack_stick(stick_x, stick_y);
+            }
+            Gamepad::Trigger(trigger) => {
+                let InputValue::Float(normal_value) = value else {
+                    return;
+                };
+                let pressed = normal_value > TRIGGER_THRESHOLD;
+                match trigger {
+                    GamepadTrigger::LeftTrigger => {
+                        self.triggers[0].1 = pressed;
+                        self.state.zl = self.triggers[0].0 || self.triggers[0].1;
+                    }
+                    GamepadTrigger::RightTrigger => {
+                        self.triggers[1].1 = pressed;
+                        self.state.zr = self.triggers[1].0 || self.triggers[1].1;
+                    }
+                    _ => (),
+                }
+            }
+            Gamepad::Accelerometer => {
+                if let InputValue::Vector3 { x, y, z } = value {
+                    self.imu.set_accel(x, y, z);
+                }
+            }
+            Gamepad::Gyro => {
+                if let InputValue::Vector3 { x, y, z } = value {
+                    self.imu.set_gyro(x, y, z);
+                }
+            }
+        }
+    }
+
+    /// Returns capabilities of the target device
+    fn get_capabilities(&self) -> Vec<Capability> {
+        vec![
+            Capability::Gamepad(Gamepad::Button(GamepadButton::South)),
+            Capability::Gamepad(Gamepad::Button(GamepadButton::North)),
+            Capability::Gamepad(Gamepad::Button(GamepadButton::East)),
+            Capability::Gamepad(Gamepad::Button(GamepadButton::West)),
+            Capability::Gamepad(Gamepad::Button(GamepadButton::Start)),
+            Capability::Gamepad(Gamepad::Button(GamepadButton::Select)),
+            Capability::Gamepad(Gamepad::Button(GamepadButton::Guide)),
+            Capability::Gamepad(Gamepad::Button(GamepadButton::QuickAccess)),
+            Capability::Gamepad(Gamepad::Button(GamepadButton::DPadDown)),
+            Capability::Gamepad(Gamepad::Button(GamepadButton::DPadUp)),
+            Capability::Gamepad(Gamepad::Button(GamepadButton::DPadLeft)),
+            Capability::Gamepad(Gamepad::Button(GamepadButton::DPadRight)),
+            Capability::Gamepad(Gamepad::Button(GamepadButton::LeftBumper)),
+            Capability::Gamepad(Gamepad::Button(GamepadButton::LeftTrigger)),
+            Capability::Gamepad(Gamepad::Button(GamepadButton::LeftStick)),
+            Capability::Gamepad(Gamepad::Button(GamepadButton::RightBumper)),
+            Capability::Gamepad(Gamepad::Button(GamepadButton::RightTrigger)),
+            Capability::Gamepad(Gamepad::Button(GamepadButton::RightStick)),
+            Capability::Gamepad(Gamepad::Axis(GamepadAxis::LeftStick)),
+            Capability::Gamepad(Gamepad::Axis(GamepadAxis::RightStick)),
+            Capability::Gamepad(Gamepad::Trigger(GamepadTrigger::LeftTrigger)),
+            Capability::Gamepad(Gamepad::Trigger(GamepadTrigger::RightTrigger)),
+            Capability::Gamepad(Gamepad::Accelerometer),
+            Capability::Gamepad(Gamepad::Gyro),
+        ]
+    }
+}
+
+/// The controller has no feature reports, so requests are failed instead of
+/// letting them time out.
+impl FeatureReports for SwitchProDevice {}
+
+/// Convert the given normalized value between -1.0 - 1.0 to a 12-bit stick
+/// value within the factory stick calibration.
+fn denormalize_stick_value(normal_value: f64) -> u16 {
+    let value = STICK_CENTER as f64 + normal_value.clamp(-1.0, 1.0) * STICK_RANGE as f64;
+    value.round() as u16
+}
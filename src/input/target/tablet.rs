@@ -0,0 +1,247 @@
+//! The TabletDevice target is an absolute pointer that positions the cursor
+//! with ABS_X/ABS_Y events, similar to a graphics tablet or the tablet devices
+//! used by virtual machines. Touchpad and touchscreen contacts move the cursor
+//! to the matching position on the screen instead of moving it relatively.
+use std::{collections::HashMap, error::Error};
+
+use evdev::{
+    uinput::{VirtualDevice, VirtualDeviceBuilder},
+    AbsInfo, AbsoluteAxisCode, AttributeSet, EventType, InputEvent, KeyCode, MiscCode,
+    SynchronizationCode, SynchronizationEvent, UinputAbsSetup,
+};
+use tokio::sync::mpsc;
+use zbus::Connection;
+
+use crate::{
+    dbus::interface::target::mouse::TargetMouseInterface,
+    input::{
+        capability::{Capability, Mouse, MouseButton, Touch, TouchButton, Touchpad},
+        composite_device,
+        event::{evdev::EvdevEvent, native::NativeEvent, value::InputValue},
+    },
+};
+
+use super::TargetCommand;
+
+/// Size of the target command channel buffer for processing events
+const BUFFER_SIZE: usize = 2048;
+
+/// Maximum value of the absolute axes
+const ABS_MAX: i32 = 32767;
+
+/// [TabletDevice] is a target virtual absolute pointer that can be used to
+/// position the cursor from touch input
+#[derive(Debug)]
+pub struct TabletDevice {
+    conn: Connection,
+    dbus_path: Option<String>,
+    tx: mpsc::Sender<TargetCommand>,
+    rx: mpsc::Receiver<TargetCommand>,
+    composite_tx: Option<mpsc::Sender<composite_device::Command>>,
+    /// Index of the touch contact that is currently positioning the pointer
+    active_touch: Option<u8>,
+}
+
+impl TabletDevice {
+    pub fn new(conn: Connection) -> Self {
+        let (tx, rx) = mpsc::channel(BUFFER_SIZE);
+        Self {
+            conn,
+            dbus_path: None,
+            composite_tx: None,
+            tx,
+            rx,
+            active_touch: None,
+        }
+    }
+
+    /// Returns a transmitter channel that can be used to send events to this device
+    pub fn transmitter(&self) -> mpsc::Sender<TargetCommand> {
+        self.tx.clone()
+    }
+
+    /// Configures the device to send output events to the given composite device
+    /// channel.
+    pub fn set_composite_device(&mut self, tx: mpsc::Sender<composite_device::Command>) {
+        self.composite_tx = Some(tx);
+    }
+
+    /// Creates a new instance of the device interface on DBus.
+    pub async fn listen_on_dbus(&mut self, path: String) -> Result<(), Box<dyn Error>> {
+        let conn = self.conn.clone();
+        self.dbus_path = Some(path.clone());
+        tokio::spawn(async move {
+            let iface = TargetMouseInterface::new();
+            if let Err(e) = conn.object_server().at(path, iface).await {
+                log::error!("Failed to setup DBus interface for device: {:?}", e);
+            }
+        });
+        Ok(())
+    }
+
+    /// Creates and runs the target device
+    pub async fn run(&mut self) -> Result<(), Box<dyn Error>> {
+        log::debug!("Creating virtual tablet");
+        let mut device = self.create_virtual_device()?;
+
+        // Listen for send events
+        log::debug!("Started listening for events to send");
+        while let Some(command) = self.rx.recv().await {
+            match command {
+                TargetCommand::SetCompositeDevice(tx) => {
+                    self.set_composite_device(tx);
+                }
+                TargetCommand::WriteEvent(event) => {
+                    log::trace!("Got event to emit: {:?}", event);
+                    let evdev_events = self.translate_event(event);
+                    if evdev_events.is_empty() {
+                        continue;
+                    }
+                    device.emit(evdev_events.as_slice())?;
+                    device.emit(&[SynchronizationEvent::new(
+                        SynchronizationCode::SYN_REPORT,
+                        0,
+                    )
+                    .into()])?;
+                }
+                TargetCommand::GetCapabilities(tx) => {
+                    let caps = self.get_capabilities();
+                    if let Err(e) = tx.send(caps).await {
+                        log::error!("Failed to send target capabilities: {e:?}");
+                    }
+                }
+                TargetCommand::Stop => break,
+            };
+        }
+
+        log::debug!("Stopping device");
+
+        // Remove the DBus interface
+        if let Some(path) = self.dbus_path.clone() {
+            log::debug!("Removing DBus interface");
+            self.conn
+                .object_server()
+                .remove::<TargetMouseInterface, String>(path)
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Translate the given native event into evdev events. Touch motion is
+    /// translated into absolute pointer positions, touch presses into left
+    /// clicks and mouse buttons are passed through.
+    fn translate_event(&mut self, event: NativeEvent) -> Vec<InputEvent> {
+        let touch = match event.as_capability() {
+            Capability::Touchscreen(touch) => touch,
+            Capability::Touchpad(touchpad) => match touchpad {
+                Touchpad::LeftPad(touch) => touch,
+                Touchpad::RightPad(touch) => touch,
+                Touchpad::CenterPad(touch) => touch,
+            },
+            Capability::Mouse(Mouse::Button(_)) => {
+                return EvdevEvent::from_native_event(event, HashMap::new())
+                    .into_iter()
+                    .map(|event| event.as_input_event())
+                    .collect();
+            }
+            _ => return vec![],
+        };
+
+        match touch {
+            Touch::Motion => self.translate_touch_motion(event.get_value()),
+            Touch::Button(TouchButton::Press) => {
+                let value = event.pressed() as i32;
+                vec![InputEvent::new(
+                    EventType::KEY.0,
+                    KeyCode::BTN_LEFT.0,
+                    value,
+                )]
+            }
+            Touch::Button(TouchButton::Touch) => vec![],
+            Touch::Tilt => vec![],
+            Touch::Tool(_) => vec![],
+        }
+    }
+
+    /// Translate the given touch value into an absolute pointer position. Only
+    /// the first contact that touches the surface moves the pointer until it is
+    /// lifted.
+    fn translate_touch_motion(&mut self, value: InputValue) -> Vec<InputEvent> {
+        let InputValue::Touch {
+            index,
+            is_touching,
+            x,
+            y,
+            pressure: _,
+        } = value
+        else {
+            return vec![];
+        };
+
+        let active_touch = *self.active_touch.get_or_insert(index);
+        if active_touch != index {
+            return vec![];
+        }
+        if !is_touching {
+            self.active_touch = None;
+            return vec![];
+        }
+
+        let mut events = vec![];
+        if let Some(x) = x {
+            events.push(InputEvent::new(
+                EventType::ABSOLUTE.0,
+                AbsoluteAxisCode::ABS_X.0,
+                denormalize(x),
+            ));
+        }
+        if let Some(y) = y {
+            events.push(InputEvent::new(
+                EventType::ABSOLUTE.0,
+                AbsoluteAxisCode::ABS_Y.0,
+                denormalize(y),
+            ));
+        }
+
+        events
+    }
+
+    /// Create the virtual device to emulate
+    fn create_virtual_device(&self) -> Result<VirtualDevice, Box<dyn Error>> {
+        let mut buttons = AttributeSet::<KeyCode>::new();
+        buttons.insert(KeyCode::BTN_LEFT);
+        buttons.insert(KeyCode::BTN_RIGHT);
+        buttons.insert(KeyCode::BTN_MIDDLE);
+        let abs_setup = AbsInfo::new(0, 0, ABS_MAX, 0, 0, 0);
+        let abs_x = UinputAbsSetup::new(AbsoluteAxisCode::ABS_X, abs_setup);
+        let abs_y = UinputAbsSetup::new(AbsoluteAxisCode::ABS_Y, abs_setup);
+        let device = VirtualDeviceBuilder::new()?
+            .name("InputPlumber Tablet")
+            .with_keys(&buttons)?
+            .with_absolute_axis(&abs_x)?
+            .with_absolute_axis(&abs_y)?
+            .with_msc(&AttributeSet::from_iter([MiscCode::MSC_TIMESTAMP]))?
+            .build()?;
+
+        Ok(device)
+    }
+
+    fn get_capabilities(&self) -> Vec<Capability> {
+        vec![
+            Capability::Mouse(Mouse::Button(MouseButton::Left)),
+            Capability::Mouse(Mouse::Button(MouseButton::Right)),
+            Capability::Mouse(Mouse::Button(MouseButton::Middle)),
+            Capability::Touchpad(Touchpad::CenterPad(Touch::Motion)),
+            Capability::Touchpad(Touchpad::CenterPad(Touch::Button(TouchButton::Press))),
+            Capability::Touchscreen(Touch::Motion),
+            Capability::Touchscreen(Touch::Button(TouchButton::Press)),
+        ]
+    }
+}
+
+/// Convert the given normalized position from 0.0 to 1.0 into an absolute
+/// axis value
+fn denormalize(value: f64) -> i32 {
+    (value.clamp(0.0, 1.0) * ABS_MAX as f64).round() as i32
+}
@@ -0,0 +1,274 @@
+//! The TouchscreenDevice target is a virtual multi-touch touchscreen that
+//! reports contacts using the slots of the multi-touch protocol B. Each new
+//! contact gets a new tracking id which is kept until liftoff, so consumers
+//! like libinput can tell a reused slot apart from a moving contact.
+//!
+//! Reference: https://www.kernel.org/doc/html/latest/input/multi-touch-protocol.html
+use std::error::Error;
+
+use evdev::{
+    uinput::{VirtualDevice, VirtualDeviceBuilder},
+    AbsInfo, AbsoluteAxisCode, AttributeSet, EventType, InputEvent, KeyCode, MiscCode, PropType,
+    SynchronizationCode, SynchronizationEvent, UinputAbsSetup,
+};
+use tokio::sync::mpsc;
+use zbus::Connection;
+
+use crate::{
+    dbus::interface::target::mouse::TargetMouseInterface,
+    input::{
+        capability::{Capability, Touch},
+        composite_device,
+        event::{native::NativeEvent, value::InputValue},
+    },
+};
+
+use super::TargetCommand;
+
+/// Size of the target command channel buffer for processing events
+const BUFFER_SIZE: usize = 2048;
+
+/// Maximum number of simultaneous touch contacts
+const MAX_SLOTS: usize = 10;
+
+/// Size of the touch surface in device units
+const TOUCHSCREEN_WIDTH: i32 = 1920;
+const TOUCHSCREEN_HEIGHT: i32 = 1080;
+
+/// [TouchscreenDevice] is a target virtual multi-touch touchscreen
+#[derive(Debug)]
+pub struct TouchscreenDevice {
+    conn: Connection,
+    dbus_path: Option<String>,
+    tx: mpsc::Sender<TargetCommand>,
+    rx: mpsc::Receiver<TargetCommand>,
+    composite_tx: Option<mpsc::Sender<composite_device::Command>>,
+    /// Tracking id to assign to the next new touch contact
+    next_tracking_id: i32,
+    /// Source touch contacts currently assigned to each slot
+    slots: [Option<u8>; MAX_SLOTS],
+}
+
+impl TouchscreenDevice {
+    pub fn new(conn: Connection) -> Self {
+        let (tx, rx) = mpsc::channel(BUFFER_SIZE);
+        Self {
+            conn,
+            dbus_path: None,
+            composite_tx: None,
+            tx,
+            rx,
+            next_tracking_id: 0,
+            slots: [None; MAX_SLOTS],
+        }
+    }
+
+    /// Returns a transmitter channel that can be used to send events to this device
+    pub fn transmitter(&self) -> mpsc::Sender<TargetCommand> {
+        self.tx.clone()
+    }
+
+    /// Configures the device to send output events to the given composite device
+    /// channel.
+    pub fn set_composite_device(&mut self, tx: mpsc::Sender<composite_device::Command>) {
+        self.composite_tx = Some(tx);
+    }
+
+    /// Creates a new instance of the device interface on DBus.
+    pub async fn listen_on_dbus(&mut self, path: String) -> Result<(), Box<dyn Error>> {
+        let conn = self.conn.clone();
+        self.dbus_path = Some(path.clone());
+        tokio::spawn(async move {
+            let iface = TargetMouseInterface::new();
+            if let Err(e) = conn.object_server().at(path, iface).await {
+                log::error!("Failed to setup DBus interface for device: {:?}", e);
+            }
+        });
+        Ok(())
+    }
+
+    /// Creates and runs the target device
+    pub async fn run(&mut self) -> Result<(), Box<dyn Error>> {
+        log::debug!("Creating virtual touchscreen");
+        let mut device = self.create_virtual_device()?;
+
+        // Listen for send events
+        log::debug!("Started listening for events to send");
+        while let Some(command) = self.rx.recv().await {
+            match command {
+                TargetCommand::SetCompositeDevice(tx) => {
+                    self.set_composite_device(tx);
+                }
+                TargetCommand::WriteEvent(event) => {
+                    log::trace!("Got event to emit: {:?}", event);
+                    let evdev_events = self.translate_event(event);
+                    if evdev_events.is_empty() {
+                        continue;
+                    }
+                    device.emit(evdev_events.as_slice())?;
+                    device.emit(&[SynchronizationEvent::new(
+                        SynchronizationCode::SYN_REPORT,
+                        0,
+                    )
+                    .into()])?;
+                }
+                TargetCommand::GetCapabilities(tx) => {
+                    let caps = self.get_capabilities();
+                    if let Err(e) = tx.send(caps).await {
+                        log::error!("Failed to send target capabilities: {e:?}");
+                    }
+                }
+                TargetCommand::Stop => break,
+            };
+        }
+
+        log::debug!("Stopping device");
+
+        // Remove the DBus interface
+        if let Some(path) = self.dbus_path.clone() {
+            log::debug!("Removing DBus interface");
+            self.conn
+                .object_server()
+                .remove::<TargetMouseInterface, String>(path)
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Translate the given native event into evdev events
+    fn translate_event(&mut self, event: NativeEvent) -> Vec<InputEvent> {
+        let Capability::Touchscreen(Touch::Motion) = event.as_capability() else {
+            return vec![];
+        };
+        let InputValue::Touch {
+            index,
+            is_touching,
+            x,
+            y,
+            pressure: _,
+        } = event.get_value()
+        else {
+            return vec![];
+        };
+        self.translate_touch(index, is_touching, x, y)
+    }
+
+    /// Translate the given source touch contact into the events of the slot
+    /// it is assigned to. Each new contact gets a new tracking id which is
+    /// kept until liftoff.
+    fn translate_touch(
+        &mut self,
+        index: u8,
+        is_touching: bool,
+        x: Option<f64>,
+        y: Option<f64>,
+    ) -> Vec<InputEvent> {
+        let mut events = vec![];
+        let slot = match self.slots.iter().position(|slot| *slot == Some(index)) {
+            Some(slot) => slot,
+            None => {
+                if !is_touching {
+                    return events;
+                }
+                // Ignore contacts beyond the number of available slots
+                let Some(slot) = self.slots.iter().position(|slot| slot.is_none()) else {
+                    return events;
+                };
+                self.slots[slot] = Some(index);
+                events.push(abs_event(AbsoluteAxisCode::ABS_MT_SLOT, slot as i32));
+                events.push(abs_event(
+                    AbsoluteAxisCode::ABS_MT_TRACKING_ID,
+                    self.next_tracking_id,
+                ));
+                self.next_tracking_id = (self.next_tracking_id + 1) & 0xffff;
+                events.push(self.touch_event());
+                slot
+            }
+        };
+        if events.is_empty() {
+            events.push(abs_event(AbsoluteAxisCode::ABS_MT_SLOT, slot as i32));
+        }
+
+        if !is_touching {
+            self.slots[slot] = None;
+            events.push(abs_event(AbsoluteAxisCode::ABS_MT_TRACKING_ID, -1));
+            events.push(self.touch_event());
+            return events;
+        }
+
+        // The first active slot also drives the single touch axes for
+        // consumers that do not support multi-touch.
+        let is_pointer = self.slots.iter().position(|slot| slot.is_some()) == Some(slot);
+        if let Some(x) = x {
+            let value = denormalize(x, TOUCHSCREEN_WIDTH);
+            events.push(abs_event(AbsoluteAxisCode::ABS_MT_POSITION_X, value));
+            if is_pointer {
+                events.push(abs_event(AbsoluteAxisCode::ABS_X, value));
+            }
+        }
+        if let Some(y) = y {
+            let value = denormalize(y, TOUCHSCREEN_HEIGHT);
+            events.push(abs_event(AbsoluteAxisCode::ABS_MT_POSITION_Y, value));
+            if is_pointer {
+                events.push(abs_event(AbsoluteAxisCode::ABS_Y, value));
+            }
+        }
+
+        events
+    }
+
+    /// Returns the touch button event for the current number of contacts
+    fn touch_event(&self) -> InputEvent {
+        let is_touching = self.slots.iter().any(|slot| slot.is_some());
+        InputEvent::new(EventType::KEY.0, KeyCode::BTN_TOUCH.0, is_touching as i32)
+    }
+
+    /// Create the virtual device to emulate
+    fn create_virtual_device(&self) -> Result<VirtualDevice, Box<dyn Error>> {
+        let mut buttons = AttributeSet::<KeyCode>::new();
+        buttons.insert(KeyCode::BTN_TOUCH);
+
+        // Setup the touch surface
+        let abs_x = AbsInfo::new(0, 0, TOUCHSCREEN_WIDTH - 1, 0, 0, 0);
+        let abs_y = AbsInfo::new(0, 0, TOUCHSCREEN_HEIGHT - 1, 0, 0, 0);
+        let abs_slot = AbsInfo::new(0, 0, MAX_SLOTS as i32 - 1, 0, 0, 0);
+        let abs_tracking_id = AbsInfo::new(0, 0, 0xffff, 0, 0, 0);
+        let axes = [
+            UinputAbsSetup::new(AbsoluteAxisCode::ABS_X, abs_x),
+            UinputAbsSetup::new(AbsoluteAxisCode::ABS_Y, abs_y),
+            UinputAbsSetup::new(AbsoluteAxisCode::ABS_MT_SLOT, abs_slot),
+            UinputAbsSetup::new(AbsoluteAxisCode::ABS_MT_TRACKING_ID, abs_tracking_id),
+            UinputAbsSetup::new(AbsoluteAxisCode::ABS_MT_POSITION_X, abs_x),
+            UinputAbsSetup::new(AbsoluteAxisCode::ABS_MT_POSITION_Y, abs_y),
+        ];
+
+        let mut builder = VirtualDeviceBuilder::new()?
+            .name("InputPlumber Touchscreen")
+            .with_properties(&AttributeSet::from_iter([PropType::DIRECT]))?
+            .with_keys(&buttons)?;
+        for axis in axes.iter() {
+            builder = builder.with_absolute_axis(axis)?;
+        }
+        let device = builder
+            .with_msc(&AttributeSet::from_iter([MiscCode::MSC_TIMESTAMP]))?
+            .build()?;
+
+        Ok(device)
+    }
+
+    fn get_capabilities(&self) -> Vec<Capability> {
+        vec![Capability::Touchscreen(Touch::Motion)]
+    }
+}
+
+/// Returns an absolute axis event with the given value
+fn abs_event(code: AbsoluteAxisCode, value: i32) -> InputEvent {
+    InputEvent::new(EventType::ABSOLUTE.0, code.0, value)
+}
+
+/// Convert the given normalized position from 0.0 to 1.0 into a position on
+/// an axis of the given size
+fn denormalize(value: f64, size: i32) -> i32 {
+    (value.clamp(0.0, 1.0) * (size - 1) as f64).round() as i32
+}
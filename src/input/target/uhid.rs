@@ -0,0 +1,143 @@
+//! Shared backend for target devices that are emulated with uhid instead of
+//! uinput. A uhid device is a full HID device, so targets can answer the
+//! feature reports that drivers, Steam and SDL request during their handshake
+//! with the real device.
+//!
+//! Reference: https://www.kernel.org/doc/html/latest/hid/uhid.html
+use std::{error::Error, fs::File};
+
+use uhid_virt::{OutputEvent, ReportType, StreamError, UHIDDevice};
+
+/// Error code to reply to report requests that a device does not support
+pub const EIO: u16 = 5;
+
+/// Handler for the GET_REPORT and SET_REPORT requests that the kernel sends to
+/// a uhid target device. Targets without feature reports can rely on the
+/// default implementations.
+pub trait FeatureReports {
+    /// Returns the data of the requested report, or an error code to reply
+    /// with if the report cannot be read.
+    fn get_report(&mut self, report_number: u8, report_type: ReportType) -> Result<Vec<u8>, u16> {
+        log::debug!("Unsupported GetReport request: num: {report_number}, type: {report_type:?}");
+        Err(EIO)
+    }
+
+    /// Handle the given report data sent by the host, returning an error code
+    /// to reply with if the report cannot be written.
+    fn set_report(
+        &mut self,
+        report_number: u8,
+        report_type: ReportType,
+        data: &[u8],
+    ) -> Result<(), u16> {
+        log::trace!(
+            "Ignoring SetReport request: num: {report_number}, type: {report_type:?}, data: {data:?}"
+        );
+        Ok(())
+    }
+}
+
+/// Read the next event from the given uhid device. GET_REPORT and SET_REPORT
+/// requests are answered using the given handler, and the data of output
+/// reports is returned to be handled by the caller. Failing to write a reply
+/// is only logged, since the kernel times out the request on its own and the
+/// device keeps working.
+pub fn read_output(
+    device: &mut UHIDDevice<File>,
+    reports: &mut impl FeatureReports,
+) -> Result<Option<Vec<u8>>, Box<dyn Error>> {
+    let event = match device.read() {
+        Ok(event) => event,
+        Err(StreamError::Io(_e)) => return Ok(None),
+        Err(StreamError::UnknownEventType(e)) => {
+            log::debug!("Unknown event type: {:?}", e);
+            return Ok(None);
+        }
+    };
+
+    match event {
+        // This is sent when the HID device is started. Consider this as an answer to
+        // UHID_CREATE. This is always the first event that is sent.
+        OutputEvent::Start { dev_flags: _ } => {
+            log::debug!("Start event received");
+        }
+        // This is sent when the HID device is stopped. Consider this as an answer to
+        // UHID_DESTROY.
+        OutputEvent::Stop => {
+            log::debug!("Stop event received");
+        }
+        // This is sent when the HID device is opened. That is, the data that the HID
+        // device provides is read by some other process. You may ignore this event but
+        // it is useful for power-management. As long as you haven't received this event
+        // there is actually no other process that reads your data so there is no need to
+        // send UHID_INPUT events to the kernel.
+        OutputEvent::Open => {
+            log::debug!("Open event received");
+        }
+        // This is sent when there are no more processes which read the HID data. It is
+        // the counterpart of UHID_OPEN and you may as well ignore this event.
+        OutputEvent::Close => {
+            log::debug!("Close event received");
+        }
+        // This is sent if the HID device driver wants to send raw data to the I/O
+        // device. You should read the payload and forward it to the device.
+        OutputEvent::Output { data } => {
+            log::trace!("Got output data: {:?}", data);
+            return Ok(Some(data));
+        }
+        // This event is sent if the kernel driver wants to perform a GET_REPORT request
+        // on the control channel as described in the HID specs. The report-type and
+        // report-number are available in the payload.
+        // The kernel serializes GET_REPORT requests so there will never be two in
+        // parallel. However, if you fail to respond with a UHID_GET_REPORT_REPLY, the
+        // request might silently time out.
+        // Once you read a GET_REPORT request, you shall forward it to the HID device and
+        // remember the "id" field in the payload. Once your HID device responds to the
+        // GET_REPORT (or if it fails), you must send a UHID_GET_REPORT_REPLY to the
+        // kernel with the exact same "id" as in the request. If the request already
+        // timed out, the kernel will ignore the response silently. The "id" field is
+        // never re-used, so conflicts cannot happen.
+        OutputEvent::GetReport {
+            id,
+            report_number,
+            report_type,
+        } => {
+            log::trace!(
+                "Received GetReport event: id: {id}, num: {report_number}, type: {:?}",
+                report_type
+            );
+            let (err, data) = match reports.get_report(report_number, report_type) {
+                Ok(data) => (0, data),
+                Err(err) => (err, vec![]),
+            };
+            if let Err(e) = device.write_get_report_reply(id, err, data) {
+                log::warn!("Failed to write get report reply: {:?}", e);
+            }
+        }
+        // This is the SET_REPORT equivalent of UHID_GET_REPORT. On receipt, you shall
+        // send a SET_REPORT request to your HID device. Once it replies, you must tell
+        // the kernel about it via UHID_SET_REPORT_REPLY.
+        // The same restrictions as for UHID_GET_REPORT apply.
+        OutputEvent::SetReport {
+            id,
+            report_number,
+            report_type,
+            data,
+        } => {
+            log::trace!(
+                "Received SetReport event: id: {id}, num: {report_number}, type: {:?}, data: {:?}",
+                report_type,
+                data
+            );
+            let err = match reports.set_report(report_number, report_type, &data) {
+                Ok(()) => 0,
+                Err(err) => err,
+            };
+            if let Err(e) = device.write_set_report_reply(id, err) {
+                log::warn!("Failed to write set report reply: {:?}", e);
+            }
+        }
+    };
+
+    Ok(None)
+}
@@ -0,0 +1,274 @@
+//! The XboxEliteController target provides a virtual Xbox Elite Series 2
+//! gamepad with four back paddles. Paddles are reported the same way the xpad
+//! driver reports them, so games and SDL pick them up as native paddles.
+use std::{
+    collections::HashMap,
+    error::Error,
+    os::fd::AsRawFd,
+    sync::{Arc, Mutex},
+};
+
+use evdev::{
+    uinput::{VirtualDevice, VirtualDeviceBuilder},
+    AbsInfo, AbsoluteAxisCode, AttributeSet, BusType, FFEffectCode, InputEvent, InputId, KeyCode,
+    MiscCode, SynchronizationCode, SynchronizationEvent, UinputAbsSetup,
+};
+use nix::fcntl::{FcntlArg, OFlag};
+use tokio::sync::mpsc;
+use zbus::Connection;
+
+use crate::{
+    dbus::interface::target::gamepad::TargetGamepadInterface,
+    input::{
+        capability::{Capability, Gamepad, GamepadAxis, GamepadButton, GamepadTrigger},
+        composite_device::Command,
+        event::{evdev::EvdevEvent, native::NativeEvent},
+    },
+};
+
+use super::{gamepad::GenericGamepad, TargetCommand};
+
+/// Size of the [TargetCommand] buffer for receiving input events
+const BUFFER_SIZE: usize = 2048;
+
+const NAME: &str = "Microsoft X-Box One Elite 2 pad";
+const VID: u16 = 0x045e;
+const PID: u16 = 0x0b00;
+const VERSION: u16 = 0x0511;
+
+#[derive(Debug)]
+pub struct XboxEliteController {
+    conn: Connection,
+    dbus_path: Option<String>,
+    tx: mpsc::Sender<TargetCommand>,
+    rx: mpsc::Receiver<TargetCommand>,
+    composite_tx: Option<mpsc::Sender<Command>>,
+}
+
+impl XboxEliteController {
+    pub fn new(conn: Connection) -> Self {
+        let (tx, rx) = mpsc::channel(BUFFER_SIZE);
+        Self {
+            conn,
+            dbus_path: None,
+            tx,
+            rx,
+            composite_tx: None,
+        }
+    }
+
+    /// Returns a transmitter channel that can be used to send events to this device
+    pub fn transmitter(&self) -> mpsc::Sender<TargetCommand> {
+        self.tx.clone()
+    }
+
+    /// Configures the device to send output events to the given composite device
+    /// channel.
+    pub fn set_composite_device(&mut self, tx: mpsc::Sender<Command>) {
+        self.composite_tx = Some(tx);
+    }
+
+    /// Creates a new instance of the dbus device interface on DBus.
+    pub async fn listen_on_dbus(&mut self, path: String) -> Result<(), Box<dyn Error>> {
+        let conn = self.conn.clone();
+        self.dbus_path = Some(path.clone());
+        tokio::spawn(async move {
+            let iface = TargetGamepadInterface::new("Xbox Elite Series 2".into());
+            if let Err(e) = conn.object_server().at(path, iface).await {
+                log::error!("Failed to setup DBus interface for Gamepad device: {:?}", e);
+            }
+        });
+        Ok(())
+    }
+
+    /// Creates and runs the target device
+    pub async fn run(&mut self) -> Result<(), Box<dyn Error>> {
+        log::debug!("Creating virtual Xbox Elite gamepad");
+        let device = self.create_virtual_device()?;
+
+        // Put the device behind an Arc Mutex so it can be shared between the
+        // read and write threads
+        let device = Arc::new(Mutex::new(device));
+
+        // Query information about the device to get the absolute ranges
+        let axes_map = self.get_abs_info();
+
+        // Listen for events from source devices
+        log::debug!("Started listening for events");
+        while let Some(command) = self.rx.recv().await {
+            match command {
+                TargetCommand::SetCompositeDevice(tx) => {
+                    self.set_composite_device(tx.clone());
+
+                    // Spawn a thread to listen for force feedback events
+                    let ff_device = device.clone();
+                    GenericGamepad::spawn_ff_thread(ff_device, tx);
+                }
+                TargetCommand::WriteEvent(event) => {
+                    log::trace!("Got event to emit: {:?}", event);
+                    let evdev_events = self.translate_event(event, axes_map.clone());
+                    if let Ok(mut dev) = device.lock() {
+                        dev.emit(evdev_events.as_slice())?;
+                        dev.emit(&[
+                            SynchronizationEvent::new(SynchronizationCode::SYN_REPORT, 0).into(),
+                        ])?;
+                    }
+                }
+                TargetCommand::GetCapabilities(tx) => {
+                    let caps = self.get_capabilities();
+                    if let Err(e) = tx.send(caps).await {
+                        log::error!("Failed to send target capabilities: {e:?}");
+                    }
+                }
+                TargetCommand::Stop => break,
+            }
+        }
+
+        log::debug!(
+            "Stopping device {}",
+            self.dbus_path.clone().unwrap_or_default()
+        );
+
+        // Remove the DBus interface
+        if let Some(path) = self.dbus_path.clone() {
+            log::debug!("Removing DBus interface for {path}");
+            self.conn
+                .object_server()
+                .remove::<TargetGamepadInterface, String>(path)
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Translate the given native event into an evdev event
+    fn translate_event(
+        &self,
+        event: NativeEvent,
+        axis_map: HashMap<AbsoluteAxisCode, AbsInfo>,
+    ) -> Vec<InputEvent> {
+        EvdevEvent::from_native_event(event, axis_map)
+            .into_iter()
+            .map(|event| event.as_input_event())
+            .collect()
+    }
+
+    /// Return a hashmap of ABS information for this virtual device. This information
+    /// is used to denormalize input event values.
+    fn get_abs_info(&self) -> HashMap<AbsoluteAxisCode, AbsInfo> {
+        let mut axes_info = HashMap::new();
+
+        let joystick_setup = AbsInfo::new(0, -32768, 32767, 16, 128, 1);
+        axes_info.insert(AbsoluteAxisCode::ABS_X, joystick_setup);
+        axes_info.insert(AbsoluteAxisCode::ABS_Y, joystick_setup);
+        axes_info.insert(AbsoluteAxisCode::ABS_RX, joystick_setup);
+        axes_info.insert(AbsoluteAxisCode::ABS_RY, joystick_setup);
+
+        let triggers_setup = AbsInfo::new(0, 0, 1023, 0, 0, 1);
+        axes_info.insert(AbsoluteAxisCode::ABS_Z, triggers_setup);
+        axes_info.insert(AbsoluteAxisCode::ABS_RZ, triggers_setup);
+
+        let dpad_setup = AbsInfo::new(0, -1, 1, 0, 0, 1);
+        axes_info.insert(AbsoluteAxisCode::ABS_HAT0X, dpad_setup);
+        axes_info.insert(AbsoluteAxisCode::ABS_HAT0Y, dpad_setup);
+
+        axes_info
+    }
+
+    /// Create the virtual device to emulate
+    fn create_virtual_device(&self) -> Result<VirtualDevice, Box<dyn Error>> {
+        // Setup Key inputs
+        let mut keys = AttributeSet::<KeyCode>::new();
+        keys.insert(KeyCode::BTN_SOUTH);
+        keys.insert(KeyCode::BTN_EAST);
+        keys.insert(KeyCode::BTN_NORTH);
+        keys.insert(KeyCode::BTN_WEST);
+        keys.insert(KeyCode::BTN_TL);
+        keys.insert(KeyCode::BTN_TR);
+        keys.insert(KeyCode::BTN_SELECT);
+        keys.insert(KeyCode::BTN_START);
+        keys.insert(KeyCode::BTN_MODE);
+        keys.insert(KeyCode::BTN_THUMBL);
+        keys.insert(KeyCode::BTN_THUMBR);
+        // Paddles P1-P4
+        keys.insert(KeyCode::BTN_TRIGGER_HAPPY5);
+        keys.insert(KeyCode::BTN_TRIGGER_HAPPY6);
+        keys.insert(KeyCode::BTN_TRIGGER_HAPPY7);
+        keys.insert(KeyCode::BTN_TRIGGER_HAPPY8);
+
+        // Setup ABS inputs
+        let joystick_setup = AbsInfo::new(0, -32768, 32767, 16, 128, 1);
+        let abs_x = UinputAbsSetup::new(AbsoluteAxisCode::ABS_X, joystick_setup);
+        let abs_y = UinputAbsSetup::new(AbsoluteAxisCode::ABS_Y, joystick_setup);
+        let abs_rx = UinputAbsSetup::new(AbsoluteAxisCode::ABS_RX, joystick_setup);
+        let abs_ry = UinputAbsSetup::new(AbsoluteAxisCode::ABS_RY, joystick_setup);
+        let triggers_setup = AbsInfo::new(0, 0, 1023, 0, 0, 1);
+        let abs_z = UinputAbsSetup::new(AbsoluteAxisCode::ABS_Z, triggers_setup);
+        let abs_rz = UinputAbsSetup::new(AbsoluteAxisCode::ABS_RZ, triggers_setup);
+        let dpad_setup = AbsInfo::new(0, -1, 1, 0, 0, 1);
+        let abs_hat0x = UinputAbsSetup::new(AbsoluteAxisCode::ABS_HAT0X, dpad_setup);
+        let abs_hat0y = UinputAbsSetup::new(AbsoluteAxisCode::ABS_HAT0Y, dpad_setup);
+
+        // Setup Force Feedback
+        let mut ff = AttributeSet::<FFEffectCode>::new();
+        ff.insert(FFEffectCode::FF_RUMBLE);
+        ff.insert(FFEffectCode::FF_PERIODIC);
+        ff.insert(FFEffectCode::FF_SQUARE);
+        ff.insert(FFEffectCode::FF_TRIANGLE);
+        ff.insert(FFEffectCode::FF_SINE);
+        ff.insert(FFEffectCode::FF_GAIN);
+
+        // Build the device
+        let device = VirtualDeviceBuilder::new()?
+            .name(NAME)
+            .input_id(InputId::new(BusType::BUS_USB, VID, PID, VERSION))
+            .with_keys(&keys)?
+            .with_absolute_axis(&abs_x)?
+            .with_absolute_axis(&abs_y)?
+            .with_absolute_axis(&abs_rx)?
+            .with_absolute_axis(&abs_ry)?
+            .with_absolute_axis(&abs_z)?
+            .with_absolute_axis(&abs_rz)?
+            .with_absolute_axis(&abs_hat0x)?
+            .with_absolute_axis(&abs_hat0y)?
+            .with_ff(&ff)?
+            .with_ff_effects_max(16)
+            .with_msc(&AttributeSet::from_iter([MiscCode::MSC_TIMESTAMP]))?
+            .build()?;
+
+        // Set the device to do non-blocking reads
+        let raw_fd = device.as_raw_fd();
+        nix::fcntl::fcntl(raw_fd, FcntlArg::F_SETFL(OFlag::O_NONBLOCK))?;
+
+        Ok(device)
+    }
+
+    /// Returns capabilities of the target device
+    fn get_capabilities(&self) -> Vec<Capability> {
+        vec![
+            Capability::Gamepad(Gamepad::Button(GamepadButton::South)),
+            Capability::Gamepad(Gamepad::Button(GamepadButton::North)),
+            Capability::Gamepad(Gamepad::Button(GamepadButton::East)),
+            Capability::Gamepad(Gamepad::Button(GamepadButton::West)),
+            Capability::Gamepad(Gamepad::Button(GamepadButton::Start)),
+            Capability::Gamepad(Gamepad::Button(GamepadButton::Select)),
+            Capability::Gamepad(Gamepad::Button(GamepadButton::Guide)),
+            Capability::Gamepad(Gamepad::Button(GamepadButton::DPadDown)),
+            Capability::Gamepad(Gamepad::Button(GamepadButton::DPadUp)),
+            Capability::Gamepad(Gamepad::Button(GamepadButton::DPadLeft)),
+            Capability::Gamepad(Gamepad::Button(GamepadButton::DPadRight)),
+            Capability::Gamepad(Gamepad::Button(GamepadButton::LeftBumper)),
+            Capability::Gamepad(Gamepad::Button(GamepadButton::LeftStick)),
+            Capability::Gamepad(Gamepad::Button(GamepadButton::LeftPaddle1)),
+            Capability::Gamepad(Gamepad::Button(GamepadButton::LeftPaddle2)),
+            Capability::Gamepad(Gamepad::Button(GamepadButton::RightBumper)),
+            Capability::Gamepad(Gamepad::Button(GamepadButton::RightStick)),
+            Capability::Gamepad(Gamepad::Button(GamepadButton::RightPaddle1)),
+            Capability::Gamepad(Gamepad::Button(GamepadButton::RightPaddle2)),
+            Capability::Gamepad(Gamepad::Axis(GamepadAxis::LeftStick)),
+            Capability::Gamepad(Gamepad::Axis(GamepadAxis::RightStick)),
+            Capability::Gamepad(Gamepad::Trigger(GamepadTrigger::LeftTrigger)),
+            Capability::Gamepad(Gamepad::Trigger(GamepadTrigger::RightTrigger)),
+        ]
+    }
+}
@@ -2,9 +2,18 @@ pub mod config;
 pub mod constants;
 pub mod dbus;
 pub mod dmi;
+pub mod dock;
 pub mod drivers;
+#[cfg(feature = "x11")]
+pub mod gamescope;
 pub mod iio;
 pub mod input;
+pub mod metrics;
+pub mod notifications;
+pub mod osk;
 pub mod procfs;
+pub mod session;
 pub mod udev;
 pub mod watcher;
+#[cfg(feature = "websocket")]
+pub mod websocket;
@@ -0,0 +1,348 @@
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    env,
+    future::Future,
+    os::unix::net::UnixDatagram,
+    sync::{OnceLock, RwLock},
+};
+
+use log::{
+    kv::{Key, Value, VisitSource},
+    Level, LevelFilter, Log, Metadata, Record,
+};
+
+/// Global logger instance used to change log levels at runtime
+static LOGGER: OnceLock<Logger> = OnceLock::new();
+
+tokio::task_local! {
+    /// Device that log records from the current task belong to
+    static TASK_DEVICE: String;
+}
+
+thread_local! {
+    /// Device that log records from the current blocking thread belong to
+    static THREAD_DEVICE: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+/// Path to the journald native protocol socket
+const JOURNALD_SOCKET: &str = "/run/systemd/journal/socket";
+/// Identifier used for log entries sent to journald
+const SYSLOG_IDENTIFIER: &str = "inputplumber";
+
+/// The [Logger] wraps the default env_logger and allows overriding the log
+/// level for individual log targets (e.g. "inputplumber::drivers::steam_deck")
+/// at runtime. The log level can also be overridden for a single device, which
+/// applies to all InputPlumber records logged from tasks running in the scope
+/// of that device (see [scope] and [spawn_blocking_in_scope]).
+///
+/// When running under systemd, log records are sent to journald using the
+/// native protocol so any key-value pairs on the record are stored as
+/// structured fields. E.g. `log::info!(device_path = path; "Device added")`
+/// can be filtered with `journalctl DEVICE_PATH=/dev/input/event0`.
+struct Logger {
+    inner: env_logger::Logger,
+    /// Formats records enabled by overrides that the default filter drops
+    unfiltered: env_logger::Logger,
+    overrides: RwLock<HashMap<String, LevelFilter>>,
+    device_overrides: RwLock<HashMap<String, LevelFilter>>,
+    journal: Option<UnixDatagram>,
+}
+
+impl Logger {
+    /// Returns the override level for the given log target if one exists. If
+    /// multiple overrides match, the most specific one is used.
+    fn override_level(&self, target: &str) -> Option<LevelFilter> {
+        let overrides = self.overrides.read().ok()?;
+        overrides
+            .iter()
+            .filter(|(prefix, _)| {
+                target == prefix.as_str()
+                    || target
+                        .strip_prefix(prefix.as_str())
+                        .is_some_and(|rest| rest.starts_with("::"))
+            })
+            .max_by_key(|(prefix, _)| prefix.len())
+            .map(|(_, level)| *level)
+    }
+
+    /// Returns the override level for the device the current task or thread
+    /// is logging for, if one exists. Only applies to InputPlumber targets.
+    fn device_level(&self, target: &str) -> Option<LevelFilter> {
+        if !target.starts_with(env!("CARGO_CRATE_NAME")) {
+            return None;
+        }
+        let overrides = self.device_overrides.read().ok()?;
+        if overrides.is_empty() {
+            return None;
+        }
+        TASK_DEVICE
+            .try_with(|device| overrides.get(device).copied())
+            .ok()
+            .flatten()
+            .or_else(|| {
+                THREAD_DEVICE.with(|device| {
+                    let device = device.borrow();
+                    overrides.get(device.as_ref()?).copied()
+                })
+            })
+    }
+
+    /// Returns the log level for the given target if it was overridden at
+    /// runtime. Device overrides take precedence over target overrides.
+    fn level(&self, target: &str) -> Option<LevelFilter> {
+        self.device_level(target)
+            .or_else(|| self.override_level(target))
+    }
+
+    /// Updates the global max log level so records enabled by overrides are
+    /// not filtered out before reaching the logger.
+    fn update_max_level(&self) {
+        let mut max_level = self.inner.filter();
+        if let Ok(overrides) = self.overrides.read() {
+            for level in overrides.values() {
+                max_level = max_level.max(*level);
+            }
+        }
+        if let Ok(overrides) = self.device_overrides.read() {
+            for level in overrides.values() {
+                max_level = max_level.max(*level);
+            }
+        }
+        log::set_max_level(max_level);
+    }
+}
+
+impl Log for Logger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        match self.level(metadata.target()) {
+            Some(level) => metadata.level() <= level,
+            None => self.inner.enabled(metadata),
+        }
+    }
+
+    fn log(&self, record: &Record) {
+        let enabled = match self.level(record.target()) {
+            Some(level) => record.level() <= level,
+            None => self.inner.matches(record),
+        };
+        if !enabled {
+            return;
+        }
+
+        // Send the record to journald with structured fields if available
+        if let Some(journal) = self.journal.as_ref() {
+            if journal.send(&journal_entry(record)).is_ok() {
+                return;
+            }
+        }
+
+        if self.inner.matches(record) {
+            self.inner.log(record);
+        } else {
+            self.unfiltered.log(record);
+        }
+    }
+
+    fn flush(&self) {
+        self.inner.flush();
+    }
+}
+
+/// Initialize the global logger using the RUST_LOG environment variable for
+/// the default log levels.
+pub fn init() {
+    let inner = env_logger::Builder::from_default_env().build();
+    let logger = LOGGER.get_or_init(|| Logger {
+        inner,
+        unfiltered: unfiltered_logger(),
+        overrides: RwLock::new(HashMap::new()),
+        device_overrides: RwLock::new(HashMap::new()),
+        journal: connect_journal(),
+    });
+    if let Err(e) = log::set_logger(logger) {
+        eprintln!("Failed to set logger: {e:?}");
+        return;
+    }
+    logger.update_max_level();
+}
+
+/// Returns an env_logger that formats records the same way as the default
+/// logger, but without filtering any of them.
+fn unfiltered_logger() -> env_logger::Logger {
+    let mut builder = env_logger::Builder::new();
+    builder.filter_level(LevelFilter::Trace);
+    if let Ok(style) = env::var("RUST_LOG_STYLE") {
+        builder.parse_write_style(&style);
+    }
+    builder.build()
+}
+
+/// Override the log level for the given log target and all of its children
+pub fn set_level(target: &str, level: LevelFilter) {
+    let Some(logger) = LOGGER.get() else {
+        return;
+    };
+    if let Ok(mut overrides) = logger.overrides.write() {
+        overrides.insert(target.to_string(), level);
+    }
+    logger.update_max_level();
+    log::info!("Log level for {target} set to {level}");
+}
+
+/// Remove any log level override for the given log target
+pub fn reset_level(target: &str) {
+    let Some(logger) = LOGGER.get() else {
+        return;
+    };
+    if let Ok(mut overrides) = logger.overrides.write() {
+        overrides.remove(target);
+    }
+    logger.update_max_level();
+    log::info!("Log level for {target} reset");
+}
+
+/// Override the log level for the given device (e.g. the DBus path of a
+/// composite device). Applies to all records logged in the scope of the device.
+pub fn set_device_level(device: &str, level: LevelFilter) {
+    let Some(logger) = LOGGER.get() else {
+        return;
+    };
+    if let Ok(mut overrides) = logger.device_overrides.write() {
+        overrides.insert(device.to_string(), level);
+    }
+    logger.update_max_level();
+    log::info!("Log level for {device} set to {level}");
+}
+
+/// Remove any log level override for the given device
+pub fn reset_device_level(device: &str) {
+    let Some(logger) = LOGGER.get() else {
+        return;
+    };
+    if let Ok(mut overrides) = logger.device_overrides.write() {
+        overrides.remove(device);
+    }
+    logger.update_max_level();
+    log::info!("Log level for {device} reset");
+}
+
+/// Run the given future with all of its log records belonging to the given
+/// device, so they follow any log level override of that device.
+pub async fn scope<F: Future>(device: String, future: F) -> F::Output {
+    TASK_DEVICE.scope(device, future).await
+}
+
+/// Run the given blocking function on the blocking thread pool. Log records
+/// from the function belong to the same device as the calling task.
+pub fn spawn_blocking_in_scope<F, R>(f: F) -> tokio::task::JoinHandle<R>
+where
+    F: FnOnce() -> R + Send + 'static,
+    R: Send + 'static,
+{
+    let device = TASK_DEVICE.try_with(|device| device.clone()).ok();
+    tokio::task::spawn_blocking(move || {
+        let _guard = ThreadDeviceGuard::enter(device);
+        f()
+    })
+}
+
+/// Sets the device of the current thread until it is dropped, so blocking
+/// pool threads do not keep the device after the task finishes.
+struct ThreadDeviceGuard;
+
+impl ThreadDeviceGuard {
+    fn enter(device: Option<String>) -> Self {
+        THREAD_DEVICE.with(|current| *current.borrow_mut() = device);
+        Self
+    }
+}
+
+impl Drop for ThreadDeviceGuard {
+    fn drop(&mut self) {
+        THREAD_DEVICE.with(|current| *current.borrow_mut() = None);
+    }
+}
+
+/// Connect to the journald socket if the process is running under systemd
+/// with its output connected to the journal.
+fn connect_journal() -> Option<UnixDatagram> {
+    env::var_os("JOURNAL_STREAM")?;
+    let socket = UnixDatagram::unbound().ok()?;
+    socket.connect(JOURNALD_SOCKET).ok()?;
+    Some(socket)
+}
+
+/// Returns the syslog priority for the given log level
+fn priority(level: Level) -> u8 {
+    match level {
+        Level::Error => 3,
+        Level::Warn => 4,
+        Level::Info => 6,
+        Level::Debug | Level::Trace => 7,
+    }
+}
+
+/// Build a journald native protocol entry for the given log record
+fn journal_entry(record: &Record) -> Vec<u8> {
+    let mut entry = Vec::new();
+    add_journal_field(&mut entry, "MESSAGE", &record.args().to_string());
+    add_journal_field(
+        &mut entry,
+        "PRIORITY",
+        &priority(record.level()).to_string(),
+    );
+    add_journal_field(&mut entry, "SYSLOG_IDENTIFIER", SYSLOG_IDENTIFIER);
+    add_journal_field(&mut entry, "TARGET", record.target());
+    if let Some(file) = record.file() {
+        add_journal_field(&mut entry, "CODE_FILE", file);
+    }
+    if let Some(line) = record.line() {
+        add_journal_field(&mut entry, "CODE_LINE", &line.to_string());
+    }
+    if let Some(module) = record.module_path() {
+        add_journal_field(&mut entry, "CODE_MODULE", module);
+    }
+    let mut visitor = JournalFields(&mut entry);
+    let _ = record.key_values().visit(&mut visitor);
+
+    entry
+}
+
+/// Append the given field to a journald native protocol entry
+fn add_journal_field(entry: &mut Vec<u8>, name: &str, value: &str) {
+    // Field names may only contain uppercase letters, digits, and underscores
+    let name: String = name
+        .chars()
+        .map(|c| match c {
+            'a'..='z' => c.to_ascii_uppercase(),
+            'A'..='Z' | '0'..='9' | '_' => c,
+            _ => '_',
+        })
+        .collect();
+    let name = name.trim_start_matches('_');
+    if name.is_empty() {
+        return;
+    }
+
+    entry.extend_from_slice(name.as_bytes());
+    // Values containing newlines must use the binary length-prefixed format
+    if value.contains('\n') {
+        entry.push(b'\n');
+        entry.extend_from_slice(&(value.len() as u64).to_le_bytes());
+    } else {
+        entry.push(b'=');
+    }
+    entry.extend_from_slice(value.as_bytes());
+    entry.push(b'\n');
+}
+
+/// Visitor to add the key-value pairs of a log record as journald fields
+struct JournalFields<'a>(&'a mut Vec<u8>);
+
+impl<'kvs> VisitSource<'kvs> for JournalFields<'_> {
+    fn visit_pair(&mut self, key: Key<'kvs>, value: Value<'kvs>) -> Result<(), log::kv::Error> {
+        add_journal_field(self.0, key.as_str(), &value.to_string());
+        Ok(())
+    }
+}
@@ -1,7 +1,9 @@
 use std::env;
 use std::error::Error;
-use std::future::pending;
+use std::panic;
 use std::process;
+use std::thread;
+use tokio::signal::unix::{signal, SignalKind};
 use zbus::fdo::ObjectManager;
 use zbus::Connection;
 
@@ -9,17 +11,28 @@ use crate::constants::BUS_NAME;
 use crate::constants::BUS_PREFIX;
 use crate::input::manager::Manager;
 use crate::udev::unhide_all;
+use crate::udev::unhide_all_blocking;
 
 mod config;
 mod constants;
 mod dbus;
 mod dmi;
+mod dock;
 mod drivers;
+#[cfg(feature = "x11")]
+mod gamescope;
 mod iio;
 mod input;
+mod logger;
+mod metrics;
+mod notifications;
+mod osk;
 mod procfs;
+mod session;
 mod udev;
 mod watcher;
+#[cfg(feature = "websocket")]
+mod websocket;
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn Error>> {
@@ -28,20 +41,28 @@ async fn main() -> Result<(), Box<dyn Error>> {
         Err(_) => "info".to_string(),
     };
     env::set_var("RUST_LOG", log_level);
-    env_logger::init();
+    logger::init();
     const VERSION: &str = env!("CARGO_PKG_VERSION");
     log::info!("Starting InputPlumber v{}", VERSION);
 
-    // Setup CTRL+C handler
-    tokio::spawn(async move {
-        tokio::signal::ctrl_c().await.unwrap();
-        log::info!("Un-hiding all devices");
-        if let Err(e) = unhide_all().await {
+    // Restore any devices left hidden if a previous instance crashed
+    if let Err(e) = unhide_all().await {
+        log::debug!("Unable to restore previously hidden devices: {:?}", e);
+    }
+
+    // Restore hidden devices if the main thread panics. Panics in other
+    // threads are handled by restarting the affected device.
+    let default_hook = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        default_hook(info);
+        if thread::current().name() != Some("main") {
+            return;
+        }
+        log::error!("InputPlumber panicked. Un-hiding all devices");
+        if let Err(e) = unhide_all_blocking() {
             log::error!("Unable to un-hide devices: {:?}", e);
         }
-        log::info!("Shutting down");
-        process::exit(0);
-    });
+    }));
 
     // Configure the DBus connection
     let connection = Connection::system().await?;
@@ -58,7 +79,7 @@ async fn main() -> Result<(), Box<dyn Error>> {
     let mut input_manager = Manager::new(connection.clone());
 
     // Start the input manager and listen on DBus
-    tokio::spawn(async move {
+    let manager_task = tokio::spawn(async move {
         log::debug!("Starting input manager thread");
         if let Err(e) = input_manager.run().await {
             log::error!("Error running input manager: {:?}", e);
@@ -68,10 +89,27 @@ async fn main() -> Result<(), Box<dyn Error>> {
     // Request the named bus
     connection.request_name(BUS_NAME).await?;
 
-    // Do other things or go to wait forever
-    pending::<()>().await;
+    // Wait for a shutdown signal or for the input manager to stop
+    let mut sigterm = signal(SignalKind::terminate())?;
+    let exit_code = tokio::select! {
+        _ = tokio::signal::ctrl_c() => 0,
+        _ = sigterm.recv() => 0,
+        result = manager_task => {
+            if let Err(e) = result {
+                log::error!("Input manager panicked: {:?}", e);
+            } else {
+                log::error!("Input manager stopped unexpectedly");
+            }
+            1
+        }
+    };
 
+    // Restore all hidden devices before exiting. Grabbed source devices and
+    // virtual target devices are released by the kernel when the process exits.
+    log::info!("Un-hiding all devices");
+    if let Err(e) = unhide_all().await {
+        log::error!("Unable to un-hide devices: {:?}", e);
+    }
     log::info!("InputPlumber stopped");
-
-    Ok(())
+    process::exit(exit_code);
 }
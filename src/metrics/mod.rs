@@ -0,0 +1,310 @@
+use std::{
+    error::Error,
+    fmt::Write as _,
+    sync::atomic::{AtomicBool, AtomicU64, Ordering},
+    time::Duration,
+};
+
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+    time,
+};
+
+/// Default TCP port to serve metrics on
+const DEFAULT_PORT: u16 = 9467;
+/// Environment variable used to enable the metrics endpoint
+pub const METRICS_ADDRESS_ENV: &str = "INPUTPLUMBER_METRICS_ADDRESS";
+/// Environment variable used to enable writing metrics to a textfile for the
+/// node exporter textfile collector
+pub const METRICS_TEXTFILE_ENV: &str = "INPUTPLUMBER_METRICS_TEXTFILE";
+/// Interval at which the metrics textfile is updated
+const TEXTFILE_INTERVAL: Duration = Duration::from_secs(15);
+/// Upper bounds in seconds of the event latency histogram buckets
+const LATENCY_BUCKETS: [f64; 10] = [
+    0.00005, 0.0001, 0.00025, 0.0005, 0.001, 0.0025, 0.005, 0.01, 0.025, 0.1,
+];
+/// Kinds of device errors that are counted
+const ERROR_KINDS: [&str; 3] = ["ReadFailed", "WriteFailed", "Dropped"];
+
+/// Global metrics registry
+static METRICS: Metrics = Metrics::new();
+/// Whether metrics are recorded. Only enabled when the exporter is running, so
+/// the event loop does not pay for metrics nobody reads.
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// Input health metrics exported in the Prometheus text format
+#[derive(Debug)]
+struct Metrics {
+    source_events: AtomicU64,
+    target_events: AtomicU64,
+    composite_devices: AtomicU64,
+    source_devices: AtomicU64,
+    target_devices: AtomicU64,
+    errors: [AtomicU64; ERROR_KINDS.len()],
+    latency: Histogram,
+}
+
+/// Histogram of event latencies
+#[derive(Debug)]
+struct Histogram {
+    buckets: [AtomicU64; LATENCY_BUCKETS.len()],
+    count: AtomicU64,
+    /// Sum of all latencies in nanoseconds
+    sum: AtomicU64,
+}
+
+impl Metrics {
+    const fn new() -> Self {
+        Self {
+            source_events: AtomicU64::new(0),
+            target_events: AtomicU64::new(0),
+            composite_devices: AtomicU64::new(0),
+            source_devices: AtomicU64::new(0),
+            target_devices: AtomicU64::new(0),
+            errors: [AtomicU64::new(0), AtomicU64::new(0), AtomicU64::new(0)],
+            latency: Histogram {
+                buckets: [const { AtomicU64::new(0) }; LATENCY_BUCKETS.len()],
+                count: AtomicU64::new(0),
+                sum: AtomicU64::new(0),
+            },
+        }
+    }
+}
+
+/// Returns true if metrics are being recorded
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// Count an event received from a source device
+pub fn record_source_event() {
+    if !is_enabled() {
+        return;
+    }
+    METRICS.source_events.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Count an event written to target devices
+pub fn record_target_event() {
+    if !is_enabled() {
+        return;
+    }
+    METRICS.target_events.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Record the time it took to process a source event
+pub fn record_latency(latency: Duration) {
+    if !is_enabled() {
+        return;
+    }
+    let histogram = &METRICS.latency;
+    let seconds = latency.as_secs_f64();
+    for (bucket, bound) in histogram.buckets.iter().zip(LATENCY_BUCKETS.iter()) {
+        if seconds <= *bound {
+            bucket.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+    histogram.count.fetch_add(1, Ordering::Relaxed);
+    histogram
+        .sum
+        .fetch_add(latency.as_nanos() as u64, Ordering::Relaxed);
+}
+
+/// Count a device error of the given kind (e.g. "ReadFailed")
+pub fn record_error(kind: &str) {
+    if !is_enabled() {
+        return;
+    }
+    if let Some(i) = ERROR_KINDS.iter().position(|k| *k == kind) {
+        METRICS.errors[i].fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// Update the number of managed devices
+pub fn set_device_counts(composite: usize, source: usize, target: usize) {
+    if !is_enabled() {
+        return;
+    }
+    METRICS
+        .composite_devices
+        .store(composite as u64, Ordering::Relaxed);
+    METRICS
+        .source_devices
+        .store(source as u64, Ordering::Relaxed);
+    METRICS
+        .target_devices
+        .store(target as u64, Ordering::Relaxed);
+}
+
+/// Render all metrics in the Prometheus text exposition format
+pub fn render() -> String {
+    let mut out = String::new();
+    let counter = |out: &mut String, name: &str, help: &str, value: &AtomicU64| {
+        let _ = writeln!(out, "# HELP {name} {help}");
+        let _ = writeln!(out, "# TYPE {name} counter");
+        let _ = writeln!(out, "{name} {}", value.load(Ordering::Relaxed));
+    };
+    let gauge = |out: &mut String, name: &str, help: &str, value: &AtomicU64| {
+        let _ = writeln!(out, "# HELP {name} {help}");
+        let _ = writeln!(out, "# TYPE {name} gauge");
+        let _ = writeln!(out, "{name} {}", value.load(Ordering::Relaxed));
+    };
+
+    counter(
+        &mut out,
+        "inputplumber_source_events_total",
+        "Events received from source devices",
+        &METRICS.source_events,
+    );
+    counter(
+        &mut out,
+        "inputplumber_target_events_total",
+        "Events written to target devices",
+        &METRICS.target_events,
+    );
+    gauge(
+        &mut out,
+        "inputplumber_composite_devices",
+        "Number of running composite devices",
+        &METRICS.composite_devices,
+    );
+    gauge(
+        &mut out,
+        "inputplumber_source_devices",
+        "Number of source devices in use",
+        &METRICS.source_devices,
+    );
+    gauge(
+        &mut out,
+        "inputplumber_target_devices",
+        "Number of running target devices",
+        &METRICS.target_devices,
+    );
+
+    let name = "inputplumber_device_errors_total";
+    let _ = writeln!(out, "# HELP {name} Device errors by kind");
+    let _ = writeln!(out, "# TYPE {name} counter");
+    for (kind, value) in ERROR_KINDS.iter().zip(METRICS.errors.iter()) {
+        let value = value.load(Ordering::Relaxed);
+        let _ = writeln!(out, "{name}{{kind=\"{kind}\"}} {value}");
+    }
+
+    let name = "inputplumber_event_latency_seconds";
+    let _ = writeln!(
+        out,
+        "# HELP {name} Time spent by a composite device processing a source event, \
+         from receiving it until it is written to target devices. Does not include \
+         the time spent reading the source device or queued in the composite device."
+    );
+    let _ = writeln!(out, "# TYPE {name} histogram");
+    let histogram = &METRICS.latency;
+    let count = histogram.count.load(Ordering::Relaxed);
+    let sum = Duration::from_nanos(histogram.sum.load(Ordering::Relaxed)).as_secs_f64();
+    for (bound, bucket) in LATENCY_BUCKETS.iter().zip(histogram.buckets.iter()) {
+        let value = bucket.load(Ordering::Relaxed);
+        let _ = writeln!(out, "{name}_bucket{{le=\"{bound}\"}} {value}");
+    }
+    let _ = writeln!(out, "{name}_bucket{{le=\"+Inf\"}} {count}");
+    let _ = writeln!(out, "{name}_sum {sum}");
+    let _ = writeln!(out, "{name}_count {count}");
+
+    out
+}
+
+/// The [MetricsExporter] publishes metrics over HTTP for Prometheus to
+/// scrape, and/or to a textfile for the node exporter textfile collector.
+#[derive(Debug)]
+pub struct MetricsExporter {
+    address: Option<String>,
+    textfile: Option<String>,
+}
+
+impl MetricsExporter {
+    pub fn new(address: Option<String>, textfile: Option<String>) -> Self {
+        let address = address.map(|address| {
+            if address.contains(':') {
+                address
+            } else {
+                format!("{address}:{DEFAULT_PORT}")
+            }
+        });
+        Self { address, textfile }
+    }
+
+    /// Returns a [MetricsExporter] if it was enabled with the
+    /// `INPUTPLUMBER_METRICS_ADDRESS` (e.g. "127.0.0.1" or "127.0.0.1:9467")
+    /// or `INPUTPLUMBER_METRICS_TEXTFILE` (e.g.
+    /// "/var/lib/node_exporter/textfile_collector/inputplumber.prom")
+    /// environment variables.
+    pub fn from_env() -> Option<Self> {
+        let address = std::env::var(METRICS_ADDRESS_ENV).ok();
+        let textfile = std::env::var(METRICS_TEXTFILE_ENV).ok();
+        if address.is_none() && textfile.is_none() {
+            return None;
+        }
+        Some(Self::new(address, textfile))
+    }
+
+    /// Serve and/or write metrics until an error occurs
+    pub async fn run(&self) -> Result<(), Box<dyn Error>> {
+        ENABLED.store(true, Ordering::Relaxed);
+
+        if let Some(path) = self.textfile.clone() {
+            tokio::spawn(async move {
+                let mut interval = time::interval(TEXTFILE_INTERVAL);
+                loop {
+                    interval.tick().await;
+                    if let Err(e) = write_textfile(&path).await {
+                        log::warn!("Failed to write metrics to {path}: {e:?}");
+                    }
+                }
+            });
+        }
+
+        let Some(address) = self.address.as_ref() else {
+            return Ok(());
+        };
+        let listener = TcpListener::bind(address).await?;
+        log::info!("Serving metrics on http://{address}/metrics");
+
+        loop {
+            let (stream, peer) = listener.accept().await?;
+            tokio::spawn(async move {
+                if let Err(e) = serve(stream).await {
+                    log::debug!("Failed to serve metrics to {peer}: {e:?}");
+                }
+            });
+        }
+    }
+}
+
+/// Answer a single HTTP request with the current metrics
+async fn serve(mut stream: TcpStream) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let mut buf = [0; 1024];
+    let len = stream.read(&mut buf).await?;
+    let request = String::from_utf8_lossy(&buf[..len]);
+    let path = request.split_whitespace().nth(1).unwrap_or_default();
+
+    let response = if path == "/metrics" {
+        let body = render();
+        format!(
+            "HTTP/1.1 200 OK\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+            body.len()
+        )
+    } else {
+        "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".to_string()
+    };
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await?;
+
+    Ok(())
+}
+
+/// Atomically replace the given textfile with the current metrics
+async fn write_textfile(path: &str) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let tmp_path = format!("{path}.tmp");
+    tokio::fs::write(&tmp_path, render()).await?;
+    tokio::fs::rename(&tmp_path, path).await?;
+    Ok(())
+}
@@ -0,0 +1,92 @@
+use std::error::Error;
+
+use crate::session;
+
+/// Battery percentage at or below which a low battery notification is shown
+pub const LOW_BATTERY_THRESHOLD: u8 = 20;
+/// Name of the application shown in notifications
+const APP_NAME: &str = "InputPlumber";
+/// Icon shown in notifications
+const APP_ICON: &str = "input-gaming";
+/// Time in milliseconds before a notification expires
+const EXPIRE_TIMEOUT_MS: i32 = 5000;
+
+/// Significant device events that can be shown as desktop notifications
+#[derive(Debug, Clone)]
+pub enum Notification {
+    DeviceConnected(String),
+    DeviceDisconnected(String),
+    ProfileLoaded(String, String),
+    BatteryLow(String, u8),
+}
+
+impl Notification {
+    /// Returns the summary text of the notification
+    fn summary(&self) -> String {
+        match self {
+            Notification::DeviceConnected(_) => "Controller connected".into(),
+            Notification::DeviceDisconnected(_) => "Controller disconnected".into(),
+            Notification::ProfileLoaded(_, _) => "Profile switched".into(),
+            Notification::BatteryLow(_, _) => "Controller battery low".into(),
+        }
+    }
+
+    /// Returns the body text of the notification
+    fn body(&self) -> String {
+        match self {
+            Notification::DeviceConnected(name) => name.clone(),
+            Notification::DeviceDisconnected(name) => name.clone(),
+            Notification::ProfileLoaded(name, profile) => format!("{name}: {profile}"),
+            Notification::BatteryLow(name, level) => format!("{name}: {level}% remaining"),
+        }
+    }
+
+    /// Returns the freedesktop notification urgency (0=low, 1=normal, 2=critical)
+    fn urgency(&self) -> u8 {
+        match self {
+            Notification::BatteryLow(_, _) => 2,
+            _ => 1,
+        }
+    }
+}
+
+/// Show the given notification on the desktop of every logged in user. This
+/// returns immediately and any failures are only logged.
+pub fn send(notification: Notification) {
+    tokio::spawn(async move {
+        if let Err(e) = notify_sessions(&notification).await {
+            log::debug!("Failed to send notification {notification:?}: {e:?}");
+        }
+    });
+}
+
+/// Send the given notification to the org.freedesktop.Notifications service
+/// on the session bus of every logged in user. The call is made with `busctl`
+/// running as each user so it is allowed to connect to their session bus.
+async fn notify_sessions(notification: &Notification) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let summary = notification.summary();
+    let body = notification.body();
+    let urgency = notification.urgency().to_string();
+    let timeout = EXPIRE_TIMEOUT_MS.to_string();
+    let args = [
+        "--user",
+        "call",
+        "org.freedesktop.Notifications",
+        "/org/freedesktop/Notifications",
+        "org.freedesktop.Notifications",
+        "Notify",
+        "susssasa{sv}i",
+        APP_NAME,
+        "0",
+        APP_ICON,
+        summary.as_str(),
+        body.as_str(),
+        "0",
+        "1",
+        "urgency",
+        "y",
+        urgency.as_str(),
+        timeout.as_str(),
+    ];
+    session::run_in_user_sessions("busctl", &args).await
+}
@@ -0,0 +1,26 @@
+use zbus::Connection;
+
+use crate::session;
+
+/// Show the on-screen keyboard by starting the given systemd user unit in the
+/// active user session. This returns immediately and any failures are only
+/// logged.
+pub fn show(conn: Connection, unit: String) {
+    tokio::spawn(async move {
+        let args = ["--user", "start", unit.as_str()];
+        if let Err(e) = session::run_in_active_session(&conn, "systemctl", &args).await {
+            log::warn!("Failed to show on-screen keyboard: {e:?}");
+        }
+    });
+}
+
+/// Hide the on-screen keyboard by stopping the given systemd user unit in the
+/// active user session.
+pub fn hide(conn: Connection, unit: String) {
+    tokio::spawn(async move {
+        let args = ["--user", "stop", unit.as_str()];
+        if let Err(e) = session::run_in_active_session(&conn, "systemctl", &args).await {
+            log::warn!("Failed to hide on-screen keyboard: {e:?}");
+        }
+    });
+}
@@ -1 +1,4 @@
 pub mod device;
+pub mod process;
+#[cfg(test)]
+mod process_test;
@@ -0,0 +1,70 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Returns the process ids of all running Steam processes. This includes the
+/// Steam client as well as its helper processes (e.g. steamwebhelper).
+pub fn get_steam_pids() -> Vec<u32> {
+    get_pids()
+        .into_iter()
+        .filter(|pid| is_steam_process(*pid))
+        .collect()
+}
+
+/// Returns the process ids of the given processes that have any of the given
+/// paths open.
+pub fn get_pids_using(paths: &[String], pids: &[u32]) -> Vec<u32> {
+    let paths: Vec<PathBuf> = paths.iter().map(PathBuf::from).collect();
+    let mut pids_using = Vec::new();
+    for pid in pids {
+        // Check to see if any open file descriptors point to the given paths
+        let Ok(fds) = fs::read_dir(format!("/proc/{pid}/fd")) else {
+            continue;
+        };
+        let has_open = fds
+            .flatten()
+            .filter_map(|fd| fs::read_link(fd.path()).ok())
+            .any(|target| paths.contains(&target));
+        if has_open {
+            pids_using.push(*pid);
+        }
+    }
+
+    pids_using
+}
+
+/// Returns whether the given process name belongs to Steam. SteamOS system
+/// services are not part of the Steam client and are ignored.
+pub fn is_steam_name(name: &str) -> bool {
+    let name = name.to_lowercase();
+    name.starts_with("steam") && !name.starts_with("steamos")
+}
+
+/// Returns whether the process with the given id belongs to Steam. The
+/// process name in /proc/<pid>/comm is truncated and can be changed by the
+/// process, so the name of the executable it was started with is also checked.
+fn is_steam_process(pid: u32) -> bool {
+    let comm = fs::read_to_string(format!("/proc/{pid}/comm")).unwrap_or_default();
+    if is_steam_name(comm.trim()) {
+        return true;
+    }
+
+    let cmdline = fs::read(format!("/proc/{pid}/cmdline")).unwrap_or_default();
+    let argv0 = cmdline.split(|byte| *byte == 0).next().unwrap_or_default();
+    let argv0 = String::from_utf8_lossy(argv0);
+    Path::new(argv0.as_ref())
+        .file_name()
+        .is_some_and(|name| is_steam_name(&name.to_string_lossy()))
+}
+
+/// Returns the process ids of all running processes
+fn get_pids() -> Vec<u32> {
+    let Ok(entries) = fs::read_dir("/proc") else {
+        return Vec::new();
+    };
+    entries
+        .flatten()
+        .filter_map(|entry| entry.file_name().to_string_lossy().parse::<u32>().ok())
+        .collect()
+}
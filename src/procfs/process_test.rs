@@ -0,0 +1,12 @@
+use crate::procfs::process::is_steam_name;
+
+#[test]
+fn test_steam_process_names() {
+    assert!(is_steam_name("steam"));
+    assert!(is_steam_name("steamwebhelper"));
+    assert!(is_steam_name("steam-runtime-l"));
+    assert!(is_steam_name("Steam"));
+    assert!(!is_steam_name("steamos-manager"));
+    assert!(!is_steam_name("inputplumber"));
+    assert!(!is_steam_name(""));
+}
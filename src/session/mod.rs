@@ -0,0 +1,246 @@
+use std::{error::Error, fs, path::Path, time::Duration};
+
+use futures_util::StreamExt;
+use nix::unistd::{Uid, User};
+use tokio::{process::Command, sync::broadcast};
+use zbus::{zvariant::OwnedObjectPath, Connection, Proxy};
+
+use crate::input::manager::ManagerCommand;
+
+/// Directory containing the runtime directories of logged in users
+const USER_RUNTIME_DIR: &str = "/run/user";
+/// Well-known name of systemd-logind
+const LOGIND_NAME: &str = "org.freedesktop.login1";
+/// DBus path of the logind manager
+const LOGIND_PATH: &str = "/org/freedesktop/login1";
+/// Seat of the built-in display and input devices
+const DEFAULT_SEAT: &str = "seat0";
+/// DBus path of the default seat
+const DEFAULT_SEAT_PATH: &str = "/org/freedesktop/login1/seat/seat0";
+/// Time to wait before subscribing to logind signals again if it fails
+const RETRY_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Type of the active graphical session
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SessionType {
+    /// No user session is active, or it could not be detected
+    #[default]
+    Unknown,
+    /// Gamescope session (e.g. Steam Game Mode)
+    Gamescope,
+    /// Any other desktop session
+    Desktop,
+}
+
+impl SessionType {
+    /// Returns the session type for the given XDG desktop name of a session
+    pub fn from_desktop(desktop: &str) -> Self {
+        if desktop.to_lowercase().contains("gamescope") {
+            SessionType::Gamescope
+        } else {
+            SessionType::Desktop
+        }
+    }
+}
+
+/// The [SessionWatcher] watches the active logind session on the default seat
+/// and notifies the input manager when the session type changes, so the
+/// matching target devices can be used.
+#[derive(Debug)]
+pub struct SessionWatcher {
+    manager: broadcast::Sender<ManagerCommand>,
+    conn: Connection,
+    session: SessionType,
+}
+
+impl SessionWatcher {
+    pub fn new(manager: broadcast::Sender<ManagerCommand>, conn: Connection) -> Self {
+        Self {
+            manager,
+            conn,
+            session: SessionType::Unknown,
+        }
+    }
+
+    /// Start watching for session type changes in a new task
+    pub fn spawn(mut self) {
+        tokio::spawn(async move {
+            loop {
+                if let Err(e) = self.watch().await {
+                    log::warn!("Stopped watching logind sessions: {e:?}");
+                }
+                tokio::time::sleep(RETRY_INTERVAL).await;
+            }
+        });
+    }
+
+    /// Check the active session whenever logind reports a new or removed
+    /// session, or the active session of the default seat changes.
+    async fn watch(&mut self) -> Result<(), Box<dyn Error>> {
+        let manager = Proxy::new(
+            &self.conn,
+            LOGIND_NAME,
+            LOGIND_PATH,
+            "org.freedesktop.login1.Manager",
+        )
+        .await?;
+        let seat = Proxy::new(
+            &self.conn,
+            LOGIND_NAME,
+            DEFAULT_SEAT_PATH,
+            "org.freedesktop.DBus.Properties",
+        )
+        .await?;
+        let mut session_new = manager.receive_signal("SessionNew").await?;
+        let mut session_removed = manager.receive_signal("SessionRemoved").await?;
+        let mut seat_changed = seat.receive_signal("PropertiesChanged").await?;
+
+        loop {
+            self.update_session().await;
+            tokio::select! {
+                Some(_) = session_new.next() => (),
+                Some(_) = session_removed.next() => (),
+                Some(_) = seat_changed.next() => (),
+                else => break,
+            }
+        }
+
+        Err("logind signal streams ended".into())
+    }
+
+    /// Look up the type of the active session
+    async fn update_session(&mut self) {
+        let session = match get_session_type(&self.conn).await {
+            Ok(session) => session,
+            Err(e) => {
+                log::trace!("Failed to get the active session: {e:?}");
+                SessionType::Unknown
+            }
+        };
+        self.set_session(session);
+    }
+
+    /// Notify the input manager if the session type changed
+    fn set_session(&mut self, session: SessionType) {
+        if self.session == session {
+            return;
+        }
+        log::info!("Session type changed to {session:?}");
+        self.session = session;
+        if let Err(e) = self
+            .manager
+            .send(ManagerCommand::SessionTypeChanged { session })
+        {
+            log::error!("Failed to send session type change: {e:?}");
+        }
+    }
+}
+
+/// Returns the type of the active user session on the default seat
+async fn get_session_type(conn: &Connection) -> Result<SessionType, Box<dyn Error>> {
+    let Some(session) = get_active_session(conn).await? else {
+        return Ok(SessionType::Unknown);
+    };
+    let desktop: String = session.get_property("Desktop").await?;
+    Ok(SessionType::from_desktop(&desktop))
+}
+
+/// Returns a proxy to the active user session on the default seat if one
+/// exists
+async fn get_active_session(conn: &Connection) -> Result<Option<Proxy<'_>>, Box<dyn Error>> {
+    let manager = Proxy::new(
+        conn,
+        LOGIND_NAME,
+        LOGIND_PATH,
+        "org.freedesktop.login1.Manager",
+    )
+    .await?;
+    let sessions: Vec<(String, u32, String, String, OwnedObjectPath)> =
+        manager.call("ListSessions", &()).await?;
+
+    for (_, _, _, seat, path) in sessions {
+        if seat != DEFAULT_SEAT {
+            continue;
+        }
+        let session = Proxy::new(conn, LOGIND_NAME, path, "org.freedesktop.login1.Session").await?;
+        let active: bool = session.get_property("Active").await?;
+        let class: String = session.get_property("Class").await?;
+        if !active || class != "user" {
+            continue;
+        }
+        return Ok(Some(session));
+    }
+
+    Ok(None)
+}
+
+/// Run the given program in the active user session on the default seat. The
+/// program runs as the user of the session with the environment needed to
+/// connect to their session bus.
+pub async fn run_in_active_session(
+    conn: &Connection,
+    program: &str,
+    args: &[&str],
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let session = get_active_session(conn)
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or("No active user session")?;
+    let (uid, _): (u32, OwnedObjectPath) = session.get_property("User").await?;
+    let runtime_dir = Path::new(USER_RUNTIME_DIR).join(uid.to_string());
+    run_in_user_session(uid, &runtime_dir, program, args).await
+}
+
+/// Run the given program in the session of every logged in user. The program
+/// runs as the user with the environment needed to connect to their session
+/// bus, so it can talk to services running in the user session.
+pub async fn run_in_user_sessions(
+    program: &str,
+    args: &[&str],
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    for entry in fs::read_dir(USER_RUNTIME_DIR)? {
+        let entry = entry?;
+        let Ok(uid) = entry.file_name().to_string_lossy().parse::<u32>() else {
+            continue;
+        };
+        let bus_path = entry.path().join("bus");
+        if !bus_path.exists() {
+            continue;
+        }
+        if let Err(e) = run_in_user_session(uid, &entry.path(), program, args).await {
+            log::debug!("Failed to run {program} for user {uid}: {e:?}");
+        }
+    }
+
+    Ok(())
+}
+
+/// Run the given program as the given user with the environment needed to
+/// connect to their session bus.
+async fn run_in_user_session(
+    uid: u32,
+    runtime_dir: &Path,
+    program: &str,
+    args: &[&str],
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let Some(user) = User::from_uid(Uid::from_raw(uid))? else {
+        return Err(format!("No user found with uid {uid}").into());
+    };
+    let bus_address = format!("unix:path={}/bus", runtime_dir.display());
+
+    let output = Command::new(program)
+        .uid(uid)
+        .gid(user.gid.as_raw())
+        .env("HOME", user.dir)
+        .env("DBUS_SESSION_BUS_ADDRESS", bus_address)
+        .env("XDG_RUNTIME_DIR", runtime_dir)
+        .args(args)
+        .output()
+        .await?;
+    if !output.status.success() {
+        let error = String::from_utf8_lossy(&output.stderr);
+        return Err(error.trim().to_string().into());
+    }
+
+    Ok(())
+}
@@ -6,13 +6,23 @@ pub mod device_test;
 
 pub mod device;
 
-use std::{error::Error, fs, path::Path};
+use std::{
+    collections::HashMap,
+    error::Error,
+    fs, io,
+    os::unix::fs::{chown, MetadataExt, PermissionsExt},
+    path::Path,
+    process,
+};
 
 use tokio::process::Command;
 
 use self::device::Device;
 
 const RULES_PREFIX: &str = "/run/udev/rules.d";
+/// Permissions given back to a device node if its original permissions are
+/// unknown, such as when it was already hidden
+const DEFAULT_NODE_MODE: u32 = 0o660;
 
 /// Hide the given input device from regular users.
 pub async fn hide_device(path: String) -> Result<(), Box<dyn Error>> {
@@ -57,6 +67,122 @@ LABEL="inputplumber_end"
     Ok(())
 }
 
+/// Un-hide the given device without using the async runtime. This should only
+/// be used where async is unavailable, such as when cleaning up in [Drop].
+pub fn unhide_device_blocking(path: &str) -> Result<(), Box<dyn Error>> {
+    let output = process::Command::new("udevadm")
+        .args(["info", path])
+        .output()?;
+    let device = parse_device(String::from_utf8(output.stdout)?);
+    let name = device.name.clone();
+    let Some(parent) = device.get_parent() else {
+        return Err("Unable to determine parent for device".into());
+    };
+    let rule_path = format!("{RULES_PREFIX}/96-inputplumber-hide-{name}.rules");
+    fs::remove_file(rule_path)?;
+
+    // Reload udev
+    let _ = process::Command::new("udevadm")
+        .args(["control", "--reload-rules"])
+        .output()?;
+    for action in ["remove", "add"] {
+        let _ = process::Command::new("udevadm")
+            .args(["trigger", "--action", action, "-b", parent.as_str()])
+            .output()?;
+    }
+
+    Ok(())
+}
+
+/// Permissions of a device node before it was hidden
+#[derive(Debug, Clone, Copy)]
+struct NodePermissions {
+    mode: u32,
+    gid: u32,
+}
+
+impl NodePermissions {
+    /// Returns the current permissions of the given device node
+    fn read(path: &str) -> io::Result<Self> {
+        let metadata = fs::metadata(path)?;
+        let mode = match metadata.permissions().mode() & 0o777 {
+            0 => DEFAULT_NODE_MODE,
+            mode => mode,
+        };
+        Ok(Self {
+            mode,
+            gid: metadata.gid(),
+        })
+    }
+}
+
+/// Keeps track of devices hidden by their owner and un-hides any that remain
+/// hidden when dropped. This ensures devices are restored even if the owner
+/// panics before it can clean up.
+#[derive(Debug, Default)]
+pub struct HiddenDeviceGuard {
+    paths: HashMap<String, Option<NodePermissions>>,
+}
+
+impl HiddenDeviceGuard {
+    /// Hide the given device and keep track of it
+    pub async fn hide(&mut self, path: String) -> Result<(), Box<dyn Error>> {
+        let permissions = NodePermissions::read(&path).ok();
+        hide_device(path.clone()).await?;
+        self.paths.insert(path, permissions);
+        Ok(())
+    }
+
+    /// Un-hide the given device if it was hidden by this guard
+    pub async fn unhide(&mut self, path: String) -> Result<(), Box<dyn Error>> {
+        if self.paths.remove(&path).is_none() {
+            return Ok(());
+        }
+        unhide_device(path).await
+    }
+
+    /// Give the nodes of all hidden devices their original permissions back so
+    /// other programs can open them, without removing the rules that hide
+    /// them. Re-triggering udev would remove and re-add the devices, so the
+    /// node permissions are changed directly.
+    pub fn restore_permissions(&self) {
+        for (path, permissions) in self.paths.iter() {
+            let Some(permissions) = permissions else {
+                continue;
+            };
+            log::debug!("Restoring permissions of hidden device: {path}");
+            let result = chown(path, None, Some(permissions.gid)).and_then(|_| {
+                fs::set_permissions(path, fs::Permissions::from_mode(permissions.mode))
+            });
+            if let Err(e) = result {
+                log::warn!("Unable to restore permissions of device {path}: {e:?}");
+            }
+        }
+    }
+
+    /// Remove all permissions from the nodes of hidden devices again after
+    /// they were restored with [HiddenDeviceGuard::restore_permissions].
+    pub fn revoke_permissions(&self) {
+        for path in self.paths.keys() {
+            log::debug!("Revoking permissions of hidden device: {path}");
+            if let Err(e) = fs::set_permissions(path, fs::Permissions::from_mode(0)) {
+                log::warn!("Unable to revoke permissions of device {path}: {e:?}");
+            }
+        }
+    }
+}
+
+impl Drop for HiddenDeviceGuard {
+    fn drop(&mut self) {
+        for (path, _) in self.paths.drain() {
+            log::warn!("Restoring hidden device that was not cleaned up: {path}");
+            if let Err(e) = unhide_device_blocking(path.as_str()) {
+                log::debug!("Unable to unhide device {path}: {e:?}");
+            }
+        }
+    }
+}
+
 /// Unhide the given device
 pub async fn unhide_device(path: String) -> Result<(), Box<dyn Error>> {
     // Get the device to unhide
@@ -76,6 +202,33 @@ pub async fn unhide_device(path: String) -> Result<(), Box<dyn Error>> {
 
 /// Unhide all devices hidden by InputPlumber
 pub async fn unhide_all() -> Result<(), Box<dyn Error>> {
+    remove_hide_rules()?;
+
+    // Reload udev rules
+    reload_all().await?;
+
+    Ok(())
+}
+
+/// Unhide all devices hidden by InputPlumber without using the async runtime.
+/// This should only be used where async is unavailable, such as in a panic hook.
+pub fn unhide_all_blocking() -> Result<(), Box<dyn Error>> {
+    remove_hide_rules()?;
+
+    // Reload udev rules
+    let _ = process::Command::new("udevadm")
+        .args(["control", "--reload-rules"])
+        .output()?;
+    let _ = process::Command::new("udevadm").arg("trigger").output()?;
+
+    Ok(())
+}
+
+/// Remove all udev rules created by InputPlumber to hide devices
+fn remove_hide_rules() -> Result<(), Box<dyn Error>> {
+    if !Path::new(RULES_PREFIX).exists() {
+        return Ok(());
+    }
     let entries = fs::read_dir(RULES_PREFIX)?;
     for entry in entries {
         let Ok(entry) = entry else {
@@ -89,9 +242,6 @@ pub async fn unhide_all() -> Result<(), Box<dyn Error>> {
         fs::remove_file(path)?;
     }
 
-    // Reload udev rules
-    reload_all().await?;
-
     Ok(())
 }
 
@@ -126,13 +276,18 @@ async fn reload_all() -> Result<(), Box<dyn Error>> {
 
 /// Returns device information for the given device path using udevadm.
 pub async fn get_device(path: String) -> Result<Device, Box<dyn Error>> {
-    let mut device = Device::default();
     let output = Command::new("udevadm")
         .args(["info", path.as_str()])
         .output()
         .await?;
     let output = String::from_utf8(output.stdout)?;
 
+    Ok(parse_device(output))
+}
+
+/// Parse the output of `udevadm info` into a [Device]
+fn parse_device(output: String) -> Device {
+    let mut device = Device::default();
     for line in output.split('\n') {
         if line.starts_with("P: ") {
             let line = line.replace("P: ", "");
@@ -210,5 +365,5 @@ pub async fn get_device(path: String) -> Result<Device, Box<dyn Error>> {
         }
     }
 
-    Ok(device)
+    device
 }
@@ -0,0 +1,518 @@
+use std::{
+    collections::HashMap,
+    error::Error,
+    net::{IpAddr, Ipv4Addr, SocketAddr},
+    path::{Path, PathBuf},
+};
+
+use futures_util::{SinkExt, StreamExt};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use tokio::{
+    io::{AsyncRead, AsyncWrite},
+    net::{TcpListener, UnixListener},
+    sync::{broadcast, mpsc},
+    task::JoinHandle,
+};
+use tokio_tungstenite::tungstenite::{
+    handshake::server::{ErrorResponse, Request as HandshakeRequest, Response},
+    http::{header, StatusCode},
+    Message,
+};
+
+use crate::input::{
+    composite_device::{Command, InterceptMode},
+    event::{native::NativeEvent, value::InputValue},
+    manager::ManagerCommand,
+};
+
+/// Size of the channel buffers for responses and events
+const BUFFER_SIZE: usize = 2048;
+/// Default TCP port to listen for WebSocket clients on
+const DEFAULT_PORT: u16 = 31416;
+/// Environment variable used to enable the WebSocket server
+pub const WEBSOCKET_ADDRESS_ENV: &str = "INPUTPLUMBER_WEBSOCKET_ADDRESS";
+/// Environment variable with the token that clients must authenticate with
+pub const WEBSOCKET_TOKEN_ENV: &str = "INPUTPLUMBER_WEBSOCKET_TOKEN";
+/// Environment variable with a comma separated list of allowed origins
+pub const WEBSOCKET_ORIGINS_ENV: &str = "INPUTPLUMBER_WEBSOCKET_ORIGINS";
+/// Directories that profiles can be loaded from over the WebSocket API
+const PROFILE_PATHS: &[&str] = &[
+    "/etc/inputplumber/profiles.d",
+    "/usr/share/inputplumber/profiles",
+];
+
+type CompositeDevices = HashMap<String, mpsc::Sender<Command>>;
+
+/// Address the [WebSocketServer] listens on
+#[derive(Debug, Clone, PartialEq)]
+enum ListenAddress {
+    /// Path to a Unix socket
+    Unix(PathBuf),
+    /// Loopback TCP address
+    Tcp(SocketAddr),
+}
+
+impl ListenAddress {
+    /// Parse the given address. Absolute paths are used as Unix sockets and
+    /// IP addresses without a port use the default port. Only loopback
+    /// addresses are accepted, since the API allows controlling all devices.
+    fn parse(address: &str) -> Result<Self, String> {
+        if address.starts_with('/') {
+            return Ok(Self::Unix(PathBuf::from(address)));
+        }
+        let address = match address.parse::<SocketAddr>() {
+            Ok(address) => address,
+            Err(_) => match address.parse::<IpAddr>() {
+                Ok(ip) => SocketAddr::new(ip, DEFAULT_PORT),
+                Err(_) if address == "localhost" => {
+                    SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), DEFAULT_PORT)
+                }
+                Err(_) => return Err(format!("Invalid WebSocket address: {address}")),
+            },
+        };
+        if !address.ip().is_loopback() {
+            return Err(format!(
+                "Refusing to listen for WebSocket clients on non-loopback address: {address}"
+            ));
+        }
+        Ok(Self::Tcp(address))
+    }
+}
+
+/// A request sent by a WebSocket client
+#[derive(Debug, Deserialize)]
+struct Request {
+    /// Optional id that is included in the response to the request
+    id: Option<Value>,
+    method: String,
+    #[serde(default)]
+    params: Value,
+}
+
+/// The [WebSocketServer] provides a local WebSocket API that mirrors the
+/// DBus API so web-based configurators can control InputPlumber without a
+/// DBus bridge.
+///
+/// The server only listens on a Unix socket or a loopback address. Clients
+/// must authenticate with the configured token, either with a `token` query
+/// parameter (e.g. `ws://127.0.0.1:31416/?token=...`) or an
+/// `Authorization: Bearer ...` header. Browser clients are rejected unless
+/// their `Origin` is in the configured list of allowed origins.
+///
+/// Clients send JSON requests in the form
+/// `{"id": 1, "method": "set_intercept_mode", "params": {"device": "...", "mode": 2}}`
+/// and receive responses in the form `{"id": 1, "result": ...}` or
+/// `{"id": 1, "error": "..."}`. Supported methods are `list_devices`,
+/// `get_intercept_mode`, `set_intercept_mode`, `get_profile_name`,
+/// `load_profile_path`, `subscribe`, and `unsubscribe`. Profiles can only be
+/// loaded from the system profile directories. Subscribed clients
+/// receive `{"event": "input", "device": "...", "capability": "...", "value": ...}`
+/// messages for every event written to the target devices of the device.
+#[derive(Debug)]
+pub struct WebSocketServer {
+    address: ListenAddress,
+    token: String,
+    origins: Vec<String>,
+    manager: broadcast::Sender<ManagerCommand>,
+}
+
+impl WebSocketServer {
+    pub fn new(
+        address: &str,
+        token: String,
+        origins: Vec<String>,
+        manager: broadcast::Sender<ManagerCommand>,
+    ) -> Result<Self, String> {
+        if token.is_empty() {
+            return Err("A WebSocket token is required".into());
+        }
+        let address = ListenAddress::parse(address)?;
+        Ok(Self {
+            address,
+            token,
+            origins,
+            manager,
+        })
+    }
+
+    /// Returns a [WebSocketServer] if it was enabled with the
+    /// `INPUTPLUMBER_WEBSOCKET_ADDRESS` environment variable (e.g.
+    /// "/run/inputplumber/websocket.sock", "127.0.0.1" or "127.0.0.1:31416").
+    /// The `INPUTPLUMBER_WEBSOCKET_TOKEN` environment variable must also be
+    /// set, and browser origins can be allowed with a comma separated list in
+    /// `INPUTPLUMBER_WEBSOCKET_ORIGINS`.
+    pub fn from_env(manager: broadcast::Sender<ManagerCommand>) -> Option<Self> {
+        let address = std::env::var(WEBSOCKET_ADDRESS_ENV).ok()?;
+        let Ok(token) = std::env::var(WEBSOCKET_TOKEN_ENV) else {
+            log::error!("WebSocket server is disabled: {WEBSOCKET_TOKEN_ENV} is not set");
+            return None;
+        };
+        let origins = std::env::var(WEBSOCKET_ORIGINS_ENV)
+            .map(|origins| {
+                origins
+                    .split(',')
+                    .map(|origin| origin.trim().to_string())
+                    .filter(|origin| !origin.is_empty())
+                    .collect()
+            })
+            .unwrap_or_default();
+        match Self::new(address.as_str(), token, origins, manager) {
+            Ok(server) => Some(server),
+            Err(e) => {
+                log::error!("WebSocket server is disabled: {e}");
+                None
+            }
+        }
+    }
+
+    /// Listen for and handle WebSocket clients
+    pub async fn run(&self) -> Result<(), Box<dyn Error>> {
+        match &self.address {
+            ListenAddress::Unix(path) => {
+                // Remove any stale socket from a previous run
+                if path.exists() {
+                    std::fs::remove_file(path)?;
+                }
+                let listener = UnixListener::bind(path)?;
+                log::info!("Listening for WebSocket clients on {}", path.display());
+                loop {
+                    let (stream, _) = listener.accept().await?;
+                    self.spawn_client(stream, path.display().to_string());
+                }
+            }
+            ListenAddress::Tcp(address) => {
+                let listener = TcpListener::bind(address).await?;
+                log::info!("Listening for WebSocket clients on {address}");
+                loop {
+                    let (stream, peer) = listener.accept().await?;
+                    self.spawn_client(stream, peer.to_string());
+                }
+            }
+        }
+    }
+
+    /// Handle the given client connection in a new task
+    fn spawn_client<S>(&self, stream: S, peer: String)
+    where
+        S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+    {
+        log::debug!("WebSocket client connected: {peer}");
+        let manager = self.manager.clone();
+        let token = self.token.clone();
+        let origins = self.origins.clone();
+        tokio::spawn(async move {
+            if let Err(e) = handle_client(stream, token, origins, manager).await {
+                log::debug!("WebSocket client {peer} failed: {e:?}");
+            }
+            log::debug!("WebSocket client disconnected: {peer}");
+        });
+    }
+}
+
+/// Validate the handshake of a WebSocket client, rejecting clients without
+/// a valid token or from an origin that is not allowed.
+fn authorize(
+    request: &HandshakeRequest,
+    token: &str,
+    origins: &[String],
+) -> Result<(), ErrorResponse> {
+    // Browsers always send an Origin header, so only allow-listed pages can
+    // connect. Other local clients do not send one.
+    if let Some(origin) = request.headers().get(header::ORIGIN) {
+        let allowed = origin
+            .to_str()
+            .map(|origin| origins.iter().any(|allowed| allowed == origin))
+            .unwrap_or(false);
+        if !allowed {
+            return Err(error_response(StatusCode::FORBIDDEN, "Origin not allowed"));
+        }
+    }
+
+    let header_token = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+    let query_token = request.uri().query().and_then(|query| {
+        query
+            .split('&')
+            .find_map(|param| param.strip_prefix("token="))
+    });
+    let Some(client_token) = header_token.or(query_token) else {
+        return Err(error_response(StatusCode::UNAUTHORIZED, "Missing token"));
+    };
+    if !tokens_match(client_token.as_bytes(), token.as_bytes()) {
+        return Err(error_response(StatusCode::UNAUTHORIZED, "Invalid token"));
+    }
+
+    Ok(())
+}
+
+/// Compare the given tokens in constant time
+fn tokens_match(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    a.iter().zip(b).fold(0, |acc, (a, b)| acc | (a ^ b)) == 0
+}
+
+/// Build a handshake error response with the given status and reason
+fn error_response(status: StatusCode, reason: &str) -> ErrorResponse {
+    let mut response = ErrorResponse::new(Some(reason.to_string()));
+    *response.status_mut() = status;
+    response
+}
+
+/// Returns the canonical path of the given profile if it is in one of the
+/// system profile directories.
+fn get_allowed_profile_path(path: &str) -> Result<String, String> {
+    let path = Path::new(path)
+        .canonicalize()
+        .map_err(|e| format!("Invalid profile path {path}: {e}"))?;
+    let allowed = PROFILE_PATHS
+        .iter()
+        .filter_map(|dir| Path::new(dir).canonicalize().ok())
+        .any(|dir| path.starts_with(dir));
+    if !allowed {
+        return Err(format!(
+            "Profiles can only be loaded from: {}",
+            PROFILE_PATHS.join(", ")
+        ));
+    }
+    Ok(path.to_string_lossy().to_string())
+}
+
+/// Handle requests from the given WebSocket client until it disconnects
+async fn handle_client<S>(
+    stream: S,
+    token: String,
+    origins: Vec<String>,
+    manager: broadcast::Sender<ManagerCommand>,
+) -> Result<(), Box<dyn Error + Send + Sync>>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let callback =
+        |request: &HandshakeRequest, response: Response| -> Result<Response, ErrorResponse> {
+            authorize(request, &token, &origins)?;
+            Ok(response)
+        };
+    let websocket = tokio_tungstenite::accept_hdr_async(stream, callback).await?;
+    let (mut writer, mut reader) = websocket.split();
+
+    // Responses and events are written to the client from a single task
+    let (tx, mut rx) = mpsc::channel::<String>(BUFFER_SIZE);
+    let writer_task = tokio::spawn(async move {
+        while let Some(message) = rx.recv().await {
+            if writer.send(Message::Text(message)).await.is_err() {
+                break;
+            }
+        }
+    });
+
+    let mut subscriptions: HashMap<String, JoinHandle<()>> = HashMap::new();
+    while let Some(message) = reader.next().await {
+        let text = match message? {
+            Message::Text(text) => text,
+            Message::Close(_) => break,
+            _ => continue,
+        };
+        let response = match serde_json::from_str::<Request>(&text) {
+            Ok(request) => {
+                let result = handle_request(&request, &manager, &tx, &mut subscriptions).await;
+                match result {
+                    Ok(result) => json!({"id": request.id, "result": result}),
+                    Err(e) => json!({"id": request.id, "error": e}),
+                }
+            }
+            Err(e) => json!({"id": null, "error": format!("Invalid request: {e}")}),
+        };
+        if tx.send(response.to_string()).await.is_err() {
+            break;
+        }
+    }
+
+    for (_, task) in subscriptions.drain() {
+        task.abort();
+    }
+    writer_task.abort();
+
+    Ok(())
+}
+
+/// Handle the given request and return its result
+async fn handle_request(
+    request: &Request,
+    manager: &broadcast::Sender<ManagerCommand>,
+    tx: &mpsc::Sender<String>,
+    subscriptions: &mut HashMap<String, JoinHandle<()>>,
+) -> Result<Value, String> {
+    let devices = get_composite_devices(manager).await?;
+    if request.method == "list_devices" {
+        let mut list = Vec::with_capacity(devices.len());
+        for (path, device) in devices.iter() {
+            let name = query(device, Command::GetName).await.unwrap_or_default();
+            let profile_name = query(device, Command::GetProfileName)
+                .await
+                .unwrap_or_default();
+            let intercept_mode = query(device, Command::GetInterceptMode)
+                .await
+                .map(|mode| intercept_mode_to_u32(&mode))
+                .unwrap_or_default();
+            list.push(json!({
+                "path": path,
+                "name": name,
+                "profile_name": profile_name,
+                "intercept_mode": intercept_mode,
+            }));
+        }
+        return Ok(Value::Array(list));
+    }
+
+    // All other methods operate on a single composite device
+    let Some(path) = request.params.get("device").and_then(Value::as_str) else {
+        return Err("Missing 'device' parameter".into());
+    };
+    let Some(device) = devices.get(path) else {
+        return Err(format!("No composite device found at {path}"));
+    };
+
+    match request.method.as_str() {
+        "get_intercept_mode" => {
+            let mode = query(device, Command::GetInterceptMode).await?;
+            Ok(json!(intercept_mode_to_u32(&mode)))
+        }
+        "set_intercept_mode" => {
+            let Some(mode) = request.params.get("mode").and_then(Value::as_u64) else {
+                return Err("Missing 'mode' parameter".into());
+            };
+            let mode = match mode {
+                0 => InterceptMode::None,
+                1 => InterceptMode::Pass,
+                2 => InterceptMode::Always,
+                3 => InterceptMode::Motion,
+                mode => return Err(format!("Invalid intercept mode: {mode}")),
+            };
+            device
+                .send(Command::SetInterceptMode(mode))
+                .await
+                .map_err(|e| e.to_string())?;
+            Ok(Value::Null)
+        }
+        "get_profile_name" => {
+            let name = query(device, Command::GetProfileName).await?;
+            Ok(json!(name))
+        }
+        "load_profile_path" => {
+            let Some(profile) = request.params.get("path").and_then(Value::as_str) else {
+                return Err("Missing 'path' parameter".into());
+            };
+            let profile = get_allowed_profile_path(profile)?;
+            let (sender, mut receiver) = mpsc::channel(1);
+            device
+                .send(Command::LoadProfilePath(profile, sender))
+                .await
+                .map_err(|e| e.to_string())?;
+            let Some(result) = receiver.recv().await else {
+                return Err("No response from composite device".into());
+            };
+            result?;
+            Ok(Value::Null)
+        }
+        "subscribe" => {
+            if subscriptions.contains_key(path) {
+                return Ok(Value::Null);
+            }
+            let (sender, mut receiver) = mpsc::channel::<NativeEvent>(BUFFER_SIZE);
+            device
+                .send(Command::SubscribeEvents(sender))
+                .await
+                .map_err(|e| e.to_string())?;
+            let tx = tx.clone();
+            let device_path = path.to_string();
+            let task = tokio::spawn(async move {
+                while let Some(event) = receiver.recv().await {
+                    let message = json!({
+                        "event": "input",
+                        "device": device_path,
+                        "capability": event.as_capability().to_capability_string(),
+                        "value": value_to_json(&event.get_value()),
+                    });
+                    if tx.send(message.to_string()).await.is_err() {
+                        break;
+                    }
+                }
+            });
+            subscriptions.insert(path.to_string(), task);
+            Ok(Value::Null)
+        }
+        "unsubscribe" => {
+            if let Some(task) = subscriptions.remove(path) {
+                task.abort();
+            }
+            Ok(Value::Null)
+        }
+        method => Err(format!("Unknown method: {method}")),
+    }
+}
+
+/// Ask the manager for all running composite devices
+async fn get_composite_devices(
+    manager: &broadcast::Sender<ManagerCommand>,
+) -> Result<CompositeDevices, String> {
+    let (sender, mut receiver) = mpsc::channel(1);
+    manager
+        .send(ManagerCommand::GetCompositeDevices { sender })
+        .map_err(|e| e.to_string())?;
+    let Some(devices) = receiver.recv().await else {
+        return Err("No response from manager".into());
+    };
+    Ok(devices)
+}
+
+/// Send the given command to a composite device and wait for its response
+async fn query<T>(
+    device: &mpsc::Sender<Command>,
+    command: fn(mpsc::Sender<T>) -> Command,
+) -> Result<T, String> {
+    let (sender, mut receiver) = mpsc::channel(1);
+    device
+        .send(command(sender))
+        .await
+        .map_err(|e| e.to_string())?;
+    receiver
+        .recv()
+        .await
+        .ok_or_else(|| "No response from composite device".to_string())
+}
+
+/// Returns the numeric intercept mode used by the DBus API
+fn intercept_mode_to_u32(mode: &InterceptMode) -> u32 {
+    match mode {
+        InterceptMode::None => 0,
+        InterceptMode::Pass => 1,
+        InterceptMode::Always => 2,
+        InterceptMode::Motion => 3,
+    }
+}
+
+/// Convert the given [InputValue] into JSON
+fn value_to_json(value: &InputValue) -> Value {
+    match value {
+        InputValue::None => Value::Null,
+        InputValue::Bool(value) => json!(value),
+        InputValue::Float(value) => json!(value),
+        InputValue::Vector2 { x, y } => json!({"x": x, "y": y}),
+        InputValue::Vector3 { x, y, z } => json!({"x": x, "y": y, "z": z}),
+        InputValue::Touch {
+            index,
+            is_touching,
+            x,
+            y,
+            pressure,
+        } => {
+            json!({"index": index, "is_touching": is_touching, "x": x, "y": y, "pressure": pressure})
+        }
+    }
+}